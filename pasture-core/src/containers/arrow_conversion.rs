@@ -0,0 +1,802 @@
+use std::{borrow::Cow, collections::HashMap, sync::Arc};
+
+use anyhow::{anyhow, bail, Context, Result};
+use arrow::{
+    array::{make_array, Array, ArrayData, ArrayRef},
+    buffer::Buffer,
+    datatypes::{DataType, Field, Schema},
+    record_batch::{RecordBatch, RecordBatchOptions},
+};
+
+use crate::layout::{PointAttributeDataType, PointAttributeDefinition, PointLayout};
+
+use super::{BorrowedBuffer, HashMapBuffer, MakeBufferFromLayout, OwningBuffer};
+
+/// Key of the field metadata that stores the name of the attribute that a column belongs to, if a vector attribute is
+/// stored as one column per component
+pub const ARROW_ATTRIBUTE_METADATA_KEY: &str = "pasture.attribute";
+
+/// Suffixes of the names of the component columns of vector attributes
+const COMPONENT_SUFFIXES: [&str; 4] = ["x", "y", "z", "w"];
+
+/// The mapping between pasture datatypes and Arrow datatypes. Each entry contains the Arrow datatype of a single
+/// component and the number of components of the pasture datatype. `ByteArray` attributes become `FixedSizeBinary`
+/// columns, `Custom` attributes are not supported
+const ARROW_DATATYPE_MAPPING: &[(PointAttributeDataType, DataType, usize)] = &[
+    (PointAttributeDataType::U8, DataType::UInt8, 1),
+    (PointAttributeDataType::I8, DataType::Int8, 1),
+    (PointAttributeDataType::U16, DataType::UInt16, 1),
+    (PointAttributeDataType::I16, DataType::Int16, 1),
+    (PointAttributeDataType::U32, DataType::UInt32, 1),
+    (PointAttributeDataType::I32, DataType::Int32, 1),
+    (PointAttributeDataType::U64, DataType::UInt64, 1),
+    (PointAttributeDataType::I64, DataType::Int64, 1),
+    (PointAttributeDataType::F32, DataType::Float32, 1),
+    (PointAttributeDataType::F64, DataType::Float64, 1),
+    (PointAttributeDataType::Vec3u8, DataType::UInt8, 3),
+    (PointAttributeDataType::Vec3u16, DataType::UInt16, 3),
+    (PointAttributeDataType::Vec3f32, DataType::Float32, 3),
+    (PointAttributeDataType::Vec3i32, DataType::Int32, 3),
+    (PointAttributeDataType::Vec3f64, DataType::Float64, 3),
+    (PointAttributeDataType::Vec4u8, DataType::UInt8, 4),
+];
+
+/// Returns the Arrow datatype of each component of values with the given `datatype`, together with the number of
+/// components
+fn arrow_component_type(datatype: PointAttributeDataType) -> Option<(&'static DataType, usize)> {
+    ARROW_DATATYPE_MAPPING
+        .iter()
+        .find(|(pasture_datatype, _, _)| *pasture_datatype == datatype)
+        .map(|(_, arrow_datatype, num_components)| (arrow_datatype, *num_components))
+}
+
+/// Returns the pasture datatype for values with `num_components` components of the given Arrow datatype
+fn pasture_datatype_from_arrow(
+    component_type: &DataType,
+    num_components: usize,
+) -> Option<PointAttributeDataType> {
+    ARROW_DATATYPE_MAPPING
+        .iter()
+        .find(|(_, arrow_datatype, components)| {
+            arrow_datatype == component_type && *components == num_components
+        })
+        .map(|(pasture_datatype, _, _)| *pasture_datatype)
+}
+
+/// How attributes with vector datatypes (e.g. `Vec3f64` for `POSITION_3D`) are stored in an Arrow `RecordBatch`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArrowVectorLayout {
+    /// Each vector attribute becomes a single `FixedSizeList` column
+    #[default]
+    FixedSizeList,
+    /// Each component of a vector attribute becomes a separate scalar column, named after the attribute with the
+    /// suffix `.x`, `.y`, `.z` or `.w`. The name of the attribute is stored in the field metadata under
+    /// [`ARROW_ATTRIBUTE_METADATA_KEY`], so that the columns can be combined again when importing the `RecordBatch`
+    Components,
+}
+
+/// Returns the Arrow fields for the given `attribute`
+fn arrow_fields_for_attribute(
+    attribute: &PointAttributeDefinition,
+    vector_layout: ArrowVectorLayout,
+) -> Result<Vec<Field>> {
+    if let PointAttributeDataType::ByteArray(size) = attribute.datatype() {
+        return Ok(vec![Field::new(
+            attribute.name(),
+            DataType::FixedSizeBinary(size as i32),
+            false,
+        )]);
+    }
+    let (component_type, num_components) =
+        arrow_component_type(attribute.datatype()).ok_or_else(|| {
+            anyhow!(
+                "Attribute {} has datatype {} which can't be stored in Arrow",
+                attribute.name(),
+                attribute.datatype()
+            )
+        })?;
+    if num_components == 1 {
+        return Ok(vec![Field::new(
+            attribute.name(),
+            component_type.clone(),
+            false,
+        )]);
+    }
+    match vector_layout {
+        ArrowVectorLayout::FixedSizeList => Ok(vec![Field::new(
+            attribute.name(),
+            DataType::FixedSizeList(
+                Arc::new(Field::new("item", component_type.clone(), false)),
+                num_components as i32,
+            ),
+            false,
+        )]),
+        ArrowVectorLayout::Components => Ok(COMPONENT_SUFFIXES[..num_components]
+            .iter()
+            .map(|suffix| {
+                Field::new(
+                    format!("{}.{}", attribute.name(), suffix),
+                    component_type.clone(),
+                    false,
+                )
+                .with_metadata(HashMap::from([(
+                    ARROW_ATTRIBUTE_METADATA_KEY.to_owned(),
+                    attribute.name().to_owned(),
+                )]))
+            })
+            .collect()),
+    }
+}
+
+/// Returns the Arrow schema for point data in the given `point_layout`. Each attribute becomes one column, except for
+/// vector attributes with [`ArrowVectorLayout::Components`], which become one column per component
+///
+/// # Errors
+///
+/// If any attribute in `point_layout` has a custom datatype
+pub fn point_layout_to_arrow_schema(
+    point_layout: &PointLayout,
+    vector_layout: ArrowVectorLayout,
+) -> Result<Schema> {
+    let mut fields = vec![];
+    for attribute in point_layout.attributes() {
+        fields.extend(arrow_fields_for_attribute(
+            attribute.attribute_definition(),
+            vector_layout,
+        )?);
+    }
+    Ok(Schema::new(fields))
+}
+
+/// A group of consecutive fields of an Arrow schema that store the values of a single point attribute
+struct ArrowAttributeColumns {
+    attribute: PointAttributeDefinition,
+    first_column: usize,
+    /// Number of columns for this attribute, which is larger than one only for vector attributes that are stored as one
+    /// column per component
+    num_columns: usize,
+}
+
+fn arrow_attribute_columns(schema: &Schema) -> Result<Vec<ArrowAttributeColumns>> {
+    let fields = schema.fields();
+    let mut attribute_columns = vec![];
+    let mut first_column = 0;
+    while first_column < fields.len() {
+        let field = &fields[first_column];
+        let (attribute, num_columns) = match field.metadata().get(ARROW_ATTRIBUTE_METADATA_KEY) {
+            Some(attribute_name) => {
+                let component_fields = fields[first_column..]
+                    .iter()
+                    .take_while(|field| {
+                        field.metadata().get(ARROW_ATTRIBUTE_METADATA_KEY) == Some(attribute_name)
+                    })
+                    .collect::<Vec<_>>();
+                if component_fields
+                    .iter()
+                    .any(|component| component.data_type() != field.data_type())
+                {
+                    bail!(
+                        "The component columns of attribute {} have different datatypes",
+                        attribute_name
+                    );
+                }
+                let datatype =
+                    pasture_datatype_from_arrow(field.data_type(), component_fields.len())
+                        .ok_or_else(|| {
+                            anyhow!(
+                            "Attribute {} with {} component columns of type {} is not supported",
+                            attribute_name,
+                            component_fields.len(),
+                            field.data_type()
+                        )
+                        })?;
+                (
+                    PointAttributeDefinition::custom(Cow::Owned(attribute_name.clone()), datatype),
+                    component_fields.len(),
+                )
+            }
+            None => {
+                let datatype = match field.data_type() {
+                    DataType::FixedSizeBinary(size) => {
+                        Some(PointAttributeDataType::ByteArray(*size as u64))
+                    }
+                    DataType::FixedSizeList(item, num_components) => {
+                        pasture_datatype_from_arrow(item.data_type(), *num_components as usize)
+                    }
+                    other => pasture_datatype_from_arrow(other, 1),
+                }
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Column {} has type {} which is not supported",
+                        field.name(),
+                        field.data_type()
+                    )
+                })?;
+                (
+                    PointAttributeDefinition::custom(Cow::Owned(field.name().clone()), datatype),
+                    1,
+                )
+            }
+        };
+        attribute_columns.push(ArrowAttributeColumns {
+            attribute,
+            first_column,
+            num_columns,
+        });
+        first_column += num_columns;
+    }
+    Ok(attribute_columns)
+}
+
+/// Creates a `PointLayout` from the given Arrow `schema`. Each column becomes an attribute with the name of the column
+/// (which is why the built-in attributes of pasture are recognized by their name), except for the component columns that
+/// [`ArrowVectorLayout::Components`] creates, which are combined into one vector attribute
+///
+/// # Errors
+///
+/// If the schema contains columns with datatypes that pasture does not support, e.g. strings
+pub fn point_layout_from_arrow_schema(schema: &Schema) -> Result<PointLayout> {
+    let attributes = arrow_attribute_columns(schema)?
+        .into_iter()
+        .map(|columns| columns.attribute)
+        .collect::<Vec<_>>();
+    Ok(PointLayout::from_attributes(&attributes))
+}
+
+/// Wraps `bytes` in an Arrow `Buffer`. This takes ownership of the memory of `bytes` if it is aligned to `alignment`
+/// and copies it otherwise, since Arrow requires aligned buffers
+fn make_aligned_buffer(bytes: Vec<u8>, alignment: usize) -> Buffer {
+    if bytes.as_ptr().align_offset(alignment) == 0 {
+        Buffer::from_vec(bytes)
+    } else {
+        Buffer::from_slice_ref(&bytes)
+    }
+}
+
+fn make_primitive_array_data(
+    component_type: &DataType,
+    bytes: Vec<u8>,
+    len: usize,
+) -> Result<ArrayData> {
+    let alignment = component_type
+        .primitive_width()
+        .expect("Component types of pasture attributes are primitive");
+    ArrayData::builder(component_type.clone())
+        .len(len)
+        .add_buffer(make_aligned_buffer(bytes, alignment))
+        .build()
+        .context("Could not create Arrow array")
+}
+
+/// Creates the Arrow columns for the values of `attribute` in `bytes`, which stores `num_points` values tightly packed
+fn make_arrow_columns(
+    attribute: &PointAttributeDefinition,
+    bytes: Vec<u8>,
+    num_points: usize,
+    vector_layout: ArrowVectorLayout,
+) -> Result<Vec<ArrayRef>> {
+    if let PointAttributeDataType::ByteArray(size) = attribute.datatype() {
+        let data = ArrayData::builder(DataType::FixedSizeBinary(size as i32))
+            .len(num_points)
+            .add_buffer(Buffer::from_vec(bytes))
+            .build()
+            .context("Could not create Arrow array")?;
+        return Ok(vec![make_array(data)]);
+    }
+    let (component_type, num_components) =
+        arrow_component_type(attribute.datatype()).ok_or_else(|| {
+            anyhow!(
+                "Attribute {} has datatype {} which can't be stored in Arrow",
+                attribute.name(),
+                attribute.datatype()
+            )
+        })?;
+    if num_components == 1 {
+        return Ok(vec![make_array(make_primitive_array_data(
+            component_type,
+            bytes,
+            num_points,
+        )?)]);
+    }
+    match vector_layout {
+        ArrowVectorLayout::FixedSizeList => {
+            let fields = arrow_fields_for_attribute(attribute, vector_layout)?;
+            let values =
+                make_primitive_array_data(component_type, bytes, num_points * num_components)?;
+            let data = ArrayData::builder(fields[0].data_type().clone())
+                .len(num_points)
+                .add_child_data(values)
+                .build()
+                .context("Could not create Arrow array")?;
+            Ok(vec![make_array(data)])
+        }
+        ArrowVectorLayout::Components => {
+            let component_size = attribute.size() as usize / num_components;
+            (0..num_components)
+                .map(|component| {
+                    let component_bytes = bytes
+                        .chunks_exact(attribute.size() as usize)
+                        .flat_map(|value| {
+                            value[component * component_size..(component + 1) * component_size]
+                                .iter()
+                                .copied()
+                        })
+                        .collect();
+                    Ok(make_array(make_primitive_array_data(
+                        component_type,
+                        component_bytes,
+                        num_points,
+                    )?))
+                })
+                .collect()
+        }
+    }
+}
+
+fn make_record_batch(
+    point_layout: &PointLayout,
+    num_points: usize,
+    vector_layout: ArrowVectorLayout,
+    mut attribute_bytes: impl FnMut(&PointAttributeDefinition) -> Vec<u8>,
+) -> Result<RecordBatch> {
+    let schema = point_layout_to_arrow_schema(point_layout, vector_layout)?;
+    let mut columns = vec![];
+    for attribute in point_layout.attributes() {
+        let attribute = attribute.attribute_definition();
+        columns.extend(make_arrow_columns(
+            attribute,
+            attribute_bytes(attribute),
+            num_points,
+            vector_layout,
+        )?);
+    }
+    RecordBatch::try_new_with_options(
+        Arc::new(schema),
+        columns,
+        &RecordBatchOptions::new().with_row_count(Some(num_points)),
+    )
+    .context("Could not create RecordBatch")
+}
+
+/// Converts the points in `buffer` into an Arrow `RecordBatch`, with one column per attribute (see
+/// [`point_layout_to_arrow_schema`]). The attribute data is copied into the `RecordBatch`, use
+/// [`hash_map_buffer_into_record_batch`] to convert a `HashMapBuffer` without copying
+///
+/// # Errors
+///
+/// If any attribute of `buffer` has a custom datatype
+///
+/// # Example
+///
+/// ```
+/// # use pasture_core::containers::*;
+/// # use pasture_core::layout::attributes::POSITION_3D;
+/// # use pasture_core::nalgebra::Vector3;
+/// # use pasture_derive::PointType;
+/// #[derive(PointType, Clone, Copy, Debug, bytemuck::AnyBitPattern, bytemuck::NoUninit)]
+/// #[repr(C, packed)]
+/// struct Point {
+///     #[pasture(BUILTIN_POSITION_3D)]
+///     position: Vector3<f64>,
+///     #[pasture(BUILTIN_INTENSITY)]
+///     intensity: u16,
+/// }
+///
+/// let buffer = (0..10)
+///     .map(|index| Point {
+///         position: Vector3::new(index as f64, 0.0, 0.0),
+///         intensity: index,
+///     })
+///     .collect::<VectorBuffer>();
+/// let record_batch = point_buffer_to_record_batch(&buffer, ArrowVectorLayout::Components).unwrap();
+/// assert_eq!(4, record_batch.num_columns());
+/// assert_eq!(10, record_batch.num_rows());
+///
+/// let buffer_from_arrow = record_batch_to_point_buffer::<HashMapBuffer>(&record_batch).unwrap();
+/// assert_eq!(
+///     buffer.view_attribute::<Vector3<f64>>(&POSITION_3D).into_iter().collect::<Vec<_>>(),
+///     buffer_from_arrow.view_attribute::<Vector3<f64>>(&POSITION_3D).into_iter().collect::<Vec<_>>()
+/// );
+/// ```
+pub fn point_buffer_to_record_batch<'a, B: BorrowedBuffer<'a>>(
+    buffer: &'a B,
+    vector_layout: ArrowVectorLayout,
+) -> Result<RecordBatch> {
+    let num_points = buffer.len();
+    make_record_batch(
+        buffer.point_layout(),
+        num_points,
+        vector_layout,
+        |attribute| {
+            if let Some(columnar_buffer) = buffer.as_columnar() {
+                return columnar_buffer
+                    .get_attribute_range_ref(attribute, 0..num_points)
+                    .to_vec();
+            }
+            let size_of_attribute = attribute.size() as usize;
+            let mut bytes = vec![0; num_points * size_of_attribute];
+            for (index, value) in bytes.chunks_exact_mut(size_of_attribute).enumerate() {
+                buffer.get_attribute(attribute, index, value);
+            }
+            bytes
+        },
+    )
+}
+
+/// Converts the given `HashMapBuffer` into an Arrow `RecordBatch` like [`point_buffer_to_record_batch`]. The memory of
+/// the scalar attributes (and of the vector attributes with [`ArrowVectorLayout::FixedSizeList`]) is moved into the
+/// `RecordBatch` without copying, unless it is not aligned to the size of the attribute's components
+///
+/// # Errors
+///
+/// If any attribute of `buffer` has a custom datatype
+pub fn hash_map_buffer_into_record_batch(
+    buffer: HashMapBuffer,
+    vector_layout: ArrowVectorLayout,
+) -> Result<RecordBatch> {
+    let num_points = buffer.len();
+    let point_layout = buffer.point_layout().clone();
+    let mut attributes_storage = buffer.into_attributes_storage();
+    make_record_batch(&point_layout, num_points, vector_layout, |attribute| {
+        attributes_storage
+            .remove(attribute)
+            .expect("Attribute not found in storage of HashMapBuffer")
+    })
+}
+
+/// Returns the memory of the values of `data`, which is an array of values with `value_size` bytes, respecting the
+/// offset of `data`
+fn primitive_array_bytes(data: &ArrayData, value_size: usize) -> &[u8] {
+    let start = data.offset() * value_size;
+    &data.buffers()[0].as_slice()[start..start + data.len() * value_size]
+}
+
+/// Returns the memory of all values of `attribute` from the columns of `record_batch`, tightly packed in the memory
+/// layout of the attribute's datatype
+fn attribute_bytes_from_arrow(
+    record_batch: &RecordBatch,
+    columns: &ArrowAttributeColumns,
+) -> Result<Vec<u8>> {
+    let arrays = &record_batch.columns()[columns.first_column..][..columns.num_columns];
+    for (column, array) in arrays.iter().enumerate() {
+        if array.null_count() > 0 {
+            bail!(
+                "Column {} contains null values, which pasture does not support",
+                record_batch
+                    .schema()
+                    .field(columns.first_column + column)
+                    .name()
+            );
+        }
+    }
+
+    let attribute = &columns.attribute;
+    if let PointAttributeDataType::ByteArray(size) = attribute.datatype() {
+        return Ok(primitive_array_bytes(&arrays[0].to_data(), size as usize).to_vec());
+    }
+    let (_, num_components) = arrow_component_type(attribute.datatype())
+        .expect("Datatype of attribute from Arrow schema must have an Arrow datatype");
+    let component_size = attribute.size() as usize / num_components;
+    if columns.num_columns > 1 {
+        let component_datas = arrays
+            .iter()
+            .map(|array| array.to_data())
+            .collect::<Vec<_>>();
+        let component_bytes = component_datas
+            .iter()
+            .map(|data| primitive_array_bytes(data, component_size))
+            .collect::<Vec<_>>();
+        let mut bytes = Vec::with_capacity(record_batch.num_rows() * attribute.size() as usize);
+        for index in 0..record_batch.num_rows() {
+            for component in component_bytes.iter() {
+                bytes.extend_from_slice(
+                    &component[index * component_size..(index + 1) * component_size],
+                );
+            }
+        }
+        return Ok(bytes);
+    }
+
+    let data = arrays[0].to_data();
+    if num_components == 1 {
+        return Ok(primitive_array_bytes(&data, component_size).to_vec());
+    }
+    let values = &data.child_data()[0];
+    if values.null_count() > 0 {
+        bail!(
+            "Column {} contains null values, which pasture does not support",
+            attribute.name()
+        );
+    }
+    let start = (values.offset() + data.offset() * num_components) * component_size;
+    let end = start + data.len() * num_components * component_size;
+    Ok(values.buffers()[0].as_slice()[start..end].to_vec())
+}
+
+/// Converts the given Arrow `record_batch` into a point buffer of type `B`. The `PointLayout` of the buffer is
+/// created from the schema of `record_batch` using [`point_layout_from_arrow_schema`]
+///
+/// # Errors
+///
+/// If the schema of `record_batch` contains unsupported columns, or if any of its columns contains null values
+pub fn record_batch_to_point_buffer<'a, B: OwningBuffer<'a> + MakeBufferFromLayout<'a> + 'a>(
+    record_batch: &RecordBatch,
+) -> Result<B> {
+    let schema = record_batch.schema();
+    let attribute_columns = arrow_attribute_columns(&schema)?;
+    let point_layout = PointLayout::from_attributes(
+        &attribute_columns
+            .iter()
+            .map(|columns| columns.attribute.clone())
+            .collect::<Vec<_>>(),
+    );
+    let num_points = record_batch.num_rows();
+    let mut buffer = B::new_from_layout(point_layout);
+    buffer.resize(num_points);
+    for columns in attribute_columns.iter() {
+        let bytes = attribute_bytes_from_arrow(record_batch, columns)?;
+        // Safe because the bytes are read from columns whose Arrow datatype matches the datatype of the attribute
+        unsafe {
+            buffer.set_attribute_range(&columns.attribute, 0..num_points, &bytes);
+        }
+    }
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Vector3, Vector4};
+    use pasture_derive::PointType;
+    use rand::{thread_rng, Rng};
+
+    use crate::{
+        containers::{ColumnarBuffer, VectorBuffer},
+        layout::{
+            attributes::{COLOR_RGB, INTENSITY, POSITION_3D},
+            PointType,
+        },
+    };
+
+    use super::*;
+
+    #[derive(
+        PointType, Clone, Copy, Debug, PartialEq, bytemuck::AnyBitPattern, bytemuck::NoUninit,
+    )]
+    #[repr(C, packed)]
+    struct AllDatatypesPoint {
+        #[pasture(attribute = "U8")]
+        u8_value: u8,
+        #[pasture(attribute = "I8")]
+        i8_value: i8,
+        #[pasture(attribute = "U16")]
+        u16_value: u16,
+        #[pasture(attribute = "I16")]
+        i16_value: i16,
+        #[pasture(attribute = "U32")]
+        u32_value: u32,
+        #[pasture(attribute = "I32")]
+        i32_value: i32,
+        #[pasture(attribute = "U64")]
+        u64_value: u64,
+        #[pasture(attribute = "I64")]
+        i64_value: i64,
+        #[pasture(attribute = "F32")]
+        f32_value: f32,
+        #[pasture(attribute = "F64")]
+        f64_value: f64,
+        #[pasture(attribute = "Vec3u8")]
+        vec3u8_value: Vector3<u8>,
+        #[pasture(BUILTIN_COLOR_RGB)]
+        vec3u16_value: Vector3<u16>,
+        #[pasture(attribute = "Vec3f32")]
+        vec3f32_value: Vector3<f32>,
+        #[pasture(attribute = "Vec3i32")]
+        vec3i32_value: Vector3<i32>,
+        #[pasture(BUILTIN_POSITION_3D)]
+        vec3f64_value: Vector3<f64>,
+        #[pasture(attribute = "Vec4u8")]
+        vec4u8_value: Vector4<u8>,
+    }
+
+    fn random_points(count: usize) -> Vec<AllDatatypesPoint> {
+        let mut rng = thread_rng();
+        (0..count)
+            .map(|_| AllDatatypesPoint {
+                u8_value: rng.gen(),
+                i8_value: rng.gen(),
+                u16_value: rng.gen(),
+                i16_value: rng.gen(),
+                u32_value: rng.gen(),
+                i32_value: rng.gen(),
+                u64_value: rng.gen(),
+                i64_value: rng.gen(),
+                f32_value: rng.gen(),
+                f64_value: rng.gen(),
+                vec3u8_value: Vector3::new(rng.gen(), rng.gen(), rng.gen()),
+                vec3u16_value: Vector3::new(rng.gen(), rng.gen(), rng.gen()),
+                vec3f32_value: Vector3::new(rng.gen(), rng.gen(), rng.gen()),
+                vec3i32_value: Vector3::new(rng.gen(), rng.gen(), rng.gen()),
+                vec3f64_value: Vector3::new(rng.gen(), rng.gen(), rng.gen()),
+                vec4u8_value: Vector4::new(rng.gen(), rng.gen(), rng.gen(), rng.gen()),
+            })
+            .collect()
+    }
+
+    fn assert_same_attribute_values<'a, 'b, B1: BorrowedBuffer<'a>, B2: BorrowedBuffer<'b>>(
+        expected: &'a B1,
+        actual: &'b B2,
+    ) {
+        assert_eq!(expected.len(), actual.len());
+        for attribute in expected.point_layout().attributes() {
+            let attribute = attribute.attribute_definition();
+            let mut expected_value = vec![0; attribute.size() as usize];
+            let mut actual_value = vec![0; attribute.size() as usize];
+            for index in 0..expected.len() {
+                expected.get_attribute(attribute, index, &mut expected_value);
+                actual.get_attribute(attribute, index, &mut actual_value);
+                assert_eq!(
+                    expected_value,
+                    actual_value,
+                    "Attribute {} of point {} differs",
+                    attribute.name(),
+                    index
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_arrow_round_trip_all_datatypes() -> Result<()> {
+        let points = random_points(64);
+        let vector_buffer = points.iter().copied().collect::<VectorBuffer>();
+        let hash_map_buffer = points.iter().copied().collect::<HashMapBuffer>();
+
+        for vector_layout in [
+            ArrowVectorLayout::FixedSizeList,
+            ArrowVectorLayout::Components,
+        ] {
+            let expected_num_columns = match vector_layout {
+                ArrowVectorLayout::FixedSizeList => 16,
+                ArrowVectorLayout::Components => 10 + 5 * 3 + 4,
+            };
+
+            let record_batches = [
+                point_buffer_to_record_batch(&vector_buffer, vector_layout)?,
+                point_buffer_to_record_batch(&hash_map_buffer, vector_layout)?,
+                hash_map_buffer_into_record_batch(hash_map_buffer.clone(), vector_layout)?,
+            ];
+            for record_batch in record_batches.iter() {
+                assert_eq!(expected_num_columns, record_batch.num_columns());
+                assert_eq!(points.len(), record_batch.num_rows());
+                // The layout from the schema has the same attributes, but uses the default alignment
+                let layout_from_arrow = point_layout_from_arrow_schema(&record_batch.schema())?;
+                assert_eq!(
+                    AllDatatypesPoint::layout()
+                        .attributes()
+                        .map(|attribute| attribute.attribute_definition().clone())
+                        .collect::<Vec<_>>(),
+                    layout_from_arrow
+                        .attributes()
+                        .map(|attribute| attribute.attribute_definition().clone())
+                        .collect::<Vec<_>>()
+                );
+
+                assert_same_attribute_values(
+                    &vector_buffer,
+                    &record_batch_to_point_buffer::<VectorBuffer>(record_batch)?,
+                );
+                assert_same_attribute_values(
+                    &vector_buffer,
+                    &record_batch_to_point_buffer::<HashMapBuffer>(record_batch)?,
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_arrow_schema() -> Result<()> {
+        let layout = PointLayout::from_attributes(&[POSITION_3D, INTENSITY]);
+        let schema = point_layout_to_arrow_schema(&layout, ArrowVectorLayout::Components)?;
+        assert_eq!(
+            vec!["Position3D.x", "Position3D.y", "Position3D.z", "Intensity"],
+            schema
+                .fields()
+                .iter()
+                .map(|field| field.name().as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(&DataType::Float64, schema.field(0).data_type());
+        assert_eq!(
+            Some(&POSITION_3D.name().to_owned()),
+            schema.field(2).metadata().get(ARROW_ATTRIBUTE_METADATA_KEY)
+        );
+        assert_eq!(&DataType::UInt16, schema.field(3).data_type());
+
+        let schema = point_layout_to_arrow_schema(&layout, ArrowVectorLayout::FixedSizeList)?;
+        assert_eq!(2, schema.fields().len());
+        assert!(matches!(
+            schema.field(0).data_type(),
+            DataType::FixedSizeList(_, 3)
+        ));
+
+        let bytes_layout = PointLayout::from_attributes(&[PointAttributeDefinition::custom(
+            Cow::Borrowed("Bytes"),
+            PointAttributeDataType::ByteArray(5),
+        )]);
+        let schema = point_layout_to_arrow_schema(&bytes_layout, ArrowVectorLayout::default())?;
+        assert_eq!(&DataType::FixedSizeBinary(5), schema.field(0).data_type());
+        assert_eq!(bytes_layout, point_layout_from_arrow_schema(&schema)?);
+
+        let unsupported_schema = Schema::new(vec![Field::new("Name", DataType::Utf8, false)]);
+        assert!(point_layout_from_arrow_schema(&unsupported_schema).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_map_buffer_into_record_batch_is_zero_copy() -> Result<()> {
+        let points = random_points(16);
+        let buffer = points.iter().copied().collect::<HashMapBuffer>();
+        let position_ptr = buffer
+            .get_attribute_range_ref(&POSITION_3D, 0..points.len())
+            .as_ptr();
+        let color_ptr = buffer
+            .get_attribute_range_ref(&COLOR_RGB, 0..points.len())
+            .as_ptr();
+
+        let record_batch =
+            hash_map_buffer_into_record_batch(buffer, ArrowVectorLayout::FixedSizeList)?;
+        let position_data = record_batch
+            .column_by_name(POSITION_3D.name())
+            .unwrap()
+            .to_data();
+        if position_ptr.align_offset(std::mem::align_of::<f64>()) == 0 {
+            assert_eq!(
+                position_ptr,
+                position_data.child_data()[0].buffers()[0].as_ptr()
+            );
+        }
+        let color_data = record_batch
+            .column_by_name(COLOR_RGB.name())
+            .unwrap()
+            .to_data();
+        if color_ptr.align_offset(std::mem::align_of::<u16>()) == 0 {
+            assert_eq!(color_ptr, color_data.child_data()[0].buffers()[0].as_ptr());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_record_batch_with_nulls() {
+        let values = arrow::array::UInt16Array::from(vec![Some(1), None, Some(3)]);
+        let record_batch = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![Field::new(
+                INTENSITY.name(),
+                DataType::UInt16,
+                true,
+            )])),
+            vec![Arc::new(values)],
+        )
+        .unwrap();
+        assert!(record_batch_to_point_buffer::<VectorBuffer>(&record_batch).is_err());
+
+        let sliced_values = arrow::array::UInt16Array::from(vec![1, 2, 3, 4]).slice(1, 2);
+        let record_batch = RecordBatch::try_new(
+            Arc::new(Schema::new(vec![Field::new(
+                INTENSITY.name(),
+                DataType::UInt16,
+                false,
+            )])),
+            vec![Arc::new(sliced_values)],
+        )
+        .unwrap();
+        let buffer = record_batch_to_point_buffer::<VectorBuffer>(&record_batch).unwrap();
+        assert_eq!(
+            vec![2, 3],
+            buffer
+                .view_attribute::<u16>(&INTENSITY)
+                .into_iter()
+                .collect::<Vec<_>>()
+        );
+    }
+}
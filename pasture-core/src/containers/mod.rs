@@ -113,3 +113,8 @@ pub use self::untyped_point::*;
 
 mod slice;
 pub use self::slice::*;
+
+#[cfg(feature = "arrow")]
+mod arrow_conversion;
+#[cfg(feature = "arrow")]
+pub use self::arrow_conversion::*;
@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use std::{collections::HashMap, iter::FromIterator, ops::Range};
 
 use crate::layout::{
@@ -619,6 +619,127 @@ impl VectorBuffer {
         let end_byte = start_byte + attribute.size() as usize;
         start_byte..end_byte
     }
+
+    /// Returns all points of this buffer as a slice of `T` without copying them. This is much faster than accessing
+    /// the points one by one through [`view`](BorrowedBuffer::view), but requires that the `PointLayout` of this buffer
+    /// is exactly `T::layout()`, including the offsets of all attributes
+    ///
+    /// # Errors
+    ///
+    /// If the `PointLayout` of this buffer does not match `T::layout()`, in which case the error names the first
+    /// attribute that differs, or if the memory of this buffer is not aligned for `T`. Types with
+    /// `#[repr(C, packed)]`, which is what `#[derive(PointType)]` is usually combined with, have no alignment
+    /// requirements
+    ///
+    /// # Example
+    /// ```
+    /// # use pasture_core::containers::*;
+    /// # use pasture_core::layout::*;
+    /// # use pasture_derive::PointType;
+    /// #[repr(C, packed)]
+    /// #[derive(Copy, Clone, Debug, PartialEq, PointType, bytemuck::AnyBitPattern, bytemuck::NoUninit)]
+    /// struct MyPointType {
+    ///     #[pasture(BUILTIN_INTENSITY)]
+    ///     pub intensity: u16,
+    ///     #[pasture(BUILTIN_CLASSIFICATION)]
+    ///     pub classification: u8,
+    /// }
+    ///
+    /// let buffer = (0..4)
+    ///     .map(|index| MyPointType { intensity: index * 10, classification: 2 })
+    ///     .collect::<VectorBuffer>();
+    /// let points = buffer.as_typed_slice::<MyPointType>().unwrap();
+    /// let total_intensity: u16 = points.iter().map(|point| point.intensity).sum();
+    /// assert_eq!(60, total_intensity);
+    /// ```
+    pub fn as_typed_slice<T: PointType>(&self) -> Result<&[T]> {
+        ensure_layout_matches_type::<T>(&self.point_layout)?;
+        if self.length == 0 {
+            return Ok(&[]);
+        }
+        let byte_range = self.get_byte_range_of_points(0..self.length);
+        bytemuck::try_cast_slice(&self.storage[byte_range]).map_err(typed_slice_cast_error::<T>)
+    }
+
+    /// Like [`as_typed_slice`](Self::as_typed_slice), but returns a mutable slice through which the points of this
+    /// buffer can be modified in place
+    ///
+    /// # Errors
+    ///
+    /// Same as [`as_typed_slice`](Self::as_typed_slice)
+    pub fn as_typed_slice_mut<T: PointType>(&mut self) -> Result<&mut [T]> {
+        ensure_layout_matches_type::<T>(&self.point_layout)?;
+        if self.length == 0 {
+            return Ok(&mut []);
+        }
+        let byte_range = self.get_byte_range_of_points(0..self.length);
+        bytemuck::try_cast_slice_mut(&mut self.storage[byte_range])
+            .map_err(typed_slice_cast_error::<T>)
+    }
+}
+
+/// Returns an error that describes the first difference between `point_layout` and `T::layout()`, or `Ok(())` if they
+/// are equal
+fn ensure_layout_matches_type<T: PointType>(point_layout: &PointLayout) -> Result<()> {
+    let type_layout = T::layout();
+    let type_name = std::any::type_name::<T>();
+    if std::mem::size_of::<T>() as u64 != type_layout.size_of_point_entry() {
+        return Err(anyhow!(
+            "The layout of type {} has a size of {} bytes, but the type has {} bytes",
+            type_name,
+            type_layout.size_of_point_entry(),
+            std::mem::size_of::<T>()
+        ));
+    }
+    if *point_layout == type_layout {
+        return Ok(());
+    }
+    let mut buffer_attributes = point_layout.attributes();
+    let mut type_attributes = type_layout.attributes();
+    loop {
+        match (buffer_attributes.next(), type_attributes.next()) {
+            (Some(buffer_attribute), Some(type_attribute)) => {
+                if buffer_attribute != type_attribute {
+                    return Err(anyhow!(
+                        "The PointLayout of the buffer does not match the layout of type {}: The buffer has attribute {} where the type has attribute {}",
+                        type_name,
+                        buffer_attribute,
+                        type_attribute
+                    ));
+                }
+            }
+            (Some(buffer_attribute), None) => {
+                return Err(anyhow!(
+                    "The PointLayout of the buffer does not match the layout of type {}: The type has no attribute {}",
+                    type_name,
+                    buffer_attribute
+                ))
+            }
+            (None, Some(type_attribute)) => {
+                return Err(anyhow!(
+                    "The PointLayout of the buffer does not match the layout of type {}: The buffer has no attribute {}",
+                    type_name,
+                    type_attribute
+                ))
+            }
+            (None, None) => {
+                return Err(anyhow!(
+                    "The PointLayout of the buffer does not match the layout of type {}: All attributes are equal, but the size ({} and {} bytes) or the alignment of the points differs",
+                    type_name,
+                    point_layout.size_of_point_entry(),
+                    type_layout.size_of_point_entry()
+                ))
+            }
+        }
+    }
+}
+
+fn typed_slice_cast_error<T: PointType>(error: bytemuck::PodCastError) -> anyhow::Error {
+    anyhow!(
+        "Can't view the points of the buffer as slice of type {}: {:?}",
+        std::any::type_name::<T>(),
+        error
+    )
 }
 
 impl<'a> MakeBufferFromLayout<'a> for VectorBuffer {
@@ -998,6 +1119,12 @@ impl HashMapBuffer {
         }
     }
 
+    /// Consumes this buffer and returns the memory of all its attributes
+    #[cfg(feature = "arrow")]
+    pub(crate) fn into_attributes_storage(self) -> HashMap<PointAttributeDefinition, Vec<u8>> {
+        self.attributes_storage
+    }
+
     /// Create a new helper object through which ranges of attribute data can be pushed into this buffer
     pub fn begin_push_attributes(&mut self) -> HashMapBufferAttributePusher<'_> {
         HashMapBufferAttributePusher::new(self)
@@ -1678,7 +1805,10 @@ mod tests {
     use nalgebra::Vector3;
     use rand::{prelude::Distribution, thread_rng, Rng};
 
-    use crate::layout::{attributes::POSITION_3D, PointAttributeDataType};
+    use crate::layout::{
+        attributes::{CLASSIFICATION, POSITION_3D},
+        PointAttributeDataType,
+    };
     use crate::test_utils::*;
 
     use super::*;
@@ -2313,4 +2443,60 @@ mod tests {
             even_points.iter().copied().collect::<VectorBuffer>()
         );
     }
+
+    #[test]
+    fn test_vector_buffer_as_typed_slice() -> Result<()> {
+        let test_points = thread_rng()
+            .sample_iter::<CustomPointTypeBig, _>(DefaultPointDistribution)
+            .take(64)
+            .collect::<Vec<_>>();
+        let mut buffer = test_points.iter().copied().collect::<VectorBuffer>();
+        assert_eq!(
+            test_points.as_slice(),
+            buffer.as_typed_slice::<CustomPointTypeBig>()?
+        );
+
+        for point in buffer.as_typed_slice_mut::<CustomPointTypeBig>()? {
+            point.classification = 42;
+        }
+        assert!(buffer
+            .view_attribute::<u8>(&CLASSIFICATION)
+            .into_iter()
+            .all(|classification| classification == 42));
+
+        let empty_buffer = VectorBuffer::new_from_layout(CustomPointTypeBig::layout());
+        assert!(empty_buffer
+            .as_typed_slice::<CustomPointTypeBig>()?
+            .is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_vector_buffer_as_typed_slice_layout_mismatch() {
+        let mut buffer = (0..8)
+            .map(|_| CustomPointTypeSmall::default())
+            .collect::<VectorBuffer>();
+        let error = buffer
+            .as_typed_slice::<CustomPointTypeBig>()
+            .expect_err("Layouts don't match")
+            .to_string();
+        assert!(error.contains("GpsTime"), "{}", error);
+        assert!(buffer.as_typed_slice_mut::<CustomPointTypeBig>().is_err());
+
+        // Same attributes, but at different offsets
+        let reordered_layout = PointLayout::from_members_and_alignment(
+            &[
+                POSITION_3D.at_offset_in_type(1),
+                CLASSIFICATION.at_offset_in_type(0),
+            ],
+            1,
+        );
+        let mut reordered_buffer = VectorBuffer::new_from_layout(reordered_layout);
+        reordered_buffer.resize(8);
+        let error = reordered_buffer
+            .as_typed_slice::<CustomPointTypeSmall>()
+            .expect_err("Offsets don't match")
+            .to_string();
+        assert!(error.contains("offset 1"), "{}", error);
+    }
 }
@@ -373,6 +373,10 @@ impl Display for PointAttributeDefinition {
 /// offset of the member within the structure
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "serde_layout::UncheckedPointAttributeMember")
+)]
 pub struct PointAttributeMember {
     attribute_definition: PointAttributeDefinition,
     offset: u64,
@@ -527,6 +531,12 @@ pub mod attributes {
         datatype: PointAttributeDataType::U8,
     };
 
+    /// Attribute definition for an overlap flag, as used by the extended LAS point formats 6-10. Default datatype is U8
+    pub const OVERLAP: PointAttributeDefinition = PointAttributeDefinition {
+        name: Cow::Borrowed("Overlap"),
+        datatype: PointAttributeDataType::U8,
+    };
+
     /// Attribute definition for a classification. Default datatype is U8
     pub const CLASSIFICATION: PointAttributeDefinition = PointAttributeDefinition {
         name: Cow::Borrowed("Classification"),
@@ -666,8 +676,16 @@ pub enum FieldAlignment {
 /// To support the different memory layouts, Pasture buffers store point data as raw binary buffers internally. To work with the data,
 /// you will want to use strongly typed Rust structures. Any type `T` that you want to use for accessing point data in a strongly typed manner
 /// must implement the `PointType` trait and thus provide Pasture with a way of figuring out the attributes and memory layout of this type `T`.
+///
+/// With the `serde` feature, `PointLayout` can be serialized together with the offsets and sizes of all attributes, so
+/// that deserializing it yields the identical `PointLayout`. Deserialization fails for layouts whose attributes don't
+/// fit the stated sizes (e.g. overlapping attributes or attributes outside of the memory layout of a point)
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "serde_layout::UncheckedPointLayout")
+)]
 pub struct PointLayout {
     attributes: Vec<PointAttributeMember>,
     #[cfg_attr(feature = "serde", serde(with = "serde_layout"))]
@@ -1112,14 +1130,186 @@ mod tests {
         let deserialized: PointLayout = serde_json::from_value(serialized).unwrap();
         assert_eq!(deserialized, original_value);
     }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_point_layout_serde_round_trip() {
+        let mut layout = PointLayout::from_attributes(&[
+            POSITION_3D,
+            INTENSITY,
+            PointAttributeDefinition::custom(
+                Cow::Owned("My custom attribute".to_owned()),
+                PointAttributeDataType::ByteArray(5),
+            ),
+        ]);
+        layout.add_attribute(
+            PointAttributeDefinition::custom(
+                Cow::Borrowed("Custom"),
+                PointAttributeDataType::Custom {
+                    size: 6,
+                    min_alignment: 2,
+                    name: Uuid::from_u128(42),
+                },
+            ),
+            FieldAlignment::Packed(1),
+        );
+        layout.add_attribute(COLOR_RGB, FieldAlignment::Packed(1));
+
+        let serialized = serde_json::to_value(&layout).unwrap();
+        assert_eq!(
+            json!({
+                "attribute_definition": {
+                    "name": "My custom attribute",
+                    "datatype": { "ByteArray": 5 },
+                },
+                "offset": 26,
+                "size": 5,
+            }),
+            serialized["attributes"][2]
+        );
+        let deserialized: PointLayout = serde_json::from_value(serialized).unwrap();
+        assert_eq!(layout, deserialized);
+        for (expected, actual) in layout.attributes().zip(deserialized.attributes()) {
+            assert_eq!(expected.offset(), actual.offset());
+            assert_eq!(expected.size(), actual.size());
+        }
+        assert_eq!(
+            layout.size_of_point_entry(),
+            deserialized.size_of_point_entry()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_point_layout_serde_errors() {
+        let layout_with_attribute =
+            |name: &str, datatype: serde_json::Value, offset: u64, size: u64| {
+                json!({
+                    "attributes": [
+                        {
+                            "attribute_definition": { "name": "Intensity", "datatype": "U16" },
+                            "offset": 0,
+                            "size": 2,
+                        },
+                        {
+                            "attribute_definition": { "name": name, "datatype": datatype },
+                            "offset": offset,
+                            "size": size,
+                        },
+                    ],
+                    "memory_layout": { "align": 2, "size": 4 },
+                })
+            };
+        let valid_layout = layout_with_attribute("Classification", json!("U8"), 2, 1);
+        assert!(serde_json::from_value::<PointLayout>(valid_layout).is_ok());
+
+        let invalid_layouts = [
+            // Unknown datatype
+            layout_with_attribute("Classification", json!("U128"), 2, 16),
+            // Size does not match the datatype
+            layout_with_attribute("Classification", json!("U8"), 2, 2),
+            // Overlaps the previous attribute
+            layout_with_attribute("Classification", json!("U8"), 1, 1),
+            // Outside of the point
+            layout_with_attribute("Classification", json!("U16"), 3, 2),
+            // Duplicate name
+            layout_with_attribute("Intensity", json!("U8"), 2, 1),
+        ];
+        for invalid_layout in invalid_layouts {
+            assert!(
+                serde_json::from_value::<PointLayout>(invalid_layout.clone()).is_err(),
+                "{}",
+                invalid_layout
+            );
+        }
+        assert!(serde_json::from_value::<PointAttributeDataType>(json!("Vec2f32")).is_err());
+    }
 }
 
 #[cfg(feature = "serde")]
 mod serde_layout {
-    use std::alloc::Layout;
+    use std::{alloc::Layout, collections::HashSet, convert::TryFrom};
 
     use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
+    use super::{PointAttributeDefinition, PointAttributeMember, PointLayout};
+
+    /// A `PointAttributeMember` as it is deserialized, before its size is checked against its datatype
+    #[derive(Deserialize)]
+    #[serde(rename = "PointAttributeMember")]
+    pub(super) struct UncheckedPointAttributeMember {
+        attribute_definition: PointAttributeDefinition,
+        offset: u64,
+        size: u64,
+    }
+
+    impl TryFrom<UncheckedPointAttributeMember> for PointAttributeMember {
+        type Error = String;
+
+        fn try_from(member: UncheckedPointAttributeMember) -> Result<Self, Self::Error> {
+            let datatype_size = member.attribute_definition.size();
+            if member.size != datatype_size {
+                return Err(format!(
+                    "Size {} of attribute {} does not match the size {} of its datatype",
+                    member.size, member.attribute_definition, datatype_size
+                ));
+            }
+            Ok(Self {
+                attribute_definition: member.attribute_definition,
+                offset: member.offset,
+                size: member.size,
+            })
+        }
+    }
+
+    /// A `PointLayout` as it is deserialized, before its attributes are checked against its memory layout
+    #[derive(Deserialize)]
+    #[serde(rename = "PointLayout")]
+    pub(super) struct UncheckedPointLayout {
+        attributes: Vec<PointAttributeMember>,
+        #[serde(deserialize_with = "deserialize")]
+        memory_layout: Layout,
+    }
+
+    impl TryFrom<UncheckedPointLayout> for PointLayout {
+        type Error = String;
+
+        /// Checks the invariants that `PointLayout::add_attribute` guarantees: Unique attribute names, and attributes
+        /// that are ordered by their offsets, don't overlap and lie within the size of a point
+        fn try_from(layout: UncheckedPointLayout) -> Result<Self, Self::Error> {
+            let mut names = HashSet::new();
+            let mut end_of_previous_attribute = 0;
+            for attribute in layout.attributes.iter() {
+                if !names.insert(attribute.name()) {
+                    return Err(format!(
+                        "Point attribute {} is present more than once in the PointLayout",
+                        attribute.name()
+                    ));
+                }
+                if attribute.offset() < end_of_previous_attribute {
+                    return Err(format!(
+                        "Point attribute {} at offset {} overlaps the previous attribute",
+                        attribute.name(),
+                        attribute.offset()
+                    ));
+                }
+                end_of_previous_attribute = attribute.offset() + attribute.size();
+                if end_of_previous_attribute > layout.memory_layout.size() as u64 {
+                    return Err(format!(
+                        "Point attribute {} ends at byte {}, but a point is only {} bytes large",
+                        attribute.name(),
+                        end_of_previous_attribute,
+                        layout.memory_layout.size()
+                    ));
+                }
+            }
+            Ok(Self {
+                attributes: layout.attributes,
+                memory_layout: layout.memory_layout,
+            })
+        }
+    }
+
     #[derive(Serialize, Deserialize)]
     #[serde(rename = "Layout")]
     struct SizeAndAlignment {
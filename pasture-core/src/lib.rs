@@ -7,6 +7,8 @@
 //! For understanding Pasture, it is best to look at the [PointLayout](crate::layout::PointLayout) type and the [containers](crate::containers) module.
 
 pub extern crate nalgebra;
+#[cfg(feature = "arrow")]
+pub extern crate arrow;
 extern crate self as pasture_core;
 
 pub mod containers;
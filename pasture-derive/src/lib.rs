@@ -253,6 +253,7 @@ fn get_attribute_name_from_field(field: &Field) -> Result<String> {
                         "BUILTIN_SCANNER_CHANNEL" => Ok("ScannerChannel".into()),
                         "BUILTIN_SCAN_DIRECTION_FLAG" => Ok("ScanDirectionFlag".into()),
                         "BUILTIN_EDGE_OF_FLIGHT_LINE" => Ok("EdgeOfFlightLine".into()),
+                        "BUILTIN_OVERLAP" => Ok("Overlap".into()),
                         "BUILTIN_CLASSIFICATION" => Ok("Classification".into()),
                         "BUILTIN_SCAN_ANGLE_RANK" => Ok("ScanAngleRank".into()),
                         "BUILTIN_SCAN_ANGLE" => Ok("ScanAngle".into()),
@@ -400,6 +401,7 @@ fn calculate_offsets_and_alignment(
 /// - `BUILTIN_SCANNER_CHANNEL` corresponding to the [SCANNER_CHANNEL](pasture_core::layout::attributes::SCANNER_CHANNEL) attribute
 /// - `BUILTIN_SCAN_DIRECTION_FLAG` corresponding to the [SCAN_DIRECTION_FLAG](pasture_core::layout::attributes::SCAN_DIRECTION_FLAG) attribute
 /// - `BUILTIN_EDGE_OF_FLIGHT_LINE` corresponding to the [EDGE_OF_FLIGHT_LINE](pasture_core::layout::attributes::EDGE_OF_FLIGHT_LINE) attribute
+/// - `BUILTIN_OVERLAP` corresponding to the [OVERLAP](pasture_core::layout::attributes::OVERLAP) attribute
 /// - `BUILTIN_CLASSIFICATION` corresponding to the [CLASSIFICATION](pasture_core::layout::attributes::CLASSIFICATION) attribute
 /// - `BUILTIN_SCAN_ANGLE_RANK` corresponding to the [SCAN_ANGLE_RANK](pasture_core::layout::attributes::SCAN_ANGLE_RANK) attribute
 /// - `BUILTIN_SCAN_ANGLE` corresponding to the [SCAN_ANGLE](pasture_core::layout::attributes::SCAN_ANGLE) attribute
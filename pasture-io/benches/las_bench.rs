@@ -1,6 +1,9 @@
-use std::{fs::File, io::BufWriter};
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+};
 
-use criterion::{criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 use las::Builder;
 use pasture_core::{
     containers::{
@@ -13,7 +16,7 @@ use pasture_core::{
 use pasture_derive::PointType;
 use pasture_io::{
     base::{PointReader, PointWriter},
-    las::{LASReader, LASWriter, LasPointFormat0},
+    las::{transcode, LASReader, LASWriter, LasPointFormat0, DEFAULT_WRITE_CHUNK_SIZE},
 };
 use rand::{distributions::Uniform, thread_rng, Rng};
 use scopeguard::defer;
@@ -34,6 +37,7 @@ struct CustomPointType {
 fn random_las_point<R: Rng + ?Sized>(rng: &mut R) -> LasPointFormat0 {
     LasPointFormat0 {
         classification: rng.sample(Uniform::new(0u8, 8)),
+        classification_flags: 0,
         edge_of_flight_line: rng.gen(),
         intensity: rng.gen::<u16>(),
         number_of_returns: rng.sample(Uniform::new(0u8, 5)),
@@ -123,6 +127,24 @@ fn write_performance<'a, B: BorrowedBuffer<'a>>(points: &'a B, compressed: bool)
     writer.flush().unwrap();
 }
 
+fn write_performance_with_chunk_size<'a, B: BorrowedBuffer<'a>>(
+    points: &'a B,
+    write_chunk_size: usize,
+) {
+    let writer = BufWriter::new(File::create(WRITE_DUMMY_FILE).unwrap());
+    let header = Builder::from((1, 4)).into_header().unwrap();
+    let mut writer = LASWriter::from_writer_and_header(writer, header, false).unwrap();
+    writer.set_write_chunk_size(write_chunk_size).unwrap();
+    writer.write(points).unwrap();
+    writer.flush().unwrap();
+}
+
+fn transcode_performance(path: &str) {
+    let source = BufReader::new(File::open(path).unwrap());
+    let target = BufWriter::new(File::create(WRITE_DUMMY_FILE).unwrap());
+    transcode(source, target).unwrap();
+}
+
 fn bench(c: &mut Criterion) {
     create_dummy_files();
     defer! {
@@ -173,6 +195,43 @@ fn bench(c: &mut Criterion) {
         });
     }
 
+    {
+        let write_data = get_dummy_points()
+            .view::<LasPointFormat0>()
+            .into_iter()
+            .collect::<HashMapBuffer>();
+        c.bench_function("las_write_columnar", |b| {
+            b.iter(|| write_performance(&write_data, false))
+        });
+        c.bench_function("laz_write_columnar", |b| {
+            b.iter(|| write_performance(&write_data, true))
+        });
+    }
+
+    {
+        let write_data = get_dummy_points();
+        // The header of `write_performance_with_chunk_size` uses point format 0 with 20 bytes per point record
+        let mut group = c.benchmark_group("las_write_throughput");
+        group.throughput(Throughput::Bytes(write_data.len() as u64 * 20));
+        for write_chunk_size in [1_000, DEFAULT_WRITE_CHUNK_SIZE] {
+            group.bench_with_input(
+                BenchmarkId::from_parameter(write_chunk_size),
+                &write_chunk_size,
+                |b, &write_chunk_size| {
+                    b.iter(|| write_performance_with_chunk_size(&write_data, write_chunk_size))
+                },
+            );
+        }
+        group.finish();
+    }
+
+    c.bench_function("las_to_laz_transcode", |b| {
+        b.iter(|| transcode_performance(LAS_PATH))
+    });
+    c.bench_function("laz_to_las_transcode", |b| {
+        b.iter(|| transcode_performance(LAZ_PATH))
+    });
+
     {
         let write_data_custom_format = get_dummy_points_custom_format();
         c.bench_function("las_write_custom_format", |b| {
@@ -190,6 +190,7 @@ fn write_test_point<W: Write>(writer: &mut W, index: usize, las_header: &Header)
             return_number: test_data_return_numbers_extended()[index],
             scan_direction_flag: test_data_scan_direction_flags()[index] as u8,
             scanner_channel: test_data_scanner_channels()[index],
+            overlap: (test_data_classification_flags()[index] >> 3) & 1,
         };
         write_las_bit_attributes(BitAttributes::Extended(bit_attributes), writer)?;
     } else {
@@ -5,11 +5,12 @@ use pasture_core::{
     containers::{BorrowedBuffer, InterleavedBuffer, VectorBuffer},
     layout::attributes::CLASSIFICATION,
 };
-use pasture_io::{base::PointReader, las::LASReader};
+use pasture_io::{base::PointReader, open_reader};
 
 fn main() -> Result<()> {
-    let mut las_reader = LASReader::from_path("pointcloud.las", false)?;
-    let points = las_reader.read::<VectorBuffer>(las_reader.remaining_points())?;
+    // `open_reader` picks the right reader for the format of the file
+    let mut reader = open_reader("pointcloud.las")?;
+    let points = reader.read_all::<VectorBuffer>()?;
 
     const CLASS_BUILDING: u8 = 6;
     for (index, _) in points
@@ -0,0 +1,50 @@
+use anyhow::{Context, Result};
+use pasture_core::{
+    containers::{BorrowedBuffer, HashMapBuffer, VectorBuffer},
+    layout::attributes::POSITION_3D,
+    nalgebra::Vector3,
+};
+use pasture_io::{
+    base::{GenericPointWriterOptions, PointReader, PointWriter, StdoutFormat},
+    create_writer_stdout, open_reader_stdin,
+};
+
+/// Reads points from stdin, keeps all points whose height is within the given range and writes them as ASCII to
+/// stdout, e.g.:
+///
+/// `cat pointcloud.las | cargo run --example stdio_pipeline -- 10.0 20.0 > filtered.xyz`
+fn main() -> Result<()> {
+    let args = std::env::args().collect::<Vec<_>>();
+    if args.len() != 3 {
+        eprintln!("Usage: {} <MIN_Z> <MAX_Z> < INPUT > OUTPUT", args[0]);
+        std::process::exit(1);
+    }
+    let min_z = args[1].parse::<f64>().context("Invalid MIN_Z")?;
+    let max_z = args[2].parse::<f64>().context("Invalid MAX_Z")?;
+
+    // The format of the input is detected from its first bytes
+    let mut reader = open_reader_stdin(None)?;
+    let point_layout = reader.get_default_point_layout().clone();
+    // Without a format string, the ASCII output gets one column for each attribute of the input that it can store
+    let mut writer = create_writer_stdout(
+        StdoutFormat::Ascii,
+        &point_layout,
+        GenericPointWriterOptions::Default,
+    )?;
+
+    // Neither stdin nor stdout have to hold all points at once, so the points are filtered in chunks
+    const CHUNK_SIZE: usize = 50_000;
+    loop {
+        let chunk = reader.read::<HashMapBuffer>(CHUNK_SIZE)?;
+        if chunk.is_empty() {
+            break;
+        }
+        let positions = chunk.view_attribute::<Vector3<f64>>(&POSITION_3D);
+        let filtered_points = chunk.filter::<VectorBuffer, _>(|index| {
+            let z = positions.at(index).z;
+            z >= min_z && z <= max_z
+        });
+        writer.write(&filtered_points)?;
+    }
+    writer.finish()
+}
@@ -1,4 +1,5 @@
 use anyhow::{bail, Result};
+use pasture_core::layout::{attributes, PointAttributeDefinition, PointLayout};
 // This enum maps the different entrys on an ascii file to later map these entries to the corresponding attribute.
 #[derive(Debug)]
 pub(crate) enum PointDataType {
@@ -81,4 +82,81 @@ impl PointDataType {
         }
         Ok(parse_layout)
     }
+
+    /// Returns the point attribute that this column is read from or written to, or `None` for skipped columns
+    pub(crate) fn attribute(&self) -> Option<PointAttributeDefinition> {
+        match self {
+            PointDataType::Skip => None,
+            PointDataType::CoordinateX
+            | PointDataType::CoordinateY
+            | PointDataType::CoordinateZ => Some(attributes::POSITION_3D),
+            PointDataType::Intensity => Some(attributes::INTENSITY),
+            PointDataType::ReturnNumber => Some(attributes::RETURN_NUMBER),
+            PointDataType::NumberOfReturns => Some(attributes::NUMBER_OF_RETURNS),
+            PointDataType::Classification => Some(attributes::CLASSIFICATION),
+            PointDataType::UserData => Some(attributes::USER_DATA),
+            PointDataType::ColorR | PointDataType::ColorG | PointDataType::ColorB => {
+                Some(attributes::COLOR_RGB)
+            }
+            PointDataType::GpsTime => Some(attributes::GPS_TIME),
+            PointDataType::PointSourceID => Some(attributes::POINT_SOURCE_ID),
+            PointDataType::EdgeOfFlightLine => Some(attributes::EDGE_OF_FLIGHT_LINE),
+            PointDataType::ScanDirectionFlag => Some(attributes::SCAN_DIRECTION_FLAG),
+            PointDataType::ScanAngleRank => Some(attributes::SCAN_ANGLE_RANK),
+            PointDataType::Nir => Some(attributes::NIR),
+        }
+    }
+
+    /// Returns the name of this column as it appears in the header row written by the `AsciiWriter`
+    pub(crate) fn column_name(&self) -> &'static str {
+        match self {
+            PointDataType::Skip => "",
+            PointDataType::CoordinateX => "x",
+            PointDataType::CoordinateY => "y",
+            PointDataType::CoordinateZ => "z",
+            PointDataType::Intensity => "intensity",
+            PointDataType::ReturnNumber => "return_number",
+            PointDataType::NumberOfReturns => "number_of_returns",
+            PointDataType::Classification => "classification",
+            PointDataType::UserData => "user_data",
+            PointDataType::ColorR => "red",
+            PointDataType::ColorG => "green",
+            PointDataType::ColorB => "blue",
+            PointDataType::GpsTime => "gps_time",
+            PointDataType::PointSourceID => "point_source_id",
+            PointDataType::EdgeOfFlightLine => "edge_of_flight_line",
+            PointDataType::ScanDirectionFlag => "scan_direction_flag",
+            PointDataType::ScanAngleRank => "scan_angle_rank",
+            PointDataType::Nir => "nir",
+        }
+    }
+}
+
+/// Returns a format string with one column for each attribute of `point_layout` that can be written to ASCII files, in
+/// the order of the attributes. All other attributes are skipped
+pub(crate) fn format_from_point_layout(point_layout: &PointLayout) -> String {
+    let format_literals = [
+        (attributes::POSITION_3D, "xyz"),
+        (attributes::INTENSITY, "i"),
+        (attributes::RETURN_NUMBER, "r"),
+        (attributes::NUMBER_OF_RETURNS, "n"),
+        (attributes::CLASSIFICATION, "c"),
+        (attributes::GPS_TIME, "t"),
+        (attributes::USER_DATA, "u"),
+        (attributes::POINT_SOURCE_ID, "p"),
+        (attributes::COLOR_RGB, "RGB"),
+        (attributes::EDGE_OF_FLIGHT_LINE, "e"),
+        (attributes::SCAN_DIRECTION_FLAG, "d"),
+        (attributes::SCAN_ANGLE_RANK, "a"),
+        (attributes::NIR, "I"),
+    ];
+    point_layout
+        .attributes()
+        .filter_map(|attribute| {
+            format_literals
+                .iter()
+                .find(|(known_attribute, _)| known_attribute.name() == attribute.name())
+                .map(|(_, literals)| *literals)
+        })
+        .collect()
 }
@@ -7,6 +7,8 @@ use std::io::{BufRead, BufReader, Read};
 use std::path::Path;
 
 use crate::ascii::RawAsciiReader;
+#[cfg(feature = "compression")]
+use crate::base::DecompressingRead;
 use crate::base::PointReader;
 
 /// `PointReader` implementation for ascii files
@@ -45,6 +47,28 @@ impl AsciiReader<BufReader<File>> {
     }
 }
 
+#[cfg(feature = "compression")]
+impl AsciiReader<DecompressingRead> {
+    /// Creates a new `AsciiReader` for the file at the given `path`, which may be gzip or Zstandard compressed (e.g.
+    /// `points.xyz.gz`). The compression is determined from the magic bytes at the start of the file or from its
+    /// extension, and uncompressed files are read as with [`AsciiReader::from_path`]. See [`DecompressingRead`] for
+    /// more information.
+    ///
+    /// # Errors
+    ///
+    /// If `path` does not exist, cannot be opened or its decoder can't be created, an error is returned.
+    ///
+    /// If `format` contains unrecoginzed literals, an error is returned.
+    pub fn from_path_decompressed<P: AsRef<Path>>(
+        path: P,
+        format: &str,
+        delimiter: &str,
+    ) -> Result<Self> {
+        let read = DecompressingRead::from_path(path)?;
+        Self::from_read(read, format, delimiter)
+    }
+}
+
 impl<R: BufRead + Read> AsciiReader<R> {
     /// Creates a new `AsciiReader` from the given `read`.
     /// The `delimiter` string slice is the column seperation pattern.
@@ -142,4 +166,8 @@ impl<R: BufRead + Read> PointReader for AsciiReader<R> {
     fn get_default_point_layout(&self) -> &PointLayout {
         self.raw_reader.get_default_point_layout()
     }
+
+    fn point_index(&self) -> Option<usize> {
+        self.raw_reader.point_index()
+    }
 }
@@ -1,6 +1,6 @@
 use std::{
     fs::File,
-    io::{BufWriter, Seek, Write},
+    io::{BufWriter, Write},
     path::Path,
 };
 
@@ -12,7 +12,7 @@ use crate::base::PointWriter;
 use super::{AsciiFormat, RawAsciiWriter};
 
 /// `PointWriterFormatting` implementation for Ascii files
-pub struct AsciiWriter<T: Write + Seek> {
+pub struct AsciiWriter<T: Write> {
     raw_writer: RawAsciiWriter<T>,
 }
 
@@ -45,7 +45,7 @@ impl AsciiWriter<BufWriter<File>> {
     }
 }
 
-impl<T: Write + Seek> AsciiWriter<T> {
+impl<T: Write> AsciiWriter<T> {
     /// Creates a new `AsciiWriter` from the given `write`.
     /// The `format` string slice coordinates the interpretation of each column.
     /// The following literals can be interpreted from `AsciiWriter`:
@@ -92,9 +92,44 @@ impl<T: Write + Seek> AsciiWriter<T> {
             raw_writer: RawAsciiWriter::from_write(write, format)?,
         })
     }
+
+    /// Sets the number of decimal places that are written for the floating-point column with the given
+    /// `format_literal`. Only the `x`, `y`, `z` and `t` columns are floating-point columns. A `precision` of `None`
+    /// writes as many digits as are required to read back the exact same value, which is the default for the
+    /// `x`, `y` and `z` columns so that positions are never truncated. The GPS time is written with 5 decimal
+    /// places by default. [`AsciiFormat::set_precision`] sets the precision of all floating-point columns at once
+    ///
+    /// # Errors
+    ///
+    /// If `format_literal` does not refer to a floating-point column, an error is returned
+    pub fn set_column_precision(
+        &mut self,
+        format_literal: char,
+        precision: Option<usize>,
+    ) -> Result<()> {
+        self.raw_writer
+            .set_column_precision(format_literal, precision)
+    }
+
+    /// Sets whether a header row with the names of all columns is written before the first point. No header row
+    /// is written by default
+    ///
+    /// # Errors
+    ///
+    /// If points have already been written, an error is returned
+    pub fn set_write_header(&mut self, write_header: bool) -> Result<()> {
+        self.raw_writer.set_write_header(write_header)
+    }
+
+    /// Sets the `placeholder` that is written for columns whose attribute is not part of the written point buffer.
+    /// If no placeholder is set, which is the default, writing such a buffer returns an error
+    pub fn set_missing_attribute_placeholder(&mut self, placeholder: Option<&str>) {
+        self.raw_writer
+            .set_missing_attribute_placeholder(placeholder);
+    }
 }
 
-impl<T: Write + Seek> PointWriter for AsciiWriter<T> {
+impl<T: Write> PointWriter for AsciiWriter<T> {
     fn write<'a, B: BorrowedBuffer<'a>>(&mut self, points: &'a B) -> Result<()> {
         self.raw_writer.write(points)
     }
@@ -108,7 +143,7 @@ impl<T: Write + Seek> PointWriter for AsciiWriter<T> {
     }
 }
 
-impl<T: Write + Seek> AsciiFormat for AsciiWriter<T> {
+impl<T: Write> AsciiFormat for AsciiWriter<T> {
     fn set_delimiter(&mut self, delimiter: &str) {
         self.raw_writer.set_delimiter(delimiter);
     }
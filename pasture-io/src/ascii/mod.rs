@@ -19,4 +19,4 @@ pub(crate) use self::ascii_format_util::*;
 #[cfg(test)]
 mod test_util;
 #[cfg(test)]
-pub(crate) use self::test_util::*;
\ No newline at end of file
+pub(crate) use self::test_util::*;
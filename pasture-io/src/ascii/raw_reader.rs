@@ -22,6 +22,7 @@ pub(crate) struct RawAsciiReader<T: Read + BufRead> {
     delimiter: String,
     point_layout: PointLayout,
     parse_layout: Vec<PointDataType>,
+    current_point_index: usize,
 }
 impl<T: Read + BufRead> RawAsciiReader<T> {
     pub fn from_read(read: T, format: &str, delimiter: &str) -> Result<Self> {
@@ -35,33 +36,14 @@ impl<T: Read + BufRead> RawAsciiReader<T> {
             delimiter: delimiter.to_string(),
             point_layout: layout,
             parse_layout,
+            current_point_index: 0,
         })
     }
 
     fn get_point_layout_from_parse_layout(parse_layout: &[PointDataType]) -> PointLayout {
         let hashset = parse_layout
             .iter()
-            .filter(|data_type| !matches!(data_type, PointDataType::Skip))
-            .map(|data_type| match data_type {
-                PointDataType::CoordinateX
-                | PointDataType::CoordinateY
-                | PointDataType::CoordinateZ => attributes::POSITION_3D,
-                PointDataType::Intensity => attributes::INTENSITY,
-                PointDataType::ReturnNumber => attributes::RETURN_NUMBER,
-                PointDataType::NumberOfReturns => attributes::NUMBER_OF_RETURNS,
-                PointDataType::Classification => attributes::CLASSIFICATION,
-                PointDataType::UserData => attributes::USER_DATA,
-                PointDataType::ColorR | PointDataType::ColorG | PointDataType::ColorB => {
-                    attributes::COLOR_RGB
-                }
-                PointDataType::GpsTime => attributes::GPS_TIME,
-                PointDataType::PointSourceID => attributes::POINT_SOURCE_ID,
-                PointDataType::EdgeOfFlightLine => attributes::EDGE_OF_FLIGHT_LINE,
-                PointDataType::ScanDirectionFlag => attributes::SCAN_DIRECTION_FLAG,
-                PointDataType::ScanAngleRank => attributes::SCAN_ANGLE_RANK,
-                PointDataType::Nir => attributes::NIR,
-                PointDataType::Skip => panic!("Skip should be filtered"),
-            })
+            .filter_map(|data_type| data_type.attribute())
             .collect::<HashSet<_>>();
         PointLayout::from_attributes(&Vec::from_iter(hashset))
     }
@@ -301,6 +283,7 @@ impl<T: Read + BufRead> PointReader for RawAsciiReader<T> {
     {
         let layout = point_buffer.point_layout().clone();
         let mut temp_point = UntypedPointBuffer::new(&layout);
+        let mut points_read = 0;
         //read line by line
         for (index, line) in (&mut self.reader).lines().take(count).enumerate() {
             let line = line?;
@@ -311,8 +294,10 @@ impl<T: Read + BufRead> PointReader for RawAsciiReader<T> {
             unsafe {
                 point_buffer.set_point(index, temp_point.get_buffer());
             }
+            points_read += 1;
+            self.current_point_index += 1;
         }
-        Ok(count)
+        Ok(points_read)
     }
 
     fn get_default_point_layout(&self) -> &PointLayout {
@@ -322,6 +307,10 @@ impl<T: Read + BufRead> PointReader for RawAsciiReader<T> {
     fn get_metadata(&self) -> &dyn Metadata {
         &self.metadata
     }
+
+    fn point_index(&self) -> Option<usize> {
+        Some(self.current_point_index)
+    }
 }
 
 // Ascii Tests
@@ -562,6 +551,28 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_points_iterator() -> Result<()> {
+        let path = get_test_file_path("10_points_ascii_all_attributes.txt");
+        let reader = BufReader::new(File::open(path)?);
+        let mut ascii_reader = RawAsciiReader::from_read(reader, "xyzirncuRGBtpedaI", ", ")?;
+        let points = ascii_reader
+            .points::<TestPointAll>()
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(10, points.len());
+
+        let positions = points.iter().map(|p| p.position).collect::<Vec<_>>();
+        assert_eq!(test_data_positions(), positions);
+        let intensities = points.iter().map(|p| p.intensity).collect::<Vec<_>>();
+        assert_eq!(test_data_intensities(), intensities);
+        let gps_times = points.iter().map(|p| p.gps_time).collect::<Vec<_>>();
+        assert_eq!(test_data_gps_times(), gps_times);
+
+        assert!(ascii_reader.points::<TestPointAll>().next().is_none());
+
+        Ok(())
+    }
+
     #[repr(C, packed)]
     #[derive(PointType, Debug, Copy, Clone, bytemuck::AnyBitPattern, bytemuck::NoUninit)]
     struct TestPointDifferent {
@@ -594,6 +605,73 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_skip() -> Result<()> {
+        let path = get_test_file_path("10_points_ascii.txt");
+        let reader = BufReader::new(File::open(path)?);
+        let mut ascii_reader = RawAsciiReader::from_read(reader, "xyzieRGB", ", ")?;
+        assert_eq!(3, ascii_reader.skip(3)?);
+        let positions = ascii_reader
+            .read::<VectorBuffer>(2)?
+            .view_attribute::<Vector3<f64>>(&attributes::POSITION_3D)
+            .into_iter()
+            .collect::<Vec<_>>();
+        assert_eq!(&test_data_positions()[3..5], positions.as_slice());
+
+        // Skipping past the end stops at the last point
+        assert_eq!(5, ascii_reader.skip(100)?);
+        assert_eq!(0, ascii_reader.read::<VectorBuffer>(1)?.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_point_index_and_point_count() -> Result<()> {
+        let path = get_test_file_path("10_points_ascii.txt");
+        let reader = BufReader::new(File::open(path)?);
+        let mut ascii_reader = RawAsciiReader::from_read(reader, "xyzieRGB", ", ")?;
+        assert_eq!(Some(0), ascii_reader.point_index());
+        // ASCII files have no header, so the number of points is unknown
+        assert_eq!(None, ascii_reader.point_count());
+
+        ascii_reader.read::<VectorBuffer>(3)?;
+        assert_eq!(Some(3), ascii_reader.point_index());
+        ascii_reader.skip(100)?;
+        assert_eq!(Some(10), ascii_reader.point_index());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_into_slice() -> Result<()> {
+        let path = get_test_file_path("10_points_ascii.txt");
+        let reader = BufReader::new(File::open(path)?);
+        let mut ascii_reader = RawAsciiReader::from_read(reader, "xyzieRGB", ", ")?;
+        let untouched_point = TestPointDifferent {
+            position: Vector3::new(-1.0, -1.0, -1.0),
+            user_data: 42,
+            classification: 42,
+        };
+        let untouched_position = untouched_point.position;
+        let mut points = vec![untouched_point; 16];
+
+        assert_eq!(10, ascii_reader.read_into_slice(&mut points)?);
+        let positions = points[..10]
+            .iter()
+            .map(|point| point.position)
+            .collect::<Vec<_>>();
+        assert_eq!(test_data_positions(), positions);
+        for point in &points[10..] {
+            let position = point.position;
+            let classification = point.classification;
+            assert_eq!(untouched_position, position);
+            assert_eq!(42, classification);
+        }
+
+        assert_eq!(0, ascii_reader.read_into_slice(&mut points)?);
+        Ok(())
+    }
+
     #[test]
     #[should_panic(expected = "FormatError can't interpret format literal")]
     fn test_error_format_unrecognized_literal() {
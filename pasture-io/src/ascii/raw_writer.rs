@@ -1,6 +1,6 @@
 use super::PointDataType;
 use crate::base::PointWriter;
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use pasture_core::containers::{BorrowedBuffer, UntypedPoint, UntypedPointSlice};
 use pasture_core::layout::{attributes, PointLayout};
 use pasture_core::nalgebra::Vector3;
@@ -10,40 +10,107 @@ pub trait AsciiFormat {
     fn set_delimiter(&mut self, delimiter: &str);
     fn set_precision(&mut self, precision: usize);
 }
-pub(crate) struct RawAsciiWriter<T: std::io::Write + std::io::Seek> {
+pub(crate) struct RawAsciiWriter<T: std::io::Write> {
     writer: T,
     delimiter: String,
-    precision: usize,
+    position_precision: [Option<usize>; 3],
+    gps_time_precision: Option<usize>,
+    write_header: bool,
+    requires_header: bool,
+    missing_attribute_placeholder: Option<String>,
     parse_layout: Vec<PointDataType>,
     default_layout: PointLayout,
 }
 
-impl<T: std::io::Write + std::io::Seek> RawAsciiWriter<T> {
+impl<T: std::io::Write> RawAsciiWriter<T> {
     pub fn from_write(write: T, format: &str) -> Result<Self> {
         Ok(Self {
             writer: write,
             delimiter: String::from(", "),
-            precision: 5,
+            position_precision: [None; 3],
+            gps_time_precision: Some(5),
+            write_header: false,
+            requires_header: true,
+            missing_attribute_placeholder: None,
             parse_layout: PointDataType::get_parse_layout(format)?,
             default_layout: PointLayout::default(),
         })
     }
+
+    pub fn set_column_precision(
+        &mut self,
+        format_literal: char,
+        precision: Option<usize>,
+    ) -> Result<()> {
+        match format_literal {
+            'x' => self.position_precision[0] = precision,
+            'y' => self.position_precision[1] = precision,
+            'z' => self.position_precision[2] = precision,
+            't' => self.gps_time_precision = precision,
+            _ => bail!(
+                "Format literal '{}' does not refer to a floating-point column",
+                format_literal
+            ),
+        }
+        Ok(())
+    }
+
+    pub fn set_write_header(&mut self, write_header: bool) -> Result<()> {
+        if !self.requires_header {
+            bail!("The header row can't be changed after points have been written");
+        }
+        self.write_header = write_header;
+        Ok(())
+    }
+
+    pub fn set_missing_attribute_placeholder(&mut self, placeholder: Option<&str>) {
+        self.missing_attribute_placeholder = placeholder.map(String::from);
+    }
+
+    fn write_header_if_required(&mut self) -> Result<()> {
+        if !self.requires_header {
+            return Ok(());
+        }
+        self.requires_header = false;
+        if !self.write_header {
+            return Ok(());
+        }
+        let header = self
+            .parse_layout
+            .iter()
+            .map(|data_type| data_type.column_name())
+            .collect::<Vec<_>>()
+            .join(&self.delimiter);
+        self.writer.write_all(header.as_bytes())?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
 }
 
-impl<T: std::io::Write + std::io::Seek> AsciiFormat for RawAsciiWriter<T> {
+impl<T: std::io::Write> AsciiFormat for RawAsciiWriter<T> {
     fn set_delimiter(&mut self, delimiter: &str) {
         self.delimiter = String::from(delimiter);
     }
     fn set_precision(&mut self, precision: usize) {
-        self.precision = precision;
+        self.position_precision = [Some(precision); 3];
+        self.gps_time_precision = Some(precision);
     }
 }
-impl<T: std::io::Write + std::io::Seek> PointWriterFormatting for RawAsciiWriter<T> {}
+impl<T: std::io::Write> PointWriterFormatting for RawAsciiWriter<T> {}
 
-impl<T: std::io::Write + std::io::Seek> PointWriter for RawAsciiWriter<T> {
+impl<T: std::io::Write> PointWriter for RawAsciiWriter<T> {
     fn write<'a, B: BorrowedBuffer<'a>>(&mut self, points: &'a B) -> anyhow::Result<()> {
-        //let point = UntypedPointBuffer::new(&self.default_layout);
+        self.write_header_if_required()?;
         let buffer_layout = points.point_layout();
+        // Columns whose attribute is not in the buffer. These are written as the placeholder if one is set, otherwise
+        // reading the attribute fails with an error
+        let missing_columns = self
+            .parse_layout
+            .iter()
+            .map(|data_type| {
+                matches!(data_type.attribute(), Some(attribute) if !buffer_layout.has_attribute_with_name(attribute.name()))
+            })
+            .collect::<Vec<_>>();
 
         // Similar to RawLASReader, write points in chunks of a fixed size to prevent overhead of
         // repeated virtual calls to 'dyn PointBuffer'
@@ -71,123 +138,114 @@ impl<T: std::io::Write + std::io::Seek> PointWriter for RawAsciiWriter<T> {
                 let point = UntypedPointSlice::new(buffer_layout, &mut chunk_buffer[start..end]);
                 //write point
                 for (index, format_literal) in self.parse_layout.iter().enumerate() {
-                    match format_literal {
-                        PointDataType::Skip => {}
-                        PointDataType::CoordinateX => {
-                            let pos =
-                                point.get_attribute::<Vector3<f64>>(&attributes::POSITION_3D)?;
-                            self.writer.write_all(
-                                trim_unnecessary_tailing_zeros(&format!(
-                                    "{:.1$}",
-                                    pos.x, self.precision
-                                ))
-                                .as_bytes(),
-                            )?;
-                        }
-                        PointDataType::CoordinateY => {
-                            let pos =
-                                point.get_attribute::<Vector3<f64>>(&attributes::POSITION_3D)?;
-                            self.writer.write_all(
-                                trim_unnecessary_tailing_zeros(&format!(
-                                    "{:.1$}",
-                                    pos.y, self.precision
-                                ))
-                                .as_bytes(),
-                            )?;
-                        }
-                        PointDataType::CoordinateZ => {
-                            let pos =
-                                point.get_attribute::<Vector3<f64>>(&attributes::POSITION_3D)?;
-                            self.writer.write_all(
-                                trim_unnecessary_tailing_zeros(&format!(
-                                    "{:.1$}",
-                                    pos.z, self.precision
-                                ))
-                                .as_bytes(),
-                            )?;
-                        }
-                        PointDataType::Intensity => {
-                            let intensity = point.get_attribute::<u64>(&attributes::INTENSITY)?;
-                            self.writer.write_all(intensity.to_string().as_bytes())?;
-                        }
-                        PointDataType::ReturnNumber => {
-                            let return_number =
-                                point.get_attribute::<u64>(&attributes::RETURN_NUMBER)?;
-                            self.writer
-                                .write_all(return_number.to_string().as_bytes())?;
-                        }
-                        PointDataType::NumberOfReturns => {
-                            let number_of_returns =
-                                point.get_attribute::<u64>(&attributes::NUMBER_OF_RETURNS)?;
-                            self.writer
-                                .write_all(number_of_returns.to_string().as_bytes())?;
-                        }
-                        PointDataType::Classification => {
-                            let classification =
-                                point.get_attribute::<u64>(&attributes::RETURN_NUMBER)?;
-                            self.writer
-                                .write_all(classification.to_string().as_bytes())?;
-                        }
-                        PointDataType::UserData => {
-                            let classification =
-                                point.get_attribute::<u64>(&attributes::RETURN_NUMBER)?;
-                            self.writer
-                                .write_all(classification.to_string().as_bytes())?;
-                        }
-                        PointDataType::ColorR => {
-                            let color =
-                                point.get_attribute::<Vector3<u16>>(&attributes::COLOR_RGB)?;
-                            self.writer.write_all(color[0].to_string().as_bytes())?;
-                        }
-                        PointDataType::ColorG => {
-                            let color =
-                                point.get_attribute::<Vector3<u16>>(&attributes::COLOR_RGB)?;
-                            self.writer.write_all(color[1].to_string().as_bytes())?;
-                        }
-                        PointDataType::ColorB => {
-                            let color =
-                                point.get_attribute::<Vector3<u16>>(&attributes::COLOR_RGB)?;
-                            self.writer.write_all(color[2].to_string().as_bytes())?;
-                        }
-                        PointDataType::GpsTime => {
-                            let gps_time = point.get_attribute::<f64>(&attributes::GPS_TIME)?;
-                            self.writer.write_all(
-                                trim_unnecessary_tailing_zeros(&format!(
-                                    "{:.1$}",
-                                    gps_time, self.precision
-                                ))
-                                .as_bytes(),
-                            )?;
-                        }
-                        PointDataType::PointSourceID => {
-                            let point_source_id =
-                                point.get_attribute::<u64>(&attributes::POINT_SOURCE_ID)?;
-                            self.writer
-                                .write_all(point_source_id.to_string().as_bytes())?;
-                        }
-                        PointDataType::EdgeOfFlightLine => {
-                            let edge_of_flight_line =
-                                point.get_attribute::<u8>(&attributes::EDGE_OF_FLIGHT_LINE)?;
-                            self.writer.write_all(
-                                (if edge_of_flight_line > 0 { "1" } else { "0" }).as_bytes(),
-                            )?;
-                        }
-                        PointDataType::ScanDirectionFlag => {
-                            let scan_direction_flag =
-                                point.get_attribute::<u8>(&attributes::SCAN_DIRECTION_FLAG)?;
-                            self.writer.write_all(
-                                (if scan_direction_flag > 0 { "1" } else { "0" }).as_bytes(),
-                            )?;
-                        }
-                        PointDataType::ScanAngleRank => {
-                            let scan_angle_rank =
-                                point.get_attribute::<i64>(&attributes::SCAN_ANGLE_RANK)?;
-                            self.writer
-                                .write_all(scan_angle_rank.to_string().as_bytes())?;
-                        }
-                        PointDataType::Nir => {
-                            let nir = point.get_attribute::<u64>(&attributes::NIR)?;
-                            self.writer.write_all(nir.to_string().as_bytes())?;
+                    if let (true, Some(placeholder)) =
+                        (missing_columns[index], &self.missing_attribute_placeholder)
+                    {
+                        self.writer.write_all(placeholder.as_bytes())?;
+                    } else {
+                        match format_literal {
+                            PointDataType::Skip => {}
+                            PointDataType::CoordinateX => {
+                                let pos = point
+                                    .get_attribute::<Vector3<f64>>(&attributes::POSITION_3D)?;
+                                self.writer.write_all(
+                                    format_float(pos.x, self.position_precision[0]).as_bytes(),
+                                )?;
+                            }
+                            PointDataType::CoordinateY => {
+                                let pos = point
+                                    .get_attribute::<Vector3<f64>>(&attributes::POSITION_3D)?;
+                                self.writer.write_all(
+                                    format_float(pos.y, self.position_precision[1]).as_bytes(),
+                                )?;
+                            }
+                            PointDataType::CoordinateZ => {
+                                let pos = point
+                                    .get_attribute::<Vector3<f64>>(&attributes::POSITION_3D)?;
+                                self.writer.write_all(
+                                    format_float(pos.z, self.position_precision[2]).as_bytes(),
+                                )?;
+                            }
+                            PointDataType::Intensity => {
+                                let intensity =
+                                    point.get_attribute::<u64>(&attributes::INTENSITY)?;
+                                self.writer.write_all(intensity.to_string().as_bytes())?;
+                            }
+                            PointDataType::ReturnNumber => {
+                                let return_number =
+                                    point.get_attribute::<u64>(&attributes::RETURN_NUMBER)?;
+                                self.writer
+                                    .write_all(return_number.to_string().as_bytes())?;
+                            }
+                            PointDataType::NumberOfReturns => {
+                                let number_of_returns =
+                                    point.get_attribute::<u64>(&attributes::NUMBER_OF_RETURNS)?;
+                                self.writer
+                                    .write_all(number_of_returns.to_string().as_bytes())?;
+                            }
+                            PointDataType::Classification => {
+                                let classification =
+                                    point.get_attribute::<u64>(&attributes::RETURN_NUMBER)?;
+                                self.writer
+                                    .write_all(classification.to_string().as_bytes())?;
+                            }
+                            PointDataType::UserData => {
+                                let classification =
+                                    point.get_attribute::<u64>(&attributes::RETURN_NUMBER)?;
+                                self.writer
+                                    .write_all(classification.to_string().as_bytes())?;
+                            }
+                            PointDataType::ColorR => {
+                                let color =
+                                    point.get_attribute::<Vector3<u16>>(&attributes::COLOR_RGB)?;
+                                self.writer.write_all(color[0].to_string().as_bytes())?;
+                            }
+                            PointDataType::ColorG => {
+                                let color =
+                                    point.get_attribute::<Vector3<u16>>(&attributes::COLOR_RGB)?;
+                                self.writer.write_all(color[1].to_string().as_bytes())?;
+                            }
+                            PointDataType::ColorB => {
+                                let color =
+                                    point.get_attribute::<Vector3<u16>>(&attributes::COLOR_RGB)?;
+                                self.writer.write_all(color[2].to_string().as_bytes())?;
+                            }
+                            PointDataType::GpsTime => {
+                                let gps_time = point.get_attribute::<f64>(&attributes::GPS_TIME)?;
+                                self.writer.write_all(
+                                    format_float(gps_time, self.gps_time_precision).as_bytes(),
+                                )?;
+                            }
+                            PointDataType::PointSourceID => {
+                                let point_source_id =
+                                    point.get_attribute::<u64>(&attributes::POINT_SOURCE_ID)?;
+                                self.writer
+                                    .write_all(point_source_id.to_string().as_bytes())?;
+                            }
+                            PointDataType::EdgeOfFlightLine => {
+                                let edge_of_flight_line =
+                                    point.get_attribute::<u8>(&attributes::EDGE_OF_FLIGHT_LINE)?;
+                                self.writer.write_all(
+                                    (if edge_of_flight_line > 0 { "1" } else { "0" }).as_bytes(),
+                                )?;
+                            }
+                            PointDataType::ScanDirectionFlag => {
+                                let scan_direction_flag =
+                                    point.get_attribute::<u8>(&attributes::SCAN_DIRECTION_FLAG)?;
+                                self.writer.write_all(
+                                    (if scan_direction_flag > 0 { "1" } else { "0" }).as_bytes(),
+                                )?;
+                            }
+                            PointDataType::ScanAngleRank => {
+                                let scan_angle_rank =
+                                    point.get_attribute::<i64>(&attributes::SCAN_ANGLE_RANK)?;
+                                self.writer
+                                    .write_all(scan_angle_rank.to_string().as_bytes())?;
+                            }
+                            PointDataType::Nir => {
+                                let nir = point.get_attribute::<u64>(&attributes::NIR)?;
+                                self.writer.write_all(nir.to_string().as_bytes())?;
+                            }
                         }
                     }
                     if index != self.parse_layout.len() - 1 {
@@ -201,6 +259,7 @@ impl<T: std::io::Write + std::io::Seek> PointWriter for RawAsciiWriter<T> {
     }
 
     fn flush(&mut self) -> anyhow::Result<()> {
+        self.write_header_if_required()?;
         self.writer.flush().context("Flush failed")
     }
 
@@ -209,6 +268,19 @@ impl<T: std::io::Write + std::io::Seek> PointWriter for RawAsciiWriter<T> {
     }
 }
 
+/// Formats `value` with the given number of decimal places, or with as many digits as are required to read back the
+/// exact same value if `precision` is `None`
+fn format_float(value: f64, precision: Option<usize>) -> String {
+    match precision {
+        Some(precision) => {
+            trim_unnecessary_tailing_zeros(&format!("{:.1$}", value, precision)).to_string()
+        }
+        // The `Debug` output is the shortest representation that round-trips and always keeps a decimal point or
+        // exponent, so integral values are written as e.g. `1.0` like with a fixed precision
+        None => format!("{:?}", value),
+    }
+}
+
 fn trim_unnecessary_tailing_zeros(slice: &str) -> &str {
     let start = 0;
     let mut end = slice.len();
@@ -222,10 +294,11 @@ fn trim_unnecessary_tailing_zeros(slice: &str) -> &str {
 mod tests {
     use std::{
         fs::File,
-        io::{BufRead, BufReader, BufWriter},
+        io::{BufRead, BufReader, BufWriter, Cursor},
     };
 
-    use crate::ascii::{get_test_file_path, test_data_buffer};
+    use crate::ascii::{get_test_file_path, test_data_buffer, RawAsciiReader};
+    use crate::base::PointReader;
 
     use super::*;
     use anyhow::Result;
@@ -287,6 +360,127 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_write_header_and_column_precision() -> Result<()> {
+        let layout = PointLayout::from_attributes(&[attributes::POSITION_3D, attributes::GPS_TIME]);
+        let mut buffer = VectorBuffer::new_from_layout(layout.clone());
+        let mut point = UntypedPointBuffer::new(&layout);
+        point.set_attribute(
+            &attributes::POSITION_3D,
+            &Vector3::new(1234567.123456789, 0.1, -2.0),
+        )?;
+        point.set_attribute(&attributes::GPS_TIME, &0.123456789)?;
+        unsafe {
+            buffer.push_points(point.get_buffer());
+        }
+
+        let mut data = vec![];
+        {
+            let mut writer = RawAsciiWriter::from_write(Cursor::new(&mut data), "xyzts")?;
+            writer.set_delimiter(";");
+            writer.set_write_header(true)?;
+            writer.set_column_precision('z', Some(2))?;
+            writer.set_column_precision('t', Some(3))?;
+            assert!(writer.set_column_precision('i', Some(3)).is_err());
+            writer.write(&buffer)?;
+            writer.flush()?;
+            assert!(writer.set_write_header(false).is_err());
+        }
+        assert_eq!(
+            "x;y;z;gps_time;\n1234567.123456789;0.1;-2.0;0.123;\n",
+            String::from_utf8(data)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_header_without_points() -> Result<()> {
+        let mut data = vec![];
+        {
+            let mut writer = RawAsciiWriter::from_write(Cursor::new(&mut data), "xyzi")?;
+            writer.set_write_header(true)?;
+            writer.flush()?;
+        }
+        assert_eq!("x, y, z, intensity\n", String::from_utf8(data)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_missing_attribute_placeholder() -> Result<()> {
+        let layout = PointLayout::from_attributes(&[attributes::POSITION_3D]);
+        let mut buffer = VectorBuffer::new_from_layout(layout.clone());
+        let mut point = UntypedPointBuffer::new(&layout);
+        point.set_attribute(&attributes::POSITION_3D, &Vector3::new(1.0, 2.0, 3.0))?;
+        unsafe {
+            buffer.push_points(point.get_buffer());
+        }
+
+        let mut data = vec![];
+        {
+            let mut writer = RawAsciiWriter::from_write(Cursor::new(&mut data), "xiR")?;
+            assert!(writer.write(&buffer).is_err());
+        }
+
+        let mut data = vec![];
+        {
+            let mut writer = RawAsciiWriter::from_write(Cursor::new(&mut data), "xiR")?;
+            writer.set_missing_attribute_placeholder(Some("NA"));
+            writer.write(&buffer)?;
+            writer.flush()?;
+        }
+        assert_eq!("1.0, NA, NA\n", String::from_utf8(data)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_read_round_trip() -> Result<()> {
+        let layout = PointLayout::from_attributes(&[
+            attributes::POSITION_3D,
+            attributes::INTENSITY,
+            attributes::GPS_TIME,
+        ]);
+        let positions = vec![
+            Vector3::new(1234567.123456789, -0.000001234, 1e-12),
+            Vector3::new(0.1 + 0.2, f64::MAX, -98765.4321),
+        ];
+        let gps_times = [0.123456789, 123456.987654321];
+        let mut buffer = VectorBuffer::new_from_layout(layout.clone());
+        for (position, gps_time) in positions.iter().zip(gps_times.iter()) {
+            let mut point = UntypedPointBuffer::new(&layout);
+            point.set_attribute(&attributes::POSITION_3D, position)?;
+            point.set_attribute(&attributes::INTENSITY, &42_u16)?;
+            point.set_attribute(&attributes::GPS_TIME, gps_time)?;
+            unsafe {
+                buffer.push_points(point.get_buffer());
+            }
+        }
+
+        let mut data = vec![];
+        {
+            let mut writer = RawAsciiWriter::from_write(Cursor::new(&mut data), "xyzit")?;
+            writer.set_column_precision('t', Some(4))?;
+            writer.write(&buffer)?;
+            writer.flush()?;
+        }
+
+        let mut reader = RawAsciiReader::from_read(Cursor::new(data), "xyzit", ", ")?;
+        let read_buffer = reader.read::<VectorBuffer>(2)?;
+        assert_eq!(
+            positions,
+            read_buffer
+                .view_attribute::<Vector3<f64>>(&attributes::POSITION_3D)
+                .into_iter()
+                .collect::<Vec<_>>()
+        );
+        for (expected, actual) in gps_times
+            .iter()
+            .zip(read_buffer.view_attribute::<f64>(&attributes::GPS_TIME))
+        {
+            assert!((expected - actual).abs() <= 0.00005);
+        }
+        Ok(())
+    }
+
     #[test]
     #[should_panic(expected = "FormatError can't interpret format literal")]
     fn test_error_format_unrecognized_literal() {
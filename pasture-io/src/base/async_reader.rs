@@ -0,0 +1,39 @@
+use anyhow::Result;
+use pasture_core::containers::{BorrowedMutBuffer, MakeBufferFromLayout, OwningBuffer};
+use pasture_core::layout::PointLayout;
+use pasture_core::meta::Metadata;
+
+/// Async counterpart to [`PointReader`](super::PointReader) for readers that are backed by async I/O streams. Behaves
+/// exactly like `PointReader`, except that reading points is an `async` operation
+// The futures returned by the async functions are `Send` whenever the reader and buffer types are `Send`, which is
+// all that callers on a multi-threaded runtime need, so we can silence the lint about missing `Send` bounds
+#[allow(async_fn_in_trait)]
+pub trait AsyncPointReader {
+    /// Read `count` points from this `AsyncPointReader` into the given `point_buffer`. See
+    /// [`PointReader::read_into`](super::PointReader::read_into) for details
+    async fn read_into<'a, 'b, B: BorrowedMutBuffer<'a>>(
+        &mut self,
+        point_buffer: &'b mut B,
+        count: usize,
+    ) -> Result<usize>
+    where
+        'a: 'b;
+
+    /// Reads at most `count` points from this `AsyncPointReader` into a new buffer of type `B`. The `PointLayout`
+    /// this new buffer will be equal to `self.get_default_point_layout`
+    async fn read<'a, B: OwningBuffer<'a> + MakeBufferFromLayout<'a> + 'a>(
+        &mut self,
+        count: usize,
+    ) -> Result<B> {
+        let mut buffer = B::new_from_layout(self.get_default_point_layout().clone());
+        buffer.resize(count);
+        let actual_count = self.read_into(&mut buffer, count).await?;
+        buffer.resize(actual_count);
+        Ok(buffer)
+    }
+
+    /// Returns the `Metadata` of the associated `AsyncPointReader`
+    fn get_metadata(&self) -> &dyn Metadata;
+    /// Returns the default `PointLayout` of the associated `AsyncPointReader`
+    fn get_default_point_layout(&self) -> &PointLayout;
+}
@@ -0,0 +1,276 @@
+#[cfg(feature = "compression")]
+use std::io::{BufRead, BufReader};
+use std::{fmt::Display, fs::File, io::Read, path::Path};
+
+use anyhow::{Context, Result};
+
+const GZIP_MAGIC_BYTES: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC_BYTES: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Compression formats of whole point cloud files, such as `points.xyz.gz`. With the `compression` feature, these
+/// files can be read through a [`DecompressingRead`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FileCompression {
+    /// gzip compression (`.gz` extension)
+    Gzip,
+    /// Zstandard compression (`.zst` extension)
+    Zstd,
+}
+
+impl FileCompression {
+    /// Returns the `FileCompression` that belongs to the extension of `path`, or `None` if the extension is not `.gz`
+    /// or `.zst`
+    pub fn from_extension<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "gz" => Some(Self::Gzip),
+            "zst" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Returns the `FileCompression` that belongs to the magic bytes at the start of `bytes`, or `None` if `bytes`
+    /// don't start with the magic bytes of gzip or Zstandard data
+    pub fn from_magic_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(&GZIP_MAGIC_BYTES) {
+            Some(Self::Gzip)
+        } else if bytes.starts_with(&ZSTD_MAGIC_BYTES) {
+            Some(Self::Zstd)
+        } else {
+            None
+        }
+    }
+
+    /// Determines the `FileCompression` of the file at `path` from its magic bytes, or from its extension if the
+    /// magic bytes are unknown. Returns `None` for uncompressed files
+    ///
+    /// # Errors
+    ///
+    /// If the file can't be opened or read
+    pub fn detect<P: AsRef<Path>>(path: P) -> Result<Option<Self>> {
+        let path = path.as_ref();
+        let mut magic_bytes = Vec::with_capacity(ZSTD_MAGIC_BYTES.len());
+        File::open(path)
+            .with_context(|| format!("Could not open file {}", path.display()))?
+            .take(ZSTD_MAGIC_BYTES.len() as u64)
+            .read_to_end(&mut magic_bytes)
+            .with_context(|| format!("Could not read file {}", path.display()))?;
+        Ok(Self::from_magic_bytes(&magic_bytes).or_else(|| Self::from_extension(path)))
+    }
+}
+
+impl Display for FileCompression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileCompression::Gzip => write!(f, "gzip"),
+            FileCompression::Zstd => write!(f, "Zstandard"),
+        }
+    }
+}
+
+/// A `BufRead` that transparently decompresses gzip or Zstandard compressed data, and passes uncompressed data through
+/// unchanged. This is meant for text-based formats that are read front to back, such as ASCII files (see
+/// [`AsciiReader::from_path_decompressed`](crate::ascii::AsciiReader::from_path_decompressed)). Since decompressed data
+/// can't be seeked, formats whose readers require `Seek` (LAS, 3D Tiles, PCD, ...) can't be read through a
+/// `DecompressingRead`
+#[cfg(feature = "compression")]
+pub struct DecompressingRead {
+    inner: Box<dyn BufRead + Send>,
+    compression: Option<FileCompression>,
+}
+
+#[cfg(feature = "compression")]
+impl DecompressingRead {
+    /// Opens the file at `path` and decompresses it if it is compressed. The compression is determined from the magic
+    /// bytes at the start of the file, or from its `.gz` or `.zst` extension (see [`FileCompression::detect`])
+    ///
+    /// # Errors
+    ///
+    /// If the file can't be opened, or if the decoder can't be created
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let compression = FileCompression::detect(path)?;
+        let file =
+            File::open(path).with_context(|| format!("Could not open file {}", path.display()))?;
+        Self::from_read_with_compression(BufReader::new(file), compression)
+    }
+
+    /// Wraps `read` in the decoder for the compression that is determined from the magic bytes at the start of `read`.
+    /// Data without known magic bytes is passed through unchanged
+    ///
+    /// # Errors
+    ///
+    /// If `read` can't be read, or if the decoder can't be created
+    pub fn from_read<R: BufRead + Send + 'static>(mut read: R) -> Result<Self> {
+        let compression = FileCompression::from_magic_bytes(
+            read.fill_buf()
+                .context("Could not read the magic bytes of the data")?,
+        );
+        Self::from_read_with_compression(read, compression)
+    }
+
+    /// Wraps `read` in the decoder for the given `compression`, or passes it through unchanged if `compression` is
+    /// `None`
+    ///
+    /// # Errors
+    ///
+    /// If the decoder can't be created
+    pub fn from_read_with_compression<R: BufRead + Send + 'static>(
+        read: R,
+        compression: Option<FileCompression>,
+    ) -> Result<Self> {
+        let inner: Box<dyn BufRead + Send> = match compression {
+            // `MultiGzDecoder` also reads files that consist of several gzip members, such as those of `pigz`
+            Some(FileCompression::Gzip) => {
+                Box::new(BufReader::new(flate2::bufread::MultiGzDecoder::new(read)))
+            }
+            Some(FileCompression::Zstd) => Box::new(BufReader::new(
+                zstd::stream::read::Decoder::with_buffer(read)
+                    .context("Could not create Zstandard decoder")?,
+            )),
+            None => Box::new(read),
+        };
+        Ok(Self { inner, compression })
+    }
+
+    /// Returns the compression of the underlying data, or `None` if it is uncompressed
+    pub fn compression(&self) -> Option<FileCompression> {
+        self.compression
+    }
+}
+
+#[cfg(feature = "compression")]
+impl Read for DecompressingRead {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+#[cfg(feature = "compression")]
+impl BufRead for DecompressingRead {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use scopeguard::defer;
+
+    use super::*;
+
+    fn test_file_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("pasture_test_compression_{}", name))
+    }
+
+    #[test]
+    fn test_detect_compression() -> Result<()> {
+        assert_eq!(
+            Some(FileCompression::Gzip),
+            FileCompression::from_magic_bytes(&[0x1f, 0x8b, 0x08, 0x00])
+        );
+        assert_eq!(
+            Some(FileCompression::Zstd),
+            FileCompression::from_magic_bytes(&[0x28, 0xb5, 0x2f, 0xfd])
+        );
+        assert_eq!(None, FileCompression::from_magic_bytes(b"pnts"));
+        assert_eq!(None, FileCompression::from_magic_bytes(&[0x1f]));
+
+        assert_eq!(
+            Some(FileCompression::Gzip),
+            FileCompression::from_extension("points.xyz.GZ")
+        );
+        assert_eq!(
+            Some(FileCompression::Zstd),
+            FileCompression::from_extension("points.pts.zst")
+        );
+        assert_eq!(None, FileCompression::from_extension("points.xyz"));
+
+        // The magic bytes take precedence over the extension
+        let path = test_file_path("magic_bytes.zst");
+        defer! {
+            let _ = std::fs::remove_file(&path);
+        }
+        std::fs::write(&path, [0x1f, 0x8b, 0x08, 0x00, 0x00])?;
+        assert_eq!(Some(FileCompression::Gzip), FileCompression::detect(&path)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_reader_rejects_compressed_files() -> Result<()> {
+        let path = test_file_path("points.pnts.gz");
+        defer! {
+            let _ = std::fs::remove_file(&path);
+        }
+        std::fs::write(&path, [0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00])?;
+
+        let error = crate::open_reader(&path)
+            .err()
+            .expect("Opening a compressed file should fail")
+            .to_string();
+        assert!(error.contains("gzip compressed"));
+
+        Ok(())
+    }
+
+    #[cfg(all(feature = "compression", feature = "ascii"))]
+    #[test]
+    fn test_read_compressed_ascii_files() -> Result<()> {
+        use std::io::Write;
+
+        use pasture_core::{
+            containers::{BorrowedBuffer, VectorBuffer},
+            layout::attributes::POSITION_3D,
+            nalgebra::Vector3,
+        };
+
+        use crate::{
+            ascii::{get_test_file_path, test_data_positions, AsciiReader},
+            base::PointReader,
+        };
+
+        let uncompressed = std::fs::read(get_test_file_path("10_points_ascii.txt"))?;
+        let gzip_path = test_file_path("10_points_ascii.txt.gz");
+        let zstd_path = test_file_path("10_points_ascii_without_extension");
+        defer! {
+            let _ = std::fs::remove_file(&gzip_path);
+            let _ = std::fs::remove_file(&zstd_path);
+        }
+        {
+            let mut encoder = flate2::write::GzEncoder::new(
+                File::create(&gzip_path)?,
+                flate2::Compression::default(),
+            );
+            encoder.write_all(&uncompressed)?;
+            encoder.finish()?;
+        }
+        std::fs::write(&zstd_path, zstd::encode_all(uncompressed.as_slice(), 0)?)?;
+
+        for (path, expected_compression) in [
+            (gzip_path.clone(), Some(FileCompression::Gzip)),
+            (zstd_path.clone(), Some(FileCompression::Zstd)),
+            (get_test_file_path("10_points_ascii.txt"), None),
+        ] {
+            assert_eq!(
+                expected_compression,
+                DecompressingRead::from_path(&path)?.compression()
+            );
+
+            let positions = AsciiReader::from_path_decompressed(&path, "xyzieRGB", ", ")?
+                .read::<VectorBuffer>(10)?
+                .view_attribute::<Vector3<f64>>(&POSITION_3D)
+                .into_iter()
+                .collect::<Vec<_>>();
+            assert_eq!(test_data_positions(), positions);
+        }
+
+        Ok(())
+    }
+}
@@ -2,58 +2,235 @@
 
 use std::{
     fs::File,
-    io::{BufReader, BufWriter},
+    io::{BufReader, BufWriter, Read},
     path::Path,
 };
 
-use anyhow::{anyhow, Context, Result};
-use pasture_core::{containers::BorrowedMutBuffer, layout::PointLayout};
-
-// use crate::las::{LASReader, LASWriter};
-
-use crate::{
-    las::{LASReader, LASWriter},
-    tiles3d::{PntsReader, PntsWriter},
+use anyhow::{anyhow, bail, Context, Result};
+use pasture_core::{
+    containers::BorrowedMutBuffer,
+    layout::{attributes::POSITION_3D, PointLayout},
 };
 
-use super::{PointReader, PointWriter, SeekToPoint};
+#[cfg(feature = "ascii")]
+use crate::ascii::{format_from_point_layout, AsciiWriter};
+#[cfg(feature = "gltf")]
+use crate::gltf::{GltfPointsWriter, GltfPointsWriterBuilder};
+#[cfg(feature = "las")]
+use crate::las::{LASReader, LASWriter, LASWriterBuilder};
+#[cfg(feature = "parquet")]
+use crate::parquet::{ParquetReader, ParquetWriter, ParquetWriterBuilder};
+#[cfg(feature = "pcd")]
+use crate::pcd::{PcdDataFormat, PcdReader, PcdWriter};
+#[cfg(feature = "raw-dump")]
+use crate::raw_dump::{raw_dump_sidecar_path, RawDumpReader};
+use crate::tiles3d::{PntsReader, PntsWriter, PntsWriterBuilder};
+
+use super::{FileCompression, PointReader, PointWriter, SeekToPoint};
 
 #[derive(Debug)]
 enum SupportedFileExtensions {
+    #[cfg(feature = "las")]
     Las,
     Tiles3D,
+    #[cfg(feature = "pcd")]
+    Pcd,
+    #[cfg(feature = "parquet")]
+    Parquet,
+    #[cfg(feature = "ascii")]
+    Ascii,
+    #[cfg(feature = "gltf")]
+    Gltf,
+}
+
+/// Returns a human-readable list of all file formats that can be read (or written, if `for_writing` is set) with the
+/// enabled features
+fn supported_formats(for_writing: bool) -> String {
+    let formats = [
+        (cfg!(feature = "las"), "LAS (.las)"),
+        (cfg!(feature = "laz"), "LAZ (.laz)"),
+        (true, "3D Tiles (.pnts)"),
+        (cfg!(feature = "pcd"), "PCD (.pcd)"),
+        (cfg!(feature = "parquet"), "Parquet (.parquet)"),
+        (
+            cfg!(feature = "ascii") && for_writing,
+            "ASCII (.txt, .xyz, .csv)",
+        ),
+        (cfg!(feature = "gltf") && for_writing, "glTF (.glb)"),
+        (
+            cfg!(feature = "raw-dump") && !for_writing,
+            "raw dumps (any extension, with a JSON sidecar)",
+        ),
+    ];
+    formats
+        .iter()
+        .filter(|(is_enabled, _)| *is_enabled)
+        .map(|(_, format)| *format)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Returns a lookup value for the file extension of the given file path, or `None` if the file has no extension or an
+/// unknown extension
+fn format_from_extension(path: &Path) -> Result<Option<SupportedFileExtensions>> {
+    let extension = match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) => extension.to_lowercase(),
+        None => return Ok(None),
+    };
+    match extension.as_str() {
+        #[cfg(feature = "las")]
+        "las" => Ok(Some(SupportedFileExtensions::Las)),
+        #[cfg(not(feature = "las"))]
+        "las" => Err(anyhow!(
+            "Reading and writing LAS files requires the `las` feature of pasture-io"
+        )),
+        #[cfg(feature = "laz")]
+        "laz" => Ok(Some(SupportedFileExtensions::Las)),
+        #[cfg(not(feature = "laz"))]
+        "laz" => Err(anyhow!(
+            "Reading and writing LAZ files requires the `laz` feature of pasture-io"
+        )),
+        "pnts" => Ok(Some(SupportedFileExtensions::Tiles3D)),
+        #[cfg(feature = "pcd")]
+        "pcd" => Ok(Some(SupportedFileExtensions::Pcd)),
+        #[cfg(not(feature = "pcd"))]
+        "pcd" => Err(anyhow!(
+            "Reading and writing PCD files requires the `pcd` feature of pasture-io"
+        )),
+        #[cfg(feature = "parquet")]
+        "parquet" => Ok(Some(SupportedFileExtensions::Parquet)),
+        #[cfg(not(feature = "parquet"))]
+        "parquet" => Err(anyhow!(
+            "Reading and writing Parquet files requires the `parquet` feature of pasture-io"
+        )),
+        #[cfg(feature = "ascii")]
+        "txt" | "xyz" | "csv" => Ok(Some(SupportedFileExtensions::Ascii)),
+        #[cfg(feature = "gltf")]
+        "glb" => Ok(Some(SupportedFileExtensions::Gltf)),
+        "ply" => Err(anyhow!("PLY files are not supported by pasture-io")),
+        _ => Ok(None),
+    }
+}
+
+/// Returns a lookup value for the file format of the file at `path`, determined from the magic bytes at the start of
+/// the file. Returns `None` if the magic bytes belong to no known format
+fn format_from_content(path: &Path) -> Result<Option<SupportedFileExtensions>> {
+    let mut magic_bytes = Vec::with_capacity(8);
+    File::open(path)
+        .with_context(|| format!("Could not open file {}", path.display()))?
+        .take(8)
+        .read_to_end(&mut magic_bytes)
+        .with_context(|| format!("Could not read file {}", path.display()))?;
+
+    if magic_bytes.starts_with(b"LASF") {
+        #[cfg(feature = "las")]
+        return Ok(Some(SupportedFileExtensions::Las));
+        #[cfg(not(feature = "las"))]
+        bail!("Reading and writing LAS files requires the `las` feature of pasture-io");
+    }
+    if magic_bytes.starts_with(b"pnts") {
+        return Ok(Some(SupportedFileExtensions::Tiles3D));
+    }
+    // The header of PCD files usually starts with a comment line, but the comment is optional
+    if magic_bytes.starts_with(b"# .PCD") || magic_bytes.starts_with(b"VERSION") {
+        #[cfg(feature = "pcd")]
+        return Ok(Some(SupportedFileExtensions::Pcd));
+        #[cfg(not(feature = "pcd"))]
+        bail!("Reading and writing PCD files requires the `pcd` feature of pasture-io");
+    }
+    if magic_bytes.starts_with(b"PAR1") {
+        #[cfg(feature = "parquet")]
+        return Ok(Some(SupportedFileExtensions::Parquet));
+        #[cfg(not(feature = "parquet"))]
+        bail!("Reading and writing Parquet files requires the `parquet` feature of pasture-io");
+    }
+    if let Some(compression) = FileCompression::from_magic_bytes(&magic_bytes) {
+        bail!(
+            "The file {} is {} compressed, but the readers of GenericPointReader require seeking, which compressed files don't support. Decompress the file first, or read compressed ASCII files with `AsciiReader::from_path_decompressed` (`compression` feature)",
+            path.display(),
+            compression
+        );
+    }
+    if magic_bytes.starts_with(b"ply") {
+        bail!(
+            "PLY files are not supported by pasture-io. Supported formats are: {}",
+            supported_formats(false)
+        );
+    }
+    Ok(None)
 }
 
-/// Returns a lookup value for the file extension of the given file path
+/// Returns a lookup value for the file extension of the given file path, or an error listing all supported formats if
+/// the extension is unknown
 fn get_extension_lookup(path: &Path) -> Result<SupportedFileExtensions> {
-    let extension = path.extension().ok_or_else(|| {
-        anyhow!(
-            "File extension could not be determined from path {}",
-            path.display()
-        )
-    })?;
-    let extension_str = extension.to_str().ok_or_else(|| {
+    format_from_extension(path)?.ok_or_else(|| {
         anyhow!(
-            "File extension of path {} is no valid Unicode string",
-            path.display()
+            "Unsupported file extension of path {}. Supported formats are: {}",
+            path.display(),
+            supported_formats(true)
         )
-    })?;
-    match extension_str.to_lowercase().as_str() {
-        "las" | "laz" => Ok(SupportedFileExtensions::Las),
-        "pnts" => Ok(SupportedFileExtensions::Tiles3D),
-        other => Err(anyhow!("Unsupported file extension {other}")),
-    }
+    })
 }
 
+/// Reader for all point cloud formats that pasture-io supports, with the format chosen at runtime. Use
+/// [`open_reader`] or [`GenericPointReader::open`] to open a file with the reader for its format
 pub enum GenericPointReader {
+    #[cfg(feature = "las")]
     LAS(LASReader<'static, BufReader<File>>),
     Tiles3D(PntsReader<BufReader<File>>),
+    #[cfg(feature = "pcd")]
+    PCD(PcdReader<BufReader<File>>),
+    #[cfg(feature = "parquet")]
+    Parquet(ParquetReader),
+    #[cfg(feature = "raw-dump")]
+    RawDump(RawDumpReader<BufReader<File>>),
 }
 
 impl GenericPointReader {
-    pub fn open_file<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let extension = get_extension_lookup(path.as_ref())?;
-        match extension {
+    /// Opens the point cloud file at `path` with the reader for its format. The format is determined from the file
+    /// extension. If the file has no extension or an unknown extension, the format is determined from the magic bytes
+    /// at the start of the file instead (`LASF`, `pnts`, `# .PCD` or `VERSION`, `PAR1`). Raw dump files (`raw-dump`
+    /// feature) have no fixed extension and are recognized by their JSON sidecar
+    ///
+    /// # Errors
+    ///
+    /// If the format of the file can't be determined, in which case the error lists all supported formats, or if the
+    /// reader for the format can't be created
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        // ASCII and glTF files can't be read through `GenericPointReader`, but other point cloud files might use
+        // the same extensions, so their format is determined from the content instead
+        let (format, reason) = match format_from_extension(path)? {
+            #[cfg(feature = "ascii")]
+            Some(SupportedFileExtensions::Ascii) => (
+                None,
+                " ASCII files can only be read with a format string that describes their columns, use `AsciiReader::from_path` instead.",
+            ),
+            #[cfg(feature = "gltf")]
+            Some(SupportedFileExtensions::Gltf) => {
+                (None, " Reading glTF files is not supported.")
+            }
+            format => (format, ""),
+        };
+        let format = match format {
+            Some(format) => format,
+            None => {
+                #[cfg(feature = "raw-dump")]
+                if raw_dump_sidecar_path(path).is_file() {
+                    return Ok(Self::RawDump(RawDumpReader::from_path(path)?));
+                }
+                format_from_content(path)?.ok_or_else(|| {
+                    anyhow!(
+                        "Could not determine the format of point cloud file {}.{} Supported formats are: {}",
+                        path.display(),
+                        reason,
+                        supported_formats(false)
+                    )
+                })?
+            }
+        };
+        match format {
+            #[cfg(feature = "las")]
             SupportedFileExtensions::Las => {
                 let reader = LASReader::from_path(path, false)?;
                 Ok(Self::LAS(reader))
@@ -62,19 +239,75 @@ impl GenericPointReader {
                 let reader = PntsReader::from_path(path)?;
                 Ok(Self::Tiles3D(reader))
             }
+            #[cfg(feature = "pcd")]
+            SupportedFileExtensions::Pcd => {
+                let reader = PcdReader::from_path(path)?;
+                Ok(Self::PCD(reader))
+            }
+            #[cfg(feature = "parquet")]
+            SupportedFileExtensions::Parquet => {
+                let reader = ParquetReader::from_path(path)?;
+                Ok(Self::Parquet(reader))
+            }
+            #[cfg(feature = "ascii")]
+            SupportedFileExtensions::Ascii => {
+                unreachable!("ASCII files are never detected from their content")
+            }
+            #[cfg(feature = "gltf")]
+            SupportedFileExtensions::Gltf => {
+                unreachable!("glTF files are never detected from their content")
+            }
         }
     }
 
-    /// Returns the total number of points in the underlying point cloud file. Returns `None` if the number of
-    /// points is unknown (e.g. for ASCII files which don't have header information)
-    pub fn point_count(&self) -> Option<usize> {
+    /// Opens the point cloud file at `path` with the reader for its format. Same as [`GenericPointReader::open`]
+    pub fn open_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open(path)
+    }
+
+    /// Returns `true` if the underlying reader supports seeking through [`SeekToPoint`]. For all other readers,
+    /// `seek_point` returns an error
+    pub fn supports_seeking(&self) -> bool {
         match self {
-            GenericPointReader::LAS(reader) => reader.get_metadata().number_of_points(),
-            GenericPointReader::Tiles3D(reader) => reader.get_metadata().number_of_points(),
+            #[cfg(feature = "las")]
+            GenericPointReader::LAS(_) => true,
+            GenericPointReader::Tiles3D(_) => true,
+            #[cfg(feature = "pcd")]
+            GenericPointReader::PCD(_) => false,
+            #[cfg(feature = "parquet")]
+            GenericPointReader::Parquet(_) => false,
+            #[cfg(feature = "raw-dump")]
+            GenericPointReader::RawDump(_) => true,
         }
     }
 }
 
+/// Returns the error for `options` that don't belong to the format of the file at `path`
+fn mismatched_options_error(path: &Path, options: &GenericPointWriterOptions) -> anyhow::Error {
+    anyhow!(
+        "The writer options {:?} can't be used for the file {}, as they belong to a different format",
+        options,
+        path.display()
+    )
+}
+
+/// Opens the point cloud file at `path` with the reader for its format, see [`GenericPointReader::open`] for how the
+/// format is determined. This is the recommended way of opening point cloud files if the format is not known in
+/// advance
+pub fn open_reader<P: AsRef<Path>>(path: P) -> Result<GenericPointReader> {
+    GenericPointReader::open(path)
+}
+
+/// Creates the point cloud file at `path` with the writer for the format that is chosen from the file extension, see
+/// [`GenericPointWriter::create`]. Use [`GenericPointWriterOptions::Default`] for the default settings of the format
+pub fn create_writer<P: AsRef<Path>>(
+    path: P,
+    point_layout: &PointLayout,
+    options: GenericPointWriterOptions,
+) -> Result<GenericPointWriter> {
+    GenericPointWriter::create(path, point_layout, options)
+}
+
 impl PointReader for GenericPointReader {
     fn read_into<'a, 'b, B: BorrowedMutBuffer<'a>>(
         &mut self,
@@ -85,22 +318,85 @@ impl PointReader for GenericPointReader {
         'a: 'b,
     {
         match self {
+            #[cfg(feature = "las")]
             GenericPointReader::LAS(reader) => reader.read_into(point_buffer, count),
             GenericPointReader::Tiles3D(reader) => reader.read_into(point_buffer, count),
+            #[cfg(feature = "pcd")]
+            GenericPointReader::PCD(reader) => reader.read_into(point_buffer, count),
+            #[cfg(feature = "parquet")]
+            GenericPointReader::Parquet(reader) => reader.read_into(point_buffer, count),
+            #[cfg(feature = "raw-dump")]
+            GenericPointReader::RawDump(reader) => reader.read_into(point_buffer, count),
         }
     }
 
     fn get_metadata(&self) -> &dyn pasture_core::meta::Metadata {
         match self {
+            #[cfg(feature = "las")]
             GenericPointReader::LAS(reader) => reader.get_metadata(),
             GenericPointReader::Tiles3D(reader) => reader.get_metadata(),
+            #[cfg(feature = "pcd")]
+            GenericPointReader::PCD(reader) => reader.get_metadata(),
+            #[cfg(feature = "parquet")]
+            GenericPointReader::Parquet(reader) => reader.get_metadata(),
+            #[cfg(feature = "raw-dump")]
+            GenericPointReader::RawDump(reader) => reader.get_metadata(),
         }
     }
 
     fn get_default_point_layout(&self) -> &PointLayout {
         match self {
+            #[cfg(feature = "las")]
             GenericPointReader::LAS(reader) => reader.get_default_point_layout(),
             GenericPointReader::Tiles3D(reader) => reader.get_default_point_layout(),
+            #[cfg(feature = "pcd")]
+            GenericPointReader::PCD(reader) => reader.get_default_point_layout(),
+            #[cfg(feature = "parquet")]
+            GenericPointReader::Parquet(reader) => reader.get_default_point_layout(),
+            #[cfg(feature = "raw-dump")]
+            GenericPointReader::RawDump(reader) => reader.get_default_point_layout(),
+        }
+    }
+
+    fn skip(&mut self, count: usize) -> Result<usize> {
+        match self {
+            #[cfg(feature = "las")]
+            GenericPointReader::LAS(reader) => reader.skip(count),
+            GenericPointReader::Tiles3D(reader) => reader.skip(count),
+            #[cfg(feature = "pcd")]
+            GenericPointReader::PCD(reader) => reader.skip(count),
+            #[cfg(feature = "parquet")]
+            GenericPointReader::Parquet(reader) => reader.skip(count),
+            #[cfg(feature = "raw-dump")]
+            GenericPointReader::RawDump(reader) => reader.skip(count),
+        }
+    }
+
+    fn point_index(&self) -> Option<usize> {
+        match self {
+            #[cfg(feature = "las")]
+            GenericPointReader::LAS(reader) => reader.point_index(),
+            GenericPointReader::Tiles3D(reader) => reader.point_index(),
+            #[cfg(feature = "pcd")]
+            GenericPointReader::PCD(reader) => reader.point_index(),
+            #[cfg(feature = "parquet")]
+            GenericPointReader::Parquet(reader) => reader.point_index(),
+            #[cfg(feature = "raw-dump")]
+            GenericPointReader::RawDump(reader) => reader.point_index(),
+        }
+    }
+
+    fn point_count(&self) -> Option<usize> {
+        match self {
+            #[cfg(feature = "las")]
+            GenericPointReader::LAS(reader) => reader.point_count(),
+            GenericPointReader::Tiles3D(reader) => reader.point_count(),
+            #[cfg(feature = "pcd")]
+            GenericPointReader::PCD(reader) => reader.point_count(),
+            #[cfg(feature = "parquet")]
+            GenericPointReader::Parquet(reader) => reader.point_count(),
+            #[cfg(feature = "raw-dump")]
+            GenericPointReader::RawDump(reader) => reader.point_count(),
         }
     }
 }
@@ -108,33 +404,190 @@ impl PointReader for GenericPointReader {
 impl SeekToPoint for GenericPointReader {
     fn seek_point(&mut self, position: std::io::SeekFrom) -> Result<usize> {
         match self {
+            #[cfg(feature = "las")]
             GenericPointReader::LAS(reader) => reader.seek_point(position),
             GenericPointReader::Tiles3D(reader) => reader.seek_point(position),
+            #[cfg(feature = "pcd")]
+            GenericPointReader::PCD(_) => Err(anyhow!("Seeking is not supported for PCD files")),
+            #[cfg(feature = "parquet")]
+            GenericPointReader::Parquet(_) => {
+                Err(anyhow!("Seeking is not supported for Parquet files"))
+            }
+            #[cfg(feature = "raw-dump")]
+            GenericPointReader::RawDump(reader) => reader.seek_point(position),
         }
     }
 }
 
+/// Format-specific settings for [`create_writer`]. The settings have to match the format that is chosen from the file
+/// extension, e.g. `GenericPointWriterOptions::Pcd` can only be used for `.pcd` files
+#[derive(Clone, Debug, Default)]
+pub enum GenericPointWriterOptions {
+    /// The default settings of the chosen format. LAS and LAZ files use the smallest point format that stores all LAS
+    /// attributes of the `PointLayout` (see [`point_format_from_layout`](crate::las::point_format_from_layout)), PCD
+    /// files use binary data, and ASCII files get one column for each attribute of the `PointLayout` that they can
+    /// store
+    #[default]
+    Default,
+    /// Settings for LAS and LAZ files. Whether the file is compressed is still determined from the file extension
+    #[cfg(feature = "las")]
+    Las(LASWriterBuilder),
+    /// Settings for 3D Tiles point clouds
+    Pnts(PntsWriterBuilder),
+    /// Data format of PCD files
+    #[cfg(feature = "pcd")]
+    Pcd(PcdDataFormat),
+    /// Settings for Parquet files
+    #[cfg(feature = "parquet")]
+    Parquet(ParquetWriterBuilder),
+    /// Format string that describes the columns of ASCII files, see [`AsciiWriter::from_write`]
+    #[cfg(feature = "ascii")]
+    Ascii(String),
+    /// Settings for binary glTF files
+    #[cfg(feature = "gltf")]
+    Gltf(GltfPointsWriterBuilder),
+}
+
+/// Writer for all point cloud formats that pasture-io supports, with the format chosen at runtime. Use
+/// [`create_writer`] or [`GenericPointWriter::create`] to create a file with the writer for its format
 pub enum GenericPointWriter {
+    #[cfg(feature = "las")]
     LAS(LASWriter<BufWriter<File>>),
     Tiles3D(PntsWriter<BufWriter<File>>),
+    #[cfg(feature = "pcd")]
+    PCD(PcdWriter<BufWriter<File>>),
+    #[cfg(feature = "parquet")]
+    Parquet(ParquetWriter<BufWriter<File>>),
+    #[cfg(feature = "ascii")]
+    Ascii(AsciiWriter<BufWriter<File>>),
+    #[cfg(feature = "gltf")]
+    Gltf(GltfPointsWriter<BufWriter<File>>),
 }
 
 impl GenericPointWriter {
-    pub fn open_file<P: AsRef<Path>>(path: P, point_layout: &PointLayout) -> Result<Self> {
-        let extension = get_extension_lookup(path.as_ref())?;
-        match extension {
-            SupportedFileExtensions::Las => {
-                let writer = LASWriter::from_path_and_point_layout(path, point_layout)?;
+    /// Creates the point cloud file at `path` with the writer for the format that is chosen from the file extension,
+    /// using the format-specific settings in `options`
+    ///
+    /// # Errors
+    ///
+    /// If the file extension belongs to no supported format, in which case the error lists all supported formats, if
+    /// `options` are meant for a different format, if the format can't represent points in the given `point_layout`
+    /// (e.g. LAS files require a `POSITION_3D` attribute), or if the file can't be created
+    pub fn create<P: AsRef<Path>>(
+        path: P,
+        point_layout: &PointLayout,
+        options: GenericPointWriterOptions,
+    ) -> Result<Self> {
+        let path = path.as_ref();
+        let format = get_extension_lookup(path)?;
+        match (format, options) {
+            #[cfg(feature = "las")]
+            (SupportedFileExtensions::Las, GenericPointWriterOptions::Default) => {
+                let writer = LASWriter::from_path_and_point_layout(path, point_layout)
+                    .context("Can't write the points as LAS file")?;
                 Ok(Self::LAS(writer))
             }
-            SupportedFileExtensions::Tiles3D => {
-                let file = BufWriter::new(File::create(path.as_ref()).context(format!(
-                    "Could not open file {} for writing",
-                    path.as_ref().display()
-                ))?);
-                let writer = PntsWriter::from_write_and_layout(file, point_layout.clone());
+            #[cfg(feature = "las")]
+            (SupportedFileExtensions::Las, GenericPointWriterOptions::Las(builder)) => {
+                let writer = builder.build_for_path(path)?;
+                Ok(Self::LAS(writer))
+            }
+            (SupportedFileExtensions::Tiles3D, options) => {
+                let builder = match options {
+                    GenericPointWriterOptions::Default => PntsWriterBuilder::new(),
+                    GenericPointWriterOptions::Pnts(builder) => builder,
+                    // Only reachable if other formats are enabled
+                    #[allow(unreachable_patterns)]
+                    other => return Err(mismatched_options_error(path, &other)),
+                };
+                if !point_layout.has_attribute_with_name(POSITION_3D.name()) {
+                    bail!(
+                        "Can't write the points as 3D Tiles point cloud, as the point layout {} has no {} attribute",
+                        point_layout,
+                        POSITION_3D.name()
+                    );
+                }
+                let writer = builder.build_for_path(path, point_layout.clone())?;
                 Ok(Self::Tiles3D(writer))
             }
+            #[cfg(feature = "pcd")]
+            (SupportedFileExtensions::Pcd, options) => {
+                let data_format = match options {
+                    GenericPointWriterOptions::Default => PcdDataFormat::Binary,
+                    GenericPointWriterOptions::Pcd(data_format) => data_format,
+                    other => return Err(mismatched_options_error(path, &other)),
+                };
+                let writer =
+                    PcdWriter::from_path_and_layout(path, point_layout.clone(), data_format)
+                        .context("Can't write the points as PCD file")?;
+                Ok(Self::PCD(writer))
+            }
+            #[cfg(feature = "parquet")]
+            (SupportedFileExtensions::Parquet, options) => {
+                let builder = match options {
+                    GenericPointWriterOptions::Default => ParquetWriterBuilder::new(),
+                    GenericPointWriterOptions::Parquet(builder) => builder,
+                    other => return Err(mismatched_options_error(path, &other)),
+                };
+                let writer = builder
+                    .build_for_path(path, point_layout.clone())
+                    .context("Can't write the points as Parquet file")?;
+                Ok(Self::Parquet(writer))
+            }
+            #[cfg(feature = "ascii")]
+            (SupportedFileExtensions::Ascii, options) => {
+                let format = match options {
+                    GenericPointWriterOptions::Default => format_from_point_layout(point_layout),
+                    GenericPointWriterOptions::Ascii(format) => format,
+                    other => return Err(mismatched_options_error(path, &other)),
+                };
+                if format.is_empty() {
+                    bail!(
+                        "Can't write the points as ASCII file, as the point layout {} has no attribute that ASCII files can store",
+                        point_layout
+                    );
+                }
+                let writer = AsciiWriter::from_path(path, &format)?;
+                Ok(Self::Ascii(writer))
+            }
+            #[cfg(feature = "gltf")]
+            (SupportedFileExtensions::Gltf, options) => {
+                let builder = match options {
+                    GenericPointWriterOptions::Default => GltfPointsWriterBuilder::new(),
+                    GenericPointWriterOptions::Gltf(builder) => builder,
+                    other => return Err(mismatched_options_error(path, &other)),
+                };
+                let writer = builder
+                    .build_for_path(path, point_layout.clone())
+                    .context("Can't write the points as glTF file")?;
+                Ok(Self::Gltf(writer))
+            }
+            #[allow(unreachable_patterns)]
+            (_, other) => Err(mismatched_options_error(path, &other)),
+        }
+    }
+
+    /// Creates the point cloud file at `path` with the writer for the format that is chosen from the file extension,
+    /// using the default settings of the format. Same as [`GenericPointWriter::create`] with
+    /// [`GenericPointWriterOptions::Default`]
+    pub fn open_file<P: AsRef<Path>>(path: P, point_layout: &PointLayout) -> Result<Self> {
+        Self::create(path, point_layout, GenericPointWriterOptions::Default)
+    }
+
+    /// Finishes the file after all points have been written. Unlike `flush`, this finalizes the header of LAS files
+    pub fn finish(self) -> Result<()> {
+        match self {
+            #[cfg(feature = "las")]
+            GenericPointWriter::LAS(writer) => writer.finalize().map(|_| ()),
+            GenericPointWriter::Tiles3D(mut writer) => writer.flush(),
+            #[cfg(feature = "pcd")]
+            GenericPointWriter::PCD(mut writer) => writer.flush(),
+            #[cfg(feature = "parquet")]
+            GenericPointWriter::Parquet(writer) => writer.finalize(),
+            #[cfg(feature = "ascii")]
+            GenericPointWriter::Ascii(mut writer) => writer.flush(),
+            #[cfg(feature = "gltf")]
+            GenericPointWriter::Gltf(mut writer) => writer.flush(),
         }
     }
 }
@@ -145,22 +598,368 @@ impl PointWriter for GenericPointWriter {
         points: &'a B,
     ) -> Result<()> {
         match self {
+            #[cfg(feature = "las")]
             GenericPointWriter::LAS(writer) => writer.write(points),
             GenericPointWriter::Tiles3D(writer) => writer.write(points),
+            #[cfg(feature = "pcd")]
+            GenericPointWriter::PCD(writer) => writer.write(points),
+            #[cfg(feature = "parquet")]
+            GenericPointWriter::Parquet(writer) => writer.write(points),
+            #[cfg(feature = "ascii")]
+            GenericPointWriter::Ascii(writer) => writer.write(points),
+            #[cfg(feature = "gltf")]
+            GenericPointWriter::Gltf(writer) => writer.write(points),
         }
     }
 
     fn flush(&mut self) -> Result<()> {
         match self {
+            #[cfg(feature = "las")]
             GenericPointWriter::LAS(writer) => writer.flush(),
             GenericPointWriter::Tiles3D(writer) => writer.flush(),
+            #[cfg(feature = "pcd")]
+            GenericPointWriter::PCD(writer) => writer.flush(),
+            #[cfg(feature = "parquet")]
+            GenericPointWriter::Parquet(writer) => writer.flush(),
+            #[cfg(feature = "ascii")]
+            GenericPointWriter::Ascii(writer) => writer.flush(),
+            #[cfg(feature = "gltf")]
+            GenericPointWriter::Gltf(writer) => writer.flush(),
         }
     }
 
     fn get_default_point_layout(&self) -> &PointLayout {
         match self {
+            #[cfg(feature = "las")]
             GenericPointWriter::LAS(writer) => writer.get_default_point_layout(),
             GenericPointWriter::Tiles3D(writer) => writer.get_default_point_layout(),
+            #[cfg(feature = "pcd")]
+            GenericPointWriter::PCD(writer) => writer.get_default_point_layout(),
+            #[cfg(feature = "parquet")]
+            GenericPointWriter::Parquet(writer) => writer.get_default_point_layout(),
+            #[cfg(feature = "ascii")]
+            GenericPointWriter::Ascii(writer) => writer.get_default_point_layout(),
+            #[cfg(feature = "gltf")]
+            GenericPointWriter::Gltf(writer) => writer.get_default_point_layout(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use pasture_core::containers::{BorrowedBuffer, VectorBuffer};
+    #[cfg(any(feature = "pcd", feature = "raw-dump", feature = "ascii"))]
+    use pasture_core::layout::PointType;
+    use scopeguard::defer;
+
+    use super::*;
+    #[cfg(any(feature = "pcd", feature = "raw-dump", feature = "ascii"))]
+    use crate::base::{test_points, TestPoint};
+
+    fn test_file_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("pasture_test_open_reader_{}", name))
+    }
+
+    fn get_test_pnts_path() -> PathBuf {
+        let mut test_file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_file_path.push("resources/test/points.pnts");
+        test_file_path
+    }
+
+    #[test]
+    fn test_open_reader_detects_pnts_from_content() -> Result<()> {
+        let expected_points = open_reader(get_test_pnts_path())?.read_all::<VectorBuffer>()?;
+
+        for name in ["pnts_without_extension", "pnts_with_unknown_extension.bin"] {
+            let path = test_file_path(name);
+            defer! {
+                let _ = std::fs::remove_file(&path);
+            }
+            std::fs::copy(get_test_pnts_path(), &path)?;
+
+            let mut reader = open_reader(&path)?;
+            assert!(matches!(reader, GenericPointReader::Tiles3D(_)));
+            assert!(reader.supports_seeking());
+            let points = reader.read_all::<VectorBuffer>()?;
+            assert_eq!(expected_points.point_layout(), points.point_layout());
+            assert_eq!(expected_points.len(), points.len());
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "pcd")]
+    #[test]
+    fn test_open_reader_detects_pcd_from_content() -> Result<()> {
+        let path = test_file_path("pcd.xyz");
+        defer! {
+            let _ = std::fs::remove_file(&path);
+        }
+        let expected_points = test_points();
+        {
+            let mut writer =
+                PcdWriter::from_path_and_layout(&path, TestPoint::layout(), PcdDataFormat::Binary)?;
+            writer.write(&expected_points)?;
+            writer.flush()?;
+        }
+
+        let mut reader = open_reader(&path)?;
+        assert!(matches!(reader, GenericPointReader::PCD(_)));
+        assert!(!reader.supports_seeking());
+        let points = reader.points::<TestPoint>().collect::<Result<Vec<_>>>()?;
+        assert_eq!(
+            expected_points
+                .view::<TestPoint>()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            points
+        );
+
+        Ok(())
+    }
+
+    #[cfg(feature = "raw-dump")]
+    #[test]
+    fn test_open_reader_detects_raw_dump_from_sidecar() -> Result<()> {
+        use crate::raw_dump::RawDumpWriter;
+
+        let path = test_file_path("raw_dump.bin");
+        defer! {
+            let _ = std::fs::remove_file(&path);
+            let _ = std::fs::remove_file(raw_dump_sidecar_path(&path));
+        }
+        let expected_points = test_points();
+        {
+            let mut writer = RawDumpWriter::from_path_and_layout(&path, TestPoint::layout())?;
+            writer.write(&expected_points)?;
+        }
+
+        let mut reader = open_reader(&path)?;
+        assert!(matches!(reader, GenericPointReader::RawDump(_)));
+        assert!(reader.supports_seeking());
+        let points = reader.read_all::<VectorBuffer>()?;
+        assert_eq!(
+            expected_points
+                .view::<TestPoint>()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            points.view::<TestPoint>().into_iter().collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+
+    #[cfg(feature = "las")]
+    #[test]
+    fn test_open_reader_detects_las_from_content() -> Result<()> {
+        use crate::las::{compare_to_reference_data, get_test_las_path};
+        use las::point::Format;
+
+        let path = test_file_path("las_without_extension");
+        defer! {
+            let _ = std::fs::remove_file(&path);
         }
+        std::fs::copy(get_test_las_path(0), &path)?;
+
+        let mut reader = open_reader(&path)?;
+        assert!(matches!(reader, GenericPointReader::LAS(_)));
+        assert!(reader.supports_seeking());
+        let points = reader.read_all::<VectorBuffer>()?;
+        compare_to_reference_data(&points, Format::new(0)?);
+
+        Ok(())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_open_reader_unknown_format() -> Result<()> {
+        let path = test_file_path("unknown.xyz");
+        defer! {
+            let _ = std::fs::remove_file(&path);
+        }
+        std::fs::write(&path, b"1.0 2.0 3.0\n4.0 5.0 6.0\n")?;
+
+        let error = open_reader(&path)
+            .err()
+            .expect("Opening a file with an unknown format should fail")
+            .to_string();
+        assert!(error.contains("Supported formats are"));
+        assert!(error.contains("3D Tiles (.pnts)"));
+
+        let path = test_file_path("ply");
+        defer! {
+            let _ = std::fs::remove_file(&path);
+        }
+        std::fs::write(&path, b"ply\nformat ascii 1.0\nend_header\n")?;
+        let error = open_reader(&path)
+            .err()
+            .expect("Opening a PLY file should fail")
+            .to_string();
+        assert!(error.contains("PLY files are not supported"));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "pcd")]
+    #[test]
+    fn test_create_writer_pcd() -> Result<()> {
+        let expected_points = test_points();
+        for (name, options) in [
+            (
+                "create_writer_default.pcd",
+                GenericPointWriterOptions::Default,
+            ),
+            (
+                "create_writer_ascii.pcd",
+                GenericPointWriterOptions::Pcd(PcdDataFormat::Ascii),
+            ),
+        ] {
+            let path = test_file_path(name);
+            defer! {
+                let _ = std::fs::remove_file(&path);
+            }
+            {
+                let mut writer = create_writer(&path, &TestPoint::layout(), options)?;
+                assert!(matches!(writer, GenericPointWriter::PCD(_)));
+                writer.write(&expected_points)?;
+                writer.finish()?;
+            }
+
+            let points = open_reader(&path)?.read_all::<VectorBuffer>()?;
+            assert_eq!(
+                expected_points
+                    .view::<TestPoint>()
+                    .into_iter()
+                    .collect::<Vec<_>>(),
+                points.view::<TestPoint>().into_iter().collect::<Vec<_>>()
+            );
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "las")]
+    #[test]
+    fn test_create_writer_las() -> Result<()> {
+        use crate::las::{compare_to_reference_data, get_test_las_path};
+        use las::point::Format;
+
+        let path = test_file_path("create_writer.las");
+        defer! {
+            let _ = std::fs::remove_file(&path);
+        }
+        let expected_points = open_reader(get_test_las_path(0))?.read_all::<VectorBuffer>()?;
+        {
+            let mut writer = create_writer(
+                &path,
+                expected_points.point_layout(),
+                GenericPointWriterOptions::Default,
+            )?;
+            assert!(matches!(writer, GenericPointWriter::LAS(_)));
+            writer.write(&expected_points)?;
+            writer.finish()?;
+        }
+
+        let points = open_reader(&path)?.read_all::<VectorBuffer>()?;
+        compare_to_reference_data(&points, Format::new(0)?);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "ascii")]
+    #[test]
+    fn test_create_writer_ascii_with_format_from_layout() -> Result<()> {
+        use crate::ascii::AsciiReader;
+
+        let path = test_file_path("create_writer.xyz");
+        defer! {
+            let _ = std::fs::remove_file(&path);
+        }
+        let expected_points = test_points();
+        {
+            let mut writer = create_writer(
+                &path,
+                &TestPoint::layout(),
+                GenericPointWriterOptions::Default,
+            )?;
+            assert!(matches!(writer, GenericPointWriter::Ascii(_)));
+            writer.write(&expected_points)?;
+            writer.finish()?;
+        }
+
+        let points = AsciiReader::from_path(&path, "xyzi", ", ")?
+            .points::<TestPoint>()
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(
+            expected_points
+                .view::<TestPoint>()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            points
+        );
+
+        let error = create_writer(
+            test_file_path("create_writer_no_columns.csv"),
+            &PointLayout::default(),
+            GenericPointWriterOptions::Default,
+        )
+        .err()
+        .expect("Creating an ASCII file without columns should fail")
+        .to_string();
+        assert!(error.contains("no attribute that ASCII files can store"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_writer_errors() -> Result<()> {
+        let error = create_writer(
+            test_file_path("create_writer_no_positions.pnts"),
+            &PointLayout::default(),
+            GenericPointWriterOptions::Default,
+        )
+        .err()
+        .expect("Creating a 3D Tiles file without positions should fail")
+        .to_string();
+        assert!(error.contains(POSITION_3D.name()));
+
+        #[cfg(feature = "pcd")]
+        {
+            let path = test_file_path("create_writer_mismatched_options.pnts");
+            let error = create_writer(
+                &path,
+                &PointLayout::default(),
+                GenericPointWriterOptions::Pcd(PcdDataFormat::Binary),
+            )
+            .err()
+            .expect("Creating a 3D Tiles file with PCD options should fail")
+            .to_string();
+            assert!(error.contains("belong to a different format"));
+            assert!(!path.exists());
+        }
+
+        let error = create_writer(
+            test_file_path("create_writer.ply"),
+            &PointLayout::default(),
+            GenericPointWriterOptions::Default,
+        )
+        .err()
+        .expect("Creating a PLY file should fail")
+        .to_string();
+        assert!(error.contains("PLY files are not supported"));
+
+        let error = create_writer(
+            test_file_path("create_writer.unknown"),
+            &PointLayout::default(),
+            GenericPointWriterOptions::Default,
+        )
+        .err()
+        .expect("Creating a file with an unknown extension should fail")
+        .to_string();
+        assert!(error.contains("Supported formats are"));
+        assert!(error.contains("3D Tiles (.pnts)"));
+
+        Ok(())
+    }
+}
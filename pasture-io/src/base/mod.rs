@@ -8,6 +8,14 @@ use std::path::Path;
 
 pub use self::reader::*;
 
+mod point_iterator;
+pub use self::point_iterator::*;
+
+#[cfg(feature = "async")]
+mod async_reader;
+#[cfg(feature = "async")]
+pub use self::async_reader::*;
+
 mod writer;
 pub use self::writer::*;
 
@@ -17,6 +25,60 @@ pub use self::seek::*;
 mod io_factory;
 pub use self::io_factory::*;
 
+mod compression;
+pub use self::compression::*;
+
+#[cfg(any(feature = "las", feature = "ascii", feature = "pcd"))]
+mod stdin_reader;
+#[cfg(any(feature = "las", feature = "ascii", feature = "pcd"))]
+pub use self::stdin_reader::*;
+
+#[cfg(any(
+    feature = "las",
+    feature = "ascii",
+    feature = "parquet",
+    feature = "gltf"
+))]
+mod stdout_writer;
+#[cfg(any(
+    feature = "las",
+    feature = "ascii",
+    feature = "parquet",
+    feature = "gltf"
+))]
+pub use self::stdout_writer::*;
+
+#[cfg(feature = "http")]
+mod range_reader;
+#[cfg(feature = "http")]
+pub use self::range_reader::*;
+
+#[cfg(feature = "object-store")]
+mod object_store_reader;
+#[cfg(feature = "object-store")]
+pub use self::object_store_reader::*;
+
+#[cfg(all(
+    test,
+    any(
+        feature = "las",
+        feature = "ascii",
+        feature = "pcd",
+        feature = "raw-dump"
+    )
+))]
+mod test_util;
+#[cfg(all(
+    test,
+    any(
+        feature = "las",
+        feature = "ascii",
+        feature = "pcd",
+        feature = "raw-dump"
+    )
+))]
+pub(crate) use self::test_util::*;
+
 /// Try to read all points in the given point cloud file. This function uses the default `IOFactory` to determine the
 /// file type from the file extension of `path`. If this succeeds, an appropriate reader is created and all points are
 /// read into an implementation-defined `PointBuffer` type. If you want to use a specific type of `PointBuffer`, use
@@ -24,7 +86,7 @@ pub use self::io_factory::*;
 pub fn read_all<'a, B: MakeBufferFromLayout<'a> + OwningBuffer<'a> + 'a, P: AsRef<Path>>(
     path: P,
 ) -> Result<B> {
-    let mut reader = GenericPointReader::open_file(path.as_ref()).context(format!(
+    let mut reader = GenericPointReader::open(path.as_ref()).context(format!(
         "Could not create appropriate reader for point cloud file {}",
         path.as_ref().display()
     ))?;
@@ -41,7 +103,7 @@ pub fn read_all_into<'a, B: OwningBuffer<'a>, P: AsRef<Path>>(
     buffer: &'a mut B,
     path: P,
 ) -> Result<usize> {
-    let mut reader = GenericPointReader::open_file(path.as_ref()).context(format!(
+    let mut reader = GenericPointReader::open(path.as_ref()).context(format!(
         "Could not create appropriate reader for point cloud file {}",
         path.as_ref().display()
     ))?;
@@ -63,8 +125,8 @@ pub fn write_all<'a, B: BorrowedBuffer<'a>, P: AsRef<Path>>(buffer: &'a B, path:
         "Failed to write points to file {}",
         path.as_ref().display()
     ))?;
-    writer.flush().context(format!(
-        "Failed to flush points to file {}",
+    writer.finish().context(format!(
+        "Failed to finish file {}",
         path.as_ref().display()
     ))?;
     Ok(())
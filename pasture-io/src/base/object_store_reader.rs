@@ -0,0 +1,505 @@
+use std::{
+    io::{BufRead, Read, Seek, SeekFrom},
+    ops::Range,
+    sync::{mpsc, Arc},
+    thread,
+};
+
+use anyhow::{anyhow, Context, Result};
+use bytes::Bytes;
+use object_store::{path::Path as ObjectPath, ObjectStore};
+
+/// Default number of bytes that an [`ObjectStoreReader`] fetches with each range request
+pub const DEFAULT_OBJECT_STORE_RANGE_SIZE: u64 = 4 * 1024 * 1024;
+/// Default number of bytes that an [`ObjectStoreReader`] prefetches during sequential reads
+pub const DEFAULT_OBJECT_STORE_READAHEAD: u64 = 4 * 1024 * 1024;
+
+type FetchResult = object_store::Result<Bytes>;
+
+/// Request for the IO thread to fetch a range of the object
+struct FetchRequest {
+    range: Range<usize>,
+    response: mpsc::SyncSender<FetchResult>,
+}
+
+/// Thread with a tokio runtime that runs the async requests of the `ObjectStore`, so that the `ObjectStoreReader` can
+/// be used from synchronous code, including code that runs inside of another tokio runtime. The thread stops once the
+/// `ObjectStoreReader` is dropped
+struct IoThread {
+    requests: tokio::sync::mpsc::UnboundedSender<FetchRequest>,
+}
+
+impl IoThread {
+    fn new(store: Arc<dyn ObjectStore>, location: ObjectPath) -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .context("Could not create the runtime for object store requests")?;
+        let (requests, mut receiver) = tokio::sync::mpsc::unbounded_channel::<FetchRequest>();
+        thread::Builder::new()
+            .name("pasture-object-store".into())
+            .spawn(move || {
+                runtime.block_on(async move {
+                    // All requests run concurrently, so that prefetching doesn't delay other reads
+                    while let Some(request) = receiver.recv().await {
+                        let store = store.clone();
+                        let location = location.clone();
+                        tokio::spawn(async move {
+                            let result = store.get_range(&location, request.range).await;
+                            let _ = request.response.send(result);
+                        });
+                    }
+                })
+            })
+            .context("Could not start the IO thread for object store requests")?;
+        Ok(Self { requests })
+    }
+
+    /// Starts fetching `range` and returns the receiver for the fetched bytes
+    fn fetch(&self, range: Range<u64>) -> Result<mpsc::Receiver<FetchResult>> {
+        let (response, receiver) = mpsc::sync_channel(1);
+        self.requests
+            .send(FetchRequest {
+                range: range.start as usize..range.end as usize,
+                response,
+            })
+            .map_err(|_| anyhow!("The IO thread for object store requests has stopped"))?;
+        Ok(receiver)
+    }
+}
+
+/// Range of the object that is currently being fetched in the background
+struct PendingFetch {
+    range: Range<u64>,
+    receiver: mpsc::Receiver<FetchResult>,
+}
+
+impl PendingFetch {
+    fn wait(self) -> Result<Bytes> {
+        self.receiver
+            .recv()
+            .map_err(|_| anyhow!("The IO thread for object store requests has stopped"))?
+            .context("Range request to the object store failed")
+    }
+}
+
+/// `Read`, `BufRead` and `Seek` implementation for an object in an object store such as Amazon S3, Google Cloud
+/// Storage or Azure Blob Storage, based on the [`object_store`] crate (`object-store` feature). All readers that work
+/// on a `Read + Seek` type (such as `LASReader`, `RawLASReader`, `RawLAZReader` or `CopcReader`) can read from an
+/// `ObjectStoreReader`:
+///
+/// ```no_run
+/// # use anyhow::Result;
+/// # use std::sync::Arc;
+/// use object_store::{local::LocalFileSystem, path::Path};
+/// use pasture_io::base::ObjectStoreReader;
+/// use pasture_io::tiles3d::PntsReader;
+///
+/// # fn main() -> Result<()> {
+/// let store = Arc::new(LocalFileSystem::new());
+/// let object_reader = ObjectStoreReader::new(store, Path::from("data/points.pnts"))?;
+/// let reader = PntsReader::from_read(object_reader)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Performance
+///
+/// Each request to an object store has a latency of tens of milliseconds, so the `ObjectStoreReader` never fetches
+/// single reads. Instead, it fetches the object in ranges of at least [`ObjectStoreReaderBuilder::range_size`] bytes
+/// (4 MiB by default) and serves all reads within the current range from memory, which coalesces the many small reads
+/// of the LAS header, the VLRs and the point records into few requests. Ranges of about 1 to 10 MiB are a good
+/// tradeoff between the number of requests and the amount of data that is fetched but never read. Reads that are
+/// larger than the range size are fetched with a single request of their size.
+///
+/// While the data is read sequentially, the next [`ObjectStoreReader::readahead`] bytes after the current range are
+/// prefetched in the background, so that sequential scans over all points don't wait for each request. Seeking to a
+/// position outside of the current and the prefetched range discards both and fetches a new range, so random access
+/// with a small stride (e.g. reading every n-th chunk of a LAZ file) is slower than reading sequentially.
+///
+/// The requests run on a dedicated IO thread with its own tokio runtime, so the `ObjectStoreReader` is a regular
+/// synchronous reader that can also be used within an async context
+pub struct ObjectStoreReader {
+    io_thread: IoThread,
+    length: u64,
+    position: u64,
+    range_size: u64,
+    readahead: u64,
+    current_range_start: u64,
+    current_range: Bytes,
+    prefetch: Option<PendingFetch>,
+    number_of_requests: usize,
+}
+
+impl ObjectStoreReader {
+    /// Creates an `ObjectStoreReader` for the object at `location` in `store` with the default range size and
+    /// readahead. This requests the metadata of the object to determine its length
+    ///
+    /// # Errors
+    ///
+    /// If the metadata of the object can't be requested, e.g. because the object does not exist
+    pub fn new(store: Arc<dyn ObjectStore>, location: ObjectPath) -> Result<Self> {
+        ObjectStoreReaderBuilder::new().build(store, location)
+    }
+
+    /// Returns an [`ObjectStoreReaderBuilder`] for configuring the range size and the readahead of an
+    /// `ObjectStoreReader`
+    pub fn builder() -> ObjectStoreReaderBuilder {
+        ObjectStoreReaderBuilder::new()
+    }
+
+    /// Returns the length of the object in bytes
+    pub fn len(&self) -> u64 {
+        self.length
+    }
+
+    /// Returns `true` if the object is empty
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Returns the number of bytes that are prefetched while the object is read sequentially
+    pub fn readahead(&self) -> u64 {
+        self.readahead
+    }
+
+    /// Returns the number of range requests that this `ObjectStoreReader` has sent so far, including prefetches
+    pub fn number_of_requests(&self) -> usize {
+        self.number_of_requests
+    }
+
+    fn current_range_end(&self) -> u64 {
+        self.current_range_start + self.current_range.len() as u64
+    }
+
+    fn start_fetch(&mut self, range: Range<u64>) -> Result<PendingFetch> {
+        let receiver = self.io_thread.fetch(range.clone())?;
+        self.number_of_requests += 1;
+        Ok(PendingFetch { range, receiver })
+    }
+
+    /// Makes the range that contains the current position the current range, fetching at least `min_length` bytes if
+    /// the position is not within the prefetched range
+    fn load_range(&mut self, min_length: u64) -> Result<()> {
+        // Reads are sequential if they continue at the end of the current range or within the prefetched range
+        let mut is_sequential = self.position == self.current_range_end();
+        let range = match self.prefetch.take() {
+            Some(prefetch) if prefetch.range.contains(&self.position) => {
+                is_sequential = true;
+                let start = prefetch.range.start;
+                (start, prefetch.wait()?)
+            }
+            // A prefetch that doesn't contain the position is discarded, its request completes in the background
+            _ => {
+                let end = (self.position + min_length.max(self.range_size)).min(self.length);
+                let fetch = self.start_fetch(self.position..end)?;
+                (self.position, fetch.wait()?)
+            }
+        };
+        self.current_range_start = range.0;
+        self.current_range = range.1;
+
+        let range_end = self.current_range_end();
+        if is_sequential && self.readahead > 0 && range_end < self.length {
+            let prefetch_end = (range_end + self.readahead).min(self.length);
+            self.prefetch = Some(self.start_fetch(range_end..prefetch_end)?);
+        }
+        Ok(())
+    }
+}
+
+impl Read for ObjectStoreReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() || self.position >= self.length {
+            return Ok(0);
+        }
+        if !(self.current_range_start..self.current_range_end()).contains(&self.position) {
+            self.load_range(buf.len() as u64)
+                .map_err(std::io::Error::other)?;
+        }
+        let available = self.fill_buf()?;
+        let bytes_to_copy = available.len().min(buf.len());
+        buf[..bytes_to_copy].copy_from_slice(&available[..bytes_to_copy]);
+        self.consume(bytes_to_copy);
+        Ok(bytes_to_copy)
+    }
+}
+
+impl BufRead for ObjectStoreReader {
+    /// Returns the remaining bytes of the current range, which is fetched if the position is outside of it
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        if self.position >= self.length {
+            return Ok(&[]);
+        }
+        if !(self.current_range_start..self.current_range_end()).contains(&self.position) {
+            self.load_range(0).map_err(std::io::Error::other)?;
+        }
+        let offset = (self.position - self.current_range_start) as usize;
+        Ok(&self.current_range[offset..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.position += amt as u64;
+    }
+}
+
+impl Seek for ObjectStoreReader {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let new_position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.length.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        match new_position {
+            Some(new_position) => {
+                self.position = new_position;
+                Ok(new_position)
+            }
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+}
+
+/// Builder for an [`ObjectStoreReader`]
+#[derive(Clone, Debug)]
+pub struct ObjectStoreReaderBuilder {
+    range_size: u64,
+    readahead: u64,
+}
+
+impl Default for ObjectStoreReaderBuilder {
+    fn default() -> Self {
+        Self {
+            range_size: DEFAULT_OBJECT_STORE_RANGE_SIZE,
+            readahead: DEFAULT_OBJECT_STORE_READAHEAD,
+        }
+    }
+}
+
+impl ObjectStoreReaderBuilder {
+    /// Creates a new `ObjectStoreReaderBuilder` with the default settings
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the minimum number of bytes that are fetched with each range request. Defaults to
+    /// [`DEFAULT_OBJECT_STORE_RANGE_SIZE`]
+    pub fn range_size(mut self, range_size: u64) -> Self {
+        self.range_size = range_size.max(1);
+        self
+    }
+
+    /// Sets the number of bytes that are prefetched in the background while the object is read sequentially, or `0`
+    /// to disable prefetching. Defaults to [`DEFAULT_OBJECT_STORE_READAHEAD`]
+    pub fn readahead(mut self, readahead: u64) -> Self {
+        self.readahead = readahead;
+        self
+    }
+
+    /// Creates an `ObjectStoreReader` for the object at `location` in `store`. This requests the metadata of the
+    /// object to determine its length
+    ///
+    /// # Errors
+    ///
+    /// If the metadata of the object can't be requested, e.g. because the object does not exist
+    pub fn build(
+        &self,
+        store: Arc<dyn ObjectStore>,
+        location: ObjectPath,
+    ) -> Result<ObjectStoreReader> {
+        let io_thread = IoThread::new(store.clone(), location.clone())?;
+        // The metadata is requested on a separate thread, as `block_on` panics within an async context
+        let metadata = thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    tokio::runtime::Builder::new_current_thread()
+                        .enable_all()
+                        .build()?
+                        .block_on(store.head(&location))
+                        .with_context(|| {
+                            format!("Could not request metadata of object {}", location)
+                        })
+                })
+                .join()
+                .map_err(|_| anyhow!("Requesting the metadata of object {} panicked", location))?
+        })?;
+        Ok(ObjectStoreReader {
+            io_thread,
+            length: metadata.size as u64,
+            position: 0,
+            range_size: self.range_size,
+            readahead: self.readahead,
+            current_range_start: 0,
+            current_range: Default::default(),
+            prefetch: None,
+            number_of_requests: 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use object_store::memory::InMemory;
+    use pasture_core::containers::{BorrowedBuffer, InterleavedBuffer, VectorBuffer};
+
+    use crate::{base::PointReader, tiles3d::PntsReader};
+
+    use super::*;
+
+    fn get_test_file_path(filename: &str) -> PathBuf {
+        let mut test_file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_file_path.push(format!("resources/test/{}", filename));
+        test_file_path
+    }
+
+    /// Returns an in-memory object store that contains `data` at the returned location
+    fn store_with_object(data: Vec<u8>) -> Result<(Arc<dyn ObjectStore>, ObjectPath)> {
+        let store = Arc::new(InMemory::new());
+        let location = ObjectPath::from("test/object");
+        tokio::runtime::Builder::new_current_thread()
+            .build()?
+            .block_on(store.put(&location, data.into()))?;
+        Ok((store, location))
+    }
+
+    #[test]
+    fn test_object_store_reader_read_and_seek() -> Result<()> {
+        let data = (0..10_000_u32)
+            .flat_map(|value| value.to_le_bytes())
+            .collect::<Vec<_>>();
+        let (store, location) = store_with_object(data.clone())?;
+
+        let mut reader = ObjectStoreReader::builder()
+            .range_size(1000)
+            .readahead(0)
+            .build(store, location)?;
+        assert_eq!(data.len() as u64, reader.len());
+        assert_eq!(0, reader.number_of_requests());
+
+        // Small reads are coalesced into a single range
+        let mut buffer = vec![0; 10];
+        for offset in (0..1000).step_by(10) {
+            reader.read_exact(&mut buffer)?;
+            assert_eq!(&data[offset..offset + 10], buffer.as_slice());
+        }
+        assert_eq!(1, reader.number_of_requests());
+
+        // Reads that are larger than the range size are fetched at once
+        reader.seek(SeekFrom::Start(5000))?;
+        let mut buffer = vec![0; 3000];
+        reader.read_exact(&mut buffer)?;
+        assert_eq!(&data[5000..8000], buffer.as_slice());
+        assert_eq!(2, reader.number_of_requests());
+
+        reader.seek(SeekFrom::End(-10))?;
+        let mut buffer = vec![];
+        reader.read_to_end(&mut buffer)?;
+        assert_eq!(&data[data.len() - 10..], buffer.as_slice());
+        assert_eq!(3, reader.number_of_requests());
+
+        reader.rewind()?;
+        let mut buffer = vec![];
+        reader.read_to_end(&mut buffer)?;
+        assert_eq!(data, buffer);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_object_store_reader_readahead() -> Result<()> {
+        let data = (0..4000).map(|value| value as u8).collect::<Vec<_>>();
+        let (store, location) = store_with_object(data.clone())?;
+
+        let mut reader = ObjectStoreReader::builder()
+            .range_size(1000)
+            .readahead(1000)
+            .build(store, location)?;
+        let mut buffer = vec![0; 500];
+        reader.read_exact(&mut buffer)?;
+        // The first range is fetched, and the next range is prefetched
+        assert_eq!(2, reader.number_of_requests());
+
+        // Reading into the prefetched range prefetches the range after it
+        reader.seek(SeekFrom::Start(1500))?;
+        reader.read_exact(&mut buffer)?;
+        assert_eq!(&data[1500..2000], buffer.as_slice());
+        assert_eq!(3, reader.number_of_requests());
+
+        // Seeking away from the prefetched range fetches a new range without prefetching
+        reader.seek(SeekFrom::Start(100))?;
+        reader.read_exact(&mut buffer)?;
+        assert_eq!(&data[100..600], buffer.as_slice());
+        assert_eq!(4, reader.number_of_requests());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_object_store_reader_within_async_context() -> Result<()> {
+        let path = get_test_file_path("points.pnts");
+        let expected_points = PntsReader::from_path(&path)?.read_all::<VectorBuffer>()?;
+        let store = Arc::new(InMemory::new());
+        let location = ObjectPath::from("points.pnts");
+        store.put(&location, std::fs::read(&path)?.into()).await?;
+
+        let object_reader = ObjectStoreReader::builder()
+            .range_size(128)
+            .build(store, location)?;
+        let points = PntsReader::from_read(object_reader)?.read_all::<VectorBuffer>()?;
+        assert_eq!(expected_points.point_layout(), points.point_layout());
+        assert_eq!(
+            expected_points.get_point_range_ref(0..expected_points.len()),
+            points.get_point_range_ref(0..points.len())
+        );
+
+        Ok(())
+    }
+
+    #[cfg(feature = "las")]
+    #[test]
+    fn test_object_store_reader_with_las_readers() -> Result<()> {
+        use crate::las::{
+            compare_to_reference_data, get_test_las_path, get_test_laz_path, RawLASReader,
+        };
+        use las::point::Format;
+
+        let (store, location) = store_with_object(std::fs::read(get_test_las_path(1))?)?;
+        let object_reader = ObjectStoreReader::builder()
+            .range_size(256)
+            .build(store, location)?;
+        let points = RawLASReader::from_read(object_reader, false)?.read::<VectorBuffer>(10)?;
+        compare_to_reference_data(&points, Format::new(1)?);
+
+        #[cfg(feature = "laz")]
+        {
+            use crate::las::RawLAZReader;
+
+            let (store, location) = store_with_object(std::fs::read(get_test_laz_path(1))?)?;
+            let object_reader = ObjectStoreReader::builder()
+                .range_size(256)
+                .build(store, location)?;
+            let points = RawLAZReader::from_read(object_reader, false)?.read::<VectorBuffer>(10)?;
+            compare_to_reference_data(&points, Format::new(1)?);
+        }
+        #[cfg(not(feature = "laz"))]
+        let _ = get_test_laz_path;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_object_store_reader_missing_object() -> Result<()> {
+        let store = Arc::new(InMemory::new());
+        let error = ObjectStoreReader::new(store, ObjectPath::from("missing"))
+            .err()
+            .expect("Reading a missing object should fail")
+            .to_string();
+        assert!(error.contains("Could not request metadata of object missing"));
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+use anyhow::Result;
+use pasture_core::containers::ExternalMemoryBuffer;
+use pasture_core::layout::{PointLayout, PointType};
+
+use super::PointReader;
+
+/// Iterator over the points of a `PointReader`, yielding owned values of the `PointType` `T`. Points are read in
+/// chunks of fixed size into a reusable buffer, so iterating over a point cloud never holds more than one chunk
+/// in memory. Create it through [`PointReader::points`]
+///
+/// Reading errors are yielded as `Err` items. After an error, the iterator is exhausted and returns `None`
+///
+/// Readers with filters can read zero points for a chunk in which no point matches the filter. Such a chunk only ends
+/// the iteration if the reader does not report its [`point_index`](PointReader::point_index) and
+/// [`point_count`](PointReader::point_count), or if it has no points left
+pub struct PointIterator<'r, R: PointReader, T: PointType> {
+    reader: &'r mut R,
+    point_layout: PointLayout,
+    chunk: Vec<T>,
+    points_in_chunk: usize,
+    index_in_chunk: usize,
+    is_exhausted: bool,
+}
+
+impl<'r, R: PointReader, T: PointType> PointIterator<'r, R, T> {
+    /// Number of points that are read from the underlying reader at once
+    pub const POINTS_PER_CHUNK: usize = 4096;
+
+    pub(crate) fn new(reader: &'r mut R) -> Self {
+        Self {
+            reader,
+            point_layout: T::layout(),
+            chunk: vec![bytemuck::Zeroable::zeroed(); Self::POINTS_PER_CHUNK],
+            points_in_chunk: 0,
+            index_in_chunk: 0,
+            is_exhausted: false,
+        }
+    }
+
+    fn read_next_chunk(&mut self) -> Result<usize> {
+        let mut chunk_buffer = ExternalMemoryBuffer::new(
+            bytemuck::cast_slice_mut(&mut self.chunk[..]),
+            self.point_layout.clone(),
+        );
+        self.reader
+            .read_into(&mut chunk_buffer, Self::POINTS_PER_CHUNK)
+    }
+
+    /// Is the underlying reader out of points after a read of zero points that started at `point_index_before_read`?
+    /// Readers that did not advance are treated as exhausted as well, so that the iterator never loops forever
+    fn is_end_of_data(&self, point_index_before_read: Option<usize>) -> bool {
+        match (
+            point_index_before_read,
+            self.reader.point_index(),
+            self.reader.point_count(),
+        ) {
+            (Some(index_before_read), Some(index), Some(count)) => {
+                index == index_before_read || index >= count
+            }
+            _ => true,
+        }
+    }
+}
+
+impl<'r, R: PointReader, T: PointType> Iterator for PointIterator<'r, R, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_exhausted {
+            return None;
+        }
+
+        while self.index_in_chunk == self.points_in_chunk {
+            let point_index_before_read = self.reader.point_index();
+            match self.read_next_chunk() {
+                Ok(0) if self.is_end_of_data(point_index_before_read) => {
+                    self.is_exhausted = true;
+                    return None;
+                }
+                Ok(points_read) => {
+                    self.points_in_chunk = points_read;
+                    self.index_in_chunk = 0;
+                }
+                Err(why) => {
+                    self.is_exhausted = true;
+                    return Some(Err(why));
+                }
+            }
+        }
+
+        let point = self.chunk[self.index_in_chunk];
+        self.index_in_chunk += 1;
+        Some(Ok(point))
+    }
+}
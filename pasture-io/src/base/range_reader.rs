@@ -0,0 +1,568 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{BufRead, Read, Seek, SeekFrom},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+
+/// Default size of the blocks that a [`RangeReader`] fetches with each HTTP range request
+pub const DEFAULT_RANGE_READER_BLOCK_SIZE: u64 = 64 * 1024;
+/// Default number of blocks that a [`RangeReader`] keeps in its cache
+pub const DEFAULT_RANGE_READER_CACHE_CAPACITY: usize = 256;
+
+/// Least-recently-used cache of the blocks that a `RangeReader` has fetched
+struct BlockCache {
+    capacity: usize,
+    blocks: HashMap<u64, Vec<u8>>,
+    // Block indices from least to most recently used
+    usage: VecDeque<u64>,
+}
+
+impl BlockCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            blocks: HashMap::with_capacity(capacity),
+            usage: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    fn contains(&self, block_index: u64) -> bool {
+        self.blocks.contains_key(&block_index)
+    }
+
+    fn get(&mut self, block_index: u64) -> Option<&[u8]> {
+        if !self.blocks.contains_key(&block_index) {
+            return None;
+        }
+        self.mark_as_used(block_index);
+        self.blocks.get(&block_index).map(Vec::as_slice)
+    }
+
+    fn insert(&mut self, block_index: u64, block: Vec<u8>) {
+        if self.blocks.insert(block_index, block).is_some() {
+            self.mark_as_used(block_index);
+            return;
+        }
+        self.usage.push_back(block_index);
+        while self.blocks.len() > self.capacity {
+            if let Some(evicted_block) = self.usage.pop_front() {
+                self.blocks.remove(&evicted_block);
+            }
+        }
+    }
+
+    fn mark_as_used(&mut self, block_index: u64) {
+        if let Some(position) = self.usage.iter().position(|index| *index == block_index) {
+            self.usage.remove(position);
+        }
+        self.usage.push_back(block_index);
+    }
+}
+
+/// `Read`, `BufRead` and `Seek` implementation for a file that is served over HTTP, which fetches the file in blocks through HTTP
+/// range requests (`http` feature). Fetched blocks are kept in a least-recently-used cache, so that e.g. reading the
+/// header and the chunk table of a LAZ or COPC file doesn't fetch the same data twice. All readers that work on a
+/// `Read + Seek` type (such as `LASReader`, `CopcReader` or `PntsReader`) can read from a `RangeReader`:
+///
+/// ```no_run
+/// # use anyhow::Result;
+/// use pasture_io::base::RangeReader;
+/// use pasture_io::tiles3d::PntsReader;
+///
+/// # fn main() -> Result<()> {
+/// let range_reader = RangeReader::builder()
+///     .block_size(256 * 1024)
+///     .build("https://example.com/points.pnts")?;
+/// let reader = PntsReader::from_read(range_reader)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The server has to support range requests (i.e. answer them with `206 Partial Content`)
+pub struct RangeReader {
+    agent: ureq::Agent,
+    url: String,
+    length: u64,
+    position: u64,
+    block_size: u64,
+    cache: BlockCache,
+    number_of_requests: usize,
+}
+
+impl RangeReader {
+    /// Creates a `RangeReader` for the file at `url` with the default block size and cache capacity. This fetches the
+    /// first block of the file to determine its length
+    ///
+    /// # Errors
+    ///
+    /// If the request fails, or if the server doesn't support range requests
+    pub fn new<U: Into<String>>(url: U) -> Result<Self> {
+        RangeReaderBuilder::new().build(url)
+    }
+
+    /// Returns a [`RangeReaderBuilder`] for configuring the block size and the cache capacity of a `RangeReader`
+    pub fn builder() -> RangeReaderBuilder {
+        RangeReaderBuilder::new()
+    }
+
+    /// Returns the URL of the file
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Returns the length of the file in bytes
+    pub fn len(&self) -> u64 {
+        self.length
+    }
+
+    /// Returns `true` if the file is empty
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Returns the number of HTTP requests that this `RangeReader` has sent so far
+    pub fn number_of_requests(&self) -> usize {
+        self.number_of_requests
+    }
+
+    /// Fetches the blocks `first_block..=last_block` with a single range request and inserts them into the cache
+    fn fetch_blocks(&mut self, first_block: u64, last_block: u64) -> Result<()> {
+        let start = first_block * self.block_size;
+        let end = ((last_block + 1) * self.block_size).min(self.length);
+        let (data, _) = fetch_range(&self.agent, &self.url, start, end)?;
+        self.number_of_requests += 1;
+        if data.len() as u64 != end - start {
+            bail!(
+                "Server returned {} bytes for the range {}..{} of {}",
+                data.len(),
+                start,
+                end,
+                self.url
+            );
+        }
+        for (block_index, block) in
+            (first_block..=last_block).zip(data.chunks(self.block_size as usize))
+        {
+            self.cache.insert(block_index, block.to_vec());
+        }
+        Ok(())
+    }
+}
+
+impl Read for RangeReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() || self.position >= self.length {
+            return Ok(0);
+        }
+        let end = (self.position + buf.len() as u64).min(self.length);
+        let first_block = self.position / self.block_size;
+        let last_block = (end - 1) / self.block_size;
+
+        if !self.cache.contains(first_block) {
+            // Fetch all missing blocks that this read covers at once, but not more blocks than the cache can hold
+            let last_missing_block = (first_block..=last_block)
+                .take(self.cache.capacity)
+                .take_while(|block_index| !self.cache.contains(*block_index))
+                .last()
+                .unwrap_or(first_block);
+            self.fetch_blocks(first_block, last_missing_block)
+                .map_err(std::io::Error::other)?;
+        }
+
+        let available = self.fill_buf()?;
+        let bytes_to_copy = available.len().min(buf.len());
+        buf[..bytes_to_copy].copy_from_slice(&available[..bytes_to_copy]);
+        self.consume(bytes_to_copy);
+        Ok(bytes_to_copy)
+    }
+}
+
+impl BufRead for RangeReader {
+    /// Returns the remaining bytes of the current block, which is fetched if it is not cached
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        if self.position >= self.length {
+            return Ok(&[]);
+        }
+        let block_index = self.position / self.block_size;
+        if !self.cache.contains(block_index) {
+            self.fetch_blocks(block_index, block_index)
+                .map_err(std::io::Error::other)?;
+        }
+        let offset_in_block = (self.position - block_index * self.block_size) as usize;
+        let block = self
+            .cache
+            .get(block_index)
+            .expect("Block was fetched before");
+        Ok(&block[offset_in_block..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.position += amt as u64;
+    }
+}
+
+impl Seek for RangeReader {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let new_position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.length.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        match new_position {
+            Some(new_position) => {
+                self.position = new_position;
+                Ok(new_position)
+            }
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Invalid seek to a negative or overflowing position",
+            )),
+        }
+    }
+}
+
+/// Builder for a [`RangeReader`]
+#[derive(Clone, Debug)]
+pub struct RangeReaderBuilder {
+    block_size: u64,
+    cache_capacity: usize,
+}
+
+impl Default for RangeReaderBuilder {
+    fn default() -> Self {
+        Self {
+            block_size: DEFAULT_RANGE_READER_BLOCK_SIZE,
+            cache_capacity: DEFAULT_RANGE_READER_CACHE_CAPACITY,
+        }
+    }
+}
+
+impl RangeReaderBuilder {
+    /// Creates a new `RangeReaderBuilder` with the default settings
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the size of the blocks that are fetched with each range request in bytes. Reads that span several missing
+    /// blocks fetch them with a single request. Defaults to [`DEFAULT_RANGE_READER_BLOCK_SIZE`]
+    pub fn block_size(mut self, block_size: u64) -> Self {
+        self.block_size = block_size.max(1);
+        self
+    }
+
+    /// Sets the maximum number of blocks that are kept in the cache. Defaults to
+    /// [`DEFAULT_RANGE_READER_CACHE_CAPACITY`]
+    pub fn cache_capacity(mut self, cache_capacity: usize) -> Self {
+        self.cache_capacity = cache_capacity.max(1);
+        self
+    }
+
+    /// Creates a `RangeReader` for the file at `url`. This fetches the first block of the file to determine its length
+    ///
+    /// # Errors
+    ///
+    /// If the request fails, or if the server doesn't support range requests
+    pub fn build<U: Into<String>>(&self, url: U) -> Result<RangeReader> {
+        let url = url.into();
+        let agent = ureq::Agent::new();
+        let (first_block, length) = fetch_range(&agent, &url, 0, self.block_size)?;
+        let mut cache = BlockCache::new(self.cache_capacity);
+        if !first_block.is_empty() {
+            cache.insert(0, first_block);
+        }
+        Ok(RangeReader {
+            agent,
+            url,
+            length,
+            position: 0,
+            block_size: self.block_size,
+            cache,
+            number_of_requests: 1,
+        })
+    }
+}
+
+/// Fetches the bytes `start..end` of the file at `url` with a range request. Returns the bytes and the length of the
+/// whole file, which the server reports in the `Content-Range` header. For ranges past the end of the file, fewer
+/// bytes are returned
+fn fetch_range(agent: &ureq::Agent, url: &str, start: u64, end: u64) -> Result<(Vec<u8>, u64)> {
+    let response = match agent
+        .get(url)
+        .set("Range", &format!("bytes={}-{}", start, end - 1))
+        .call()
+    {
+        Ok(response) => response,
+        // Range requests that start at the end of an empty file are not satisfiable
+        Err(ureq::Error::Status(416, response)) if start == 0 => {
+            let length = content_range_length(&response).unwrap_or(0);
+            return Ok((vec![], length));
+        }
+        Err(e) => return Err(e).with_context(|| format!("Range request to {} failed", url)),
+    };
+    if response.status() != 206 {
+        bail!(
+            "Server of {} does not support range requests (status {} instead of 206 Partial Content)",
+            url,
+            response.status()
+        );
+    }
+    let length = content_range_length(&response).ok_or_else(|| {
+        anyhow!(
+            "Server of {} did not report the length of the file in the Content-Range header",
+            url
+        )
+    })?;
+    let mut data = Vec::with_capacity((end - start) as usize);
+    response
+        .into_reader()
+        .read_to_end(&mut data)
+        .with_context(|| {
+            format!(
+                "Could not read the response of the range request to {}",
+                url
+            )
+        })?;
+    Ok((data, length))
+}
+
+/// Returns the length of the whole file from a `Content-Range` header such as `bytes 0-1023/4096`
+fn content_range_length(response: &ureq::Response) -> Option<u64> {
+    response
+        .header("Content-Range")?
+        .rsplit('/')
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::Infallible, net::SocketAddr, path::PathBuf, thread};
+
+    use http_body_util::Full;
+    use hyper::{
+        body::{Bytes, Incoming},
+        header,
+        service::service_fn,
+        Request, Response, StatusCode,
+    };
+    use hyper_util::rt::TokioIo;
+    use pasture_core::containers::{BorrowedBuffer, InterleavedBuffer, VectorBuffer};
+
+    use crate::{base::PointReader, tiles3d::PntsReader};
+
+    use super::*;
+
+    fn get_test_file_path(filename: &str) -> PathBuf {
+        let mut test_file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_file_path.push(format!("resources/test/{}", filename));
+        test_file_path
+    }
+
+    /// Answers range requests for `file` like a static file server
+    fn serve_range(file: &[u8], request: &Request<Incoming>) -> Response<Full<Bytes>> {
+        let range = request
+            .headers()
+            .get(header::RANGE)
+            .and_then(|range| range.to_str().ok())
+            .and_then(|range| range.strip_prefix("bytes="))
+            .and_then(|range| range.split_once('-'))
+            .and_then(|(start, end)| Some((start.parse::<u64>().ok()?, end.parse::<u64>().ok()?)));
+        let (start, end) = match range {
+            Some((start, end)) if start < file.len() as u64 => {
+                (start as usize, (end as usize + 1).min(file.len()))
+            }
+            _ => {
+                return Response::builder()
+                    .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                    .header(header::CONTENT_RANGE, format!("bytes */{}", file.len()))
+                    .body(Full::new(Bytes::new()))
+                    .unwrap()
+            }
+        };
+        Response::builder()
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header(
+                header::CONTENT_RANGE,
+                format!("bytes {}-{}/{}", start, end - 1, file.len()),
+            )
+            .body(Full::new(Bytes::copy_from_slice(&file[start..end])))
+            .unwrap()
+    }
+
+    /// Starts a local HTTP server that serves the given `file` with support for range requests and returns its URL
+    fn serve_file(file: Vec<u8>) -> Result<String> {
+        let listener = std::net::TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0)))?;
+        listener.set_nonblocking(true)?;
+        let url = format!("http://{}/file", listener.local_addr()?);
+        let file: &'static [u8] = Box::leak(file.into_boxed_slice());
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_io()
+            .build()?;
+        thread::spawn(move || {
+            runtime.block_on(async move {
+                let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+                loop {
+                    let (stream, _) = listener.accept().await.unwrap();
+                    let service = service_fn(move |request: Request<Incoming>| async move {
+                        Ok::<_, Infallible>(serve_range(file, &request))
+                    });
+                    tokio::spawn(async move {
+                        let _ = hyper::server::conn::http1::Builder::new()
+                            .serve_connection(TokioIo::new(stream), service)
+                            .await;
+                    });
+                }
+            })
+        });
+        Ok(url)
+    }
+
+    #[test]
+    fn test_range_reader_read_and_seek() -> Result<()> {
+        let file = (0..10_000_u32)
+            .flat_map(|value| value.to_le_bytes())
+            .collect::<Vec<_>>();
+        let url = serve_file(file.clone())?;
+
+        let mut reader = RangeReader::builder().block_size(1000).build(&url)?;
+        assert_eq!(file.len() as u64, reader.len());
+        assert_eq!(1, reader.number_of_requests());
+
+        // Reads within the first block are served from the cache
+        let mut buffer = vec![0; 100];
+        reader.read_exact(&mut buffer)?;
+        assert_eq!(&file[..100], buffer.as_slice());
+        assert_eq!(1, reader.number_of_requests());
+
+        // A read that spans several missing blocks fetches them with a single request
+        reader.seek(SeekFrom::Start(2500))?;
+        let mut buffer = vec![0; 3000];
+        reader.read_exact(&mut buffer)?;
+        assert_eq!(&file[2500..5500], buffer.as_slice());
+        assert_eq!(2, reader.number_of_requests());
+
+        reader.seek(SeekFrom::Current(-4000))?;
+        let mut buffer = vec![0; 500];
+        reader.read_exact(&mut buffer)?;
+        assert_eq!(&file[1500..2000], buffer.as_slice());
+        assert_eq!(3, reader.number_of_requests());
+
+        reader.seek(SeekFrom::End(-10))?;
+        let mut buffer = vec![];
+        reader.read_to_end(&mut buffer)?;
+        assert_eq!(&file[file.len() - 10..], buffer.as_slice());
+        assert_eq!(4, reader.number_of_requests());
+
+        reader.rewind()?;
+        let mut buffer = vec![];
+        reader.read_to_end(&mut buffer)?;
+        assert_eq!(file, buffer);
+
+        // All blocks fit into the cache, so reading the file again sends no requests
+        let number_of_requests = reader.number_of_requests();
+        reader.rewind()?;
+        let mut buffer = vec![];
+        reader.read_to_end(&mut buffer)?;
+        assert_eq!(file, buffer);
+        assert_eq!(number_of_requests, reader.number_of_requests());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_range_reader_evicts_least_recently_used_blocks() -> Result<()> {
+        let file = vec![42; 1000];
+        let url = serve_file(file)?;
+
+        let mut reader = RangeReader::builder()
+            .block_size(100)
+            .cache_capacity(2)
+            .build(&url)?;
+        let mut buffer = [0; 1];
+        for (position, expected_requests) in
+            [(0, 1), (150, 2), (10, 2), (250, 3), (10, 3), (150, 4)]
+        {
+            reader.seek(SeekFrom::Start(position))?;
+            reader.read_exact(&mut buffer)?;
+            assert_eq!(expected_requests, reader.number_of_requests());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_range_reader_with_pnts_reader() -> Result<()> {
+        let path = get_test_file_path("points.pnts");
+        let expected_points = PntsReader::from_path(&path)?.read_all::<VectorBuffer>()?;
+        let url = serve_file(std::fs::read(&path)?)?;
+
+        let range_reader = RangeReader::builder().block_size(128).build(&url)?;
+        let points = PntsReader::from_read(range_reader)?.read_all::<VectorBuffer>()?;
+        assert_eq!(expected_points.point_layout(), points.point_layout());
+        assert_eq!(expected_points.len(), points.len());
+        assert_eq!(
+            expected_points.get_point_range_ref(0..expected_points.len()),
+            points.get_point_range_ref(0..points.len())
+        );
+
+        Ok(())
+    }
+
+    #[cfg(feature = "las")]
+    #[test]
+    fn test_range_reader_with_las_reader() -> Result<()> {
+        use crate::las::{compare_to_reference_data, get_test_las_path, LASReader};
+        use las::point::Format;
+
+        for format in [0, 6] {
+            let url = serve_file(std::fs::read(get_test_las_path(format))?)?;
+            let range_reader = RangeReader::builder().block_size(256).build(&url)?;
+            let points =
+                LASReader::from_read(range_reader, false, false)?.read::<VectorBuffer>(10)?;
+            compare_to_reference_data(&points, Format::new(format)?);
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "laz")]
+    #[test]
+    fn test_range_reader_with_laz_reader() -> Result<()> {
+        use crate::las::{compare_to_reference_data, get_test_laz_path, LASReader};
+        use las::point::Format;
+
+        let url = serve_file(std::fs::read(get_test_laz_path(1))?)?;
+        let range_reader = RangeReader::builder().block_size(256).build(&url)?;
+        let points = LASReader::from_read(range_reader, true, false)?.read::<VectorBuffer>(10)?;
+        compare_to_reference_data(&points, Format::new(1)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_range_reader_requires_range_support() -> Result<()> {
+        let listener = std::net::TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0)))?;
+        let url = format!("http://{}/file", listener.local_addr()?);
+        thread::spawn(move || {
+            use std::io::Write;
+            if let Ok((mut stream, _)) = listener.accept() {
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request);
+                let _ = stream.write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\nConnection: close\r\n\r\npnts",
+                );
+            }
+        });
+
+        let error = RangeReader::new(url)
+            .err()
+            .expect("Servers without range requests should be rejected")
+            .to_string();
+        assert!(error.contains("does not support range requests"));
+
+        Ok(())
+    }
+}
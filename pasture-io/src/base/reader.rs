@@ -1,8 +1,16 @@
 use anyhow::Result;
-use pasture_core::containers::{BorrowedMutBuffer, MakeBufferFromLayout, OwningBuffer};
-use pasture_core::layout::PointLayout;
+use pasture_core::containers::{
+    BorrowedMutBuffer, ExternalMemoryBuffer, MakeBufferFromLayout, OwningBuffer, VectorBuffer,
+};
+use pasture_core::layout::{PointAttributeDefinition, PointLayout, PointType, PrimitiveType};
 use pasture_core::meta::Metadata;
 
+use super::PointIterator;
+
+/// Callback for reporting the progress of long-running reads. It is invoked with the number of points that have been
+/// read so far and the total number of points
+pub type ProgressCallback = Box<dyn FnMut(usize, usize) + Send>;
+
 /// Base trait for all types that support reading point data
 pub trait PointReader {
     /// Read `count` points from this `PointReader` into the given `point_buffer`. Uses the `PointLayout`
@@ -31,6 +39,106 @@ pub trait PointReader {
         Ok(buffer)
     }
 
+    /// Reads all remaining points from this `PointReader` into a new buffer of type `B`. The `PointLayout` of this
+    /// new buffer will be equal to `self.get_default_point_layout`. If the number of remaining points is known from
+    /// [`Self::point_count`] and [`Self::point_index`], the buffer is allocated once and filled by a single call to
+    /// `read_into`, otherwise points are read in chunks until the reader runs out of points
+    fn read_all<'a, B: OwningBuffer<'a> + MakeBufferFromLayout<'a> + 'a>(&mut self) -> Result<B> {
+        if let (Some(point_count), Some(point_index)) = (self.point_count(), self.point_index()) {
+            // Only the remaining points are allocated, which is less than the whole file after points were read,
+            // skipped or seeked over
+            return self.read(point_count.saturating_sub(point_index));
+        }
+
+        const POINTS_PER_CHUNK: usize = 1 << 16;
+        let mut points = B::new_from_layout(self.get_default_point_layout().clone());
+        loop {
+            let chunk: B = self.read(POINTS_PER_CHUNK)?;
+            if chunk.is_empty() {
+                break;
+            }
+            points.append(&chunk);
+        }
+        Ok(points)
+    }
+
+    /// Returns an iterator over all remaining points of this `PointReader` as values of the `PointType` `T`. Points
+    /// are converted from the default `PointLayout` of this reader into the `PointLayout` of `T` while reading, if
+    /// this conversion is not possible, the iterator yields an error
+    fn points<T: PointType>(&mut self) -> PointIterator<'_, Self, T>
+    where
+        Self: Sized,
+    {
+        PointIterator::new(self)
+    }
+
+    /// Reads at most `count` values of the given `attribute` from this `PointReader`, skipping all other attributes.
+    /// The values are read directly into the returned `Vec`, converting them into the datatype of `T` if it differs
+    /// from the datatype of the attribute in the file
+    fn read_attribute<T: PrimitiveType>(
+        &mut self,
+        attribute: &PointAttributeDefinition,
+        count: usize,
+    ) -> Result<Vec<T>> {
+        let attribute_with_type = attribute.with_custom_datatype(T::data_type());
+        let layout = PointLayout::from_attributes_packed(&[attribute_with_type], 1);
+        let mut values = vec![T::zeroed(); count];
+        let actual_count = {
+            let mut buffer =
+                ExternalMemoryBuffer::new(bytemuck::cast_slice_mut::<T, u8>(&mut values), layout);
+            self.read_into(&mut buffer, count)?
+        };
+        values.truncate(actual_count);
+        Ok(values)
+    }
+
+    /// Reads at most `points.len()` points from this `PointReader` directly into the memory of `points`, converting
+    /// them into the `PointLayout` of `T` if it differs from the default `PointLayout` of this reader. No intermediate
+    /// buffer is allocated, which makes this suitable for streaming points into a reused slice. Returns the number of
+    /// points that were read. Only this many elements at the start of `points` are overwritten, all remaining elements
+    /// are left untouched
+    fn read_into_slice<T: PointType>(&mut self, points: &mut [T]) -> Result<usize> {
+        let count = points.len();
+        let mut buffer =
+            ExternalMemoryBuffer::new(bytemuck::cast_slice_mut::<T, u8>(points), T::layout());
+        self.read_into(&mut buffer, count)
+    }
+
+    /// Skips the next `count` points of this `PointReader` and returns the number of points that were actually skipped,
+    /// which is smaller than `count` if the reader runs out of points. The default implementation reads the points in
+    /// chunks and discards them. Readers that implement [`SeekToPoint`](crate::base::SeekToPoint) override this with a
+    /// single seek (see [`skip_points_by_seeking`](crate::base::skip_points_by_seeking)), in which case the points
+    /// are skipped in the underlying source, regardless of any filters of the reader
+    fn skip(&mut self, count: usize) -> Result<usize> {
+        const POINTS_PER_CHUNK: usize = 1 << 16;
+        let mut chunk = VectorBuffer::new_from_layout(self.get_default_point_layout().clone());
+        chunk.resize(count.min(POINTS_PER_CHUNK));
+        let mut skipped_points = 0;
+        while skipped_points < count {
+            let points_in_chunk = (count - skipped_points).min(POINTS_PER_CHUNK);
+            let points_read = self.read_into(&mut chunk, points_in_chunk)?;
+            if points_read == 0 {
+                break;
+            }
+            skipped_points += points_read;
+        }
+        Ok(skipped_points)
+    }
+
+    /// Returns the index of the next point that this `PointReader` will read, i.e. the number of points that were read
+    /// or skipped so far, unless the reader was moved through [`SeekToPoint`](crate::base::SeekToPoint). Returns
+    /// `None` if the reader does not keep track of its position, which is what the default implementation does
+    fn point_index(&self) -> Option<usize> {
+        None
+    }
+
+    /// Returns the total number of points of this `PointReader`, independent of its current position. Returns `None` if
+    /// the number of points is unknown (e.g. for ASCII files which don't have header information). The default
+    /// implementation takes the number of points from the `Metadata` of this reader
+    fn point_count(&self) -> Option<usize> {
+        self.get_metadata().number_of_points()
+    }
+
     /// Returns the `Metadata` of the associated `PointReader`
     fn get_metadata(&self) -> &dyn Metadata;
     /// Returns the default `PointLayout` of the associated `PointReader`
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use std::convert::TryFrom;
 use std::io::SeekFrom;
 
 /// Base trait for all readers and writers that support seeking to a specific point in their
@@ -23,3 +24,18 @@ pub trait SeekToPoint {
         Ok(len)
     }
 }
+
+/// Skips the next `count` points of `reader` with a single relative seek. This is the implementation of
+/// [`PointReader::skip`](crate::base::PointReader::skip) for all readers that support seeking. Returns the number of
+/// points that were actually skipped, which is smaller than `count` if `reader` reaches the end of its points
+pub fn skip_points_by_seeking<R: SeekToPoint + ?Sized>(
+    reader: &mut R,
+    count: usize,
+) -> Result<usize> {
+    let previous_point_index = reader.point_index()?;
+    // Seeking past the last point moves the reader to the end, so only overflows have to be prevented here
+    let max_count = i64::MAX - i64::try_from(previous_point_index)?;
+    let count = i64::try_from(count).unwrap_or(i64::MAX).min(max_count);
+    let new_point_index = reader.seek_point(SeekFrom::Current(count))?;
+    Ok(new_point_index - previous_point_index)
+}
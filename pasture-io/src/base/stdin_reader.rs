@@ -0,0 +1,382 @@
+#![allow(clippy::large_enum_variant)]
+
+use std::io::{BufRead, StdinLock};
+
+use anyhow::{bail, Context, Result};
+use pasture_core::{containers::BorrowedMutBuffer, layout::PointLayout, meta::Metadata};
+
+#[cfg(feature = "ascii")]
+use crate::ascii::AsciiReader;
+#[cfg(feature = "las")]
+use crate::las::StreamingLASReader;
+#[cfg(feature = "pcd")]
+use crate::pcd::PcdReader;
+
+use super::{FileCompression, PointReader};
+
+/// Offset of the point data format in the LAS header
+#[cfg(feature = "las")]
+const LAS_POINT_FORMAT_OFFSET: usize = 104;
+/// LAZ files set one of the two upper bits of the point data format in the LAS header
+#[cfg(feature = "las")]
+const LAZ_POINT_FORMAT_MASK: u8 = 0b1100_0000;
+
+/// Point cloud formats that can be read from a stream that does not support seeking, such as `stdin`. All other
+/// formats require seeking: The point records of LAZ files can only be decompressed with the chunk table at the end of
+/// the file, and the readers for 3D Tiles and Parquet files jump between the different sections of the file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StdinFormat {
+    /// Uncompressed LAS files, which are read with a [`StreamingLASReader`]
+    #[cfg(feature = "las")]
+    Las,
+    /// ASCII files, see [`AsciiReader::from_read`]
+    #[cfg(feature = "ascii")]
+    Ascii {
+        /// Format string that describes the columns of the ASCII data
+        format: String,
+        /// Delimiter between the columns of the ASCII data
+        delimiter: String,
+    },
+    /// PCD files of the Point Cloud Library
+    #[cfg(feature = "pcd")]
+    Pcd,
+}
+
+/// Determines the format of the data in `read` from the magic bytes at its start, without consuming them
+fn format_from_stream<R: BufRead>(read: &mut R) -> Result<StdinFormat> {
+    let magic_bytes = read
+        .fill_buf()
+        .context("Could not read the start of the stream")?;
+    if magic_bytes.is_empty() {
+        bail!("Can't determine the format of the points, as the stream is empty");
+    }
+    if magic_bytes.starts_with(b"LASF") {
+        #[cfg(feature = "las")]
+        return Ok(StdinFormat::Las);
+        #[cfg(not(feature = "las"))]
+        bail!("Reading LAS files requires the `las` feature of pasture-io");
+    }
+    // The header of PCD files usually starts with a comment line, but the comment is optional
+    if magic_bytes.starts_with(b"# .PCD") || magic_bytes.starts_with(b"VERSION") {
+        #[cfg(feature = "pcd")]
+        return Ok(StdinFormat::Pcd);
+        #[cfg(not(feature = "pcd"))]
+        bail!("Reading PCD files requires the `pcd` feature of pasture-io");
+    }
+    if magic_bytes.starts_with(b"pnts") {
+        bail!("3D Tiles point clouds can't be read from a stream, as their reader requires seeking. Write the data to a file first and open it with `open_reader`");
+    }
+    if magic_bytes.starts_with(b"PAR1") {
+        bail!("Parquet files can't be read from a stream, as their metadata is stored at the end of the file. Write the data to a file first and open it with `open_reader`");
+    }
+    if let Some(compression) = FileCompression::from_magic_bytes(magic_bytes) {
+        bail!(
+            "The stream is {} compressed. Decompress it before it is passed to pasture-io, e.g. with `gzip -dc` or `zstd -dc`, or read compressed ASCII data through a `DecompressingRead` (`compression` feature)",
+            compression
+        );
+    }
+    if magic_bytes.starts_with(b"ply") {
+        bail!("PLY files are not supported by pasture-io");
+    }
+    bail!("Could not determine the format of the points in the stream. ASCII data can only be read with a format string that describes its columns, pass `StdinFormat::Ascii` as format hint instead (`ascii` feature)")
+}
+
+/// Returns an error if the LAS header at the start of `read` belongs to a LAZ file
+#[cfg(feature = "las")]
+fn ensure_uncompressed_las<R: BufRead>(read: &mut R) -> Result<()> {
+    let header = read
+        .fill_buf()
+        .context("Could not read the LAS header from the stream")?;
+    if matches!(header.get(LAS_POINT_FORMAT_OFFSET), Some(point_format) if point_format & LAZ_POINT_FORMAT_MASK != 0)
+    {
+        bail!("LAZ files can't be read from a stream, as decompressing them requires seeking. Write the data to a file first and open it with `open_reader`, or decompress it before it is passed to pasture-io, e.g. with `las2las`");
+    }
+    Ok(())
+}
+
+/// Reader for all point cloud formats that can be read from a stream that does not support seeking, with the format
+/// chosen at runtime. Use [`open_reader_stdin`] to read points from `stdin`, or [`GenericStreamingReader::from_read`]
+/// for any other `BufRead`
+pub enum GenericStreamingReader<R: BufRead> {
+    #[cfg(feature = "las")]
+    Las(StreamingLASReader<R>),
+    #[cfg(feature = "ascii")]
+    Ascii(AsciiReader<R>),
+    #[cfg(feature = "pcd")]
+    Pcd(PcdReader<R>),
+}
+
+impl<R: BufRead> GenericStreamingReader<R> {
+    /// Creates the reader for the points in `read`. The format is given by `format_hint`, or determined from the magic
+    /// bytes at the start of `read` (`LASF`, `# .PCD` or `VERSION`) if there is no hint. ASCII data can't be detected
+    /// and always requires a [`StdinFormat::Ascii`] hint
+    ///
+    /// # Errors
+    ///
+    /// If the format of the data can't be determined, or if it is a format that requires seeking (LAZ, 3D Tiles,
+    /// Parquet, compressed data). The errors for these formats explain how the data can be read instead. Also returns
+    /// an error if the reader for the format can't be created
+    pub fn from_read(mut read: R, format_hint: Option<StdinFormat>) -> Result<Self> {
+        let format = match format_hint {
+            Some(format) => format,
+            None => format_from_stream(&mut read)?,
+        };
+        match format {
+            #[cfg(feature = "las")]
+            StdinFormat::Las => {
+                ensure_uncompressed_las(&mut read)?;
+                let reader = StreamingLASReader::from_read(read, false)
+                    .context("Could not read the points from the stream as LAS file")?;
+                Ok(Self::Las(reader))
+            }
+            #[cfg(feature = "ascii")]
+            StdinFormat::Ascii { format, delimiter } => {
+                let reader = AsciiReader::from_read(read, &format, &delimiter)?;
+                Ok(Self::Ascii(reader))
+            }
+            #[cfg(feature = "pcd")]
+            StdinFormat::Pcd => {
+                let reader = PcdReader::from_read(read)
+                    .context("Could not read the points from the stream as PCD file")?;
+                Ok(Self::Pcd(reader))
+            }
+        }
+    }
+}
+
+/// Reads points from `stdin`, e.g. in a pipeline like `cat points.las | my_tool`. The format is given by
+/// `format_hint`, or determined from the start of the data if there is no hint, see
+/// [`GenericStreamingReader::from_read`]. Only formats that can be read without seeking are supported, see
+/// [`StdinFormat`]. `stdin` is locked for as long as the returned reader exists
+///
+/// # Example
+///
+/// ```no_run
+/// # use anyhow::Result;
+/// use pasture_core::containers::VectorBuffer;
+/// use pasture_io::{base::PointReader, open_reader_stdin};
+///
+/// # fn main() -> Result<()> {
+/// let points = open_reader_stdin(None)?.read_all::<VectorBuffer>()?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn open_reader_stdin(
+    format_hint: Option<StdinFormat>,
+) -> Result<GenericStreamingReader<StdinLock<'static>>> {
+    GenericStreamingReader::from_read(std::io::stdin().lock(), format_hint)
+}
+
+impl<R: BufRead> PointReader for GenericStreamingReader<R> {
+    fn read_into<'a, 'b, B: BorrowedMutBuffer<'a>>(
+        &mut self,
+        point_buffer: &'b mut B,
+        count: usize,
+    ) -> Result<usize>
+    where
+        'a: 'b,
+    {
+        match self {
+            #[cfg(feature = "las")]
+            GenericStreamingReader::Las(reader) => reader.read_into(point_buffer, count),
+            #[cfg(feature = "ascii")]
+            GenericStreamingReader::Ascii(reader) => reader.read_into(point_buffer, count),
+            #[cfg(feature = "pcd")]
+            GenericStreamingReader::Pcd(reader) => reader.read_into(point_buffer, count),
+        }
+    }
+
+    fn get_metadata(&self) -> &dyn Metadata {
+        match self {
+            #[cfg(feature = "las")]
+            GenericStreamingReader::Las(reader) => reader.get_metadata(),
+            #[cfg(feature = "ascii")]
+            GenericStreamingReader::Ascii(reader) => reader.get_metadata(),
+            #[cfg(feature = "pcd")]
+            GenericStreamingReader::Pcd(reader) => reader.get_metadata(),
+        }
+    }
+
+    fn get_default_point_layout(&self) -> &PointLayout {
+        match self {
+            #[cfg(feature = "las")]
+            GenericStreamingReader::Las(reader) => reader.get_default_point_layout(),
+            #[cfg(feature = "ascii")]
+            GenericStreamingReader::Ascii(reader) => reader.get_default_point_layout(),
+            #[cfg(feature = "pcd")]
+            GenericStreamingReader::Pcd(reader) => reader.get_default_point_layout(),
+        }
+    }
+
+    fn skip(&mut self, count: usize) -> Result<usize> {
+        match self {
+            #[cfg(feature = "las")]
+            GenericStreamingReader::Las(reader) => reader.skip(count),
+            #[cfg(feature = "ascii")]
+            GenericStreamingReader::Ascii(reader) => reader.skip(count),
+            #[cfg(feature = "pcd")]
+            GenericStreamingReader::Pcd(reader) => reader.skip(count),
+        }
+    }
+
+    fn point_index(&self) -> Option<usize> {
+        match self {
+            #[cfg(feature = "las")]
+            GenericStreamingReader::Las(reader) => reader.point_index(),
+            #[cfg(feature = "ascii")]
+            GenericStreamingReader::Ascii(reader) => reader.point_index(),
+            #[cfg(feature = "pcd")]
+            GenericStreamingReader::Pcd(reader) => reader.point_index(),
+        }
+    }
+
+    fn point_count(&self) -> Option<usize> {
+        match self {
+            #[cfg(feature = "las")]
+            GenericStreamingReader::Las(reader) => reader.point_count(),
+            #[cfg(feature = "ascii")]
+            GenericStreamingReader::Ascii(reader) => reader.point_count(),
+            #[cfg(feature = "pcd")]
+            GenericStreamingReader::Pcd(reader) => reader.point_count(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use pasture_core::containers::{BorrowedBuffer, VectorBuffer};
+    #[cfg(any(feature = "ascii", feature = "pcd"))]
+    use pasture_core::{layout::attributes::POSITION_3D, nalgebra::Vector3};
+
+    use super::*;
+
+    /// Returns the error message of opening a `GenericStreamingReader` for `data` without a format hint
+    fn open_error(data: &[u8]) -> String {
+        GenericStreamingReader::from_read(Cursor::new(data), None)
+            .err()
+            .expect("Opening the stream should fail")
+            .to_string()
+    }
+
+    #[test]
+    fn test_streaming_reader_rejects_formats_that_require_seeking() -> Result<()> {
+        let pnts_data = std::fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/resources/test/points.pnts"
+        ))?;
+        assert!(open_error(&pnts_data).contains("open_reader"));
+        assert!(open_error(b"PAR1\0\0\0\0").contains("open_reader"));
+        assert!(open_error(&[0x1f, 0x8b, 0x08, 0x00]).contains("gzip compressed"));
+        assert!(open_error(b"ply\nformat ascii 1.0\n").contains("PLY"));
+        assert!(open_error(b"").contains("empty"));
+        assert!(open_error(b"0.0, 1.0, 2.0\n").contains("StdinFormat::Ascii"));
+
+        #[cfg(feature = "las")]
+        {
+            let mut laz_header = vec![0; 227];
+            laz_header[..4].copy_from_slice(b"LASF");
+            laz_header[LAS_POINT_FORMAT_OFFSET] = 0x83;
+            assert!(open_error(&laz_header).contains("LAZ files can't be read from a stream"));
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "ascii")]
+    #[test]
+    fn test_streaming_reader_ascii() -> Result<()> {
+        use crate::ascii::{get_test_file_path, test_data_positions};
+
+        let data = std::fs::read(get_test_file_path("10_points_ascii.txt"))?;
+        let mut reader = GenericStreamingReader::from_read(
+            Cursor::new(data),
+            Some(StdinFormat::Ascii {
+                format: "xyzieRGB".to_string(),
+                delimiter: ", ".to_string(),
+            }),
+        )?;
+        let points = reader.read_all::<VectorBuffer>()?;
+        assert_eq!(
+            test_data_positions(),
+            points
+                .view_attribute::<Vector3<f64>>(&POSITION_3D)
+                .into_iter()
+                .collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+
+    #[cfg(feature = "pcd")]
+    #[test]
+    fn test_streaming_reader_detects_pcd() -> Result<()> {
+        use pasture_derive::PointType;
+
+        use crate::{
+            base::PointWriter,
+            pcd::{PcdDataFormat, PcdWriter},
+        };
+
+        #[derive(PointType, Copy, Clone, bytemuck::AnyBitPattern, bytemuck::NoUninit)]
+        #[repr(C, packed)]
+        struct TestPoint {
+            #[pasture(BUILTIN_POSITION_3D)]
+            position: Vector3<f64>,
+        }
+
+        let positions = (0..10)
+            .map(|index| Vector3::new(index as f64, -(index as f64), 0.5))
+            .collect::<Vec<_>>();
+        let points = positions
+            .iter()
+            .map(|position| TestPoint {
+                position: *position,
+            })
+            .collect::<VectorBuffer>();
+        let mut pcd_data = Cursor::new(Vec::new());
+        {
+            let mut writer = PcdWriter::from_write_and_layout(
+                &mut pcd_data,
+                points.point_layout().clone(),
+                PcdDataFormat::Binary,
+            )?;
+            writer.write(&points)?;
+            writer.flush()?;
+        }
+
+        let mut reader =
+            GenericStreamingReader::from_read(Cursor::new(pcd_data.into_inner()), None)?;
+        assert!(matches!(reader, GenericStreamingReader::Pcd(_)));
+        let read_points = reader.read_all::<VectorBuffer>()?;
+        assert_eq!(
+            positions,
+            read_points
+                .view_attribute::<Vector3<f64>>(&POSITION_3D)
+                .into_iter()
+                .collect::<Vec<_>>()
+        );
+
+        Ok(())
+    }
+
+    #[cfg(feature = "las")]
+    #[test]
+    fn test_streaming_reader_detects_las() -> Result<()> {
+        use crate::las::{get_test_las_path, LASReader};
+
+        let expected_points =
+            LASReader::from_path(get_test_las_path(1), false)?.read_all::<VectorBuffer>()?;
+        let mut reader = GenericStreamingReader::from_read(
+            Cursor::new(std::fs::read(get_test_las_path(1))?),
+            None,
+        )?;
+        assert!(matches!(reader, GenericStreamingReader::Las(_)));
+        assert_eq!(Some(10), reader.point_count());
+        let points = reader.read_all::<VectorBuffer>()?;
+        assert_eq!(expected_points, points);
+
+        Ok(())
+    }
+}
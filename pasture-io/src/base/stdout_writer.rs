@@ -0,0 +1,320 @@
+#![allow(clippy::large_enum_variant)]
+
+use std::io::{BufWriter, Stdout, Write};
+
+#[cfg(feature = "ascii")]
+use anyhow::bail;
+#[cfg(any(feature = "las", feature = "parquet", feature = "gltf"))]
+use anyhow::Context;
+use anyhow::{anyhow, Result};
+use pasture_core::{containers::BorrowedBuffer, layout::PointLayout};
+
+#[cfg(feature = "ascii")]
+use crate::ascii::{format_from_point_layout, AsciiWriter};
+#[cfg(feature = "gltf")]
+use crate::gltf::{GltfPointsWriter, GltfPointsWriterBuilder};
+#[cfg(feature = "las")]
+use crate::las::{
+    point_format_from_layout, LASStreamStatistics, LASWriterBuilder, StreamingLASWriter,
+};
+#[cfg(feature = "parquet")]
+use crate::parquet::{ParquetWriter, ParquetWriterBuilder};
+
+use super::{GenericPointWriterOptions, PointWriter};
+
+/// Point cloud formats that can be written to a stream that does not support seeking, such as `stdout`. The writers
+/// for LAZ, 3D Tiles and PCD files require seeking, as they update the header (or the chunk table of LAZ files) after
+/// all points have been written, so these formats have to be written to a file with [`create_writer`](super::create_writer)
+#[derive(Clone, Debug, PartialEq)]
+pub enum StdoutFormat {
+    /// Uncompressed LAS files. As the header is written before the points, the number of points and their bounds
+    /// have to be known in advance, see [`StreamingLASWriter`]
+    #[cfg(feature = "las")]
+    Las(LASStreamStatistics),
+    /// ASCII files
+    #[cfg(feature = "ascii")]
+    Ascii,
+    /// Apache Parquet files
+    #[cfg(feature = "parquet")]
+    Parquet,
+    /// Binary glTF files
+    #[cfg(feature = "gltf")]
+    Gltf,
+}
+
+/// Returns the error for `options` that don't belong to the format `format_name`
+fn mismatched_options_error(
+    format_name: &str,
+    options: &GenericPointWriterOptions,
+) -> anyhow::Error {
+    anyhow!(
+        "The writer options {:?} can't be used for writing {} data, as they belong to a different format",
+        options,
+        format_name
+    )
+}
+
+/// Writer for all point cloud formats that can be written to a stream that does not support seeking, with the format
+/// chosen at runtime. Use [`create_writer_stdout`] to write points to `stdout`, or
+/// [`GenericStreamingWriter::from_write`] for any other `Write`
+pub enum GenericStreamingWriter<W: Write + Send> {
+    #[cfg(feature = "las")]
+    Las(StreamingLASWriter<W>),
+    #[cfg(feature = "ascii")]
+    Ascii(AsciiWriter<W>),
+    #[cfg(feature = "parquet")]
+    Parquet(ParquetWriter<W>),
+    #[cfg(feature = "gltf")]
+    Gltf(GltfPointsWriter<W>),
+}
+
+impl<W: Write + Send> GenericStreamingWriter<W> {
+    /// Creates the writer for the given `format` that writes points with the given `point_layout` into `write`, using
+    /// the format-specific settings in `options`. [`GenericPointWriterOptions::Default`] uses the same defaults as
+    /// [`create_writer`](super::create_writer)
+    ///
+    /// # Errors
+    ///
+    /// If `options` are meant for a different format, if they request a compressed LAZ file, or if the format can't
+    /// represent points in the given `point_layout` (e.g. LAS files require a `POSITION_3D` attribute)
+    pub fn from_write(
+        write: W,
+        format: StdoutFormat,
+        point_layout: &PointLayout,
+        options: GenericPointWriterOptions,
+    ) -> Result<Self> {
+        match format {
+            #[cfg(feature = "las")]
+            StdoutFormat::Las(statistics) => {
+                let builder = match options {
+                    GenericPointWriterOptions::Default => LASWriterBuilder::new(
+                        point_format_from_layout(point_layout)
+                            .context("Can't write the points as LAS file")?,
+                    ),
+                    GenericPointWriterOptions::Las(builder) => builder,
+                    other => return Err(mismatched_options_error("LAS", &other)),
+                };
+                let writer = builder.build_streaming(write, statistics)?;
+                Ok(Self::Las(writer))
+            }
+            #[cfg(feature = "ascii")]
+            StdoutFormat::Ascii => {
+                let format = match options {
+                    GenericPointWriterOptions::Default => format_from_point_layout(point_layout),
+                    GenericPointWriterOptions::Ascii(format) => format,
+                    other => return Err(mismatched_options_error("ASCII", &other)),
+                };
+                if format.is_empty() {
+                    bail!(
+                        "Can't write the points as ASCII data, as the point layout {} has no attribute that ASCII files can store",
+                        point_layout
+                    );
+                }
+                let writer = AsciiWriter::from_write(write, &format)?;
+                Ok(Self::Ascii(writer))
+            }
+            #[cfg(feature = "parquet")]
+            StdoutFormat::Parquet => {
+                let builder = match options {
+                    GenericPointWriterOptions::Default => ParquetWriterBuilder::new(),
+                    GenericPointWriterOptions::Parquet(builder) => builder,
+                    other => return Err(mismatched_options_error("Parquet", &other)),
+                };
+                let writer = builder
+                    .build(write, point_layout.clone())
+                    .context("Can't write the points as Parquet file")?;
+                Ok(Self::Parquet(writer))
+            }
+            #[cfg(feature = "gltf")]
+            StdoutFormat::Gltf => {
+                let builder = match options {
+                    GenericPointWriterOptions::Default => GltfPointsWriterBuilder::new(),
+                    GenericPointWriterOptions::Gltf(builder) => builder,
+                    other => return Err(mismatched_options_error("glTF", &other)),
+                };
+                let writer = builder
+                    .build(write, point_layout.clone())
+                    .context("Can't write the points as glTF file")?;
+                Ok(Self::Gltf(writer))
+            }
+        }
+    }
+
+    /// Finishes the output after all points have been written. This writes the footer of Parquet files and flushes
+    /// all buffered data into the underlying `Write`
+    pub fn finish(self) -> Result<()> {
+        match self {
+            #[cfg(feature = "las")]
+            GenericStreamingWriter::Las(mut writer) => writer.flush(),
+            #[cfg(feature = "ascii")]
+            GenericStreamingWriter::Ascii(mut writer) => writer.flush(),
+            #[cfg(feature = "parquet")]
+            GenericStreamingWriter::Parquet(writer) => writer.finalize(),
+            #[cfg(feature = "gltf")]
+            GenericStreamingWriter::Gltf(mut writer) => writer.flush(),
+        }
+    }
+}
+
+/// Writes points to `stdout` in the given `format`, e.g. in a pipeline like `my_tool > points.xyz`. Only formats that
+/// can be written without seeking are supported, see [`StdoutFormat`]. Call [`GenericStreamingWriter::finish`] after
+/// all points have been written
+///
+/// # Example
+///
+/// ```no_run
+/// # use anyhow::Result;
+/// use pasture_core::containers::{BorrowedBuffer, VectorBuffer};
+/// use pasture_io::{base::{GenericPointWriterOptions, PointWriter, StdoutFormat}, create_writer_stdout};
+///
+/// # fn main() -> Result<()> {
+/// # let points: VectorBuffer = unimplemented!();
+/// # #[cfg(feature = "ascii")]
+/// # {
+/// let mut writer = create_writer_stdout(
+///     StdoutFormat::Ascii,
+///     points.point_layout(),
+///     GenericPointWriterOptions::Ascii("xyz".to_string()),
+/// )?;
+/// writer.write(&points)?;
+/// writer.finish()?;
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+pub fn create_writer_stdout(
+    format: StdoutFormat,
+    point_layout: &PointLayout,
+    options: GenericPointWriterOptions,
+) -> Result<GenericStreamingWriter<BufWriter<Stdout>>> {
+    GenericStreamingWriter::from_write(
+        BufWriter::new(std::io::stdout()),
+        format,
+        point_layout,
+        options,
+    )
+}
+
+impl<W: Write + Send> PointWriter for GenericStreamingWriter<W> {
+    fn write<'a, B: BorrowedBuffer<'a>>(&mut self, points: &'a B) -> Result<()> {
+        match self {
+            #[cfg(feature = "las")]
+            GenericStreamingWriter::Las(writer) => writer.write(points),
+            #[cfg(feature = "ascii")]
+            GenericStreamingWriter::Ascii(writer) => writer.write(points),
+            #[cfg(feature = "parquet")]
+            GenericStreamingWriter::Parquet(writer) => writer.write(points),
+            #[cfg(feature = "gltf")]
+            GenericStreamingWriter::Gltf(writer) => writer.write(points),
+        }
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        match self {
+            #[cfg(feature = "las")]
+            GenericStreamingWriter::Las(writer) => writer.flush(),
+            #[cfg(feature = "ascii")]
+            GenericStreamingWriter::Ascii(writer) => writer.flush(),
+            #[cfg(feature = "parquet")]
+            GenericStreamingWriter::Parquet(writer) => writer.flush(),
+            #[cfg(feature = "gltf")]
+            GenericStreamingWriter::Gltf(writer) => writer.flush(),
+        }
+    }
+
+    fn get_default_point_layout(&self) -> &PointLayout {
+        match self {
+            #[cfg(feature = "las")]
+            GenericStreamingWriter::Las(writer) => writer.get_default_point_layout(),
+            #[cfg(feature = "ascii")]
+            GenericStreamingWriter::Ascii(writer) => writer.get_default_point_layout(),
+            #[cfg(feature = "parquet")]
+            GenericStreamingWriter::Parquet(writer) => writer.get_default_point_layout(),
+            #[cfg(feature = "gltf")]
+            GenericStreamingWriter::Gltf(writer) => writer.get_default_point_layout(),
+        }
+    }
+}
+
+#[cfg(all(test, any(feature = "ascii", feature = "las")))]
+mod tests {
+    use super::*;
+    use crate::base::test_points;
+
+    #[cfg(feature = "ascii")]
+    #[test]
+    fn test_streaming_writer_ascii() -> Result<()> {
+        let points = test_points();
+        let mut output = Vec::new();
+        let mut writer = GenericStreamingWriter::from_write(
+            &mut output,
+            StdoutFormat::Ascii,
+            points.point_layout(),
+            GenericPointWriterOptions::Ascii("xyzi".to_string()),
+        )?;
+        writer.write(&points)?;
+        writer.finish()?;
+
+        let lines = String::from_utf8(output)?
+            .lines()
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(10, lines.len());
+        assert_eq!("1.0, 2.0, -1.0, 100", lines[1]);
+
+        assert!(GenericStreamingWriter::from_write(
+            Vec::new(),
+            StdoutFormat::Ascii,
+            points.point_layout(),
+            GenericPointWriterOptions::Pnts(Default::default()),
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "las")]
+    #[test]
+    fn test_streaming_writer_las() -> Result<()> {
+        use std::io::Cursor;
+
+        use pasture_core::{
+            containers::VectorBuffer,
+            layout::attributes::POSITION_3D,
+            math::AABB,
+            nalgebra::{Point3, Vector3},
+        };
+
+        use crate::{base::PointReader, las::LASReader};
+
+        let points = test_points();
+        let statistics = LASStreamStatistics::new(
+            10,
+            AABB::from_min_max_unchecked(Point3::new(0.0, 0.0, -9.0), Point3::new(9.0, 18.0, 0.0)),
+        );
+        let mut output = Vec::new();
+        let mut writer = GenericStreamingWriter::from_write(
+            &mut output,
+            StdoutFormat::Las(statistics),
+            points.point_layout(),
+            GenericPointWriterOptions::Default,
+        )?;
+        writer.write(&points)?;
+        writer.finish()?;
+
+        let mut reader = LASReader::from_read(Cursor::new(output), false, false)?;
+        assert_eq!(Some(10), reader.point_count());
+        let positions = reader
+            .read_all::<VectorBuffer>()?
+            .view_attribute::<Vector3<f64>>(&POSITION_3D)
+            .into_iter()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            points
+                .view_attribute::<Vector3<f64>>(&POSITION_3D)
+                .into_iter()
+                .collect::<Vec<_>>(),
+            positions
+        );
+        Ok(())
+    }
+}
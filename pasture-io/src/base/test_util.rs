@@ -0,0 +1,22 @@
+use pasture_core::{containers::VectorBuffer, nalgebra::Vector3};
+use pasture_derive::PointType;
+
+/// The `PointType` of the points from [`test_points`]
+#[derive(Debug, PointType, Copy, Clone, PartialEq, bytemuck::AnyBitPattern, bytemuck::NoUninit)]
+#[repr(C, packed)]
+pub(crate) struct TestPoint {
+    #[pasture(BUILTIN_POSITION_3D)]
+    pub(crate) position: Vector3<f64>,
+    #[pasture(BUILTIN_INTENSITY)]
+    pub(crate) intensity: u16,
+}
+
+/// Returns ten points with distinct positions and intensities for testing the format-independent readers and writers
+pub(crate) fn test_points() -> VectorBuffer {
+    (0..10)
+        .map(|index| TestPoint {
+            position: Vector3::new(index as f64, index as f64 * 2.0, -(index as f64)),
+            intensity: index * 100,
+        })
+        .collect()
+}
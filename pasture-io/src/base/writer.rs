@@ -1,8 +1,14 @@
 use anyhow::Result;
-use pasture_core::{containers::BorrowedBuffer, layout::PointLayout};
+use pasture_core::{
+    containers::{BorrowedBuffer, ExternalMemoryBuffer},
+    layout::{PointLayout, PointType},
+};
 
 /// Base trait for all types that support writing point data
 pub trait PointWriter {
+    /// Number of points that [`PointWriter::write_points`] collects before writing them at once
+    const POINTS_PER_WRITE_CHUNK: usize = 4096;
+
     /// Write the points in the given `PointBuffer` to the associated `PointWriter`.
     fn write<'a, B: BorrowedBuffer<'a>>(&mut self, points: &'a B) -> Result<()>;
     /// Flush this `PointWriter`, ensuring that all points are written to their destination and that all required
@@ -11,4 +17,42 @@ pub trait PointWriter {
 
     /// Returns the default `PointLayout` of the associated `PointWriter`
     fn get_default_point_layout(&self) -> &PointLayout;
+
+    /// Writes all points of the iterator `points` as values of the `PointType` `T` and returns the number of points
+    /// that were written. Points are collected in a reusable buffer of [`Self::POINTS_PER_WRITE_CHUNK`] points, which
+    /// is passed to [`Self::write`] with the `PointLayout` of `T` whenever it is full. This allows writing points
+    /// from an iterator pipeline without materializing them in a `PointBuffer` first
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of [`Self::write`]. The iterator is not consumed any further in this case, but all
+    /// chunks that were written before the error are kept by the `PointWriter`, so the output contains a prefix of
+    /// the points
+    fn write_points<T: PointType, I: IntoIterator<Item = T>>(
+        &mut self,
+        points: I,
+    ) -> Result<usize> {
+        let point_layout = T::layout();
+        let mut chunk = Vec::with_capacity(Self::POINTS_PER_WRITE_CHUNK);
+        let mut points_written = 0;
+        for point in points {
+            chunk.push(point);
+            if chunk.len() == Self::POINTS_PER_WRITE_CHUNK {
+                self.write(&ExternalMemoryBuffer::new(
+                    bytemuck::cast_slice::<T, u8>(&chunk),
+                    point_layout.clone(),
+                ))?;
+                points_written += chunk.len();
+                chunk.clear();
+            }
+        }
+        if !chunk.is_empty() {
+            self.write(&ExternalMemoryBuffer::new(
+                bytemuck::cast_slice::<T, u8>(&chunk),
+                point_layout,
+            ))?;
+            points_written += chunk.len();
+        }
+        Ok(points_written)
+    }
 }
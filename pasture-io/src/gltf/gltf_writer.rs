@@ -0,0 +1,922 @@
+use std::{
+    convert::TryInto,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::{bail, Context, Result};
+use pasture_core::{
+    containers::{
+        BorrowedBuffer, InterleavedBuffer, MakeBufferFromLayout, OwningBuffer, VectorBuffer,
+    },
+    layout::{
+        attributes::{COLOR_RGB, POSITION_3D},
+        conversion::{get_converter_for_attributes, AttributeConversionFn},
+        PointAttributeDataType, PointLayout,
+    },
+    math::Alignable,
+    nalgebra::Vector3,
+};
+use serde_json::{json, Map, Value};
+
+use crate::base::PointWriter;
+
+/// Magic number at the start of every GLB file (`glTF` in ASCII)
+const GLB_MAGIC: u32 = 0x46546C67;
+/// Version of the GLB container
+const GLB_VERSION: u32 = 2;
+/// Size of the GLB header in bytes
+const GLB_HEADER_SIZE: usize = 12;
+/// Size of the header of each GLB chunk in bytes
+const GLB_CHUNK_HEADER_SIZE: usize = 8;
+/// Chunk type of the JSON chunk (`JSON` in ASCII)
+const GLB_CHUNK_TYPE_JSON: u32 = 0x4E4F534A;
+/// Chunk type of the binary chunk (`BIN` in ASCII)
+const GLB_CHUNK_TYPE_BIN: u32 = 0x004E4942;
+/// Vertex attributes in glTF must be aligned to 4 bytes, both the start of each bufferView and each element in it
+const GLTF_VERTEX_ALIGNMENT: usize = 4;
+/// Primitive mode for rendering each vertex as a point
+const GLTF_MODE_POINTS: u32 = 0;
+/// bufferView target for vertex attributes
+const GLTF_TARGET_ARRAY_BUFFER: u32 = 34962;
+
+const GLTF_COMPONENT_TYPE_BYTE: u32 = 5120;
+const GLTF_COMPONENT_TYPE_UNSIGNED_BYTE: u32 = 5121;
+const GLTF_COMPONENT_TYPE_SHORT: u32 = 5122;
+const GLTF_COMPONENT_TYPE_UNSIGNED_SHORT: u32 = 5123;
+const GLTF_COMPONENT_TYPE_FLOAT: u32 = 5126;
+
+/// How the values of an attribute are stored in a glTF accessor
+#[derive(Copy, Clone, Debug)]
+struct GltfAccessorFormat {
+    datatype: PointAttributeDataType,
+    component_type: u32,
+    accessor_type: &'static str,
+    normalized: bool,
+}
+
+impl GltfAccessorFormat {
+    const fn new(
+        datatype: PointAttributeDataType,
+        component_type: u32,
+        accessor_type: &'static str,
+    ) -> Self {
+        Self {
+            datatype,
+            component_type,
+            accessor_type,
+            normalized: false,
+        }
+    }
+
+    const fn normalized(mut self) -> Self {
+        self.normalized = true;
+        self
+    }
+}
+
+/// Returns the accessor format for an application-specific attribute with the given `datatype`. glTF has no 32-bit
+/// and 64-bit integers or 64-bit floats for vertex attributes, so these are stored as `f32` values. Returns `None` for
+/// datatypes that glTF can't store (byte arrays and custom types)
+fn gltf_accessor_format(datatype: PointAttributeDataType) -> Option<GltfAccessorFormat> {
+    use PointAttributeDataType::*;
+    match datatype {
+        U8 => Some(GltfAccessorFormat::new(
+            U8,
+            GLTF_COMPONENT_TYPE_UNSIGNED_BYTE,
+            "SCALAR",
+        )),
+        I8 => Some(GltfAccessorFormat::new(
+            I8,
+            GLTF_COMPONENT_TYPE_BYTE,
+            "SCALAR",
+        )),
+        U16 => Some(GltfAccessorFormat::new(
+            U16,
+            GLTF_COMPONENT_TYPE_UNSIGNED_SHORT,
+            "SCALAR",
+        )),
+        I16 => Some(GltfAccessorFormat::new(
+            I16,
+            GLTF_COMPONENT_TYPE_SHORT,
+            "SCALAR",
+        )),
+        U32 | I32 | U64 | I64 | F32 | F64 => Some(GltfAccessorFormat::new(
+            F32,
+            GLTF_COMPONENT_TYPE_FLOAT,
+            "SCALAR",
+        )),
+        Vec3u8 => Some(GltfAccessorFormat::new(
+            Vec3u8,
+            GLTF_COMPONENT_TYPE_UNSIGNED_BYTE,
+            "VEC3",
+        )),
+        Vec3u16 => Some(GltfAccessorFormat::new(
+            Vec3u16,
+            GLTF_COMPONENT_TYPE_UNSIGNED_SHORT,
+            "VEC3",
+        )),
+        Vec3f32 | Vec3i32 | Vec3f64 => Some(GltfAccessorFormat::new(
+            Vec3f32,
+            GLTF_COMPONENT_TYPE_FLOAT,
+            "VEC3",
+        )),
+        Vec4u8 => Some(GltfAccessorFormat::new(
+            Vec4u8,
+            GLTF_COMPONENT_TYPE_UNSIGNED_BYTE,
+            "VEC4",
+        )),
+        ByteArray(_) | Custom { .. } => None,
+    }
+}
+
+/// Returns the accessor format of `COLOR_0` for colors with the given `datatype`, or `None` if glTF has no color format
+/// for the datatype. Integer colors are normalized, so their maximum value is full intensity
+fn gltf_color_format(datatype: PointAttributeDataType) -> Option<GltfAccessorFormat> {
+    use PointAttributeDataType::*;
+    match datatype {
+        Vec3u8 | Vec3u16 | Vec4u8 => {
+            gltf_accessor_format(datatype).map(|format| format.normalized())
+        }
+        Vec3f32 | Vec3f64 => gltf_accessor_format(datatype),
+        _ => None,
+    }
+}
+
+/// Returns the name of the application-specific glTF attribute for the pasture attribute with the given name, which is
+/// the name in upper snake case with a leading underscore (e.g. `_GPS_TIME` for `GpsTime`)
+pub fn gltf_attribute_semantic(attribute_name: &str) -> String {
+    let chars = attribute_name.chars().collect::<Vec<_>>();
+    let mut semantic = String::from("_");
+    for (index, c) in chars.iter().enumerate() {
+        if !c.is_ascii_alphanumeric() {
+            semantic.push('_');
+            continue;
+        }
+        if index > 0 {
+            let previous = chars[index - 1];
+            let next_is_lowercase =
+                matches!(chars.get(index + 1), Some(next) if next.is_ascii_lowercase());
+            // Start a new word at `xY`, `x1` and at the last letter of an acronym (`GPSTime` -> `GPS_TIME`)
+            let starts_word = if c.is_ascii_uppercase() {
+                previous.is_ascii_lowercase()
+                    || (previous.is_ascii_uppercase() && next_is_lowercase)
+            } else {
+                c.is_ascii_digit() && previous.is_ascii_alphabetic()
+            };
+            if starts_word {
+                semantic.push('_');
+            }
+        }
+        semantic.push(c.to_ascii_uppercase());
+    }
+    semantic
+}
+
+/// An attribute of the written points that is stored as a glTF vertex attribute, together with the conversion into the
+/// datatype that it is stored with
+struct GltfOutputAttribute {
+    semantic: String,
+    offset_in_point: usize,
+    source_size: usize,
+    format: GltfAccessorFormat,
+    converter: Option<AttributeConversionFn>,
+}
+
+/// Appends the `values` with the given `element_size` to `bin` as a new bufferView, padding each element to the
+/// vertex alignment of glTF. Returns the index of the new bufferView
+fn append_buffer_view(
+    bin: &mut Vec<u8>,
+    buffer_views: &mut Vec<Value>,
+    values: &[u8],
+    element_size: usize,
+) -> usize {
+    let byte_offset = bin.len();
+    let byte_stride = element_size.align_to(GLTF_VERTEX_ALIGNMENT);
+    if byte_stride == element_size {
+        bin.extend_from_slice(values);
+    } else {
+        bin.reserve((values.len() / element_size) * byte_stride);
+        for value in values.chunks_exact(element_size) {
+            bin.extend_from_slice(value);
+            bin.resize(bin.len() + byte_stride - element_size, 0);
+        }
+    }
+
+    let mut buffer_view = json!({
+        "buffer": 0,
+        "byteOffset": byte_offset,
+        "byteLength": bin.len() - byte_offset,
+        "target": GLTF_TARGET_ARRAY_BUFFER,
+    });
+    if byte_stride != element_size {
+        buffer_view["byteStride"] = json!(byte_stride);
+    }
+    buffer_views.push(buffer_view);
+    buffer_views.len() - 1
+}
+
+/// Defines the translation of the node that contains the points in the glTF files that a [`GltfPointsWriter`] writes
+#[derive(Copy, Clone, Debug, PartialEq, Default)]
+pub enum GltfTranslation {
+    /// Positions are written unchanged and the node has no translation
+    None,
+    /// Subtracts the given translation from all positions and stores it in the node
+    Fixed(Vector3<f64>),
+    /// Subtracts the center of the bounds of all positions from all positions and stores it in the node. This keeps
+    /// the stored positions close to the origin, so that large coordinates don't lose precision when they are stored
+    /// as `f32`
+    #[default]
+    BoundsCenter,
+}
+
+/// Writer for point clouds in the binary glTF 2.0 format (GLB), which web viewers such as three.js can display
+/// directly. The GLB file contains a single mesh with one primitive in `POINTS` mode. Positions are stored as `f32`
+/// values in the `POSITION` attribute, relative to the translation of the node that contains the mesh (see
+/// [`GltfTranslation`]). The `COLOR_RGB` attribute is stored as normalized `COLOR_0`, with 16-bit colors stored as
+/// 8-bit colors if no channel exceeds 255. All other attributes are stored as application-specific attributes with the
+/// name from [`gltf_attribute_semantic`] (e.g. `_INTENSITY`). glTF has no 32-bit or 64-bit integers or 64-bit floats
+/// for vertex attributes, so these are stored as `f32` values. Attributes with datatypes that glTF can't store (byte
+/// arrays and custom types) are ignored.
+///
+/// Positions are written unchanged, so point clouds with a z-up coordinate system appear rotated in the y-up coordinate
+/// system of glTF. The GLB file can only be written once all points are known, so the `GltfPointsWriter` keeps all
+/// written points in memory and writes the file during `flush`. After that, no more points can be written
+pub struct GltfPointsWriter<W: Write> {
+    writer: W,
+    default_layout: PointLayout,
+    cached_points: VectorBuffer,
+    positions: Vec<Vector3<f64>>,
+    output_attributes: Vec<GltfOutputAttribute>,
+    translation: GltfTranslation,
+    requires_flush: bool,
+}
+
+impl<W: Write> GltfPointsWriter<W> {
+    /// Creates a new `GltfPointsWriter` that writes points in the given `point_layout` to `writer`, with the node
+    /// translated to the center of the bounds of the points
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `point_layout` has no `POSITION_3D` attribute, or if two attributes of `point_layout` have
+    /// the same glTF attribute name
+    pub fn from_write_and_layout(writer: W, point_layout: PointLayout) -> Result<Self> {
+        GltfPointsWriterBuilder::new().build(writer, point_layout)
+    }
+
+    fn make_output_attributes(point_layout: &PointLayout) -> Result<Vec<GltfOutputAttribute>> {
+        let mut output_attributes: Vec<GltfOutputAttribute> = vec![];
+        for attribute in point_layout.attributes() {
+            if attribute.name() == POSITION_3D.name() {
+                continue;
+            }
+            let color_format = if attribute.name() == COLOR_RGB.name() {
+                gltf_color_format(attribute.datatype())
+            } else {
+                None
+            };
+            let (semantic, format) = match color_format {
+                Some(format) => ("COLOR_0".to_owned(), format),
+                None => match gltf_accessor_format(attribute.datatype()) {
+                    Some(format) => (gltf_attribute_semantic(attribute.name()), format),
+                    None => continue,
+                },
+            };
+            if output_attributes
+                .iter()
+                .any(|output_attribute| output_attribute.semantic == semantic)
+            {
+                bail!(
+                    "Attribute {} would be written as glTF attribute {}, which another attribute is already written as",
+                    attribute.name(),
+                    semantic
+                );
+            }
+            output_attributes.push(GltfOutputAttribute {
+                semantic,
+                offset_in_point: attribute.offset() as usize,
+                source_size: attribute.size() as usize,
+                format,
+                converter: get_converter_for_attributes(
+                    attribute.attribute_definition(),
+                    &attribute
+                        .attribute_definition()
+                        .with_custom_datatype(format.datatype),
+                ),
+            });
+        }
+        Ok(output_attributes)
+    }
+
+    /// Returns the values of `attribute` for all cached points, converted into the datatype of its accessor
+    fn attribute_values(&self, attribute: &GltfOutputAttribute) -> Vec<u8> {
+        let target_size = attribute.format.datatype.size() as usize;
+        let mut values = Vec::with_capacity(self.cached_points.len() * target_size);
+        let mut converted_value = vec![0; target_size];
+        for point_index in 0..self.cached_points.len() {
+            let point = self.cached_points.get_point_ref(point_index);
+            let source_value = &point
+                [attribute.offset_in_point..attribute.offset_in_point + attribute.source_size];
+            match attribute.converter {
+                Some(converter) => {
+                    unsafe {
+                        converter(source_value, &mut converted_value);
+                    }
+                    values.extend_from_slice(&converted_value);
+                }
+                None => values.extend_from_slice(source_value),
+            }
+        }
+        values
+    }
+
+    /// Builds the glTF JSON and binary buffer from all cached points and writes them as a GLB file
+    fn write_glb(&mut self) -> Result<()> {
+        let (min, max) = self.positions.iter().fold(
+            (
+                Vector3::repeat(f64::INFINITY),
+                Vector3::repeat(f64::NEG_INFINITY),
+            ),
+            |(min, max), position| (min.inf(position), max.sup(position)),
+        );
+        let translation = match self.translation {
+            GltfTranslation::None => Vector3::zeros(),
+            GltfTranslation::Fixed(translation) => translation,
+            GltfTranslation::BoundsCenter if self.positions.is_empty() => Vector3::zeros(),
+            GltfTranslation::BoundsCenter => (min + max) / 2.0,
+        };
+
+        let mut gltf = json!({
+            "asset": {
+                "version": "2.0",
+                "generator": "pasture",
+            },
+            "scene": 0,
+            "scenes": [{}],
+        });
+        let mut bin = vec![];
+        // Accessors can't be empty, so an empty point cloud is a scene without nodes
+        if !self.positions.is_empty() {
+            let mut buffer_views = vec![];
+            let mut accessors = vec![];
+            let mut attributes = Map::new();
+
+            let mut local_positions = Vec::with_capacity(self.positions.len() * 12);
+            let mut local_min = Vector3::repeat(f32::INFINITY);
+            let mut local_max = Vector3::repeat(f32::NEG_INFINITY);
+            for position in self.positions.iter() {
+                let local_position = (position - translation).map(|coordinate| coordinate as f32);
+                local_min = local_min.inf(&local_position);
+                local_max = local_max.sup(&local_position);
+                for coordinate in local_position.iter() {
+                    local_positions.extend_from_slice(&coordinate.to_le_bytes());
+                }
+            }
+            let buffer_view = append_buffer_view(&mut bin, &mut buffer_views, &local_positions, 12);
+            // The spec requires min and max for POSITION. They are written as the exact `f64` values of the stored
+            // `f32` values
+            accessors.push(json!({
+                "bufferView": buffer_view,
+                "componentType": GLTF_COMPONENT_TYPE_FLOAT,
+                "count": self.positions.len(),
+                "type": "VEC3",
+                "min": local_min.iter().map(|&value| value as f64).collect::<Vec<_>>(),
+                "max": local_max.iter().map(|&value| value as f64).collect::<Vec<_>>(),
+            }));
+            attributes.insert("POSITION".into(), json!(accessors.len() - 1));
+
+            for attribute in self.output_attributes.iter() {
+                let mut values = self.attribute_values(attribute);
+                let mut component_type = attribute.format.component_type;
+                if attribute.semantic == "COLOR_0"
+                    && component_type == GLTF_COMPONENT_TYPE_UNSIGNED_SHORT
+                {
+                    let channels = values
+                        .chunks_exact(2)
+                        .map(|channel| u16::from_le_bytes([channel[0], channel[1]]))
+                        .collect::<Vec<_>>();
+                    // Colors in the 8-bit range would be almost black as normalized 16-bit colors
+                    if channels.iter().all(|&channel| channel <= u8::MAX as u16) {
+                        values = channels.into_iter().map(|channel| channel as u8).collect();
+                        component_type = GLTF_COMPONENT_TYPE_UNSIGNED_BYTE;
+                    }
+                }
+                let element_size = values.len() / self.positions.len();
+                let buffer_view =
+                    append_buffer_view(&mut bin, &mut buffer_views, &values, element_size);
+                let mut accessor = json!({
+                    "bufferView": buffer_view,
+                    "componentType": component_type,
+                    "count": self.positions.len(),
+                    "type": attribute.format.accessor_type,
+                });
+                if attribute.format.normalized {
+                    accessor["normalized"] = json!(true);
+                }
+                accessors.push(accessor);
+                attributes.insert(attribute.semantic.clone(), json!(accessors.len() - 1));
+            }
+
+            let mut node = json!({ "mesh": 0 });
+            if translation != Vector3::zeros() {
+                node["translation"] = json!([translation.x, translation.y, translation.z]);
+            }
+            gltf["scenes"] = json!([{ "nodes": [0] }]);
+            gltf["nodes"] = json!([node]);
+            gltf["meshes"] = json!([{
+                "primitives": [{
+                    "attributes": attributes,
+                    "mode": GLTF_MODE_POINTS,
+                }],
+            }]);
+            gltf["accessors"] = json!(accessors);
+            gltf["bufferViews"] = json!(buffer_views);
+            gltf["buffers"] = json!([{ "byteLength": bin.len() }]);
+        }
+
+        let mut json_chunk =
+            serde_json::to_vec(&gltf).context("Could not convert glTF JSON to string")?;
+        json_chunk.resize(json_chunk.len().align_to(4), b' ');
+        let mut chunks = vec![(GLB_CHUNK_TYPE_JSON, json_chunk)];
+        if !bin.is_empty() {
+            chunks.push((GLB_CHUNK_TYPE_BIN, bin));
+        }
+        let total_length: u32 = (GLB_HEADER_SIZE
+            + chunks
+                .iter()
+                .map(|(_, chunk)| GLB_CHUNK_HEADER_SIZE + chunk.len())
+                .sum::<usize>())
+        .try_into()
+        .context("The points don't fit into a GLB file, which can be at most 4 GiB large")?;
+
+        self.writer
+            .write_all(&GLB_MAGIC.to_le_bytes())
+            .and_then(|_| self.writer.write_all(&GLB_VERSION.to_le_bytes()))
+            .and_then(|_| self.writer.write_all(&total_length.to_le_bytes()))
+            .context("Could not write GLB header")?;
+        for (chunk_type, chunk) in chunks {
+            self.writer
+                .write_all(&(chunk.len() as u32).to_le_bytes())
+                .and_then(|_| self.writer.write_all(&chunk_type.to_le_bytes()))
+                .and_then(|_| self.writer.write_all(&chunk))
+                .context("Could not write GLB chunk")?;
+        }
+        self.writer.flush().context("Could not flush GLB file")
+    }
+}
+
+impl GltfPointsWriter<BufWriter<File>> {
+    /// Creates a new `GltfPointsWriter` that writes to the file at `path`, see
+    /// [`GltfPointsWriter::from_write_and_layout`]
+    pub fn from_path_and_layout<P: AsRef<Path>>(
+        path: P,
+        point_layout: PointLayout,
+    ) -> Result<Self> {
+        GltfPointsWriterBuilder::new().build_for_path(path, point_layout)
+    }
+}
+
+impl<W: Write> PointWriter for GltfPointsWriter<W> {
+    fn write<'a, B: BorrowedBuffer<'a>>(&mut self, points: &'a B) -> Result<()> {
+        if !self.requires_flush {
+            bail!("The GLB file has already been written, no more points can be added");
+        }
+        if *points.point_layout() != self.default_layout {
+            bail!(
+                "PointLayout of the points does not match the PointLayout of the GltfPointsWriter"
+            );
+        }
+        self.cached_points.append(points);
+        self.positions
+            .extend(points.view_attribute_with_conversion::<Vector3<f64>>(&POSITION_3D)?);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if !self.requires_flush {
+            return Ok(());
+        }
+        self.write_glb()?;
+        self.requires_flush = false;
+        // The GLB file is written, so the cached points are not needed anymore
+        self.cached_points.clear();
+        self.positions = vec![];
+        Ok(())
+    }
+
+    fn get_default_point_layout(&self) -> &PointLayout {
+        &self.default_layout
+    }
+}
+
+impl<W: Write> Drop for GltfPointsWriter<W> {
+    fn drop(&mut self) {
+        if let Err(why) = self.flush() {
+            let _ = writeln!(
+                std::io::stderr(),
+                "Error while flushing GltfPointsWriter: {:?}",
+                why
+            );
+        }
+    }
+}
+
+/// Builder for a [`GltfPointsWriter`] with a custom translation of the points
+///
+/// ```no_run
+/// # use anyhow::Result;
+/// # use pasture_core::layout::{attributes::{COLOR_RGB, POSITION_3D}, PointLayout};
+/// # use pasture_io::gltf::{GltfPointsWriterBuilder, GltfTranslation};
+/// # fn main() -> Result<()> {
+/// let writer = GltfPointsWriterBuilder::new()
+///     .translation(GltfTranslation::None)
+///     .build_for_path(
+///         "points.glb",
+///         PointLayout::from_attributes(&[POSITION_3D, COLOR_RGB]),
+///     )?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Copy, Clone, Debug, Default)]
+pub struct GltfPointsWriterBuilder {
+    translation: GltfTranslation,
+}
+
+impl GltfPointsWriterBuilder {
+    /// Creates a new `GltfPointsWriterBuilder` that translates the node to the center of the bounds of the points
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets how the translation of the node is determined, see [`GltfTranslation`]
+    pub fn translation(mut self, translation: GltfTranslation) -> Self {
+        self.translation = translation;
+        self
+    }
+
+    /// Creates a `GltfPointsWriter` that writes points in the given `point_layout` to `writer`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `point_layout` has no `POSITION_3D` attribute, or if two attributes of `point_layout` have
+    /// the same glTF attribute name
+    pub fn build<W: Write>(
+        &self,
+        writer: W,
+        point_layout: PointLayout,
+    ) -> Result<GltfPointsWriter<W>> {
+        if !point_layout.has_attribute_with_name(POSITION_3D.name()) {
+            bail!("The PointLayout of a GltfPointsWriter must have a POSITION_3D attribute");
+        }
+        Ok(GltfPointsWriter {
+            writer,
+            output_attributes: GltfPointsWriter::<W>::make_output_attributes(&point_layout)?,
+            cached_points: VectorBuffer::new_from_layout(point_layout.clone()),
+            default_layout: point_layout,
+            positions: vec![],
+            translation: self.translation,
+            requires_flush: true,
+        })
+    }
+
+    /// Creates a `GltfPointsWriter` that writes to the file at `path`
+    pub fn build_for_path<P: AsRef<Path>>(
+        &self,
+        path: P,
+        point_layout: PointLayout,
+    ) -> Result<GltfPointsWriter<BufWriter<File>>> {
+        let file = BufWriter::new(File::create(path.as_ref()).context(format!(
+            "Could not open file {} for writing",
+            path.as_ref().display()
+        ))?);
+        self.build(file, point_layout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use pasture_core::layout::{
+        attributes::{CLASSIFICATION, INTENSITY},
+        PointAttributeDefinition, PointType,
+    };
+    use pasture_derive::PointType;
+
+    use super::*;
+
+    #[derive(
+        Debug, PointType, Copy, Clone, PartialEq, bytemuck::AnyBitPattern, bytemuck::NoUninit,
+    )]
+    #[repr(C, packed)]
+    struct GltfTestPoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        position: Vector3<f64>,
+        #[pasture(BUILTIN_COLOR_RGB)]
+        color: Vector3<u16>,
+        #[pasture(BUILTIN_INTENSITY)]
+        intensity: u16,
+        #[pasture(BUILTIN_CLASSIFICATION)]
+        classification: u8,
+        #[pasture(BUILTIN_GPS_TIME)]
+        gps_time: f64,
+        #[pasture(attribute = "Reflectance")]
+        reflectance: f32,
+    }
+
+    fn test_points(color_scale: u16) -> Vec<GltfTestPoint> {
+        (0..100)
+            .map(|index| GltfTestPoint {
+                position: Vector3::new(
+                    500_000.0 + index as f64 * 0.125,
+                    5_400_000.0 + (index % 10) as f64,
+                    250.0 - index as f64 * 0.5,
+                ),
+                color: Vector3::new(index, 2 * index, 255) * color_scale,
+                intensity: index * 10,
+                classification: (index % 8) as u8,
+                gps_time: 1000.0 + index as f64,
+                reflectance: index as f32 / 100.0,
+            })
+            .collect()
+    }
+
+    fn write_glb(points: &[GltfTestPoint], translation: GltfTranslation) -> Result<Vec<u8>> {
+        let mut glb = vec![];
+        {
+            let mut writer = GltfPointsWriterBuilder::new()
+                .translation(translation)
+                .build(&mut glb, GltfTestPoint::layout())?;
+            writer.write(&points.iter().copied().collect::<VectorBuffer>())?;
+            writer.flush()?;
+        }
+        Ok(glb)
+    }
+
+    fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+    }
+
+    /// Checks the GLB container and returns the JSON and binary chunk
+    fn parse_glb(glb: &[u8]) -> (Value, Vec<u8>) {
+        assert_eq!(GLB_MAGIC, read_u32(glb, 0));
+        assert_eq!(GLB_VERSION, read_u32(glb, 4));
+        assert_eq!(glb.len(), read_u32(glb, 8) as usize);
+
+        let json_length = read_u32(glb, 12) as usize;
+        assert_eq!(0, json_length % 4);
+        assert_eq!(GLB_CHUNK_TYPE_JSON, read_u32(glb, 16));
+        let json_end = 20 + json_length;
+        let gltf = serde_json::from_slice(&glb[20..json_end]).expect("Invalid glTF JSON");
+        if json_end == glb.len() {
+            return (gltf, vec![]);
+        }
+
+        let bin_length = read_u32(glb, json_end) as usize;
+        assert_eq!(0, bin_length % 4);
+        assert_eq!(GLB_CHUNK_TYPE_BIN, read_u32(glb, json_end + 4));
+        assert_eq!(glb.len(), json_end + 8 + bin_length);
+        (gltf, glb[json_end + 8..].to_vec())
+    }
+
+    /// Returns the bytes of each element of the accessor of the glTF attribute with the given `semantic`
+    fn accessor_elements(gltf: &Value, bin: &[u8], semantic: &str) -> Vec<Vec<u8>> {
+        let accessor_index = gltf["meshes"][0]["primitives"][0]["attributes"][semantic]
+            .as_u64()
+            .unwrap() as usize;
+        let accessor = &gltf["accessors"][accessor_index];
+        let buffer_view = &gltf["bufferViews"][accessor["bufferView"].as_u64().unwrap() as usize];
+        let component_size = match accessor["componentType"].as_u64().unwrap() as u32 {
+            GLTF_COMPONENT_TYPE_BYTE | GLTF_COMPONENT_TYPE_UNSIGNED_BYTE => 1,
+            GLTF_COMPONENT_TYPE_SHORT | GLTF_COMPONENT_TYPE_UNSIGNED_SHORT => 2,
+            GLTF_COMPONENT_TYPE_FLOAT => 4,
+            other => panic!("Unexpected component type {}", other),
+        };
+        let num_components = match accessor["type"].as_str().unwrap() {
+            "SCALAR" => 1,
+            "VEC3" => 3,
+            "VEC4" => 4,
+            other => panic!("Unexpected accessor type {}", other),
+        };
+        let element_size = component_size * num_components;
+        let byte_offset = buffer_view["byteOffset"].as_u64().unwrap() as usize;
+        let byte_stride = buffer_view["byteStride"]
+            .as_u64()
+            .map_or(element_size, |stride| stride as usize);
+        assert_eq!(0, byte_offset % GLTF_VERTEX_ALIGNMENT);
+        assert_eq!(0, byte_stride % GLTF_VERTEX_ALIGNMENT);
+
+        (0..accessor["count"].as_u64().unwrap() as usize)
+            .map(|index| {
+                let start = byte_offset + index * byte_stride;
+                bin[start..start + element_size].to_vec()
+            })
+            .collect()
+    }
+
+    fn f32_elements(elements: &[Vec<u8>]) -> Vec<Vec<f32>> {
+        elements
+            .iter()
+            .map(|element| {
+                element
+                    .chunks_exact(4)
+                    .map(|component| f32::from_le_bytes(component.try_into().unwrap()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_gltf_points_writer() -> Result<()> {
+        let points = test_points(256);
+        let (gltf, bin) = parse_glb(&write_glb(&points, GltfTranslation::BoundsCenter)?);
+
+        let primitive = &gltf["meshes"][0]["primitives"][0];
+        assert_eq!(GLTF_MODE_POINTS as u64, primitive["mode"].as_u64().unwrap());
+        let mut semantics = primitive["attributes"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        semantics.sort();
+        assert_eq!(
+            vec![
+                "COLOR_0",
+                "POSITION",
+                "_CLASSIFICATION",
+                "_GPS_TIME",
+                "_INTENSITY",
+                "_REFLECTANCE"
+            ],
+            semantics
+        );
+        assert_eq!(
+            bin.len() as u64,
+            gltf["buffers"][0]["byteLength"].as_u64().unwrap()
+        );
+
+        let translation = Vector3::new(500_006.187_5, 5_400_004.5, 225.25);
+        assert_eq!(
+            json!([translation.x, translation.y, translation.z]),
+            gltf["nodes"][0]["translation"]
+        );
+        let positions = f32_elements(&accessor_elements(&gltf, &bin, "POSITION"));
+        for (point, position) in points.iter().zip(positions.iter()) {
+            let expected_position = point.position - translation;
+            for (expected, actual) in expected_position.iter().zip(position.iter()) {
+                assert_eq!(*expected as f32, *actual);
+            }
+        }
+        let position_accessor =
+            &gltf["accessors"][primitive["attributes"]["POSITION"].as_u64().unwrap() as usize];
+        for component in 0..3 {
+            let min = positions
+                .iter()
+                .map(|position| position[component])
+                .fold(f32::INFINITY, f32::min);
+            let max = positions
+                .iter()
+                .map(|position| position[component])
+                .fold(f32::NEG_INFINITY, f32::max);
+            assert_eq!(
+                min as f64,
+                position_accessor["min"][component].as_f64().unwrap()
+            );
+            assert_eq!(
+                max as f64,
+                position_accessor["max"][component].as_f64().unwrap()
+            );
+        }
+
+        let color_accessor =
+            &gltf["accessors"][primitive["attributes"]["COLOR_0"].as_u64().unwrap() as usize];
+        assert_eq!(
+            GLTF_COMPONENT_TYPE_UNSIGNED_SHORT as u64,
+            color_accessor["componentType"].as_u64().unwrap()
+        );
+        assert_eq!(Some(true), color_accessor["normalized"].as_bool());
+        let colors = accessor_elements(&gltf, &bin, "COLOR_0");
+        for (point, color) in points.iter().zip(colors.iter()) {
+            let expected_color = point.color;
+            let expected_bytes = expected_color
+                .iter()
+                .flat_map(|channel| channel.to_le_bytes())
+                .collect::<Vec<_>>();
+            assert_eq!(&expected_bytes, color);
+        }
+
+        let classifications = accessor_elements(&gltf, &bin, "_CLASSIFICATION");
+        let intensities = accessor_elements(&gltf, &bin, "_INTENSITY");
+        let gps_times = f32_elements(&accessor_elements(&gltf, &bin, "_GPS_TIME"));
+        let reflectances = f32_elements(&accessor_elements(&gltf, &bin, "_REFLECTANCE"));
+        for (index, point) in points.iter().enumerate() {
+            assert_eq!(vec![point.classification], classifications[index]);
+            assert_eq!(point.intensity.to_le_bytes().to_vec(), intensities[index]);
+            assert_eq!(vec![point.gps_time as f32], gps_times[index]);
+            assert_eq!(vec![point.reflectance], reflectances[index]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_gltf_points_writer_with_8_bit_colors() -> Result<()> {
+        let points = test_points(1);
+        let (gltf, bin) = parse_glb(&write_glb(&points, GltfTranslation::None)?);
+
+        assert!(gltf["nodes"][0].get("translation").is_none());
+        let positions = f32_elements(&accessor_elements(&gltf, &bin, "POSITION"));
+        assert_eq!(500_000.0_f32, positions[0][0]);
+
+        let primitive = &gltf["meshes"][0]["primitives"][0];
+        let color_accessor =
+            &gltf["accessors"][primitive["attributes"]["COLOR_0"].as_u64().unwrap() as usize];
+        assert_eq!(
+            GLTF_COMPONENT_TYPE_UNSIGNED_BYTE as u64,
+            color_accessor["componentType"].as_u64().unwrap()
+        );
+        let colors = accessor_elements(&gltf, &bin, "COLOR_0");
+        for (point, color) in points.iter().zip(colors.iter()) {
+            let expected_color = point.color;
+            assert_eq!(
+                &expected_color
+                    .iter()
+                    .map(|channel| *channel as u8)
+                    .collect::<Vec<_>>(),
+                color
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_gltf_points_writer_with_fixed_translation() -> Result<()> {
+        let points = test_points(1);
+        let translation = Vector3::new(500_000.0, 5_400_000.0, 0.0);
+        let (gltf, bin) = parse_glb(&write_glb(&points, GltfTranslation::Fixed(translation))?);
+
+        assert_eq!(
+            json!([500_000.0, 5_400_000.0, 0.0]),
+            gltf["nodes"][0]["translation"]
+        );
+        let positions = f32_elements(&accessor_elements(&gltf, &bin, "POSITION"));
+        assert_eq!(vec![0.0, 0.0, 250.0], positions[0]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_gltf_points_writer_without_points() -> Result<()> {
+        let (gltf, bin) = parse_glb(&write_glb(&[], GltfTranslation::BoundsCenter)?);
+        assert!(bin.is_empty());
+        assert_eq!(json!([{}]), gltf["scenes"]);
+        assert!(gltf.get("meshes").is_none());
+        assert!(gltf.get("buffers").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_gltf_points_writer_errors() -> Result<()> {
+        assert!(GltfPointsWriter::from_write_and_layout(
+            vec![],
+            PointLayout::from_attributes(&[INTENSITY])
+        )
+        .is_err());
+        // `Classification` and a custom `CLASSIFICATION` attribute are both written as `_CLASSIFICATION`
+        assert!(GltfPointsWriter::from_write_and_layout(
+            vec![],
+            PointLayout::from_attributes(&[
+                POSITION_3D,
+                CLASSIFICATION,
+                PointAttributeDefinition::custom(
+                    Cow::Borrowed("CLASSIFICATION"),
+                    PointAttributeDataType::U8
+                ),
+            ])
+        )
+        .is_err());
+
+        let mut writer = GltfPointsWriter::from_write_and_layout(vec![], GltfTestPoint::layout())?;
+        assert!(writer
+            .write(&VectorBuffer::new_from_layout(
+                PointLayout::from_attributes(&[POSITION_3D])
+            ))
+            .is_err());
+        writer.flush()?;
+        assert!(writer
+            .write(&test_points(1).into_iter().collect::<VectorBuffer>())
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_gltf_attribute_semantic() {
+        assert_eq!("_INTENSITY", gltf_attribute_semantic("Intensity"));
+        assert_eq!("_GPS_TIME", gltf_attribute_semantic("GpsTime"));
+        assert_eq!("_POINT_SOURCE_ID", gltf_attribute_semantic("PointSourceID"));
+        assert_eq!("_NIR", gltf_attribute_semantic("NIR"));
+        assert_eq!("_GPS_TIME", gltf_attribute_semantic("GPSTime"));
+        assert_eq!("_SCAN_ANGLE_2", gltf_attribute_semantic("scan angle 2"));
+        assert_eq!("_POSITION_3D", gltf_attribute_semantic("Position3D"));
+    }
+}
@@ -0,0 +1,2 @@
+mod gltf_writer;
+pub use self::gltf_writer::*;
@@ -0,0 +1,342 @@
+use std::io::{Cursor, SeekFrom};
+
+use anyhow::{bail, Context, Result};
+use byteorder::{ByteOrder, LittleEndian};
+use las_rs::{raw, Header};
+use pasture_core::containers::{BorrowedMutBuffer, OwningBuffer, VectorBuffer};
+use pasture_core::{layout::PointLayout, meta::Metadata};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+use super::{
+    get_default_las_converter, point_layout_from_las_metadata, LASMetadata, LASReadOptions,
+    LASReaderBase, RawLASReader,
+};
+use crate::base::{AsyncPointReader, PointReader};
+
+/// Byte offset of the `offset_to_point_data` field within the LAS header
+const OFFSET_TO_POINT_DATA_FIELD_OFFSET: usize = 96;
+
+/// Async reader for uncompressed LAS files, based on the async I/O traits of `tokio`. Apart from being async, this
+/// behaves exactly like the synchronous LAS reader
+pub struct AsyncRawLASReader<T: AsyncRead + AsyncSeek + Unpin> {
+    reader: T,
+    metadata: LASMetadata,
+    layout: PointLayout,
+    las_point_records_layout: PointLayout,
+    options: LASReadOptions,
+    current_point_index: usize,
+    offset_to_first_point_in_file: u64,
+    size_of_point_in_file: u64,
+}
+
+impl<T: AsyncRead + AsyncSeek + Unpin> AsyncRawLASReader<T> {
+    /// Creates a new `AsyncRawLASReader` from the given `reader`. See [`LASReader::from_read`](super::LASReader::from_read)
+    /// for an explanation of the `point_layout_matches_memory_layout` parameter
+    pub async fn from_read(reader: T, point_layout_matches_memory_layout: bool) -> Result<Self> {
+        Self::from_read_with_options(
+            reader,
+            LASReadOptions {
+                point_layout_matches_memory_layout,
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    /// Creates a new `AsyncRawLASReader` from the given `reader`, using the given `options` to determine the default
+    /// `PointLayout` of the reader. See [`LASReadOptions`] for more information
+    pub async fn from_read_with_options(mut reader: T, options: LASReadOptions) -> Result<Self> {
+        // Everything before the first point record (header, VLRs, padding) is read asynchronously into memory and then
+        // parsed with the synchronous reader. First read enough of the header to know where the point records start
+        let mut header_bytes = vec![0; OFFSET_TO_POINT_DATA_FIELD_OFFSET + 4];
+        reader
+            .read_exact(&mut header_bytes)
+            .await
+            .context("Failed to read LAS header")?;
+        let offset_to_first_point_in_file =
+            LittleEndian::read_u32(&header_bytes[OFFSET_TO_POINT_DATA_FIELD_OFFSET..]) as u64;
+        if (offset_to_first_point_in_file as usize) < header_bytes.len() {
+            bail!(
+                "Invalid offset to point data ({}) in LAS header",
+                offset_to_first_point_in_file
+            );
+        }
+        let bytes_read = header_bytes.len();
+        header_bytes.resize(offset_to_first_point_in_file as usize, 0);
+        reader
+            .read_exact(&mut header_bytes[bytes_read..])
+            .await
+            .context("Failed to read LAS header and VLRs")?;
+
+        let (metadata, layout) = {
+            let header_reader = RawLASReader::from_header_read_with_options(
+                Cursor::new(&header_bytes[..]),
+                options.clone(),
+            )?;
+            (
+                header_reader.las_metadata().clone(),
+                header_reader.get_default_point_layout().clone(),
+            )
+        };
+        if metadata.point_format().is_compressed {
+            bail!("AsyncRawLASReader does not support compressed LAZ files");
+        }
+        let raw_header = raw::Header::read_from(Cursor::new(&header_bytes[..]))?;
+        let size_of_point_in_file = raw_header.point_data_record_length as u64;
+        let matching_memory_layout = point_layout_from_las_metadata(&metadata, true)?;
+
+        Ok(Self {
+            reader,
+            metadata,
+            layout,
+            las_point_records_layout: matching_memory_layout,
+            options,
+            current_point_index: 0,
+            offset_to_first_point_in_file,
+            size_of_point_in_file,
+        })
+    }
+
+    pub fn las_metadata(&self) -> &LASMetadata {
+        &self.metadata
+    }
+
+    /// Seek to the point at the given `position`. Behaves like [`SeekToPoint::seek_point`](crate::base::SeekToPoint::seek_point)
+    pub async fn seek_point(&mut self, position: SeekFrom) -> Result<usize> {
+        let new_position = match position {
+            SeekFrom::Start(from_start) => from_start as i64,
+            SeekFrom::End(from_end) => self.metadata.point_count() as i64 + from_end,
+            SeekFrom::Current(from_current) => self.current_point_index as i64 + from_current,
+        };
+        if new_position < 0 {
+            panic!("AsyncRawLASReader::seek_point: It is an error to seek to a point position smaller than zero!");
+        }
+        let clamped_position =
+            std::cmp::min(self.metadata.point_count() as i64, new_position) as usize;
+
+        if self.current_point_index != clamped_position {
+            let position_within_file = self.offset_to_first_point_in_file
+                + clamped_position as u64 * self.size_of_point_in_file;
+            self.reader
+                .seek(SeekFrom::Start(position_within_file))
+                .await?;
+            self.current_point_index = clamped_position;
+        }
+
+        Ok(self.current_point_index)
+    }
+
+    async fn read_into_default_layout<'a, 'b, B: BorrowedMutBuffer<'a>>(
+        &mut self,
+        point_buffer: &'b mut B,
+        count: usize,
+    ) -> Result<usize>
+    where
+        'a: 'b,
+    {
+        let num_points_to_read = usize::min(count, self.remaining_points());
+        if num_points_to_read == 0 {
+            return Ok(0);
+        }
+
+        if let Some(interleaved_buffer) = point_buffer.as_interleaved_mut() {
+            let new_point_data = interleaved_buffer.get_point_range_mut(0..num_points_to_read);
+            self.reader
+                .read_exact(new_point_data)
+                .await
+                .context("Failed to read point records")?;
+        } else {
+            // Read point data in chunks of ~1MiB size, each chunk is a single awaited read
+            const CHUNK_MEM_SIZE: usize = 1 << 20;
+            let num_points_per_chunk = CHUNK_MEM_SIZE / self.size_of_point_in_file as usize;
+            let num_chunks = (num_points_to_read + num_points_per_chunk - 1) / num_points_per_chunk;
+            let mut read_buffer =
+                vec![0; num_points_per_chunk * self.size_of_point_in_file as usize];
+            for chunk_idx in 0..num_chunks {
+                let bytes_in_chunk = if chunk_idx == num_chunks - 1 {
+                    (num_points_to_read - (chunk_idx * num_points_per_chunk))
+                        * self.size_of_point_in_file as usize
+                } else {
+                    read_buffer.len()
+                };
+                let chunk_bytes = &mut read_buffer[..bytes_in_chunk];
+                self.reader
+                    .read_exact(chunk_bytes)
+                    .await
+                    .context("Failed to read chunk of points")?;
+                let first_point_in_chunk = chunk_idx * num_points_per_chunk;
+                let chunk_end = ((chunk_idx + 1) * num_points_per_chunk).min(num_points_to_read);
+                // Safe because this function (`read_into_default_layout`) is only called if the buffer has the exact
+                // binary memory layout of the LAS file
+                unsafe {
+                    point_buffer.set_point_range(first_point_in_chunk..chunk_end, chunk_bytes);
+                }
+            }
+        }
+
+        self.current_point_index += num_points_to_read;
+
+        Ok(num_points_to_read)
+    }
+
+    async fn read_into_custom_layout<'a, 'b, B: BorrowedMutBuffer<'a>>(
+        &mut self,
+        point_buffer: &'b mut B,
+        count: usize,
+    ) -> Result<usize> {
+        let num_points_to_read = usize::min(count, self.remaining_points());
+        if num_points_to_read == 0 {
+            return Ok(0);
+        }
+
+        const CHUNK_BYTES: usize = 1 << 20; // 1 MiB
+        let points_per_chunk =
+            CHUNK_BYTES / self.las_point_records_layout.size_of_point_entry() as usize;
+        let num_chunks = (num_points_to_read + points_per_chunk - 1) / points_per_chunk;
+
+        let size_of_chunk = if num_chunks > 1 {
+            points_per_chunk
+        } else {
+            num_points_to_read
+        };
+
+        let mut convert_buffer =
+            VectorBuffer::with_capacity(size_of_chunk, self.las_point_records_layout.clone());
+        convert_buffer.resize(size_of_chunk);
+
+        let source_layout = self.las_point_records_layout.clone();
+        let target_layout = point_buffer.point_layout().clone();
+        let converter = get_default_las_converter(
+            &source_layout,
+            &target_layout,
+            &self.metadata,
+            &self.options,
+        )
+        .context("Unsupported conversion")?;
+
+        for chunk_idx in 0..num_chunks {
+            let points_in_current_chunk = if chunk_idx == num_chunks - 1 {
+                num_points_to_read - ((num_chunks - 1) * size_of_chunk)
+            } else {
+                size_of_chunk
+            };
+
+            self.read_into_default_layout(&mut convert_buffer, points_in_current_chunk)
+                .await?;
+            let target_buffer_first_point = chunk_idx * size_of_chunk;
+            let target_buffer_last_point = target_buffer_first_point + points_in_current_chunk;
+            converter.convert_into_range(
+                &convert_buffer,
+                0..points_in_current_chunk,
+                point_buffer,
+                target_buffer_first_point..target_buffer_last_point,
+            );
+        }
+
+        Ok(num_points_to_read)
+    }
+}
+
+impl<T: AsyncRead + AsyncSeek + Unpin> LASReaderBase for AsyncRawLASReader<T> {
+    fn remaining_points(&self) -> usize {
+        self.metadata.point_count() - self.current_point_index
+    }
+
+    fn header(&self) -> &Header {
+        self.metadata.raw_las_header().unwrap()
+    }
+}
+
+impl<T: AsyncRead + AsyncSeek + Unpin> AsyncPointReader for AsyncRawLASReader<T> {
+    async fn read_into<'a, 'b, B: BorrowedMutBuffer<'a>>(
+        &mut self,
+        point_buffer: &'b mut B,
+        count: usize,
+    ) -> Result<usize>
+    where
+        'a: 'b,
+    {
+        if point_buffer.len() < count {
+            panic!("point_buffer.len() must be >= count");
+        }
+
+        if *point_buffer.point_layout() != self.las_point_records_layout {
+            self.read_into_custom_layout(point_buffer, count).await
+        } else {
+            self.read_into_default_layout(point_buffer, count).await
+        }
+    }
+
+    fn get_metadata(&self) -> &dyn Metadata {
+        &self.metadata
+    }
+
+    fn get_default_point_layout(&self) -> &PointLayout {
+        &self.layout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use las_rs::point::Format;
+    use pasture_core::containers::BorrowedBuffer;
+
+    use super::*;
+    use crate::las::{
+        compare_to_reference_data, compare_to_reference_data_range, get_test_las_path,
+        get_test_las_path_with_extra_bytes,
+    };
+
+    #[tokio::test]
+    async fn test_async_raw_las_reader_read() -> Result<()> {
+        for format in 0..=10 {
+            let file = tokio::fs::File::open(get_test_las_path(format)).await?;
+            let mut reader = AsyncRawLASReader::from_read(file, false).await?;
+            assert_eq!(10, reader.remaining_points());
+
+            let points = reader.read::<VectorBuffer>(10).await?;
+            compare_to_reference_data(&points, Format::new(format)?);
+            assert_eq!(0, reader.remaining_points());
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_async_raw_las_reader_matches_sync_reader() -> Result<()> {
+        for format in 0..=10 {
+            let path = get_test_las_path_with_extra_bytes(format);
+            let file = tokio::fs::File::open(&path).await?;
+            let mut reader = AsyncRawLASReader::from_read(file, true).await?;
+            let mut sync_reader = RawLASReader::from_read(
+                std::io::BufReader::new(std::fs::File::open(&path)?),
+                true,
+            )?;
+
+            let expected_points = sync_reader.read::<VectorBuffer>(10)?;
+            let actual_points = reader.read::<VectorBuffer>(10).await?;
+            assert_eq!(expected_points, actual_points, "Format {}", format);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_async_raw_las_reader_seek() -> Result<()> {
+        for format in 0..=10 {
+            let file = tokio::fs::File::open(get_test_las_path(format)).await?;
+            let mut reader = AsyncRawLASReader::from_read(file, false).await?;
+
+            assert_eq!(6, reader.seek_point(SeekFrom::Start(6)).await?);
+            let points = reader.read::<VectorBuffer>(4).await?;
+            assert_eq!(4, points.len());
+            compare_to_reference_data_range(&points, Format::new(format)?, 6..10);
+
+            assert_eq!(1, reader.seek_point(SeekFrom::Current(-9)).await?);
+            let points = reader.read::<VectorBuffer>(2).await?;
+            compare_to_reference_data_range(&points, Format::new(format)?, 1..3);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,380 @@
+use std::convert::TryInto;
+use std::ops::Range;
+
+use anyhow::{bail, Context, Result};
+use pasture_core::math::AABB;
+use pasture_core::nalgebra::{Point3, Vector3};
+
+use super::LASMetadata;
+
+/// User ID of the VLRs and EVLRs that are specific to COPC files
+pub const COPC_VLR_USER_ID: &str = "copc";
+/// Record ID of the COPC info VLR
+pub const COPC_INFO_RECORD_ID: u16 = 1;
+/// Record ID of the EVLR that contains the COPC hierarchy pages
+pub const COPC_HIERARCHY_RECORD_ID: u16 = 1000;
+
+/// Size of a single entry within a COPC hierarchy page
+const SIZE_OF_HIERARCHY_ENTRY: usize = 32;
+
+/// Contents of the COPC info VLR, which describes the octree of a Cloud Optimized Point Cloud
+#[derive(Clone, Debug, PartialEq)]
+pub struct CopcInfo {
+    /// Center of the root node of the octree
+    pub center: Vector3<f64>,
+    /// Half of the side length of the (cubic) root node of the octree
+    pub halfsize: f64,
+    /// Space between points at the root node of the octree. This value is halved at each level
+    pub spacing: f64,
+    /// File offset of the root hierarchy page
+    pub root_hierarchy_offset: u64,
+    /// Size of the root hierarchy page in bytes
+    pub root_hierarchy_size: u64,
+    /// Minimum GPS time of all points
+    pub gps_time_minimum: f64,
+    /// Maximum GPS time of all points
+    pub gps_time_maximum: f64,
+}
+
+impl CopcInfo {
+    /// Size of the COPC info VLR in bytes
+    pub const SIZE: usize = 160;
+
+    /// Parses the `CopcInfo` from the binary data of the COPC info VLR
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        if data.len() < Self::SIZE {
+            bail!(
+                "COPC info VLR must be {} bytes long, but is only {} bytes long",
+                Self::SIZE,
+                data.len()
+            );
+        }
+        let f64_at =
+            |offset: usize| f64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+        let u64_at =
+            |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+        Ok(Self {
+            center: Vector3::new(f64_at(0), f64_at(8), f64_at(16)),
+            halfsize: f64_at(24),
+            spacing: f64_at(32),
+            root_hierarchy_offset: u64_at(40),
+            root_hierarchy_size: u64_at(48),
+            gps_time_minimum: f64_at(56),
+            gps_time_maximum: f64_at(64),
+        })
+    }
+
+    /// Parses the `CopcInfo` from the VLRs of a LAS file. Returns `None` if there is no COPC info VLR, i.e. if the
+    /// file is no COPC file
+    pub fn from_las_metadata(metadata: &LASMetadata) -> Result<Option<Self>> {
+        match metadata.find_vlr(COPC_VLR_USER_ID, COPC_INFO_RECORD_ID) {
+            Some(vlr) => Ok(Some(Self::from_bytes(&vlr.data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the bounding box of the root node of the octree
+    pub fn root_bounds(&self) -> AABB<f64> {
+        let halfsize = Vector3::new(self.halfsize, self.halfsize, self.halfsize);
+        AABB::from_min_max_unchecked(
+            Point3::from(self.center - halfsize),
+            Point3::from(self.center + halfsize),
+        )
+    }
+}
+
+/// Key of a node within the COPC octree. The root node has level 0, and `x`, `y` and `z` are the index of the node
+/// within the grid of all nodes of the same level
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct VoxelKey {
+    pub level: i32,
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl VoxelKey {
+    /// Returns the bounding box of the node with this key within the octree described by `info`
+    pub fn bounds(&self, info: &CopcInfo) -> AABB<f64> {
+        let root_bounds = info.root_bounds();
+        let node_size = (2.0 * info.halfsize) / 2.0_f64.powi(self.level);
+        let min = root_bounds.min()
+            + Vector3::new(self.x as f64, self.y as f64, self.z as f64) * node_size;
+        AABB::from_min_max_unchecked(min, min + Vector3::new(node_size, node_size, node_size))
+    }
+}
+
+/// Entry of the COPC hierarchy that describes the point data of a single octree node. The point data of each node is
+/// stored as a single LAZ chunk
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CopcHierarchyEntry {
+    pub key: VoxelKey,
+    /// File offset of the LAZ chunk with the points of this node
+    pub offset: u64,
+    /// Size of the LAZ chunk in bytes
+    pub byte_size: i32,
+    /// Number of points in this node
+    pub point_count: i32,
+}
+
+/// The hierarchy of a COPC file, i.e. all octree nodes that contain points
+#[derive(Clone, Debug)]
+pub struct CopcHierarchy {
+    /// All nodes that contain points, sorted by the offset of their point data
+    entries: Vec<CopcHierarchyEntry>,
+    /// For each entry, the range of point indices of its points within the file
+    point_ranges: Vec<Range<usize>>,
+}
+
+impl CopcHierarchy {
+    /// Parses the hierarchy from the data of the COPC hierarchy EVLR, starting at the root page described by `info`.
+    /// `evlr_data_offset` is the offset of the EVLR data within the file, which is needed because hierarchy pages
+    /// reference each other through file offsets
+    pub fn from_evlr_data(data: &[u8], evlr_data_offset: u64, info: &CopcInfo) -> Result<Self> {
+        let page_data = |offset: u64, size: u64| -> Result<&[u8]> {
+            let start = offset
+                .checked_sub(evlr_data_offset)
+                .context("COPC hierarchy page lies outside of the hierarchy EVLR")?
+                as usize;
+            data.get(start..start + size as usize)
+                .context("COPC hierarchy page lies outside of the hierarchy EVLR")
+        };
+
+        let mut entries = vec![];
+        let mut pages_to_visit = vec![(info.root_hierarchy_offset, info.root_hierarchy_size)];
+        // Every page takes up at least one entry in the EVLR, so there can't be more pages than that
+        let max_page_count = data.len() / SIZE_OF_HIERARCHY_ENTRY + 1;
+        let mut visited_page_count = 0;
+        while let Some((page_offset, page_size)) = pages_to_visit.pop() {
+            visited_page_count += 1;
+            if visited_page_count > max_page_count {
+                bail!("COPC hierarchy contains cyclic page references");
+            }
+            if page_size as usize % SIZE_OF_HIERARCHY_ENTRY != 0 {
+                bail!(
+                    "Size of COPC hierarchy page must be a multiple of {} bytes, but is {}",
+                    SIZE_OF_HIERARCHY_ENTRY,
+                    page_size
+                );
+            }
+            for raw_entry in
+                page_data(page_offset, page_size)?.chunks_exact(SIZE_OF_HIERARCHY_ENTRY)
+            {
+                let i32_at = |offset: usize| {
+                    i32::from_le_bytes(raw_entry[offset..offset + 4].try_into().unwrap())
+                };
+                let entry = CopcHierarchyEntry {
+                    key: VoxelKey {
+                        level: i32_at(0),
+                        x: i32_at(4),
+                        y: i32_at(8),
+                        z: i32_at(12),
+                    },
+                    offset: u64::from_le_bytes(raw_entry[16..24].try_into().unwrap()),
+                    byte_size: i32_at(24),
+                    point_count: i32_at(28),
+                };
+                match entry.point_count {
+                    -1 => pages_to_visit.push((entry.offset, entry.byte_size as u64)),
+                    0 => (),
+                    count if count < 0 => {
+                        bail!("Invalid point count {} in COPC hierarchy", count)
+                    }
+                    _ => entries.push(entry),
+                }
+            }
+        }
+
+        Ok(Self::from_entries(entries))
+    }
+
+    /// Creates a `CopcHierarchy` from the given `entries` of all nodes that contain points
+    pub fn from_entries(mut entries: Vec<CopcHierarchyEntry>) -> Self {
+        // The LAZ chunks of all nodes are stored back to back, so sorting the nodes by their offset gives the
+        // order of their points within the file
+        entries.sort_by_key(|entry| entry.offset);
+        let mut first_point = 0;
+        let point_ranges = entries
+            .iter()
+            .map(|entry| {
+                let range = first_point..(first_point + entry.point_count as usize);
+                first_point = range.end;
+                range
+            })
+            .collect();
+        Self {
+            entries,
+            point_ranges,
+        }
+    }
+
+    /// Returns all nodes of the hierarchy that contain points, sorted by the offset of their point data
+    pub fn entries(&self) -> &[CopcHierarchyEntry] {
+        &self.entries
+    }
+
+    /// Returns the total number of points in all nodes of the hierarchy
+    pub fn point_count(&self) -> usize {
+        self.point_ranges
+            .last()
+            .map(|range| range.end)
+            .unwrap_or_default()
+    }
+
+    /// Returns the maximum level of all nodes in the hierarchy
+    pub fn max_level(&self) -> Option<u32> {
+        self.entries
+            .iter()
+            .map(|entry| entry.key.level as u32)
+            .max()
+    }
+
+    /// Returns the sorted, non-overlapping ranges of point indices of all nodes that intersect `bounds` and whose
+    /// level is at most `max_level`. If `max_level` is `None`, nodes of all levels are considered
+    pub fn point_ranges_in_bounds(
+        &self,
+        info: &CopcInfo,
+        bounds: &AABB<f64>,
+        max_level: Option<u32>,
+    ) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = vec![];
+        for (entry, range) in self.entries.iter().zip(self.point_ranges.iter()) {
+            if let Some(max_level) = max_level {
+                if entry.key.level as u32 > max_level {
+                    continue;
+                }
+            }
+            if !entry.key.bounds(info).intersects(bounds) {
+                continue;
+            }
+            match ranges.last_mut() {
+                Some(previous) if previous.end == range.start => previous.end = range.end,
+                _ => ranges.push(range.clone()),
+            }
+        }
+        ranges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_info_vlr_data(root_hierarchy_offset: u64, root_hierarchy_size: u64) -> Vec<u8> {
+        let mut data = vec![];
+        for value in [10.0_f64, 20.0, 30.0, 8.0, 1.0] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&root_hierarchy_offset.to_le_bytes());
+        data.extend_from_slice(&root_hierarchy_size.to_le_bytes());
+        data.extend_from_slice(&0.0_f64.to_le_bytes());
+        data.extend_from_slice(&100.0_f64.to_le_bytes());
+        data.resize(CopcInfo::SIZE, 0);
+        data
+    }
+
+    fn push_entry(
+        data: &mut Vec<u8>,
+        key: [i32; 4],
+        offset: u64,
+        byte_size: i32,
+        point_count: i32,
+    ) {
+        for value in key {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&offset.to_le_bytes());
+        data.extend_from_slice(&byte_size.to_le_bytes());
+        data.extend_from_slice(&point_count.to_le_bytes());
+    }
+
+    #[test]
+    fn test_copc_info_from_bytes() -> Result<()> {
+        let info = CopcInfo::from_bytes(&make_info_vlr_data(1000, 64))?;
+        assert_eq!(Vector3::new(10.0, 20.0, 30.0), info.center);
+        assert_eq!(8.0, info.halfsize);
+        assert_eq!(1.0, info.spacing);
+        assert_eq!(1000, info.root_hierarchy_offset);
+        assert_eq!(64, info.root_hierarchy_size);
+        assert_eq!(100.0, info.gps_time_maximum);
+        assert_eq!(
+            AABB::from_min_max(Point3::new(2.0, 12.0, 22.0), Point3::new(18.0, 28.0, 38.0)),
+            info.root_bounds()
+        );
+
+        assert!(CopcInfo::from_bytes(&[0; 100]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_voxel_key_bounds() -> Result<()> {
+        let info = CopcInfo::from_bytes(&make_info_vlr_data(0, 0))?;
+        let key = VoxelKey {
+            level: 2,
+            x: 1,
+            y: 0,
+            z: 3,
+        };
+        assert_eq!(
+            AABB::from_min_max(Point3::new(6.0, 12.0, 34.0), Point3::new(10.0, 16.0, 38.0)),
+            key.bounds(&info)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_copc_hierarchy_from_evlr_data() -> Result<()> {
+        // The hierarchy EVLR data starts at offset 1000 in the file. The root page contains the root node, one child
+        // node, and a reference to a second page with another child node
+        const EVLR_DATA_OFFSET: u64 = 1000;
+        let mut data = vec![];
+        push_entry(&mut data, [0, 0, 0, 0], 500, 100, 10);
+        push_entry(&mut data, [1, 1, 1, 1], 400, 50, 5);
+        push_entry(&mut data, [1, 0, 0, 0], EVLR_DATA_OFFSET + 128, 64, -1);
+        push_entry(&mut data, [1, 0, 1, 0], 0, 0, 0);
+        push_entry(&mut data, [1, 0, 0, 0], 300, 20, 3);
+        push_entry(&mut data, [2, 0, 0, 0], 600, 20, 2);
+
+        let info = CopcInfo::from_bytes(&make_info_vlr_data(EVLR_DATA_OFFSET, 128))?;
+        let hierarchy = CopcHierarchy::from_evlr_data(&data, EVLR_DATA_OFFSET, &info)?;
+        assert_eq!(4, hierarchy.entries().len());
+        assert_eq!(20, hierarchy.point_count());
+        assert_eq!(Some(2), hierarchy.max_level());
+        let offsets = hierarchy
+            .entries()
+            .iter()
+            .map(|entry| entry.offset)
+            .collect::<Vec<_>>();
+        assert_eq!(vec![300, 400, 500, 600], offsets);
+
+        // Points are ordered by offset: node 1-0-0-0 (0..3), 1-1-1-1 (3..8), 0-0-0-0 (8..18), 2-0-0-0 (18..20)
+        let root_bounds = info.root_bounds();
+        assert_eq!(
+            vec![0..20],
+            hierarchy.point_ranges_in_bounds(&info, &root_bounds, None)
+        );
+        assert_eq!(
+            vec![8..18],
+            hierarchy.point_ranges_in_bounds(&info, &root_bounds, Some(0))
+        );
+        assert_eq!(
+            vec![0..18],
+            hierarchy.point_ranges_in_bounds(&info, &root_bounds, Some(1))
+        );
+        // A small query in the corner with the minimum coordinates only touches the nodes with x=y=z=0
+        let corner = AABB::from_min_max(Point3::new(2.0, 12.0, 22.0), Point3::new(3.0, 13.0, 23.0));
+        assert_eq!(
+            vec![0..3, 8..20],
+            hierarchy.point_ranges_in_bounds(&info, &corner, None)
+        );
+        assert_eq!(
+            vec![0..3, 8..18],
+            hierarchy.point_ranges_in_bounds(&info, &corner, Some(1))
+        );
+
+        // Pages that are not within the EVLR data are an error
+        let info = CopcInfo::from_bytes(&make_info_vlr_data(0, 128))?;
+        assert!(CopcHierarchy::from_evlr_data(&data, EVLR_DATA_OFFSET, &info).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,197 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use las_rs::raw;
+use pasture_core::containers::{BorrowedMutBuffer, MakeBufferFromLayout, OwningBuffer};
+use pasture_core::layout::PointLayout;
+use pasture_core::math::AABB;
+use pasture_core::meta::Metadata;
+
+use super::{
+    read_candidate_point_ranges, CopcHierarchy, CopcInfo, LASMetadata, LASReadOptions,
+    RawLAZReader, COPC_HIERARCHY_RECORD_ID, COPC_VLR_USER_ID,
+};
+use crate::base::{skip_points_by_seeking, PointReader, SeekToPoint};
+
+/// Size of the header of an EVLR in bytes
+const SIZE_OF_EVLR_HEADER: u64 = 60;
+
+/// Reader for Cloud Optimized Point Clouds (COPC). A COPC file is a LAZ 1.4 file whose points are organized in an
+/// octree, where the points of each octree node are stored in a separate LAZ chunk. The `CopcReader` supports
+/// regular sequential reading through [`PointReader`], as well as spatial queries through [`CopcReader::query`],
+/// which only decompress the chunks of the octree nodes that intersect the query
+pub struct CopcReader<'a, T: Read + Seek + 'a> {
+    raw_reader: RawLAZReader<'a, T>,
+    info: CopcInfo,
+    hierarchy: CopcHierarchy,
+}
+
+impl CopcReader<'static, BufReader<File>> {
+    /// Opens the COPC file at the given `path`
+    ///
+    /// # Errors
+    ///
+    /// If `path` does not exist, cannot be opened or does not point to a valid COPC file, an error is returned
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_path_with_options(path, Default::default())
+    }
+
+    /// Like [`Self::from_path`], but uses the given `options` to determine the default `PointLayout` of the reader
+    pub fn from_path_with_options<P: AsRef<Path>>(
+        path: P,
+        options: LASReadOptions,
+    ) -> Result<Self> {
+        let file = BufReader::new(File::open(path)?);
+        Self::from_read_with_options(file, options)
+    }
+}
+
+impl<'a, T: Read + Seek + 'a> CopcReader<'a, T> {
+    /// Creates a new `CopcReader` from the given `read`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `read` does not contain a valid COPC file, e.g. if it is a regular LAZ file without the
+    /// COPC info VLR
+    pub fn from_read(read: T) -> Result<Self> {
+        Self::from_read_with_options(read, Default::default())
+    }
+
+    /// Like [`Self::from_read`], but uses the given `options` to determine the default `PointLayout` of the reader.
+    /// See [`LASReadOptions`] for more information
+    pub fn from_read_with_options(mut read: T, options: LASReadOptions) -> Result<Self> {
+        // The hierarchy pages reference each other through file offsets, so we need the position of the EVLRs, which
+        // is not part of the parsed `Header`
+        let raw_header = raw::Header::read_from(&mut read)?;
+        read.seek(SeekFrom::Start(0))?;
+        let raw_reader = RawLAZReader::from_read_with_options(read, options)?;
+
+        let metadata = raw_reader.las_metadata();
+        let info = CopcInfo::from_las_metadata(metadata)?
+            .context("File is no COPC file, the COPC info VLR is missing")?;
+
+        let start_of_first_evlr = raw_header
+            .evlr
+            .as_ref()
+            .map(|evlr_info| evlr_info.start_of_first_evlr)
+            .context("COPC file has no EVLRs")?;
+        let mut evlr_offset = start_of_first_evlr;
+        let mut hierarchy = None;
+        for evlr in metadata.evlrs() {
+            let evlr_data_offset = evlr_offset + SIZE_OF_EVLR_HEADER;
+            if evlr.user_id == COPC_VLR_USER_ID && evlr.record_id == COPC_HIERARCHY_RECORD_ID {
+                hierarchy = Some(
+                    CopcHierarchy::from_evlr_data(&evlr.data, evlr_data_offset, &info)
+                        .context("Failed to parse COPC hierarchy")?,
+                );
+                break;
+            }
+            evlr_offset = evlr_data_offset + evlr.data.len() as u64;
+        }
+        let hierarchy = hierarchy.context("COPC hierarchy EVLR is missing")?;
+        if hierarchy.point_count() != metadata.point_count() {
+            bail!(
+                "COPC hierarchy contains {} points, but the LAS header states {} points",
+                hierarchy.point_count(),
+                metadata.point_count()
+            );
+        }
+
+        Ok(Self {
+            raw_reader,
+            info,
+            hierarchy,
+        })
+    }
+
+    /// Returns the contents of the COPC info VLR
+    pub fn copc_info(&self) -> &CopcInfo {
+        &self.info
+    }
+
+    /// Returns the octree hierarchy of the COPC file
+    pub fn hierarchy(&self) -> &CopcHierarchy {
+        &self.hierarchy
+    }
+
+    /// Returns the LAS metadata of the COPC file
+    pub fn las_metadata(&self) -> &LASMetadata {
+        self.raw_reader.las_metadata()
+    }
+
+    /// Reads all points within `bounds` from the octree nodes up to and including `max_level` into a new buffer with
+    /// the default `PointLayout` of this reader. If `max_level` is `None`, all levels are read, otherwise the result
+    /// is a subsampled version of the points within `bounds`, since every level of the octree is a subsample of the
+    /// point cloud with decreasing point spacing. Only the octree nodes that intersect `bounds` are decompressed.
+    /// Afterwards, the reader is positioned at an unspecified point
+    pub fn query<'b, B: OwningBuffer<'b> + MakeBufferFromLayout<'b> + 'b>(
+        &mut self,
+        bounds: &AABB<f64>,
+        max_level: Option<u32>,
+    ) -> Result<B> {
+        let candidate_ranges = self
+            .hierarchy
+            .point_ranges_in_bounds(&self.info, bounds, max_level);
+        let mut points = B::new_from_layout(self.raw_reader.get_default_point_layout().clone());
+        read_candidate_point_ranges(
+            &mut self.raw_reader,
+            &candidate_ranges,
+            &mut points,
+            |reader, chunk, count| reader.read_into_filtered(chunk, count, bounds),
+        )?;
+        Ok(points)
+    }
+}
+
+impl<'a, T: Read + Seek + 'a> PointReader for CopcReader<'a, T> {
+    fn read_into<'b, 'c, B: BorrowedMutBuffer<'b>>(
+        &mut self,
+        point_buffer: &'c mut B,
+        count: usize,
+    ) -> Result<usize>
+    where
+        'b: 'c,
+    {
+        self.raw_reader.read_into(point_buffer, count)
+    }
+
+    fn get_metadata(&self) -> &dyn Metadata {
+        self.raw_reader.get_metadata()
+    }
+
+    fn get_default_point_layout(&self) -> &PointLayout {
+        self.raw_reader.get_default_point_layout()
+    }
+
+    fn skip(&mut self, count: usize) -> Result<usize> {
+        skip_points_by_seeking(self, count)
+    }
+
+    fn point_index(&self) -> Option<usize> {
+        self.raw_reader.point_index()
+    }
+
+    fn point_count(&self) -> Option<usize> {
+        self.raw_reader.point_count()
+    }
+}
+
+impl<'a, T: Read + Seek + 'a> SeekToPoint for CopcReader<'a, T> {
+    fn seek_point(&mut self, position: SeekFrom) -> Result<usize> {
+        self.raw_reader.seek_point(position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::las::get_test_laz_path;
+
+    #[test]
+    fn test_copc_reader_rejects_regular_laz_files() {
+        let read = BufReader::new(File::open(get_test_laz_path(6)).unwrap());
+        assert!(CopcReader::from_read(read).is_err());
+    }
+}
@@ -0,0 +1,265 @@
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use las_rs::raw;
+use pasture_core::math::AABB;
+use pasture_core::nalgebra::Point3;
+
+use super::{point_count_from_raw_header, LASReadOptions, LASReader, MultiFileLASReader};
+
+/// Information about a single file of a LAS dataset, read from the header of the file
+#[derive(Debug, Clone, PartialEq)]
+pub struct LASDatasetFile {
+    pub path: PathBuf,
+    pub point_count: usize,
+    pub bounds: AABB<f64>,
+    pub point_format: u8,
+}
+
+/// Summary of a LAS dataset, i.e. a point cloud that is split into multiple LAS/LAZ files, as returned by
+/// [`open_dataset`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct LASDatasetSummary {
+    /// All files of the dataset, in the order in which they are read
+    pub files: Vec<LASDatasetFile>,
+    /// Total number of points in all files
+    pub point_count: usize,
+    /// Union of the bounds of all files
+    pub bounds: AABB<f64>,
+    /// All point formats that are used by the files of the dataset
+    pub point_formats: BTreeSet<u8>,
+}
+
+/// Opens all LAS/LAZ files that match `pattern` as a single point cloud. `pattern` is either a directory, in which
+/// case all files with the extension `.las` or `.laz` in this directory are opened, or a path whose file name contains
+/// the wildcards `*` (any number of characters) and `?` (a single character), e.g. `path/to/tiles/*.laz`. Wildcards
+/// are only supported in the file name, not in the directories of `pattern`. The matching files are read in
+/// lexicographical order of their paths.
+///
+/// Only the headers of the files are read up front, which is enough to compute the returned [`LASDatasetSummary`].
+/// The files are opened by the returned [`MultiFileLASReader`] once reading reaches them
+///
+/// # Errors
+///
+/// Returns an error if no file matches `pattern`, or if the header of any matching file can't be read
+pub fn open_dataset<P: AsRef<Path>>(
+    pattern: P,
+) -> Result<(
+    MultiFileLASReader<'static, BufReader<File>>,
+    LASDatasetSummary,
+)> {
+    open_dataset_with_options(pattern, Default::default())
+}
+
+/// Like [`open_dataset`], but uses the given `options` for reading all files of the dataset
+pub fn open_dataset_with_options<P: AsRef<Path>>(
+    pattern: P,
+    options: LASReadOptions,
+) -> Result<(
+    MultiFileLASReader<'static, BufReader<File>>,
+    LASDatasetSummary,
+)> {
+    let paths = expand_dataset_pattern(pattern.as_ref())?;
+    let files = paths
+        .into_iter()
+        .map(read_dataset_file)
+        .collect::<Result<Vec<_>>>()?;
+    let summary = LASDatasetSummary {
+        point_count: files.iter().map(|file| file.point_count).sum(),
+        bounds: files
+            .iter()
+            .map(|file| file.bounds)
+            .reduce(|a, b| AABB::union(&a, &b))
+            .expect("There is at least one file"),
+        point_formats: files.iter().map(|file| file.point_format).collect(),
+        files,
+    };
+
+    let paths = summary
+        .files
+        .iter()
+        .map(|file| file.path.clone())
+        .collect::<Vec<_>>();
+    let file_infos = summary
+        .files
+        .iter()
+        .map(|file| (file.point_count, file.bounds))
+        .collect::<Vec<_>>();
+    let reader = MultiFileLASReader::from_lazily_opened_files(
+        &file_infos,
+        Box::new(move |file_index| {
+            let path = &paths[file_index];
+            LASReader::from_path_with_options(path, options.clone())
+                .with_context(|| format!("Could not open LAS file {}", path.display()))
+        }),
+    )?;
+    Ok((reader, summary))
+}
+
+fn read_dataset_file(path: PathBuf) -> Result<LASDatasetFile> {
+    let raw_header = File::open(&path)
+        .map_err(anyhow::Error::from)
+        .and_then(|file| Ok(raw::Header::read_from(BufReader::new(file))?))
+        .with_context(|| format!("Could not read LAS header of file {}", path.display()))?;
+    let bounds = AABB::from_min_max_unchecked(
+        Point3::new(raw_header.min_x, raw_header.min_y, raw_header.min_z),
+        Point3::new(raw_header.max_x, raw_header.max_y, raw_header.max_z),
+    );
+    Ok(LASDatasetFile {
+        point_count: point_count_from_raw_header(&raw_header) as usize,
+        bounds,
+        // The upper two bits of the point format are used by LAZ to mark compressed files
+        point_format: raw_header.point_data_record_format & 0b0011_1111,
+        path,
+    })
+}
+
+/// Returns the sorted paths of all LAS/LAZ files that match `pattern`, see [`open_dataset`]
+fn expand_dataset_pattern(pattern: &Path) -> Result<Vec<PathBuf>> {
+    let (directory, file_name_pattern) = if pattern.is_dir() {
+        (pattern, None)
+    } else {
+        let directory = match pattern.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let file_name_pattern = pattern
+            .file_name()
+            .and_then(|file_name| file_name.to_str())
+            .with_context(|| format!("Invalid dataset pattern {}", pattern.display()))?;
+        (directory, Some(file_name_pattern))
+    };
+
+    let mut paths = vec![];
+    let entries = std::fs::read_dir(directory)
+        .with_context(|| format!("Could not read directory {}", directory.display()))?;
+    for entry in entries {
+        let path = entry
+            .with_context(|| format!("Could not read directory {}", directory.display()))?
+            .path();
+        if !path.is_file() {
+            continue;
+        }
+        let file_name = match path.file_name().and_then(|file_name| file_name.to_str()) {
+            Some(file_name) => file_name,
+            None => continue,
+        };
+        let is_match = match file_name_pattern {
+            Some(file_name_pattern) => matches_wildcard_pattern(file_name_pattern, file_name),
+            None => matches!(
+                path.extension().and_then(|extension| extension.to_str()),
+                Some("las") | Some("laz")
+            ),
+        };
+        if is_match {
+            paths.push(path);
+        }
+    }
+
+    if paths.is_empty() {
+        bail!("No LAS files match the pattern {}", pattern.display());
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Does `name` match `pattern`, where `*` in `pattern` matches any number of characters and `?` matches a single
+/// character?
+fn matches_wildcard_pattern(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    let mut pattern_index = 0;
+    let mut name_index = 0;
+    // Position of the last `*` in `pattern` and of the character in `name` that it is currently matched up to, used
+    // for backtracking
+    let mut last_wildcard: Option<(usize, usize)> = None;
+    while name_index < name.len() {
+        match pattern.get(pattern_index) {
+            Some('*') => {
+                last_wildcard = Some((pattern_index, name_index));
+                pattern_index += 1;
+            }
+            Some(c) if *c == '?' || *c == name[name_index] => {
+                pattern_index += 1;
+                name_index += 1;
+            }
+            _ => match last_wildcard {
+                Some((wildcard_pattern_index, wildcard_name_index)) => {
+                    pattern_index = wildcard_pattern_index + 1;
+                    name_index = wildcard_name_index + 1;
+                    last_wildcard = Some((wildcard_pattern_index, name_index));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[pattern_index..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::SeekFrom;
+
+    use pasture_core::containers::{BorrowedBuffer, VectorBuffer};
+
+    use super::*;
+    use crate::base::{PointReader, SeekToPoint};
+    use crate::las::{test_data_bounds, test_data_point_count};
+
+    fn test_resources_dir() -> PathBuf {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("resources/test");
+        path
+    }
+
+    #[test]
+    fn test_matches_wildcard_pattern() {
+        assert!(matches_wildcard_pattern("*.las", "tile.las"));
+        assert!(matches_wildcard_pattern("*.las", ".las"));
+        assert!(!matches_wildcard_pattern("*.las", "tile.laz"));
+        assert!(matches_wildcard_pattern("tile_?.la?", "tile_1.laz"));
+        assert!(!matches_wildcard_pattern("tile_?.las", "tile_10.las"));
+        assert!(matches_wildcard_pattern("*_*.las", "tile_1_2.las"));
+        assert!(matches_wildcard_pattern("tile.las", "tile.las"));
+        assert!(!matches_wildcard_pattern("tile.las", "tile.las.bak"));
+    }
+
+    #[test]
+    fn test_open_dataset() -> Result<()> {
+        let pattern = test_resources_dir().join("10_points_format_?.las");
+        let (mut reader, summary) = open_dataset(&pattern)?;
+
+        let file_count = 10;
+        assert_eq!(file_count, summary.files.len());
+        assert_eq!(file_count * test_data_point_count(), summary.point_count);
+        assert_eq!(test_data_bounds(), summary.bounds);
+        assert_eq!((0..10).collect::<BTreeSet<u8>>(), summary.point_formats);
+
+        // Only the file that is currently read is open
+        assert!(reader.file_reader(0).is_some());
+        assert!(reader.file_reader(1).is_none());
+
+        let points = reader.read_all::<VectorBuffer>()?;
+        assert_eq!(summary.point_count, points.len());
+        assert!(reader.file_reader(0).is_none());
+        assert!(reader.file_reader(file_count - 1).is_some());
+
+        reader.seek_point(SeekFrom::Start(0))?;
+        assert!(reader.file_reader(0).is_some());
+        assert_eq!(
+            test_data_point_count(),
+            reader.read::<VectorBuffer>(test_data_point_count())?.len()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_dataset_without_matching_files() {
+        let pattern = test_resources_dir().join("*.does_not_exist");
+        assert!(open_dataset(pattern).is_err());
+    }
+}
@@ -0,0 +1,534 @@
+use std::{
+    convert::{TryFrom, TryInto},
+    io::Write,
+};
+
+use anyhow::{bail, Context, Result};
+use las_rs::{point::Format, Builder, Vlr};
+use pasture_core::layout::{
+    conversion::{get_generic_converter, AttributeConversionFn},
+    PointAttributeDataType, PointAttributeDefinition, PointLayout,
+};
+
+use super::{
+    point_layout_from_las_point_format, ExtraBytesDataType, ExtraBytesEntry,
+    ExtraBytesEntryBuilder, ExtraBytesVlr, KNOWN_VLR_USER_ID,
+};
+
+/// Maximum length of the name of an extra bytes entry, in bytes
+const MAX_EXTRA_BYTES_NAME_LENGTH: usize = 32;
+
+/// Name of the attribute that LAS readers use for extra bytes that are not described by the Extra Bytes VLR
+const UNDESCRIBED_EXTRA_BYTES_ATTRIBUTE_NAME: &str = "UndescribedExtraBytes";
+
+/// What [`LASWriterBuilder::from_point_layout`](super::LASWriterBuilder::from_point_layout) does with attributes of
+/// the `PointLayout` that no LAS point format stores (see [`is_known_las_attribute`](super::is_known_las_attribute))
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum UnknownAttributeHandling {
+    /// The attributes are not written
+    #[default]
+    Ignore,
+    /// The attributes are written as extra bytes, using [`default_extra_bytes_entry`] for each of them
+    ExtraBytes,
+    /// Creating the writer fails with an error that names the first unknown attribute
+    Error,
+}
+
+/// Returns the default `ExtraBytesEntry` for storing the given `attribute` in the extra bytes of LAS point records.
+/// The entry has the name and datatype of `attribute` and uses neither scale nor offset
+///
+/// # Errors
+///
+/// Returns an error if the datatype of `attribute` is not a scalar numeric type, which are the only types that the
+/// LAS 1.4 specification supports for extra bytes, or if the name of `attribute` is longer than 32 bytes or not ASCII
+pub fn default_extra_bytes_entry(attribute: &PointAttributeDefinition) -> Result<ExtraBytesEntry> {
+    let data_type = ExtraBytesDataType::try_from(attribute.datatype()).with_context(|| {
+        format!(
+            "Attribute {} can't be written as LAS extra bytes. Consider splitting vector attributes into one attribute per component",
+            attribute
+        )
+    })?;
+    if !attribute.name().is_ascii() || attribute.name().len() > MAX_EXTRA_BYTES_NAME_LENGTH {
+        bail!(
+            "Attribute {} can't be written as LAS extra bytes, as LAS only supports ASCII names with at most {} bytes",
+            attribute,
+            MAX_EXTRA_BYTES_NAME_LENGTH
+        );
+    }
+    Ok(ExtraBytesEntryBuilder::new(data_type, attribute.name().to_owned(), String::new()).build())
+}
+
+/// Mapping from point attributes to the extra bytes of LAS point records. This is used to write point attributes that
+/// are not part of the LAS point format, e.g. normals or a height above ground. Each attribute is described by an
+/// `ExtraBytesEntry`, which is written into the Extra Bytes VLR of the LAS file. When writing points, the value of each
+/// entry is taken from the point attribute with the same name as the entry
+#[derive(Clone, Debug, Default)]
+pub struct ExtraBytesMapping {
+    entries: Vec<ExtraBytesEntry>,
+}
+
+impl ExtraBytesMapping {
+    /// Creates an empty `ExtraBytesMapping`
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Creates an `ExtraBytesMapping` for all attributes in `point_layout` that are not part of the given LAS
+    /// `point_format`, using [`default_extra_bytes_entry`] for each of them
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of these attributes can't be stored in LAS extra bytes
+    pub fn for_point_layout(point_layout: &PointLayout, point_format: &Format) -> Result<Self> {
+        let las_layout = point_layout_from_las_point_format(point_format, false)?;
+        point_layout
+            .attributes()
+            .filter(|attribute| !las_layout.has_attribute_with_name(attribute.name()))
+            .try_fold(Self::new(), |mapping, attribute| {
+                mapping.with_attribute(attribute.attribute_definition())
+            })
+    }
+
+    /// Adds the given `attribute` with the default `ExtraBytesEntry` (see [`default_extra_bytes_entry`]) to this
+    /// mapping
+    pub fn with_attribute(self, attribute: &PointAttributeDefinition) -> Result<Self> {
+        let entry = default_extra_bytes_entry(attribute)?;
+        Ok(self.with_entry(entry))
+    }
+
+    /// Adds the given `entry` to this mapping. Use this to customize how an attribute is stored, e.g. with a scale
+    /// and offset or with a no-data value for points that don't have the attribute
+    pub fn with_entry(mut self, entry: ExtraBytesEntry) -> Self {
+        self.entries.push(entry);
+        self
+    }
+
+    /// Returns all entries of this mapping, in the order in which they are stored in the point records
+    pub fn entries(&self) -> &[ExtraBytesEntry] {
+        &self.entries
+    }
+
+    /// Returns `true` if this mapping contains no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the number of extra bytes per point record that this mapping requires
+    pub fn size_in_bytes(&self) -> usize {
+        self.entries
+            .iter()
+            .map(|entry| entry.data_type().size().unwrap_or_default())
+            .sum()
+    }
+
+    /// Adds the extra bytes of this mapping to the given `header_builder`, i.e. increases the number of extra bytes of
+    /// its point format and adds the Extra Bytes VLR. Does nothing if this mapping is empty
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `header_builder` already contains extra bytes
+    pub fn add_to_header(&self, header_builder: &mut Builder) -> Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        let has_extra_bytes_vlr = header_builder.vlrs.iter().any(|vlr| {
+            vlr.user_id == KNOWN_VLR_USER_ID && vlr.record_id == ExtraBytesVlr::RECORD_ID
+        });
+        if header_builder.point_format.extra_bytes != 0 || has_extra_bytes_vlr {
+            bail!("The LAS header already contains extra bytes");
+        }
+
+        let vlr: Vlr = (&self.entries.iter().cloned().collect::<ExtraBytesVlr>()).try_into()?;
+        header_builder.point_format.extra_bytes = self
+            .size_in_bytes()
+            .try_into()
+            .context("Too many extra bytes per point record")?;
+        header_builder.vlrs.push(vlr);
+        Ok(())
+    }
+}
+
+/// How the value of a single extra bytes entry is obtained from the source point attribute
+enum ExtraBytesSource {
+    /// The source attribute has the datatype of the entry, so its bytes are copied
+    Copy,
+    /// The source attribute is converted into the datatype of the entry
+    Convert(AttributeConversionFn),
+    /// The source attribute is converted to `f64`, the inverse of the scale and offset of the entry is applied and the
+    /// result is converted into the datatype of the entry
+    Scaled {
+        to_f64: Option<AttributeConversionFn>,
+        from_f64: Option<AttributeConversionFn>,
+        round: bool,
+        scale: f64,
+        offset: f64,
+    },
+}
+
+struct ExtraBytesField {
+    size: usize,
+    /// Offset and size of the source attribute within a point, together with the way the value is obtained from it.
+    /// `None` if the source layout does not contain the attribute
+    source: Option<(usize, usize, ExtraBytesSource)>,
+    /// Value for points whose layout does not contain the attribute
+    default_value: Vec<u8>,
+}
+
+/// Writes the extra bytes of LAS point records for points in an arbitrary `PointLayout`. Each extra bytes entry is
+/// filled from the point attribute with the same name, converting it into the datatype of the entry and applying the
+/// inverse of the scale and offset of the entry. If there is no matching attribute, the no-data value of the entry is
+/// written, or zeros if the entry has no no-data value
+pub(crate) struct ExtraBytesWriter {
+    fields: Vec<ExtraBytesField>,
+}
+
+impl ExtraBytesWriter {
+    /// Creates a new `ExtraBytesWriter` for the given extra bytes `entries` and points in the `source_layout`.
+    /// `num_extra_bytes` is the total number of extra bytes per point record, which can be larger than the size of all
+    /// `entries`. These undescribed extra bytes are written from the `UndescribedExtraBytes` attribute, if it exists
+    pub fn new(
+        entries: &[ExtraBytesEntry],
+        num_extra_bytes: usize,
+        source_layout: &PointLayout,
+    ) -> Result<Self> {
+        let mut fields = entries
+            .iter()
+            .map(|entry| Self::field_for_entry(entry, source_layout))
+            .collect::<Result<Vec<_>>>()?;
+
+        let num_described_bytes = fields.iter().map(|field| field.size).sum::<usize>();
+        let num_undescribed_bytes = num_extra_bytes.saturating_sub(num_described_bytes);
+        if num_undescribed_bytes > 0 {
+            let source = source_layout
+                .get_attribute_by_name(UNDESCRIBED_EXTRA_BYTES_ATTRIBUTE_NAME)
+                .filter(|attribute| {
+                    attribute.datatype()
+                        == PointAttributeDataType::ByteArray(num_undescribed_bytes as u64)
+                })
+                .map(|attribute| {
+                    (
+                        attribute.offset() as usize,
+                        num_undescribed_bytes,
+                        ExtraBytesSource::Copy,
+                    )
+                });
+            fields.push(ExtraBytesField {
+                size: num_undescribed_bytes,
+                source,
+                default_value: vec![0; num_undescribed_bytes],
+            });
+        }
+
+        Ok(Self { fields })
+    }
+
+    fn field_for_entry(
+        entry: &ExtraBytesEntry,
+        source_layout: &PointLayout,
+    ) -> Result<ExtraBytesField> {
+        let size = entry.data_type().size().unwrap_or_default();
+        let default_value = if entry.options().no_data_is_relevant() {
+            entry.no_data_value_raw()[..size].to_vec()
+        } else {
+            vec![0; size]
+        };
+        let source_attribute = match source_layout.get_attribute_by_name(entry.name()) {
+            Some(attribute) => attribute,
+            None => {
+                return Ok(ExtraBytesField {
+                    size,
+                    source: None,
+                    default_value,
+                })
+            }
+        };
+
+        let source_datatype = source_attribute.datatype();
+        let target_datatype: PointAttributeDataType = entry
+            .data_type()
+            .try_into()
+            .with_context(|| format!("Can't write extra bytes {}", entry.name()))?;
+        let get_converter = |from_type: PointAttributeDataType,
+                             to_type: PointAttributeDataType|
+         -> Result<Option<AttributeConversionFn>> {
+            if from_type == to_type {
+                return Ok(None);
+            }
+            get_generic_converter(from_type, to_type)
+                .map(Some)
+                .with_context(|| {
+                    format!(
+                        "Can't convert attribute {} from datatype {} into the datatype {} of its LAS extra bytes",
+                        entry.name(),
+                        source_datatype,
+                        target_datatype
+                    )
+                })
+        };
+
+        let source = if entry.is_scaled() {
+            ExtraBytesSource::Scaled {
+                to_f64: get_converter(source_datatype, PointAttributeDataType::F64)?,
+                from_f64: get_converter(PointAttributeDataType::F64, target_datatype)?,
+                round: !entry.data_type().is_floating_point(),
+                scale: entry.scale().unwrap_or(1.0),
+                offset: entry.offset().unwrap_or(0.0),
+            }
+        } else {
+            match get_converter(source_datatype, target_datatype)? {
+                Some(converter) => ExtraBytesSource::Convert(converter),
+                None => ExtraBytesSource::Copy,
+            }
+        };
+        Ok(ExtraBytesField {
+            size,
+            source: Some((
+                source_attribute.offset() as usize,
+                source_attribute.size() as usize,
+                source,
+            )),
+            default_value,
+        })
+    }
+
+    /// Writes the extra bytes for the given `point`, which is the memory of a single point in the source layout of
+    /// this `ExtraBytesWriter`
+    pub fn write_extra_bytes<W: Write>(&self, point: &[u8], mut writer: W) -> Result<()> {
+        let mut value = [0u8; 8];
+        for field in &self.fields {
+            let (source_offset, source_size, source) = match &field.source {
+                Some(source) => source,
+                None => {
+                    writer.write_all(&field.default_value)?;
+                    continue;
+                }
+            };
+            let source_bytes = &point[*source_offset..(*source_offset + *source_size)];
+            match source {
+                ExtraBytesSource::Copy => writer.write_all(source_bytes)?,
+                ExtraBytesSource::Convert(converter) => {
+                    unsafe {
+                        converter(source_bytes, &mut value[..field.size]);
+                    }
+                    writer.write_all(&value[..field.size])?;
+                }
+                ExtraBytesSource::Scaled {
+                    to_f64,
+                    from_f64,
+                    round,
+                    scale,
+                    offset,
+                } => {
+                    let mut world_value = 0.0f64;
+                    match to_f64 {
+                        Some(converter) => unsafe {
+                            converter(source_bytes, bytemuck::bytes_of_mut(&mut world_value));
+                        },
+                        None => {
+                            bytemuck::bytes_of_mut(&mut world_value).copy_from_slice(source_bytes)
+                        }
+                    }
+                    let mut raw_value = (world_value - offset) / scale;
+                    if *round {
+                        raw_value = raw_value.round();
+                    }
+                    match from_f64 {
+                        Some(converter) => unsafe {
+                            converter(bytemuck::bytes_of(&raw_value), &mut value[..field.size]);
+                        },
+                        None => value.copy_from_slice(bytemuck::bytes_of(&raw_value)),
+                    }
+                    writer.write_all(&value[..field.size])?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use pasture_core::{
+        containers::{BorrowedBuffer, VectorBuffer},
+        layout::{attributes::POSITION_3D, PointType},
+        nalgebra::Vector3,
+    };
+    use pasture_derive::PointType;
+
+    use super::*;
+    use crate::{
+        base::{PointReader, PointWriter},
+        las::{LASReader, LASWriter},
+    };
+
+    #[repr(C, packed)]
+    #[derive(Debug, Clone, Copy, PointType, bytemuck::AnyBitPattern, bytemuck::NoUninit)]
+    struct PointWithNormals {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+        #[pasture(BUILTIN_INTENSITY)]
+        pub intensity: u16,
+        #[pasture(attribute = "NormalX")]
+        pub normal_x: f32,
+        #[pasture(attribute = "HeightAboveGround")]
+        pub height_above_ground: f64,
+        #[pasture(attribute = "SegmentID")]
+        pub segment_id: u32,
+    }
+
+    fn test_points() -> Vec<PointWithNormals> {
+        (0..10)
+            .map(|index| PointWithNormals {
+                position: Vector3::new(index as f64, 2.0 * index as f64, 3.0 * index as f64),
+                intensity: index as u16 * 100,
+                normal_x: index as f32 / 10.0,
+                height_above_ground: 1.25 * index as f64,
+                segment_id: 1000 + index as u32,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_default_extra_bytes_entry() -> Result<()> {
+        let entry = default_extra_bytes_entry(&PointAttributeDefinition::custom(
+            "NormalX".into(),
+            PointAttributeDataType::F32,
+        ))?;
+        assert_eq!("NormalX", entry.name());
+        assert!(matches!(entry.data_type(), ExtraBytesDataType::F32));
+        assert!(!entry.is_scaled());
+
+        let vector_error = default_extra_bytes_entry(&POSITION_3D)
+            .expect_err("Vector attributes can't be stored in extra bytes");
+        assert!(format!("{:#}", vector_error).contains("only scalar numeric datatypes"));
+
+        let long_name = "A".repeat(33);
+        assert!(default_extra_bytes_entry(&PointAttributeDefinition::custom(
+            long_name.into(),
+            PointAttributeDataType::U8
+        ))
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_extra_bytes_mapping_for_point_layout() -> Result<()> {
+        let mapping =
+            ExtraBytesMapping::for_point_layout(&PointWithNormals::layout(), &Format::new(0)?)?;
+        let names = mapping
+            .entries()
+            .iter()
+            .map(|entry| entry.name())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["NormalX", "HeightAboveGround", "SegmentID"], names);
+        assert_eq!(16, mapping.size_in_bytes());
+        Ok(())
+    }
+
+    fn check_write_and_read_extra_bytes(is_compressed: bool) -> Result<()> {
+        let source_points = test_points();
+        let source_buffer = source_points.iter().copied().collect::<VectorBuffer>();
+
+        // Store the height above ground in centimeters as an i32 and the segment ID as u64, to test the conversions
+        let height_entry = ExtraBytesEntryBuilder::new(
+            ExtraBytesDataType::I32,
+            "HeightAboveGround".into(),
+            "Height above ground".into(),
+        )
+        .with_scale(0.01)
+        .build();
+        let segment_entry =
+            ExtraBytesEntryBuilder::new(ExtraBytesDataType::U64, "SegmentID".into(), String::new())
+                .build();
+        let mapping = ExtraBytesMapping::new()
+            .with_attribute(&PointAttributeDefinition::custom(
+                "NormalX".into(),
+                PointAttributeDataType::F32,
+            ))?
+            .with_entry(height_entry)
+            .with_entry(segment_entry);
+
+        let mut writer = LASWriter::from_writer_and_point_format_with_extra_bytes(
+            Cursor::new(Vec::new()),
+            Format::new(1)?,
+            &mapping,
+            is_compressed,
+        )?;
+        writer.write(&source_buffer)?;
+        let las_data = writer.into_inner()?.into_inner();
+
+        let mut reader = LASReader::from_read(Cursor::new(las_data), is_compressed, false)?;
+        let extra_bytes_vlr = reader
+            .las_metadata()
+            .extra_bytes_vlr()
+            .expect("Extra Bytes VLR was written");
+        assert_eq!(3, extra_bytes_vlr.entries().len());
+        assert_eq!(Some(0.01), extra_bytes_vlr.entries()[1].scale());
+
+        let read_points = reader.read::<VectorBuffer>(source_points.len())?;
+        let normal_x =
+            PointAttributeDefinition::custom("NormalX".into(), PointAttributeDataType::F32);
+        let height = PointAttributeDefinition::custom(
+            "HeightAboveGround".into(),
+            PointAttributeDataType::F64,
+        );
+        let segment_id =
+            PointAttributeDefinition::custom("SegmentID".into(), PointAttributeDataType::U64);
+        for (index, expected) in source_points.iter().enumerate() {
+            let expected_normal_x = expected.normal_x;
+            let expected_height = expected.height_above_ground;
+            let expected_segment_id = expected.segment_id as u64;
+            assert_eq!(
+                expected_normal_x,
+                read_points.view_attribute::<f32>(&normal_x).at(index)
+            );
+            assert!(
+                (expected_height - read_points.view_attribute::<f64>(&height).at(index)).abs()
+                    < 1e-9
+            );
+            assert_eq!(
+                expected_segment_id,
+                read_points.view_attribute::<u64>(&segment_id).at(index)
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_and_read_extra_bytes() -> Result<()> {
+        check_write_and_read_extra_bytes(false)
+    }
+
+    #[cfg(feature = "laz")]
+    #[test]
+    fn test_write_and_read_compressed_extra_bytes() -> Result<()> {
+        check_write_and_read_extra_bytes(true)
+    }
+
+    #[test]
+    fn test_missing_extra_bytes_attributes_use_no_data_value() -> Result<()> {
+        let mut no_data_value = [0; 8];
+        no_data_value[..4].copy_from_slice(&(-9999.0f32).to_le_bytes());
+        let entry = ExtraBytesEntryBuilder::new(
+            ExtraBytesDataType::F32,
+            "Reflectance".into(),
+            String::new(),
+        )
+        .no_data_value(no_data_value)
+        .build();
+        let source_layout = PointWithNormals::layout();
+        let extra_bytes_writer = ExtraBytesWriter::new(&[entry], 6, &source_layout)?;
+
+        let point = vec![0; source_layout.size_of_point_entry() as usize];
+        let mut extra_bytes = vec![];
+        extra_bytes_writer.write_extra_bytes(&point, &mut extra_bytes)?;
+        // No-data value for the missing attribute, followed by two undescribed bytes
+        assert_eq!(6, extra_bytes.len());
+        assert_eq!((-9999.0f32).to_le_bytes(), extra_bytes[..4]);
+        assert_eq!([0, 0], extra_bytes[4..]);
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use std::{borrow::Cow, collections::HashSet};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use las::point::Format;
 use lazy_static::lazy_static;
 use pasture_core::{
@@ -8,10 +8,10 @@ use pasture_core::{
     layout::{
         attributes::{
             CLASSIFICATION, CLASSIFICATION_FLAGS, COLOR_RGB, EDGE_OF_FLIGHT_LINE, GPS_TIME,
-            INTENSITY, NIR, NUMBER_OF_RETURNS, POINT_SOURCE_ID, POSITION_3D, RETURN_NUMBER,
-            RETURN_POINT_WAVEFORM_LOCATION, SCANNER_CHANNEL, SCAN_ANGLE, SCAN_ANGLE_RANK,
-            SCAN_DIRECTION_FLAG, USER_DATA, WAVEFORM_DATA_OFFSET, WAVEFORM_PACKET_SIZE,
-            WAVEFORM_PARAMETERS, WAVE_PACKET_DESCRIPTOR_INDEX,
+            INTENSITY, NIR, NUMBER_OF_RETURNS, OVERLAP, POINT_SOURCE_ID, POSITION_3D,
+            RETURN_NUMBER, RETURN_POINT_WAVEFORM_LOCATION, SCANNER_CHANNEL, SCAN_ANGLE,
+            SCAN_ANGLE_RANK, SCAN_DIRECTION_FLAG, USER_DATA, WAVEFORM_DATA_OFFSET,
+            WAVEFORM_PACKET_SIZE, WAVEFORM_PARAMETERS, WAVE_PACKET_DESCRIPTOR_INDEX,
         },
         FieldAlignment, PointAttributeDataType, PointAttributeDefinition, PointLayout, PointType,
     },
@@ -47,6 +47,11 @@ pub const ATTRIBUTE_LOCAL_LAS_POSITION: PointAttributeDefinition = PointAttribut
     PointAttributeDataType::Vec3i32,
 );
 
+/// Scan angle in degrees, stored as `f32` values. The LAS readers support this attribute for all point formats, converting
+/// from the whole degrees of the point formats 0-5 or the 0.006 degree increments of the point formats 6-10
+pub const ATTRIBUTE_SCAN_ANGLE_DEGREES: PointAttributeDefinition =
+    PointAttributeDefinition::custom(Cow::Borrowed("ScanAngle"), PointAttributeDataType::F32);
+
 /// Returns the default `PointLayout` for the given LAS point format. If `exact_binary_representation` is true, the
 /// layout mirrors the binary layout of the point records in the LAS format, as defined by the [LAS specification](http://www.asprs.org/wp-content/uploads/2019/03/LAS_1_4_r14.pdf). This means:
 /// - Positions are stored as `Vector3<i32>` in local space, using the custom [`ATTRIBUTE_LOCAL_LAS_POSITION`] instead of
@@ -124,10 +129,27 @@ pub fn point_layout_from_las_point_format(
     }
 }
 
+/// Returns the `PointLayout` that matches the binary point records of the given LAS point `format` byte for byte. This
+/// is the same as calling [`point_layout_from_las_point_format`] with `exact_binary_representation` set to `true`.
+/// Reading into a buffer with this layout skips all parsing and copies the point records as they are stored in the
+/// file, which is the fastest way to move points from one LAS file to another. This requires that `format` has no extra
+/// bytes, for formats with extra bytes use [`point_layout_from_las_metadata`] instead
+///
+/// # Errors
+///
+/// Returns an error if `format` is an invalid LAS point format, or if the format contains extra bytes.
+pub fn point_layout_from_las_point_format_raw(format: &Format) -> Result<PointLayout> {
+    point_layout_from_las_point_format(format, true)
+}
+
 /// Returns a matching `PointLayout` for the given `LASMetadata`. This function is similar to `point_layout_from_format`, but
 /// also supports extra bytes if the given `LASMetadata` contains an Extra Bytes VLR. If it does not, but the point format in
 /// the `LASMetadata` indicates that extra bytes are present, the extra bytes will be included in the `PointLayout` as raw bytes
 ///
+/// Each entry in the Extra Bytes VLR becomes a separate attribute with the name of the entry. If `exact_binary_representation`
+/// is `false`, entries that use a scale and/or offset are represented by `F64` attributes that store the scaled values, otherwise
+/// the raw datatype of the entry is used
+///
 /// # Errors
 ///
 /// Returns an error if `format` is an invalid LAS point format, or if the format contains extra bytes.
@@ -146,15 +168,26 @@ pub fn point_layout_from_las_metadata(
         .map(|vlr| {
             vlr.entries()
                 .iter()
-                .map(|entry| entry.get_point_attribute())
+                .map(|entry| {
+                    if exact_binary_representation {
+                        entry.get_point_attribute()
+                    } else {
+                        entry.get_scaled_point_attribute()
+                    }
+                })
                 .collect::<Result<Vec<_>>>()
         })
         .transpose()?
         .unwrap_or_default();
-    let num_described_bytes = extra_byte_attributes
-        .iter()
-        .map(|attribute| attribute.size() as usize)
-        .sum::<usize>();
+    let num_described_bytes = las_metadata
+        .extra_bytes_vlr()
+        .map(|vlr| {
+            vlr.entries()
+                .iter()
+                .map(|entry| entry.data_type().size().unwrap_or_default())
+                .sum::<usize>()
+        })
+        .unwrap_or_default();
 
     // Add the extra bytes attributes with a 1-byte alignment, because the base LAS point types are all tightly packed
     // Currently, the RawLASReader and RawLAZReader both rely on this fact when reading chunks in the default layout
@@ -174,7 +207,7 @@ pub fn point_layout_from_las_metadata(
         base_layout.add_attribute(
             PointAttributeDefinition::custom(
                 Cow::Borrowed("UndescribedExtraBytes"),
-                PointAttributeDataType::ByteArray(num_described_bytes as u64),
+                PointAttributeDataType::ByteArray(num_undescribed_bytes as u64),
             ),
             FieldAlignment::Packed(1),
         );
@@ -183,6 +216,41 @@ pub fn point_layout_from_las_metadata(
     Ok(base_layout)
 }
 
+/// Returns a copy of the given LAS `layout` where the `POSITION_3D` attribute is stored as `Vector3<i32>` instead of
+/// `Vector3<f64>`. Reading points in this layout yields the positions in the local integer coordinates of the LAS file,
+/// without applying the scale and offset from the LAS header. All attributes use a 1-byte alignment, just like the
+/// default LAS layouts
+pub fn point_layout_with_local_las_positions(layout: &PointLayout) -> PointLayout {
+    let mut local_layout = PointLayout::default();
+    for attribute in layout.attributes() {
+        let attribute_definition = if attribute.name() == POSITION_3D.name() {
+            POSITION_3D.with_custom_datatype(PointAttributeDataType::Vec3i32)
+        } else {
+            attribute.attribute_definition().clone()
+        };
+        local_layout.add_attribute(attribute_definition, FieldAlignment::Packed(1));
+    }
+    local_layout
+}
+
+/// Returns a copy of the given LAS `layout` where the `SCAN_ANGLE_RANK` or `SCAN_ANGLE` attribute is replaced by
+/// [`ATTRIBUTE_SCAN_ANGLE_DEGREES`]. Reading points in this layout yields the scan angles of all point formats in
+/// degrees. All attributes use a 1-byte alignment, just like the default LAS layouts
+pub fn point_layout_with_scan_angle_in_degrees(layout: &PointLayout) -> PointLayout {
+    let mut degrees_layout = PointLayout::default();
+    for attribute in layout.attributes() {
+        let attribute_definition = if attribute.name() == SCAN_ANGLE_RANK.name()
+            || attribute.name() == SCAN_ANGLE.name()
+        {
+            ATTRIBUTE_SCAN_ANGLE_DEGREES
+        } else {
+            attribute.attribute_definition().clone()
+        };
+        degrees_layout.add_attribute(attribute_definition, FieldAlignment::Packed(1));
+    }
+    degrees_layout
+}
+
 /// Returns the best matching LAS point format for the given `PointLayout`. This method tries to match as many attributes
 /// as possible in the given `PointLayout` to attributes that are supported by the LAS format (v1.4) natively. Attributes
 /// that do not have a corresponding LAS attribute are ignored. If no matching attributes are found, LAS point format 0 is
@@ -215,8 +283,7 @@ pub fn las_point_format_from_point_layout(point_layout: &PointLayout) -> Format
     let has_scan_angle = point_layout.has_attribute_with_name(attributes::SCAN_ANGLE.name());
     let has_scanner_channel =
         point_layout.has_attribute_with_name(attributes::SCANNER_CHANNEL.name());
-    let has_classification_flags =
-        point_layout.has_attribute_with_name(attributes::CLASSIFICATION_FLAGS.name());
+    let has_overlap = point_layout.has_attribute_with_name(attributes::OVERLAP.name());
 
     let mut format = Format::new(0).unwrap();
     format.has_color = has_colors;
@@ -224,13 +291,62 @@ pub fn las_point_format_from_point_layout(point_layout: &PointLayout) -> Format
     format.has_nir = has_nir;
     format.has_waveform = has_any_waveform_attribute;
 
-    if has_nir | has_scan_angle | has_scanner_channel | has_classification_flags {
+    // The legacy formats store the classification flags in the classification byte, so they don't require an
+    // extended format
+    if has_nir | has_scan_angle | has_scanner_channel | has_overlap {
         format.is_extended = true;
     }
 
     format
 }
 
+/// Returns the LAS point format with the smallest point records that stores all known LAS attributes (see
+/// [`is_known_las_attribute`]) of the given `point_layout`. If several formats have the same record size, the one with
+/// the lower format number is returned. Unlike [`las_point_format_from_point_layout`], the result is always a valid
+/// point format, e.g. a layout with `NIR` but without `COLOR_RGB` yields format 8, since LAS only stores NIR values in
+/// formats that also store colors. Attributes that are not known LAS attributes are ignored, they can be written as
+/// extra bytes (see [`ExtraBytesMapping`](super::ExtraBytesMapping))
+/// ```
+/// # use pasture_io::las::*;
+/// # use pasture_core::layout::*;
+///
+/// let layout = PointLayout::from_attributes(&[
+///     attributes::POSITION_3D,
+///     attributes::INTENSITY,
+///     attributes::GPS_TIME,
+///     attributes::COLOR_RGB,
+/// ]);
+/// assert_eq!(point_format_from_layout(&layout)?, las::point::Format::new(3)?);
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if `point_layout` has no `POSITION_3D` attribute, which every LAS point format requires
+pub fn point_format_from_layout(point_layout: &PointLayout) -> Result<Format> {
+    if !point_layout.has_attribute_with_name(POSITION_3D.name()) {
+        bail!(
+            "The point layout {} has no {} attribute, which is required by all LAS point formats",
+            point_layout,
+            POSITION_3D.name()
+        );
+    }
+    let required = las_point_format_from_point_layout(point_layout);
+    let best_format = (0..=10)
+        .map(|format_number| Format::new(format_number).expect("Formats 0 to 10 are valid"))
+        .filter(|format| {
+            (format.has_gps_time || !required.has_gps_time)
+                && (format.has_color || !required.has_color)
+                && (format.has_nir || !required.has_nir)
+                && (format.has_waveform || !required.has_waveform)
+                && (format.is_extended || !required.is_extended)
+        })
+        // `min_by_key` returns the first minimum, i.e. the lowest format number
+        .min_by_key(|format| format.len())
+        .expect("Format 10 stores all known LAS attributes");
+    Ok(best_format)
+}
+
 /// Returns `true` if the given `attribute` is a known LAS point attribute. This function only checks the name of
 /// the known attributes and ignores the datatype, so a `POSITION_3D` attribute with datatype `Vec3f32` is still
 /// considered to be a known LAS attribute (as pasture is able to perform type conversion).
@@ -249,6 +365,7 @@ pub fn is_known_las_attribute(attribute: &PointAttributeDefinition) -> bool {
             names.insert(SCANNER_CHANNEL.name());
             names.insert(SCAN_DIRECTION_FLAG.name());
             names.insert(EDGE_OF_FLIGHT_LINE.name());
+            names.insert(OVERLAP.name());
             names.insert(CLASSIFICATION.name());
             names.insert(USER_DATA.name());
             names.insert(SCAN_ANGLE.name());
@@ -284,6 +401,72 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_point_layout_from_las_point_format_serde_snapshots() -> Result<()> {
+        for format_number in 0..=10 {
+            let format = Format::new(format_number)?;
+            let snapshot_path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(format!(
+                "resources/test/las_point_layouts/las_point_layout_format_{}.json",
+                format_number
+            ));
+            let snapshot: serde_json::Value =
+                serde_json::from_slice(&std::fs::read(snapshot_path)?)?;
+            for (snapshot_key, exact_binary_representation) in
+                [("default", false), ("exact_binary_representation", true)]
+            {
+                let layout =
+                    point_layout_from_las_point_format(&format, exact_binary_representation)?;
+                assert_eq!(
+                    snapshot[snapshot_key],
+                    serde_json::to_value(&layout)?,
+                    "Serialized {} layout of point format {} does not match the snapshot",
+                    snapshot_key,
+                    format_number
+                );
+                let deserialized: PointLayout =
+                    serde_json::from_value(snapshot[snapshot_key].clone())?;
+                assert_eq!(layout, deserialized);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_point_layout_from_las_point_format_raw() -> Result<()> {
+        for format_number in 0..=10 {
+            let format = Format::new(format_number)?;
+            assert_eq!(
+                point_layout_from_las_point_format(&format, true)?,
+                point_layout_from_las_point_format_raw(&format)?
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_point_layout_with_local_las_positions() -> Result<()> {
+        let format = Format::new(1)?;
+        let layout = point_layout_from_las_point_format(&format, false)?;
+        let local_layout = point_layout_with_local_las_positions(&layout);
+
+        assert_eq!(
+            layout.attributes().count(),
+            local_layout.attributes().count()
+        );
+        let position_attribute = local_layout
+            .get_attribute_by_name(POSITION_3D.name())
+            .expect("POSITION_3D attribute not found");
+        assert_eq!(
+            PointAttributeDataType::Vec3i32,
+            position_attribute.datatype()
+        );
+        assert_eq!(
+            layout.size_of_point_entry() - 12,
+            local_layout.size_of_point_entry()
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_las_format_from_point_layout() {
         let format0_layout = PointLayout::from_attributes(&[POSITION_3D]);
@@ -375,4 +558,64 @@ mod tests {
             las_point_format_from_point_layout(&format10_layout)
         );
     }
+
+    #[test]
+    fn test_point_format_from_layout_selects_smallest_valid_format() -> Result<()> {
+        let test_cases = [
+            (vec![POSITION_3D, INTENSITY, CLASSIFICATION], 0),
+            (vec![POSITION_3D, INTENSITY, GPS_TIME, COLOR_RGB], 3),
+            (vec![POSITION_3D, SCAN_ANGLE], 6),
+            (vec![POSITION_3D, COLOR_RGB, OVERLAP], 7),
+            // NIR is only stored together with colors
+            (vec![POSITION_3D, NIR], 8),
+            (vec![POSITION_3D, NIR, WAVEFORM_DATA_OFFSET], 10),
+            (vec![POSITION_3D, SCANNER_CHANNEL, WAVEFORM_PACKET_SIZE], 9),
+            (vec![POSITION_3D, WAVEFORM_PARAMETERS], 4),
+        ];
+        for (attributes, expected_format) in test_cases {
+            let layout = PointLayout::from_attributes(&attributes);
+            assert_eq!(
+                Format::new(expected_format)?,
+                point_format_from_layout(&layout)?,
+                "Wrong format for layout {}",
+                layout
+            );
+        }
+
+        for format_number in 0..=10 {
+            let format = Format::new(format_number)?;
+            let layout = point_layout_from_las_point_format(&format, false)?;
+            assert_eq!(format, point_format_from_layout(&layout)?);
+        }
+
+        assert!(point_format_from_layout(&PointLayout::from_attributes(&[INTENSITY])).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_point_layout_with_scan_angle_in_degrees() -> Result<()> {
+        for format_number in [1, 6] {
+            let format = Format::new(format_number)?;
+            let layout = point_layout_from_las_point_format(&format, false)?;
+            let degrees_layout = point_layout_with_scan_angle_in_degrees(&layout);
+
+            assert_eq!(
+                layout.attributes().count(),
+                degrees_layout.attributes().count()
+            );
+            assert!(degrees_layout.has_attribute(&ATTRIBUTE_SCAN_ANGLE_DEGREES));
+            assert!(!degrees_layout.has_attribute_with_name(SCAN_ANGLE_RANK.name()));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_format_from_default_point_layout() -> Result<()> {
+        for format_number in 0..=10 {
+            let format = Format::new(format_number)?;
+            let layout = point_layout_from_las_point_format(&format, false)?;
+            assert_eq!(format, las_point_format_from_point_layout(&layout));
+        }
+        Ok(())
+    }
 }
@@ -1,8 +1,10 @@
 use std::{
     any::Any,
     borrow::Cow,
+    collections::BTreeMap,
     convert::{TryFrom, TryInto},
     fmt::Display,
+    io::{Read, Seek, SeekFrom},
     iter::FromIterator,
     path::Path,
 };
@@ -10,17 +12,19 @@ use std::{
 use anyhow::{anyhow, bail, Context, Result};
 use bitfield::bitfield;
 use chrono::Datelike;
-use las::{Bounds, Header};
-use las_rs::{point::Format, raw::vlr::RecordLength, Vector, Vlr};
+use las::{Bounds, Header, Version};
+use las_rs::{point::Format, raw, raw::vlr::RecordLength, GpsTimeType, Transform, Vector, Vlr};
 use pasture_core::{
     layout::{PointAttributeDataType, PointAttributeDefinition},
     math::AABB,
     meta::Metadata,
-    nalgebra::Point3,
+    nalgebra::{Point3, Vector3},
 };
 use static_assertions::const_assert_eq;
 
-use super::{las_string_to_rust_string, write_rust_string_into_las_ascii_array};
+use super::{
+    las_string_to_rust_string, write_rust_string_into_las_ascii_array, WaveformPacketDescriptor,
+};
 
 /// Contains constants for possible named fields in a `LASMetadata` structure
 pub mod named_fields {
@@ -36,6 +40,12 @@ pub mod named_fields {
     pub const FILE_CREATION_DAY_OF_YEAR: &str = "LASFIELD_FileCreationDayOfYear";
     /// Year in which the file was created
     pub const FILE_CREATION_YEAR: &str = "LASFIELD_FileCreationYear";
+    /// Global encoding bit field as per the LAS 1.4 specification
+    pub const GLOBAL_ENCODING: &str = "LASFIELD_GlobalEncoding";
+    /// Scale factors of the X, Y, and Z coordinates as a `Vector3<f64>`
+    pub const SCALE: &str = "LASFIELD_Scale";
+    /// Offsets of the X, Y, and Z coordinates as a `Vector3<f64>`
+    pub const OFFSET: &str = "LASFIELD_Offset";
 
     //TODO More fields
 }
@@ -63,6 +73,18 @@ pub fn pasture_bounds_to_las_bounds(bounds: &AABB<f64>) -> Bounds {
     }
 }
 
+/// Returns the number of point records stated in the given raw LAS header. For LAS 1.4 files, this is the 64-bit point
+/// count, because the legacy 32-bit point count is zero for files with extended point formats or with more than
+/// `u32::MAX` points. For older versions, the legacy point count is used
+pub fn point_count_from_raw_header(raw_header: &raw::Header) -> u64 {
+    match &raw_header.large_file {
+        Some(large_file) if raw_header.version >= Version::new(1, 4) => {
+            large_file.number_of_point_records
+        }
+        _ => raw_header.number_of_point_records as u64,
+    }
+}
+
 /// Tries to determine whether the given `path` represents a compressed LAZ file or an uncompressed LAS file
 pub fn path_is_compressed_las_file<P: AsRef<Path>>(path: P) -> Result<bool> {
     path.as_ref()
@@ -74,7 +96,69 @@ pub fn path_is_compressed_las_file<P: AsRef<Path>>(path: P) -> Result<bool> {
         ))
 }
 
-const KNOWN_VLR_USER_ID: &str = "LASF_Spec";
+/// `user_id` of the VLR that LASzip writes into compressed LAZ files
+pub(crate) const LASZIP_VLR_USER_ID: &str = "laszip encoded";
+/// Offset of the point data record format within the LAS header
+const POINT_DATA_RECORD_FORMAT_OFFSET: u64 = 104;
+
+/// Determines whether `read` contains a compressed LAZ file or an uncompressed LAS file from the contents of the file,
+/// i.e. from the compression bits of the point data record format and the presence of the LASzip VLR. Unlike
+/// [`path_is_compressed_las_file`], this also works for files with a wrong file extension. Afterwards, `read` is at the
+/// same position as before
+///
+/// # Errors
+///
+/// Returns an error if `read` does not start with a valid LAS header. The error states which value was expected at
+/// which offset
+pub fn read_is_compressed_las_file<R: Read + Seek>(read: &mut R) -> Result<bool> {
+    let start_position = read.stream_position()?;
+    let is_compressed = detect_las_compression(read, start_position);
+    read.seek(SeekFrom::Start(start_position))?;
+    is_compressed
+}
+
+fn detect_las_compression<R: Read + Seek>(read: &mut R, start_position: u64) -> Result<bool> {
+    let mut signature = [0; 4];
+    read.read_exact(&mut signature)
+        .context("Failed to read the LAS file signature at offset 0")?;
+    if &signature != b"LASF" {
+        bail!(
+            "Invalid LAS file signature at offset 0: Expected \"LASF\", but found {:?}",
+            String::from_utf8_lossy(&signature)
+        );
+    }
+    read.seek(SeekFrom::Start(start_position))?;
+    let raw_header = raw::Header::read_from(&mut *read).context("Failed to read the LAS header")?;
+
+    let point_format = raw_header.point_data_record_format;
+    if point_format & 0b0011_1111 > 10 {
+        bail!(
+            "Invalid point data record format at offset {}: Expected a value between 0 and 10, but found {}",
+            POINT_DATA_RECORD_FORMAT_OFFSET,
+            point_format
+        );
+    }
+    // LASzip marks compressed files by setting bit 7 (or bit 6 in older versions) of the point data record format
+    if point_format & 0b1100_0000 != 0 {
+        return Ok(true);
+    }
+
+    read.seek(SeekFrom::Start(
+        start_position + raw_header.header_size as u64,
+    ))?;
+    for _ in 0..raw_header.number_of_variable_length_records {
+        let vlr = raw::Vlr::read_from(&mut *read, false).context("Failed to read the VLRs")?;
+        if Vlr::new(vlr).user_id == LASZIP_VLR_USER_ID {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+pub(crate) const KNOWN_VLR_USER_ID: &str = "LASF_Spec";
+/// Record ID of VLRs that are no longer in use, as per the LAS 1.4 specification. Superseded VLRs have the user ID
+/// `LASF_Spec` and keep their original payload
+pub const SUPERSEDED_VLR_RECORD_ID: u16 = 7;
 
 #[derive(Clone, Debug, Default)]
 pub struct ClassificationLookupEntry {
@@ -314,6 +398,29 @@ impl TryFrom<ExtraBytesDataType> for PointAttributeDataType {
     }
 }
 
+impl TryFrom<PointAttributeDataType> for ExtraBytesDataType {
+    type Error = anyhow::Error;
+
+    fn try_from(value: PointAttributeDataType) -> std::result::Result<Self, Self::Error> {
+        match value {
+            PointAttributeDataType::U8 => Ok(Self::U8),
+            PointAttributeDataType::I8 => Ok(Self::I8),
+            PointAttributeDataType::U16 => Ok(Self::U16),
+            PointAttributeDataType::I16 => Ok(Self::I16),
+            PointAttributeDataType::U32 => Ok(Self::U32),
+            PointAttributeDataType::I32 => Ok(Self::I32),
+            PointAttributeDataType::U64 => Ok(Self::U64),
+            PointAttributeDataType::I64 => Ok(Self::I64),
+            PointAttributeDataType::F32 => Ok(Self::F32),
+            PointAttributeDataType::F64 => Ok(Self::F64),
+            other => bail!(
+                "Datatype {} can't be stored in LAS extra bytes, only scalar numeric datatypes are supported",
+                other
+            ),
+        }
+    }
+}
+
 impl Display for ExtraBytesDataType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:#?}", self)
@@ -513,6 +620,29 @@ impl ExtraBytesEntry {
             pasture_datatype,
         ))
     }
+
+    /// Returns `true` if the raw values of the extra bytes have to be scaled and/or offset to obtain the actual values
+    pub fn is_scaled(&self) -> bool {
+        self.options.use_scale() || self.options.use_offset()
+    }
+
+    /// Applies the scale and offset of this `ExtraBytesEntry` to the given raw `value`. If no scale is set, a scale of
+    /// `1.0` is used, if no offset is set, an offset of `0.0` is used
+    pub fn apply_scale_and_offset(&self, value: f64) -> f64 {
+        (value * self.scale().unwrap_or(1.0)) + self.offset().unwrap_or(0.0)
+    }
+
+    /// Returns a matching `PointAttributeDefinition` for the actual values of the extra bytes described by this
+    /// `ExtraBytesEntry`. If the extra bytes are scaled and/or offset (see [`Self::is_scaled`]), the attribute has
+    /// datatype `F64`, otherwise this is equal to [`Self::get_point_attribute`]
+    pub fn get_scaled_point_attribute(&self) -> Result<PointAttributeDefinition> {
+        let raw_attribute = self.get_point_attribute()?;
+        if self.is_scaled() {
+            Ok(raw_attribute.with_custom_datatype(PointAttributeDataType::F64))
+        } else {
+            Ok(raw_attribute)
+        }
+    }
 }
 
 impl Display for ExtraBytesEntry {
@@ -760,6 +890,278 @@ impl Display for ExtraBytesVlr {
     }
 }
 
+pub(crate) const PROJECTION_VLR_USER_ID: &str = "LASF_Projection";
+/// Record ID of the OGC Coordinate System WKT record
+pub(crate) const WKT_CRS_RECORD_ID: u16 = 2112;
+
+/// Returns the OGC Coordinate System WKT VLR for the given `wkt`
+pub fn crs_wkt_vlr(wkt: &str) -> Vlr {
+    let mut data = wkt.as_bytes().to_vec();
+    // The WKT string is null-terminated
+    data.push(0);
+    Vlr {
+        user_id: PROJECTION_VLR_USER_ID.to_owned(),
+        record_id: WKT_CRS_RECORD_ID,
+        description: "OGC Coordinate System WKT".to_owned(),
+        data,
+    }
+}
+
+/// Single key entry of a GeoTIFF GeoKeyDirectory
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct GeoKeyEntry {
+    pub key_id: u16,
+    /// TIFF tag in which the value of this key is stored. A value of zero means that the value is stored in
+    /// `value_offset` directly
+    pub tiff_tag_location: u16,
+    pub count: u16,
+    pub value_offset: u16,
+}
+
+/// Coordinate reference system of a LAS file, as encoded by the GeoTIFF VLRs (GeoKeyDirectory, GeoDoubleParams,
+/// and GeoAsciiParams)
+#[derive(Clone, Debug)]
+pub struct GeoTiffCrs {
+    keys: Vec<GeoKeyEntry>,
+    double_params: Vec<f64>,
+    ascii_params: String,
+}
+
+impl GeoTiffCrs {
+    pub const GEO_KEY_DIRECTORY_RECORD_ID: u16 = 34735;
+    pub const GEO_DOUBLE_PARAMS_RECORD_ID: u16 = 34736;
+    pub const GEO_ASCII_PARAMS_RECORD_ID: u16 = 34737;
+
+    const GT_MODEL_TYPE_GEO_KEY: u16 = 1024;
+    const GT_RASTER_TYPE_GEO_KEY: u16 = 1025;
+    const GEOGRAPHIC_TYPE_GEO_KEY: u16 = 2048;
+    const PROJECTED_CS_TYPE_GEO_KEY: u16 = 3072;
+    const VERTICAL_CS_TYPE_GEO_KEY: u16 = 4096;
+    const USER_DEFINED: u16 = 32767;
+
+    /// Parses the GeoTIFF CRS from the given VLRs. Returns `None` if there is no GeoKeyDirectory VLR, and an error
+    /// if any of the GeoTIFF VLRs is malformed
+    pub fn from_vlrs(vlrs: &[Vlr]) -> Result<Option<Self>> {
+        let find_vlr = |record_id: u16| {
+            vlrs.iter()
+                .find(|vlr| vlr.user_id == PROJECTION_VLR_USER_ID && vlr.record_id == record_id)
+        };
+        let key_directory_vlr = match find_vlr(Self::GEO_KEY_DIRECTORY_RECORD_ID) {
+            Some(vlr) => vlr,
+            None => return Ok(None),
+        };
+
+        if key_directory_vlr.data.len() % 2 != 0 {
+            bail!("Size of GeoKeyDirectory VLR must be a multiple of 2 bytes");
+        }
+        let shorts = key_directory_vlr
+            .data
+            .chunks_exact(2)
+            .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+            .collect::<Vec<_>>();
+        if shorts.len() < 4 {
+            bail!("GeoKeyDirectory VLR is too small to contain its header");
+        }
+        let number_of_keys = shorts[3] as usize;
+        if shorts.len() < 4 + 4 * number_of_keys {
+            bail!(
+                "GeoKeyDirectory VLR states {} keys but only has room for {}",
+                number_of_keys,
+                (shorts.len() - 4) / 4
+            );
+        }
+        let keys = shorts[4..(4 + 4 * number_of_keys)]
+            .chunks_exact(4)
+            .map(|entry| GeoKeyEntry {
+                key_id: entry[0],
+                tiff_tag_location: entry[1],
+                count: entry[2],
+                value_offset: entry[3],
+            })
+            .collect();
+
+        let double_params = match find_vlr(Self::GEO_DOUBLE_PARAMS_RECORD_ID) {
+            Some(vlr) => {
+                if vlr.data.len() % 8 != 0 {
+                    bail!("Size of GeoDoubleParams VLR must be a multiple of 8 bytes");
+                }
+                vlr.data
+                    .chunks_exact(8)
+                    .map(|bytes| f64::from_le_bytes(bytes.try_into().unwrap()))
+                    .collect()
+            }
+            None => vec![],
+        };
+        let ascii_params = find_vlr(Self::GEO_ASCII_PARAMS_RECORD_ID)
+            .map(|vlr| String::from_utf8_lossy(&vlr.data).into_owned())
+            .unwrap_or_default();
+
+        Ok(Some(Self {
+            keys,
+            double_params,
+            ascii_params,
+        }))
+    }
+
+    /// Creates a minimal GeoTIFF CRS for the horizontal coordinate system with the given EPSG code. Whether the code
+    /// refers to a geographic or a projected coordinate system is guessed from the code: Codes from 4000 to 4999 are
+    /// used for geographic coordinate systems, all other codes are assumed to be projected coordinate systems
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `epsg_code` does not fit into the 16-bit values of a GeoKeyDirectory
+    pub fn from_epsg_code(epsg_code: u32) -> Result<Self> {
+        let epsg_code: u16 = epsg_code
+            .try_into()
+            .ok()
+            .filter(|code| *code != Self::USER_DEFINED)
+            .with_context(|| {
+                format!(
+                    "EPSG code {} can't be stored in a GeoKeyDirectory, use an OGC WKT CRS instead",
+                    epsg_code
+                )
+            })?;
+        let is_geographic = (4000..5000).contains(&epsg_code);
+        let short_key = |key_id: u16, value: u16| GeoKeyEntry {
+            key_id,
+            tiff_tag_location: 0,
+            count: 1,
+            value_offset: value,
+        };
+        let keys = if is_geographic {
+            vec![
+                // ModelTypeGeographic
+                short_key(Self::GT_MODEL_TYPE_GEO_KEY, 2),
+                // RasterPixelIsArea
+                short_key(Self::GT_RASTER_TYPE_GEO_KEY, 1),
+                short_key(Self::GEOGRAPHIC_TYPE_GEO_KEY, epsg_code),
+            ]
+        } else {
+            vec![
+                // ModelTypeProjected
+                short_key(Self::GT_MODEL_TYPE_GEO_KEY, 1),
+                short_key(Self::GT_RASTER_TYPE_GEO_KEY, 1),
+                short_key(Self::PROJECTED_CS_TYPE_GEO_KEY, epsg_code),
+            ]
+        };
+        Ok(Self {
+            keys,
+            double_params: vec![],
+            ascii_params: String::new(),
+        })
+    }
+
+    /// Returns the GeoTIFF VLRs that encode this CRS. The GeoDoubleParams and GeoAsciiParams VLRs are only included
+    /// if they are not empty
+    pub fn to_vlrs(&self) -> Vec<Vlr> {
+        let make_vlr = |record_id: u16, description: &str, data: Vec<u8>| Vlr {
+            user_id: PROJECTION_VLR_USER_ID.to_owned(),
+            record_id,
+            description: description.to_owned(),
+            data,
+        };
+        let key_directory_header = [1, 1, 0, self.keys.len() as u16];
+        let key_directory_data = key_directory_header
+            .iter()
+            .copied()
+            .chain(self.keys.iter().flat_map(|key| {
+                [
+                    key.key_id,
+                    key.tiff_tag_location,
+                    key.count,
+                    key.value_offset,
+                ]
+            }))
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let mut vlrs = vec![make_vlr(
+            Self::GEO_KEY_DIRECTORY_RECORD_ID,
+            "GeoTiff GeoKeyDirectoryTag",
+            key_directory_data,
+        )];
+        if !self.double_params.is_empty() {
+            vlrs.push(make_vlr(
+                Self::GEO_DOUBLE_PARAMS_RECORD_ID,
+                "GeoTiff GeoDoubleParamsTag",
+                self.double_params
+                    .iter()
+                    .flat_map(|value| value.to_le_bytes())
+                    .collect(),
+            ));
+        }
+        if !self.ascii_params.is_empty() {
+            vlrs.push(make_vlr(
+                Self::GEO_ASCII_PARAMS_RECORD_ID,
+                "GeoTiff GeoAsciiParamsTag",
+                self.ascii_params.as_bytes().to_vec(),
+            ));
+        }
+        vlrs
+    }
+
+    /// Returns all keys of the GeoKeyDirectory
+    pub fn keys(&self) -> &[GeoKeyEntry] {
+        &self.keys
+    }
+
+    /// Returns the values of the GeoDoubleParams VLR, or an empty slice if the VLR does not exist
+    pub fn double_params(&self) -> &[f64] {
+        &self.double_params
+    }
+
+    /// Returns the contents of the GeoAsciiParams VLR, or an empty string if the VLR does not exist
+    pub fn ascii_params(&self) -> &str {
+        &self.ascii_params
+    }
+
+    /// Returns the key with the given `key_id`, if it exists
+    pub fn key(&self, key_id: u16) -> Option<&GeoKeyEntry> {
+        self.keys.iter().find(|key| key.key_id == key_id)
+    }
+
+    /// Returns the value of the key with the given `key_id`, if it exists and its value is stored directly in the
+    /// GeoKeyDirectory
+    pub fn short_value(&self, key_id: u16) -> Option<u16> {
+        self.key(key_id)
+            .filter(|key| key.tiff_tag_location == 0)
+            .map(|key| key.value_offset)
+    }
+
+    /// Returns the EPSG code of the horizontal coordinate system. This is the projected coordinate system, if one is
+    /// defined, and the geographic coordinate system otherwise. Returns `None` for user-defined coordinate systems
+    pub fn horizontal_epsg_code(&self) -> Option<u16> {
+        self.short_value(Self::PROJECTED_CS_TYPE_GEO_KEY)
+            .or_else(|| self.short_value(Self::GEOGRAPHIC_TYPE_GEO_KEY))
+            .filter(|code| *code != Self::USER_DEFINED)
+    }
+
+    /// Returns the EPSG code of the vertical coordinate system, if one is defined and it is not user-defined
+    pub fn vertical_epsg_code(&self) -> Option<u16> {
+        self.short_value(Self::VERTICAL_CS_TYPE_GEO_KEY)
+            .filter(|code| *code != Self::USER_DEFINED)
+    }
+}
+
+impl Display for GeoTiffCrs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "GeoTIFF CRS")?;
+        if let Some(epsg_code) = self.horizontal_epsg_code() {
+            writeln!(f, "\tHorizontal EPSG code: {}", epsg_code)?;
+        }
+        if let Some(epsg_code) = self.vertical_epsg_code() {
+            writeln!(f, "\tVertical EPSG code:   {}", epsg_code)?;
+        }
+        for key in &self.keys {
+            writeln!(
+                f,
+                "\tKey {:5}: location {:5}, count {:3}, value/offset {}",
+                key.key_id, key.tiff_tag_location, key.count, key.value_offset
+            )?;
+        }
+        Ok(())
+    }
+}
+
 fn display_generic_vlr(vlr: &Vlr, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
     writeln!(f, "\t{}", vlr.description)?;
     writeln!(f, "\t\tUser:      {}", vlr.user_id)?;
@@ -783,6 +1185,10 @@ pub struct LASMetadata {
     classification_lookup_vlr: Option<Box<ClassificationLookup>>, //Boxed because it is large
     text_area_description_vlr: Option<TextAreaDescription>,
     extra_bytes_vlr: Option<ExtraBytesVlr>,
+    geotiff_crs: Option<GeoTiffCrs>,
+    crs_wkt: Option<String>,
+    points_by_return: Vec<u64>,
+    warnings: Vec<String>,
     raw_las_header: Option<Header>,
 }
 
@@ -807,6 +1213,10 @@ impl LASMetadata {
             classification_lookup_vlr: None,
             extra_bytes_vlr: None,
             text_area_description_vlr: None,
+            geotiff_crs: None,
+            crs_wkt: None,
+            points_by_return: vec![],
+            warnings: vec![],
         }
     }
 
@@ -815,6 +1225,28 @@ impl LASMetadata {
         self.point_count
     }
 
+    /// Overrides the number of points for the associated `LASMetadata`. The raw LAS header is not changed by this
+    pub(crate) fn set_point_count(&mut self, point_count: u64) -> Result<()> {
+        self.point_count = point_count
+            .try_into()
+            .context("Number of points in LAS file exceeds the addressable range")?;
+        Ok(())
+    }
+
+    /// Returns all warnings that came up while reading the LAS header, e.g. if the file contains fewer point records
+    /// than stated in the header
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    pub(crate) fn add_warning(&mut self, warning: String) {
+        self.warnings.push(warning);
+    }
+
+    pub(crate) fn set_warnings(&mut self, warnings: Vec<String>) {
+        self.warnings = warnings;
+    }
+
     /// Returns the LAS point format for the associated `LASMetadata`
     pub fn point_format(&self) -> Format {
         self.point_format
@@ -826,12 +1258,213 @@ impl LASMetadata {
         self.raw_las_header.as_ref()
     }
 
-    /// Returns the Classification Lookup VLR, if it exists
+    /// Returns the file source ID from the LAS header, if the raw LAS header is present
+    pub fn file_source_id(&self) -> Option<u16> {
+        self.raw_las_header
+            .as_ref()
+            .map(|header| header.file_source_id())
+    }
+
+    /// Returns whether the GPS times of the points are GPS week times or adjusted standard GPS times, as stated by the
+    /// global encoding of the LAS header. Returns `None` if the raw LAS header is not present
+    pub fn gps_time_type(&self) -> Option<GpsTimeType> {
+        self.raw_las_header
+            .as_ref()
+            .map(|header| header.gps_time_type())
+    }
+
+    /// Returns the global encoding bit field from the LAS header, if the raw LAS header is present
+    pub fn global_encoding(&self) -> Option<u16> {
+        // las-rs only exposes the individual flags of the global encoding, so we go through the raw header to get
+        // the exact bits
+        self.raw_las_header
+            .as_ref()
+            .and_then(|header| header.clone().into_raw().ok())
+            .map(|raw_header| raw_header.global_encoding)
+    }
+
+    /// Returns the system identifier from the LAS header, if the raw LAS header is present
+    pub fn system_identifier(&self) -> Option<&str> {
+        self.raw_las_header
+            .as_ref()
+            .map(|header| header.system_identifier())
+    }
+
+    /// Returns the generating software from the LAS header, if the raw LAS header is present
+    pub fn generating_software(&self) -> Option<&str> {
+        self.raw_las_header
+            .as_ref()
+            .map(|header| header.generating_software())
+    }
+
+    /// Returns the day of year on which the file was created, if the raw LAS header is present and contains a
+    /// valid creation date
+    pub fn file_creation_day_of_year(&self) -> Option<u16> {
+        self.raw_las_header
+            .as_ref()
+            .and_then(|header| header.date())
+            .map(|date| date.ordinal().try_into().unwrap())
+    }
+
+    /// Returns the year in which the file was created, if the raw LAS header is present and contains a valid
+    /// creation date
+    pub fn file_creation_year(&self) -> Option<u16> {
+        self.raw_las_header
+            .as_ref()
+            .and_then(|header| header.date())
+            .map(|date| date.year().try_into().unwrap())
+    }
+
+    /// Returns the scale and offset values for the X, Y, and Z coordinates from the LAS header, if the raw LAS
+    /// header is present
+    pub fn transforms(&self) -> Option<&Vector<Transform>> {
+        self.raw_las_header
+            .as_ref()
+            .map(|header| header.transforms())
+    }
+
+    /// Returns the scale factors of the X, Y, and Z coordinates from the LAS header, if the raw LAS header is present.
+    /// Together with [`Self::offset`], this converts world-space positions back into the integer coordinates of the
+    /// LAS file: `local = round((world - offset) / scale)`
+    pub fn scale(&self) -> Option<Vector3<f64>> {
+        self.transforms().map(|transforms| {
+            Vector3::new(transforms.x.scale, transforms.y.scale, transforms.z.scale)
+        })
+    }
+
+    /// Returns the offsets of the X, Y, and Z coordinates from the LAS header, if the raw LAS header is present
+    pub fn offset(&self) -> Option<Vector3<f64>> {
+        self.transforms().map(|transforms| {
+            Vector3::new(
+                transforms.x.offset,
+                transforms.y.offset,
+                transforms.z.offset,
+            )
+        })
+    }
+
+    /// Returns the number of points by return from the LAS header, where the first entry is the number of points with
+    /// return number 1. Contains 15 entries for LAS 1.4 files, 5 entries for files with older versions, and no entries
+    /// if the raw LAS header is not present
+    pub fn points_by_return(&self) -> &[u64] {
+        &self.points_by_return
+    }
+
+    /// Returns all VLRs from the LAS header, or an empty slice if the raw LAS header is not present
+    pub fn vlrs(&self) -> &[Vlr] {
+        self.raw_las_header
+            .as_ref()
+            .map(|header| header.vlrs().as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Returns all extended VLRs from the LAS header, or an empty slice if the raw LAS header is not present
+    pub fn evlrs(&self) -> &[Vlr] {
+        self.raw_las_header
+            .as_ref()
+            .map(|header| header.evlrs().as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Returns the user-defined bytes between the end of the LAS header and the first VLR, i.e. all bytes up to the
+    /// `header_size` from the LAS header that are not part of the header itself. Returns an empty slice if there are no
+    /// such bytes or if the raw LAS header is not present
+    pub fn header_padding(&self) -> &[u8] {
+        self.raw_las_header
+            .as_ref()
+            .map(|header| &header.padding()[..])
+            .unwrap_or_default()
+    }
+
+    /// Returns the bytes between the end of the last VLR and the start of the point records, i.e. the user-defined data
+    /// that some producers store before `offset_to_point_data`. Returns an empty slice if there are no such bytes or if
+    /// the raw LAS header is not present
+    pub fn vlr_padding(&self) -> &[u8] {
+        self.raw_las_header
+            .as_ref()
+            .map(|header| &header.vlr_padding()[..])
+            .unwrap_or_default()
+    }
+
+    /// Returns the first VLR or EVLR with the given `user_id` and `record_id`, if it exists. VLRs are searched before
+    /// EVLRs
+    pub fn find_vlr(&self, user_id: &str, record_id: u16) -> Option<&Vlr> {
+        self.vlrs()
+            .iter()
+            .chain(self.evlrs().iter())
+            .find(|vlr| vlr.user_id == user_id && vlr.record_id == record_id)
+    }
+
+    /// Returns the `(classification, description)` pairs from the Classification Lookup VLR, if it exists. Entries
+    /// without a description are skipped
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the Classification Lookup VLR is malformed
+    pub fn classification_lookup(&self) -> Result<Option<Vec<(u8, String)>>> {
+        let vlr = match self.find_vlr(KNOWN_VLR_USER_ID, ClassificationLookup::RECORD_ID) {
+            Some(vlr) => vlr,
+            None => return Ok(None),
+        };
+        let lookup = ClassificationLookup::try_from(vlr)
+            .context("Could not parse Classification Lookup VLR")?;
+        let entries = lookup
+            .entries()
+            .iter()
+            .filter_map(|entry| {
+                let description = entry.description.split('\0').next().unwrap_or_default();
+                if description.is_empty() {
+                    None
+                } else {
+                    Some((entry.classification, description.to_owned()))
+                }
+            })
+            .collect();
+        Ok(Some(entries))
+    }
+
+    /// Returns the text of the Text Area Description VLR, if it exists
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the Text Area Description VLR is malformed
+    pub fn text_area_description(&self) -> Result<Option<String>> {
+        self.find_vlr(KNOWN_VLR_USER_ID, TextAreaDescription::RECORD_ID)
+            .map(|vlr| {
+                TextAreaDescription::try_from(vlr)
+                    .map(|description| description.text().trim_end_matches('\0').to_owned())
+                    .context("Could not parse Text Area Description VLR")
+            })
+            .transpose()
+    }
+
+    /// Returns all superseded VLRs and EVLRs, i.e. records that are no longer in use, see [`SUPERSEDED_VLR_RECORD_ID`]
+    pub fn superseded_vlrs(&self) -> Vec<&Vlr> {
+        self.vlrs()
+            .iter()
+            .chain(self.evlrs().iter())
+            .filter(|vlr| {
+                vlr.user_id == KNOWN_VLR_USER_ID && vlr.record_id == SUPERSEDED_VLR_RECORD_ID
+            })
+            .collect()
+    }
+
+    /// Returns all Waveform Packet Descriptors from the VLRs, indexed by their descriptor index, see
+    /// [`WaveformPacketDescriptor::from_vlrs`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any Waveform Packet Descriptor VLR is malformed
+    pub fn waveform_packet_descriptors(&self) -> Result<BTreeMap<u8, WaveformPacketDescriptor>> {
+        WaveformPacketDescriptor::from_vlrs(self.vlrs())
+    }
+
+    /// Returns the Classification Lookup VLR, if it exists and is well-formed
     pub fn classification_lookup_vlr(&self) -> Option<&ClassificationLookup> {
         self.classification_lookup_vlr.as_deref()
     }
 
-    /// Returns the Text Area Description VLR, if it exists
+    /// Returns the Text Area Description VLR, if it exists and is well-formed
     pub fn text_area_description_vlr(&self) -> Option<&TextAreaDescription> {
         self.text_area_description_vlr.as_ref()
     }
@@ -840,6 +1473,17 @@ impl LASMetadata {
     pub fn extra_bytes_vlr(&self) -> Option<&ExtraBytesVlr> {
         self.extra_bytes_vlr.as_ref()
     }
+
+    /// Returns the coordinate reference system from the GeoTIFF VLRs, if they exist and are well-formed
+    pub fn crs(&self) -> Option<&GeoTiffCrs> {
+        self.geotiff_crs.as_ref()
+    }
+
+    /// Returns the coordinate reference system as an OGC WKT string, if the file contains an OGC Coordinate System
+    /// WKT record as either a VLR or an EVLR
+    pub fn crs_wkt(&self) -> Option<&str> {
+        self.crs_wkt.as_deref()
+    }
 }
 
 impl Display for LASMetadata {
@@ -848,6 +1492,9 @@ impl Display for LASMetadata {
         writeln!(f, "Bounds (max):                {}", self.bounds.max())?;
         writeln!(f, "Number of point records:     {}", self.point_count)?;
         writeln!(f, "Point record format:         {}", self.point_format)?;
+        for warning in &self.warnings {
+            writeln!(f, "Warning:                     {}", warning)?;
+        }
 
         if let Some(classification_vlr) = &self.classification_lookup_vlr {
             write!(f, "{}", classification_vlr)?;
@@ -858,6 +1505,12 @@ impl Display for LASMetadata {
         if let Some(extra_bytes) = &self.extra_bytes_vlr {
             write!(f, "{}", extra_bytes)?;
         }
+        if let Some(geotiff_crs) = &self.geotiff_crs {
+            write!(f, "{}", geotiff_crs)?;
+        }
+        if let Some(crs_wkt) = &self.crs_wkt {
+            writeln!(f, "OGC WKT CRS:                 {}", crs_wkt)?;
+        }
 
         if let Some(las_header) = &self.raw_las_header {
             writeln!(f, "Raw LAS header entries:")?;
@@ -982,34 +1635,35 @@ impl Metadata for LASMetadata {
     fn get_named_field(&self, field_name: &str) -> Option<Box<dyn Any>> {
         match field_name {
             named_fields::FILE_CREATION_DAY_OF_YEAR => self
-                .raw_las_header
-                .as_ref()
-                .and_then(|header| header.date())
-                .map(|date| -> Box<dyn Any> {
-                    let day_of_year: u16 = date.ordinal().try_into().unwrap();
-                    Box::new(day_of_year)
-                }),
+                .file_creation_day_of_year()
+                .map(|day_of_year| -> Box<dyn Any> { Box::new(day_of_year) }),
             named_fields::FILE_CREATION_YEAR => self
-                .raw_las_header
-                .as_ref()
-                .and_then(|header| header.date())
-                .map(|date| -> Box<dyn Any> {
-                    let year: u16 = date.year().try_into().unwrap();
-                    Box::new(year)
-                }),
+                .file_creation_year()
+                .map(|year| -> Box<dyn Any> { Box::new(year) }),
             named_fields::FILE_SOURCE_I_D => self
-                .raw_las_header
-                .as_ref()
-                .map(|header| -> Box<dyn Any> { Box::new(header.file_source_id()) }),
+                .file_source_id()
+                .map(|file_source_id| -> Box<dyn Any> { Box::new(file_source_id) }),
             named_fields::GENERATING_SOFTWARE => {
-                self.raw_las_header.as_ref().map(|header| -> Box<dyn Any> {
-                    Box::new(header.generating_software().to_owned())
-                })
+                self.generating_software()
+                    .map(|generating_software| -> Box<dyn Any> {
+                        Box::new(generating_software.to_owned())
+                    })
             }
-            named_fields::SYSTEM_IDENTIFIER => self
-                .raw_las_header
-                .as_ref()
-                .map(|header| -> Box<dyn Any> { Box::new(header.system_identifier().to_owned()) }),
+            named_fields::GLOBAL_ENCODING => self
+                .global_encoding()
+                .map(|global_encoding| -> Box<dyn Any> { Box::new(global_encoding) }),
+            named_fields::SYSTEM_IDENTIFIER => {
+                self.system_identifier()
+                    .map(|system_identifier| -> Box<dyn Any> {
+                        Box::new(system_identifier.to_owned())
+                    })
+            }
+            named_fields::SCALE => self
+                .scale()
+                .map(|scale| -> Box<dyn Any> { Box::new(scale) }),
+            named_fields::OFFSET => self
+                .offset()
+                .map(|offset| -> Box<dyn Any> { Box::new(offset) }),
             named_fields::VERSION => self
                 .raw_las_header
                 .as_ref()
@@ -1027,15 +1681,26 @@ impl TryFrom<&las::Header> for LASMetadata {
     type Error = anyhow::Error;
 
     fn try_from(header: &las::Header) -> std::result::Result<Self, Self::Error> {
+        // The Classification Lookup and Text Area Description VLRs are purely informational, so malformed payloads only
+        // produce a warning here. The errors are available through the typed accessors, e.g.
+        // `LASMetadata::classification_lookup`
+        let mut warnings = vec![];
         let classification_lookup_vlr = header
             .vlrs()
             .iter()
             .find(|vlr| {
                 vlr.user_id == KNOWN_VLR_USER_ID && vlr.record_id == ClassificationLookup::RECORD_ID
             })
-            .map(ClassificationLookup::try_from)
-            .transpose()
-            .context("Could not parse Classification Lookup VLR")?;
+            .and_then(|vlr| match ClassificationLookup::try_from(vlr) {
+                Ok(lookup) => Some(lookup),
+                Err(why) => {
+                    warnings.push(format!(
+                        "Ignoring invalid Classification Lookup VLR: {:#}",
+                        why
+                    ));
+                    None
+                }
+            });
 
         let text_area_description_vlr = header
             .vlrs()
@@ -1043,9 +1708,16 @@ impl TryFrom<&las::Header> for LASMetadata {
             .find(|vlr| {
                 vlr.user_id == KNOWN_VLR_USER_ID && vlr.record_id == TextAreaDescription::RECORD_ID
             })
-            .map(TextAreaDescription::try_from)
-            .transpose()
-            .context("Could not parse Text Area Description VLR")?;
+            .and_then(|vlr| match TextAreaDescription::try_from(vlr) {
+                Ok(description) => Some(description),
+                Err(why) => {
+                    warnings.push(format!(
+                        "Ignoring invalid Text Area Description VLR: {:#}",
+                        why
+                    ));
+                    None
+                }
+            });
 
         let extra_bytes_vlr = header
             .vlrs()
@@ -1057,6 +1729,33 @@ impl TryFrom<&las::Header> for LASMetadata {
             .transpose()
             .context("Could not parse Extra Bytes VLR")?;
 
+        // The CRS is purely informational, so a malformed GeoKeyDirectory should not prevent opening the file
+        let geotiff_crs = GeoTiffCrs::from_vlrs(header.vlrs()).ok().flatten();
+
+        let crs_wkt = header
+            .vlrs()
+            .iter()
+            .chain(header.evlrs().iter())
+            .find(|vlr| vlr.user_id == PROJECTION_VLR_USER_ID && vlr.record_id == WKT_CRS_RECORD_ID)
+            .map(|vlr| {
+                String::from_utf8_lossy(&vlr.data)
+                    .trim_end_matches('\0')
+                    .to_owned()
+            });
+
+        let max_return_number = if header.version() >= Version::new(1, 4) {
+            15
+        } else {
+            5
+        };
+        let points_by_return = (1..=max_return_number)
+            .map(|return_number| {
+                header
+                    .number_of_points_by_return(return_number)
+                    .unwrap_or_default()
+            })
+            .collect();
+
         Ok(Self {
             bounds: las_bounds_to_pasture_bounds(header.bounds()),
             point_count: header.number_of_points() as usize,
@@ -1065,6 +1764,10 @@ impl TryFrom<&las::Header> for LASMetadata {
             classification_lookup_vlr: classification_lookup_vlr.map(Box::new),
             extra_bytes_vlr,
             text_area_description_vlr,
+            geotiff_crs,
+            crs_wkt,
+            points_by_return,
+            warnings,
         })
     }
 }
@@ -1076,3 +1779,424 @@ impl TryFrom<las::Header> for LASMetadata {
         (&value).try_into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::{BufReader, Cursor};
+
+    use chrono::NaiveDate;
+    use las::{raw::header::LargeFile, Builder, GpsTimeType};
+    use pasture_core::containers::{BorrowedBuffer, VectorBuffer};
+    use pasture_core::layout::attributes::POSITION_3D;
+
+    use super::*;
+    use crate::base::PointReader;
+    #[cfg(feature = "laz")]
+    use crate::las::get_test_laz_path;
+    use crate::las::{get_test_las_path, LASReadOptions, LASReader};
+
+    #[test]
+    fn test_read_is_compressed_las_file() -> Result<()> {
+        let mut read = BufReader::new(File::open(get_test_las_path(1))?);
+        assert!(!read_is_compressed_las_file(&mut read)?);
+        assert_eq!(0, read.stream_position()?);
+
+        #[cfg(feature = "laz")]
+        {
+            let mut read = BufReader::new(File::open(get_test_laz_path(1))?);
+            assert!(read_is_compressed_las_file(&mut read)?);
+            assert_eq!(0, read.stream_position()?);
+        }
+
+        let mut invalid_signature = std::fs::read(get_test_las_path(1))?;
+        invalid_signature[3] = b'X';
+        let error = read_is_compressed_las_file(&mut Cursor::new(invalid_signature)).unwrap_err();
+        assert!(error.to_string().contains("offset 0"), "{}", error);
+
+        let mut invalid_point_format = std::fs::read(get_test_las_path(1))?;
+        invalid_point_format[POINT_DATA_RECORD_FORMAT_OFFSET as usize] = 42;
+        let error =
+            read_is_compressed_las_file(&mut Cursor::new(invalid_point_format)).unwrap_err();
+        assert!(error.to_string().contains("offset 104"), "{}", error);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "laz")]
+    #[test]
+    fn test_las_reader_opens_misnamed_laz_file() -> Result<()> {
+        use crate::las::test_data_point_count;
+        use scopeguard::defer;
+
+        let mut test_file_path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_file_path.push("test_misnamed_laz_file.las");
+        std::fs::copy(get_test_laz_path(1), &test_file_path)?;
+        defer! {
+            std::fs::remove_file(&test_file_path).expect("Removing test file failed!");
+        }
+
+        let mut reader = LASReader::from_path(&test_file_path, false)?;
+        let points = reader.read_all::<VectorBuffer>()?;
+        assert_eq!(test_data_point_count(), points.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_metadata_header_accessors() -> Result<()> {
+        let mut builder = Builder::from((1, 4));
+        builder.file_source_id = 42;
+        builder.system_identifier = "pasture test".to_owned();
+        builder.generating_software = "pasture-io".to_owned();
+        builder.date = NaiveDate::from_yo_opt(2021, 123);
+        builder.gps_time_type = GpsTimeType::Standard;
+        builder.transforms = Vector {
+            x: Transform {
+                scale: 0.01,
+                offset: 100.0,
+            },
+            y: Transform {
+                scale: 0.02,
+                offset: 200.0,
+            },
+            z: Transform {
+                scale: 0.001,
+                offset: -5.0,
+            },
+        };
+        let metadata = LASMetadata::try_from(builder.into_header()?)?;
+
+        assert_eq!(Some(42), metadata.file_source_id());
+        assert_eq!(Some("pasture test"), metadata.system_identifier());
+        assert_eq!(Some("pasture-io"), metadata.generating_software());
+        assert_eq!(Some(123), metadata.file_creation_day_of_year());
+        assert_eq!(Some(2021), metadata.file_creation_year());
+        assert_eq!(
+            Some(1),
+            metadata.global_encoding().map(|encoding| encoding & 1),
+            "GPS time type bit should be set"
+        );
+        assert_eq!(Some(GpsTimeType::Standard), metadata.gps_time_type());
+        let transforms = metadata.transforms().expect("Transforms should be present");
+        assert_eq!(0.02, transforms.y.scale);
+        assert_eq!(-5.0, transforms.z.offset);
+        assert_eq!(Some(Vector3::new(0.01, 0.02, 0.001)), metadata.scale());
+        assert_eq!(Some(Vector3::new(100.0, 200.0, -5.0)), metadata.offset());
+
+        let file_source_id = metadata
+            .get_named_field(named_fields::FILE_SOURCE_I_D)
+            .and_then(|field| field.downcast::<u16>().ok());
+        assert_eq!(Some(Box::new(42)), file_source_id);
+        let scale = metadata
+            .get_named_field(named_fields::SCALE)
+            .and_then(|field| field.downcast::<Vector3<f64>>().ok());
+        assert_eq!(Some(Box::new(Vector3::new(0.01, 0.02, 0.001))), scale);
+
+        let metadata_without_header = LASMetadata::new(metadata.bounds, 0, metadata.point_format());
+        assert_eq!(None, metadata_without_header.file_source_id());
+        assert!(metadata_without_header.transforms().is_none());
+        assert!(metadata_without_header.scale().is_none());
+        assert!(metadata_without_header.offset().is_none());
+        assert!(metadata_without_header.gps_time_type().is_none());
+
+        Ok(())
+    }
+
+    fn geotiff_vlr(record_id: u16, data: Vec<u8>) -> Vlr {
+        Vlr {
+            user_id: PROJECTION_VLR_USER_ID.to_owned(),
+            record_id,
+            description: Default::default(),
+            data,
+        }
+    }
+
+    fn geo_key_directory_data(keys: &[[u16; 4]]) -> Vec<u8> {
+        let mut shorts = vec![1, 1, 0, keys.len() as u16];
+        shorts.extend(keys.iter().flatten());
+        shorts
+            .iter()
+            .flat_map(|short| short.to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn test_las_metadata_scale_and_offset_round_trip() -> Result<()> {
+        let path = get_test_las_path(0);
+        let mut reader = LASReader::from_path(&path, false)?;
+        let world_positions = reader
+            .read::<VectorBuffer>(reader.remaining_points())?
+            .view_attribute::<Vector3<f64>>(&POSITION_3D)
+            .into_iter()
+            .collect::<Vec<_>>();
+        let scale = reader
+            .las_metadata()
+            .scale()
+            .expect("Scale should be present");
+        let offset = reader
+            .las_metadata()
+            .offset()
+            .expect("Offset should be present");
+
+        let local_options = LASReadOptions {
+            convert_positions_to_world_space: false,
+            ..Default::default()
+        };
+        let mut local_reader = LASReader::from_path_with_options(&path, local_options)?;
+        let local_positions = local_reader
+            .read::<VectorBuffer>(local_reader.remaining_points())?
+            .view_attribute::<Vector3<i32>>(
+                &POSITION_3D.with_custom_datatype(PointAttributeDataType::Vec3i32),
+            )
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        let requantized_positions = world_positions
+            .iter()
+            .map(|world_position| {
+                (world_position - offset)
+                    .component_div(&scale)
+                    .map(|coordinate| coordinate.round() as i32)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(local_positions, requantized_positions);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_metadata_geotiff_crs() -> Result<()> {
+        let mut builder = Builder::from((1, 2));
+        builder.vlrs.push(geotiff_vlr(
+            GeoTiffCrs::GEO_KEY_DIRECTORY_RECORD_ID,
+            geo_key_directory_data(&[[1024, 0, 1, 1], [3072, 0, 1, 25832], [4096, 0, 1, 5783]]),
+        ));
+        builder.vlrs.push(geotiff_vlr(
+            GeoTiffCrs::GEO_DOUBLE_PARAMS_RECORD_ID,
+            1.5f64.to_le_bytes().to_vec(),
+        ));
+        let metadata = LASMetadata::try_from(builder.into_header()?)?;
+
+        let crs = metadata.crs().expect("GeoTIFF CRS should be present");
+        assert_eq!(3, crs.keys().len());
+        assert_eq!(Some(25832), crs.horizontal_epsg_code());
+        assert_eq!(Some(5783), crs.vertical_epsg_code());
+        assert_eq!(&[1.5], crs.double_params());
+        assert_eq!("", crs.ascii_params());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_metadata_malformed_geotiff_crs() -> Result<()> {
+        let mut builder = Builder::from((1, 2));
+        // States 2 keys but only contains one
+        let mut data = geo_key_directory_data(&[[3072, 0, 1, 25832]]);
+        data[6] = 2;
+        builder
+            .vlrs
+            .push(geotiff_vlr(GeoTiffCrs::GEO_KEY_DIRECTORY_RECORD_ID, data));
+        let metadata = LASMetadata::try_from(builder.into_header()?)?;
+        assert!(metadata.crs().is_none());
+
+        let metadata = LASMetadata::try_from(Builder::from((1, 2)).into_header()?)?;
+        assert!(metadata.crs().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_metadata_crs_wkt() -> Result<()> {
+        const WKT: &str = r#"PROJCS["ETRS89 / UTM zone 32N"]"#;
+        let mut wkt_data = WKT.as_bytes().to_vec();
+        wkt_data.extend_from_slice(&[0, 0]);
+
+        let mut builder = Builder::from((1, 4));
+        builder
+            .vlrs
+            .push(geotiff_vlr(WKT_CRS_RECORD_ID, wkt_data.clone()));
+        let metadata = LASMetadata::try_from(builder.into_header()?)?;
+        assert_eq!(Some(WKT), metadata.crs_wkt());
+
+        let mut builder = Builder::from((1, 4));
+        builder.evlrs.push(geotiff_vlr(WKT_CRS_RECORD_ID, wkt_data));
+        let metadata = LASMetadata::try_from(builder.into_header()?)?;
+        assert_eq!(Some(WKT), metadata.crs_wkt());
+
+        let metadata = LASMetadata::try_from(Builder::from((1, 4)).into_header()?)?;
+        assert_eq!(None, metadata.crs_wkt());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_geotiff_crs_from_epsg_code() -> Result<()> {
+        let mut builder = Builder::from((1, 2));
+        builder
+            .vlrs
+            .extend(GeoTiffCrs::from_epsg_code(25832)?.to_vlrs());
+        let metadata = LASMetadata::try_from(builder.into_header()?)?;
+        let crs = metadata.crs().expect("GeoTIFF CRS should be present");
+        assert_eq!(Some(25832), crs.horizontal_epsg_code());
+        assert_eq!(Some(1), crs.short_value(1024));
+
+        let mut builder = Builder::from((1, 2));
+        builder
+            .vlrs
+            .extend(GeoTiffCrs::from_epsg_code(4326)?.to_vlrs());
+        let metadata = LASMetadata::try_from(builder.into_header()?)?;
+        let crs = metadata.crs().expect("GeoTIFF CRS should be present");
+        assert_eq!(Some(4326), crs.horizontal_epsg_code());
+        assert_eq!(Some(2), crs.short_value(1024));
+
+        assert!(GeoTiffCrs::from_epsg_code(100_000).is_err());
+        assert!(GeoTiffCrs::from_epsg_code(32767).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_crs_wkt_vlr() -> Result<()> {
+        const WKT: &str = r#"PROJCS["ETRS89 / UTM zone 32N"]"#;
+        let mut builder = Builder::from((1, 4));
+        builder.vlrs.push(crs_wkt_vlr(WKT));
+        let metadata = LASMetadata::try_from(builder.into_header()?)?;
+        assert_eq!(Some(WKT), metadata.crs_wkt());
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_metadata_find_vlr() -> Result<()> {
+        let vendor_vlr = |record_id: u16| Vlr {
+            user_id: "TerraScan".to_owned(),
+            record_id,
+            description: "Trajectory".to_owned(),
+            data: vec![1, 2, 3, 4],
+        };
+        let mut builder = Builder::from((1, 4));
+        builder.vlrs.push(vendor_vlr(1));
+        builder.evlrs.push(vendor_vlr(2));
+        let metadata = LASMetadata::try_from(builder.into_header()?)?;
+
+        assert_eq!(1, metadata.vlrs().len());
+        assert_eq!(1, metadata.evlrs().len());
+        assert_eq!(Some(&vendor_vlr(1)), metadata.find_vlr("TerraScan", 1));
+        assert_eq!(Some(&vendor_vlr(2)), metadata.find_vlr("TerraScan", 2));
+        assert_eq!(None, metadata.find_vlr("TerraScan", 3));
+        assert_eq!(None, metadata.find_vlr("LASF_Spec", 1));
+
+        let metadata_without_header = LASMetadata::new(metadata.bounds, 0, metadata.point_format());
+        assert!(metadata_without_header.vlrs().is_empty());
+        assert!(metadata_without_header.find_vlr("TerraScan", 1).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_metadata_typed_vlr_accessors() -> Result<()> {
+        let spec_vlr = |record_id: u16, data: Vec<u8>| Vlr {
+            user_id: KNOWN_VLR_USER_ID.to_owned(),
+            record_id,
+            description: Default::default(),
+            data,
+        };
+        let mut classification_lookup_data = vec![0; 256 * 16];
+        classification_lookup_data[0] = 2;
+        classification_lookup_data[1..7].copy_from_slice(b"Ground");
+        classification_lookup_data[16] = 6;
+        classification_lookup_data[17..25].copy_from_slice(b"Building");
+        let mut waveform_descriptor_data = vec![8, 0];
+        waveform_descriptor_data.extend_from_slice(&[0; 24]);
+
+        let mut builder = Builder::from((1, 4));
+        builder.vlrs.push(spec_vlr(
+            ClassificationLookup::RECORD_ID,
+            classification_lookup_data,
+        ));
+        builder.vlrs.push(spec_vlr(
+            TextAreaDescription::RECORD_ID,
+            b"Survey 2021\0".to_vec(),
+        ));
+        builder
+            .vlrs
+            .push(spec_vlr(SUPERSEDED_VLR_RECORD_ID, vec![1, 2, 3]));
+        builder.vlrs.push(spec_vlr(101, waveform_descriptor_data));
+        let metadata = LASMetadata::try_from(builder.into_header()?)?;
+
+        assert_eq!(
+            Some(vec![(2, "Ground".to_owned()), (6, "Building".to_owned())]),
+            metadata.classification_lookup()?
+        );
+        assert_eq!(
+            Some("Survey 2021".to_owned()),
+            metadata.text_area_description()?
+        );
+        assert_eq!(
+            vec![&spec_vlr(SUPERSEDED_VLR_RECORD_ID, vec![1, 2, 3])],
+            metadata.superseded_vlrs()
+        );
+        let waveform_descriptors = metadata.waveform_packet_descriptors()?;
+        assert_eq!(1, waveform_descriptors.len());
+        assert_eq!(8, waveform_descriptors[&2].bits_per_sample);
+
+        let metadata = LASMetadata::try_from(Builder::from((1, 4)).into_header()?)?;
+        assert_eq!(None, metadata.classification_lookup()?);
+        assert_eq!(None, metadata.text_area_description()?);
+        assert!(metadata.superseded_vlrs().is_empty());
+        assert!(metadata.waveform_packet_descriptors()?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_metadata_malformed_well_known_vlrs() -> Result<()> {
+        let mut builder = Builder::from((1, 4));
+        builder.vlrs.push(Vlr {
+            user_id: KNOWN_VLR_USER_ID.to_owned(),
+            record_id: ClassificationLookup::RECORD_ID,
+            description: Default::default(),
+            data: vec![0; 10],
+        });
+        builder.vlrs.push(Vlr {
+            user_id: KNOWN_VLR_USER_ID.to_owned(),
+            record_id: TextAreaDescription::RECORD_ID,
+            description: Default::default(),
+            data: vec![0xFF, 0xFE],
+        });
+        // Malformed payloads must not prevent opening the file
+        let metadata = LASMetadata::try_from(builder.into_header()?)?;
+        assert_eq!(2, metadata.warnings().len());
+        assert!(metadata.classification_lookup_vlr().is_none());
+        assert!(metadata.classification_lookup().is_err());
+        assert!(metadata.text_area_description_vlr().is_none());
+        assert!(metadata.text_area_description().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_point_count_from_raw_header() -> Result<()> {
+        const EXTENDED_POINT_COUNT: u64 = 5_000_000_000;
+        let mut raw_header = Builder::from((1, 4)).into_header()?.into_raw()?;
+        raw_header.number_of_point_records = 0;
+        raw_header.large_file = Some(LargeFile {
+            number_of_point_records: EXTENDED_POINT_COUNT,
+            number_of_points_by_return: Default::default(),
+        });
+        assert_eq!(
+            EXTENDED_POINT_COUNT,
+            point_count_from_raw_header(&raw_header)
+        );
+
+        let mut metadata = LASMetadata::try_from(Builder::new(raw_header)?.into_header()?)?;
+        metadata.set_point_count(EXTENDED_POINT_COUNT)?;
+        assert_eq!(EXTENDED_POINT_COUNT as usize, metadata.point_count());
+
+        let mut raw_header = Builder::from((1, 2)).into_header()?.into_raw()?;
+        raw_header.number_of_point_records = 42;
+        assert_eq!(42, point_count_from_raw_header(&raw_header));
+
+        Ok(())
+    }
+}
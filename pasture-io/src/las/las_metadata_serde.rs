@@ -0,0 +1,514 @@
+use std::convert::{TryFrom, TryInto};
+
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use las::{point::Format, Builder, Header, Vlr};
+use pasture_core::{math::AABB, meta::Metadata, nalgebra::Vector3};
+use serde::{de::Error as _, ser::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{
+    write_rust_string_into_las_ascii_array, ClassificationLookup, ExtraBytesVlr, LASMetadata,
+    TextAreaDescription, KNOWN_VLR_USER_ID, PROJECTION_VLR_USER_ID,
+    WAVEFORM_PACKET_DESCRIPTOR_FIRST_RECORD_ID,
+};
+
+/// Serializes and deserializes a `LASMetadata` without the payloads of most VLRs and EVLRs. Use this together with
+/// `#[serde(with = "pasture_io::las::las_metadata_without_vlr_payloads")]` to keep large records such as spatial
+/// indices or waveform data out of serialized metadata.
+///
+/// The user ID, record ID, description and payload length of every record are still serialized. Only the payloads of
+/// the records that `LASMetadata` interprets itself are kept, i.e. the Classification Lookup, Text Area Description,
+/// Extra Bytes and Waveform Packet Descriptor VLRs as well as all `LASF_Projection` records, so that the point format,
+/// the extra bytes and the CRS of deserialized metadata are unchanged. All other records are deserialized with an
+/// empty payload
+pub mod las_metadata_without_vlr_payloads {
+    use serde::{ser::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::{SerializedLASMetadata, VlrPayloads};
+    use crate::las::LASMetadata;
+
+    /// Serializes `metadata` without the payloads of most VLRs and EVLRs
+    pub fn serialize<S: Serializer>(
+        metadata: &LASMetadata,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        SerializedLASMetadata::from_metadata(metadata, VlrPayloads::InterpretedOnly)
+            .map_err(|why| S::Error::custom(format!("{:#}", why)))?
+            .serialize(serializer)
+    }
+
+    /// Deserializes a `LASMetadata`. This accepts metadata that was serialized with and without VLR payloads
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<LASMetadata, D::Error> {
+        LASMetadata::deserialize(deserializer)
+    }
+}
+
+/// `LASMetadata` is serialized with the payloads of all VLRs and EVLRs encoded as base64. Deserialized metadata contains
+/// a raw LAS header that is rebuilt from the serialized values, so all accessors of `LASMetadata` work as they do for
+/// metadata that was read from a file
+impl Serialize for LASMetadata {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializedLASMetadata::from_metadata(self, VlrPayloads::All)
+            .map_err(|why| S::Error::custom(format!("{:#}", why)))?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for LASMetadata {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        SerializedLASMetadata::deserialize(deserializer)?
+            .try_into()
+            .map_err(|why: anyhow::Error| D::Error::custom(format!("{:#}", why)))
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum VlrPayloads {
+    All,
+    InterpretedOnly,
+}
+
+/// Binary data that is serialized as a base64 string
+#[derive(Clone, Debug, Default)]
+struct Base64Data(Vec<u8>);
+
+impl Serialize for Base64Data {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(&self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for Base64Data {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        STANDARD
+            .decode(encoded)
+            .map(Self)
+            .map_err(|why| D::Error::custom(format!("Invalid base64 data: {}", why)))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedPointFormat {
+    format: u8,
+    extra_bytes: u16,
+    is_compressed: bool,
+}
+
+impl TryFrom<Format> for SerializedPointFormat {
+    type Error = anyhow::Error;
+
+    fn try_from(format: Format) -> Result<Self> {
+        Ok(Self {
+            format: format.to_u8().context("Invalid LAS point format")?,
+            extra_bytes: format.extra_bytes,
+            is_compressed: format.is_compressed,
+        })
+    }
+}
+
+impl TryFrom<SerializedPointFormat> for Format {
+    type Error = anyhow::Error;
+
+    fn try_from(value: SerializedPointFormat) -> Result<Self> {
+        let mut format = Format::new(value.format).context("Invalid LAS point format")?;
+        format.extra_bytes = value.extra_bytes;
+        format.is_compressed = value.is_compressed;
+        Ok(format)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedVlr {
+    user_id: String,
+    record_id: u16,
+    description: String,
+    length: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    data: Option<Base64Data>,
+}
+
+impl SerializedVlr {
+    fn new(vlr: &Vlr, payloads: VlrPayloads) -> Self {
+        let data = match payloads {
+            VlrPayloads::All => Some(Base64Data(vlr.data.clone())),
+            VlrPayloads::InterpretedOnly if is_interpreted_vlr(vlr) => {
+                Some(Base64Data(vlr.data.clone()))
+            }
+            VlrPayloads::InterpretedOnly => None,
+        };
+        Self {
+            user_id: vlr.user_id.clone(),
+            record_id: vlr.record_id,
+            description: vlr.description.clone(),
+            length: vlr.data.len(),
+            data,
+        }
+    }
+}
+
+impl From<SerializedVlr> for Vlr {
+    fn from(value: SerializedVlr) -> Self {
+        Vlr {
+            user_id: value.user_id,
+            record_id: value.record_id,
+            description: value.description,
+            data: value.data.unwrap_or_default().0,
+        }
+    }
+}
+
+/// Is the given VLR interpreted by `LASMetadata`?
+fn is_interpreted_vlr(vlr: &Vlr) -> bool {
+    if vlr.user_id == PROJECTION_VLR_USER_ID {
+        return true;
+    }
+    // The descriptor indices 1 to 255 are stored in 255 consecutive record IDs
+    let waveform_packet_descriptor_record_ids = WAVEFORM_PACKET_DESCRIPTOR_FIRST_RECORD_ID
+        ..=WAVEFORM_PACKET_DESCRIPTOR_FIRST_RECORD_ID + 254;
+    vlr.user_id == KNOWN_VLR_USER_ID
+        && (vlr.record_id == ClassificationLookup::RECORD_ID
+            || vlr.record_id == TextAreaDescription::RECORD_ID
+            || vlr.record_id == ExtraBytesVlr::RECORD_ID
+            || waveform_packet_descriptor_record_ids.contains(&vlr.record_id))
+}
+
+/// The values of a LAS header that are not already part of the `LASMetadata` itself. The bounds and the number of points
+/// by return of the header are taken from the `LASMetadata`
+#[derive(Serialize, Deserialize)]
+struct SerializedLASHeader {
+    version: [u8; 2],
+    file_source_id: u16,
+    global_encoding: u16,
+    guid: [u8; 16],
+    system_identifier: String,
+    generating_software: String,
+    file_creation_day_of_year: u16,
+    file_creation_year: u16,
+    point_data_record_format: u8,
+    point_data_record_length: u16,
+    number_of_point_records: u64,
+    scale: Vector3<f64>,
+    offset: Vector3<f64>,
+    #[serde(default)]
+    padding: Base64Data,
+    #[serde(default)]
+    vlr_padding: Base64Data,
+    #[serde(default)]
+    vlrs: Vec<SerializedVlr>,
+    #[serde(default)]
+    evlrs: Vec<SerializedVlr>,
+}
+
+impl SerializedLASHeader {
+    fn new(header: &Header, payloads: VlrPayloads) -> Result<Self> {
+        let raw_header = header
+            .clone()
+            .into_raw()
+            .context("Could not convert LAS header")?;
+        Ok(Self {
+            version: [raw_header.version.major, raw_header.version.minor],
+            file_source_id: raw_header.file_source_id,
+            global_encoding: raw_header.global_encoding,
+            guid: raw_header.guid,
+            system_identifier: header.system_identifier().to_owned(),
+            generating_software: header.generating_software().to_owned(),
+            file_creation_day_of_year: raw_header.file_creation_day_of_year,
+            file_creation_year: raw_header.file_creation_year,
+            point_data_record_format: raw_header.point_data_record_format,
+            point_data_record_length: raw_header.point_data_record_length,
+            number_of_point_records: header.number_of_points(),
+            scale: Vector3::new(
+                raw_header.x_scale_factor,
+                raw_header.y_scale_factor,
+                raw_header.z_scale_factor,
+            ),
+            offset: Vector3::new(
+                raw_header.x_offset,
+                raw_header.y_offset,
+                raw_header.z_offset,
+            ),
+            padding: Base64Data(header.padding().to_vec()),
+            vlr_padding: Base64Data(header.vlr_padding().to_vec()),
+            vlrs: header
+                .vlrs()
+                .iter()
+                .map(|vlr| SerializedVlr::new(vlr, payloads))
+                .collect(),
+            evlrs: header
+                .evlrs()
+                .iter()
+                .map(|evlr| SerializedVlr::new(evlr, payloads))
+                .collect(),
+        })
+    }
+
+    fn into_las_header(self, bounds: &AABB<f64>, points_by_return: &[u64]) -> Result<Header> {
+        // Start from a valid raw header for the version, so that all values that are not serialized (e.g. the header
+        // size) are consistent
+        let mut raw_header = Builder::from((self.version[0], self.version[1]))
+            .into_header()
+            .and_then(|header| header.into_raw())
+            .context("Invalid LAS version")?;
+        raw_header.file_source_id = self.file_source_id;
+        raw_header.global_encoding = self.global_encoding;
+        raw_header.guid = self.guid;
+        raw_header.system_identifier = [0; 32];
+        write_rust_string_into_las_ascii_array(
+            &self.system_identifier,
+            &mut raw_header.system_identifier,
+        );
+        raw_header.generating_software = [0; 32];
+        write_rust_string_into_las_ascii_array(
+            &self.generating_software,
+            &mut raw_header.generating_software,
+        );
+        raw_header.file_creation_day_of_year = self.file_creation_day_of_year;
+        raw_header.file_creation_year = self.file_creation_year;
+        raw_header.point_data_record_format = self.point_data_record_format;
+        raw_header.point_data_record_length = self.point_data_record_length;
+        raw_header.x_scale_factor = self.scale.x;
+        raw_header.y_scale_factor = self.scale.y;
+        raw_header.z_scale_factor = self.scale.z;
+        raw_header.x_offset = self.offset.x;
+        raw_header.y_offset = self.offset.y;
+        raw_header.z_offset = self.offset.z;
+        raw_header.min_x = bounds.min().x;
+        raw_header.min_y = bounds.min().y;
+        raw_header.min_z = bounds.min().z;
+        raw_header.max_x = bounds.max().x;
+        raw_header.max_y = bounds.max().y;
+        raw_header.max_z = bounds.max().z;
+        raw_header.header_size = raw_header
+            .header_size
+            .checked_add(
+                self.padding
+                    .0
+                    .len()
+                    .try_into()
+                    .context("LAS header padding is too large")?,
+            )
+            .context("LAS header padding is too large")?;
+        raw_header.padding = self.padding.0;
+
+        // Same rules as for writing LAS files: The legacy point counts are zero for extended point formats and for
+        // files with more than u32::MAX points
+        let format = Format::new(self.point_data_record_format & 0b0011_1111)
+            .context("Invalid LAS point format")?;
+        let legacy_point_count = if format.is_extended {
+            None
+        } else {
+            u32::try_from(self.number_of_point_records).ok()
+        };
+        raw_header.number_of_point_records = legacy_point_count.unwrap_or_default();
+        for (legacy_count, count) in raw_header
+            .number_of_points_by_return
+            .iter_mut()
+            .zip(points_by_return)
+        {
+            *legacy_count = legacy_point_count
+                .and_then(|_| u32::try_from(*count).ok())
+                .unwrap_or_default();
+        }
+        if let Some(large_file) = raw_header.large_file.as_mut() {
+            large_file.number_of_point_records = self.number_of_point_records;
+            for (large_count, count) in large_file
+                .number_of_points_by_return
+                .iter_mut()
+                .zip(points_by_return)
+            {
+                *large_count = *count;
+            }
+        }
+
+        let mut builder = Builder::new(raw_header).context("Invalid LAS header")?;
+        builder.vlrs = self.vlrs.into_iter().map(Vlr::from).collect();
+        builder.evlrs = self.evlrs.into_iter().map(Vlr::from).collect();
+        builder.vlr_padding = self.vlr_padding.0;
+        builder.into_header().context("Invalid LAS header")
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedLASMetadata {
+    bounds: AABB<f64>,
+    point_count: usize,
+    point_format: SerializedPointFormat,
+    #[serde(default)]
+    points_by_return: Vec<u64>,
+    #[serde(default)]
+    warnings: Vec<String>,
+    #[serde(default)]
+    header: Option<SerializedLASHeader>,
+}
+
+impl SerializedLASMetadata {
+    fn from_metadata(metadata: &LASMetadata, payloads: VlrPayloads) -> Result<Self> {
+        Ok(Self {
+            bounds: metadata.bounds().context("LASMetadata without bounds")?,
+            point_count: metadata.point_count(),
+            point_format: metadata.point_format().try_into()?,
+            points_by_return: metadata.points_by_return().to_vec(),
+            warnings: metadata.warnings().to_vec(),
+            header: metadata
+                .raw_las_header()
+                .map(|header| SerializedLASHeader::new(header, payloads))
+                .transpose()?,
+        })
+    }
+}
+
+impl TryFrom<SerializedLASMetadata> for LASMetadata {
+    type Error = anyhow::Error;
+
+    fn try_from(value: SerializedLASMetadata) -> Result<Self> {
+        let mut metadata = match value.header {
+            Some(header) => {
+                let header = header.into_las_header(&value.bounds, &value.points_by_return)?;
+                LASMetadata::try_from(header)?
+            }
+            None => LASMetadata::new(
+                value.bounds,
+                value.point_count,
+                value.point_format.try_into()?,
+            ),
+        };
+        // The point count of the metadata can differ from the header, e.g. for truncated files
+        metadata.set_point_count(value.point_count as u64)?;
+        metadata.set_warnings(value.warnings);
+        Ok(metadata)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use las::GpsTimeType;
+
+    use super::*;
+    use crate::las::{get_test_las_path, LASReader, WKT_CRS_RECORD_ID};
+
+    fn assert_metadata_eq(expected: &LASMetadata, actual: &LASMetadata) {
+        assert_eq!(expected.bounds(), actual.bounds());
+        assert_eq!(expected.point_count(), actual.point_count());
+        assert_eq!(expected.point_format(), actual.point_format());
+        assert_eq!(expected.points_by_return(), actual.points_by_return());
+        assert_eq!(expected.warnings(), actual.warnings());
+        assert_eq!(
+            expected.raw_las_header().is_some(),
+            actual.raw_las_header().is_some()
+        );
+        assert_eq!(expected.file_source_id(), actual.file_source_id());
+        assert_eq!(expected.gps_time_type(), actual.gps_time_type());
+        assert_eq!(expected.global_encoding(), actual.global_encoding());
+        assert_eq!(expected.system_identifier(), actual.system_identifier());
+        assert_eq!(expected.generating_software(), actual.generating_software());
+        assert_eq!(
+            expected.file_creation_day_of_year(),
+            actual.file_creation_day_of_year()
+        );
+        assert_eq!(expected.file_creation_year(), actual.file_creation_year());
+        assert_eq!(expected.scale(), actual.scale());
+        assert_eq!(expected.offset(), actual.offset());
+        assert_eq!(expected.header_padding(), actual.header_padding());
+        assert_eq!(expected.vlr_padding(), actual.vlr_padding());
+        assert_eq!(expected.crs_wkt(), actual.crs_wkt());
+        assert_eq!(
+            expected.extra_bytes_vlr().map(|vlr| vlr.entries().len()),
+            actual.extra_bytes_vlr().map(|vlr| vlr.entries().len())
+        );
+        assert_eq!(
+            expected
+                .raw_las_header()
+                .map(|header| header.number_of_points()),
+            actual
+                .raw_las_header()
+                .map(|header| header.number_of_points())
+        );
+    }
+
+    #[test]
+    fn test_las_metadata_serde_round_trip() -> Result<()> {
+        for format in 0..=10 {
+            let reader = LASReader::from_path(get_test_las_path(format), false)?;
+            let expected_metadata = reader.las_metadata().clone();
+
+            let json = serde_json::to_string(&expected_metadata)?;
+            let metadata: LASMetadata = serde_json::from_str(&json)?;
+
+            assert_metadata_eq(&expected_metadata, &metadata);
+            assert_eq!(expected_metadata.vlrs().len(), metadata.vlrs().len());
+            for (expected_vlr, vlr) in expected_metadata.vlrs().iter().zip(metadata.vlrs()) {
+                assert_eq!(expected_vlr.user_id, vlr.user_id);
+                assert_eq!(expected_vlr.record_id, vlr.record_id);
+                assert_eq!(expected_vlr.description, vlr.description);
+                assert_eq!(expected_vlr.data, vlr.data);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_metadata_serde_without_header() -> Result<()> {
+        let bounds = AABB::from_min_max([0.0, 1.0, 2.0].into(), [10.0, 11.0, 12.0].into());
+        let expected_metadata = LASMetadata::new(bounds, 1024, Format::new(7)?);
+
+        let json = serde_json::to_string(&expected_metadata)?;
+        let metadata: LASMetadata = serde_json::from_str(&json)?;
+
+        assert_metadata_eq(&expected_metadata, &metadata);
+        assert!(metadata.raw_las_header().is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_metadata_serde_without_vlr_payloads() -> Result<()> {
+        #[derive(Serialize, Deserialize)]
+        struct IndexEntry {
+            #[serde(with = "las_metadata_without_vlr_payloads")]
+            metadata: LASMetadata,
+        }
+
+        const WKT: &str = "PROJCS[\"ETRS89 / UTM zone 32N\"]";
+        let mut builder = Builder::from((1, 4));
+        builder.gps_time_type = GpsTimeType::Standard;
+        builder.vlrs.push(Vlr {
+            user_id: "pasture".to_owned(),
+            record_id: 42,
+            description: "Custom VLR".to_owned(),
+            data: vec![1, 2, 3, 4],
+        });
+        builder.vlrs.push(Vlr {
+            user_id: PROJECTION_VLR_USER_ID.to_owned(),
+            record_id: WKT_CRS_RECORD_ID,
+            description: Default::default(),
+            data: WKT.as_bytes().to_vec(),
+        });
+        let expected_metadata = LASMetadata::try_from(builder.into_header()?)?;
+
+        let json = serde_json::to_string(&IndexEntry {
+            metadata: expected_metadata.clone(),
+        })?;
+        let entry: IndexEntry = serde_json::from_str(&json)?;
+        let metadata = entry.metadata;
+
+        assert_metadata_eq(&expected_metadata, &metadata);
+        assert_eq!(Some(WKT), metadata.crs_wkt());
+        let custom_vlr = metadata
+            .find_vlr("pasture", 42)
+            .expect("Custom VLR should be present");
+        assert_eq!("Custom VLR", custom_vlr.description);
+        assert!(custom_vlr.data.is_empty());
+
+        // Metadata without VLR payloads can be deserialized as regular metadata
+        let metadata: LASMetadata = serde_json::from_value(
+            serde_json::from_str::<serde_json::Value>(&json)?["metadata"].clone(),
+        )?;
+        assert_eq!(Some(WKT), metadata.crs_wkt());
+
+        Ok(())
+    }
+}
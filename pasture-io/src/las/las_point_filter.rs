@@ -0,0 +1,282 @@
+use std::convert::TryInto;
+
+use anyhow::{Context, Result};
+use pasture_core::containers::{
+    BorrowedMutBuffer, ExternalMemoryBuffer, InterleavedBuffer, OwningBuffer, VectorBuffer,
+};
+use pasture_core::layout::attributes::{CLASSIFICATION, GPS_TIME};
+use pasture_core::layout::conversion::BufferLayoutConverter;
+use pasture_core::layout::PointLayout;
+use pasture_core::math::AABB;
+use pasture_core::nalgebra::Point3;
+
+use super::{
+    point_layout_from_las_metadata, LASMetadata, OutOfBoundsHandling, ATTRIBUTE_BASIC_FLAGS,
+    ATTRIBUTE_EXTENDED_FLAGS,
+};
+
+/// Predicate on a single binary LAS point record
+pub(crate) type LASRecordPredicate = Box<dyn Fn(&[u8]) -> bool>;
+/// In-place modification of a single binary LAS point record
+pub(crate) type LASRecordModifier = Box<dyn Fn(&mut [u8])>;
+
+/// Filter on the return number of points, see [`LASReader::set_return_filter`](super::LASReader::set_return_filter)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ReturnFilter {
+    /// Accept all points
+    #[default]
+    All,
+    /// Accept only first returns, i.e. points with a return number of 1
+    FirstOnly,
+    /// Accept only last returns, i.e. points whose return number equals their number of returns
+    LastOnly,
+    /// Accept only points with the given return number
+    Specific(u8),
+}
+
+/// Filter criteria that the LAS readers evaluate on the binary point records, before any attribute conversion
+/// takes place. Points that don't match the filter are never written into the target buffer
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LASPointFilter {
+    /// Only accept points whose world-space position lies within these bounds
+    pub bounds: Option<AABB<f64>>,
+    /// Only accept points with one of these classifications. An empty list accepts all classifications
+    pub classifications: Vec<u8>,
+    /// Only accept points with matching return numbers
+    pub returns: ReturnFilter,
+    /// Only accept points whose GPS time lies within this inclusive range
+    pub gps_time_range: Option<(f64, f64)>,
+    /// Skip or clamp points outside of the header bounds, see [`OutOfBoundsHandling`]
+    pub out_of_bounds: OutOfBoundsHandling,
+}
+
+impl LASPointFilter {
+    /// Returns `true` if this filter accepts all points
+    pub fn is_empty(&self) -> bool {
+        self.bounds.is_none()
+            && self.classifications.is_empty()
+            && self.returns == ReturnFilter::All
+            && self.gps_time_range.is_none()
+            && self.out_of_bounds == OutOfBoundsHandling::Keep
+    }
+
+    /// Returns a predicate that evaluates this filter on binary LAS point records matching the given `metadata`
+    pub fn record_predicate(&self, metadata: &LASMetadata) -> Result<LASRecordPredicate> {
+        let mut predicates: Vec<LASRecordPredicate> = vec![];
+
+        if let Some(bounds) = self.bounds {
+            let transforms = *metadata
+                .raw_las_header()
+                .context("Missing LAS header")?
+                .transforms();
+            predicates.push(Box::new(move |record: &[u8]| {
+                let world_position = Point3::new(
+                    local_coordinate(record, 0) as f64 * transforms.x.scale + transforms.x.offset,
+                    local_coordinate(record, 1) as f64 * transforms.y.scale + transforms.y.offset,
+                    local_coordinate(record, 2) as f64 * transforms.z.scale + transforms.z.offset,
+                );
+                bounds.contains(&world_position)
+            }));
+        }
+
+        if self.out_of_bounds == OutOfBoundsHandling::Skip {
+            let local_bounds = local_header_bounds(metadata)?;
+            predicates.push(Box::new(move |record: &[u8]| {
+                (0..3).all(|index| {
+                    let (min, max) = local_bounds[index];
+                    (min..=max).contains(&local_coordinate(record, index))
+                })
+            }));
+        }
+
+        if !self.classifications.is_empty() {
+            let classification_offset = point_layout_from_las_metadata(metadata, true)?
+                .get_attribute(&CLASSIFICATION)
+                .expect("LAS point records always contain a classification")
+                .offset() as usize;
+            // In the legacy point formats 0-5, the upper three bits of the classification byte are the synthetic,
+            // key-point and withheld flags
+            let classification_mask = if metadata.point_format().is_extended {
+                0xFF
+            } else {
+                0x1F
+            };
+            let mut accepted_classifications = [false; 256];
+            for classification in &self.classifications {
+                accepted_classifications[*classification as usize] = true;
+            }
+            predicates.push(Box::new(move |record: &[u8]| {
+                let classification = record[classification_offset] & classification_mask;
+                accepted_classifications[classification as usize]
+            }));
+        }
+
+        if self.returns != ReturnFilter::All {
+            let is_extended = metadata.point_format().is_extended;
+            let flags_attribute = if is_extended {
+                &ATTRIBUTE_EXTENDED_FLAGS
+            } else {
+                &ATTRIBUTE_BASIC_FLAGS
+            };
+            let flags_offset = point_layout_from_las_metadata(metadata, true)?
+                .get_attribute(flags_attribute)
+                .expect("LAS point records always contain return numbers")
+                .offset() as usize;
+            // Return number and number of returns are stored in the first byte of the flags, using 3 bits each in
+            // the legacy formats and 4 bits each in the extended formats
+            let (bits_per_value, value_mask) = if is_extended { (4, 0b1111) } else { (3, 0b111) };
+            let returns = self.returns;
+            predicates.push(Box::new(move |record: &[u8]| {
+                let flags = record[flags_offset];
+                let return_number = flags & value_mask;
+                let number_of_returns = (flags >> bits_per_value) & value_mask;
+                match returns {
+                    ReturnFilter::All => true,
+                    ReturnFilter::FirstOnly => return_number == 1,
+                    ReturnFilter::LastOnly => return_number == number_of_returns,
+                    ReturnFilter::Specific(expected_return_number) => {
+                        return_number == expected_return_number
+                    }
+                }
+            }));
+        }
+
+        if let Some((min_gps_time, max_gps_time)) = self.gps_time_range {
+            let gps_time_offset = point_layout_from_las_metadata(metadata, true)?
+                .get_attribute(&GPS_TIME)
+                .context("LAS point format has no GPS time")?
+                .offset() as usize;
+            predicates.push(Box::new(move |record: &[u8]| {
+                let gps_time = f64::from_le_bytes(
+                    record[gps_time_offset..gps_time_offset + 8]
+                        .try_into()
+                        .unwrap(),
+                );
+                gps_time >= min_gps_time && gps_time <= max_gps_time
+            }));
+        }
+
+        Ok(Box::new(move |record: &[u8]| {
+            predicates.iter().all(|predicate| predicate(record))
+        }))
+    }
+
+    /// Returns a function that modifies the accepted binary LAS point records in place before they are converted, or
+    /// `None` if the records are used unchanged. Currently, this only clamps positions to the header bounds
+    pub fn record_modifier(&self, metadata: &LASMetadata) -> Result<Option<LASRecordModifier>> {
+        if self.out_of_bounds != OutOfBoundsHandling::ClampToHeaderBounds {
+            return Ok(None);
+        }
+        let local_bounds = local_header_bounds(metadata)?;
+        Ok(Some(Box::new(move |record: &mut [u8]| {
+            for (index, (min, max)) in local_bounds.iter().enumerate() {
+                let clamped_coordinate = local_coordinate(record, index).clamp(*min, *max);
+                record[index * 4..index * 4 + 4].copy_from_slice(&clamped_coordinate.to_le_bytes());
+            }
+        })))
+    }
+}
+
+/// Returns the local integer coordinate with the given `index` (0 = X, 1 = Y, 2 = Z) of a binary LAS point record. The
+/// local position is always the first field of a LAS point record, in all point formats
+fn local_coordinate(record: &[u8], index: usize) -> i32 {
+    let offset = index * 4;
+    i32::from_le_bytes(record[offset..offset + 4].try_into().unwrap())
+}
+
+/// Returns the inclusive `(min, max)` range of the local integer coordinates of the X, Y, and Z axis that lie within
+/// the header bounds of the LAS file. The header bounds are quantized by rounding, so all positions that round to
+/// the header bounds are considered inside
+fn local_header_bounds(metadata: &LASMetadata) -> Result<[(i32, i32); 3]> {
+    let header = metadata.raw_las_header().context("Missing LAS header")?;
+    let transforms = header.transforms();
+    let bounds = header.bounds();
+    let quantize = |value: f64, transform: &las_rs::Transform| -> i32 {
+        ((value - transform.offset) / transform.scale)
+            .round()
+            .clamp(i32::MIN as f64, i32::MAX as f64) as i32
+    };
+    let local_range = |min: f64, max: f64, transform: &las_rs::Transform| -> (i32, i32) {
+        let (local_min, local_max) = (quantize(min, transform), quantize(max, transform));
+        // Negative scale factors swap the order
+        (local_min.min(local_max), local_min.max(local_max))
+    };
+    Ok([
+        local_range(bounds.min.x, bounds.max.x, &transforms.x),
+        local_range(bounds.min.y, bounds.max.y, &transforms.y),
+        local_range(bounds.min.z, bounds.max.z, &transforms.z),
+    ])
+}
+
+/// Reads `count` binary LAS point records through `read_raw_points` and writes only those records for which
+/// `predicate` returns `true` into `point_buffer`. The accepted records are modified by `modifier` first, if it is
+/// set. If `converter` is `None`, `point_buffer` must have the `raw_las_layout`, otherwise the accepted records are
+/// converted using `converter`. Returns the number of accepted points
+pub(crate) fn read_filtered_las_points<'a, 'b, B: BorrowedMutBuffer<'a>>(
+    point_buffer: &'b mut B,
+    count: usize,
+    raw_las_layout: &PointLayout,
+    converter: Option<&BufferLayoutConverter>,
+    predicate: LASRecordPredicate,
+    modifier: Option<LASRecordModifier>,
+    mut read_raw_points: impl FnMut(&mut VectorBuffer, usize) -> Result<usize>,
+) -> Result<usize>
+where
+    'a: 'b,
+{
+    const CHUNK_BYTES: usize = 1 << 20; // 1 MiB
+    let size_of_point = raw_las_layout.size_of_point_entry() as usize;
+    let points_per_chunk = (CHUNK_BYTES / size_of_point).max(1);
+
+    let mut chunk =
+        VectorBuffer::with_capacity(points_per_chunk.min(count), raw_las_layout.clone());
+    let mut accepted_records = vec![];
+    let mut num_accepted_points = 0;
+    let mut points_remaining = count;
+    while points_remaining > 0 {
+        let points_in_chunk = points_remaining.min(points_per_chunk);
+        chunk.resize(points_in_chunk);
+        let points_read = read_raw_points(&mut chunk, points_in_chunk)?;
+        if points_read == 0 {
+            break;
+        }
+        points_remaining -= points_read;
+
+        accepted_records.clear();
+        for record in chunk
+            .get_point_range_ref(0..points_read)
+            .chunks_exact(size_of_point)
+            .filter(|record| predicate(record))
+        {
+            accepted_records.extend_from_slice(record);
+        }
+        let num_accepted_in_chunk = accepted_records.len() / size_of_point;
+        if num_accepted_in_chunk == 0 {
+            continue;
+        }
+        if let Some(modifier) = modifier.as_ref() {
+            accepted_records
+                .chunks_exact_mut(size_of_point)
+                .for_each(|record| modifier(record));
+        }
+
+        let target_range = num_accepted_points..(num_accepted_points + num_accepted_in_chunk);
+        match converter {
+            Some(converter) => {
+                let accepted_points =
+                    ExternalMemoryBuffer::new(&accepted_records[..], raw_las_layout.clone());
+                converter.convert_into_range(
+                    &accepted_points,
+                    0..num_accepted_in_chunk,
+                    point_buffer,
+                    target_range,
+                );
+            }
+            // Safe because without a converter, `point_buffer` has the exact binary layout of the LAS records
+            None => unsafe { point_buffer.set_point_range(target_range, &accepted_records) },
+        }
+        num_accepted_points += num_accepted_in_chunk;
+    }
+
+    Ok(num_accepted_points)
+}
@@ -0,0 +1,38 @@
+use std::fmt::Display;
+
+/// Error that is raised when a LAS file contains fewer point records than stated in its header, see
+/// [`LASReadOptions::strict_point_count`](super::LASReadOptions::strict_point_count). This error is also returned when
+/// reading points from an uncompressed LAS file whose stream ends before the last point record. In this case, all
+/// complete point records up to `available_point_count` are read, but the incomplete point record is never written
+/// into the point buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TruncatedLASFileError {
+    /// Number of point records according to the LAS header
+    pub point_count_in_header: u64,
+    /// Number of complete point records that are actually present in the file. This is also the index of the first
+    /// point record that is missing or incomplete
+    pub available_point_count: u64,
+    /// Number of bytes of the incomplete point record at index `available_point_count` that are present in the file.
+    /// Zero if the file ends exactly at a record boundary
+    pub bytes_in_partial_record: u64,
+}
+
+impl Display for TruncatedLASFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "LAS header states {} point records, but the file only contains {} complete point records",
+            self.point_count_in_header, self.available_point_count
+        )?;
+        if self.bytes_in_partial_record > 0 {
+            write!(
+                f,
+                " (the file ends {} bytes into point record {})",
+                self.bytes_in_partial_record, self.available_point_count
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for TruncatedLASFileError {}
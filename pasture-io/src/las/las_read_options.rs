@@ -0,0 +1,236 @@
+use serde::{Deserialize, Serialize};
+
+/// How the LAS readers convert the 16-bit color channels of a LAS file when reading them into a `COLOR_RGB` attribute
+/// with datatype `Vector3<u8>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum ColorConversion {
+    /// Keep the lower 8 bits of each color channel, like a regular cast from `u16` to `u8`
+    #[default]
+    Truncate,
+    /// Divide each color channel by 257, which maps the full 16-bit range `[0; 65535]` onto `[0; 255]`
+    DivideBy257,
+    /// Keep the upper 8 bits of each color channel. Most LAS producers store 8-bit colors by shifting them left by 8
+    /// bits, which this reverts exactly
+    ShiftBy8,
+}
+
+/// What the LAS readers do with points whose position lies outside of the bounds stated in the LAS header. Such points
+/// are often sentinel values, e.g. at `(0, 0, 0)`, that would otherwise distort all bounding box computations. The
+/// positions are compared to the header bounds with a tolerance of half the scale factor of the file, i.e. after
+/// quantizing the header bounds into the integer coordinates of the file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum OutOfBoundsHandling {
+    /// Read all points, regardless of their position
+    #[default]
+    Keep,
+    /// Skip points outside of the header bounds, like a filter. This reduces the number of points that `read_into`
+    /// returns
+    Skip,
+    /// Clamp the position of points outside of the header bounds to the header bounds. All other attributes of these
+    /// points are read unchanged
+    ClampToHeaderBounds,
+}
+
+/// Options that control how the LAS/LAZ readers (e.g. [`LASReader`](crate::las::LASReader)) read point data. The
+/// options can be set through [`LASReadOptions::builder`] and are serializable, options that are missing during
+/// deserialization keep their default value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LASReadOptions {
+    /// If `true`, the default `PointLayout` of the reader will exactly match the binary layout of the LAS point
+    /// records. See [`point_layout_from_las_point_format`](crate::las::point_layout_from_las_point_format) for more
+    /// information. Defaults to `false`
+    pub point_layout_matches_memory_layout: bool,
+    /// If `true`, positions are converted from the local integer coordinates of the LAS file into world-space
+    /// coordinates by applying the scale and offset from the LAS header. If `false`, the default `PointLayout`
+    /// of the reader stores the `POSITION_3D` attribute as `Vector3<i32>` values in local space, exactly as they
+    /// are stored in the file. This has no effect if `point_layout_matches_memory_layout` is `true`, since the
+    /// exact binary layout always stores positions in local space. Defaults to `true`
+    pub convert_positions_to_world_space: bool,
+    /// Controls what happens if an uncompressed LAS file contains fewer point records than stated in its header. If
+    /// `true`, opening the file fails with a [`TruncatedLASFileError`](crate::las::TruncatedLASFileError). If
+    /// `false`, the point count of the reader is clamped to the number of complete point records in the file and a
+    /// warning is added to the [`LASMetadata`](crate::las::LASMetadata). Defaults to `false`
+    pub strict_point_count: bool,
+    /// If `true`, the default `PointLayout` of the reader stores the scan angle of all point formats as
+    /// [`ATTRIBUTE_SCAN_ANGLE_DEGREES`](crate::las::ATTRIBUTE_SCAN_ANGLE_DEGREES), i.e. in degrees as `f32` values.
+    /// This replaces the `SCAN_ANGLE_RANK` attribute of the point formats 0-5 (whole degrees) and the `SCAN_ANGLE`
+    /// attribute of the point formats 6-10 (increments of 0.006 degrees). This has no effect if
+    /// `point_layout_matches_memory_layout` is `true`. Defaults to `false`
+    pub scan_angle_in_degrees: bool,
+    /// How colors are converted when reading them into a `COLOR_RGB` attribute with datatype `Vector3<u8>`. See
+    /// [`ColorConversion`] for the available options. Defaults to [`ColorConversion::Truncate`]
+    pub color_conversion: ColorConversion,
+    /// If `true`, intensities that are read into an `INTENSITY` attribute with datatype `f32` or `f64` are normalized
+    /// to the range `[0; 1]` by dividing them by `u16::MAX`. Otherwise, the raw intensity values are converted. This
+    /// does not affect the default `PointLayout` of the reader, which always stores intensities as `u16` values.
+    /// Defaults to `false`
+    pub normalize_intensity: bool,
+    /// What to do with points whose position lies outside of the bounds stated in the LAS header. See
+    /// [`OutOfBoundsHandling`] for the available options. Defaults to [`OutOfBoundsHandling::Keep`]
+    pub out_of_bounds_handling: OutOfBoundsHandling,
+    /// If `true`, LAZ readers skip chunks that fail to decompress instead of failing the whole read. The points of
+    /// skipped chunks are missing from the result of `read_into`, their ranges are available through
+    /// [`LASReader::skipped_point_ranges`](crate::las::LASReader::skipped_point_ranges) and as warnings in the
+    /// [`LASMetadata`](crate::las::LASMetadata). This requires a LAZ file whose chunks can be located, i.e. with
+    /// fixed-size chunks or with a chunk table. Has no effect on uncompressed LAS files. Defaults to `false`
+    pub skip_corrupt_laz_chunks: bool,
+}
+
+impl Default for LASReadOptions {
+    fn default() -> Self {
+        Self {
+            point_layout_matches_memory_layout: false,
+            convert_positions_to_world_space: true,
+            strict_point_count: false,
+            scan_angle_in_degrees: false,
+            color_conversion: ColorConversion::Truncate,
+            normalize_intensity: false,
+            out_of_bounds_handling: OutOfBoundsHandling::Keep,
+            skip_corrupt_laz_chunks: false,
+        }
+    }
+}
+
+impl LASReadOptions {
+    /// Returns a builder for `LASReadOptions`, starting from the default options
+    pub fn builder() -> LASReadOptionsBuilder {
+        LASReadOptionsBuilder::default()
+    }
+}
+
+/// Builder for [`LASReadOptions`]. All options that are not set keep their default value
+#[derive(Debug, Clone, Default)]
+pub struct LASReadOptionsBuilder {
+    options: LASReadOptions,
+}
+
+impl LASReadOptionsBuilder {
+    /// Sets [`LASReadOptions::point_layout_matches_memory_layout`]
+    pub fn point_layout_matches_memory_layout(
+        mut self,
+        point_layout_matches_memory_layout: bool,
+    ) -> Self {
+        self.options.point_layout_matches_memory_layout = point_layout_matches_memory_layout;
+        self
+    }
+
+    /// Sets [`LASReadOptions::convert_positions_to_world_space`]
+    pub fn convert_positions_to_world_space(
+        mut self,
+        convert_positions_to_world_space: bool,
+    ) -> Self {
+        self.options.convert_positions_to_world_space = convert_positions_to_world_space;
+        self
+    }
+
+    /// Sets [`LASReadOptions::strict_point_count`]
+    pub fn strict_point_count(mut self, strict_point_count: bool) -> Self {
+        self.options.strict_point_count = strict_point_count;
+        self
+    }
+
+    /// Sets [`LASReadOptions::scan_angle_in_degrees`]
+    pub fn scan_angle_in_degrees(mut self, scan_angle_in_degrees: bool) -> Self {
+        self.options.scan_angle_in_degrees = scan_angle_in_degrees;
+        self
+    }
+
+    /// Sets [`LASReadOptions::color_conversion`]
+    pub fn color_conversion(mut self, color_conversion: ColorConversion) -> Self {
+        self.options.color_conversion = color_conversion;
+        self
+    }
+
+    /// Sets [`LASReadOptions::normalize_intensity`]
+    pub fn normalize_intensity(mut self, normalize_intensity: bool) -> Self {
+        self.options.normalize_intensity = normalize_intensity;
+        self
+    }
+
+    /// Sets [`LASReadOptions::out_of_bounds_handling`]
+    pub fn out_of_bounds_handling(mut self, out_of_bounds_handling: OutOfBoundsHandling) -> Self {
+        self.options.out_of_bounds_handling = out_of_bounds_handling;
+        self
+    }
+
+    /// Sets [`LASReadOptions::skip_corrupt_laz_chunks`]
+    pub fn skip_corrupt_laz_chunks(mut self, skip_corrupt_laz_chunks: bool) -> Self {
+        self.options.skip_corrupt_laz_chunks = skip_corrupt_laz_chunks;
+        self
+    }
+
+    pub fn build(self) -> LASReadOptions {
+        self.options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use pasture_core::layout::{attributes::POSITION_3D, PointAttributeDataType};
+
+    use super::*;
+    use crate::base::PointReader;
+    use crate::las::{get_test_las_path, LASReader};
+
+    #[test]
+    fn test_las_read_options_builder() {
+        assert_eq!(LASReadOptions::default(), LASReadOptions::builder().build());
+
+        let options = LASReadOptions::builder()
+            .convert_positions_to_world_space(false)
+            .color_conversion(ColorConversion::ShiftBy8)
+            .out_of_bounds_handling(OutOfBoundsHandling::Skip)
+            .build();
+        let expected_options = LASReadOptions {
+            convert_positions_to_world_space: false,
+            color_conversion: ColorConversion::ShiftBy8,
+            out_of_bounds_handling: OutOfBoundsHandling::Skip,
+            ..Default::default()
+        };
+        assert_eq!(expected_options, options);
+    }
+
+    #[test]
+    fn test_las_read_options_serialization() -> Result<()> {
+        let options = LASReadOptions::builder()
+            .strict_point_count(true)
+            .scan_angle_in_degrees(true)
+            .color_conversion(ColorConversion::DivideBy257)
+            .build();
+        let json = serde_json::to_string(&options)?;
+        assert_eq!(options, serde_json::from_str::<LASReadOptions>(&json)?);
+
+        // Options that are missing keep their default value
+        let partial_options = serde_json::from_str::<LASReadOptions>(
+            r#"{ "normalize_intensity": true, "out_of_bounds_handling": "ClampToHeaderBounds" }"#,
+        )?;
+        let expected_options = LASReadOptions {
+            normalize_intensity: true,
+            out_of_bounds_handling: OutOfBoundsHandling::ClampToHeaderBounds,
+            ..Default::default()
+        };
+        assert_eq!(expected_options, partial_options);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_reader_with_options_from_builder() -> Result<()> {
+        let options = LASReadOptions::builder()
+            .convert_positions_to_world_space(false)
+            .build();
+        let reader = LASReader::from_path_with_options(get_test_las_path(0), options)?;
+        let position_attribute = reader
+            .get_default_point_layout()
+            .get_attribute_by_name(POSITION_3D.name())
+            .expect("LAS files always contain positions");
+        assert_eq!(
+            PointAttributeDataType::Vec3i32,
+            position_attribute.datatype()
+        );
+
+        Ok(())
+    }
+}
@@ -2,38 +2,64 @@ use std::{
     fs::File,
     io::{BufReader, Read, Seek},
 };
-use std::{io::SeekFrom, path::Path};
+use std::{
+    io::SeekFrom,
+    ops::Range,
+    path::{Path, PathBuf},
+};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use las_rs::Header;
 
-use crate::base::{PointReader, SeekToPoint};
-use pasture_core::{containers::BorrowedMutBuffer, layout::PointLayout, meta::Metadata};
+use crate::base::{skip_points_by_seeking, PointReader, ProgressCallback, SeekToPoint};
+use pasture_core::{
+    containers::{BorrowedMutBuffer, OwningBuffer},
+    layout::PointLayout,
+    math::AABB,
+    meta::Metadata,
+};
 
-use super::{path_is_compressed_las_file, LASMetadata, LASReaderBase, RawLASReader, RawLAZReader};
+#[cfg(feature = "laz")]
+use super::RawLAZReader;
+use super::{
+    point_layout_from_las_metadata, read_is_compressed_las_file, read_waveform_samples,
+    LASMetadata, LASReadOptions, LASReaderBase, LAXIndex, RawLASReader, ReturnFilter,
+    ValidationOptions, ValidationReport, WaveformSamples,
+};
 
-pub enum LASReaderFlavor<'a, T: Read + Seek + Send + 'a> {
+pub enum LASReaderFlavor<'a, T: Read + Seek + 'a> {
     LAS(RawLASReader<T>),
+    #[cfg(feature = "laz")]
     LAZ(RawLAZReader<'a, T>),
+    /// Never constructed, only keeps the lifetime `'a` in use if the `laz` feature is disabled
+    #[cfg(not(feature = "laz"))]
+    #[doc(hidden)]
+    Unreachable(std::convert::Infallible, std::marker::PhantomData<&'a ()>),
 }
 
-impl<'a, T: Read + Seek + Send + 'a> LASReaderFlavor<'a, T> {
+impl<'a, T: Read + Seek + 'a> LASReaderFlavor<'a, T> {
     pub fn remaining_points(&self) -> usize {
         match self {
             LASReaderFlavor::LAS(reader) => reader.remaining_points(),
+            #[cfg(feature = "laz")]
             LASReaderFlavor::LAZ(reader) => reader.remaining_points(),
+            #[cfg(not(feature = "laz"))]
+            LASReaderFlavor::Unreachable(never, _) => match *never {},
         }
     }
 
     pub fn header(&self) -> &Header {
         match self {
             LASReaderFlavor::LAS(reader) => reader.header(),
+            #[cfg(feature = "laz")]
             LASReaderFlavor::LAZ(reader) => reader.header(),
+            #[cfg(not(feature = "laz"))]
+            LASReaderFlavor::Unreachable(never, _) => match *never {},
         }
     }
 }
 
-impl<'a, T: Read + Seek + Send + 'a> PointReader for LASReaderFlavor<'a, T> {
+impl<'a, T: Read + Seek + 'a> PointReader for LASReaderFlavor<'a, T> {
     fn read_into<'b, 'c, B: BorrowedMutBuffer<'b>>(
         &mut self,
         point_buffer: &'c mut B,
@@ -44,43 +70,77 @@ impl<'a, T: Read + Seek + Send + 'a> PointReader for LASReaderFlavor<'a, T> {
     {
         match self {
             LASReaderFlavor::LAS(reader) => reader.read_into(point_buffer, count),
+            #[cfg(feature = "laz")]
             LASReaderFlavor::LAZ(reader) => reader.read_into(point_buffer, count),
+            #[cfg(not(feature = "laz"))]
+            LASReaderFlavor::Unreachable(never, _) => match *never {},
         }
     }
 
     fn get_metadata(&self) -> &dyn Metadata {
         match self {
             LASReaderFlavor::LAS(reader) => reader.get_metadata(),
+            #[cfg(feature = "laz")]
             LASReaderFlavor::LAZ(reader) => reader.get_metadata(),
+            #[cfg(not(feature = "laz"))]
+            LASReaderFlavor::Unreachable(never, _) => match *never {},
         }
     }
 
     fn get_default_point_layout(&self) -> &PointLayout {
         match self {
             LASReaderFlavor::LAS(reader) => reader.get_default_point_layout(),
+            #[cfg(feature = "laz")]
             LASReaderFlavor::LAZ(reader) => reader.get_default_point_layout(),
+            #[cfg(not(feature = "laz"))]
+            LASReaderFlavor::Unreachable(never, _) => match *never {},
+        }
+    }
+
+    fn point_index(&self) -> Option<usize> {
+        match self {
+            LASReaderFlavor::LAS(reader) => reader.point_index(),
+            #[cfg(feature = "laz")]
+            LASReaderFlavor::LAZ(reader) => reader.point_index(),
+            #[cfg(not(feature = "laz"))]
+            LASReaderFlavor::Unreachable(never, _) => match *never {},
+        }
+    }
+
+    fn point_count(&self) -> Option<usize> {
+        match self {
+            LASReaderFlavor::LAS(reader) => reader.point_count(),
+            #[cfg(feature = "laz")]
+            LASReaderFlavor::LAZ(reader) => reader.point_count(),
+            #[cfg(not(feature = "laz"))]
+            LASReaderFlavor::Unreachable(never, _) => match *never {},
         }
     }
 }
 
-impl<'a, T: Read + Seek + Send + 'a> SeekToPoint for LASReaderFlavor<'a, T> {
+impl<'a, T: Read + Seek + 'a> SeekToPoint for LASReaderFlavor<'a, T> {
     fn seek_point(&mut self, position: SeekFrom) -> Result<usize> {
         match self {
             LASReaderFlavor::LAS(reader) => reader.seek_point(position),
+            #[cfg(feature = "laz")]
             LASReaderFlavor::LAZ(reader) => reader.seek_point(position),
+            #[cfg(not(feature = "laz"))]
+            LASReaderFlavor::Unreachable(never, _) => match *never {},
         }
     }
 }
 
 /// `PointReader` implementation for LAS/LAZ files
-pub struct LASReader<'a, R: Read + Seek + Send + 'a> {
+pub struct LASReader<'a, R: Read + Seek + 'a> {
     raw_reader: LASReaderFlavor<'a, R>,
+    /// Path of the external `.wdp` file that contains the waveform data, if known
+    waveform_data_path: Option<PathBuf>,
 }
 
 impl LASReader<'static, BufReader<File>> {
-    /// Creates a new `LASReader` by opening the file at the given `path`. Tries to determine whether
-    /// the file is compressed from the file extension (i.e. files with extension `.laz` are assumed to be
-    /// compressed). If `point_layout_matches_memory_layout`
+    /// Creates a new `LASReader` by opening the file at the given `path`. Whether the file is compressed is
+    /// determined from the contents of the file (see [`read_is_compressed_las_file`]), so files with a wrong
+    /// extension are opened correctly. If `point_layout_matches_memory_layout`
     /// is `true`, the reader will return point data with a `PointLayout` that exactly matches the binary
     /// layout of the LAS point records. See [`point_layout_from_las_point_format`] for more information.
     ///
@@ -91,13 +151,38 @@ impl LASReader<'static, BufReader<File>> {
         path: P,
         point_layout_matches_memory_layout: bool,
     ) -> Result<LASReader<'static, BufReader<File>>> {
-        let is_compressed = path_is_compressed_las_file(path.as_ref())?;
-        let file = BufReader::new(File::open(path)?);
-        Self::from_read(file, is_compressed, point_layout_matches_memory_layout)
+        Self::from_path_with_options(
+            path,
+            LASReadOptions {
+                point_layout_matches_memory_layout,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Like [`Self::from_path`], but uses the given `options` to determine the default `PointLayout` of the reader.
+    /// See [`LASReadOptions`] for more information
+    ///
+    /// # Errors
+    ///
+    /// If `path` does not exist, cannot be opened or does not point to a valid LAS/LAZ file, an error is returned.
+    pub fn from_path_with_options<P: AsRef<Path>>(
+        path: P,
+        options: LASReadOptions,
+    ) -> Result<LASReader<'static, BufReader<File>>> {
+        let file = BufReader::new(File::open(path.as_ref())?);
+        let mut reader = Self::from_read_detect_compression(file, options)?;
+        let sidecar_path = LAXIndex::sidecar_path(path.as_ref());
+        if sidecar_path.is_file() {
+            let spatial_index = LAXIndex::from_path(&sidecar_path)?;
+            reader.set_spatial_index(Some(spatial_index));
+        }
+        reader.set_waveform_data_path(Some(path.as_ref().with_extension("wdp")));
+        Ok(reader)
     }
 }
 
-impl<'a, R: Read + Seek + Send> LASReader<'a, R> {
+impl<'a, R: Read + Seek> LASReader<'a, R> {
     /// Creates a new `LASReader` from the given `read`. This method has to know whether
     /// the `read` points to a compressed LAZ file or a regular LAS file. If `point_layout_matches_memory_layout`
     /// is `true`, the reader will return point data with a `PointLayout` that exactly matches the binary
@@ -105,24 +190,66 @@ impl<'a, R: Read + Seek + Send> LASReader<'a, R> {
     ///
     /// # Errors
     ///
-    /// If the given `Read` does not represent a valid LAS/LAZ file, an error is returned.
+    /// If the given `Read` does not represent a valid LAS/LAZ file, an error is returned. If `is_compressed` is `true`
+    /// but the `laz` feature of pasture-io is disabled, an error is returned as well.
     pub fn from_read(
         read: R,
         is_compressed: bool,
         point_layout_matches_memory_layout: bool,
     ) -> Result<Self> {
-        let raw_reader = if is_compressed {
-            LASReaderFlavor::LAZ(RawLAZReader::from_read(
-                read,
-                point_layout_matches_memory_layout,
-            )?)
-        } else {
-            LASReaderFlavor::LAS(RawLASReader::from_read(
-                read,
+        Self::from_read_with_options(
+            read,
+            is_compressed,
+            LASReadOptions {
                 point_layout_matches_memory_layout,
-            )?)
-        };
-        Ok(Self { raw_reader })
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Like [`Self::from_read`], but uses the given `options` to determine the default `PointLayout` of the reader.
+    /// See [`LASReadOptions`] for more information
+    ///
+    /// # Errors
+    ///
+    /// If the given `Read` does not represent a valid LAS/LAZ file, an error is returned. If `is_compressed` is `true`
+    /// but the `laz` feature of pasture-io is disabled, an error is returned as well.
+    pub fn from_read_with_options(
+        read: R,
+        is_compressed: bool,
+        options: LASReadOptions,
+    ) -> Result<Self> {
+        #[cfg(feature = "laz")]
+        if is_compressed {
+            let raw_reader =
+                LASReaderFlavor::LAZ(RawLAZReader::from_read_with_options(read, options)?);
+            return Ok(Self {
+                raw_reader,
+                waveform_data_path: None,
+            });
+        }
+        #[cfg(not(feature = "laz"))]
+        if is_compressed {
+            bail!("Reading compressed LAZ files requires the `laz` feature of pasture-io");
+        }
+
+        let raw_reader = LASReaderFlavor::LAS(RawLASReader::from_read_with_options(read, options)?);
+        Ok(Self {
+            raw_reader,
+            waveform_data_path: None,
+        })
+    }
+
+    /// Like [`Self::from_read_with_options`], but determines whether `read` contains a compressed LAZ file from the
+    /// contents of `read`, so the caller doesn't have to know. See [`read_is_compressed_las_file`] for more information
+    ///
+    /// # Errors
+    ///
+    /// If the given `Read` does not represent a valid LAS/LAZ file, an error is returned. If `read` contains a LAZ file
+    /// but the `laz` feature of pasture-io is disabled, an error is returned as well.
+    pub fn from_read_detect_compression(mut read: R, options: LASReadOptions) -> Result<Self> {
+        let is_compressed = read_is_compressed_las_file(&mut read)?;
+        Self::from_read_with_options(read, is_compressed, options)
     }
 
     pub fn remaining_points(&self) -> usize {
@@ -138,12 +265,292 @@ impl<'a, R: Read + Seek + Send> LASReader<'a, R> {
     pub fn las_metadata(&self) -> &LASMetadata {
         match &self.raw_reader {
             LASReaderFlavor::LAS(reader) => reader.las_metadata(),
+            #[cfg(feature = "laz")]
             LASReaderFlavor::LAZ(reader) => reader.las_metadata(),
+            #[cfg(not(feature = "laz"))]
+            LASReaderFlavor::Unreachable(never, _) => match *never {},
+        }
+    }
+
+    /// Reads `count` points, but only writes the points whose world-space position lies within `bounds` into
+    /// `point_buffer`. Returns the number of points that were written. The reader always advances by `count` points
+    /// (or the number of remaining points, if that is smaller), regardless of how many points matched
+    pub fn read_into_filtered<'b, 'c, B: BorrowedMutBuffer<'b>>(
+        &mut self,
+        point_buffer: &'c mut B,
+        count: usize,
+        bounds: &AABB<f64>,
+    ) -> Result<usize>
+    where
+        'b: 'c,
+    {
+        match &mut self.raw_reader {
+            LASReaderFlavor::LAS(reader) => reader.read_into_filtered(point_buffer, count, bounds),
+            #[cfg(feature = "laz")]
+            LASReaderFlavor::LAZ(reader) => reader.read_into_filtered(point_buffer, count, bounds),
+            #[cfg(not(feature = "laz"))]
+            LASReaderFlavor::Unreachable(never, _) => match *never {},
+        }
+    }
+
+    /// Returns the number of points per chunk for compressed LAZ files with fixed-size chunks. Reading in multiples
+    /// of the chunk size, starting at a chunk boundary, gives the best throughput. Returns `None` for uncompressed
+    /// LAS files and LAZ files with variable-sized chunks
+    pub fn chunk_size(&self) -> Option<usize> {
+        match &self.raw_reader {
+            LASReaderFlavor::LAS(_) => None,
+            #[cfg(feature = "laz")]
+            LASReaderFlavor::LAZ(reader) => reader.chunk_size(),
+            #[cfg(not(feature = "laz"))]
+            LASReaderFlavor::Unreachable(never, _) => match *never {},
+        }
+    }
+
+    /// Returns the point ranges of all corrupt LAZ chunks that were skipped so far, in the order in which they were
+    /// encountered. Chunks are only skipped if [`LASReadOptions::skip_corrupt_laz_chunks`] is enabled, otherwise
+    /// reading fails on the first corrupt chunk. Always empty for uncompressed LAS files
+    pub fn skipped_point_ranges(&self) -> &[Range<usize>] {
+        match &self.raw_reader {
+            LASReaderFlavor::LAS(_) => &[],
+            #[cfg(feature = "laz")]
+            LASReaderFlavor::LAZ(reader) => reader.skipped_point_ranges(),
+            #[cfg(not(feature = "laz"))]
+            LASReaderFlavor::Unreachable(never, _) => match *never {},
+        }
+    }
+
+    /// Moves the reader to the first point whose GPS time is greater than or equal to `gps_time` and returns the index
+    /// of this point, or the number of points if there is no such point. This does a binary search, so it requires
+    /// the points to be sorted by GPS time. Use [`Self::seek_to_gps_time_linear`] for files that are not sorted
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the point format of the file has no GPS time, or if the file is a compressed LAZ file,
+    /// since these don't support reading individual point records
+    pub fn seek_to_gps_time(&mut self, gps_time: f64) -> Result<usize> {
+        match &mut self.raw_reader {
+            LASReaderFlavor::LAS(reader) => reader.seek_to_gps_time(gps_time),
+            #[cfg(feature = "laz")]
+            LASReaderFlavor::LAZ(_) => {
+                bail!("Seeking by GPS time is only supported for uncompressed LAS files")
+            }
+            #[cfg(not(feature = "laz"))]
+            LASReaderFlavor::Unreachable(never, _) => match *never {},
+        }
+    }
+
+    /// Like [`Self::seek_to_gps_time`], but scans all points from the start of the file, so it also works for files
+    /// that are not sorted by GPS time
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the point format of the file has no GPS time, or if the file is a compressed LAZ file
+    pub fn seek_to_gps_time_linear(&mut self, gps_time: f64) -> Result<usize> {
+        match &mut self.raw_reader {
+            LASReaderFlavor::LAS(reader) => reader.seek_to_gps_time_linear(gps_time),
+            #[cfg(feature = "laz")]
+            LASReaderFlavor::LAZ(_) => {
+                bail!("Seeking by GPS time is only supported for uncompressed LAS files")
+            }
+            #[cfg(not(feature = "laz"))]
+            LASReaderFlavor::Unreachable(never, _) => match *never {},
+        }
+    }
+
+    /// Returns the spatial index of this reader, if there is one. The index is loaded from the EVLRs of the file, or
+    /// from a `.lax` sidecar file if the reader was created with [`LASReader::from_path`]
+    pub fn spatial_index(&self) -> Option<&LAXIndex> {
+        match &self.raw_reader {
+            LASReaderFlavor::LAS(reader) => reader.spatial_index(),
+            #[cfg(feature = "laz")]
+            LASReaderFlavor::LAZ(reader) => reader.spatial_index(),
+            #[cfg(not(feature = "laz"))]
+            LASReaderFlavor::Unreachable(never, _) => match *never {},
+        }
+    }
+
+    /// Sets the spatial index that [`Self::read_bounds`] uses. Passing `None` removes the current index
+    pub fn set_spatial_index(&mut self, spatial_index: Option<LAXIndex>) {
+        match &mut self.raw_reader {
+            LASReaderFlavor::LAS(reader) => reader.set_spatial_index(spatial_index),
+            #[cfg(feature = "laz")]
+            LASReaderFlavor::LAZ(reader) => reader.set_spatial_index(spatial_index),
+            #[cfg(not(feature = "laz"))]
+            LASReaderFlavor::Unreachable(never, _) => match *never {},
+        }
+    }
+
+    /// Appends all points whose world-space position lies within `bounds` to `point_buffer` and returns the number
+    /// of appended points. If the reader has a spatial index, only the candidate points of the index are read,
+    /// otherwise all points are scanned. Afterwards, the reader is positioned at an unspecified point
+    pub fn read_bounds<'b, B: OwningBuffer<'b>>(
+        &mut self,
+        bounds: &AABB<f64>,
+        point_buffer: &mut B,
+    ) -> Result<usize> {
+        match &mut self.raw_reader {
+            LASReaderFlavor::LAS(reader) => reader.read_bounds(bounds, point_buffer),
+            #[cfg(feature = "laz")]
+            LASReaderFlavor::LAZ(reader) => reader.read_bounds(bounds, point_buffer),
+            #[cfg(not(feature = "laz"))]
+            LASReaderFlavor::Unreachable(never, _) => match *never {},
+        }
+    }
+
+    /// Checks all point records of the file against the LAS header, e.g. whether all positions lie within the bounds
+    /// of the header or whether all return numbers are valid. See [`ValidationOptions`] for the available checks.
+    /// The returned [`ValidationReport`] contains the number of violations per kind together with the indices of the
+    /// first offending points. The reader is at the same point afterwards as before
+    pub fn validate(&mut self, options: &ValidationOptions) -> Result<ValidationReport> {
+        match &mut self.raw_reader {
+            LASReaderFlavor::LAS(reader) => reader.validate(options),
+            #[cfg(feature = "laz")]
+            LASReaderFlavor::LAZ(reader) => reader.validate(options),
+            #[cfg(not(feature = "laz"))]
+            LASReaderFlavor::Unreachable(never, _) => match *never {},
+        }
+    }
+
+    /// Reads the waveform samples of the point at `point_index` for the point formats with waveform data (4, 5, 9 and
+    /// 10). The waveform data is read from the Waveform Data Packets EVLR of the file if there is one, otherwise from
+    /// the external `.wdp` file, see [`Self::set_waveform_data_path`]. The filters of this reader do not apply, and the
+    /// reader is at the same point afterwards as before
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the point format has no waveform data, if the point has no waveform or if the waveform data
+    /// can't be read
+    pub fn read_waveform(&mut self, point_index: usize) -> Result<WaveformSamples> {
+        let record = match &mut self.raw_reader {
+            LASReaderFlavor::LAS(reader) => reader.read_point_record(point_index)?,
+            #[cfg(feature = "laz")]
+            LASReaderFlavor::LAZ(reader) => reader.read_point_record(point_index)?,
+            #[cfg(not(feature = "laz"))]
+            LASReaderFlavor::Unreachable(never, _) => match *never {},
+        };
+        let metadata = self.las_metadata();
+        let raw_layout = point_layout_from_las_metadata(metadata, true)?;
+        read_waveform_samples(
+            metadata,
+            &raw_layout,
+            &record,
+            self.waveform_data_path.as_deref(),
+        )
+    }
+
+    /// Sets the path of the external `.wdp` file that contains the waveform data of this file. Readers created with
+    /// [`LASReader::from_path`] use the path of the LAS file with the extension `.wdp`
+    pub fn set_waveform_data_path(&mut self, waveform_data_path: Option<PathBuf>) {
+        self.waveform_data_path = waveform_data_path;
+    }
+
+    /// Only read points with one of the given `classifications`. `read_into` then returns only the number of
+    /// accepted points. Passing an empty slice accepts all points
+    pub fn set_classification_filter(&mut self, classifications: &[u8]) {
+        match &mut self.raw_reader {
+            LASReaderFlavor::LAS(reader) => reader.set_classification_filter(classifications),
+            #[cfg(feature = "laz")]
+            LASReaderFlavor::LAZ(reader) => reader.set_classification_filter(classifications),
+            #[cfg(not(feature = "laz"))]
+            LASReaderFlavor::Unreachable(never, _) => match *never {},
+        }
+    }
+
+    /// Only read points whose return number matches the given `filter`, e.g. only first or last returns.
+    /// `read_into` then returns only the number of accepted points
+    pub fn set_return_filter(&mut self, filter: ReturnFilter) {
+        match &mut self.raw_reader {
+            LASReaderFlavor::LAS(reader) => reader.set_return_filter(filter),
+            #[cfg(feature = "laz")]
+            LASReaderFlavor::LAZ(reader) => reader.set_return_filter(filter),
+            #[cfg(not(feature = "laz"))]
+            LASReaderFlavor::Unreachable(never, _) => match *never {},
         }
     }
+
+    /// Only read points whose GPS time lies within `min..=max`. `read_into` then returns only the number of
+    /// accepted points
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the point format of the file has no GPS time, or if `min` is larger than `max`
+    pub fn set_gps_time_range(&mut self, min: f64, max: f64) -> Result<()> {
+        match &mut self.raw_reader {
+            LASReaderFlavor::LAS(reader) => reader.set_gps_time_range(min, max),
+            #[cfg(feature = "laz")]
+            LASReaderFlavor::LAZ(reader) => reader.set_gps_time_range(min, max),
+            #[cfg(not(feature = "laz"))]
+            LASReaderFlavor::Unreachable(never, _) => match *never {},
+        }
+    }
+
+    /// Sets a callback that is invoked once per chunk of points while reading. See [`ProgressCallback`] for the
+    /// parameters that are passed to the callback
+    pub fn set_progress_callback(&mut self, callback: ProgressCallback) {
+        match &mut self.raw_reader {
+            LASReaderFlavor::LAS(reader) => reader.set_progress_callback(callback),
+            #[cfg(feature = "laz")]
+            LASReaderFlavor::LAZ(reader) => reader.set_progress_callback(callback),
+            #[cfg(not(feature = "laz"))]
+            LASReaderFlavor::Unreachable(never, _) => match *never {},
+        }
+    }
+}
+
+/// Parallel decompression requires the underlying reader to be `Send`, since it is accessed from the threads of a
+/// thread pool
+#[cfg(feature = "laz-parallel")]
+impl<'a, R: Read + Seek + Send + 'a> LASReader<'a, R> {
+    /// Creates a new `LASReader` for the compressed LAZ file in the given `read`, which decompresses the point records
+    /// using `num_threads` threads. The chunks of the LAZ file are decompressed in parallel, the points are still
+    /// returned in the order in which they are stored in the file. If `num_threads` is zero, the number of threads is
+    /// determined automatically based on the number of available CPU cores. See [`Self::from_read`] for an explanation
+    /// of the `point_layout_matches_memory_layout` parameter
+    ///
+    /// # Errors
+    ///
+    /// If the given `Read` does not represent a valid LAZ file or if the thread pool for decompression can't be
+    /// created, an error is returned
+    pub fn from_read_parallel(
+        read: R,
+        num_threads: usize,
+        point_layout_matches_memory_layout: bool,
+    ) -> Result<Self> {
+        Self::from_read_parallel_with_options(
+            read,
+            num_threads,
+            LASReadOptions {
+                point_layout_matches_memory_layout,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Like [`Self::from_read_parallel`], but uses the given `options` to determine the default `PointLayout` of the
+    /// reader. See [`LASReadOptions`] for more information
+    ///
+    /// # Errors
+    ///
+    /// If the given `Read` does not represent a valid LAZ file or if the thread pool for decompression can't be
+    /// created, an error is returned
+    pub fn from_read_parallel_with_options(
+        read: R,
+        num_threads: usize,
+        options: LASReadOptions,
+    ) -> Result<Self> {
+        let raw_reader = LASReaderFlavor::LAZ(RawLAZReader::from_read_parallel_with_options(
+            read,
+            num_threads,
+            options,
+        )?);
+        Ok(Self {
+            raw_reader,
+            waveform_data_path: None,
+        })
+    }
 }
 
-impl<'a, R: Read + Seek + Send + 'a> PointReader for LASReader<'a, R> {
+impl<'a, R: Read + Seek + 'a> PointReader for LASReader<'a, R> {
     fn get_metadata(&self) -> &dyn Metadata {
         self.raw_reader.get_metadata()
     }
@@ -152,6 +559,18 @@ impl<'a, R: Read + Seek + Send + 'a> PointReader for LASReader<'a, R> {
         self.raw_reader.get_default_point_layout()
     }
 
+    fn skip(&mut self, count: usize) -> Result<usize> {
+        skip_points_by_seeking(self, count)
+    }
+
+    fn point_index(&self) -> Option<usize> {
+        self.raw_reader.point_index()
+    }
+
+    fn point_count(&self) -> Option<usize> {
+        self.raw_reader.point_count()
+    }
+
     fn read_into<'b, 'c, B: BorrowedMutBuffer<'b>>(
         &mut self,
         point_buffer: &'c mut B,
@@ -164,7 +583,7 @@ impl<'a, R: Read + Seek + Send + 'a> PointReader for LASReader<'a, R> {
     }
 }
 
-impl<'a, R: Read + Seek + Send + 'a> SeekToPoint for LASReader<'a, R> {
+impl<'a, R: Read + Seek + 'a> SeekToPoint for LASReader<'a, R> {
     fn seek_point(&mut self, position: SeekFrom) -> Result<usize> {
         self.raw_reader.seek_point(position)
     }
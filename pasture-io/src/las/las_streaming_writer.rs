@@ -0,0 +1,359 @@
+use std::io::{Seek, SeekFrom, Write};
+
+use anyhow::{bail, Result};
+use pasture_core::{containers::BorrowedBuffer, layout::PointLayout, math::AABB};
+
+use crate::base::PointWriter;
+
+use super::{
+    las_bounds_to_pasture_bounds, validate_las_version_and_point_format,
+    BitAttributeOverflowHandling, ColorUpscaling, LASMetadata, MissingAttributeDefaults,
+    OverlapFlagHandling, RawLASWriter, LAS_WRITER_REQUIRES_SEEK,
+};
+
+/// Point count, points by return and bounds of a LAS file that are known before its points are written. A
+/// [`StreamingLASWriter`] writes them into the LAS header before the point records, so that the header never has to
+/// be updated afterwards
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LASStreamStatistics {
+    /// The number of points that will be written
+    pub point_count: u64,
+    /// The bounds of the positions of all points that will be written
+    pub bounds: AABB<f64>,
+    /// The number of points for each return number from 1 to 15. If these are unknown, the points by return are
+    /// written as zero and are not checked
+    pub points_by_return: Option<[u64; 15]>,
+}
+
+impl LASStreamStatistics {
+    /// Creates `LASStreamStatistics` for `point_count` points within `bounds`, with unknown points by return
+    pub fn new(point_count: u64, bounds: AABB<f64>) -> Self {
+        Self {
+            point_count,
+            bounds,
+            points_by_return: None,
+        }
+    }
+
+    /// Returns the statistics that are stored in the header of an existing LAS file, e.g. to write the points of
+    /// this file to a non-seekable output. Returns `None` if `metadata` has no LAS header
+    pub fn from_las_metadata(metadata: &LASMetadata) -> Option<Self> {
+        let header = metadata.raw_las_header()?;
+        let mut points_by_return = [0; 15];
+        for (index, count) in points_by_return.iter_mut().enumerate() {
+            *count = header
+                .number_of_points_by_return(index as u8 + 1)
+                .unwrap_or_default();
+        }
+        Some(Self {
+            point_count: header.number_of_points(),
+            bounds: las_bounds_to_pasture_bounds(header.bounds()),
+            points_by_return: Some(points_by_return),
+        })
+    }
+}
+
+/// Adapter that keeps track of the position within a non-seekable `Write`. It only supports seeking to the current
+/// position, which is all that a streaming `RawLASWriter` does
+struct PositionTrackingWrite<W: Write> {
+    write: W,
+    position: u64,
+}
+
+impl<W: Write> Write for PositionTrackingWrite<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let bytes_written = self.write.write(buf)?;
+        self.position += bytes_written as u64;
+        Ok(bytes_written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.write.flush()
+    }
+}
+
+impl<W: Write> Seek for PositionTrackingWrite<W> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        match pos {
+            SeekFrom::Start(position) if position == self.position => Ok(self.position),
+            SeekFrom::Current(0) => Ok(self.position),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                LAS_WRITER_REQUIRES_SEEK,
+            )),
+        }
+    }
+}
+
+/// Writer for uncompressed LAS files to outputs that are not seekable, such as pipes or network streams. Since the
+/// LAS header is written before the point records, the point count and bounds have to be known up front and are
+/// given as [`LASStreamStatistics`]. Flushing the writer finishes the LAS file, so no points can be written
+/// afterwards. If the written points don't match the statistics, flushing returns an error describing the mismatch
+///
+/// Create a `StreamingLASWriter` with a specific LAS header through
+/// [`LASWriterBuilder::build_streaming`](super::LASWriterBuilder::build_streaming)
+pub struct StreamingLASWriter<W: Write> {
+    /// Only `None` after `into_inner` has been called
+    writer: Option<RawLASWriter<PositionTrackingWrite<W>>>,
+}
+
+impl<W: Write> StreamingLASWriter<W> {
+    /// Creates a new `StreamingLASWriter` that writes the given `header` with the given `statistics` to `writer`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header describes a compressed LAZ file, which can't be written without seeking, or if
+    /// the LAS version of `header` does not support its point format
+    pub fn from_writer_and_header(
+        writer: W,
+        header: las::Header,
+        statistics: LASStreamStatistics,
+    ) -> Result<Self> {
+        validate_las_version_and_point_format(header.version(), header.point_format())?;
+        if header.point_format().is_compressed {
+            bail!("Compressed LAZ files can't be written to a non-seekable output");
+        }
+        let write = PositionTrackingWrite {
+            write: writer,
+            position: 0,
+        };
+        let raw_writer = RawLASWriter::from_write_and_header_streaming(write, header, statistics)?;
+        Ok(Self {
+            writer: Some(raw_writer),
+        })
+    }
+
+    /// Sets how values that don't fit into the bit fields of the LAS point records are handled. See
+    /// [`LASWriter::set_bit_attribute_overflow_handling`](super::LASWriter::set_bit_attribute_overflow_handling)
+    pub fn set_bit_attribute_overflow_handling(
+        &mut self,
+        overflow_handling: BitAttributeOverflowHandling,
+    ) {
+        self.raw_writer_mut()
+            .set_bit_attribute_overflow_handling(overflow_handling);
+    }
+
+    /// Sets what happens to the overlap flag of points that are written to the point formats 0-5. See
+    /// [`LASWriter::set_overlap_flag_handling`](super::LASWriter::set_overlap_flag_handling)
+    pub fn set_overlap_flag_handling(&mut self, overlap_handling: OverlapFlagHandling) {
+        self.raw_writer_mut()
+            .set_overlap_flag_handling(overlap_handling);
+    }
+
+    /// Returns the number of points whose overlap flag was dropped. See
+    /// [`LASWriter::dropped_overlap_flags`](super::LASWriter::dropped_overlap_flags)
+    pub fn dropped_overlap_flags(&self) -> usize {
+        self.writer
+            .as_ref()
+            .expect("writer is only taken in into_inner")
+            .dropped_overlap_flags()
+    }
+
+    /// Sets the values that are written for LAS attributes that are missing from the `PointLayout` of the points. See
+    /// [`LASWriter::set_missing_attribute_defaults`](super::LASWriter::set_missing_attribute_defaults)
+    pub fn set_missing_attribute_defaults(&mut self, defaults: MissingAttributeDefaults) {
+        self.raw_writer_mut()
+            .set_missing_attribute_defaults(defaults);
+    }
+
+    /// Sets how `Vector3<u8>` colors are converted into the 16-bit colors of the point records. See
+    /// [`LASWriter::set_color_upscaling`](super::LASWriter::set_color_upscaling)
+    pub fn set_color_upscaling(&mut self, color_upscaling: ColorUpscaling) {
+        self.raw_writer_mut().set_color_upscaling(color_upscaling);
+    }
+
+    /// Sets the number of points whose records are assembled in memory and written at once. See
+    /// [`LASWriter::set_write_chunk_size`](super::LASWriter::set_write_chunk_size)
+    pub fn set_write_chunk_size(&mut self, write_chunk_size: usize) -> Result<()> {
+        self.raw_writer_mut().set_write_chunk_size(write_chunk_size)
+    }
+
+    fn raw_writer_mut(&mut self) -> &mut RawLASWriter<PositionTrackingWrite<W>> {
+        self.writer
+            .as_mut()
+            .expect("writer is only taken in into_inner")
+    }
+
+    /// Finishes the LAS file and returns the underlying write type `W`
+    pub fn into_inner(mut self) -> Result<W> {
+        let raw_writer = self
+            .writer
+            .take()
+            .expect("writer is only taken in into_inner");
+        Ok(raw_writer.into_inner()?.write)
+    }
+}
+
+impl<W: Write> Drop for StreamingLASWriter<W> {
+    fn drop(&mut self) {
+        if self.writer.is_some() {
+            // Errors can't be propagated from `drop`, users that care about them call `flush` or `into_inner`
+            let _ = self.flush();
+        }
+    }
+}
+
+impl<W: Write> PointWriter for StreamingLASWriter<W> {
+    fn write<'a, B: BorrowedBuffer<'a>>(&mut self, points: &'a B) -> Result<()> {
+        self.raw_writer_mut().write(points)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        // The point records follow the header that was written up front, so flushing can finish the file right away
+        self.raw_writer_mut().finalize()
+    }
+
+    fn get_default_point_layout(&self) -> &PointLayout {
+        self.writer
+            .as_ref()
+            .expect("writer is only taken in into_inner")
+            .get_default_point_layout()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, ops::Range};
+
+    use las::point::Format;
+    use pasture_core::{
+        containers::VectorBuffer,
+        layout::{attributes::POSITION_3D, PointType},
+        nalgebra::{Point3, Vector3},
+    };
+    use pasture_derive::PointType;
+
+    use crate::{
+        base::PointReader,
+        las::{
+            compare_to_reference_data, get_test_las_path, test_data_point_count, LASReader,
+            LASWriterBuilder,
+        },
+    };
+
+    use super::*;
+
+    /// Output that only implements `Write`, like a pipe or a network stream
+    struct NonSeekableWriter(Vec<u8>);
+
+    impl Write for NonSeekableWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[repr(C, packed)]
+    #[derive(Debug, Clone, Copy, PointType, bytemuck::AnyBitPattern, bytemuck::NoUninit)]
+    struct PositionPoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+    }
+
+    fn positions(indices: Range<usize>) -> VectorBuffer {
+        indices
+            .map(|index| PositionPoint {
+                position: Vector3::new(index as f64, 2.0 * index as f64, 1.0),
+            })
+            .collect()
+    }
+
+    fn statistics_of_positions(count: usize) -> LASStreamStatistics {
+        let max = (count.max(1) - 1) as f64;
+        LASStreamStatistics::new(
+            count as u64,
+            AABB::from_min_max(Point3::new(0.0, 0.0, 1.0), Point3::new(max, 2.0 * max, 1.0)),
+        )
+    }
+
+    #[test]
+    fn test_streaming_las_writer_copies_las_file() -> Result<()> {
+        for point_format in 0..=3 {
+            let mut reader = LASReader::from_path(get_test_las_path(point_format), false)?;
+            let statistics = LASStreamStatistics::from_las_metadata(reader.las_metadata())
+                .expect("LAS files have a LAS header");
+            let points = reader.read::<VectorBuffer>(test_data_point_count())?;
+
+            let format = Format::new(point_format)?;
+            let mut writer = LASWriterBuilder::new(format)
+                .build_streaming(NonSeekableWriter(vec![]), statistics)?;
+            writer.write(&points)?;
+            let las_data = writer.into_inner()?.0;
+
+            let mut reader = LASReader::from_read(Cursor::new(las_data), false, false)?;
+            assert_eq!(
+                Some(&statistics),
+                LASStreamStatistics::from_las_metadata(reader.las_metadata()).as_ref()
+            );
+            let points = reader.read::<VectorBuffer>(test_data_point_count())?;
+            compare_to_reference_data(&points, format);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_streaming_las_writer_with_evlrs() -> Result<()> {
+        let evlr = las_rs::Vlr {
+            user_id: "pasture".to_owned(),
+            record_id: 1,
+            description: "Test EVLR".to_owned(),
+            data: vec![42; 128],
+        };
+        let mut writer = LASWriterBuilder::new(Format::new(6)?)
+            .with_evlr(evlr.clone())
+            .build_streaming(NonSeekableWriter(vec![]), statistics_of_positions(100))?;
+        writer.write(&positions(0..60))?;
+        writer.write(&positions(60..100))?;
+        let las_data = writer.into_inner()?.0;
+
+        let mut reader = LASReader::from_read(Cursor::new(las_data), false, false)?;
+        assert_eq!(1, reader.las_metadata().evlrs().len());
+        assert_eq!(evlr.data, reader.las_metadata().evlrs()[0].data);
+        let points = reader.read::<VectorBuffer>(100)?;
+        assert_eq!(100, points.len());
+        assert_eq!(
+            Vector3::new(99.0, 198.0, 1.0),
+            points.view_attribute::<Vector3<f64>>(&POSITION_3D).at(99)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_streaming_las_writer_detects_wrong_point_count() -> Result<()> {
+        let builder = LASWriterBuilder::new(Format::new(0)?);
+
+        let mut writer =
+            builder.build_streaming(NonSeekableWriter(vec![]), statistics_of_positions(10))?;
+        writer.write(&positions(0..9))?;
+        let error = writer.flush().expect_err("Too few points must be an error");
+        assert!(error.to_string().contains("10 points, but 9 points"));
+        assert!(writer.write(&positions(9..10)).is_err());
+
+        let mut writer =
+            builder.build_streaming(NonSeekableWriter(vec![]), statistics_of_positions(10))?;
+        writer.write(&positions(0..6))?;
+        assert!(writer.write(&positions(6..12)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_streaming_las_writer_detects_wrong_bounds() -> Result<()> {
+        let mut writer = LASWriterBuilder::new(Format::new(0)?)
+            .build_streaming(NonSeekableWriter(vec![]), statistics_of_positions(5))?;
+        writer.write(&positions(5..10))?;
+        assert!(writer.flush().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_streaming_las_writer_rejects_laz() -> Result<()> {
+        assert!(LASWriterBuilder::new(Format::new(0)?)
+            .compressed(true)
+            .build_streaming(NonSeekableWriter(vec![]), statistics_of_positions(1))
+            .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,489 @@
+use std::{
+    convert::TryInto,
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use pasture_core::{
+    containers::{BorrowedBuffer, OwningBuffer, VectorBuffer},
+    layout::{attributes::POSITION_3D, PointLayout},
+    math::AABB,
+    nalgebra::{Point3, Vector3},
+};
+
+use crate::base::PointWriter;
+
+use super::{
+    point_layout_from_las_metadata, validate_source_layout_for_las, LASMetadata, LASWriter,
+    LASWriterBuilder,
+};
+
+/// Number of points that a [`TiledLASWriter`] buffers for each tile before writing them to the tile file, unless
+/// configured otherwise through [`TiledLASWriter::set_tile_buffer_capacity`]
+pub const DEFAULT_TILE_BUFFER_CAPACITY: usize = 4096;
+
+/// A regular grid of tiles over the XY extent of a point cloud, which [`TiledLASWriter`] uses to assign points to
+/// tiles. Tiles are identified by their index `(x, y)`, starting at the minimum corner of the grid bounds
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileGrid {
+    bounds: AABB<f64>,
+    tiles_x: usize,
+    tiles_y: usize,
+}
+
+impl TileGrid {
+    /// Creates a grid of `tiles_x` tiles along the x-axis and `tiles_y` tiles along the y-axis, which evenly split the
+    /// XY extent of `bounds`. The z-extent of `bounds` is ignored when assigning points to tiles
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `tiles_x` or `tiles_y` is zero
+    pub fn new(bounds: AABB<f64>, tiles_x: usize, tiles_y: usize) -> Result<Self> {
+        if tiles_x == 0 || tiles_y == 0 {
+            bail!(
+                "A tile grid needs at least one tile along each axis, but {}x{} tiles were requested",
+                tiles_x,
+                tiles_y
+            );
+        }
+        if tiles_x.checked_mul(tiles_y).is_none() {
+            bail!("A tile grid of {}x{} tiles is too large", tiles_x, tiles_y);
+        }
+        Ok(Self {
+            bounds,
+            tiles_x,
+            tiles_y,
+        })
+    }
+
+    /// The bounds that this grid covers
+    pub fn bounds(&self) -> &AABB<f64> {
+        &self.bounds
+    }
+
+    /// The number of tiles along the x-axis
+    pub fn tiles_x(&self) -> usize {
+        self.tiles_x
+    }
+
+    /// The number of tiles along the y-axis
+    pub fn tiles_y(&self) -> usize {
+        self.tiles_y
+    }
+
+    /// Returns the index of the tile that contains `position`, or `None` if `position` lies outside of the XY extent
+    /// of the grid bounds. Positions on the border between two tiles belong to the upper tile, positions on the
+    /// maximum border of the grid belong to the last tile
+    pub fn tile_of(&self, position: &Vector3<f64>) -> Option<(usize, usize)> {
+        let (min, max) = (self.bounds.min(), self.bounds.max());
+        if !(min.x..=max.x).contains(&position.x) || !(min.y..=max.y).contains(&position.y) {
+            return None;
+        }
+        Some((
+            tile_index_along_axis(position.x, min.x, max.x, self.tiles_x),
+            tile_index_along_axis(position.y, min.y, max.y, self.tiles_y),
+        ))
+    }
+
+    /// Returns the bounds of the tile with the given index. Their z-extent equals that of the grid bounds
+    pub fn tile_bounds(&self, tile: (usize, usize)) -> AABB<f64> {
+        let (min, max) = (self.bounds.min(), self.bounds.max());
+        let tile_size_x = (max.x - min.x) / self.tiles_x as f64;
+        let tile_size_y = (max.y - min.y) / self.tiles_y as f64;
+        AABB::from_min_max_unchecked(
+            Point3::new(
+                min.x + tile.0 as f64 * tile_size_x,
+                min.y + tile.1 as f64 * tile_size_y,
+                min.z,
+            ),
+            Point3::new(
+                min.x + (tile.0 + 1) as f64 * tile_size_x,
+                min.y + (tile.1 + 1) as f64 * tile_size_y,
+                max.z,
+            ),
+        )
+    }
+}
+
+fn tile_index_along_axis(coordinate: f64, min: f64, max: f64, tiles: usize) -> usize {
+    let extent = max - min;
+    if extent <= 0.0 {
+        return 0;
+    }
+    (((coordinate - min) / extent * tiles as f64) as usize).min(tiles - 1)
+}
+
+/// Information about a tile file that a [`TiledLASWriter`] has written
+#[derive(Debug, Clone, PartialEq)]
+pub struct LASTileFile {
+    /// Index of the tile within the [`TileGrid`]
+    pub tile: (usize, usize),
+    pub path: PathBuf,
+    /// Number of points that were written to the tile file
+    pub point_count: usize,
+    /// Bounds of the positions of all points that were written to the tile file. These are the positions before they
+    /// are quantized to the scale of the LAS file
+    pub bounds: AABB<f64>,
+}
+
+struct Tile {
+    writer: LASWriter<BufWriter<File>>,
+    /// Points that were routed to this tile but not written to the tile file yet
+    buffer: VectorBuffer,
+    file: LASTileFile,
+}
+
+impl Tile {
+    fn write_buffered_points(&mut self) -> Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        self.writer.write(&self.buffer).with_context(|| {
+            format!(
+                "Could not write points to tile file {}",
+                self.file.path.display()
+            )
+        })?;
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+/// Writer that splits a point cloud into a grid of LAS/LAZ files, e.g. to prepare it for tiled processing. Points can
+/// be written in any order, each point is routed to the file of the tile of the [`TileGrid`] that contains its XY
+/// position. The points of each tile are buffered and written in chunks of [`DEFAULT_TILE_BUFFER_CAPACITY`] points,
+/// so that points arriving in random order don't result in many tiny writes
+///
+/// A tile file is created when the first point of the tile is written, so tiles without points have no file. All
+/// tile files are created with the settings of the same [`LASWriterBuilder`] and stay open until the
+/// `TiledLASWriter` is finished. Flushing the `TiledLASWriter` writes all buffered points to the tile files.
+/// [`Self::finish`] additionally finalizes the headers of all tile files and returns them with their point counts and
+/// bounds. Dropping the `TiledLASWriter` finalizes the tile files as well, but can't report errors
+pub struct TiledLASWriter {
+    grid: TileGrid,
+    builder: LASWriterBuilder,
+    directory: PathBuf,
+    file_stem: String,
+    extension: String,
+    /// All tiles of the grid in row-major order, `None` for tiles without points
+    tiles: Vec<Option<Tile>>,
+    default_layout: PointLayout,
+    tile_buffer_capacity: usize,
+}
+
+impl TiledLASWriter {
+    /// Creates a `TiledLASWriter` that splits the points into the tiles of `grid`. The tile files are written next to
+    /// `path` and are named after it: For the path `out/tiles.laz`, the tile `(2, 3)` is written to
+    /// `out/tiles_2_3.laz`. Whether the tile files are compressed is determined from the extension of `path`,
+    /// overriding [`LASWriterBuilder::compressed`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the extension of `path` is neither `las` nor `laz`, or if `builder` can't build a valid
+    /// LAS header
+    pub fn new<P: AsRef<Path>>(path: P, grid: TileGrid, builder: LASWriterBuilder) -> Result<Self> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .filter(|extension| *extension == "las" || *extension == "laz")
+            .ok_or_else(|| {
+                anyhow!(
+                    "Could not determine whether the tiles of {} are LAS or LAZ files, the extension has to be 'las' or 'laz'",
+                    path.display()
+                )
+            })?;
+        let file_stem = path
+            .file_stem()
+            .and_then(|file_stem| file_stem.to_str())
+            .ok_or_else(|| anyhow!("Invalid path for the tile files: {}", path.display()))?;
+
+        let header = builder.build_header()?;
+        let las_metadata: LASMetadata =
+            (&header).try_into().context("Could not parse LAS header")?;
+        let default_layout = point_layout_from_las_metadata(&las_metadata, false)
+            .context("Could not determine PointLayout from given LAS header")?;
+
+        Ok(Self {
+            grid,
+            builder,
+            directory: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+            file_stem: file_stem.to_owned(),
+            extension: extension.to_owned(),
+            tiles: (0..grid.tiles_x * grid.tiles_y).map(|_| None).collect(),
+            default_layout,
+            tile_buffer_capacity: DEFAULT_TILE_BUFFER_CAPACITY,
+        })
+    }
+
+    /// The grid that this writer splits the points into
+    pub fn grid(&self) -> &TileGrid {
+        &self.grid
+    }
+
+    /// Sets the number of points that are buffered for each tile before they are written to the tile file. Larger
+    /// buffers result in fewer writes, but need more memory, as up to this many points are buffered for each tile
+    pub fn set_tile_buffer_capacity(&mut self, tile_buffer_capacity: usize) {
+        self.tile_buffer_capacity = tile_buffer_capacity;
+    }
+
+    /// Returns the path of the file for the tile with the given index
+    pub fn tile_path(&self, tile: (usize, usize)) -> PathBuf {
+        self.directory.join(format!(
+            "{}_{}_{}.{}",
+            self.file_stem, tile.0, tile.1, self.extension
+        ))
+    }
+
+    /// Returns all tile files that have been created so far, ordered by their y-index and then by their x-index. The
+    /// point counts include points that are still buffered
+    pub fn tile_files(&self) -> Vec<LASTileFile> {
+        self.tiles
+            .iter()
+            .flatten()
+            .map(|tile| tile.file.clone())
+            .collect()
+    }
+
+    /// Writes all buffered points, finalizes all tile files and returns them, see [`Self::tile_files`]
+    pub fn finish(mut self) -> Result<Vec<LASTileFile>> {
+        self.flush()?;
+        let mut tile_files = vec![];
+        for Tile { writer, file, .. } in self.tiles.iter_mut().filter_map(Option::take) {
+            writer
+                .finalize()
+                .with_context(|| format!("Could not finalize tile file {}", file.path.display()))?;
+            tile_files.push(file);
+        }
+        Ok(tile_files)
+    }
+
+    fn create_tile(&self, tile: (usize, usize), point_layout: &PointLayout) -> Result<Tile> {
+        let path = self.tile_path(tile);
+        let writer = self
+            .builder
+            .build_for_path(&path)
+            .with_context(|| format!("Could not create tile file {}", path.display()))?;
+        Ok(Tile {
+            writer,
+            buffer: VectorBuffer::with_capacity(self.tile_buffer_capacity, point_layout.clone()),
+            file: LASTileFile {
+                tile,
+                path,
+                point_count: 0,
+                bounds: AABB::from_min_max_unchecked(Point3::origin(), Point3::origin()),
+            },
+        })
+    }
+}
+
+impl Drop for TiledLASWriter {
+    fn drop(&mut self) {
+        // Errors can't be propagated from `drop`, users that care about them call `finish`. The tile files are finalized
+        // when their writers are dropped
+        let _ = self.flush();
+    }
+}
+
+impl PointWriter for TiledLASWriter {
+    fn write<'a, B: BorrowedBuffer<'a>>(&mut self, points: &'a B) -> Result<()> {
+        let point_layout = points.point_layout();
+        validate_source_layout_for_las(point_layout)?;
+        let positions = points.view_attribute_with_conversion::<Vector3<f64>>(&POSITION_3D)?;
+        let mut point_memory = vec![0; point_layout.size_of_point_entry() as usize];
+        for point_index in 0..points.len() {
+            let position = positions.at(point_index);
+            let tile_index = self.grid.tile_of(&position).ok_or_else(|| {
+                anyhow!(
+                    "Point {} at position {} lies outside of the tile grid, which covers {} to {}",
+                    point_index,
+                    position,
+                    self.grid.bounds.min(),
+                    self.grid.bounds.max()
+                )
+            })?;
+            let slot = tile_index.1 * self.grid.tiles_x + tile_index.0;
+            if self.tiles[slot].is_none() {
+                let tile = self.create_tile(tile_index, point_layout)?;
+                self.tiles[slot] = Some(tile);
+            }
+            let tile = self.tiles[slot].as_mut().expect("tile was created above");
+
+            // Buffers only hold points of a single PointLayout, so a new layout requires writing the buffered points
+            if tile.buffer.point_layout() != point_layout {
+                tile.write_buffered_points()?;
+                tile.buffer =
+                    VectorBuffer::with_capacity(self.tile_buffer_capacity, point_layout.clone());
+            }
+            points.get_point(point_index, &mut point_memory);
+            // Safety: `point_memory` contains a single point in the PointLayout of the buffer
+            unsafe {
+                tile.buffer.push_points(&point_memory);
+            }
+
+            let position = Point3::from(position);
+            tile.file.bounds = if tile.file.point_count == 0 {
+                AABB::from_min_max_unchecked(position, position)
+            } else {
+                AABB::extend_with_point(&tile.file.bounds, &position)
+            };
+            tile.file.point_count += 1;
+            if tile.buffer.len() >= self.tile_buffer_capacity {
+                tile.write_buffered_points()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        for tile in self.tiles.iter_mut().flatten() {
+            tile.write_buffered_points()?;
+            tile.writer.flush()?;
+        }
+        Ok(())
+    }
+
+    fn get_default_point_layout(&self) -> &PointLayout {
+        &self.default_layout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use las::point::Format;
+    use pasture_core::layout::PointType;
+    use pasture_derive::PointType;
+    use scopeguard::defer;
+
+    use crate::{base::PointReader, las::LASReader};
+
+    use super::*;
+
+    #[repr(C, packed)]
+    #[derive(Debug, Clone, Copy, PointType, bytemuck::AnyBitPattern, bytemuck::NoUninit)]
+    struct PositionPoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+    }
+
+    fn test_grid() -> Result<TileGrid> {
+        TileGrid::new(
+            AABB::from_min_max(Point3::new(0.0, 0.0, 0.0), Point3::new(10.0, 20.0, 1.0)),
+            2,
+            4,
+        )
+    }
+
+    #[test]
+    fn test_tile_grid_tile_of() -> Result<()> {
+        let grid = test_grid()?;
+        assert_eq!(Some((0, 0)), grid.tile_of(&Vector3::new(0.0, 0.0, 0.0)));
+        assert_eq!(Some((0, 1)), grid.tile_of(&Vector3::new(4.9, 5.0, 0.0)));
+        assert_eq!(Some((1, 3)), grid.tile_of(&Vector3::new(5.0, 19.0, 0.0)));
+        assert_eq!(Some((1, 3)), grid.tile_of(&Vector3::new(10.0, 20.0, 0.0)));
+        // The z-extent of the grid bounds is ignored
+        assert_eq!(Some((1, 2)), grid.tile_of(&Vector3::new(7.0, 12.0, 100.0)));
+        assert_eq!(None, grid.tile_of(&Vector3::new(-0.1, 5.0, 0.0)));
+        assert_eq!(None, grid.tile_of(&Vector3::new(5.0, 20.1, 0.0)));
+        assert_eq!(None, grid.tile_of(&Vector3::new(f64::NAN, 5.0, 0.0)));
+
+        assert_eq!(
+            AABB::from_min_max(Point3::new(5.0, 10.0, 0.0), Point3::new(10.0, 15.0, 1.0)),
+            grid.tile_bounds((1, 2))
+        );
+        assert!(TileGrid::new(*grid.bounds(), 0, 4).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_tiled_las_writer() -> Result<()> {
+        let out_dir = std::env::temp_dir().join("pasture_test_tiled_las_writer");
+        std::fs::create_dir_all(&out_dir)?;
+        defer! {
+            std::fs::remove_dir_all(&out_dir).expect("Could not remove test directory");
+        }
+
+        // Points in the lower half of the grid only, interleaved between the tiles
+        let positions = (0..200)
+            .map(|index| Vector3::new((index % 20) as f64 * 0.5, (index / 20) as f64, 0.5))
+            .collect::<Vec<_>>();
+        let grid = test_grid()?;
+        let mut writer = TiledLASWriter::new(
+            out_dir.join("tiles.las"),
+            grid,
+            LASWriterBuilder::new(Format::new(0)?),
+        )?;
+        writer.set_tile_buffer_capacity(7);
+        writer.write_points(positions.iter().map(|position| PositionPoint {
+            position: *position,
+        }))?;
+        let tile_files = writer.finish()?;
+
+        let expected_tiles = [(0, 0), (1, 0), (0, 1), (1, 1)];
+        assert_eq!(
+            expected_tiles.to_vec(),
+            tile_files.iter().map(|file| file.tile).collect::<Vec<_>>()
+        );
+        assert!(!out_dir.join("tiles_0_2.las").exists());
+        for tile_file in &tile_files {
+            let expected_positions = positions
+                .iter()
+                .copied()
+                .filter(|position| grid.tile_of(position) == Some(tile_file.tile))
+                .collect::<Vec<_>>();
+            assert_eq!(expected_positions.len(), tile_file.point_count);
+            assert_eq!(
+                out_dir.join(format!(
+                    "tiles_{}_{}.las",
+                    tile_file.tile.0, tile_file.tile.1
+                )),
+                tile_file.path
+            );
+
+            let mut reader = LASReader::from_path(&tile_file.path, false)?;
+            assert_eq!(Some(tile_file.point_count), reader.point_count());
+            let points = reader.read::<VectorBuffer>(tile_file.point_count)?;
+            let positions_in_file = points
+                .view_attribute::<Vector3<f64>>(&POSITION_3D)
+                .into_iter()
+                .collect::<Vec<_>>();
+            assert_eq!(expected_positions, positions_in_file);
+
+            let expected_bounds = expected_positions.iter().skip(1).fold(
+                AABB::from_min_max(expected_positions[0].into(), expected_positions[0].into()),
+                |bounds, position| AABB::extend_with_point(&bounds, &(*position).into()),
+            );
+            assert_eq!(expected_bounds, tile_file.bounds);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_tiled_las_writer_rejects_points_outside_of_grid() -> Result<()> {
+        let out_dir = std::env::temp_dir().join("pasture_test_tiled_las_writer_outside");
+        std::fs::create_dir_all(&out_dir)?;
+        defer! {
+            std::fs::remove_dir_all(&out_dir).expect("Could not remove test directory");
+        }
+
+        let mut writer = TiledLASWriter::new(
+            out_dir.join("tiles.laz"),
+            test_grid()?,
+            LASWriterBuilder::new(Format::new(0)?),
+        )?;
+        assert!(writer
+            .write_points([PositionPoint {
+                position: Vector3::new(11.0, 0.0, 0.0),
+            }])
+            .is_err());
+        assert!(TiledLASWriter::new(
+            out_dir.join("tiles.txt"),
+            test_grid()?,
+            LASWriterBuilder::new(Format::new(0)?)
+        )
+        .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,404 @@
+use std::{
+    convert::TryInto,
+    fs::File,
+    io::{BufReader, BufWriter, Cursor, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use las_rs::{point::Format, raw};
+use laz::{LasZipCompressor, LasZipDecompressor, LazItemRecordBuilder, LazVlr, LazVlrBuilder};
+
+use super::{
+    map_laz_err, path_is_compressed_las_file, point_count_from_raw_header,
+    read_is_compressed_las_file, update_evlrs_in_las_header, DEFAULT_LAZ_CHUNK_SIZE,
+    DEFAULT_WRITE_CHUNK_SIZE, GLOBAL_ENCODING_INTERNAL_WAVEFORM_DATA_BIT, LASZIP_VLR_USER_ID,
+    SIZE_OF_VLR_HEADER, WAVEFORM_DATA_PACKETS_RECORD_ID, WAVEFORM_VLR_USER_ID,
+};
+
+/// LASzip marks compressed files by setting bit 7 (or bit 6 in older versions) of the point data record format
+const LASZIP_COMPRESSION_BITS: u8 = 0b1100_0000;
+/// The bit of the point data record format that is set in the LAZ files that `transcode` writes, like las-rs does
+const LASZIP_COMPRESSION_BIT: u8 = 1 << 7;
+
+/// Returns `true` if the user ID of the given raw (E)VLR equals `user_id`
+fn has_user_id(vlr: &raw::Vlr, user_id: &str) -> bool {
+    vlr.user_id.split(|byte| *byte == 0).next() == Some(user_id.as_bytes())
+}
+
+fn is_laszip_vlr(vlr: &raw::Vlr) -> bool {
+    has_user_id(vlr, LASZIP_VLR_USER_ID) && vlr.record_id == LazVlr::RECORD_ID
+}
+
+/// Everything of a LAS/LAZ file except for its point records, which `transcode` copies verbatim except for the
+/// fields that describe the compression
+struct TranscodedFile {
+    header: raw::Header,
+    vlrs: Vec<raw::Vlr>,
+    /// Bytes between the last VLR and the first point record
+    vlr_padding: Vec<u8>,
+    evlrs: Vec<raw::Vlr>,
+    point_count: u64,
+}
+
+impl TranscodedFile {
+    /// Reads the header, VLRs and EVLRs of the LAS/LAZ file in `source` and leaves `source` at the start of the point
+    /// records
+    fn read_from<R: Read + Seek>(source: &mut R) -> Result<Self> {
+        source.seek(SeekFrom::Start(0))?;
+        let header =
+            raw::Header::read_from(&mut *source).context("Failed to read the LAS header")?;
+        source.seek(SeekFrom::Start(header.header_size as u64))?;
+        let vlrs = (0..header.number_of_variable_length_records)
+            .map(|_| raw::Vlr::read_from(&mut *source, false))
+            .collect::<Result<Vec<_>, _>>()
+            .context("Failed to read the VLRs")?;
+
+        let end_of_vlrs = source.stream_position()?;
+        let offset_to_point_data = header.offset_to_point_data as u64;
+        if end_of_vlrs > offset_to_point_data {
+            bail!(
+                "The VLRs end at offset {}, behind the start of the point records at offset {}",
+                end_of_vlrs,
+                offset_to_point_data
+            );
+        }
+        let mut vlr_padding = vec![0; (offset_to_point_data - end_of_vlrs) as usize];
+        source.read_exact(&mut vlr_padding)?;
+
+        let evlrs = match header.evlr.as_ref() {
+            Some(evlr_info) if evlr_info.number_of_evlrs > 0 => {
+                source.seek(SeekFrom::Start(evlr_info.start_of_first_evlr))?;
+                (0..evlr_info.number_of_evlrs)
+                    .map(|_| raw::Vlr::read_from(&mut *source, true))
+                    .collect::<Result<Vec<_>, _>>()
+                    .context("Failed to read the EVLRs")?
+            }
+            _ => vec![],
+        };
+        // In LAS 1.3, the waveform data packets are stored in an EVLR that las-rs doesn't read, so it would be lost
+        let has_internal_waveform_data =
+            header.global_encoding & GLOBAL_ENCODING_INTERNAL_WAVEFORM_DATA_BIT != 0
+                && header
+                    .start_of_waveform_data_packet_record
+                    .unwrap_or_default()
+                    != 0;
+        if has_internal_waveform_data
+            && !evlrs.iter().any(|evlr| {
+                has_user_id(evlr, WAVEFORM_VLR_USER_ID)
+                    && evlr.record_id == WAVEFORM_DATA_PACKETS_RECORD_ID
+            })
+        {
+            bail!("Can't transcode a LAS file whose internal waveform data packets are not stored in an EVLR");
+        }
+
+        source.seek(SeekFrom::Start(offset_to_point_data))?;
+        Ok(Self {
+            point_count: point_count_from_raw_header(&header),
+            header,
+            vlrs,
+            vlr_padding,
+            evlrs,
+        })
+    }
+
+    fn size_of_point_records(&self) -> u64 {
+        self.point_count * self.header.point_data_record_length as u64
+    }
+
+    /// Writes the header and VLRs to `target`, after updating the header fields that depend on the VLRs
+    fn write_header_and_vlrs<W: Write>(&mut self, target: &mut W) -> Result<()> {
+        let size_of_vlrs = self
+            .vlrs
+            .iter()
+            .map(|vlr| SIZE_OF_VLR_HEADER + vlr.data.len() as u64)
+            .sum::<u64>();
+        self.header.offset_to_point_data =
+            (self.header.header_size as u64 + size_of_vlrs + self.vlr_padding.len() as u64)
+                .try_into()
+                .context("The VLRs are too large for a LAS file")?;
+        self.header.number_of_variable_length_records = self.vlrs.len() as u32;
+
+        self.header.write_to(&mut *target)?;
+        for vlr in &self.vlrs {
+            vlr.write_to(&mut *target)?;
+        }
+        target.write_all(&self.vlr_padding)?;
+        Ok(())
+    }
+
+    /// Writes the EVLRs behind the point records, which end at the current position of `target`, and rewrites the
+    /// header with the final positions of the EVLRs
+    fn write_evlrs_and_finish<W: Write + Seek>(&mut self, target: &mut W) -> Result<()> {
+        let end_of_point_records = target.stream_position()?;
+        update_evlrs_in_las_header(end_of_point_records, &self.evlrs, &mut self.header);
+        for evlr in &self.evlrs {
+            evlr.write_to(&mut *target)?;
+        }
+        let end_of_file = target.stream_position()?;
+        target.seek(SeekFrom::Start(0))?;
+        self.header.write_to(&mut *target)?;
+        target.seek(SeekFrom::Start(end_of_file))?;
+        Ok(())
+    }
+}
+
+/// Converts the uncompressed LAS file in `source` into a compressed LAZ file, or the compressed LAZ file in `source`
+/// into an uncompressed LAS file, and writes the result to `target`. Unlike reading the points with a
+/// [`LASReader`](super::LASReader) and writing them with a [`LASWriter`](super::LASWriter), this moves the binary
+/// point records from the decompressor to `target` (or from `source` to the compressor) without decoding any of
+/// their attributes, which is several times faster
+///
+/// The header, VLRs, VLR padding and EVLRs of `source` are copied verbatim, so the point format, the scales and
+/// offsets, the bounds and the point counts of the written file are identical to those of `source`. Only the fields
+/// that describe the compression are rewritten: the compression bit of the point data record format, the LASzip VLR
+/// (which is added with chunks of [`DEFAULT_LAZ_CHUNK_SIZE`] points or removed) and the offsets to the point records
+/// and EVLRs. Both `source` and `target` have to start at the beginning of the file, pass `&mut` references to keep
+/// using them afterwards
+///
+/// # Errors
+///
+/// Returns an error if `source` is no valid LAS/LAZ file, if it contains internal waveform data packets that are not
+/// stored in an EVLR (as in LAS 1.3) or if an I/O error occurs
+pub fn transcode<R: Read + Seek, W: Write + Seek + Send>(
+    mut source: R,
+    mut target: W,
+) -> Result<()> {
+    let mut file = TranscodedFile::read_from(&mut source)?;
+    let laszip_vlr_index = file.vlrs.iter().position(is_laszip_vlr);
+    let is_compressed = file.header.point_data_record_format & LASZIP_COMPRESSION_BITS != 0
+        || laszip_vlr_index.is_some();
+    if is_compressed {
+        let laszip_vlr_index = laszip_vlr_index.ok_or_else(|| {
+            anyhow!("LAZ file contains no LASzip VLR, so it can't be decompressed")
+        })?;
+        let laszip_vlr = file.vlrs.remove(laszip_vlr_index);
+        let laz_vlr = LazVlr::from_buffer(&laszip_vlr.data).map_err(map_laz_err)?;
+        file.header.point_data_record_format &= !LASZIP_COMPRESSION_BITS;
+        file.write_header_and_vlrs(&mut target)?;
+        decompress_point_records(&mut source, &mut target, laz_vlr, &file)?;
+    } else {
+        let laz_vlr = laz_vlr_for_las_header(&file.header)?;
+        let mut laz_vlr_data = Cursor::new(Vec::<u8>::new());
+        laz_vlr.write_to(&mut laz_vlr_data)?;
+        let laszip_vlr = las_rs::Vlr {
+            user_id: LazVlr::USER_ID.to_owned(),
+            record_id: LazVlr::RECORD_ID,
+            description: LazVlr::DESCRIPTION.to_owned(),
+            data: laz_vlr_data.into_inner(),
+        };
+        file.vlrs.push(laszip_vlr.into_raw(false)?);
+        file.header.point_data_record_format |= LASZIP_COMPRESSION_BIT;
+        file.write_header_and_vlrs(&mut target)?;
+        compress_point_records(&mut source, &mut target, laz_vlr, &file)?;
+    }
+    file.write_evlrs_and_finish(&mut target)?;
+    target.flush()?;
+    Ok(())
+}
+
+/// Converts the LAS file at `source_path` into a LAZ file at `target_path` or vice versa, see [`transcode`]
+///
+/// # Errors
+///
+/// In addition to the errors of [`transcode`], returns an error if the extension of `target_path` doesn't match the
+/// transcoded file, i.e. if it is not `.laz` for an uncompressed source or not `.las` for a compressed source
+pub fn transcode_file<P: AsRef<Path>, Q: AsRef<Path>>(
+    source_path: P,
+    target_path: Q,
+) -> Result<()> {
+    let mut source = BufReader::new(
+        File::open(source_path.as_ref())
+            .with_context(|| format!("Could not open file {}", source_path.as_ref().display()))?,
+    );
+    let is_compressed = read_is_compressed_las_file(&mut source)?;
+    if path_is_compressed_las_file(target_path.as_ref())? == is_compressed {
+        bail!(
+            "Transcoding {} creates a {} file, which doesn't match the extension of {}",
+            source_path.as_ref().display(),
+            if is_compressed { "LAS" } else { "LAZ" },
+            target_path.as_ref().display()
+        );
+    }
+    let target =
+        BufWriter::new(File::create(target_path.as_ref()).with_context(|| {
+            format!("Could not create file {}", target_path.as_ref().display())
+        })?);
+    transcode(source, target)
+}
+
+/// Returns the LASzip VLR for compressing the point records of the LAS file with the given `header`
+fn laz_vlr_for_las_header(header: &raw::Header) -> Result<LazVlr> {
+    let point_format_id = header.point_data_record_format & !LASZIP_COMPRESSION_BITS;
+    let size_of_point_format = Format::new(point_format_id)?.len();
+    let num_extra_bytes = header
+        .point_data_record_length
+        .checked_sub(size_of_point_format)
+        .ok_or_else(|| {
+            anyhow!(
+                "Point record length {} is smaller than the {} bytes of point format {}",
+                header.point_data_record_length,
+                size_of_point_format,
+                point_format_id
+            )
+        })?;
+    let laz_items =
+        LazItemRecordBuilder::default_for_point_format_id(point_format_id, num_extra_bytes)
+            .map_err(map_laz_err)?;
+    Ok(LazVlrBuilder::new(laz_items)
+        .with_fixed_chunk_size(DEFAULT_LAZ_CHUNK_SIZE)
+        .build())
+}
+
+/// Compresses the point records of `file`, which `source` is positioned at, into `target`
+fn compress_point_records<R: Read, W: Write + Seek + Send>(
+    source: &mut R,
+    target: &mut W,
+    laz_vlr: LazVlr,
+    file: &TranscodedFile,
+) -> Result<()> {
+    let mut compressor = LasZipCompressor::new(target, laz_vlr).map_err(map_laz_err)?;
+    for_each_chunk_of_point_records(file, |chunk| {
+        source
+            .read_exact(chunk)
+            .context("Failed to read chunk of points")?;
+        compressor.compress_many(chunk)?;
+        Ok(())
+    })?;
+    compressor.done()?;
+    Ok(())
+}
+
+/// Decompresses the point records of `file`, which `source` is positioned at, into `target`
+fn decompress_point_records<R: Read + Seek, W: Write>(
+    source: &mut R,
+    target: &mut W,
+    laz_vlr: LazVlr,
+    file: &TranscodedFile,
+) -> Result<()> {
+    let mut decompressor = LasZipDecompressor::new(source, laz_vlr).map_err(map_laz_err)?;
+    for_each_chunk_of_point_records(file, |chunk| {
+        decompressor
+            .decompress_many(chunk)
+            .context("Failed to decompress chunk of points")?;
+        target.write_all(chunk)?;
+        Ok(())
+    })
+}
+
+/// Calls `process_chunk` with a buffer for each chunk of up to [`DEFAULT_WRITE_CHUNK_SIZE`] point records of `file`.
+/// The buffer is reused for all chunks
+fn for_each_chunk_of_point_records<F: FnMut(&mut [u8]) -> Result<()>>(
+    file: &TranscodedFile,
+    mut process_chunk: F,
+) -> Result<()> {
+    let size_of_point = file.header.point_data_record_length as u64;
+    let size_of_chunk = size_of_point * DEFAULT_WRITE_CHUNK_SIZE as u64;
+    let size_of_point_records = file.size_of_point_records();
+    let mut chunk_buffer = vec![0; size_of_chunk.min(size_of_point_records) as usize];
+    let mut bytes_processed = 0;
+    while bytes_processed < size_of_point_records {
+        let bytes_in_chunk = size_of_chunk.min(size_of_point_records - bytes_processed);
+        process_chunk(&mut chunk_buffer[..bytes_in_chunk as usize])?;
+        bytes_processed += bytes_in_chunk;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pasture_core::containers::VectorBuffer;
+    use scopeguard::defer;
+
+    use crate::{
+        base::{PointReader, PointWriter},
+        las::{
+            compare_to_reference_data, get_test_las_path, get_test_laz_path, test_data_point_count,
+            LASReader, LASWriterBuilder,
+        },
+    };
+
+    use super::*;
+
+    fn read_and_compare_to_reference_data(data: Vec<u8>, format: u8) -> Result<()> {
+        let mut reader =
+            LASReader::from_read_detect_compression(Cursor::new(data), Default::default())?;
+        let points = reader.read::<VectorBuffer>(test_data_point_count())?;
+        compare_to_reference_data(&points, Format::new(format)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_transcode_las_to_laz_and_back() -> Result<()> {
+        for format in 0..=10 {
+            let las_data = std::fs::read(get_test_las_path(format))?;
+
+            let mut laz_data = Cursor::new(Vec::<u8>::new());
+            transcode(Cursor::new(&las_data), &mut laz_data)?;
+            let laz_data = laz_data.into_inner();
+            assert!(read_is_compressed_las_file(&mut Cursor::new(&laz_data))?);
+            read_and_compare_to_reference_data(laz_data.clone(), format)?;
+
+            let mut round_trip_data = Cursor::new(Vec::<u8>::new());
+            transcode(Cursor::new(&laz_data), &mut round_trip_data)?;
+            assert_eq!(
+                las_data,
+                round_trip_data.into_inner(),
+                "Point format {}",
+                format
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_transcode_laz_to_las() -> Result<()> {
+        for format in 0..=10 {
+            let mut las_data = Cursor::new(Vec::<u8>::new());
+            transcode(File::open(get_test_laz_path(format))?, &mut las_data)?;
+            let las_data = las_data.into_inner();
+            assert!(!read_is_compressed_las_file(&mut Cursor::new(&las_data))?);
+            read_and_compare_to_reference_data(las_data, format)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_transcode_keeps_evlrs() -> Result<()> {
+        let evlr = las_rs::Vlr {
+            user_id: "pasture".to_owned(),
+            record_id: 1,
+            description: "Test EVLR".to_owned(),
+            data: vec![42; 1000],
+        };
+        let mut reader = LASReader::from_path(get_test_las_path(6), false)?;
+        let points = reader.read::<VectorBuffer>(test_data_point_count())?;
+        let mut writer = LASWriterBuilder::new(Format::new(6)?)
+            .with_evlr(evlr.clone())
+            .build(Cursor::new(Vec::<u8>::new()))?;
+        writer.write(&points)?;
+        let las_data = writer.into_inner()?.into_inner();
+
+        let mut laz_data = Cursor::new(Vec::<u8>::new());
+        transcode(Cursor::new(&las_data), &mut laz_data)?;
+        let reader = LASReader::from_read(Cursor::new(laz_data.into_inner()), true, false)?;
+        assert_eq!(1, reader.las_metadata().evlrs().len());
+        assert_eq!(evlr.data, reader.las_metadata().evlrs()[0].data);
+        Ok(())
+    }
+
+    #[test]
+    fn test_transcode_file_checks_target_extension() -> Result<()> {
+        let out_path = std::env::temp_dir().join("pasture_test_transcode_file.laz");
+        defer! {
+            std::fs::remove_file(&out_path).expect("Could not remove test file");
+        }
+        transcode_file(get_test_las_path(0), &out_path)?;
+        let mut reader = LASReader::from_path(&out_path, false)?;
+        let points = reader.read::<VectorBuffer>(test_data_point_count())?;
+        compare_to_reference_data(&points, Format::new(0)?);
+
+        assert!(transcode_file(get_test_las_path(0), out_path.with_extension("las")).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,242 @@
+use anyhow::{bail, ensure, Context, Result};
+use las_rs::{Transform, Vector};
+use pasture_core::{
+    containers::BorrowedBuffer,
+    layout::attributes::POSITION_3D,
+    math::AABB,
+    nalgebra::{Point3, Vector3},
+};
+
+/// Largest local coordinate that automatically selected LAS transforms produce for points within the bounds. This is
+/// half of the range of `i32`, which leaves room for points that lie slightly outside of the bounds
+const MAX_COMFORTABLE_LOCAL_COORDINATE: f64 = (i32::MAX / 2) as f64;
+
+/// How the offset of automatically selected LAS transforms is chosen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LASOffsetMode {
+    /// Use the minimum of the bounds as offset, so that all local coordinates are positive
+    #[default]
+    BoundsMinimum,
+    /// Use the center of the bounds as offset, which allows twice the extent for the same scale
+    BoundsCenter,
+}
+
+/// Automatic selection of the scale factors and offsets of a LAS file from the bounds of the points that will be
+/// written into it, see e.g. [`LASWriter::from_writer_and_bounds`](super::LASWriter::from_writer_and_bounds)
+///
+/// The scale of each axis is the `target_resolution`, unless the extent of the bounds along this axis can't be
+/// represented with this resolution within `i32`. In this case, the scale is increased in powers of ten until the
+/// extent fits comfortably. Offsets are multiples of the `target_resolution`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutomaticLASTransforms {
+    /// The desired resolution of the positions in world space units. Defaults to 0.001, i.e. 1mm for metric
+    /// coordinate systems
+    pub target_resolution: f64,
+    /// How the offsets are chosen
+    pub offset_mode: LASOffsetMode,
+}
+
+impl Default for AutomaticLASTransforms {
+    fn default() -> Self {
+        Self {
+            target_resolution: 0.001,
+            offset_mode: Default::default(),
+        }
+    }
+}
+
+impl AutomaticLASTransforms {
+    /// Creates `AutomaticLASTransforms` with the given `target_resolution` and the default offset mode
+    pub fn with_target_resolution(target_resolution: f64) -> Self {
+        Self {
+            target_resolution,
+            ..Default::default()
+        }
+    }
+
+    /// Selects the LAS transforms for points within the given `bounds`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `target_resolution` is not a positive number or if `bounds` is not finite
+    pub fn transforms_for_bounds(&self, bounds: &AABB<f64>) -> Result<Vector<Transform>> {
+        ensure!(
+            self.target_resolution.is_finite() && self.target_resolution > 0.0,
+            "The target resolution for LAS transforms must be a positive number, but was {}",
+            self.target_resolution
+        );
+        Ok(Vector {
+            x: self.transform_for_axis("X", bounds.min().x, bounds.max().x)?,
+            y: self.transform_for_axis("Y", bounds.min().y, bounds.max().y)?,
+            z: self.transform_for_axis("Z", bounds.min().z, bounds.max().z)?,
+        })
+    }
+
+    /// Selects the LAS transforms for the given `points` by first calculating their bounds
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `points` is empty or has no `POSITION_3D` attribute, and in all cases in which
+    /// [`Self::transforms_for_bounds`] returns an error
+    pub fn transforms_for_points<'a, B: BorrowedBuffer<'a>>(
+        &self,
+        points: &'a B,
+    ) -> Result<Vector<Transform>> {
+        let bounds = bounds_of_positions(points)?;
+        self.transforms_for_bounds(&bounds)
+    }
+
+    fn transform_for_axis(&self, axis: &str, min: f64, max: f64) -> Result<Transform> {
+        ensure!(
+            min.is_finite() && max.is_finite(),
+            "Can't select LAS transforms for non-finite bounds ({} axis ranges from {} to {})",
+            axis,
+            min,
+            max
+        );
+        let resolution = self.target_resolution;
+        let offset = match self.offset_mode {
+            LASOffsetMode::BoundsMinimum => (min / resolution).floor() * resolution,
+            LASOffsetMode::BoundsCenter => ((min + max) / 2.0 / resolution).round() * resolution,
+        };
+        let max_distance_to_offset = (max - offset).abs().max((offset - min).abs());
+        let mut scale = resolution;
+        while max_distance_to_offset / scale > MAX_COMFORTABLE_LOCAL_COORDINATE {
+            scale *= 10.0;
+        }
+        Ok(Transform { scale, offset })
+    }
+}
+
+/// Returns the bounds of the `POSITION_3D` attribute of all `points`
+fn bounds_of_positions<'a, B: BorrowedBuffer<'a>>(points: &'a B) -> Result<AABB<f64>> {
+    ensure!(
+        points.len() > 0,
+        "Can't calculate the bounds of an empty point buffer"
+    );
+    let positions = points
+        .view_attribute_with_conversion::<Vector3<f64>>(&POSITION_3D)
+        .context("Can't calculate the bounds of points without a POSITION_3D attribute")?;
+    let mut min = Point3::new(f64::MAX, f64::MAX, f64::MAX);
+    let mut max = Point3::new(f64::MIN, f64::MIN, f64::MIN);
+    for position in positions {
+        if !(position.x.is_finite() && position.y.is_finite() && position.z.is_finite()) {
+            bail!(
+                "Position {} is not finite and can't be written to a LAS file",
+                position
+            );
+        }
+        let position = Point3::from(position);
+        min = min.inf(&position);
+        max = max.sup(&position);
+    }
+    Ok(AABB::from_min_max_unchecked(min, max))
+}
+
+#[cfg(test)]
+mod tests {
+    use pasture_core::{
+        containers::{MakeBufferFromLayout, VectorBuffer},
+        layout::PointType,
+    };
+    use pasture_derive::PointType;
+
+    use super::*;
+
+    #[repr(C, packed)]
+    #[derive(Debug, Clone, Copy, PointType, bytemuck::AnyBitPattern, bytemuck::NoUninit)]
+    struct PositionPoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+    }
+
+    fn bounds(min: [f64; 3], max: [f64; 3]) -> AABB<f64> {
+        AABB::from_min_max(min.into(), max.into())
+    }
+
+    #[test]
+    fn test_transforms_use_target_resolution_for_small_extents() -> Result<()> {
+        let transforms = AutomaticLASTransforms::default().transforms_for_bounds(&bounds(
+            [500_000.1234, 5_400_000.5, -12.3456],
+            [501_000.0, 5_401_000.0, 120.0],
+        ))?;
+        assert_eq!(0.001, transforms.x.scale);
+        assert_eq!(0.001, transforms.y.scale);
+        assert_eq!(0.001, transforms.z.scale);
+        assert!((transforms.x.offset - 500_000.123).abs() < 1e-6);
+        assert!((transforms.y.offset - 5_400_000.5).abs() < 1e-6);
+        assert!((transforms.z.offset - -12.346).abs() < 1e-6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_transforms_for_geographic_coordinates() -> Result<()> {
+        let transforms = AutomaticLASTransforms::with_target_resolution(1e-7)
+            .transforms_for_bounds(&bounds([8.25, 49.75, 100.0], [8.5, 50.0, 200.0]))?;
+        assert_eq!(1e-7, transforms.x.scale);
+        assert_eq!(1e-7, transforms.y.scale);
+        assert!((transforms.x.offset - 8.25).abs() < 1e-6);
+        assert!((transforms.y.offset - 49.75).abs() < 1e-6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_transforms_increase_scale_for_large_extents() -> Result<()> {
+        let transforms = AutomaticLASTransforms::default()
+            .transforms_for_bounds(&bounds([0.0, 0.0, 0.0], [5_000_000.0, 1_000.0, 1.0]))?;
+        // 5,000 km in 1mm steps don't fit into i32, but in 1cm steps they do
+        assert!((transforms.x.scale - 0.01).abs() < 1e-12);
+        assert_eq!(0.001, transforms.y.scale);
+        assert_eq!(0.001, transforms.z.scale);
+        Ok(())
+    }
+
+    #[test]
+    fn test_transforms_with_bounds_center_offset() -> Result<()> {
+        let options = AutomaticLASTransforms {
+            offset_mode: LASOffsetMode::BoundsCenter,
+            ..Default::default()
+        };
+        let transforms =
+            options.transforms_for_bounds(&bounds([-10.0, 100.0, 0.0], [10.0, 300.0, 1.0]))?;
+        assert_eq!(0.0, transforms.x.offset);
+        assert!((transforms.y.offset - 200.0).abs() < 1e-9);
+        assert!((transforms.z.offset - 0.5).abs() < 1e-9);
+        Ok(())
+    }
+
+    #[test]
+    fn test_transforms_with_invalid_resolution() {
+        let bounds = bounds([0.0; 3], [1.0; 3]);
+        assert!(AutomaticLASTransforms::with_target_resolution(0.0)
+            .transforms_for_bounds(&bounds)
+            .is_err());
+        assert!(AutomaticLASTransforms::with_target_resolution(f64::NAN)
+            .transforms_for_bounds(&bounds)
+            .is_err());
+    }
+
+    #[test]
+    fn test_transforms_for_points() -> Result<()> {
+        let points = vec![
+            PositionPoint {
+                position: Vector3::new(1.0, -2.0, 3.0),
+            },
+            PositionPoint {
+                position: Vector3::new(-1.0, 2.0, 5.0),
+            },
+        ]
+        .into_iter()
+        .collect::<VectorBuffer>();
+        let transforms = AutomaticLASTransforms::default().transforms_for_points(&points)?;
+        assert!((transforms.x.offset - -1.0).abs() < 1e-9);
+        assert!((transforms.y.offset - -2.0).abs() < 1e-9);
+        assert!((transforms.z.offset - 3.0).abs() < 1e-9);
+
+        let empty_points = VectorBuffer::new_from_layout(PositionPoint::layout());
+        assert!(AutomaticLASTransforms::default()
+            .transforms_for_points(&empty_points)
+            .is_err());
+        Ok(())
+    }
+}
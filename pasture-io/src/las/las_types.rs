@@ -1,10 +1,21 @@
 //! Contains types for each of the LAS point formats
 
+use anyhow::{bail, Result};
 use las::{point::ScanDirection, Point};
 use pasture_core::nalgebra::Vector3;
 use pasture_derive::PointType;
 use static_assertions::const_assert_eq;
-use std::convert::From;
+use std::{convert::From, fmt::Display};
+
+use super::legacy_las_classification_byte;
+
+/// Returns the synthetic, key-point and withheld flags of the given `las_point` in the same bit order as the
+/// classification flags of the extended point formats
+fn legacy_classification_flags(las_point: &Point) -> u8 {
+    (las_point.is_synthetic as u8)
+        | (las_point.is_key_point as u8) << 1
+        | (las_point.is_withheld as u8) << 2
+}
 
 /// Point type for LAS point format 0
 #[repr(C, packed)]
@@ -26,6 +37,8 @@ pub struct LasPointFormat0 {
     pub edge_of_flight_line: u8,
     #[pasture(BUILTIN_CLASSIFICATION)]
     pub classification: u8,
+    #[pasture(BUILTIN_CLASSIFICATION_FLAGS)]
+    pub classification_flags: u8,
     #[pasture(BUILTIN_SCAN_ANGLE_RANK)]
     pub scan_angle_rank: i8,
     #[pasture(BUILTIN_USER_DATA)]
@@ -34,7 +47,7 @@ pub struct LasPointFormat0 {
     pub point_source_id: u16,
 }
 
-const_assert_eq!(std::mem::size_of::<LasPointFormat0>(), 35);
+const_assert_eq!(std::mem::size_of::<LasPointFormat0>(), 36);
 
 impl From<Point> for LasPointFormat0 {
     fn from(las_point: Point) -> Self {
@@ -53,6 +66,7 @@ impl From<Point> for LasPointFormat0 {
                 0
             },
             classification: las_point.classification.into(),
+            classification_flags: legacy_classification_flags(&las_point),
             scan_angle_rank: las_point.scan_angle as i8,
             user_data: las_point.user_data,
             point_source_id: las_point.point_source_id,
@@ -80,6 +94,8 @@ pub struct LasPointFormat1 {
     pub edge_of_flight_line: u8,
     #[pasture(BUILTIN_CLASSIFICATION)]
     pub classification: u8,
+    #[pasture(BUILTIN_CLASSIFICATION_FLAGS)]
+    pub classification_flags: u8,
     #[pasture(BUILTIN_SCAN_ANGLE_RANK)]
     pub scan_angle_rank: i8,
     #[pasture(BUILTIN_USER_DATA)]
@@ -90,7 +106,7 @@ pub struct LasPointFormat1 {
     pub gps_time: f64,
 }
 
-const_assert_eq!(std::mem::size_of::<LasPointFormat1>(), 43);
+const_assert_eq!(std::mem::size_of::<LasPointFormat1>(), 44);
 
 impl From<Point> for LasPointFormat1 {
     fn from(las_point: Point) -> Self {
@@ -109,6 +125,7 @@ impl From<Point> for LasPointFormat1 {
                 0
             },
             classification: las_point.classification.into(),
+            classification_flags: legacy_classification_flags(&las_point),
             scan_angle_rank: las_point.scan_angle as i8,
             user_data: las_point.user_data,
             point_source_id: las_point.point_source_id,
@@ -137,6 +154,8 @@ pub struct LasPointFormat2 {
     pub edge_of_flight_line: u8,
     #[pasture(BUILTIN_CLASSIFICATION)]
     pub classification: u8,
+    #[pasture(BUILTIN_CLASSIFICATION_FLAGS)]
+    pub classification_flags: u8,
     #[pasture(BUILTIN_SCAN_ANGLE_RANK)]
     pub scan_angle_rank: i8,
     #[pasture(BUILTIN_USER_DATA)]
@@ -147,7 +166,7 @@ pub struct LasPointFormat2 {
     pub color_rgb: Vector3<u16>,
 }
 
-const_assert_eq!(std::mem::size_of::<LasPointFormat2>(), 41);
+const_assert_eq!(std::mem::size_of::<LasPointFormat2>(), 42);
 
 impl From<Point> for LasPointFormat2 {
     fn from(las_point: Point) -> Self {
@@ -169,6 +188,7 @@ impl From<Point> for LasPointFormat2 {
                 0
             },
             classification: las_point.classification.into(),
+            classification_flags: legacy_classification_flags(&las_point),
             scan_angle_rank: las_point.scan_angle as i8,
             user_data: las_point.user_data,
             point_source_id: las_point.point_source_id,
@@ -197,6 +217,8 @@ pub struct LasPointFormat3 {
     pub edge_of_flight_line: u8,
     #[pasture(BUILTIN_CLASSIFICATION)]
     pub classification: u8,
+    #[pasture(BUILTIN_CLASSIFICATION_FLAGS)]
+    pub classification_flags: u8,
     #[pasture(BUILTIN_SCAN_ANGLE_RANK)]
     pub scan_angle_rank: i8,
     #[pasture(BUILTIN_USER_DATA)]
@@ -209,7 +231,7 @@ pub struct LasPointFormat3 {
     pub color_rgb: Vector3<u16>,
 }
 
-const_assert_eq!(std::mem::size_of::<LasPointFormat3>(), 49);
+const_assert_eq!(std::mem::size_of::<LasPointFormat3>(), 50);
 
 impl From<Point> for LasPointFormat3 {
     fn from(las_point: Point) -> Self {
@@ -231,6 +253,7 @@ impl From<Point> for LasPointFormat3 {
                 0
             },
             classification: las_point.classification.into(),
+            classification_flags: legacy_classification_flags(&las_point),
             scan_angle_rank: las_point.scan_angle as i8,
             user_data: las_point.user_data,
             point_source_id: las_point.point_source_id,
@@ -260,6 +283,8 @@ pub struct LasPointFormat4 {
     pub edge_of_flight_line: u8,
     #[pasture(BUILTIN_CLASSIFICATION)]
     pub classification: u8,
+    #[pasture(BUILTIN_CLASSIFICATION_FLAGS)]
+    pub classification_flags: u8,
     #[pasture(BUILTIN_SCAN_ANGLE_RANK)]
     pub scan_angle_rank: i8,
     #[pasture(BUILTIN_USER_DATA)]
@@ -280,7 +305,7 @@ pub struct LasPointFormat4 {
     pub waveform_parameters: Vector3<f32>,
 }
 
-const_assert_eq!(std::mem::size_of::<LasPointFormat4>(), 72);
+const_assert_eq!(std::mem::size_of::<LasPointFormat4>(), 73);
 
 impl From<Point> for LasPointFormat4 {
     fn from(las_point: Point) -> Self {
@@ -300,6 +325,7 @@ impl From<Point> for LasPointFormat4 {
                 0
             },
             classification: las_point.classification.into(),
+            classification_flags: legacy_classification_flags(&las_point),
             scan_angle_rank: las_point.scan_angle as i8,
             user_data: las_point.user_data,
             point_source_id: las_point.point_source_id,
@@ -333,6 +359,8 @@ pub struct LasPointFormat5 {
     pub edge_of_flight_line: u8,
     #[pasture(BUILTIN_CLASSIFICATION)]
     pub classification: u8,
+    #[pasture(BUILTIN_CLASSIFICATION_FLAGS)]
+    pub classification_flags: u8,
     #[pasture(BUILTIN_SCAN_ANGLE_RANK)]
     pub scan_angle_rank: i8,
     #[pasture(BUILTIN_USER_DATA)]
@@ -355,7 +383,7 @@ pub struct LasPointFormat5 {
     pub waveform_parameters: Vector3<f32>,
 }
 
-const_assert_eq!(std::mem::size_of::<LasPointFormat5>(), 78);
+const_assert_eq!(std::mem::size_of::<LasPointFormat5>(), 79);
 
 impl From<Point> for LasPointFormat5 {
     fn from(las_point: Point) -> Self {
@@ -376,6 +404,7 @@ impl From<Point> for LasPointFormat5 {
                 0
             },
             classification: las_point.classification.into(),
+            classification_flags: legacy_classification_flags(&las_point),
             scan_angle_rank: las_point.scan_angle as i8,
             user_data: las_point.user_data,
             point_source_id: las_point.point_source_id,
@@ -412,6 +441,8 @@ pub struct LasPointFormat6 {
     pub scan_direction_flag: u8,
     #[pasture(BUILTIN_EDGE_OF_FLIGHT_LINE)]
     pub edge_of_flight_line: u8,
+    #[pasture(BUILTIN_OVERLAP)]
+    pub overlap: u8,
     #[pasture(BUILTIN_CLASSIFICATION)]
     pub classification: u8,
     #[pasture(BUILTIN_USER_DATA)]
@@ -424,7 +455,7 @@ pub struct LasPointFormat6 {
     pub gps_time: f64,
 }
 
-const_assert_eq!(std::mem::size_of::<LasPointFormat6>(), 46);
+const_assert_eq!(std::mem::size_of::<LasPointFormat6>(), 47);
 
 /// Point type for LAS point format 7
 #[repr(C, packed)]
@@ -448,6 +479,8 @@ pub struct LasPointFormat7 {
     pub scan_direction_flag: u8,
     #[pasture(BUILTIN_EDGE_OF_FLIGHT_LINE)]
     pub edge_of_flight_line: u8,
+    #[pasture(BUILTIN_OVERLAP)]
+    pub overlap: u8,
     #[pasture(BUILTIN_CLASSIFICATION)]
     pub classification: u8,
     #[pasture(BUILTIN_USER_DATA)]
@@ -462,7 +495,7 @@ pub struct LasPointFormat7 {
     pub color_rgb: Vector3<u16>,
 }
 
-const_assert_eq!(std::mem::size_of::<LasPointFormat7>(), 52);
+const_assert_eq!(std::mem::size_of::<LasPointFormat7>(), 53);
 
 /// Point type for LAS point format 8
 #[repr(C, packed)]
@@ -486,6 +519,8 @@ pub struct LasPointFormat8 {
     pub scan_direction_flag: u8,
     #[pasture(BUILTIN_EDGE_OF_FLIGHT_LINE)]
     pub edge_of_flight_line: u8,
+    #[pasture(BUILTIN_OVERLAP)]
+    pub overlap: u8,
     #[pasture(BUILTIN_CLASSIFICATION)]
     pub classification: u8,
     #[pasture(BUILTIN_USER_DATA)]
@@ -502,7 +537,7 @@ pub struct LasPointFormat8 {
     pub nir: u16,
 }
 
-const_assert_eq!(std::mem::size_of::<LasPointFormat8>(), 54);
+const_assert_eq!(std::mem::size_of::<LasPointFormat8>(), 55);
 
 /// Point type for LAS point format 9
 #[repr(C, packed)]
@@ -526,6 +561,8 @@ pub struct LasPointFormat9 {
     pub scan_direction_flag: u8,
     #[pasture(BUILTIN_EDGE_OF_FLIGHT_LINE)]
     pub edge_of_flight_line: u8,
+    #[pasture(BUILTIN_OVERLAP)]
+    pub overlap: u8,
     #[pasture(BUILTIN_CLASSIFICATION)]
     pub classification: u8,
     #[pasture(BUILTIN_USER_DATA)]
@@ -548,7 +585,7 @@ pub struct LasPointFormat9 {
     pub waveform_parameters: Vector3<f32>,
 }
 
-const_assert_eq!(std::mem::size_of::<LasPointFormat9>(), 75);
+const_assert_eq!(std::mem::size_of::<LasPointFormat9>(), 76);
 
 /// Point type for LAS point format 10
 #[repr(C, packed)]
@@ -572,6 +609,8 @@ pub struct LasPointFormat10 {
     pub scan_direction_flag: u8,
     #[pasture(BUILTIN_EDGE_OF_FLIGHT_LINE)]
     pub edge_of_flight_line: u8,
+    #[pasture(BUILTIN_OVERLAP)]
+    pub overlap: u8,
     #[pasture(BUILTIN_CLASSIFICATION)]
     pub classification: u8,
     #[pasture(BUILTIN_USER_DATA)]
@@ -598,7 +637,151 @@ pub struct LasPointFormat10 {
     pub waveform_parameters: Vector3<f32>,
 }
 
-const_assert_eq!(std::mem::size_of::<LasPointFormat10>(), 83);
+const_assert_eq!(std::mem::size_of::<LasPointFormat10>(), 84);
+
+/// What the LAS writers do with values that don't fit into the bit fields of a LAS point record, e.g. a return number
+/// of 16 in the 4-bit return number field of the extended point formats, and with values outside of the range that the
+/// LAS specification allows, such as classifications above 31 in the point formats 0-5 or scan angles beyond 90 degrees
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BitAttributeOverflowHandling {
+    /// Writing a point with a value that does not fit into its bit field or range fails with an error
+    #[default]
+    Error,
+    /// Values that don't fit into their bit field or range are replaced with the closest representable value
+    Saturate,
+    /// Only the lowest bits of values that don't fit into their bit field are written. Values outside of their range
+    /// are written unchanged
+    Wrap,
+}
+
+/// Returns `value` if it fits into `bits` bits, otherwise handles the overflow according to `overflow_handling`
+fn fit_to_bit_width(
+    attribute_name: &str,
+    value: u8,
+    bits: u32,
+    overflow_handling: BitAttributeOverflowHandling,
+) -> Result<u8> {
+    let max_value = ((1u32 << bits) - 1) as u8;
+    if value <= max_value {
+        return Ok(value);
+    }
+    match overflow_handling {
+        BitAttributeOverflowHandling::Error => bail!(
+            "{} {} can't be written to the LAS point record, as it only has {} bits for this value (maximum value {})",
+            attribute_name,
+            value,
+            bits,
+            max_value
+        ),
+        BitAttributeOverflowHandling::Saturate => Ok(max_value),
+        BitAttributeOverflowHandling::Wrap => Ok(value & max_value),
+    }
+}
+
+/// Returns `value` if it is within `min..=max`, otherwise handles the overflow according to `overflow_handling`
+fn fit_to_range<T: PartialOrd + Display + Copy>(
+    attribute_name: &str,
+    value: T,
+    min: T,
+    max: T,
+    overflow_handling: BitAttributeOverflowHandling,
+) -> Result<T> {
+    if value >= min && value <= max {
+        return Ok(value);
+    }
+    match overflow_handling {
+        BitAttributeOverflowHandling::Error => bail!(
+            "{} {} can't be written to the LAS point record, as it is outside of the valid range [{};{}]",
+            attribute_name,
+            value,
+            min,
+            max
+        ),
+        BitAttributeOverflowHandling::Saturate if value < min => Ok(min),
+        BitAttributeOverflowHandling::Saturate => Ok(max),
+        BitAttributeOverflowHandling::Wrap => Ok(value),
+    }
+}
+
+/// Returns the byte that stores the `classification` and the `classification_flags` in the point formats 0-5, which
+/// only have 5 bits for the classification. Of the classification flags, only the synthetic, key-point and withheld
+/// flags can be stored in bits 5-7, the overlap flag of the extended point formats is handled by
+/// [`drops_legacy_overlap_flag`]
+pub(crate) fn fit_legacy_classification_byte(
+    classification: u8,
+    classification_flags: u8,
+    overflow_handling: BitAttributeOverflowHandling,
+) -> Result<u8> {
+    Ok(legacy_las_classification_byte(
+        fit_to_bit_width("Classification", classification, 5, overflow_handling)?,
+        classification_flags,
+    ))
+}
+
+/// What the LAS writers do with points whose overlap flag is set when writing the point formats 0-5, which have no bit
+/// for the overlap flag
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum OverlapFlagHandling {
+    /// The overlap flag is dropped. The writers count the points whose overlap flag was dropped, so that callers can
+    /// warn about it, see e.g. `LASWriter::dropped_overlap_flags`
+    #[default]
+    Drop,
+    /// Writing a point with the overlap flag set fails with an error
+    Error,
+}
+
+/// Returns `true` if a point that is written to the point formats 0-5 has its overlap flag set, which these formats
+/// can't store. The flag is set if either the `overlap` attribute is non-zero or if bit 3 of the `classification_flags`
+/// is set, which is where the extended point formats store it. With [`OverlapFlagHandling::Error`], a set overlap flag
+/// is an error instead
+pub(crate) fn drops_legacy_overlap_flag(
+    overlap: u8,
+    classification_flags: u8,
+    overlap_handling: OverlapFlagHandling,
+) -> Result<bool> {
+    if overlap == 0 && classification_flags & 0b1000 == 0 {
+        return Ok(false);
+    }
+    match overlap_handling {
+        OverlapFlagHandling::Drop => Ok(true),
+        OverlapFlagHandling::Error => bail!(
+            "The overlap flag can't be written to the point formats 0-5, use one of the extended point formats 6-10 to keep it"
+        ),
+    }
+}
+
+/// Largest absolute scan angle rank of the point formats 0-5 in degrees
+const MAX_SCAN_ANGLE_RANK: i8 = 90;
+/// Largest absolute scan angle of the point formats 6-10 in increments of 0.006 degrees, i.e. 180 degrees
+const MAX_EXTENDED_SCAN_ANGLE: i16 = 30_000;
+
+/// Checks that the `scan_angle_rank` of the point formats 0-5 is within -90 to 90 degrees
+pub(crate) fn fit_scan_angle_rank(
+    scan_angle_rank: i8,
+    overflow_handling: BitAttributeOverflowHandling,
+) -> Result<i8> {
+    fit_to_range(
+        "Scan angle rank",
+        scan_angle_rank,
+        -MAX_SCAN_ANGLE_RANK,
+        MAX_SCAN_ANGLE_RANK,
+        overflow_handling,
+    )
+}
+
+/// Checks that the `scan_angle` of the point formats 6-10 is within -30000 to 30000, i.e. -180 to 180 degrees
+pub(crate) fn fit_extended_scan_angle(
+    scan_angle: i16,
+    overflow_handling: BitAttributeOverflowHandling,
+) -> Result<i16> {
+    fit_to_range(
+        "Scan angle",
+        scan_angle,
+        -MAX_EXTENDED_SCAN_ANGLE,
+        MAX_EXTENDED_SCAN_ANGLE,
+        overflow_handling,
+    )
+}
 
 #[derive(Debug, Copy, Clone)]
 pub struct BitAttributesRegular {
@@ -616,6 +799,7 @@ pub struct BitAttributesExtended {
     pub scanner_channel: u8,
     pub scan_direction_flag: u8,
     pub edge_of_flight_line: u8,
+    pub overlap: u8,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -623,3 +807,67 @@ pub enum BitAttributes {
     Regular(BitAttributesRegular),
     Extended(BitAttributesExtended),
 }
+
+impl BitAttributes {
+    /// Returns the return number stored in these `BitAttributes`
+    pub fn return_number(&self) -> u8 {
+        match self {
+            BitAttributes::Regular(attributes) => attributes.return_number,
+            BitAttributes::Extended(attributes) => attributes.return_number,
+        }
+    }
+
+    /// Makes sure that all values fit into their bit fields in the LAS point record. The regular point formats 0-5
+    /// store the return number and number of returns with 3 bits, the extended point formats 6-10 use 4 bits for them
+    /// and for the classification flags and 2 bits for the scanner channel. All flags have a single bit. Values that
+    /// are too large are handled according to `overflow_handling`
+    pub fn fit_to_bit_widths(
+        self,
+        overflow_handling: BitAttributeOverflowHandling,
+    ) -> Result<Self> {
+        let fit = |attribute_name: &str, value: u8, bits: u32| {
+            fit_to_bit_width(attribute_name, value, bits, overflow_handling)
+        };
+        match self {
+            BitAttributes::Regular(attributes) => {
+                Ok(BitAttributes::Regular(BitAttributesRegular {
+                    return_number: fit("Return number", attributes.return_number, 3)?,
+                    number_of_returns: fit("Number of returns", attributes.number_of_returns, 3)?,
+                    scan_direction_flag: fit(
+                        "Scan direction flag",
+                        attributes.scan_direction_flag,
+                        1,
+                    )?,
+                    edge_of_flight_line: fit(
+                        "Edge of flight line flag",
+                        attributes.edge_of_flight_line,
+                        1,
+                    )?,
+                }))
+            }
+            BitAttributes::Extended(attributes) => {
+                Ok(BitAttributes::Extended(BitAttributesExtended {
+                    return_number: fit("Return number", attributes.return_number, 4)?,
+                    number_of_returns: fit("Number of returns", attributes.number_of_returns, 4)?,
+                    classification_flags: fit(
+                        "Classification flags",
+                        attributes.classification_flags,
+                        4,
+                    )?,
+                    scanner_channel: fit("Scanner channel", attributes.scanner_channel, 2)?,
+                    scan_direction_flag: fit(
+                        "Scan direction flag",
+                        attributes.scan_direction_flag,
+                        1,
+                    )?,
+                    edge_of_flight_line: fit(
+                        "Edge of flight line flag",
+                        attributes.edge_of_flight_line,
+                        1,
+                    )?,
+                    overlap: fit("Overlap flag", attributes.overlap, 1)?,
+                }))
+            }
+        }
+    }
+}
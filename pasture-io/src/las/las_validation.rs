@@ -0,0 +1,337 @@
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::fmt::Display;
+use std::io::SeekFrom;
+
+use anyhow::{Context, Result};
+use pasture_core::containers::{InterleavedBuffer, OwningBuffer, VectorBuffer};
+use pasture_core::layout::attributes::{CLASSIFICATION, GPS_TIME};
+use pasture_core::layout::PointLayout;
+use pasture_core::math::AABB;
+use pasture_core::nalgebra::{Point3, Vector3};
+
+use super::{
+    las_bounds_to_pasture_bounds, point_layout_from_las_metadata, LASMetadata,
+    ATTRIBUTE_BASIC_FLAGS, ATTRIBUTE_EXTENDED_FLAGS,
+};
+use crate::base::SeekToPoint;
+
+/// Options for validating the point records of a LAS file against its header, see e.g.
+/// [`LASReader::validate`](super::LASReader::validate)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationOptions {
+    /// Check that all positions lie within the bounds stated in the LAS header
+    pub check_bounds: bool,
+    /// Check that the return number of each point is at most its number of returns
+    pub check_return_numbers: bool,
+    /// Check that no point uses a classification that is reserved for its point format
+    pub check_classifications: bool,
+    /// Check that the GPS times of the points never decrease. Many files are not sorted by GPS time, so this check is
+    /// disabled by default
+    pub check_gps_time_monotonicity: bool,
+    /// For each kind of violation, at most this many indices of offending points are stored in the
+    /// [`ValidationReport`]
+    pub max_reported_indices: usize,
+}
+
+impl Default for ValidationOptions {
+    fn default() -> Self {
+        Self {
+            check_bounds: true,
+            check_return_numbers: true,
+            check_classifications: true,
+            check_gps_time_monotonicity: false,
+            max_reported_indices: 100,
+        }
+    }
+}
+
+/// Kinds of violations that the validation of LAS point records can find
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ValidationViolation {
+    /// The position of the point lies outside of the bounds stated in the LAS header
+    PositionOutsideOfBounds,
+    /// The return number of the point is larger than its number of returns
+    ReturnNumberExceedsNumberOfReturns,
+    /// The classification of the point is reserved for its point format
+    ReservedClassification,
+    /// The GPS time of the point is smaller than the GPS time of the previous point
+    DecreasingGPSTime,
+}
+
+impl Display for ValidationViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationViolation::PositionOutsideOfBounds => write!(f, "Position outside of bounds"),
+            ValidationViolation::ReturnNumberExceedsNumberOfReturns => {
+                write!(f, "Return number exceeds number of returns")
+            }
+            ValidationViolation::ReservedClassification => write!(f, "Reserved classification"),
+            ValidationViolation::DecreasingGPSTime => write!(f, "Decreasing GPS time"),
+        }
+    }
+}
+
+/// All occurrences of a single kind of violation
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ViolationSummary {
+    /// Number of points with this violation
+    pub count: usize,
+    /// Indices of the first offending points, at most [`ValidationOptions::max_reported_indices`] many
+    pub point_indices: Vec<usize>,
+}
+
+/// Result of validating the point records of a LAS file against its header
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    point_count: usize,
+    violations: BTreeMap<ValidationViolation, ViolationSummary>,
+}
+
+impl ValidationReport {
+    /// Returns the number of points that were checked
+    pub fn point_count(&self) -> usize {
+        self.point_count
+    }
+
+    /// Returns `true` if no violations were found
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Returns all violations that were found, together with the offending points
+    pub fn violations(&self) -> &BTreeMap<ValidationViolation, ViolationSummary> {
+        &self.violations
+    }
+
+    /// Returns the number of points with the given `violation`
+    pub fn violation_count(&self, violation: ValidationViolation) -> usize {
+        self.violations
+            .get(&violation)
+            .map(|summary| summary.count)
+            .unwrap_or_default()
+    }
+
+    fn add_violation(
+        &mut self,
+        violation: ValidationViolation,
+        point_index: usize,
+        max_reported_indices: usize,
+    ) {
+        let summary = self.violations.entry(violation).or_default();
+        summary.count += 1;
+        if summary.point_indices.len() < max_reported_indices {
+            summary.point_indices.push(point_index);
+        }
+    }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Validated {} points", self.point_count)?;
+        if self.is_valid() {
+            return writeln!(f, "\tNo violations found");
+        }
+        for (violation, summary) in &self.violations {
+            writeln!(
+                f,
+                "\t{}: {} points, e.g. {:?}",
+                violation, summary.count, summary.point_indices
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Validates binary LAS point records against the header of the LAS file
+pub(crate) struct LASRecordValidator {
+    options: ValidationOptions,
+    /// Header bounds, extended by half the scale factor to account for the quantization of the positions
+    bounds: AABB<f64>,
+    scale: Vector3<f64>,
+    offset: Vector3<f64>,
+    is_extended: bool,
+    flags_offset: usize,
+    classification_offset: usize,
+    gps_time_offset: Option<usize>,
+    previous_gps_time: Option<f64>,
+    report: ValidationReport,
+}
+
+impl LASRecordValidator {
+    pub fn new(metadata: &LASMetadata, options: &ValidationOptions) -> Result<Self> {
+        let header = metadata.raw_las_header().context("Missing LAS header")?;
+        let transforms = header.transforms();
+        let scale = Vector3::new(transforms.x.scale, transforms.y.scale, transforms.z.scale);
+        let offset = Vector3::new(
+            transforms.x.offset,
+            transforms.y.offset,
+            transforms.z.offset,
+        );
+        let header_bounds = las_bounds_to_pasture_bounds(header.bounds());
+        let tolerance = scale.abs() / 2.0;
+        let bounds = AABB::from_min_max_unchecked(
+            header_bounds.min() - tolerance,
+            header_bounds.max() + tolerance,
+        );
+
+        let is_extended = metadata.point_format().is_extended;
+        let raw_layout = point_layout_from_las_metadata(metadata, true)?;
+        let flags_attribute = if is_extended {
+            &ATTRIBUTE_EXTENDED_FLAGS
+        } else {
+            &ATTRIBUTE_BASIC_FLAGS
+        };
+        let flags_offset = raw_layout
+            .get_attribute(flags_attribute)
+            .expect("LAS point records always contain return numbers")
+            .offset() as usize;
+        let classification_offset = raw_layout
+            .get_attribute(&CLASSIFICATION)
+            .expect("LAS point records always contain a classification")
+            .offset() as usize;
+        let gps_time_offset = raw_layout
+            .get_attribute(&GPS_TIME)
+            .map(|attribute| attribute.offset() as usize);
+
+        Ok(Self {
+            options: options.clone(),
+            bounds,
+            scale,
+            offset,
+            is_extended,
+            flags_offset,
+            classification_offset,
+            gps_time_offset,
+            previous_gps_time: None,
+            report: Default::default(),
+        })
+    }
+
+    /// Is `classification` reserved in the LAS 1.4 specification for the point format of the file?
+    fn is_reserved_classification(&self, classification: u8) -> bool {
+        if self.is_extended {
+            classification == 12 || (23..=63).contains(&classification)
+        } else {
+            matches!(classification & 0x1F, 10 | 11 | 13..=31)
+        }
+    }
+
+    pub fn validate_record(&mut self, point_index: usize, record: &[u8]) {
+        let max_reported_indices = self.options.max_reported_indices;
+        self.report.point_count += 1;
+
+        if self.options.check_bounds {
+            let local_coordinate = |index: usize| -> f64 {
+                let offset = index * 4;
+                i32::from_le_bytes(record[offset..offset + 4].try_into().unwrap()) as f64
+            };
+            let local_position = Vector3::new(
+                local_coordinate(0),
+                local_coordinate(1),
+                local_coordinate(2),
+            );
+            let world_position =
+                Point3::from(local_position.component_mul(&self.scale) + self.offset);
+            if !self.bounds.contains(&world_position) {
+                self.report.add_violation(
+                    ValidationViolation::PositionOutsideOfBounds,
+                    point_index,
+                    max_reported_indices,
+                );
+            }
+        }
+
+        if self.options.check_return_numbers {
+            let flags = record[self.flags_offset];
+            let (bits_per_value, value_mask) = if self.is_extended {
+                (4, 0b1111)
+            } else {
+                (3, 0b111)
+            };
+            let return_number = flags & value_mask;
+            let number_of_returns = (flags >> bits_per_value) & value_mask;
+            if return_number > number_of_returns {
+                self.report.add_violation(
+                    ValidationViolation::ReturnNumberExceedsNumberOfReturns,
+                    point_index,
+                    max_reported_indices,
+                );
+            }
+        }
+
+        if self.options.check_classifications
+            && self.is_reserved_classification(record[self.classification_offset])
+        {
+            self.report.add_violation(
+                ValidationViolation::ReservedClassification,
+                point_index,
+                max_reported_indices,
+            );
+        }
+
+        if self.options.check_gps_time_monotonicity {
+            if let Some(gps_time_offset) = self.gps_time_offset {
+                let gps_time = f64::from_le_bytes(
+                    record[gps_time_offset..gps_time_offset + 8]
+                        .try_into()
+                        .unwrap(),
+                );
+                if let Some(previous_gps_time) = self.previous_gps_time {
+                    if gps_time < previous_gps_time {
+                        self.report.add_violation(
+                            ValidationViolation::DecreasingGPSTime,
+                            point_index,
+                            max_reported_indices,
+                        );
+                    }
+                }
+                self.previous_gps_time = Some(gps_time);
+            }
+        }
+    }
+
+    pub fn into_report(self) -> ValidationReport {
+        self.report
+    }
+}
+
+/// Validates all points of `reader` using `validator`. The binary point records are read in chunks through
+/// `read_raw_points`, which gets a buffer with the `raw_las_layout`. Afterwards, `reader` is moved back to the point
+/// it was at before the validation
+pub(crate) fn validate_las_points<R: SeekToPoint>(
+    reader: &mut R,
+    mut validator: LASRecordValidator,
+    raw_las_layout: &PointLayout,
+    mut read_raw_points: impl FnMut(&mut R, &mut VectorBuffer, usize) -> Result<usize>,
+) -> Result<ValidationReport> {
+    const CHUNK_BYTES: usize = 1 << 20; // 1 MiB
+    let size_of_point = raw_las_layout.size_of_point_entry() as usize;
+    let points_per_chunk = (CHUNK_BYTES / size_of_point).max(1);
+
+    let previous_point_index = reader.point_index()?;
+    let point_count = reader.point_count()?;
+    reader.seek_point(SeekFrom::Start(0))?;
+
+    let mut chunk =
+        VectorBuffer::with_capacity(points_per_chunk.min(point_count), raw_las_layout.clone());
+    let mut point_index = 0;
+    while point_index < point_count {
+        let points_in_chunk = (point_count - point_index).min(points_per_chunk);
+        chunk.resize(points_in_chunk);
+        let points_read = read_raw_points(reader, &mut chunk, points_in_chunk)?;
+        if points_read == 0 {
+            break;
+        }
+        for record in chunk
+            .get_point_range_ref(0..points_read)
+            .chunks_exact(size_of_point)
+        {
+            validator.validate_record(point_index, record);
+            point_index += 1;
+        }
+    }
+
+    reader.seek_point(SeekFrom::Start(previous_point_index as u64))?;
+    Ok(validator.into_report())
+}
@@ -0,0 +1,444 @@
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use byteorder::{LittleEndian, ReadBytesExt};
+use las_rs::Vlr;
+use pasture_core::layout::attributes::{
+    RETURN_POINT_WAVEFORM_LOCATION, WAVEFORM_DATA_OFFSET, WAVEFORM_PACKET_SIZE,
+    WAVE_PACKET_DESCRIPTOR_INDEX,
+};
+use pasture_core::layout::{PointAttributeDefinition, PointLayout};
+
+use super::LASMetadata;
+
+/// `user_id` of the waveform VLRs and EVLRs
+pub const WAVEFORM_VLR_USER_ID: &str = "LASF_Spec";
+/// Record ID of the Waveform Packet Descriptor VLR with index 1. The descriptors with the indices 1 to 255 are stored
+/// in the VLRs with the record IDs 100 to 354
+pub const WAVEFORM_PACKET_DESCRIPTOR_FIRST_RECORD_ID: u16 = 100;
+/// Record ID of the EVLR that contains the waveform data packets, if they are stored within the LAS file
+pub const WAVEFORM_DATA_PACKETS_RECORD_ID: u16 = 65535;
+
+/// Size of the header of an EVLR in bytes. The waveform data offsets of the points are relative to the start of this
+/// header, both for internal waveform data and for external `.wdp` files
+pub(crate) const SIZE_OF_EVLR_HEADER: u64 = 60;
+
+/// Contents of a Waveform Packet Descriptor VLR, which describes how the waveform packets of the points that reference
+/// this descriptor are stored
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WaveformPacketDescriptor {
+    pub bits_per_sample: u8,
+    /// Compression type of the waveform packets, zero means uncompressed
+    pub compression_type: u8,
+    pub number_of_samples: u32,
+    /// Temporal spacing between two samples in picoseconds
+    pub temporal_sample_spacing: u32,
+    pub digitizer_gain: f64,
+    pub digitizer_offset: f64,
+}
+
+impl WaveformPacketDescriptor {
+    /// Size of the Waveform Packet Descriptor VLR data in bytes
+    pub const SIZE: usize = 26;
+
+    /// Parses a `WaveformPacketDescriptor` from the data of a Waveform Packet Descriptor VLR
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < Self::SIZE {
+            bail!(
+                "Waveform Packet Descriptor VLR must be {} bytes long, but is only {} bytes long",
+                Self::SIZE,
+                bytes.len()
+            );
+        }
+        let mut reader = bytes;
+        Ok(Self {
+            bits_per_sample: reader.read_u8()?,
+            compression_type: reader.read_u8()?,
+            number_of_samples: reader.read_u32::<LittleEndian>()?,
+            temporal_sample_spacing: reader.read_u32::<LittleEndian>()?,
+            digitizer_gain: reader.read_f64::<LittleEndian>()?,
+            digitizer_offset: reader.read_f64::<LittleEndian>()?,
+        })
+    }
+
+    /// Serializes this descriptor into the data of a Waveform Packet Descriptor VLR
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::SIZE);
+        bytes.push(self.bits_per_sample);
+        bytes.push(self.compression_type);
+        bytes.extend_from_slice(&self.number_of_samples.to_le_bytes());
+        bytes.extend_from_slice(&self.temporal_sample_spacing.to_le_bytes());
+        bytes.extend_from_slice(&self.digitizer_gain.to_le_bytes());
+        bytes.extend_from_slice(&self.digitizer_offset.to_le_bytes());
+        bytes
+    }
+
+    /// Creates the Waveform Packet Descriptor VLR for this descriptor with the given `descriptor_index`, which is the
+    /// value of the `WAVE_PACKET_DESCRIPTOR_INDEX` attribute of the points that use this descriptor
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `descriptor_index` is zero, which is used by points without waveform data
+    pub fn to_vlr(&self, descriptor_index: u8) -> Result<Vlr> {
+        if descriptor_index == 0 {
+            bail!(
+                "Waveform Packet Descriptor index 0 is reserved for points without waveform data"
+            );
+        }
+        Ok(Vlr {
+            user_id: WAVEFORM_VLR_USER_ID.to_owned(),
+            record_id: WAVEFORM_PACKET_DESCRIPTOR_FIRST_RECORD_ID + descriptor_index as u16 - 1,
+            description: "Waveform Packet Descriptor".to_owned(),
+            data: self.to_bytes(),
+        })
+    }
+
+    /// Parses all Waveform Packet Descriptor VLRs within `vlrs`. The descriptors are indexed by their descriptor
+    /// index, which is what the points reference through the `WAVE_PACKET_DESCRIPTOR_INDEX` attribute
+    pub fn from_vlrs(vlrs: &[Vlr]) -> Result<BTreeMap<u8, Self>> {
+        vlrs.iter()
+            .filter_map(|vlr| {
+                let descriptor_index = waveform_packet_descriptor_index(vlr)?;
+                Some(
+                    Self::from_bytes(&vlr.data)
+                        .with_context(|| {
+                            format!(
+                                "Invalid Waveform Packet Descriptor VLR with record ID {}",
+                                vlr.record_id
+                            )
+                        })
+                        .map(|descriptor| (descriptor_index, descriptor)),
+                )
+            })
+            .collect()
+    }
+
+    /// Decodes the samples of the given waveform `packet` and applies the digitizer gain and offset of this
+    /// descriptor to them
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the waveform packet is compressed, if the samples are not 8, 16 or 32 bits large, or if
+    /// `packet` is too small for the number of samples of this descriptor
+    pub fn decode_samples(&self, packet: &[u8]) -> Result<Vec<f64>> {
+        if self.compression_type != 0 {
+            bail!(
+                "Compressed waveform packets (compression type {}) are not supported",
+                self.compression_type
+            );
+        }
+        let bytes_per_sample = match self.bits_per_sample {
+            8 => 1,
+            16 => 2,
+            32 => 4,
+            other => bail!("Waveform samples with {} bits are not supported", other),
+        };
+        let size_of_samples = self.number_of_samples as usize * bytes_per_sample;
+        if packet.len() < size_of_samples {
+            bail!(
+                "Waveform packet is {} bytes large, but {} samples with {} bits require {} bytes",
+                packet.len(),
+                self.number_of_samples,
+                self.bits_per_sample,
+                size_of_samples
+            );
+        }
+
+        Ok(packet[..size_of_samples]
+            .chunks_exact(bytes_per_sample)
+            .map(|sample| {
+                let raw_sample = match bytes_per_sample {
+                    1 => sample[0] as f64,
+                    2 => u16::from_le_bytes(sample.try_into().unwrap()) as f64,
+                    _ => u32::from_le_bytes(sample.try_into().unwrap()) as f64,
+                };
+                self.digitizer_gain * raw_sample + self.digitizer_offset
+            })
+            .collect())
+    }
+}
+
+/// Returns the descriptor index of the given `vlr` if it is a Waveform Packet Descriptor VLR
+fn waveform_packet_descriptor_index(vlr: &Vlr) -> Option<u8> {
+    if vlr.user_id != WAVEFORM_VLR_USER_ID {
+        return None;
+    }
+    let descriptor_index = vlr
+        .record_id
+        .checked_sub(WAVEFORM_PACKET_DESCRIPTOR_FIRST_RECORD_ID - 1)?;
+    let descriptor_index: u8 = descriptor_index.try_into().ok()?;
+    if descriptor_index == 0 {
+        return None;
+    }
+    Some(descriptor_index)
+}
+
+/// Returns the VLRs of the given `metadata` that are required to write its waveform data into another LAS file: All
+/// Waveform Packet Descriptor VLRs and, if the waveform data packets are stored within the file, their EVLR. The
+/// waveform data is not decoded, so the waveform attributes of the points remain valid in the other file
+pub(crate) fn waveform_vlrs_of(metadata: &LASMetadata) -> (Vec<Vlr>, Option<Vlr>) {
+    let descriptor_vlrs = metadata
+        .vlrs()
+        .iter()
+        .filter(|vlr| waveform_packet_descriptor_index(vlr).is_some())
+        .cloned()
+        .collect();
+    let waveform_data_evlr = metadata
+        .find_vlr(WAVEFORM_VLR_USER_ID, WAVEFORM_DATA_PACKETS_RECORD_ID)
+        .cloned();
+    (descriptor_vlrs, waveform_data_evlr)
+}
+
+/// Decoded waveform samples of a single point, as returned by
+/// [`LASReader::read_waveform`](super::LASReader::read_waveform)
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaveformSamples {
+    /// The samples of the waveform, with the digitizer gain and offset applied
+    pub samples: Vec<f64>,
+    /// Temporal spacing between two samples in picoseconds
+    pub temporal_sample_spacing: u32,
+    /// Location of the return of the point within the waveform, as the time in picoseconds from the first sample
+    pub return_point_location: f32,
+}
+
+/// Reads the waveform samples for the point with the given binary point `record`, which has the `raw_layout`. The
+/// waveform data packets are read from the EVLR of `metadata` if there is one, otherwise from the external
+/// `waveform_data_path`
+pub(crate) fn read_waveform_samples(
+    metadata: &LASMetadata,
+    raw_layout: &PointLayout,
+    record: &[u8],
+    waveform_data_path: Option<&Path>,
+) -> Result<WaveformSamples> {
+    let attribute_bytes = |attribute: &PointAttributeDefinition| {
+        raw_layout
+            .get_attribute(attribute)
+            .map(|member| &record[member.byte_range_within_point()])
+            .context("The point format of the LAS file has no waveform data")
+    };
+    let descriptor_index = attribute_bytes(&WAVE_PACKET_DESCRIPTOR_INDEX)?[0];
+    let data_offset = u64::from_le_bytes(attribute_bytes(&WAVEFORM_DATA_OFFSET)?.try_into()?);
+    let packet_size = u32::from_le_bytes(attribute_bytes(&WAVEFORM_PACKET_SIZE)?.try_into()?);
+    let return_point_location =
+        f32::from_le_bytes(attribute_bytes(&RETURN_POINT_WAVEFORM_LOCATION)?.try_into()?);
+    if descriptor_index == 0 {
+        bail!("The point has no waveform data");
+    }
+
+    let descriptors = WaveformPacketDescriptor::from_vlrs(metadata.vlrs())?;
+    let descriptor = descriptors.get(&descriptor_index).with_context(|| {
+        format!(
+            "The point references the Waveform Packet Descriptor {}, which does not exist",
+            descriptor_index
+        )
+    })?;
+
+    let packet = match metadata.find_vlr(WAVEFORM_VLR_USER_ID, WAVEFORM_DATA_PACKETS_RECORD_ID) {
+        Some(waveform_data_evlr) => {
+            let start = data_offset
+                .checked_sub(SIZE_OF_EVLR_HEADER)
+                .context("Waveform data offset points into the header of the waveform data EVLR")?
+                as usize;
+            let end = start + packet_size as usize;
+            waveform_data_evlr
+                .data
+                .get(start..end)
+                .with_context(|| {
+                    format!(
+                        "Waveform packet at bytes {}..{} is outside of the waveform data EVLR",
+                        start, end
+                    )
+                })?
+                .to_vec()
+        }
+        None => {
+            let path = waveform_data_path
+                .context("The LAS file has no internal waveform data and no external waveform data file is known")?;
+            let mut file = BufReader::new(File::open(path).with_context(|| {
+                format!("Could not open waveform data file {}", path.display())
+            })?);
+            file.seek(SeekFrom::Start(data_offset))?;
+            let mut packet = vec![0; packet_size as usize];
+            file.read_exact(&mut packet).with_context(|| {
+                format!(
+                    "Failed to read waveform packet from waveform data file {}",
+                    path.display()
+                )
+            })?;
+            packet
+        }
+    };
+
+    Ok(WaveformSamples {
+        samples: descriptor.decode_samples(&packet)?,
+        temporal_sample_spacing: descriptor.temporal_sample_spacing,
+        return_point_location,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use las_rs::{point::Format, Builder};
+    use pasture_core::containers::VectorBuffer;
+
+    use super::*;
+    use crate::{
+        base::{PointReader, PointWriter},
+        las::{
+            make_las_file_in_memory, point_layout_from_las_point_format, LASReader,
+            LASWriterBuilder,
+        },
+    };
+
+    fn descriptor_vlr(descriptor_index: u8, bits_per_sample: u8, number_of_samples: u32) -> Vlr {
+        let mut data = vec![bits_per_sample, 0];
+        data.extend_from_slice(&number_of_samples.to_le_bytes());
+        data.extend_from_slice(&1000u32.to_le_bytes());
+        data.extend_from_slice(&0.5f64.to_le_bytes());
+        data.extend_from_slice(&1.0f64.to_le_bytes());
+        Vlr {
+            user_id: WAVEFORM_VLR_USER_ID.to_owned(),
+            record_id: WAVEFORM_PACKET_DESCRIPTOR_FIRST_RECORD_ID + descriptor_index as u16 - 1,
+            description: Default::default(),
+            data,
+        }
+    }
+
+    #[test]
+    fn test_waveform_packet_descriptor_decode_samples() -> Result<()> {
+        let descriptors = WaveformPacketDescriptor::from_vlrs(&[
+            descriptor_vlr(1, 8, 3),
+            descriptor_vlr(2, 16, 2),
+        ])?;
+        assert_eq!(2, descriptors.len());
+
+        let descriptor_8_bit = descriptors[&1];
+        assert_eq!(1000, descriptor_8_bit.temporal_sample_spacing);
+        assert_eq!(
+            vec![1.0, 6.0, 11.0],
+            descriptor_8_bit.decode_samples(&[0, 10, 20])?
+        );
+        assert!(descriptor_8_bit.decode_samples(&[0, 10]).is_err());
+
+        let descriptor_16_bit = descriptors[&2];
+        assert_eq!(
+            vec![1.0, 501.0],
+            descriptor_16_bit.decode_samples(&[0, 0, 0xE8, 0x03])?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_waveform_packet_descriptor_to_vlr() -> Result<()> {
+        let descriptor = WaveformPacketDescriptor {
+            bits_per_sample: 16,
+            compression_type: 0,
+            number_of_samples: 64,
+            temporal_sample_spacing: 500,
+            digitizer_gain: 0.25,
+            digitizer_offset: -3.0,
+        };
+        let vlr = descriptor.to_vlr(7)?;
+        assert_eq!(106, vlr.record_id);
+        assert_eq!(WaveformPacketDescriptor::SIZE, vlr.data.len());
+        assert_eq!(
+            Some(&descriptor),
+            WaveformPacketDescriptor::from_vlrs(&[vlr])?.get(&7)
+        );
+        assert!(descriptor.to_vlr(0).is_err());
+        Ok(())
+    }
+
+    /// Creates a LAS file with point format 4 and two points, whose waveforms are stored in the waveform data packets
+    /// EVLR
+    fn make_las_file_with_internal_waveforms() -> Result<Vec<u8>> {
+        let format = Format::new(4)?;
+        let raw_layout = point_layout_from_las_point_format(&format, true)?;
+        let size_of_point = raw_layout.size_of_point_entry() as usize;
+        let samples_per_point = [[10u8, 20, 30, 40], [1, 2, 3, 4]];
+
+        let mut point_records = vec![0; size_of_point * samples_per_point.len()];
+        let offset_of = |attribute: &PointAttributeDefinition| {
+            raw_layout.get_attribute(attribute).unwrap().offset() as usize
+        };
+        for (point_index, record) in point_records.chunks_exact_mut(size_of_point).enumerate() {
+            let data_offset = SIZE_OF_EVLR_HEADER + (point_index * 4) as u64;
+            record[offset_of(&WAVE_PACKET_DESCRIPTOR_INDEX)] = 1;
+            let offset = offset_of(&WAVEFORM_DATA_OFFSET);
+            record[offset..offset + 8].copy_from_slice(&data_offset.to_le_bytes());
+            let offset = offset_of(&WAVEFORM_PACKET_SIZE);
+            record[offset..offset + 4].copy_from_slice(&4u32.to_le_bytes());
+            let offset = offset_of(&RETURN_POINT_WAVEFORM_LOCATION);
+            record[offset..offset + 4].copy_from_slice(&1500.0f32.to_le_bytes());
+        }
+
+        let mut header_builder = Builder::from((1, 4));
+        header_builder.point_format = format;
+        header_builder.vlrs.push(descriptor_vlr(1, 8, 4));
+        header_builder.evlrs.push(Vlr {
+            user_id: WAVEFORM_VLR_USER_ID.to_owned(),
+            record_id: WAVEFORM_DATA_PACKETS_RECORD_ID,
+            description: Default::default(),
+            data: samples_per_point.concat(),
+        });
+        let header = header_builder.into_header()?;
+        make_las_file_in_memory(&header, samples_per_point.len(), &point_records)
+    }
+
+    #[test]
+    fn test_las_reader_read_waveform_from_evlr() -> Result<()> {
+        let las_data = make_las_file_with_internal_waveforms()?;
+        let mut reader = LASReader::from_read(Cursor::new(las_data), false, false)?;
+        let waveform = reader.read_waveform(1)?;
+        assert_eq!(vec![1.5, 2.0, 2.5, 3.0], waveform.samples);
+        assert_eq!(1000, waveform.temporal_sample_spacing);
+        assert_eq!(1500.0, waveform.return_point_location);
+        assert_eq!(
+            vec![6.0, 11.0, 16.0, 21.0],
+            reader.read_waveform(0)?.samples
+        );
+        assert!(reader.read_waveform(2).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_writer_copies_waveforms() -> Result<()> {
+        let mut source_reader = LASReader::from_read(
+            Cursor::new(make_las_file_with_internal_waveforms()?),
+            false,
+            false,
+        )?;
+        let points = source_reader.read::<VectorBuffer>(2)?;
+
+        let mut writer = LASWriterBuilder::new(Format::new(4)?)
+            .with_waveforms_of(source_reader.las_metadata())
+            .build(Cursor::new(Vec::<u8>::new()))?;
+        writer.write(&points)?;
+        let las_data = writer.into_inner()?.into_inner();
+
+        let raw_header = las_rs::raw::Header::read_from(Cursor::new(&las_data))?;
+        assert_ne!(0, raw_header.global_encoding & 0b10);
+        let evlr_info = raw_header.evlr.expect("LAS 1.4 header has EVLR fields");
+        assert_eq!(
+            Some(evlr_info.start_of_first_evlr),
+            raw_header.start_of_waveform_data_packet_record
+        );
+
+        let mut reader = LASReader::from_read(Cursor::new(las_data), false, false)?;
+        for point_index in 0..2 {
+            assert_eq!(
+                source_reader.read_waveform(point_index)?,
+                reader.read_waveform(point_index)?
+            );
+        }
+        Ok(())
+    }
+}
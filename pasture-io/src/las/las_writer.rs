@@ -1,31 +1,89 @@
 #![allow(clippy::upper_case_acronyms)]
-use std::{fs::File, io::BufWriter, io::Seek, io::Write, path::Path};
-
-use anyhow::{Context, Result};
-use las_rs::Builder;
-use pasture_core::{containers::BorrowedBuffer, layout::PointLayout};
+use std::{
+    collections::HashSet, fs::File, fs::OpenOptions, io::BufWriter, io::Seek, io::Write, path::Path,
+};
+
+use anyhow::{bail, Context, Result};
+use chrono::{NaiveDate, Utc};
+use las_rs::{point::Format, Builder, GpsTimeType};
+use pasture_core::{containers::BorrowedBuffer, layout::PointLayout, math::AABB};
+
+use crate::base::PointWriter;
+
+#[cfg(feature = "laz")]
+use super::RawLAZWriter;
+use super::{
+    crs_wkt_vlr, is_known_las_attribute, path_is_compressed_las_file, point_format_from_layout,
+    waveform_vlrs_of, AutomaticLASTransforms, BitAttributeOverflowHandling, ColorUpscaling,
+    ExtraBytesMapping, ExtraBytesVlr, GeoTiffCrs, LASMetadata, LASStreamStatistics,
+    MissingAttributeDefaults, OverlapFlagHandling, RawLASWriter, StreamingLASWriter,
+    UnknownAttributeHandling, COPC_VLR_USER_ID, KNOWN_VLR_USER_ID, LASZIP_VLR_USER_ID,
+    LAX_VLR_USER_ID, PROJECTION_VLR_USER_ID, SUPERSEDED_VLR_RECORD_ID, WKT_CRS_RECORD_ID,
+};
+
+/// Returns an error if LAS files with the given `version` can't store points in the given `point_format`. This catches
+/// invalid combinations before any data is written, instead of producing a malformed file
+pub(crate) fn validate_las_version_and_point_format(
+    version: las_rs::Version,
+    point_format: &Format,
+) -> Result<()> {
+    if version.major != 1 || version.minor > 4 {
+        bail!(
+            "LAS version {}.{} is not supported, only LAS versions 1.0 to 1.4 can be written",
+            version.major,
+            version.minor
+        );
+    }
+    let format_number = point_format.to_u8()?;
+    let minimum_minor_version = match format_number {
+        0 | 1 => 0,
+        2 | 3 => 2,
+        4 | 5 => 3,
+        _ => 4,
+    };
+    if version.minor < minimum_minor_version {
+        bail!(
+            "LAS point format {} requires at least LAS version 1.{}, but the LAS version is {}.{}",
+            format_number,
+            minimum_minor_version,
+            version.major,
+            version.minor
+        );
+    }
+    Ok(())
+}
 
-use crate::{base::PointWriter, las::las_point_format_from_point_layout};
+/// Number of points per chunk of compressed LAZ files, unless configured otherwise through
+/// [`LASWriterBuilder::laz_chunk_size`]. This matches the default chunk size of LASzip
+pub const DEFAULT_LAZ_CHUNK_SIZE: u32 = 50_000;
 
-use super::{path_is_compressed_las_file, RawLASWriter, RawLAZWriter};
+/// Number of points whose records the LAS writers assemble in memory before writing them with a single call to the
+/// underlying writer, unless configured otherwise through [`LASWriterBuilder::write_chunk_size`]
+pub const DEFAULT_WRITE_CHUNK_SIZE: usize = 50_000;
 
 enum WriterVariant<T: Write + Seek + Send + 'static> {
     LAS(RawLASWriter<T>),
+    #[cfg(feature = "laz")]
     LAZ(RawLAZWriter<T>),
 }
 
 /// `PointWriter` implementation for LAS/LAZ files.
 ///
-/// *NOTE*: Due to the nature of the LAS file format, this file
-/// writer requires manual `flush` calls in order to actually write the LAS/LAZ data. Once you are done
-/// writing points, make sure to call `flush` so that the LAS header is updated correctly.
+/// The writer keeps track of the bounds and the point counts (in total and by return) of all written points. Since
+/// these are stored in the LAS header, the header is rewritten once all points have been written, which requires a
+/// seekable output. This happens when calling [`Self::finalize`] or [`Self::into_inner`]. `flush` only flushes the
+/// underlying writer and does not finalize the file. If the writer is dropped without being finalized, it tries to
+/// finalize the file and prints errors to stderr, as errors can't be returned from `drop`. So make sure to call
+/// `finalize` or `into_inner` once you are done writing points.
 pub struct LASWriter<T: Write + Seek + Send + 'static> {
-    writer: WriterVariant<T>,
+    /// Only `None` after `into_inner` or `finalize` has been called
+    writer: Option<WriterVariant<T>>,
 }
 
 impl<T: Write + Seek + Send + 'static> LASWriter<T> {
     /// Creates a new `LASWriter` from the given `writer`. This uses a default-created LAS header for writing,
-    /// with an appropriate point format determined from the given `point_layout`. The LAS header uses a scale
+    /// with the smallest point format that stores all LAS attributes of the given `point_layout` (see
+    /// [`point_format_from_layout`]). The LAS header uses a scale
     /// of 0.001, which yields 1mm precision. LAS version 1.4 is used.
     /// If `is_compressed` is set, the writer will write compressed `LAZ` files instead of `LAS` files.
     pub fn from_writer_and_point_layout(
@@ -33,56 +91,340 @@ impl<T: Write + Seek + Send + 'static> LASWriter<T> {
         point_layout: &PointLayout,
         is_compressed: bool,
     ) -> Result<Self> {
-        // TODO Support writing extra bytes, for now they will be ignored
-        let point_format = las_point_format_from_point_layout(point_layout);
-        let mut header_builder = Builder::from((1, 4));
-        header_builder.point_format = point_format;
-        header_builder.transforms = las_rs::Vector {
-            x: las_rs::Transform {
-                offset: 0.0,
-                scale: 0.001,
-            },
-            y: las_rs::Transform {
-                offset: 0.0,
-                scale: 0.001,
-            },
-            z: las_rs::Transform {
-                offset: 0.0,
-                scale: 0.001,
-            },
-        };
-        let las_header = header_builder
-            .into_header()
-            .context("Could not default-create LAS header")?;
-        Self::from_writer_and_header(writer, las_header, is_compressed)
+        // Extra bytes are only written if explicitly requested, see `from_writer_and_point_format_with_extra_bytes`
+        let point_format = point_format_from_layout(point_layout)?;
+        Self::from_writer_and_point_format(writer, point_format, is_compressed)
+    }
+
+    /// Creates a new `LASWriter` from the given `writer` that writes points in the given `point_format`. Like
+    /// [`Self::from_writer_and_point_layout`], this uses a default-created LAS 1.4 header with a scale of 0.001. The
+    /// points that are passed to `write` can have any `PointLayout` that contains a `POSITION_3D` attribute. Their
+    /// attributes are converted into the datatypes of `point_format`, attributes that `point_format` does not
+    /// support are ignored and attributes that are missing are written with default values.
+    /// If `is_compressed` is set, the writer will write compressed `LAZ` files instead of `LAS` files.
+    pub fn from_writer_and_point_format(
+        writer: T,
+        point_format: Format,
+        is_compressed: bool,
+    ) -> Result<Self> {
+        Self::from_writer_and_point_format_with_extra_bytes(
+            writer,
+            point_format,
+            &ExtraBytesMapping::new(),
+            is_compressed,
+        )
+    }
+
+    /// Creates a new `LASWriter` from the given `writer` that writes points in the given `point_format`. The scale
+    /// factors and offsets of the LAS header are selected from the `bounds` of the points that will be written, using
+    /// the given `transforms`. Writing a point that lies far outside of `bounds` can fail, because its position might
+    /// not be representable with the selected scale and offset.
+    /// If `is_compressed` is set, the writer will write compressed `LAZ` files instead of `LAS` files.
+    pub fn from_writer_and_bounds(
+        writer: T,
+        point_format: Format,
+        bounds: &AABB<f64>,
+        transforms: &AutomaticLASTransforms,
+        is_compressed: bool,
+    ) -> Result<Self> {
+        let transforms = transforms.transforms_for_bounds(bounds)?;
+        Self::from_writer_and_transforms(
+            writer,
+            point_format,
+            transforms,
+            &ExtraBytesMapping::new(),
+            is_compressed,
+        )
+    }
+
+    /// Like [`Self::from_writer_and_bounds`], but calculates the bounds from the given `points`. This requires an
+    /// additional pass over the points before they are written. The returned writer does not write `points` itself,
+    /// they (and any other points within their bounds) have to be passed to `write` afterwards
+    pub fn from_writer_and_points<'a, B: BorrowedBuffer<'a>>(
+        writer: T,
+        point_format: Format,
+        points: &'a B,
+        transforms: &AutomaticLASTransforms,
+        is_compressed: bool,
+    ) -> Result<Self> {
+        let transforms = transforms.transforms_for_points(points)?;
+        Self::from_writer_and_transforms(
+            writer,
+            point_format,
+            transforms,
+            &ExtraBytesMapping::new(),
+            is_compressed,
+        )
+    }
+
+    /// Like [`Self::from_writer_and_point_format`], but additionally writes the attributes in the given `extra_bytes`
+    /// mapping as extra bytes of the LAS point records. The Extra Bytes VLR that describes these attributes is added to
+    /// the LAS header, so that LAS readers can read them back. Use [`ExtraBytesMapping::for_point_layout`] to write
+    /// all attributes of a `PointLayout` that are not part of `point_format`
+    pub fn from_writer_and_point_format_with_extra_bytes(
+        writer: T,
+        point_format: Format,
+        extra_bytes: &ExtraBytesMapping,
+        is_compressed: bool,
+    ) -> Result<Self> {
+        LASWriterBuilder::new(point_format)
+            .extra_bytes(extra_bytes.clone())
+            .compressed(is_compressed)
+            .build(writer)
+    }
+
+    fn from_writer_and_transforms(
+        writer: T,
+        point_format: Format,
+        transforms: las_rs::Vector<las_rs::Transform>,
+        extra_bytes: &ExtraBytesMapping,
+        is_compressed: bool,
+    ) -> Result<Self> {
+        LASWriterBuilder::new(point_format)
+            .transforms(transforms)
+            .extra_bytes(extra_bytes.clone())
+            .compressed(is_compressed)
+            .build(writer)
     }
 
     /// Creates a new `LASWriter` from the given writer and LAS header. If `is_compressed` is set,
-    /// the writer will write compressed `LAZ` files instead of `LAS` files.
+    /// the writer will write compressed `LAZ` files instead of `LAS` files, which requires the `laz`
+    /// feature of pasture-io.
     pub fn from_writer_and_header(
         writer: T,
         header: las::Header,
         is_compressed: bool,
     ) -> Result<Self> {
-        let raw_writer: WriterVariant<T> = if is_compressed {
-            WriterVariant::LAZ(RawLAZWriter::from_write_and_header(writer, header)?)
-        } else {
-            WriterVariant::LAS(RawLASWriter::from_write_and_header(writer, header)?)
-        };
-        Ok(Self { writer: raw_writer })
+        validate_las_version_and_point_format(header.version(), header.point_format())?;
+        #[cfg(feature = "laz")]
+        if is_compressed {
+            let raw_writer =
+                WriterVariant::LAZ(RawLAZWriter::from_write_and_header(writer, header)?);
+            return Ok(Self {
+                writer: Some(raw_writer),
+            });
+        }
+        #[cfg(not(feature = "laz"))]
+        if is_compressed {
+            bail!("Writing compressed LAZ files requires the `laz` feature of pasture-io");
+        }
+
+        let raw_writer = WriterVariant::LAS(RawLASWriter::from_write_and_header(writer, header)?);
+        Ok(Self {
+            writer: Some(raw_writer),
+        })
+    }
+
+    /// Creates a new `LASWriter` from the given writer and LAS header that writes a compressed `LAZ` file. The point
+    /// records are compressed using `num_threads` threads: Points are buffered until there is one LAZ chunk for each
+    /// thread, the chunks are compressed in parallel and written in order. The output is identical to the output of
+    /// a single-threaded writer. If `num_threads` is zero, the number of threads is determined automatically based on
+    /// the number of available CPU cores
+    #[cfg(feature = "laz-parallel")]
+    pub fn from_writer_and_header_parallel(
+        writer: T,
+        header: las::Header,
+        num_threads: usize,
+    ) -> Result<Self> {
+        validate_las_version_and_point_format(header.version(), header.point_format())?;
+        let raw_writer = WriterVariant::LAZ(RawLAZWriter::from_write_and_header_parallel(
+            writer,
+            header,
+            num_threads,
+            DEFAULT_LAZ_CHUNK_SIZE,
+        )?);
+        Ok(Self {
+            writer: Some(raw_writer),
+        })
+    }
+
+    /// Queues the given extended VLR (EVLR), which is written after the last point record when the writer is flushed.
+    /// EVLRs can store payloads that are larger than `u16::MAX` bytes, but require LAS 1.4. For LAZ files, EVLRs can
+    /// only be added before the writer has been flushed
+    pub fn add_evlr(&mut self, evlr: las_rs::Vlr) -> Result<()> {
+        match self.raw_writer_mut() {
+            WriterVariant::LAS(writer) => writer.add_evlr(evlr),
+            #[cfg(feature = "laz")]
+            WriterVariant::LAZ(writer) => writer.add_evlr(evlr),
+        }
+    }
+
+    /// Sets how values that don't fit into the bit fields of the LAS point records are handled, e.g. a return number
+    /// above 15 for the extended point formats 6-10. By default, writing such points fails with an error
+    pub fn set_bit_attribute_overflow_handling(
+        &mut self,
+        overflow_handling: BitAttributeOverflowHandling,
+    ) {
+        match self.raw_writer_mut() {
+            WriterVariant::LAS(writer) => {
+                writer.set_bit_attribute_overflow_handling(overflow_handling)
+            }
+            #[cfg(feature = "laz")]
+            WriterVariant::LAZ(writer) => {
+                writer.set_bit_attribute_overflow_handling(overflow_handling)
+            }
+        }
+    }
+
+    /// Sets what happens to the overlap flag of points that are written to the point formats 0-5, which can only store
+    /// the synthetic, key-point and withheld flags in the upper bits of the classification byte. The overlap flag is
+    /// taken from the `OVERLAP` attribute or from bit 3 of the `CLASSIFICATION_FLAGS`. By default, it is dropped and
+    /// counted, see [`Self::dropped_overlap_flags`]. Only affects points that are written afterwards
+    pub fn set_overlap_flag_handling(&mut self, overlap_handling: OverlapFlagHandling) {
+        match self.raw_writer_mut() {
+            WriterVariant::LAS(writer) => writer.set_overlap_flag_handling(overlap_handling),
+            #[cfg(feature = "laz")]
+            WriterVariant::LAZ(writer) => writer.set_overlap_flag_handling(overlap_handling),
+        }
+    }
+
+    /// Returns the number of points whose overlap flag was dropped because the point format of the file has no bit for
+    /// it. Callers can use this to warn about the lost flags once all points are written
+    pub fn dropped_overlap_flags(&self) -> usize {
+        match self
+            .writer
+            .as_ref()
+            .expect("writer is only taken in into_inner and finalize")
+        {
+            WriterVariant::LAS(writer) => writer.dropped_overlap_flags(),
+            #[cfg(feature = "laz")]
+            WriterVariant::LAZ(writer) => writer.dropped_overlap_flags(),
+        }
+    }
+
+    /// Sets the values that are written for LAS attributes that are missing from the `PointLayout` of the points, e.g.
+    /// a constant GPS time for points without a `GPS_TIME` attribute. By default, missing attributes are written as
+    /// zero. Only affects points that are written afterwards
+    pub fn set_missing_attribute_defaults(&mut self, defaults: MissingAttributeDefaults) {
+        match self.raw_writer_mut() {
+            WriterVariant::LAS(writer) => writer.set_missing_attribute_defaults(defaults),
+            #[cfg(feature = "laz")]
+            WriterVariant::LAZ(writer) => writer.set_missing_attribute_defaults(defaults),
+        }
+    }
+
+    /// Sets how a `COLOR_RGB` attribute with datatype `Vector3<u8>` is converted into the 16-bit color channels of the
+    /// point records. Defaults to [`ColorUpscaling::MultiplyBy257`]. Only affects points that are written afterwards
+    pub fn set_color_upscaling(&mut self, color_upscaling: ColorUpscaling) {
+        match self.raw_writer_mut() {
+            WriterVariant::LAS(writer) => writer.set_color_upscaling(color_upscaling),
+            #[cfg(feature = "laz")]
+            WriterVariant::LAZ(writer) => writer.set_color_upscaling(color_upscaling),
+        }
+    }
+
+    /// Sets the number of points whose records are assembled in an in-memory buffer before they are written to the
+    /// underlying writer (or passed to the LAZ compressor) with a single call. The buffer is reused between calls to
+    /// `write`. Defaults to [`DEFAULT_WRITE_CHUNK_SIZE`]. Larger chunks need more memory, but result in fewer and
+    /// larger writes
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `write_chunk_size` is zero
+    pub fn set_write_chunk_size(&mut self, write_chunk_size: usize) -> Result<()> {
+        match self.raw_writer_mut() {
+            WriterVariant::LAS(writer) => writer.set_write_chunk_size(write_chunk_size),
+            #[cfg(feature = "laz")]
+            WriterVariant::LAZ(writer) => writer.set_write_chunk_size(write_chunk_size),
+        }
+    }
+
+    fn raw_writer_mut(&mut self) -> &mut WriterVariant<T> {
+        self.writer
+            .as_mut()
+            .expect("writer is only taken in into_inner and finalize")
+    }
+
+    /// Finalizes the LAS file by writing the LAS header with the bounds and point counts of all written points, as
+    /// well as the EVLRs, and returns the final metadata of the file. For LAZ files, this also finishes the compressed
+    /// point data
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header or the EVLRs can't be written, or if the underlying writer can't be flushed. The
+    /// file is incomplete in this case
+    pub fn finalize(mut self) -> Result<LASMetadata> {
+        match self
+            .writer
+            .take()
+            .expect("writer is only taken in into_inner and finalize")
+        {
+            WriterVariant::LAS(mut writer) => {
+                writer.finalize()?;
+                writer.las_metadata()
+            }
+            #[cfg(feature = "laz")]
+            WriterVariant::LAZ(mut writer) => {
+                writer.finalize()?;
+                writer.las_metadata()
+            }
+        }
     }
 
-    /// Unwraps with LASWriter, returning the underlying write type `T`. All internal data is flushed before returning
-    /// the writer
-    pub fn into_inner(self) -> Result<T> {
-        match self.writer {
+    /// Unwraps with LASWriter, returning the underlying write type `T`. The LAS file is finalized before returning the
+    /// writer, see [`Self::finalize`]
+    pub fn into_inner(mut self) -> Result<T> {
+        match self
+            .writer
+            .take()
+            .expect("writer is only taken in into_inner and finalize")
+        {
             WriterVariant::LAS(writer) => writer.into_inner(),
+            #[cfg(feature = "laz")]
             WriterVariant::LAZ(writer) => writer.into_inner(),
         }
     }
 }
 
+impl<T: Write + Seek + Send + 'static> Drop for LASWriter<T> {
+    fn drop(&mut self) {
+        // Errors can't be propagated from `drop`, users that care about them call `finalize` or `into_inner`. Printing
+        // must not panic either, as this might run while unwinding
+        let result = match self.writer.as_mut() {
+            Some(WriterVariant::LAS(writer)) => writer.finalize(),
+            #[cfg(feature = "laz")]
+            Some(WriterVariant::LAZ(writer)) => writer.finalize(),
+            None => Ok(()),
+        };
+        if let Err(why) = result {
+            let _ = writeln!(
+                std::io::stderr(),
+                "Could not finalize LAS file while dropping the LASWriter: {:#}",
+                why
+            );
+        }
+    }
+}
+
 impl LASWriter<BufWriter<File>> {
+    /// Opens the existing LAS file at `path` for appending points to it. The header and VLRs of the file are kept, so
+    /// the points are written in the point format of the file and the bounds and point counts in the header are
+    /// updated. EVLRs of the file are moved behind the appended points
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be opened, if it is a compressed LAZ file (which can't be appended to), or
+    /// if it contains data that can't be relocated, such as the waveform data packets of a LAS 1.3 file. Writing
+    /// points whose `PointLayout` can't be converted into the point format of the file fails as well
+    pub fn append<P: AsRef<Path>>(path: P) -> Result<Self> {
+        if path_is_compressed_las_file(path.as_ref())? {
+            bail!(
+                "Can't append points to {}, appending to compressed LAZ files is not supported",
+                path.as_ref().display()
+            );
+        }
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path.as_ref())
+            .with_context(|| format!("Could not open file {}", path.as_ref().display()))?;
+        let raw_writer = RawLASWriter::append_to_file(file)
+            .with_context(|| format!("Can't append points to {}", path.as_ref().display()))?;
+        Ok(Self {
+            writer: Some(WriterVariant::LAS(raw_writer)),
+        })
+    }
+
     /// Creates a new `LASWriter` from the given path and LAS header
     pub fn from_path_and_header<P: AsRef<Path>>(path: P, header: las::Header) -> Result<Self> {
         let is_compressed = path_is_compressed_las_file(path.as_ref())?;
@@ -99,26 +441,677 @@ impl LASWriter<BufWriter<File>> {
         let writer = BufWriter::new(File::create(path)?);
         Self::from_writer_and_point_layout(writer, point_layout, is_compressed)
     }
+
+    /// Creates a new `LASWriter` from the given `path` that writes points in the given `point_format`, see
+    /// [`Self::from_writer_and_point_format`]
+    pub fn from_path_and_point_format<P: AsRef<Path>>(
+        path: P,
+        point_format: Format,
+    ) -> Result<Self> {
+        let is_compressed = path_is_compressed_las_file(path.as_ref())?;
+        let writer = BufWriter::new(File::create(path)?);
+        Self::from_writer_and_point_format(writer, point_format, is_compressed)
+    }
+}
+
+/// Flag of the global encoding that states that the return numbers have been generated synthetically
+const GLOBAL_ENCODING_SYNTHETIC_RETURN_NUMBERS_BIT: u16 = 1 << 3;
+
+/// Returns `true` if [`LASWriterBuilder::with_metadata_from`] must not copy the given `vlr`, because the writer creates
+/// it from its own configuration (LASzip, Extra Bytes and CRS VLRs), or because it describes the point records of the
+/// source file and would be invalid for the written points (COPC and LAX spatial index VLRs)
+fn is_vlr_excluded_from_metadata_copy(vlr: &las_rs::Vlr) -> bool {
+    match vlr.user_id.as_str() {
+        LASZIP_VLR_USER_ID | COPC_VLR_USER_ID | LAX_VLR_USER_ID => true,
+        KNOWN_VLR_USER_ID => vlr.record_id == ExtraBytesVlr::RECORD_ID,
+        PROJECTION_VLR_USER_ID => [
+            WKT_CRS_RECORD_ID,
+            GeoTiffCrs::GEO_KEY_DIRECTORY_RECORD_ID,
+            GeoTiffCrs::GEO_DOUBLE_PARAMS_RECORD_ID,
+            GeoTiffCrs::GEO_ASCII_PARAMS_RECORD_ID,
+        ]
+        .contains(&vlr.record_id),
+        _ => false,
+    }
+}
+
+/// Size of the header of a VLR in bytes
+pub(crate) const SIZE_OF_VLR_HEADER: u64 = 54;
+
+/// Returns an error if the VLRs of `header_builder` can't be written between the header and the point records, i.e.
+/// if a VLR is too large, if the point records would start beyond the largest offset that the LAS header can store,
+/// or if a VLR that is defined by the LAS specification appears more than once
+fn validate_vlrs(header_builder: &Builder) -> Result<()> {
+    let mut offset_to_point_data = header_builder.version.header_size() as u64
+        + header_builder.padding.len() as u64
+        + header_builder.vlr_padding.len() as u64;
+    let mut specified_records = HashSet::new();
+    for vlr in &header_builder.vlrs {
+        if vlr.data.len() > u16::MAX as usize {
+            bail!(
+                "VLR '{}' ({}) has {} bytes of data, but VLRs can store at most {} bytes. Add it as an EVLR instead",
+                vlr.user_id,
+                vlr.record_id,
+                vlr.data.len(),
+                u16::MAX
+            );
+        }
+        offset_to_point_data += SIZE_OF_VLR_HEADER + vlr.data.len() as u64;
+
+        let is_specified_record = vlr.user_id == PROJECTION_VLR_USER_ID
+            || (vlr.user_id == KNOWN_VLR_USER_ID && vlr.record_id != SUPERSEDED_VLR_RECORD_ID);
+        if is_specified_record && !specified_records.insert((vlr.user_id.as_str(), vlr.record_id)) {
+            bail!(
+                "The LAS header contains more than one VLR '{}' ({}), but this VLR must be unique",
+                vlr.user_id,
+                vlr.record_id
+            );
+        }
+    }
+    if offset_to_point_data > u32::MAX as u64 {
+        bail!(
+            "The VLRs of the LAS header are too large, the point records would start at byte {}, but the LAS header can only store offsets up to {}",
+            offset_to_point_data,
+            u32::MAX
+        );
+    }
+    Ok(())
+}
+
+/// Size of the system identifier and generating software fields of the LAS header in bytes
+const LAS_HEADER_STRING_LENGTH: usize = 32;
+
+/// Returns the longest prefix of `value` that fits into a string field of the LAS header without splitting a character
+fn truncate_to_las_header_string(value: &str) -> &str {
+    if value.len() <= LAS_HEADER_STRING_LENGTH {
+        return value;
+    }
+    let mut end = LAS_HEADER_STRING_LENGTH;
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    &value[..end]
+}
+
+/// Builder for a `LASWriter` that allows configuring the LAS header, including the coordinate reference system (CRS)
+/// of the LAS file. The CRS can be given as OGC WKT (LAS 1.4 only) or as an EPSG code, which is written as GeoTIFF
+/// keys. By default, a LAS 1.4 header with a scale of 0.001 and an offset of zero is used, and no CRS is written. The
+/// generating software defaults to `pasture <version>` and the file creation date to the current date
+///
+/// ```no_run
+/// # use anyhow::Result;
+/// # use las_rs::point::Format;
+/// # use pasture_io::las::LASWriterBuilder;
+/// # fn main() -> Result<()> {
+/// let writer = LASWriterBuilder::new(Format::new(1)?)
+///     .version(1, 2)
+///     .with_epsg(25832)
+///     .build_for_path("points.las")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct LASWriterBuilder {
+    header_builder: Builder,
+    extra_bytes: ExtraBytesMapping,
+    crs_wkt: Option<String>,
+    geotiff_crs: Option<GeoTiffCrs>,
+    epsg_code: Option<u32>,
+    bit_attribute_overflow: BitAttributeOverflowHandling,
+    overlap_flag_handling: OverlapFlagHandling,
+    missing_attribute_defaults: MissingAttributeDefaults,
+    color_upscaling: ColorUpscaling,
+    write_chunk_size: usize,
+    strict_header_strings: bool,
+    is_compressed: bool,
+    #[cfg(feature = "laz")]
+    laz_chunk_size: u32,
+    #[cfg(feature = "laz-parallel")]
+    compression_threads: Option<usize>,
+}
+
+impl LASWriterBuilder {
+    /// Creates a new `LASWriterBuilder` for LAS 1.4 files with the given `point_format`
+    pub fn new(point_format: Format) -> Self {
+        let mut header_builder = Builder::from((1, 4));
+        header_builder.point_format = point_format;
+        let transform = las_rs::Transform {
+            offset: 0.0,
+            scale: 0.001,
+        };
+        header_builder.transforms = las_rs::Vector {
+            x: transform,
+            y: transform,
+            z: transform,
+        };
+        header_builder.generating_software = format!("pasture {}", env!("CARGO_PKG_VERSION"));
+        header_builder.date = Some(Utc::now().date_naive());
+        Self {
+            header_builder,
+            extra_bytes: ExtraBytesMapping::new(),
+            crs_wkt: None,
+            geotiff_crs: None,
+            epsg_code: None,
+            bit_attribute_overflow: Default::default(),
+            overlap_flag_handling: Default::default(),
+            missing_attribute_defaults: Default::default(),
+            color_upscaling: Default::default(),
+            write_chunk_size: DEFAULT_WRITE_CHUNK_SIZE,
+            strict_header_strings: false,
+            is_compressed: false,
+            #[cfg(feature = "laz")]
+            laz_chunk_size: DEFAULT_LAZ_CHUNK_SIZE,
+            #[cfg(feature = "laz-parallel")]
+            compression_threads: None,
+        }
+    }
+
+    /// Creates a new `LASWriterBuilder` for LAS 1.4 files with the smallest point format that stores all LAS attributes
+    /// of `point_layout`, see [`point_format_from_layout`]. Attributes of `point_layout` that no LAS point format
+    /// stores are handled according to `unknown_attributes`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `point_layout` has no `POSITION_3D` attribute, if it has unknown attributes and
+    /// `unknown_attributes` is [`UnknownAttributeHandling::Error`], or if an unknown attribute can't be stored as extra
+    /// bytes
+    pub fn from_point_layout(
+        point_layout: &PointLayout,
+        unknown_attributes: UnknownAttributeHandling,
+    ) -> Result<Self> {
+        let mut builder = Self::new(point_format_from_layout(point_layout)?);
+        let mut unknown_attributes_of_layout = point_layout
+            .attributes()
+            .map(|attribute| attribute.attribute_definition())
+            .filter(|attribute| !is_known_las_attribute(attribute));
+        match unknown_attributes {
+            UnknownAttributeHandling::Ignore => (),
+            UnknownAttributeHandling::ExtraBytes => {
+                builder.extra_bytes = unknown_attributes_of_layout
+                    .try_fold(ExtraBytesMapping::new(), |mapping, attribute| {
+                        mapping.with_attribute(attribute)
+                    })?;
+            }
+            UnknownAttributeHandling::Error => {
+                if let Some(attribute) = unknown_attributes_of_layout.next() {
+                    bail!(
+                        "Attribute {} is not stored by any LAS point format. Write it as extra bytes or remove it from the point layout",
+                        attribute
+                    );
+                }
+            }
+        }
+        Ok(builder)
+    }
+
+    /// Sets the LAS version of the file, which must be between 1.0 and 1.4. The size of the LAS header and the point
+    /// count fields that are written depend on the version: LAS 1.0 to 1.2 use a 227 byte header, LAS 1.3 a 235 byte
+    /// header and LAS 1.4 a 375 byte header with additional 64-bit point counts. Note that the point format has to be
+    /// supported by the version: Point formats 2 and 3 require LAS 1.2, formats 4 and 5 require LAS 1.3 and formats 6
+    /// to 10 require LAS 1.4. Invalid combinations are reported by [`Self::build`]
+    pub fn version(mut self, major: u8, minor: u8) -> Self {
+        self.header_builder.version = las_rs::Version::new(major, minor);
+        self
+    }
+
+    /// Sets the scale factors and offsets of the file, see also [`AutomaticLASTransforms`]
+    pub fn transforms(mut self, transforms: las_rs::Vector<las_rs::Transform>) -> Self {
+        self.header_builder.transforms = transforms;
+        self
+    }
+
+    /// Sets the attributes that are written as extra bytes, see
+    /// [`LASWriter::from_writer_and_point_format_with_extra_bytes`]
+    pub fn extra_bytes(mut self, extra_bytes: ExtraBytesMapping) -> Self {
+        self.extra_bytes = extra_bytes;
+        self
+    }
+
+    /// Writes the given OGC WKT as the CRS of the file. This requires LAS 1.4, for older versions use
+    /// [`Self::with_epsg`] instead
+    pub fn with_crs_wkt(mut self, wkt: &str) -> Self {
+        self.crs_wkt = Some(wkt.to_owned());
+        self
+    }
+
+    /// Writes the coordinate system with the given EPSG code as the CRS of the file, using a minimal GeoTIFF
+    /// GeoKeyDirectory (see [`GeoTiffCrs::from_epsg_code`]). This works for all LAS versions
+    pub fn with_epsg(mut self, epsg_code: u32) -> Self {
+        self.epsg_code = Some(epsg_code);
+        self.geotiff_crs = None;
+        self
+    }
+
+    /// Writes the CRS of the LAS file described by `metadata`, i.e. its OGC WKT and GeoTIFF keys, if present. This
+    /// preserves the CRS when converting LAS files
+    pub fn with_crs_of(mut self, metadata: &LASMetadata) -> Self {
+        if let Some(wkt) = metadata.crs_wkt() {
+            self.crs_wkt = Some(wkt.to_owned());
+        }
+        if let Some(crs) = metadata.crs() {
+            self.geotiff_crs = Some(crs.clone());
+            self.epsg_code = None;
+        }
+        self
+    }
+
+    /// Copies the waveform information of the LAS file described by `metadata`, i.e. its Waveform Packet Descriptor
+    /// VLRs and, if the waveform data packets are stored within the file, the EVLR with the waveform data. The waveform
+    /// data is copied opaquely, so that the waveform attributes of points that are read from this file and written
+    /// with point format 4, 5, 9 or 10 remain valid. Internal waveform data requires LAS 1.4
+    pub fn with_waveforms_of(mut self, metadata: &LASMetadata) -> Self {
+        let (descriptor_vlrs, waveform_data_evlr) = waveform_vlrs_of(metadata);
+        self.header_builder.vlrs.extend(descriptor_vlrs);
+        self.header_builder.evlrs.extend(waveform_data_evlr);
+        self
+    }
+
+    /// Copies the metadata of the LAS file described by `metadata` into the header, so that the points of this file can
+    /// be filtered or modified and written again without losing its metadata. The following fields are copied:
+    ///
+    /// - All VLRs and EVLRs, including the waveform VLRs of [`Self::with_waveforms_of`]. VLRs that were added to this
+    ///   builder before take precedence over copied VLRs with the same user ID and record ID
+    /// - The CRS, see [`Self::with_crs_of`]
+    /// - The GPS time type and the synthetic return numbers flag of the global encoding
+    /// - The generating software. Call [`Self::generating_software`] afterwards to override it
+    /// - The entries of the Extra Bytes VLR, unless [`Self::extra_bytes`] was called before. Points without these
+    ///   attributes are written with the no-data value of each entry
+    ///
+    /// Records that describe the compression or the order of the point records of the source file are not copied,
+    /// i.e. the LASzip VLR and COPC or LAX spatial index VLRs. The bounds, point counts and the offset to the point
+    /// records are always calculated from the written points, while the point format, LAS version, scale factors and
+    /// offsets are taken from the configuration of this builder. Copied EVLRs and OGC WKT CRS require LAS 1.4
+    pub fn with_metadata_from(mut self, metadata: &LASMetadata) -> Self {
+        let is_in_header_builder = |header_builder: &Builder, vlr: &las_rs::Vlr| {
+            header_builder
+                .vlrs
+                .iter()
+                .chain(header_builder.evlrs.iter())
+                .any(|existing| {
+                    existing.user_id == vlr.user_id && existing.record_id == vlr.record_id
+                })
+        };
+        let copied_vlrs = metadata
+            .vlrs()
+            .iter()
+            .filter(|vlr| {
+                !is_vlr_excluded_from_metadata_copy(vlr)
+                    && !is_in_header_builder(&self.header_builder, vlr)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        let copied_evlrs = metadata
+            .evlrs()
+            .iter()
+            .filter(|evlr| {
+                !is_vlr_excluded_from_metadata_copy(evlr)
+                    && !is_in_header_builder(&self.header_builder, evlr)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        self.header_builder.vlrs.extend(copied_vlrs);
+        self.header_builder.evlrs.extend(copied_evlrs);
+
+        if let Some(gps_time_type) = metadata.gps_time_type() {
+            self.header_builder.gps_time_type = gps_time_type;
+        }
+        if let Some(global_encoding) = metadata.global_encoding() {
+            self.header_builder.has_synthetic_return_numbers =
+                global_encoding & GLOBAL_ENCODING_SYNTHETIC_RETURN_NUMBERS_BIT != 0;
+        }
+        if let Some(generating_software) = metadata.generating_software() {
+            self.header_builder.generating_software = generating_software.to_owned();
+        }
+        if self.extra_bytes.is_empty() {
+            if let Some(extra_bytes_vlr) = metadata.extra_bytes_vlr() {
+                self.extra_bytes = extra_bytes_vlr
+                    .entries()
+                    .iter()
+                    .cloned()
+                    .fold(ExtraBytesMapping::new(), ExtraBytesMapping::with_entry);
+            }
+        }
+        self.with_crs_of(metadata)
+    }
+
+    /// Adds the given VLR to the LAS header, e.g. a vendor-specific VLR with processing parameters. VLRs are written
+    /// between the header and the point records and can store up to `u16::MAX` bytes, use [`Self::with_evlr`] for
+    /// larger payloads
+    pub fn with_vlr(mut self, vlr: las_rs::Vlr) -> Self {
+        self.header_builder.vlrs.push(vlr);
+        self
+    }
+
+    /// Adds all given VLRs to the LAS header, see [`Self::with_vlr`]
+    pub fn with_vlrs(mut self, vlrs: Vec<las_rs::Vlr>) -> Self {
+        self.header_builder.vlrs.extend(vlrs);
+        self
+    }
+
+    /// Adds the given extended VLR (EVLR) to the LAS header, which requires LAS 1.4. See also [`LASWriter::add_evlr`]
+    /// for adding EVLRs while writing points
+    pub fn with_evlr(mut self, evlr: las_rs::Vlr) -> Self {
+        self.header_builder.evlrs.push(evlr);
+        self
+    }
+
+    /// Sets how values that don't fit into the bit fields of the LAS point records are handled, see
+    /// [`LASWriter::set_bit_attribute_overflow_handling`]
+    pub fn bit_attribute_overflow_handling(
+        mut self,
+        overflow_handling: BitAttributeOverflowHandling,
+    ) -> Self {
+        self.bit_attribute_overflow = overflow_handling;
+        self
+    }
+
+    /// Sets what happens to the overlap flag of points that are written to the point formats 0-5, see
+    /// [`LASWriter::set_overlap_flag_handling`]
+    pub fn overlap_flag_handling(mut self, overlap_handling: OverlapFlagHandling) -> Self {
+        self.overlap_flag_handling = overlap_handling;
+        self
+    }
+
+    /// Sets the values that are written for LAS attributes that are missing from the `PointLayout` of the points, see
+    /// [`LASWriter::set_missing_attribute_defaults`]
+    pub fn missing_attribute_defaults(mut self, defaults: MissingAttributeDefaults) -> Self {
+        self.missing_attribute_defaults = defaults;
+        self
+    }
+
+    /// Sets how `Vector3<u8>` colors are converted into the 16-bit colors of the point records, see
+    /// [`LASWriter::set_color_upscaling`]
+    pub fn color_upscaling(mut self, color_upscaling: ColorUpscaling) -> Self {
+        self.color_upscaling = color_upscaling;
+        self
+    }
+
+    /// Sets the number of points whose records are assembled in memory and written at once, see
+    /// [`LASWriter::set_write_chunk_size`]. The chunk size must be at least 1, which is checked by [`Self::build`]
+    pub fn write_chunk_size(mut self, write_chunk_size: usize) -> Self {
+        self.write_chunk_size = write_chunk_size;
+        self
+    }
+
+    /// Sets the file source ID of the file, e.g. the ID of the flight line that the points belong to. Defaults to 0
+    pub fn file_source_id(mut self, file_source_id: u16) -> Self {
+        self.header_builder.file_source_id = file_source_id;
+        self
+    }
+
+    /// Sets whether the GPS times of the points are GPS week times or adjusted standard GPS times, which is stored in
+    /// the global encoding of the header. Defaults to GPS week time
+    pub fn gps_time_type(mut self, gps_time_type: GpsTimeType) -> Self {
+        self.header_builder.gps_time_type = gps_time_type;
+        self
+    }
+
+    /// Sets the flag of the global encoding that states that the return numbers of the points have been synthetically
+    /// generated
+    pub fn synthetic_return_numbers(mut self, has_synthetic_return_numbers: bool) -> Self {
+        self.header_builder.has_synthetic_return_numbers = has_synthetic_return_numbers;
+        self
+    }
+
+    /// Sets the system identifier of the file, i.e. the hardware or process that generated the points. The LAS header
+    /// has 32 bytes for this value, see [`Self::strict_header_strings`] for longer values
+    pub fn system_identifier(mut self, system_identifier: &str) -> Self {
+        self.header_builder.system_identifier = system_identifier.to_owned();
+        self
+    }
+
+    /// Sets the generating software of the file. Defaults to `pasture <version>`. The LAS header has 32 bytes for this
+    /// value, see [`Self::strict_header_strings`] for longer values
+    pub fn generating_software(mut self, generating_software: &str) -> Self {
+        self.header_builder.generating_software = generating_software.to_owned();
+        self
+    }
+
+    /// Sets the creation date of the file, which is stored as day of year and year. Defaults to the current date (UTC)
+    pub fn file_creation_date(mut self, date: NaiveDate) -> Self {
+        self.header_builder.date = Some(date);
+        self
+    }
+
+    /// Sets whether a system identifier or generating software that is longer than the 32 bytes of the LAS header is an
+    /// error. By default, such values are truncated, which is reported by [`Self::warnings`]
+    pub fn strict_header_strings(mut self, strict_header_strings: bool) -> Self {
+        self.strict_header_strings = strict_header_strings;
+        self
+    }
+
+    /// Returns warnings about the header that [`Self::build_header`] creates, e.g. because a string is truncated to
+    /// fit into the LAS header
+    pub fn warnings(&self) -> Vec<String> {
+        if self.strict_header_strings {
+            return vec![];
+        }
+        self.header_strings()
+            .into_iter()
+            .filter(|(_, value)| value.len() > LAS_HEADER_STRING_LENGTH)
+            .map(|(field_name, value)| {
+                format!(
+                    "The {} '{}' is longer than {} bytes and is truncated to '{}'",
+                    field_name,
+                    value,
+                    LAS_HEADER_STRING_LENGTH,
+                    truncate_to_las_header_string(value)
+                )
+            })
+            .collect()
+    }
+
+    fn header_strings(&self) -> Vec<(&'static str, &str)> {
+        vec![
+            (
+                "system identifier",
+                self.header_builder.system_identifier.as_str(),
+            ),
+            (
+                "generating software",
+                self.header_builder.generating_software.as_str(),
+            ),
+        ]
+    }
+
+    /// Sets whether compressed `LAZ` files are written instead of `LAS` files, which requires the `laz` feature of
+    /// pasture-io
+    pub fn compressed(mut self, is_compressed: bool) -> Self {
+        self.is_compressed = is_compressed;
+        self
+    }
+
+    /// Sets the number of points per chunk of compressed LAZ files, which defaults to [`DEFAULT_LAZ_CHUNK_SIZE`]. LAZ
+    /// readers can only seek to the start of a chunk, so small chunks (e.g. 4096 points) allow faster random access
+    /// while large chunks compress slightly better. The chunk size must be between 1 and `u32::MAX - 1`, which is
+    /// checked by [`Self::build`]. Has no effect on uncompressed files
+    #[cfg(feature = "laz")]
+    pub fn laz_chunk_size(mut self, chunk_size: u32) -> Self {
+        self.laz_chunk_size = chunk_size;
+        self
+    }
+
+    /// Compresses LAZ files using `num_threads` threads, see [`LASWriter::from_writer_and_header_parallel`]. If
+    /// `num_threads` is zero, the number of threads is determined automatically. Has no effect on uncompressed files
+    #[cfg(feature = "laz-parallel")]
+    pub fn compression_threads(mut self, num_threads: usize) -> Self {
+        self.compression_threads = Some(num_threads);
+        self
+    }
+
+    /// Creates the LAS header from the current configuration
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the point format is not supported by the LAS version, if an OGC WKT CRS or EVLRs are
+    /// requested for a LAS version prior to 1.4, if the EPSG code can't be written as GeoTIFF keys, if a string is too
+    /// long for the header with [`Self::strict_header_strings`] enabled, or if the header is invalid
+    pub fn build_header(&self) -> Result<las::Header> {
+        if self.strict_header_strings {
+            for (field_name, value) in self.header_strings() {
+                if value.len() > LAS_HEADER_STRING_LENGTH {
+                    bail!(
+                        "The {} '{}' is {} bytes long, but the LAS header only has {} bytes for it",
+                        field_name,
+                        value,
+                        value.len(),
+                        LAS_HEADER_STRING_LENGTH
+                    );
+                }
+            }
+        }
+        let mut header_builder = self.header_builder.clone();
+        header_builder.system_identifier =
+            truncate_to_las_header_string(&header_builder.system_identifier).to_owned();
+        header_builder.generating_software =
+            truncate_to_las_header_string(&header_builder.generating_software).to_owned();
+        let version = header_builder.version;
+        validate_las_version_and_point_format(version, &header_builder.point_format)?;
+        let is_las_1_4 = (version.major, version.minor) >= (1, 4);
+        if !header_builder.evlrs.is_empty() && !is_las_1_4 {
+            bail!(
+                "EVLRs can only be written to LAS 1.4 files, but the LAS version is {}.{}",
+                version.major,
+                version.minor
+            );
+        }
+        if let Some(wkt) = &self.crs_wkt {
+            if !is_las_1_4 {
+                bail!(
+                    "An OGC WKT CRS can only be written to LAS 1.4 files, but the LAS version is {}.{}. Use an EPSG code instead",
+                    version.major,
+                    version.minor
+                );
+            }
+            header_builder.vlrs.push(crs_wkt_vlr(wkt));
+        }
+        let geotiff_crs = match self.epsg_code {
+            Some(epsg_code) => Some(GeoTiffCrs::from_epsg_code(epsg_code)?),
+            None => self.geotiff_crs.clone(),
+        };
+        if let Some(geotiff_crs) = geotiff_crs {
+            header_builder.vlrs.extend(geotiff_crs.to_vlrs());
+        }
+        self.extra_bytes.add_to_header(&mut header_builder)?;
+        validate_vlrs(&header_builder)?;
+        header_builder
+            .into_header()
+            .context("Could not create LAS header")
+    }
+
+    /// Creates a `LASWriter` that writes into `writer`
+    pub fn build<T: Write + Seek + Send + 'static>(&self, writer: T) -> Result<LASWriter<T>> {
+        self.build_with_compression(writer, self.is_compressed)
+    }
+
+    /// Creates a `LASWriter` that writes to the file at `path`. Whether the file is compressed is determined from
+    /// the extension of `path`, overriding [`Self::compressed`]
+    pub fn build_for_path<P: AsRef<Path>>(&self, path: P) -> Result<LASWriter<BufWriter<File>>> {
+        let is_compressed = path_is_compressed_las_file(path.as_ref())?;
+        let writer = BufWriter::new(File::create(path)?);
+        self.build_with_compression(writer, is_compressed)
+    }
+
+    /// Creates a [`StreamingLASWriter`] that writes an uncompressed LAS file to `writer`, which doesn't have to be
+    /// seekable. The point count and bounds in the header are taken from `statistics`, see [`StreamingLASWriter`]
+    pub fn build_streaming<W: Write>(
+        &self,
+        writer: W,
+        statistics: LASStreamStatistics,
+    ) -> Result<StreamingLASWriter<W>> {
+        if self.is_compressed {
+            bail!("Compressed LAZ files can't be written to a non-seekable output");
+        }
+        let header = self.build_header()?;
+        let mut writer = StreamingLASWriter::from_writer_and_header(writer, header, statistics)?;
+        writer.set_bit_attribute_overflow_handling(self.bit_attribute_overflow);
+        writer.set_overlap_flag_handling(self.overlap_flag_handling);
+        writer.set_missing_attribute_defaults(self.missing_attribute_defaults);
+        writer.set_color_upscaling(self.color_upscaling);
+        writer.set_write_chunk_size(self.write_chunk_size)?;
+        Ok(writer)
+    }
+
+    fn build_with_compression<T: Write + Seek + Send + 'static>(
+        &self,
+        writer: T,
+        is_compressed: bool,
+    ) -> Result<LASWriter<T>> {
+        let header = self.build_header()?;
+        #[cfg(feature = "laz")]
+        let mut writer = if is_compressed {
+            self.build_laz(writer, header)?
+        } else {
+            LASWriter::from_writer_and_header(writer, header, false)?
+        };
+        #[cfg(not(feature = "laz"))]
+        let mut writer = LASWriter::from_writer_and_header(writer, header, is_compressed)?;
+        writer.set_bit_attribute_overflow_handling(self.bit_attribute_overflow);
+        writer.set_overlap_flag_handling(self.overlap_flag_handling);
+        writer.set_missing_attribute_defaults(self.missing_attribute_defaults);
+        writer.set_color_upscaling(self.color_upscaling);
+        writer.set_write_chunk_size(self.write_chunk_size)?;
+        Ok(writer)
+    }
+
+    #[cfg(feature = "laz")]
+    fn build_laz<T: Write + Seek + Send + 'static>(
+        &self,
+        writer: T,
+        header: las::Header,
+    ) -> Result<LASWriter<T>> {
+        validate_las_version_and_point_format(header.version(), header.point_format())?;
+        #[cfg(feature = "laz-parallel")]
+        let raw_writer = match self.compression_threads {
+            Some(num_threads) => RawLAZWriter::from_write_and_header_parallel(
+                writer,
+                header,
+                num_threads,
+                self.laz_chunk_size,
+            )?,
+            None => RawLAZWriter::from_write_and_header_with_chunk_size(
+                writer,
+                header,
+                self.laz_chunk_size,
+            )?,
+        };
+        #[cfg(not(feature = "laz-parallel"))]
+        let raw_writer = RawLAZWriter::from_write_and_header_with_chunk_size(
+            writer,
+            header,
+            self.laz_chunk_size,
+        )?;
+        Ok(LASWriter {
+            writer: Some(WriterVariant::LAZ(raw_writer)),
+        })
+    }
 }
 
 impl<T: Write + Seek + Send + 'static> PointWriter for LASWriter<T> {
     fn write<'a, B: BorrowedBuffer<'a>>(&mut self, points: &'a B) -> Result<()> {
-        match &mut self.writer {
+        match self.raw_writer_mut() {
             WriterVariant::LAS(writer) => writer.write(points),
+            #[cfg(feature = "laz")]
             WriterVariant::LAZ(writer) => writer.write(points),
         }
     }
 
     fn flush(&mut self) -> Result<()> {
-        match &mut self.writer {
+        match self.raw_writer_mut() {
             WriterVariant::LAS(writer) => writer.flush(),
+            #[cfg(feature = "laz")]
             WriterVariant::LAZ(writer) => writer.flush(),
         }
     }
 
     fn get_default_point_layout(&self) -> &PointLayout {
-        match &self.writer {
+        match self
+            .writer
+            .as_ref()
+            .expect("writer is only taken in into_inner and finalize")
+        {
             WriterVariant::LAS(writer) => writer.get_default_point_layout(),
+            #[cfg(feature = "laz")]
             WriterVariant::LAZ(writer) => writer.get_default_point_layout(),
         }
     }
@@ -126,21 +1119,39 @@ impl<T: Write + Seek + Send + 'static> PointWriter for LASWriter<T> {
 
 #[cfg(test)]
 mod tests {
-    use std::{io::Cursor, path::PathBuf};
+    use std::{
+        io::Cursor,
+        path::PathBuf,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+    };
 
     use las::{point::Format, Builder};
     use pasture_core::{
-        containers::{MakeBufferFromLayout, OwningBuffer, VectorBuffer},
-        layout::PointType,
-        nalgebra::Vector3,
+        containers::{
+            BorrowedMutBuffer, HashMapBuffer, MakeBufferFromLayout, OwningBuffer, VectorBuffer,
+        },
+        layout::{
+            attributes::{
+                CLASSIFICATION, CLASSIFICATION_FLAGS, COLOR_RGB, GPS_TIME, INTENSITY,
+                NUMBER_OF_RETURNS, POSITION_3D, RETURN_NUMBER, SCANNER_CHANNEL, SCAN_ANGLE,
+                SCAN_ANGLE_RANK,
+            },
+            PointAttributeDefinition, PointType,
+        },
+        meta::Metadata,
+        nalgebra::{Point3, Vector3},
     };
     use scopeguard::defer;
 
     use crate::{
         base::PointReader,
         las::{
-            LASReader, LasPointFormat0, LasPointFormat1, LasPointFormat2, LasPointFormat3,
-            LasPointFormat4, LasPointFormat5,
+            compare_to_reference_data, get_test_las_path, test_data_point_count, ColorConversion,
+            LASReadOptions, LASReader, LasPointFormat0, LasPointFormat1, LasPointFormat2,
+            LasPointFormat3, LasPointFormat4, LasPointFormat5, LasPointFormat6,
         },
     };
     use pasture_derive::PointType;
@@ -173,6 +1184,7 @@ mod tests {
         vec![
             LasPointFormat0 {
                 classification: 1,
+                classification_flags: 0,
                 edge_of_flight_line: 0,
                 intensity: 1,
                 number_of_returns: 1,
@@ -185,6 +1197,8 @@ mod tests {
             },
             LasPointFormat0 {
                 classification: 2,
+                // Withheld
+                classification_flags: 0b100,
                 edge_of_flight_line: 1,
                 intensity: 2,
                 number_of_returns: 2,
@@ -202,6 +1216,7 @@ mod tests {
         vec![
             LasPointFormat1 {
                 classification: 1,
+                classification_flags: 0,
                 edge_of_flight_line: 0,
                 intensity: 1,
                 number_of_returns: 1,
@@ -215,6 +1230,7 @@ mod tests {
             },
             LasPointFormat1 {
                 classification: 2,
+                classification_flags: 0,
                 edge_of_flight_line: 1,
                 intensity: 2,
                 number_of_returns: 2,
@@ -233,6 +1249,7 @@ mod tests {
         vec![
             LasPointFormat2 {
                 classification: 1,
+                classification_flags: 0,
                 edge_of_flight_line: 0,
                 intensity: 1,
                 number_of_returns: 1,
@@ -246,6 +1263,7 @@ mod tests {
             },
             LasPointFormat2 {
                 classification: 2,
+                classification_flags: 0,
                 edge_of_flight_line: 1,
                 intensity: 2,
                 number_of_returns: 2,
@@ -264,6 +1282,7 @@ mod tests {
         vec![
             LasPointFormat3 {
                 classification: 1,
+                classification_flags: 0,
                 edge_of_flight_line: 0,
                 intensity: 1,
                 number_of_returns: 1,
@@ -278,6 +1297,8 @@ mod tests {
             },
             LasPointFormat3 {
                 classification: 2,
+                // Synthetic and key-point
+                classification_flags: 0b011,
                 edge_of_flight_line: 1,
                 intensity: 2,
                 number_of_returns: 2,
@@ -297,6 +1318,7 @@ mod tests {
         vec![
             LasPointFormat4 {
                 classification: 1,
+                classification_flags: 0,
                 edge_of_flight_line: 0,
                 intensity: 1,
                 number_of_returns: 1,
@@ -315,6 +1337,7 @@ mod tests {
             },
             LasPointFormat4 {
                 classification: 2,
+                classification_flags: 0,
                 edge_of_flight_line: 1,
                 intensity: 2,
                 number_of_returns: 2,
@@ -338,6 +1361,7 @@ mod tests {
         vec![
             LasPointFormat5 {
                 classification: 1,
+                classification_flags: 0,
                 edge_of_flight_line: 0,
                 intensity: 1,
                 number_of_returns: 1,
@@ -357,6 +1381,7 @@ mod tests {
             },
             LasPointFormat5 {
                 classification: 2,
+                classification_flags: 0,
                 edge_of_flight_line: 1,
                 intensity: 2,
                 number_of_returns: 2,
@@ -1214,6 +2239,7 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(feature = "laz")]
     #[test]
     fn test_laz_writer_into_inner() -> Result<()> {
         let source_points = get_test_points_las_format_0();
@@ -1242,4 +2268,1521 @@ mod tests {
 
         Ok(())
     }
+
+    fn get_test_points_with_utm_coordinates() -> Vec<TestPoint> {
+        vec![
+            TestPoint {
+                position: Vector3::new(500_000.1234, 5_400_000.5678, 12.345),
+                color: Vector3::new(1, 2, 3),
+            },
+            TestPoint {
+                position: Vector3::new(512_345.678, 5_401_234.567, 250.0),
+                color: Vector3::new(4, 5, 6),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_las_writer_reports_quantization_overflow() -> Result<()> {
+        let source_point_buffer = prepare_point_buffer(&get_test_points_with_utm_coordinates());
+
+        // With an offset of zero and a scale of 1mm, the Y coordinates don't fit into an i32
+        let mut writer = LASWriter::from_writer_and_point_format(
+            Cursor::new(Vec::<u8>::new()),
+            Format::new(2)?,
+            false,
+        )?;
+        let error = writer
+            .write(&source_point_buffer)
+            .expect_err("Writing positions that overflow i32 should fail");
+        let message = error.to_string();
+        assert!(message.contains("Y coordinate 5400000.5678"), "{}", message);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_writer_with_automatic_transforms() -> Result<()> {
+        let source_points = get_test_points_with_utm_coordinates();
+        let source_point_buffer = prepare_point_buffer(&source_points);
+
+        let mut writer = LASWriter::from_writer_and_points(
+            Cursor::new(Vec::<u8>::new()),
+            Format::new(2)?,
+            &source_point_buffer,
+            &AutomaticLASTransforms::default(),
+            false,
+        )?;
+        writer.write(&source_point_buffer)?;
+        let las_data = writer.into_inner()?.into_inner();
+
+        let mut reader = LASReader::from_read(Cursor::new(las_data), false, false)?;
+        let header = reader.header().clone();
+        assert_eq!(0.001, header.transforms().y.scale);
+        assert!((header.transforms().y.offset - 5_400_000.567).abs() < 1e-6);
+
+        let read_points = reader.read::<VectorBuffer>(source_points.len())?;
+        let read_positions = read_points
+            .view_attribute::<Vector3<f64>>(&POSITION_3D)
+            .into_iter()
+            .collect::<Vec<_>>();
+        for (expected, actual) in source_points.iter().zip(read_positions.iter()) {
+            let expected_position = expected.position;
+            assert!(
+                (expected_position - actual).abs().max() <= 0.0005 + 1e-9,
+                "Expected {} but got {}",
+                expected_position,
+                actual
+            );
+        }
+
+        Ok(())
+    }
+
+    #[repr(C, packed)]
+    #[derive(Debug, Clone, Copy, PointType, bytemuck::AnyBitPattern, bytemuck::NoUninit)]
+    struct PointWithReturns {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+        #[pasture(BUILTIN_RETURN_NUMBER)]
+        pub return_number: u8,
+        #[pasture(BUILTIN_NUMBER_OF_RETURNS)]
+        pub number_of_returns: u8,
+    }
+
+    fn points_with_return_number(
+        return_number: u8,
+        count: usize,
+        first_coordinate: f64,
+    ) -> VectorBuffer {
+        (0..count)
+            .map(|index| {
+                let coordinate = first_coordinate + index as f64;
+                PointWithReturns {
+                    position: Vector3::new(coordinate, -coordinate, coordinate / 2.0),
+                    return_number,
+                    number_of_returns: 2,
+                }
+            })
+            .collect()
+    }
+
+    /// Writes two batches of points into a file, drops the writer without flushing and checks the statistics in the
+    /// header of the file
+    fn check_header_is_updated_on_drop(file_name: &str, point_format: u8) -> Result<()> {
+        let mut test_file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_file_path.push(file_name);
+        defer! {
+            std::fs::remove_file(&test_file_path).expect("Removing test file failed!");
+        }
+
+        {
+            let mut writer =
+                LASWriter::from_path_and_point_format(&test_file_path, Format::new(point_format)?)?;
+            writer.write(&points_with_return_number(1, 10, 0.0))?;
+            writer.write(&points_with_return_number(2, 5, 100.0))?;
+        }
+
+        let raw_header =
+            las::raw::Header::read_from(std::io::BufReader::new(File::open(&test_file_path)?))?;
+        let large_file = raw_header
+            .large_file
+            .expect("LAS 1.4 header has large_file field");
+        assert_eq!(15, large_file.number_of_point_records);
+        assert_eq!(10, large_file.number_of_points_by_return[0]);
+        assert_eq!(5, large_file.number_of_points_by_return[1]);
+        assert!(large_file.number_of_points_by_return[2..]
+            .iter()
+            .all(|count| *count == 0));
+        if point_format < 6 {
+            assert_eq!(15, raw_header.number_of_point_records);
+            assert_eq!([10, 5, 0, 0, 0], raw_header.number_of_points_by_return);
+        } else {
+            // The legacy fields must be zero for the extended point formats
+            assert_eq!(0, raw_header.number_of_point_records);
+            assert_eq!([0; 5], raw_header.number_of_points_by_return);
+        }
+        assert_eq!((0.0, 104.0), (raw_header.min_x, raw_header.max_x));
+        assert_eq!((-104.0, 0.0), (raw_header.min_y, raw_header.max_y));
+        assert_eq!((0.0, 52.0), (raw_header.min_z, raw_header.max_z));
+
+        let reader = LASReader::from_path(&test_file_path, false)?;
+        assert_eq!(Some(15), reader.point_count());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_writer_updates_legacy_header_fields_on_drop() -> Result<()> {
+        check_header_is_updated_on_drop("test_las_writer_updates_legacy_header_fields.las", 1)
+    }
+
+    #[test]
+    fn test_las_writer_updates_extended_header_fields_on_drop() -> Result<()> {
+        check_header_is_updated_on_drop("test_las_writer_updates_extended_header_fields.las", 6)
+    }
+
+    #[cfg(feature = "laz")]
+    #[test]
+    fn test_laz_writer_updates_header_fields_on_drop() -> Result<()> {
+        check_header_is_updated_on_drop("test_laz_writer_updates_header_fields.laz", 1)
+    }
+
+    #[test]
+    fn test_las_writer_finalize_returns_final_metadata() -> Result<()> {
+        let mut test_file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_file_path.push("test_las_writer_finalize_returns_final_metadata.las");
+        defer! {
+            std::fs::remove_file(&test_file_path).expect("Removing test file failed!");
+        }
+
+        let mut writer = LASWriter::from_path_and_point_format(&test_file_path, Format::new(1)?)?;
+        writer.write(&points_with_return_number(1, 10, 0.0))?;
+        writer.flush()?;
+        // Flushing does not finalize the file, so the header still states zero points
+        let raw_header = las::raw::Header::read_from(File::open(&test_file_path)?)?;
+        assert_eq!(0, raw_header.number_of_point_records);
+
+        let metadata = writer.finalize()?;
+        assert_eq!(10, metadata.point_count());
+        assert_eq!(
+            Some(AABB::from_min_max(
+                Point3::new(0.0, -9.0, 0.0),
+                Point3::new(9.0, 0.0, 4.5)
+            )),
+            metadata.bounds()
+        );
+        let raw_header = las::raw::Header::read_from(File::open(&test_file_path)?)?;
+        assert_eq!(10, raw_header.number_of_point_records);
+        Ok(())
+    }
+
+    /// Output that fails all writes once `is_read_only` is set, like a file system that was remounted read-only
+    struct ReadOnlySink {
+        data: Cursor<Vec<u8>>,
+        is_read_only: Arc<AtomicBool>,
+    }
+
+    impl Write for ReadOnlySink {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.is_read_only.load(Ordering::SeqCst) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::PermissionDenied,
+                    "Output is read-only",
+                ));
+            }
+            self.data.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Seek for ReadOnlySink {
+        fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+            self.data.seek(pos)
+        }
+    }
+
+    fn check_finalize_reports_errors(is_compressed: bool) -> Result<()> {
+        let is_read_only = Arc::new(AtomicBool::new(false));
+        let make_writer = || {
+            LASWriterBuilder::new(Format::new(1)?)
+                .compressed(is_compressed)
+                .build(ReadOnlySink {
+                    data: Cursor::new(vec![]),
+                    is_read_only: is_read_only.clone(),
+                })
+        };
+
+        let mut writer = make_writer()?;
+        writer.write(&points_with_return_number(1, 10, 0.0))?;
+        is_read_only.store(true, Ordering::SeqCst);
+        let error = writer
+            .finalize()
+            .expect_err("Finalizing must fail if the header can't be written");
+        assert!(format!("{:#}", error).contains("read-only"), "{:#}", error);
+
+        // Dropping the writer tries to finalize the file as well, which must not panic
+        is_read_only.store(false, Ordering::SeqCst);
+        let mut writer = make_writer()?;
+        writer.write(&points_with_return_number(1, 10, 0.0))?;
+        is_read_only.store(true, Ordering::SeqCst);
+        drop(writer);
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_writer_finalize_reports_errors() -> Result<()> {
+        check_finalize_reports_errors(false)
+    }
+
+    #[cfg(feature = "laz")]
+    #[test]
+    fn test_laz_writer_finalize_reports_errors() -> Result<()> {
+        check_finalize_reports_errors(true)
+    }
+
+    #[test]
+    fn test_las_writer_without_points_writes_zero_bounds() -> Result<()> {
+        let writer = LASWriter::from_writer_and_point_format(
+            Cursor::new(Vec::<u8>::new()),
+            Format::new(0)?,
+            false,
+        )?;
+        let las_data = writer.into_inner()?.into_inner();
+        let raw_header = las::raw::Header::read_from(Cursor::new(las_data))?;
+        assert_eq!(0, raw_header.number_of_point_records);
+        assert_eq!((0.0, 0.0), (raw_header.min_x, raw_header.max_x));
+        Ok(())
+    }
+
+    struct NonSeekableWriter(Vec<u8>);
+
+    impl Write for NonSeekableWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Seek for NonSeekableWriter {
+        fn seek(&mut self, _pos: std::io::SeekFrom) -> std::io::Result<u64> {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "Seeking is not supported",
+            ))
+        }
+    }
+
+    #[test]
+    fn test_las_writer_requires_seekable_output() -> Result<()> {
+        let error = match LASWriter::from_writer_and_point_format(
+            NonSeekableWriter(vec![]),
+            Format::new(0)?,
+            false,
+        ) {
+            Ok(_) => panic!("Creating a LASWriter for a non-seekable output should fail"),
+            Err(error) => error,
+        };
+        assert!(error.to_string().contains("seekable"), "{}", error);
+        Ok(())
+    }
+
+    const TEST_WKT: &str = r#"PROJCS["ETRS89 / UTM zone 32N",GEOGCS["ETRS89"],UNIT["metre",1]]"#;
+
+    fn write_empty_las_file(builder: &LASWriterBuilder) -> Result<Vec<u8>> {
+        let writer = builder.build(Cursor::new(Vec::<u8>::new()))?;
+        Ok(writer.into_inner()?.into_inner())
+    }
+
+    fn global_encoding_of_las_file(las_data: &[u8]) -> u16 {
+        u16::from_le_bytes([las_data[6], las_data[7]])
+    }
+
+    #[test]
+    fn test_las_writer_builder_with_crs_wkt() -> Result<()> {
+        let las_data =
+            write_empty_las_file(&LASWriterBuilder::new(Format::new(6)?).with_crs_wkt(TEST_WKT))?;
+        assert_ne!(0, global_encoding_of_las_file(&las_data) & 0x10);
+
+        let reader = LASReader::from_read(Cursor::new(las_data), false, false)?;
+        assert_eq!(Some(TEST_WKT), reader.las_metadata().crs_wkt());
+        assert!(reader.las_metadata().crs().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_writer_builder_with_crs_wkt_requires_las_1_4() -> Result<()> {
+        let builder = LASWriterBuilder::new(Format::new(1)?)
+            .version(1, 2)
+            .with_crs_wkt(TEST_WKT);
+        assert!(builder.build(Cursor::new(Vec::<u8>::new())).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_writer_builder_with_epsg() -> Result<()> {
+        let las_data = write_empty_las_file(
+            &LASWriterBuilder::new(Format::new(1)?)
+                .version(1, 2)
+                .with_epsg(25832),
+        )?;
+        assert_eq!(0, global_encoding_of_las_file(&las_data) & 0x10);
+
+        let reader = LASReader::from_read(Cursor::new(las_data), false, false)?;
+        let crs = reader
+            .las_metadata()
+            .crs()
+            .expect("GeoTIFF CRS should be present");
+        assert_eq!(Some(25832), crs.horizontal_epsg_code());
+        assert_eq!(None, reader.las_metadata().crs_wkt());
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_writer_builder_preserves_crs_of_source_file() -> Result<()> {
+        let source_data = write_empty_las_file(
+            &LASWriterBuilder::new(Format::new(6)?)
+                .with_crs_wkt(TEST_WKT)
+                .with_epsg(4326),
+        )?;
+        let source_reader = LASReader::from_read(Cursor::new(source_data), false, false)?;
+
+        let target_data = write_empty_las_file(
+            &LASWriterBuilder::new(Format::new(7)?).with_crs_of(source_reader.las_metadata()),
+        )?;
+        assert_ne!(0, global_encoding_of_las_file(&target_data) & 0x10);
+        let target_reader = LASReader::from_read(Cursor::new(target_data), false, false)?;
+        assert_eq!(Some(TEST_WKT), target_reader.las_metadata().crs_wkt());
+        assert_eq!(
+            Some(4326),
+            target_reader
+                .las_metadata()
+                .crs()
+                .and_then(|crs| crs.horizontal_epsg_code())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_writer_builder_with_las_versions() -> Result<()> {
+        for (minor_version, expected_header_size) in
+            vec![(0, 227), (1, 227), (2, 227), (3, 235), (4, 375)]
+        {
+            let mut writer = LASWriterBuilder::new(Format::new(1)?)
+                .version(1, minor_version)
+                .build(Cursor::new(Vec::<u8>::new()))?;
+            writer.write(&points_with_return_number(1, 10, 0.0))?;
+            writer.write(&points_with_return_number(2, 5, 100.0))?;
+            let las_data = writer.into_inner()?.into_inner();
+
+            let raw_header = las::raw::Header::read_from(Cursor::new(&las_data))?;
+            assert_eq!(
+                (1, minor_version),
+                (raw_header.version.major, raw_header.version.minor)
+            );
+            assert_eq!(expected_header_size, raw_header.header_size);
+            assert_eq!(15, raw_header.number_of_point_records);
+            assert_eq!([10, 5, 0, 0, 0], raw_header.number_of_points_by_return);
+            assert_eq!(minor_version == 4, raw_header.large_file.is_some());
+
+            let mut reader = LASReader::from_read(Cursor::new(las_data), false, false)?;
+            assert_eq!(Some(15), reader.point_count());
+            let points = reader.read::<VectorBuffer>(15)?;
+            assert_eq!(15, points.len());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_writer_builder_rejects_invalid_las_versions() -> Result<()> {
+        let invalid_combinations = vec![
+            (6, 1, 2),
+            (10, 1, 3),
+            (4, 1, 2),
+            (2, 1, 1),
+            (1, 1, 5),
+            (1, 2, 0),
+        ];
+        for (point_format, major_version, minor_version) in invalid_combinations {
+            let builder = LASWriterBuilder::new(Format::new(point_format)?)
+                .version(major_version, minor_version);
+            let error = match builder.build(Cursor::new(Vec::<u8>::new())) {
+                Ok(_) => panic!(
+                    "Point format {} and LAS version {}.{} should be rejected",
+                    point_format, major_version, minor_version
+                ),
+                Err(error) => error,
+            };
+            assert!(error.to_string().contains("LAS version"), "{}", error);
+        }
+
+        assert!(LASWriterBuilder::new(Format::new(3)?)
+            .version(1, 2)
+            .build(Cursor::new(Vec::<u8>::new()))
+            .is_ok());
+        assert!(LASWriterBuilder::new(Format::new(5)?)
+            .version(1, 3)
+            .build(Cursor::new(Vec::<u8>::new()))
+            .is_ok());
+        Ok(())
+    }
+
+    fn test_evlr(record_id: u16, payload_size: usize) -> las_rs::Vlr {
+        las_rs::Vlr {
+            user_id: "pasture".to_owned(),
+            record_id,
+            description: "Test EVLR".to_owned(),
+            data: (0..payload_size).map(|index| (index % 251) as u8).collect(),
+        }
+    }
+
+    fn check_evlrs_round_trip(is_compressed: bool) -> Result<()> {
+        let header_evlr = test_evlr(1, 16);
+        // Larger than u16::MAX, which regular VLRs can't store
+        let large_evlr = test_evlr(2, 70_000);
+
+        let mut writer = LASWriterBuilder::new(Format::new(6)?)
+            .with_evlr(header_evlr.clone())
+            .compressed(is_compressed)
+            .build(Cursor::new(Vec::<u8>::new()))?;
+        writer.write(&points_with_return_number(1, 10, 0.0))?;
+        writer.write(&points_with_return_number(2, 5, 100.0))?;
+        writer.add_evlr(large_evlr.clone())?;
+        let las_data = writer.into_inner()?.into_inner();
+
+        let raw_header = las::raw::Header::read_from(Cursor::new(&las_data))?;
+        let evlr_info = raw_header.evlr.expect("LAS 1.4 header has EVLR fields");
+        assert_eq!(2, evlr_info.number_of_evlrs);
+        if !is_compressed {
+            assert_eq!(
+                raw_header.offset_to_point_data as u64
+                    + 15 * raw_header.point_data_record_length as u64,
+                evlr_info.start_of_first_evlr
+            );
+        }
+
+        let mut reader = LASReader::from_read(Cursor::new(las_data), is_compressed, false)?;
+        let evlrs = reader.las_metadata().evlrs();
+        assert_eq!(2, evlrs.len());
+        assert_eq!(header_evlr.data, evlrs[0].data);
+        assert_eq!(large_evlr.data, evlrs[1].data);
+        assert_eq!(
+            Some(&large_evlr.data),
+            reader
+                .las_metadata()
+                .find_vlr("pasture", 2)
+                .map(|vlr| &vlr.data)
+        );
+        let points = reader.read::<VectorBuffer>(15)?;
+        assert_eq!(15, points.len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_writer_with_evlrs() -> Result<()> {
+        check_evlrs_round_trip(false)
+    }
+
+    #[cfg(feature = "laz")]
+    #[test]
+    fn test_laz_writer_with_evlrs() -> Result<()> {
+        check_evlrs_round_trip(true)
+    }
+
+    #[test]
+    fn test_las_writer_evlrs_require_las_1_4() -> Result<()> {
+        let builder = LASWriterBuilder::new(Format::new(1)?)
+            .version(1, 2)
+            .with_evlr(test_evlr(1, 16));
+        assert!(builder.build(Cursor::new(Vec::<u8>::new())).is_err());
+
+        let mut writer = LASWriterBuilder::new(Format::new(1)?)
+            .version(1, 2)
+            .build(Cursor::new(Vec::<u8>::new()))?;
+        assert!(writer.add_evlr(test_evlr(1, 16)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_writer_rejects_return_numbers_that_overflow_bit_fields() -> Result<()> {
+        for (point_format, return_number) in vec![(1, 8), (6, 16)] {
+            let mut writer = LASWriterBuilder::new(Format::new(point_format)?)
+                .build(Cursor::new(Vec::<u8>::new()))?;
+            let error = writer
+                .write(&points_with_return_number(return_number, 3, 0.0))
+                .expect_err("Return number should not fit into the LAS point record");
+            let message = format!("{:#}", error);
+            assert!(
+                message.contains(&format!("Return number {}", return_number)),
+                "{}",
+                message
+            );
+            assert!(
+                message.contains("point 0 of the given points"),
+                "{}",
+                message
+            );
+        }
+        Ok(())
+    }
+
+    fn check_header_matches_written_points_after_invalid_chunk(is_compressed: bool) -> Result<()> {
+        let mut reader = LASReader::from_path(get_test_las_path(3), false)?;
+        let mut default_layout_points = reader.read::<VectorBuffer>(test_data_point_count())?;
+        let mut custom_layout_points = points_with_return_number(1, 6, 0.0);
+        // The fourth point is part of the second chunk and has a return number that does not fit into the 3 bits of
+        // point format 3
+        default_layout_points
+            .view_attribute_mut::<u8>(&RETURN_NUMBER)
+            .set_at(3, 8);
+        custom_layout_points
+            .view_attribute_mut::<u8>(&RETURN_NUMBER)
+            .set_at(3, 8);
+        let valid_points = points_with_return_number(1, 2, 100.0);
+
+        for invalid_points in [&default_layout_points, &custom_layout_points] {
+            let mut writer = LASWriterBuilder::new(Format::new(3)?)
+                .compressed(is_compressed)
+                .write_chunk_size(2)
+                .build(Cursor::new(Vec::<u8>::new()))?;
+            writer
+                .write(invalid_points)
+                .expect_err("Return number should not fit into the LAS point record");
+            writer.write(&valid_points)?;
+            let las_data = writer.into_inner()?.into_inner();
+
+            // Only the first chunk of the invalid points was written, and the header must match these points
+            let expected_positions = invalid_points
+                .view_attribute::<Vector3<f64>>(&POSITION_3D)
+                .into_iter()
+                .take(2)
+                .chain(valid_points.view_attribute::<Vector3<f64>>(&POSITION_3D))
+                .collect::<Vec<_>>();
+            let mut reader = LASReader::from_read(Cursor::new(las_data), is_compressed, false)?;
+            assert_eq!(Some(expected_positions.len()), reader.point_count());
+            let las_header = reader
+                .las_metadata()
+                .raw_las_header()
+                .expect("LAS header is present");
+            assert_eq!(
+                Some(expected_positions.len() as u64),
+                las_header.number_of_points_by_return(1)
+            );
+
+            let expected_bounds = expected_positions.iter().fold(
+                AABB::from_min_max_unchecked(
+                    expected_positions[0].into(),
+                    expected_positions[0].into(),
+                ),
+                |bounds, position| AABB::extend_with_point(&bounds, &(*position).into()),
+            );
+            assert_eq!(Some(expected_bounds), reader.get_metadata().bounds());
+
+            let points = reader.read_all::<VectorBuffer>()?;
+            for (index, (expected, actual)) in expected_positions
+                .iter()
+                .zip(
+                    points
+                        .view_attribute::<Vector3<f64>>(&POSITION_3D)
+                        .into_iter(),
+                )
+                .enumerate()
+            {
+                assert!((expected - actual).norm() < 1e-3, "point {}", index);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_writer_header_matches_written_points_after_invalid_chunk() -> Result<()> {
+        check_header_matches_written_points_after_invalid_chunk(false)
+    }
+
+    #[cfg(feature = "laz")]
+    #[test]
+    fn test_laz_writer_header_matches_written_points_after_invalid_chunk() -> Result<()> {
+        check_header_matches_written_points_after_invalid_chunk(true)
+    }
+
+    #[test]
+    fn test_las_writer_builder_header_fields_round_trip() -> Result<()> {
+        let creation_date = NaiveDate::from_yo_opt(2021, 123).expect("Valid date");
+        let writer = LASWriterBuilder::new(Format::new(1)?)
+            .file_source_id(4711)
+            .gps_time_type(GpsTimeType::Standard)
+            .synthetic_return_numbers(true)
+            .system_identifier("Test scanner")
+            .generating_software("pasture tests")
+            .file_creation_date(creation_date)
+            .build(Cursor::new(Vec::<u8>::new()))?;
+        let las_data = writer.into_inner()?.into_inner();
+
+        let reader = LASReader::from_read(Cursor::new(las_data), false, false)?;
+        let metadata = reader.las_metadata();
+        assert_eq!(Some(4711), metadata.file_source_id());
+        assert_eq!(Some(GpsTimeType::Standard), metadata.gps_time_type());
+        let global_encoding = metadata.global_encoding().expect("LAS header is present");
+        assert_eq!(0b1001, global_encoding & 0b1001);
+        assert_eq!(Some("Test scanner"), metadata.system_identifier());
+        assert_eq!(Some("pasture tests"), metadata.generating_software());
+        assert_eq!(Some(123), metadata.file_creation_day_of_year());
+        assert_eq!(Some(2021), metadata.file_creation_year());
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_writer_builder_default_header_fields() -> Result<()> {
+        let date_before = Utc::now().date_naive();
+        let header = LASWriterBuilder::new(Format::new(0)?).build_header()?;
+        let date_after = Utc::now().date_naive();
+        assert_eq!(
+            format!("pasture {}", env!("CARGO_PKG_VERSION")),
+            header.generating_software()
+        );
+        let date = header.date().expect("Creation date is set by default");
+        assert!(date >= date_before && date <= date_after);
+        assert_eq!(GpsTimeType::Week, header.gps_time_type());
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_writer_builder_truncates_long_header_strings() -> Result<()> {
+        let long_identifier = "ä".repeat(20);
+        let builder = LASWriterBuilder::new(Format::new(0)?).system_identifier(&long_identifier);
+        assert_eq!(1, builder.warnings().len());
+        let header = builder.build_header()?;
+        assert_eq!("ä".repeat(16), header.system_identifier());
+
+        let strict_builder = builder.strict_header_strings(true);
+        assert!(strict_builder.warnings().is_empty());
+        let error = strict_builder
+            .build_header()
+            .expect_err("Strings that are too long must be rejected in strict mode");
+        assert!(error.to_string().contains("system identifier"));
+        Ok(())
+    }
+
+    #[repr(C, packed)]
+    #[derive(Debug, Clone, Copy, PointType, bytemuck::AnyBitPattern, bytemuck::NoUninit)]
+    struct ColoredPointWithHeight {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+        #[pasture(BUILTIN_INTENSITY)]
+        pub intensity: u16,
+        #[pasture(BUILTIN_GPS_TIME)]
+        pub gps_time: f64,
+        #[pasture(BUILTIN_COLOR_RGB)]
+        pub color: Vector3<u16>,
+        #[pasture(attribute = "HeightAboveGround")]
+        pub height_above_ground: f32,
+    }
+
+    #[test]
+    fn test_las_writer_builder_from_point_layout() -> Result<()> {
+        let layout = ColoredPointWithHeight::layout();
+        let builder =
+            LASWriterBuilder::from_point_layout(&layout, UnknownAttributeHandling::Ignore)?;
+        let header = builder.build_header()?;
+        assert_eq!(Format::new(3)?, *header.point_format());
+        assert!(header.vlrs().is_empty());
+
+        let builder =
+            LASWriterBuilder::from_point_layout(&layout, UnknownAttributeHandling::ExtraBytes)?;
+        let header = builder.build_header()?;
+        assert_eq!(4, header.point_format().extra_bytes);
+        assert_eq!(3, header.point_format().to_u8()?);
+
+        let error = LASWriterBuilder::from_point_layout(&layout, UnknownAttributeHandling::Error)
+            .expect_err("Unknown attributes must be rejected");
+        assert!(error.to_string().contains("HeightAboveGround"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_writer_writes_custom_vlrs() -> Result<()> {
+        let vendor_vlr = las_rs::Vlr {
+            user_id: "pasture".to_owned(),
+            record_id: 42,
+            description: "Processing parameters".to_owned(),
+            data: b"voxel_size=0.25".to_vec(),
+        };
+        let other_vlrs = vec![test_evlr(43, 100), test_evlr(44, 0)];
+        let mut writer = LASWriterBuilder::new(Format::new(1)?)
+            .version(1, 2)
+            .with_vlr(vendor_vlr.clone())
+            .with_vlrs(other_vlrs)
+            .build(Cursor::new(Vec::<u8>::new()))?;
+        writer.write(&points_with_return_number(1, 10, 0.0))?;
+        let las_data = writer.into_inner()?.into_inner();
+
+        let raw_header = las::raw::Header::read_from(Cursor::new(&las_data))?;
+        assert_eq!(3, raw_header.number_of_variable_length_records);
+        assert_eq!(
+            227 + 3 * 54 + vendor_vlr.data.len() as u32 + 100,
+            raw_header.offset_to_point_data
+        );
+
+        let mut reader = LASReader::from_read(Cursor::new(las_data), false, false)?;
+        let vlr = reader
+            .las_metadata()
+            .find_vlr("pasture", 42)
+            .expect("Custom VLR must be written");
+        assert_eq!(vendor_vlr.description, vlr.description);
+        assert_eq!(vendor_vlr.data, vlr.data);
+        assert!(reader.las_metadata().find_vlr("pasture", 44).is_some());
+        assert_eq!(10, reader.read::<VectorBuffer>(10)?.len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_writer_rejects_invalid_custom_vlrs() -> Result<()> {
+        let error = LASWriterBuilder::new(Format::new(0)?)
+            .with_vlr(test_evlr(1, 70_000))
+            .build_header()
+            .expect_err("VLRs larger than u16::MAX bytes must be rejected");
+        assert!(error.to_string().contains("Add it as an EVLR instead"));
+
+        let epsg_vlr = las_rs::Vlr {
+            user_id: PROJECTION_VLR_USER_ID.to_owned(),
+            record_id: 34735,
+            description: String::new(),
+            data: vec![0; 8],
+        };
+        assert!(LASWriterBuilder::new(Format::new(0)?)
+            .with_vlrs(vec![epsg_vlr.clone(), epsg_vlr])
+            .build_header()
+            .is_err());
+        Ok(())
+    }
+
+    /// Writes `points` with the given `overflow_handling` and returns the points that were written, as read by a
+    /// `LASReader`
+    fn write_points_with_overflow_handling<T: PointType>(
+        points: &[T],
+        point_format: u8,
+        overflow_handling: BitAttributeOverflowHandling,
+    ) -> Result<VectorBuffer> {
+        let mut writer = LASWriterBuilder::new(Format::new(point_format)?)
+            .bit_attribute_overflow_handling(overflow_handling)
+            .build(Cursor::new(Vec::<u8>::new()))?;
+        writer.write(&points.iter().copied().collect::<VectorBuffer>())?;
+        let las_data = writer.into_inner()?.into_inner();
+        let mut reader = LASReader::from_read(Cursor::new(las_data), false, false)?;
+        reader.read::<VectorBuffer>(points.len())
+    }
+
+    /// Writes three points of which the last one has an out-of-range value for `attribute` and checks the result of
+    /// each `BitAttributeOverflowHandling`. `expected_values` are the values of `attribute` of the last point that are
+    /// written with `Saturate` and `Wrap`
+    fn check_out_of_range_attribute<T: PointType + Default>(
+        point_format: u8,
+        attribute: &PointAttributeDefinition,
+        attribute_name: &str,
+        make_invalid: impl Fn(&mut T),
+        expected_values: (f64, f64),
+    ) -> Result<()> {
+        let mut points = vec![T::default(); 3];
+        make_invalid(&mut points[2]);
+
+        let error = write_points_with_overflow_handling(
+            &points,
+            point_format,
+            BitAttributeOverflowHandling::Error,
+        )
+        .expect_err("Writing an out-of-range value must fail");
+        let message = format!("{:#}", error);
+        assert!(message.contains(attribute_name), "{}", message);
+        assert!(
+            message.contains("point 2 of the given points"),
+            "{}",
+            message
+        );
+
+        let (saturated, wrapped) = expected_values;
+        for (overflow_handling, expected_value) in vec![
+            (BitAttributeOverflowHandling::Saturate, saturated),
+            (BitAttributeOverflowHandling::Wrap, wrapped),
+        ] {
+            let written_points =
+                write_points_with_overflow_handling(&points, point_format, overflow_handling)?;
+            let value = written_points
+                .view_attribute_with_conversion::<f64>(attribute)?
+                .at(2);
+            assert_eq!(
+                expected_value, value,
+                "{} with {:?}",
+                attribute_name, overflow_handling
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_writer_out_of_range_attributes_in_legacy_formats() -> Result<()> {
+        check_out_of_range_attribute(
+            1,
+            &RETURN_NUMBER,
+            "Return number",
+            |point: &mut LasPointFormat1| point.return_number = 9,
+            (7.0, 1.0),
+        )?;
+        check_out_of_range_attribute(
+            1,
+            &NUMBER_OF_RETURNS,
+            "Number of returns",
+            |point: &mut LasPointFormat1| point.number_of_returns = 10,
+            (7.0, 2.0),
+        )?;
+        check_out_of_range_attribute(
+            1,
+            &CLASSIFICATION,
+            "Classification",
+            |point: &mut LasPointFormat1| point.classification = 40,
+            (31.0, 8.0),
+        )?;
+        check_out_of_range_attribute(
+            1,
+            &SCAN_ANGLE_RANK,
+            "Scan angle rank",
+            |point: &mut LasPointFormat1| point.scan_angle_rank = 100,
+            (90.0, 100.0),
+        )?;
+        check_out_of_range_attribute(
+            1,
+            &SCAN_ANGLE_RANK,
+            "Scan angle rank",
+            |point: &mut LasPointFormat1| point.scan_angle_rank = -120,
+            (-90.0, -120.0),
+        )
+    }
+
+    #[test]
+    fn test_las_writer_out_of_range_attributes_in_extended_formats() -> Result<()> {
+        check_out_of_range_attribute(
+            6,
+            &RETURN_NUMBER,
+            "Return number",
+            |point: &mut LasPointFormat6| point.return_number = 17,
+            (15.0, 1.0),
+        )?;
+        check_out_of_range_attribute(
+            6,
+            &NUMBER_OF_RETURNS,
+            "Number of returns",
+            |point: &mut LasPointFormat6| point.number_of_returns = 18,
+            (15.0, 2.0),
+        )?;
+        check_out_of_range_attribute(
+            6,
+            &SCANNER_CHANNEL,
+            "Scanner channel",
+            |point: &mut LasPointFormat6| point.scanner_channel = 5,
+            (3.0, 1.0),
+        )?;
+        check_out_of_range_attribute(
+            6,
+            &CLASSIFICATION_FLAGS,
+            "Classification flags",
+            |point: &mut LasPointFormat6| point.classification_flags = 17,
+            (15.0, 1.0),
+        )?;
+        check_out_of_range_attribute(
+            6,
+            &SCAN_ANGLE,
+            "Scan angle",
+            |point: &mut LasPointFormat6| point.scan_angle = 31_000,
+            (30_000.0, 31_000.0),
+        )
+    }
+
+    #[test]
+    fn test_las_writer_saturates_return_numbers_that_overflow_bit_fields() -> Result<()> {
+        for (point_format, max_return_number) in vec![(1, 7), (6, 15)] {
+            let mut writer = LASWriterBuilder::new(Format::new(point_format)?)
+                .bit_attribute_overflow_handling(BitAttributeOverflowHandling::Saturate)
+                .build(Cursor::new(Vec::<u8>::new()))?;
+            writer.write(&points_with_return_number(20, 3, 0.0))?;
+            let las_data = writer.into_inner()?.into_inner();
+
+            let raw_header = las::raw::Header::read_from(Cursor::new(&las_data))?;
+            let large_file = raw_header
+                .large_file
+                .expect("LAS 1.4 header has large_file field");
+            assert_eq!(
+                3,
+                large_file.number_of_points_by_return[max_return_number - 1]
+            );
+
+            let mut reader = LASReader::from_read(Cursor::new(las_data), false, false)?;
+            let points = reader.read::<VectorBuffer>(3)?;
+            let return_numbers = points
+                .view_attribute::<u8>(&RETURN_NUMBER)
+                .into_iter()
+                .collect::<Vec<_>>();
+            assert_eq!(vec![max_return_number as u8; 3], return_numbers);
+        }
+        Ok(())
+    }
+
+    /// Points in the extended point format 6 with classification flags, some of which have the overlap flag set
+    /// either through the `OVERLAP` attribute or through bit 3 of the classification flags
+    fn points_with_classification_flags() -> Vec<LasPointFormat6> {
+        [(2, 0b001, 0), (31, 0b110, 1), (7, 0b1101, 0), (0, 0b111, 0)]
+            .iter()
+            .map(
+                |&(classification, classification_flags, overlap)| LasPointFormat6 {
+                    classification,
+                    classification_flags,
+                    overlap,
+                    ..Default::default()
+                },
+            )
+            .collect()
+    }
+
+    #[test]
+    fn test_las_writer_packs_classification_flags_into_legacy_classification_byte() -> Result<()> {
+        let points = points_with_classification_flags();
+        let mut writer =
+            LASWriterBuilder::new(Format::new(1)?).build(Cursor::new(Vec::<u8>::new()))?;
+        writer.write(&points.iter().copied().collect::<VectorBuffer>())?;
+        assert_eq!(2, writer.dropped_overlap_flags());
+        let las_data = writer.into_inner()?.into_inner();
+
+        // The classification byte follows the position, intensity and the return number bit field
+        let raw_header = las::raw::Header::read_from(Cursor::new(&las_data))?;
+        let classification_bytes = (0..points.len())
+            .map(|index| {
+                las_data[raw_header.offset_to_point_data as usize
+                    + index * raw_header.point_data_record_length as usize
+                    + 15]
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![2 | 0b001 << 5, 31 | 0b110 << 5, 7 | 0b101 << 5, 0b111 << 5],
+            classification_bytes
+        );
+
+        let mut reader = LASReader::from_read(Cursor::new(las_data), false, false)?;
+        let read_points = reader.read::<VectorBuffer>(points.len())?;
+        assert_eq!(
+            vec![2, 31, 7, 0],
+            read_points
+                .view_attribute::<u8>(&CLASSIFICATION)
+                .into_iter()
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![0b001, 0b110, 0b101, 0b111],
+            read_points
+                .view_attribute::<u8>(&CLASSIFICATION_FLAGS)
+                .into_iter()
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_writer_rejects_overlap_flags_in_legacy_formats_in_strict_mode() -> Result<()> {
+        let points = points_with_classification_flags();
+        for overlapping_point in [1, 2] {
+            let mut writer = LASWriterBuilder::new(Format::new(1)?)
+                .overlap_flag_handling(OverlapFlagHandling::Error)
+                .build(Cursor::new(Vec::<u8>::new()))?;
+            let error = writer
+                .write(
+                    &points[overlapping_point..=overlapping_point]
+                        .iter()
+                        .copied()
+                        .collect::<VectorBuffer>(),
+                )
+                .expect_err("Writing the overlap flag to point format 1 must fail in strict mode");
+            assert!(format!("{:#}", error).contains("overlap flag"));
+        }
+
+        // Points in the point format of the file, where the overlap flag can only be part of the classification flags
+        let mut legacy_point = LasPointFormat1 {
+            classification: 3,
+            classification_flags: 0b1010,
+            ..Default::default()
+        };
+        let mut writer = LASWriterBuilder::new(Format::new(1)?)
+            .overlap_flag_handling(OverlapFlagHandling::Error)
+            .build(Cursor::new(Vec::<u8>::new()))?;
+        assert!(writer
+            .write(&[legacy_point].iter().copied().collect::<VectorBuffer>())
+            .is_err());
+
+        legacy_point.classification_flags = 0b010;
+        writer.write(&[legacy_point].iter().copied().collect::<VectorBuffer>())?;
+        assert_eq!(0, writer.dropped_overlap_flags());
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_writer_round_trip_of_waveform_formats() -> Result<()> {
+        for point_format in vec![4, 5, 9, 10] {
+            let format = Format::new(point_format)?;
+            let mut source_reader = LASReader::from_path(get_test_las_path(point_format), false)?;
+            let source_points = source_reader.read::<VectorBuffer>(test_data_point_count())?;
+
+            let mut writer = LASWriterBuilder::new(format)
+                .with_waveforms_of(source_reader.las_metadata())
+                .build(Cursor::new(Vec::<u8>::new()))?;
+            writer.write(&source_points)?;
+            let las_data = writer.into_inner()?.into_inner();
+
+            let mut reader = LASReader::from_read(Cursor::new(las_data), false, false)?;
+            let points = reader.read::<VectorBuffer>(test_data_point_count())?;
+            compare_to_reference_data(&points, format);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_writer_append_to_existing_file() -> Result<()> {
+        let mut test_file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_file_path.push("test_append_to_las_file.las");
+        std::fs::copy(get_test_las_path(1), &test_file_path)?;
+        defer! {
+            std::fs::remove_file(&test_file_path).expect("Removing test file failed!");
+        }
+
+        {
+            let mut writer = LASWriter::append(&test_file_path)?;
+            writer.write(&points_with_return_number(1, 5, 100.0))?;
+            writer.flush()?;
+        }
+
+        let raw_header = las::raw::Header::read_from(File::open(&test_file_path)?)?;
+        assert_eq!(
+            test_data_point_count() as u32 + 5,
+            raw_header.number_of_point_records
+        );
+        assert_eq!(
+            (0.0, -104.0, 0.0),
+            (raw_header.min_x, raw_header.min_y, raw_header.min_z)
+        );
+        assert_eq!(
+            (104.0, 9.0, 52.0),
+            (raw_header.max_x, raw_header.max_y, raw_header.max_z)
+        );
+
+        let mut reader = LASReader::from_path(&test_file_path, false)?;
+        let original_points = reader.read::<VectorBuffer>(test_data_point_count())?;
+        compare_to_reference_data(&original_points, Format::new(1)?);
+        let appended_points = reader.read::<VectorBuffer>(5)?;
+        assert_eq!(5, appended_points.len());
+        let positions = appended_points
+            .view_attribute::<Vector3<f64>>(&POSITION_3D)
+            .into_iter()
+            .collect::<Vec<_>>();
+        assert_eq!(Vector3::new(100.0, -100.0, 50.0), positions[0]);
+        assert_eq!(Vector3::new(104.0, -104.0, 52.0), positions[4]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_writer_append_relocates_evlrs() -> Result<()> {
+        let mut test_file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_file_path.push("test_append_to_las_file_with_evlrs.las");
+        defer! {
+            std::fs::remove_file(&test_file_path).expect("Removing test file failed!");
+        }
+        let evlr = test_evlr(1, 70_000);
+        {
+            let mut writer = LASWriterBuilder::new(Format::new(6)?)
+                .with_evlr(evlr.clone())
+                .build_for_path(&test_file_path)?;
+            writer.write(&points_with_return_number(1, 10, 0.0))?;
+            writer.flush()?;
+        }
+        {
+            let mut writer = LASWriter::append(&test_file_path)?;
+            writer.write(&points_with_return_number(2, 5, 100.0))?;
+            writer.flush()?;
+        }
+
+        let mut reader = LASReader::from_path(&test_file_path, false)?;
+        assert_eq!(15, reader.las_metadata().point_count());
+        let evlrs = reader.las_metadata().evlrs();
+        assert_eq!(1, evlrs.len());
+        assert_eq!(evlr.data, evlrs[0].data);
+        let points = reader.read::<VectorBuffer>(15)?;
+        let return_numbers = points
+            .view_attribute::<u8>(&RETURN_NUMBER)
+            .into_iter()
+            .collect::<Vec<_>>();
+        assert_eq!(vec![1; 10], return_numbers[..10].to_vec());
+        assert_eq!(vec![2; 5], return_numbers[10..].to_vec());
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_writer_append_rejects_laz_files() {
+        let error = LASWriter::append("points.laz")
+            .err()
+            .expect("Appending to LAZ files must fail");
+        assert!(error.to_string().contains("compressed LAZ files"));
+    }
+
+    #[test]
+    fn test_las_writer_write_points_from_iterator() -> Result<()> {
+        // More points than fit into a single chunk of `write_points`
+        let point_count = 2 * LASWriter::<Cursor<Vec<u8>>>::POINTS_PER_WRITE_CHUNK + 17;
+        let mut writer =
+            LASWriterBuilder::new(Format::new(1)?).build(Cursor::new(Vec::<u8>::new()))?;
+        let points_written =
+            writer.write_points((0..point_count).map(|index| PointWithReturns {
+                position: Vector3::new(index as f64, 0.0, 1.0),
+                return_number: 1,
+                number_of_returns: 1,
+            }))?;
+        assert_eq!(point_count, points_written);
+        let las_data = writer.into_inner()?.into_inner();
+
+        let mut reader = LASReader::from_read(Cursor::new(las_data), false, false)?;
+        assert_eq!(point_count, reader.las_metadata().point_count());
+        let points = reader.read::<VectorBuffer>(point_count)?;
+        let positions = points
+            .view_attribute::<Vector3<f64>>(&POSITION_3D)
+            .into_iter()
+            .collect::<Vec<_>>();
+        for (index, position) in positions.iter().enumerate() {
+            assert_eq!(Vector3::new(index as f64, 0.0, 1.0), *position);
+        }
+        Ok(())
+    }
+
+    #[repr(C, packed)]
+    #[derive(Debug, Clone, Copy, PointType, bytemuck::AnyBitPattern, bytemuck::NoUninit)]
+    struct PositionOnlyPoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+    }
+
+    #[test]
+    fn test_las_writer_writes_defaults_for_missing_attributes() -> Result<()> {
+        let defaults = MissingAttributeDefaults {
+            intensity: 1234,
+            classification: 2,
+            gps_time: 4711.5,
+            color_rgb: Vector3::new(100, 200, 300),
+            ..Default::default()
+        };
+        let mut writer = LASWriterBuilder::new(Format::new(3)?)
+            .missing_attribute_defaults(defaults)
+            .build(Cursor::new(Vec::<u8>::new()))?;
+        writer.write_points((0..10).map(|index| PositionOnlyPoint {
+            position: Vector3::new(index as f64, 0.0, 0.0),
+        }))?;
+        let las_data = writer.into_inner()?.into_inner();
+
+        let mut reader = LASReader::from_read(Cursor::new(las_data), false, false)?;
+        let points = reader.read::<VectorBuffer>(10)?;
+        for index in 0..10 {
+            assert_eq!(
+                Vector3::new(index as f64, 0.0, 0.0),
+                points
+                    .view_attribute::<Vector3<f64>>(&POSITION_3D)
+                    .at(index)
+            );
+            assert_eq!(1234, points.view_attribute::<u16>(&INTENSITY).at(index));
+            assert_eq!(2, points.view_attribute::<u8>(&CLASSIFICATION).at(index));
+            assert_eq!(4711.5, points.view_attribute::<f64>(&GPS_TIME).at(index));
+            assert_eq!(
+                Vector3::new(100, 200, 300),
+                points.view_attribute::<Vector3<u16>>(&COLOR_RGB).at(index)
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_writer_requires_positions_with_missing_attribute_defaults() -> Result<()> {
+        #[repr(C, packed)]
+        #[derive(Debug, Clone, Copy, PointType, bytemuck::AnyBitPattern, bytemuck::NoUninit)]
+        struct IntensityOnlyPoint {
+            #[pasture(BUILTIN_INTENSITY)]
+            pub intensity: u16,
+        }
+
+        let mut writer = LASWriterBuilder::new(Format::new(3)?)
+            .missing_attribute_defaults(MissingAttributeDefaults::default())
+            .build(Cursor::new(Vec::<u8>::new()))?;
+        assert!(writer
+            .write_points([IntensityOnlyPoint { intensity: 1 }])
+            .is_err());
+        Ok(())
+    }
+
+    #[repr(C, packed)]
+    #[derive(Debug, Clone, Copy, PointType, bytemuck::AnyBitPattern, bytemuck::NoUninit)]
+    struct PointWith8BitColor {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+        #[pasture(BUILTIN_COLOR_RGB)]
+        pub color: Vector3<u8>,
+    }
+
+    #[test]
+    fn test_las_writer_upscales_8_bit_colors() -> Result<()> {
+        let colors = [Vector3::new(255, 128, 0), Vector3::new(1, 2, 3)];
+        // Each upscaling together with the conversion on the read side that inverts it
+        let test_cases = [
+            (ColorUpscaling::Cast, ColorConversion::Truncate, 1),
+            (
+                ColorUpscaling::MultiplyBy257,
+                ColorConversion::DivideBy257,
+                257,
+            ),
+            (ColorUpscaling::ShiftBy8, ColorConversion::ShiftBy8, 256),
+        ];
+        for (color_upscaling, color_conversion, factor) in test_cases {
+            let mut writer = LASWriterBuilder::new(Format::new(2)?)
+                .color_upscaling(color_upscaling)
+                .build(Cursor::new(Vec::<u8>::new()))?;
+            writer.write_points(colors.iter().map(|color| PointWith8BitColor {
+                position: Vector3::zeros(),
+                color: *color,
+            }))?;
+            let las_data = writer.into_inner()?.into_inner();
+
+            let mut reader = LASReader::from_read(Cursor::new(las_data.clone()), false, false)?;
+            let points = reader.read::<VectorBuffer>(colors.len())?;
+            let expected_colors = colors
+                .iter()
+                .map(|color| color.map(|channel| channel as u16 * factor))
+                .collect::<Vec<_>>();
+            let written_colors = points
+                .view_attribute::<Vector3<u16>>(&COLOR_RGB)
+                .into_iter()
+                .collect::<Vec<_>>();
+            assert_eq!(expected_colors, written_colors, "{:?}", color_upscaling);
+
+            let options = LASReadOptions::builder()
+                .color_conversion(color_conversion)
+                .build();
+            let mut reader =
+                LASReader::from_read_with_options(Cursor::new(las_data), false, options)?;
+            let mut points = vec![
+                PointWith8BitColor {
+                    position: Vector3::zeros(),
+                    color: Vector3::zeros(),
+                };
+                colors.len()
+            ];
+            assert_eq!(colors.len(), reader.read_into_slice(&mut points)?);
+            let round_trip_colors = points.iter().map(|point| point.color).collect::<Vec<_>>();
+            assert_eq!(colors.to_vec(), round_trip_colors, "{:?}", color_upscaling);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_writer_upscales_8_bit_colors_by_default() -> Result<()> {
+        let mut writer =
+            LASWriterBuilder::new(Format::new(2)?).build(Cursor::new(Vec::<u8>::new()))?;
+        writer.write_points([PointWith8BitColor {
+            position: Vector3::zeros(),
+            color: Vector3::new(255, 0, 1),
+        }])?;
+        let las_data = writer.into_inner()?.into_inner();
+
+        let mut reader = LASReader::from_read(Cursor::new(las_data), false, false)?;
+        let points = reader.read::<VectorBuffer>(1)?;
+        assert_eq!(
+            Vector3::new(65535, 0, 257),
+            points.view_attribute::<Vector3<u16>>(&COLOR_RGB).at(0)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_writer_output_does_not_depend_on_write_chunk_size() -> Result<()> {
+        let format = Format::new(3)?;
+        let mut reader = LASReader::from_path(get_test_las_path(3), false)?;
+        let default_layout_points = reader.read::<VectorBuffer>(test_data_point_count())?;
+        let custom_layout_points = get_test_points_custom_format();
+
+        let write_las_file = |write_chunk_size: usize| -> Result<Vec<u8>> {
+            let mut writer = LASWriterBuilder::new(format)
+                .write_chunk_size(write_chunk_size)
+                .build(Cursor::new(Vec::<u8>::new()))?;
+            // Written in two calls, so that the reused chunk buffers are larger than the second batch of points
+            writer.write(&default_layout_points)?;
+            writer.write_points(custom_layout_points.iter().copied())?;
+            Ok(writer.into_inner()?.into_inner())
+        };
+
+        let expected_data = write_las_file(DEFAULT_WRITE_CHUNK_SIZE)?;
+        for write_chunk_size in [1, 3, test_data_point_count() + 1] {
+            assert_eq!(
+                expected_data,
+                write_las_file(write_chunk_size)?,
+                "write chunk size {}",
+                write_chunk_size
+            );
+        }
+
+        let mut reader = LASReader::from_read(Cursor::new(expected_data), false, false)?;
+        let points = reader.read::<VectorBuffer>(test_data_point_count())?;
+        compare_to_reference_data(&points, format);
+        Ok(())
+    }
+
+    fn write_las_file_in_chunks_of_3<'a, B: BorrowedBuffer<'a>>(
+        points: &'a B,
+        is_compressed: bool,
+    ) -> Result<Vec<u8>> {
+        let mut writer = LASWriterBuilder::new(Format::new(3)?)
+            .compressed(is_compressed)
+            .write_chunk_size(3)
+            .build(Cursor::new(Vec::<u8>::new()))?;
+        writer.write(points)?;
+        Ok(writer.into_inner()?.into_inner())
+    }
+
+    fn check_columnar_buffers_are_written_like_interleaved_buffers(
+        is_compressed: bool,
+    ) -> Result<()> {
+        let mut reader = LASReader::from_path(get_test_las_path(3), false)?;
+        let interleaved_points = reader.read::<VectorBuffer>(test_data_point_count())?;
+        let mut reader = LASReader::from_path(get_test_las_path(3), false)?;
+        let columnar_points = reader.read::<HashMapBuffer>(test_data_point_count())?;
+        assert_eq!(
+            write_las_file_in_chunks_of_3(&interleaved_points, is_compressed)?,
+            write_las_file_in_chunks_of_3(&columnar_points, is_compressed)?
+        );
+
+        // A layout that does not match the point format goes through the attribute-wise conversion instead
+        let custom_points = get_test_points_custom_format();
+        assert_eq!(
+            write_las_file_in_chunks_of_3(
+                &custom_points.iter().copied().collect::<VectorBuffer>(),
+                is_compressed
+            )?,
+            write_las_file_in_chunks_of_3(
+                &custom_points.iter().copied().collect::<HashMapBuffer>(),
+                is_compressed
+            )?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_writer_writes_columnar_buffers_like_interleaved_buffers() -> Result<()> {
+        check_columnar_buffers_are_written_like_interleaved_buffers(false)
+    }
+
+    #[cfg(feature = "laz")]
+    #[test]
+    fn test_laz_writer_writes_columnar_buffers_like_interleaved_buffers() -> Result<()> {
+        check_columnar_buffers_are_written_like_interleaved_buffers(true)
+    }
+
+    #[test]
+    fn test_las_writer_rejects_zero_write_chunk_size() -> Result<()> {
+        assert!(LASWriterBuilder::new(Format::new(0)?)
+            .write_chunk_size(0)
+            .build(Cursor::new(Vec::<u8>::new()))
+            .is_err());
+
+        let mut writer =
+            LASWriterBuilder::new(Format::new(0)?).build(Cursor::new(Vec::<u8>::new()))?;
+        assert!(writer.set_write_chunk_size(0).is_err());
+        Ok(())
+    }
+
+    /// Returns the chunk size that is stored in the LASzip VLR of the given LAZ file
+    #[cfg(feature = "laz")]
+    fn laz_chunk_size_of(laz_data: &[u8]) -> Result<u32> {
+        let mut cursor = Cursor::new(laz_data);
+        let raw_header = las::raw::Header::read_from(&mut cursor)?;
+        cursor.set_position(raw_header.header_size as u64);
+        for _ in 0..raw_header.number_of_variable_length_records {
+            let vlr = las::raw::Vlr::read_from(&mut cursor, false)?;
+            let user_id = laz::LazVlr::USER_ID.as_bytes();
+            if &vlr.user_id[..user_id.len()] == user_id && vlr.record_id == laz::LazVlr::RECORD_ID {
+                return Ok(laz::LazVlr::read_from(vlr.data.as_slice())?.chunk_size());
+            }
+        }
+        bail!("LAZ file has no LASzip VLR")
+    }
+
+    #[cfg(feature = "laz")]
+    #[test]
+    fn test_laz_writer_with_custom_chunk_size() -> Result<()> {
+        use crate::base::SeekToPoint;
+        use std::io::SeekFrom;
+
+        let points = points_with_return_number(1, 2500, 0.0);
+        let mut writer = LASWriterBuilder::new(Format::new(1)?)
+            .compressed(true)
+            .laz_chunk_size(1000)
+            .build(Cursor::new(Vec::<u8>::new()))?;
+        writer.write(&points)?;
+        let laz_data = writer.into_inner()?.into_inner();
+        assert_eq!(1000, laz_chunk_size_of(&laz_data)?);
+
+        // Point 1500 is in the middle of the second chunk, so the reader has to decompress from the start of that chunk
+        let mut reader = LASReader::from_read(Cursor::new(laz_data), true, false)?;
+        assert_eq!(1500, reader.seek_point(SeekFrom::Start(1500))?);
+        let points_after_seek = reader.read::<VectorBuffer>(600)?;
+        let positions = points_after_seek
+            .view_attribute::<Vector3<f64>>(&POSITION_3D)
+            .into_iter()
+            .collect::<Vec<_>>();
+        for (index, position) in positions.iter().enumerate() {
+            let coordinate = (1500 + index) as f64;
+            assert_eq!(
+                Vector3::new(coordinate, -coordinate, coordinate / 2.0),
+                *position
+            );
+        }
+
+        let default_data = LASWriterBuilder::new(Format::new(1)?)
+            .compressed(true)
+            .build(Cursor::new(Vec::<u8>::new()))?
+            .into_inner()?
+            .into_inner();
+        assert_eq!(DEFAULT_LAZ_CHUNK_SIZE, laz_chunk_size_of(&default_data)?);
+        Ok(())
+    }
+
+    #[cfg(feature = "laz")]
+    #[test]
+    fn test_laz_writer_rejects_invalid_chunk_sizes() -> Result<()> {
+        for chunk_size in [0, u32::MAX] {
+            assert!(LASWriterBuilder::new(Format::new(1)?)
+                .compressed(true)
+                .laz_chunk_size(chunk_size)
+                .build(Cursor::new(Vec::<u8>::new()))
+                .is_err());
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "laz-parallel")]
+    #[test]
+    fn test_laz_writer_parallel_matches_sequential() -> Result<()> {
+        // More than two LAZ chunks of 50,000 points, written in batches that don't align with the chunks
+        let batches = (0..5)
+            .map(|batch| points_with_return_number(1, 25_000, batch as f64 * 25_000.0))
+            .collect::<Vec<_>>();
+        let write_laz_file = |builder: LASWriterBuilder| -> Result<Vec<u8>> {
+            let mut writer = builder.build(Cursor::new(Vec::<u8>::new()))?;
+            for batch in &batches {
+                writer.write(batch)?;
+            }
+            Ok(writer.into_inner()?.into_inner())
+        };
+
+        let builder = LASWriterBuilder::new(Format::new(1)?).compressed(true);
+        let sequential_data = write_laz_file(builder.clone())?;
+        let parallel_data = write_laz_file(builder.compression_threads(4))?;
+        assert_eq!(sequential_data.len(), parallel_data.len());
+        assert!(sequential_data == parallel_data);
+
+        let mut reader = LASReader::from_read(Cursor::new(parallel_data), true, false)?;
+        assert_eq!(Some(125_000), reader.point_count());
+        let points = reader.read::<VectorBuffer>(125_000)?;
+        let positions = points
+            .view_attribute::<Vector3<f64>>(&POSITION_3D)
+            .into_iter()
+            .collect::<Vec<_>>();
+        assert_eq!(Vector3::new(0.0, 0.0, 0.0), positions[0]);
+        assert_eq!(
+            Vector3::new(124_999.0, -124_999.0, 62_499.5),
+            positions[124_999]
+        );
+        Ok(())
+    }
 }
@@ -0,0 +1,329 @@
+use std::fs::File;
+use std::io::{BufReader, Read, SeekFrom};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use byteorder::{LittleEndian, ReadBytesExt};
+use pasture_core::containers::{MakeBufferFromLayout, OwningBuffer, VectorBuffer};
+use pasture_core::math::AABB;
+
+use super::LASMetadata;
+use crate::base::SeekToPoint;
+
+/// User ID of the EVLR that stores an embedded LAX index
+pub const LAX_VLR_USER_ID: &str = "LAStools";
+/// Record ID of the EVLR that stores an embedded LAX index
+pub const LAX_VLR_RECORD_ID: u16 = 30;
+
+/// A single cell of a LAX index together with the intervals of point records that fall into the cell
+#[derive(Clone, Debug)]
+struct LAXCell {
+    cell_index: i32,
+    /// Inclusive `(first, last)` point indices
+    intervals: Vec<(u32, u32)>,
+}
+
+/// Spatial index over the point records of a LAS file, as written by the `lasindex` tool of LAStools. The index is a
+/// 2D quadtree over the XY extent of the file, where each cell stores the intervals of point records that lie within
+/// the cell. It is either stored in a `.lax` sidecar file next to the LAS file or embedded as an EVLR
+#[derive(Clone, Debug)]
+pub struct LAXIndex {
+    levels: u32,
+    min_x: f32,
+    max_x: f32,
+    min_y: f32,
+    max_y: f32,
+    cells: Vec<LAXCell>,
+}
+
+impl LAXIndex {
+    /// Parses a LAX index from the given `reader`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data in `reader` is not a valid LAX index, or if it uses a spatial structure other than
+    /// a quadtree
+    pub fn read_from<R: Read>(mut reader: R) -> Result<Self> {
+        expect_signature(&mut reader, b"LASX")?;
+        let _version = reader.read_u32::<LittleEndian>()?;
+
+        expect_signature(&mut reader, b"LASS")?;
+        let spatial_type = reader.read_u32::<LittleEndian>()?;
+        if spatial_type != 0 {
+            bail!("Unsupported LAX spatial index type {}", spatial_type);
+        }
+        expect_signature(&mut reader, b"LASQ")?;
+        let _version = reader.read_u32::<LittleEndian>()?;
+        let levels = reader.read_u32::<LittleEndian>()?;
+        let _level_index = reader.read_u32::<LittleEndian>()?;
+        let _implicit_levels = reader.read_u32::<LittleEndian>()?;
+        let min_x = reader.read_f32::<LittleEndian>()?;
+        let max_x = reader.read_f32::<LittleEndian>()?;
+        let min_y = reader.read_f32::<LittleEndian>()?;
+        let max_y = reader.read_f32::<LittleEndian>()?;
+        // Cell indices are stored as `i32` values, so deeper quadtrees can't be addressed anyways
+        if levels > 15 {
+            bail!("Invalid number of quadtree levels {} in LAX index", levels);
+        }
+
+        expect_signature(&mut reader, b"LASV")?;
+        let _version = reader.read_u32::<LittleEndian>()?;
+        let number_of_cells = reader.read_u32::<LittleEndian>()?;
+        let cells = (0..number_of_cells)
+            .map(|_| -> Result<LAXCell> {
+                let cell_index = reader.read_i32::<LittleEndian>()?;
+                let number_of_intervals = reader.read_u32::<LittleEndian>()?;
+                let _number_of_points = reader.read_u32::<LittleEndian>()?;
+                let intervals = (0..number_of_intervals)
+                    .map(|_| -> Result<(u32, u32)> {
+                        let first = reader.read_u32::<LittleEndian>()?;
+                        let last = reader.read_u32::<LittleEndian>()?;
+                        if last < first {
+                            bail!("Invalid point interval {}..={} in LAX index", first, last);
+                        }
+                        Ok((first, last))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(LAXCell {
+                    cell_index,
+                    intervals,
+                })
+            })
+            .collect::<Result<Vec<_>>>()
+            .context("Failed to read cells of LAX index")?;
+
+        Ok(Self {
+            levels,
+            min_x,
+            max_x,
+            min_y,
+            max_y,
+            cells,
+        })
+    }
+
+    /// Parses the `.lax` sidecar file at `path`
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path.as_ref())
+            .with_context(|| format!("Could not open LAX file {}", path.as_ref().display()))?;
+        Self::read_from(BufReader::new(file))
+    }
+
+    /// Parses the LAX index that is embedded into the (E)VLRs of a LAS file. Returns `None` if the file contains no
+    /// such VLR
+    pub fn from_las_metadata(metadata: &LASMetadata) -> Result<Option<Self>> {
+        match metadata.find_vlr(LAX_VLR_USER_ID, LAX_VLR_RECORD_ID) {
+            Some(vlr) => Ok(Some(Self::read_from(&vlr.data[..])?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the path of the `.lax` sidecar file for the LAS file at `las_path`
+    pub fn sidecar_path<P: AsRef<Path>>(las_path: P) -> PathBuf {
+        las_path.as_ref().with_extension("lax")
+    }
+
+    /// Returns the sorted, non-overlapping ranges of point indices that might lie within `bounds`. Only the XY extent
+    /// of `bounds` is considered. All ranges are clamped to `point_count`
+    pub fn candidate_point_ranges(
+        &self,
+        bounds: &AABB<f64>,
+        point_count: usize,
+    ) -> Vec<Range<usize>> {
+        let mut ranges = self
+            .cells
+            .iter()
+            .filter(|cell| {
+                let (cell_min, cell_max) = self.cell_bounds(cell.cell_index);
+                cell_min[0] <= bounds.max().x
+                    && cell_max[0] >= bounds.min().x
+                    && cell_min[1] <= bounds.max().y
+                    && cell_max[1] >= bounds.min().y
+            })
+            .flat_map(|cell| cell.intervals.iter())
+            .map(|(first, last)| {
+                (*first as usize).min(point_count)..(*last as usize + 1).min(point_count)
+            })
+            .filter(|range| !range.is_empty())
+            .collect::<Vec<_>>();
+        ranges.sort_by_key(|range| range.start);
+
+        let mut merged_ranges: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged_ranges.last_mut() {
+                Some(previous) if range.start <= previous.end => {
+                    previous.end = previous.end.max(range.end);
+                }
+                _ => merged_ranges.push(range),
+            }
+        }
+        merged_ranges
+    }
+
+    /// Returns the minimum and maximum XY coordinates of the quadtree cell with the given `cell_index`. Cells are
+    /// numbered level by level, so the cells of level `l` start at index `(4^l - 1) / 3`
+    fn cell_bounds(&self, cell_index: i32) -> ([f64; 2], [f64; 2]) {
+        let level_offset = |level: u32| ((1_i64 << (2 * level)) - 1) / 3;
+        let cell_index = cell_index as i64;
+        let mut level = 0;
+        while level < self.levels && cell_index >= level_offset(level + 1) {
+            level += 1;
+        }
+        let level_index = cell_index - level_offset(level);
+
+        let (mut min_x, mut max_x) = (self.min_x, self.max_x);
+        let (mut min_y, mut max_y) = (self.min_y, self.max_y);
+        for current_level in (1..=level).rev() {
+            let quadrant = (level_index >> (2 * (current_level - 1))) & 0b11;
+            let mid_x = (min_x + max_x) / 2.0;
+            let mid_y = (min_y + max_y) / 2.0;
+            if quadrant & 0b01 != 0 {
+                min_x = mid_x;
+            } else {
+                max_x = mid_x;
+            }
+            if quadrant & 0b10 != 0 {
+                min_y = mid_y;
+            } else {
+                max_y = mid_y;
+            }
+        }
+        ([min_x as f64, min_y as f64], [max_x as f64, max_y as f64])
+    }
+}
+
+fn expect_signature<R: Read>(reader: &mut R, expected: &[u8; 4]) -> Result<()> {
+    let mut signature = [0; 4];
+    reader
+        .read_exact(&mut signature)
+        .context("Failed to read LAX index")?;
+    if &signature != expected {
+        bail!(
+            "Invalid LAX index, expected signature {} but found {:?}",
+            String::from_utf8_lossy(expected),
+            signature
+        );
+    }
+    Ok(())
+}
+
+/// Reads all points within `candidate_ranges` through `read_filtered` and appends the points that `read_filtered`
+/// accepts to `point_buffer`. `read_filtered` gets a buffer with the layout of `point_buffer` and the number of
+/// points to read, and returns the number of accepted points. Returns the total number of accepted points
+pub(crate) fn read_candidate_point_ranges<'a, R: SeekToPoint, B: OwningBuffer<'a>>(
+    reader: &mut R,
+    candidate_ranges: &[Range<usize>],
+    point_buffer: &mut B,
+    mut read_filtered: impl FnMut(&mut R, &mut VectorBuffer, usize) -> Result<usize>,
+) -> Result<usize> {
+    const POINTS_PER_CHUNK: usize = 1 << 14;
+    let mut chunk = VectorBuffer::new_from_layout(point_buffer.point_layout().clone());
+    let mut num_accepted_points = 0;
+    for range in candidate_ranges {
+        reader.seek_point(SeekFrom::Start(range.start as u64))?;
+        let mut points_remaining = range.len();
+        while points_remaining > 0 {
+            let points_in_chunk = points_remaining.min(POINTS_PER_CHUNK);
+            chunk.resize(points_in_chunk);
+            let num_accepted_in_chunk = read_filtered(reader, &mut chunk, points_in_chunk)?;
+            chunk.resize(num_accepted_in_chunk);
+            point_buffer.append(&chunk);
+            num_accepted_points += num_accepted_in_chunk;
+            points_remaining -= points_in_chunk;
+        }
+    }
+    Ok(num_accepted_points)
+}
+
+#[cfg(test)]
+mod tests {
+    use pasture_core::nalgebra::Point3;
+
+    use super::*;
+    use crate::las::make_lax_index_in_memory;
+
+    fn bounds_2d(min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> AABB<f64> {
+        AABB::from_min_max(
+            Point3::new(min_x, min_y, f64::MIN),
+            Point3::new(max_x, max_y, f64::MAX),
+        )
+    }
+
+    #[test]
+    fn test_lax_index_candidate_point_ranges() -> Result<()> {
+        // Single-level quadtree over [0;4]x[0;4], cells 1 to 4 are the lower left, lower right, upper left, and upper
+        // right quadrants
+        let lax_data = make_lax_index_in_memory(
+            1,
+            [0.0, 4.0, 0.0, 4.0],
+            &[
+                (1, &[(0, 9), (40, 49)]),
+                (2, &[(10, 19)]),
+                (3, &[(20, 29), (50, 59)]),
+                (4, &[(30, 39)]),
+            ],
+        );
+        let index = LAXIndex::read_from(&lax_data[..])?;
+
+        assert_eq!(
+            vec![0..10, 40..50],
+            index.candidate_point_ranges(&bounds_2d(0.5, 0.5, 1.5, 1.5), 60)
+        );
+        assert_eq!(
+            vec![10..20, 30..40],
+            index.candidate_point_ranges(&bounds_2d(3.0, 0.0, 4.0, 4.0), 60)
+        );
+        // Adjacent intervals of different cells are merged
+        assert_eq!(
+            vec![0..20, 40..50],
+            index.candidate_point_ranges(&bounds_2d(0.5, 0.5, 3.0, 1.5), 60)
+        );
+        assert_eq!(
+            vec![0..45],
+            index.candidate_point_ranges(&bounds_2d(0.0, 0.0, 4.0, 4.0), 45)
+        );
+        assert!(index
+            .candidate_point_ranges(&bounds_2d(5.0, 5.0, 6.0, 6.0), 60)
+            .is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_lax_index_coarser_cells() -> Result<()> {
+        // Two-level quadtree where the lower left quadrant was split further (cells 5 to 8) and the rest is stored in
+        // the level 1 cells
+        let lax_data = make_lax_index_in_memory(
+            2,
+            [0.0, 4.0, 0.0, 4.0],
+            &[
+                (5, &[(0, 4)]),
+                (8, &[(5, 9)]),
+                (2, &[(10, 19)]),
+                (4, &[(20, 29)]),
+            ],
+        );
+        let index = LAXIndex::read_from(&lax_data[..])?;
+
+        assert_eq!(
+            vec![0..5],
+            index.candidate_point_ranges(&bounds_2d(0.1, 0.1, 0.9, 0.9), 30)
+        );
+        assert_eq!(
+            vec![5..10],
+            index.candidate_point_ranges(&bounds_2d(1.1, 1.1, 1.9, 1.9), 30)
+        );
+        assert_eq!(
+            vec![20..30],
+            index.candidate_point_ranges(&bounds_2d(3.0, 3.0, 3.5, 3.5), 30)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_lax_index_invalid_signature() {
+        let mut lax_data = make_lax_index_in_memory(1, [0.0, 1.0, 0.0, 1.0], &[]);
+        lax_data[0] = b'X';
+        assert!(LAXIndex::read_from(&lax_data[..]).is_err());
+    }
+}
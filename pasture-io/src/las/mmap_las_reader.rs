@@ -0,0 +1,326 @@
+use std::fs::File;
+use std::io::{Cursor, SeekFrom};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use las_rs::{raw, Header};
+use memmap2::Mmap;
+use pasture_core::containers::{BorrowedMutBuffer, ExternalMemoryBuffer};
+use pasture_core::{layout::PointLayout, meta::Metadata};
+
+use super::{
+    get_default_las_converter, point_layout_from_las_metadata, LASMetadata, LASReadOptions,
+    LASReaderBase, RawLASReader,
+};
+use crate::base::{skip_points_by_seeking, PointReader, SeekToPoint};
+
+/// Reader for uncompressed LAS files that is backed by a memory-mapped file instead of a `Read + Seek` stream.
+/// Reading points copies (or converts) them directly from the mapped memory, so random access through `seek_point`
+/// does not require any system calls. Paging is left to the operating system
+pub struct MmapLASReader {
+    mmap: Mmap,
+    metadata: LASMetadata,
+    layout: PointLayout,
+    las_point_records_layout: PointLayout,
+    options: LASReadOptions,
+    current_point_index: usize,
+    offset_to_first_point_in_file: usize,
+    size_of_point_in_file: usize,
+}
+
+impl MmapLASReader {
+    /// Creates a new `MmapLASReader` by memory-mapping the LAS file at the given `path`. See
+    /// [`LASReader::from_path`](super::LASReader::from_path) for an explanation of the `point_layout_matches_memory_layout`
+    /// parameter
+    pub fn from_path<P: AsRef<Path>>(
+        path: P,
+        point_layout_matches_memory_layout: bool,
+    ) -> Result<Self> {
+        Self::from_path_with_options(
+            path,
+            LASReadOptions {
+                point_layout_matches_memory_layout,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Creates a new `MmapLASReader` by memory-mapping the LAS file at the given `path`, using the given `options` to
+    /// determine the default `PointLayout` of the reader. See [`LASReadOptions`] for more information
+    pub fn from_path_with_options<P: AsRef<Path>>(
+        path: P,
+        options: LASReadOptions,
+    ) -> Result<Self> {
+        let file = File::open(path.as_ref())
+            .with_context(|| format!("Could not open file {}", path.as_ref().display()))?;
+        // Safe as long as the file is not modified by someone else while it is mapped, which is the usual caveat
+        // of memory-mapped I/O
+        let mmap = unsafe { Mmap::map(&file) }.context("Could not memory-map LAS file")?;
+        Self::from_mmap_with_options(mmap, options)
+    }
+
+    /// Creates a new `MmapLASReader` from an existing memory mapping of a LAS file, using the given `options` to
+    /// determine the default `PointLayout` of the reader
+    pub fn from_mmap_with_options(mmap: Mmap, options: LASReadOptions) -> Result<Self> {
+        // Header and VLRs are parsed the same way as for a regular `RawLASReader`, only the point records are read
+        // from the mapped memory directly
+        let (metadata, layout) = {
+            let header_reader =
+                RawLASReader::from_read_with_options(Cursor::new(&mmap[..]), options.clone())?;
+            (
+                header_reader.las_metadata().clone(),
+                header_reader.get_default_point_layout().clone(),
+            )
+        };
+        if metadata.point_format().is_compressed {
+            bail!("MmapLASReader does not support compressed LAZ files");
+        }
+
+        let raw_header = raw::Header::read_from(Cursor::new(&mmap[..]))?;
+        let offset_to_first_point_in_file = raw_header.offset_to_point_data as usize;
+        let size_of_point_in_file = raw_header.point_data_record_length as usize;
+
+        let las_point_records_layout = point_layout_from_las_metadata(&metadata, true)?;
+        if las_point_records_layout.size_of_point_entry() as usize != size_of_point_in_file {
+            bail!(
+                "Point record length in LAS header ({} bytes) does not match the size of point format {} ({} bytes)",
+                size_of_point_in_file,
+                metadata.point_format(),
+                las_point_records_layout.size_of_point_entry()
+            );
+        }
+
+        let end_of_point_records =
+            offset_to_first_point_in_file + metadata.point_count() * size_of_point_in_file;
+        if end_of_point_records > mmap.len() {
+            bail!(
+                "LAS file is too small ({} bytes) for the {} point records stated in its header",
+                mmap.len(),
+                metadata.point_count()
+            );
+        }
+
+        Ok(Self {
+            mmap,
+            metadata,
+            layout,
+            las_point_records_layout,
+            options,
+            current_point_index: 0,
+            offset_to_first_point_in_file,
+            size_of_point_in_file,
+        })
+    }
+
+    pub fn las_metadata(&self) -> &LASMetadata {
+        &self.metadata
+    }
+
+    /// Returns the memory of the next `count` point records, starting at the current point
+    fn point_records_memory(&self, count: usize) -> &[u8] {
+        let start = self.offset_to_first_point_in_file
+            + self.current_point_index * self.size_of_point_in_file;
+        let end = start + count * self.size_of_point_in_file;
+        &self.mmap[start..end]
+    }
+
+    fn read_into_default_layout<'a, 'b, B: BorrowedMutBuffer<'a>>(
+        &mut self,
+        point_buffer: &'b mut B,
+        count: usize,
+    ) -> Result<usize>
+    where
+        'a: 'b,
+    {
+        let num_points_to_read = usize::min(count, self.remaining_points());
+        if num_points_to_read == 0 {
+            return Ok(0);
+        }
+
+        let point_records = self.point_records_memory(num_points_to_read);
+        if let Some(interleaved_buffer) = point_buffer.as_interleaved_mut() {
+            interleaved_buffer
+                .get_point_range_mut(0..num_points_to_read)
+                .copy_from_slice(point_records);
+        } else {
+            // Safe because this function (`read_into_default_layout`) is only called if the buffer has the exact
+            // binary memory layout of the LAS file
+            unsafe {
+                point_buffer.set_point_range(0..num_points_to_read, point_records);
+            }
+        }
+
+        self.current_point_index += num_points_to_read;
+
+        Ok(num_points_to_read)
+    }
+
+    fn read_into_custom_layout<'a, 'b, B: BorrowedMutBuffer<'a>>(
+        &mut self,
+        point_buffer: &'b mut B,
+        count: usize,
+    ) -> Result<usize>
+    where
+        'a: 'b,
+    {
+        let num_points_to_read = usize::min(count, self.remaining_points());
+        if num_points_to_read == 0 {
+            return Ok(0);
+        }
+
+        // No intermediate buffer necessary, we can convert straight from the mapped memory
+        let point_records = ExternalMemoryBuffer::new(
+            self.point_records_memory(num_points_to_read),
+            self.las_point_records_layout.clone(),
+        );
+        let target_layout = point_buffer.point_layout().clone();
+        let converter = get_default_las_converter(
+            &self.las_point_records_layout,
+            &target_layout,
+            &self.metadata,
+            &self.options,
+        )
+        .context("Unsupported conversion")?;
+        converter.convert_into_range(
+            &point_records,
+            0..num_points_to_read,
+            point_buffer,
+            0..num_points_to_read,
+        );
+
+        self.current_point_index += num_points_to_read;
+
+        Ok(num_points_to_read)
+    }
+}
+
+impl LASReaderBase for MmapLASReader {
+    fn remaining_points(&self) -> usize {
+        self.metadata.point_count() - self.current_point_index
+    }
+
+    fn header(&self) -> &Header {
+        self.metadata.raw_las_header().unwrap()
+    }
+}
+
+impl PointReader for MmapLASReader {
+    fn read_into<'a, 'b, B: BorrowedMutBuffer<'a>>(
+        &mut self,
+        point_buffer: &'b mut B,
+        count: usize,
+    ) -> Result<usize>
+    where
+        'a: 'b,
+    {
+        if point_buffer.len() < count {
+            panic!("point_buffer.len() must be >= count");
+        }
+
+        if *point_buffer.point_layout() != self.las_point_records_layout {
+            self.read_into_custom_layout(point_buffer, count)
+        } else {
+            self.read_into_default_layout(point_buffer, count)
+        }
+    }
+
+    fn get_metadata(&self) -> &dyn Metadata {
+        &self.metadata
+    }
+
+    fn get_default_point_layout(&self) -> &PointLayout {
+        &self.layout
+    }
+
+    fn skip(&mut self, count: usize) -> Result<usize> {
+        skip_points_by_seeking(self, count)
+    }
+
+    fn point_index(&self) -> Option<usize> {
+        Some(self.current_point_index)
+    }
+
+    fn point_count(&self) -> Option<usize> {
+        Some(self.metadata.point_count())
+    }
+}
+
+impl SeekToPoint for MmapLASReader {
+    fn seek_point(&mut self, position: SeekFrom) -> Result<usize> {
+        let new_position = match position {
+            SeekFrom::Start(from_start) => from_start as i64,
+            SeekFrom::End(from_end) => self.metadata.point_count() as i64 + from_end,
+            SeekFrom::Current(from_current) => self.current_point_index as i64 + from_current,
+        };
+        if new_position < 0 {
+            panic!("MmapLASReader::seek_point: It is an error to seek to a point position smaller than zero!");
+        }
+        // Seeking is just an index update, the memory is only touched once points are read
+        self.current_point_index =
+            std::cmp::min(self.metadata.point_count() as i64, new_position) as usize;
+        Ok(self.current_point_index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use las_rs::point::Format;
+    use pasture_core::containers::{BorrowedBuffer, VectorBuffer};
+
+    use super::*;
+    use crate::las::{
+        compare_to_reference_data, compare_to_reference_data_range, get_test_las_path,
+    };
+
+    #[test]
+    fn test_mmap_las_reader_read() -> Result<()> {
+        for format in 0..=10 {
+            let mut reader = MmapLASReader::from_path(get_test_las_path(format), false)?;
+            assert_eq!(10, reader.remaining_points());
+
+            let points = reader.read::<VectorBuffer>(10)?;
+            compare_to_reference_data(&points, Format::new(format)?);
+            assert_eq!(0, reader.remaining_points());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mmap_las_reader_read_memory_layout() -> Result<()> {
+        for format in 0..=10 {
+            let mut reader = MmapLASReader::from_path(get_test_las_path(format), true)?;
+            let mut raw_reader = RawLASReader::from_read(
+                std::io::BufReader::new(File::open(get_test_las_path(format))?),
+                true,
+            )?;
+
+            let expected_points = raw_reader.read::<VectorBuffer>(10)?;
+            let actual_points = reader.read::<VectorBuffer>(10)?;
+            assert_eq!(expected_points, actual_points, "Format {}", format);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mmap_las_reader_seek() -> Result<()> {
+        for format in 0..=10 {
+            let mut reader = MmapLASReader::from_path(get_test_las_path(format), false)?;
+
+            assert_eq!(7, reader.seek_point(SeekFrom::Start(7))?);
+            let points = reader.read::<VectorBuffer>(3)?;
+            assert_eq!(3, points.len());
+            compare_to_reference_data_range(&points, Format::new(format)?, 7..10);
+
+            assert_eq!(2, reader.seek_point(SeekFrom::End(-8))?);
+            let points = reader.read::<VectorBuffer>(4)?;
+            compare_to_reference_data_range(&points, Format::new(format)?, 2..6);
+
+            assert_eq!(10, reader.seek_point(SeekFrom::Current(100))?);
+            assert_eq!(0, reader.read::<VectorBuffer>(1)?.len());
+        }
+
+        Ok(())
+    }
+}
@@ -4,6 +4,23 @@ pub use self::las_reader::*;
 mod las_writer;
 pub use self::las_writer::*;
 
+mod las_streaming_writer;
+pub use self::las_streaming_writer::*;
+
+#[cfg(feature = "laz")]
+mod las_transcode;
+#[cfg(feature = "laz")]
+pub use self::las_transcode::*;
+
+mod las_tiled_writer;
+pub use self::las_tiled_writer::*;
+
+mod las_transforms;
+pub use self::las_transforms::*;
+
+mod las_extra_bytes;
+pub use self::las_extra_bytes::*;
+
 mod las_layout;
 pub use self::las_layout::*;
 
@@ -13,12 +30,72 @@ pub use self::las_types::*;
 mod las_metadata;
 pub use self::las_metadata::*;
 
+#[cfg(feature = "serde")]
+mod las_metadata_serde;
+#[cfg(feature = "serde")]
+pub use self::las_metadata_serde::*;
+
+mod las_read_options;
+pub use self::las_read_options::*;
+
+mod las_read_errors;
+pub use self::las_read_errors::*;
+
+mod las_point_filter;
+pub use self::las_point_filter::*;
+
+mod las_validation;
+pub use self::las_validation::*;
+
+mod las_waveform;
+pub use self::las_waveform::*;
+
+mod lax;
+pub use self::lax::*;
+
+mod copc;
+pub use self::copc::*;
+
+#[cfg(feature = "laz")]
+mod copc_reader;
+#[cfg(feature = "laz")]
+pub use self::copc_reader::*;
+
 mod raw_readers;
 pub(crate) use self::raw_readers::*;
 
+#[cfg(feature = "laz")]
+mod raw_laz_reader;
+#[cfg(feature = "laz")]
+pub(crate) use self::raw_laz_reader::*;
+
+#[cfg(feature = "mmap")]
+mod mmap_las_reader;
+#[cfg(feature = "mmap")]
+pub use self::mmap_las_reader::*;
+
+mod multi_file_las_reader;
+pub use self::multi_file_las_reader::*;
+
+mod las_dataset;
+pub use self::las_dataset::*;
+
+mod streaming_las_reader;
+pub use self::streaming_las_reader::*;
+
+#[cfg(feature = "async")]
+mod async_las_reader;
+#[cfg(feature = "async")]
+pub use self::async_las_reader::*;
+
 mod raw_writers;
 pub(crate) use self::raw_writers::*;
 
+#[cfg(feature = "laz")]
+mod raw_laz_writer;
+#[cfg(feature = "laz")]
+pub(crate) use self::raw_laz_writer::*;
+
 #[cfg(test)]
 mod test_util;
 #[cfg(test)]
@@ -30,5 +107,7 @@ pub(crate) use self::read_helpers::*;
 mod write_helpers;
 pub use self::write_helpers::*;
 
+#[cfg(feature = "laz")]
 mod las_err;
+#[cfg(feature = "laz")]
 pub(crate) use self::las_err::*;
@@ -0,0 +1,450 @@
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use pasture_core::containers::{
+    BorrowedBuffer, BorrowedMutBuffer, InterleavedBuffer, MakeBufferFromLayout, OwningBuffer,
+    VectorBuffer,
+};
+use pasture_core::layout::{PointAttributeDataType, PointAttributeDefinition, PointLayout};
+use pasture_core::math::AABB;
+use pasture_core::meta::Metadata;
+
+use super::{LASMetadata, LASReadOptions, LASReader};
+use crate::base::{skip_points_by_seeking, PointReader, SeekToPoint};
+
+/// Function that opens the file with the given index for a lazily opening [`MultiFileLASReader`]
+pub(crate) type OpenFileFn<'a, R> = Box<dyn FnMut(usize) -> Result<LASReader<'a, R>> + 'a>;
+
+/// Attribute for the index of the file that a point was read from, used by the [`MultiFileLASReader`]. The index
+/// refers to the order of the files that the reader was created from
+pub const ATTRIBUTE_SOURCE_FILE_INDEX: PointAttributeDefinition = PointAttributeDefinition::custom(
+    Cow::Borrowed("SourceFileIndex"),
+    PointAttributeDataType::U32,
+);
+
+/// `PointReader` that reads multiple LAS/LAZ files as if they were a single point cloud, e.g. for tiled surveys.
+/// The points of all files are read in the order of the files, and seeking works across file boundaries.
+///
+/// The files may have different point formats. The points of all files are converted into the `PointLayout` of
+/// the buffer passed to `read_into`, which for `read` and `read_all` is the default `PointLayout` of the reader.
+/// This is the default `PointLayout` of the first file, unless a different one is set with
+/// [`MultiFileLASReader::set_default_point_layout`]. If the target `PointLayout` contains the
+/// [`ATTRIBUTE_SOURCE_FILE_INDEX`] attribute, it is set to the index of the file that each point was read from
+///
+/// A `MultiFileLASReader` created through [`open_dataset`](super::open_dataset) opens its files lazily, so only the
+/// file that is currently being read is open
+pub struct MultiFileLASReader<'a, R: Read + Seek + 'a> {
+    /// Readers for all files, `None` for files that are currently not open
+    readers: Vec<Option<LASReader<'a, R>>>,
+    /// Opens the file with the given index. Only set if files are opened lazily
+    open_file: Option<OpenFileFn<'a, R>>,
+    point_counts: Vec<usize>,
+    /// Index of the first point of each file within the merged point cloud
+    first_point_indices: Vec<usize>,
+    current_reader: usize,
+    current_point_index: usize,
+    metadata: LASMetadata,
+    default_point_layout: PointLayout,
+    /// Buffer for the points of a single file, reused between calls to `read_into`
+    chunk_buffer: Option<VectorBuffer>,
+}
+
+impl MultiFileLASReader<'static, BufReader<File>> {
+    /// Opens all LAS/LAZ files at the given `paths` using the given `options`. Whether a file is compressed is
+    /// determined from its file extension, see [`LASReader::from_path`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `paths` is empty, or if any of the files cannot be opened as a LAS/LAZ file
+    pub fn from_paths<P: AsRef<Path>>(paths: &[P], options: LASReadOptions) -> Result<Self> {
+        let readers = paths
+            .iter()
+            .map(|path| {
+                LASReader::from_path_with_options(path, options.clone())
+                    .with_context(|| format!("Could not open LAS file {}", path.as_ref().display()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Self::from_readers(readers)
+    }
+}
+
+impl<'a, R: Read + Seek + 'a> MultiFileLASReader<'a, R> {
+    /// Creates a new `MultiFileLASReader` that reads the points of all `readers` in order. The readers are read from
+    /// their first point onwards, regardless of their current position
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `readers` is empty
+    pub fn from_readers(mut readers: Vec<LASReader<'a, R>>) -> Result<Self> {
+        if readers.is_empty() {
+            bail!("MultiFileLASReader requires at least one LAS file");
+        }
+        for reader in readers.iter_mut() {
+            reader.seek_point(SeekFrom::Start(0))?;
+        }
+        let files = readers
+            .iter()
+            .map(|reader| {
+                let las_metadata = reader.las_metadata();
+                let bounds = las_metadata
+                    .bounds()
+                    .expect("LASMetadata always has bounds");
+                (las_metadata.point_count(), bounds)
+            })
+            .collect::<Vec<_>>();
+        Self::new(readers.into_iter().map(Some).collect(), None, &files)
+    }
+
+    /// Creates a new `MultiFileLASReader` for files with the given point counts and bounds, which are opened through
+    /// `open_file` once reading reaches them. Whenever reading or seeking moves to another file, the previous file is
+    /// closed
+    pub(crate) fn from_lazily_opened_files(
+        files: &[(usize, AABB<f64>)],
+        mut open_file: OpenFileFn<'a, R>,
+    ) -> Result<Self> {
+        if files.is_empty() {
+            bail!("MultiFileLASReader requires at least one LAS file");
+        }
+        let mut readers = (0..files.len()).map(|_| None).collect::<Vec<_>>();
+        let first_reader = open_file(0)?;
+        Self::check_point_count(&first_reader, 0, files[0].0)?;
+        readers[0] = Some(first_reader);
+        Self::new(readers, Some(open_file), files)
+    }
+
+    /// Creates a new `MultiFileLASReader` from the point count and bounds of all `files`. The first reader must be
+    /// open
+    fn new(
+        readers: Vec<Option<LASReader<'a, R>>>,
+        open_file: Option<OpenFileFn<'a, R>>,
+        files: &[(usize, AABB<f64>)],
+    ) -> Result<Self> {
+        let point_counts = files
+            .iter()
+            .map(|(point_count, _)| *point_count)
+            .collect::<Vec<_>>();
+        let first_point_indices = point_counts
+            .iter()
+            .scan(0, |first_point_index, point_count| {
+                let current = *first_point_index;
+                *first_point_index += point_count;
+                Some(current)
+            })
+            .collect::<Vec<_>>();
+        let point_count = point_counts.iter().sum();
+        let bounds = files
+            .iter()
+            .map(|(_, bounds)| *bounds)
+            .reduce(|a, b| AABB::union(&a, &b))
+            .context("MultiFileLASReader requires at least one LAS file")?;
+
+        let first_reader = readers[0].as_ref().expect("The first file is always open");
+        let metadata = LASMetadata::new(
+            bounds,
+            point_count,
+            first_reader.las_metadata().point_format(),
+        );
+        let default_point_layout = first_reader.get_default_point_layout().clone();
+        Ok(Self {
+            readers,
+            open_file,
+            point_counts,
+            first_point_indices,
+            current_reader: 0,
+            current_point_index: 0,
+            metadata,
+            default_point_layout,
+            chunk_buffer: None,
+        })
+    }
+
+    fn check_point_count(
+        reader: &LASReader<'a, R>,
+        file_index: usize,
+        expected_point_count: usize,
+    ) -> Result<()> {
+        let point_count = reader.las_metadata().point_count();
+        if point_count != expected_point_count {
+            bail!(
+                "File {} contains {} points, but {} points were expected",
+                file_index,
+                point_count,
+                expected_point_count
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the number of files of this reader
+    pub fn file_count(&self) -> usize {
+        self.readers.len()
+    }
+
+    /// Returns the reader for the file at `file_index`, or `None` if the file is currently not open. This only
+    /// happens for files that are opened lazily
+    pub fn file_reader(&self, file_index: usize) -> Option<&LASReader<'a, R>> {
+        self.readers[file_index].as_ref()
+    }
+
+    /// Returns the merged metadata of all files. Its bounds are the union of the bounds of all files, its point count
+    /// is the sum of the point counts of all files, and its point format is the point format of the first file. Use
+    /// [`Self::file_reader`] to access the metadata of individual files
+    pub fn las_metadata(&self) -> &LASMetadata {
+        &self.metadata
+    }
+
+    /// Sets the default `PointLayout` of this reader, which is used by `read` and `read_all`. This is useful if the
+    /// files have different point formats and the default `PointLayout` of the first file does not contain all
+    /// attributes of interest
+    pub fn set_default_point_layout(&mut self, point_layout: PointLayout) {
+        self.default_point_layout = point_layout;
+    }
+
+    fn remaining_points_in_current_file(&self) -> usize {
+        let file_index = self.current_reader;
+        self.first_point_indices[file_index] + self.point_counts[file_index]
+            - self.current_point_index
+    }
+
+    /// Returns the reader for the current file, opening the file if necessary
+    fn current_file_reader(&mut self) -> Result<&mut LASReader<'a, R>> {
+        let file_index = self.current_reader;
+        if self.readers[file_index].is_none() {
+            let open_file = self
+                .open_file
+                .as_mut()
+                .expect("Only files that can be opened again are closed");
+            let mut reader = open_file(file_index)?;
+            Self::check_point_count(&reader, file_index, self.point_counts[file_index])?;
+            let position_within_file =
+                self.current_point_index - self.first_point_indices[file_index];
+            reader.seek_point(SeekFrom::Start(position_within_file as u64))?;
+            self.readers[file_index] = Some(reader);
+        }
+        Ok(self.readers[file_index]
+            .as_mut()
+            .expect("The current file was just opened"))
+    }
+
+    /// Makes `file_index` the current file and moves its reader to `self.current_point_index`. For lazily opened
+    /// files, the previous file is closed
+    fn set_current_file(&mut self, file_index: usize) -> Result<()> {
+        if file_index != self.current_reader && self.open_file.is_some() {
+            self.readers[self.current_reader] = None;
+        }
+        self.current_reader = file_index;
+        let position_within_file = self.current_point_index - self.first_point_indices[file_index];
+        if let Some(reader) = self.readers[file_index].as_mut() {
+            reader.seek_point(SeekFrom::Start(position_within_file as u64))?;
+        }
+        Ok(())
+    }
+
+    /// Reads `count` points from the current file into `self.chunk_buffer` and sets their source file index, if the
+    /// `target_layout` contains it
+    fn read_into_chunk_buffer(
+        &mut self,
+        target_layout: &PointLayout,
+        count: usize,
+    ) -> Result<usize> {
+        let mut chunk_buffer = match self.chunk_buffer.take() {
+            Some(buffer) if buffer.point_layout() == target_layout => buffer,
+            _ => VectorBuffer::new_from_layout(target_layout.clone()),
+        };
+        if chunk_buffer.len() < count {
+            chunk_buffer.resize(count);
+        }
+
+        let points_read = self
+            .current_file_reader()?
+            .read_into(&mut chunk_buffer, count)?;
+        if target_layout.has_attribute(&ATTRIBUTE_SOURCE_FILE_INDEX) {
+            let mut source_file_indices =
+                chunk_buffer.view_attribute_mut::<u32>(&ATTRIBUTE_SOURCE_FILE_INDEX);
+            for index in 0..points_read {
+                source_file_indices.set_at(index, self.current_reader as u32);
+            }
+        }
+        self.chunk_buffer = Some(chunk_buffer);
+        Ok(points_read)
+    }
+}
+
+impl<'a, R: Read + Seek + 'a> PointReader for MultiFileLASReader<'a, R> {
+    fn read_into<'b, 'c, B: BorrowedMutBuffer<'b>>(
+        &mut self,
+        point_buffer: &'c mut B,
+        count: usize,
+    ) -> Result<usize>
+    where
+        'b: 'c,
+    {
+        if point_buffer.len() < count {
+            panic!("point_buffer.len() must be >= count");
+        }
+
+        let target_layout = point_buffer.point_layout().clone();
+        let mut points_read = 0;
+        while points_read < count {
+            if self.remaining_points_in_current_file() == 0 {
+                if self.current_reader + 1 == self.readers.len() {
+                    break;
+                }
+                self.set_current_file(self.current_reader + 1)?;
+                continue;
+            }
+
+            let points_in_file = self
+                .remaining_points_in_current_file()
+                .min(count - points_read);
+            let points_read_from_file =
+                self.read_into_chunk_buffer(&target_layout, points_in_file)?;
+            if points_read_from_file == 0 {
+                break;
+            }
+            let chunk_buffer = self
+                .chunk_buffer
+                .as_ref()
+                .expect("Chunk buffer was just set");
+            // Safe because the chunk buffer has the same `PointLayout` as `point_buffer`
+            unsafe {
+                point_buffer.set_point_range(
+                    points_read..points_read + points_read_from_file,
+                    chunk_buffer.get_point_range_ref(0..points_read_from_file),
+                );
+            }
+            points_read += points_read_from_file;
+            self.current_point_index += points_read_from_file;
+        }
+
+        Ok(points_read)
+    }
+
+    fn get_metadata(&self) -> &dyn Metadata {
+        &self.metadata
+    }
+
+    fn get_default_point_layout(&self) -> &PointLayout {
+        &self.default_point_layout
+    }
+
+    fn skip(&mut self, count: usize) -> Result<usize> {
+        skip_points_by_seeking(self, count)
+    }
+
+    fn point_index(&self) -> Option<usize> {
+        Some(self.current_point_index)
+    }
+
+    fn point_count(&self) -> Option<usize> {
+        Some(self.metadata.point_count())
+    }
+}
+
+impl<'a, R: Read + Seek + 'a> SeekToPoint for MultiFileLASReader<'a, R> {
+    fn seek_point(&mut self, position: SeekFrom) -> Result<usize> {
+        let point_count = self.metadata.point_count();
+        let new_position = match position {
+            SeekFrom::Start(from_start) => from_start as i64,
+            SeekFrom::End(from_end) => point_count as i64 + from_end,
+            SeekFrom::Current(from_current) => self.current_point_index as i64 + from_current,
+        };
+        if new_position < 0 {
+            panic!("MultiFileLASReader::seek_point: It is an error to seek to a point position smaller than zero!");
+        }
+        let clamped_position = std::cmp::min(point_count as i64, new_position) as usize;
+
+        // The last file whose first point is at or before the new position. For positions at the end of the
+        // point cloud, this is the last file
+        let file_index = self
+            .first_point_indices
+            .partition_point(|first_point| *first_point <= clamped_position)
+            - 1;
+        self.current_point_index = clamped_position;
+        self.set_current_file(file_index)?;
+
+        Ok(clamped_position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pasture_core::layout::attributes::{INTENSITY, POSITION_3D};
+    use pasture_core::layout::FieldAlignment;
+    use pasture_core::nalgebra::Vector3;
+
+    use super::*;
+    use crate::las::{
+        get_test_las_path, test_data_bounds, test_data_intensities, test_data_point_count,
+        test_data_positions,
+    };
+
+    #[test]
+    fn test_multi_file_las_reader() -> Result<()> {
+        let paths = [get_test_las_path(1), get_test_las_path(6)];
+        let mut reader = MultiFileLASReader::from_paths(&paths, Default::default())?;
+        let point_count = 2 * test_data_point_count();
+        assert_eq!(2, reader.file_count());
+        assert_eq!(Some(point_count), reader.point_count());
+        assert_eq!(Some(test_data_bounds()), reader.get_metadata().bounds());
+
+        let mut layout = PointLayout::default();
+        layout.add_attribute(POSITION_3D, FieldAlignment::Default);
+        layout.add_attribute(INTENSITY, FieldAlignment::Default);
+        layout.add_attribute(ATTRIBUTE_SOURCE_FILE_INDEX, FieldAlignment::Default);
+        reader.set_default_point_layout(layout);
+
+        let points = reader.read::<VectorBuffer>(point_count)?;
+        assert_eq!(point_count, points.len());
+        let expected_positions = test_data_positions()
+            .into_iter()
+            .chain(test_data_positions())
+            .collect::<Vec<_>>();
+        let positions = points
+            .view_attribute::<Vector3<f64>>(&POSITION_3D)
+            .into_iter()
+            .collect::<Vec<_>>();
+        assert_eq!(expected_positions, positions);
+        let source_file_indices = points
+            .view_attribute::<u32>(&ATTRIBUTE_SOURCE_FILE_INDEX)
+            .into_iter()
+            .collect::<Vec<_>>();
+        let expected_source_file_indices = (0..point_count)
+            .map(|index| (index / test_data_point_count()) as u32)
+            .collect::<Vec<_>>();
+        assert_eq!(expected_source_file_indices, source_file_indices);
+
+        // Seeking across the boundary between the two files
+        let seek_position = test_data_point_count() - 2;
+        assert_eq!(
+            seek_position,
+            reader.seek_point(SeekFrom::Start(seek_position as u64))?
+        );
+        let points = reader.read::<VectorBuffer>(4)?;
+        let intensities = points
+            .view_attribute::<u16>(&INTENSITY)
+            .into_iter()
+            .collect::<Vec<_>>();
+        let expected_intensities = test_data_intensities()[8..]
+            .iter()
+            .chain(test_data_intensities()[..2].iter())
+            .copied()
+            .collect::<Vec<_>>();
+        assert_eq!(expected_intensities, intensities);
+        assert_eq!(Some(test_data_point_count() + 2), reader.point_index());
+
+        assert_eq!(point_count, reader.seek_point(SeekFrom::End(0))?);
+        assert_eq!(0, reader.read::<VectorBuffer>(10)?.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_file_las_reader_without_files() {
+        let paths: [&str; 0] = [];
+        assert!(MultiFileLASReader::from_paths(&paths, Default::default()).is_err());
+    }
+}
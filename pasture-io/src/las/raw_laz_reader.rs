@@ -0,0 +1,1141 @@
+use std::convert::TryInto;
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::Range;
+
+use anyhow::{anyhow, bail, Context, Result};
+use las_rs::Header;
+use las_rs::{raw, Builder, Vlr};
+use laz::laszip::ChunkTable;
+#[cfg(feature = "laz-parallel")]
+use laz::ParLasZipDecompressor;
+use laz::{LasZipDecompressor, LazItemType, LazVlr};
+use pasture_core::containers::{
+    BorrowedMutBuffer, ExternalMemoryBuffer, MakeBufferFromLayout, OwningBuffer, VectorBuffer,
+};
+use pasture_core::math::AABB;
+use pasture_core::{layout::PointLayout, meta::Metadata};
+
+use super::{
+    default_point_layout_for_read_options, get_default_las_converter, map_laz_err,
+    point_count_from_raw_header, point_layout_from_las_metadata, read_candidate_point_ranges,
+    read_evlrs, read_filtered_las_points, validate_las_points, LASMetadata, LASPointFilter,
+    LASReadOptions, LASReaderBase, LASRecordValidator, LAXIndex, ReturnFilter, ValidationOptions,
+    ValidationReport,
+};
+use crate::base::{skip_points_by_seeking, PointReader, ProgressCallback, SeekToPoint};
+
+/// Is the given VLR the LASzip VLR? Function taken from the `las` crate because it is not exported there
+fn is_laszip_vlr(vlr: &Vlr) -> bool {
+    vlr.user_id == LazVlr::USER_ID && vlr.record_id == LazVlr::RECORD_ID
+}
+
+/// Returns the LASzip VLR of the given `header`. Some writers of LAS 1.4 files store it as an EVLR behind the point
+/// data instead of as a regular VLR
+fn find_laszip_vlr(header: &Header) -> Option<&Vlr> {
+    header
+        .vlrs()
+        .iter()
+        .chain(header.evlrs().iter())
+        .find(|vlr| is_laszip_vlr(vlr))
+}
+
+/// Makes sure that the items in the given `laszip_vlr` match the point records described by the given `header`. This
+/// catches files where e.g. the point format indicates wave packets, but the compressed records contain no wave packet
+/// data, which would otherwise result in garbage values
+fn validate_laz_items(laszip_vlr: &LazVlr, header: &Header) -> Result<()> {
+    let size_of_compressed_record = laszip_vlr
+        .items()
+        .iter()
+        .map(|item| item.size() as u64)
+        .sum::<u64>();
+    let point_format = header.point_format();
+    if size_of_compressed_record != point_format.len() as u64 {
+        bail!(
+            "Size of the compressed point records ({} bytes) does not match the size of point records in LAS point format {} ({} bytes)",
+            size_of_compressed_record,
+            point_format,
+            point_format.len()
+        );
+    }
+
+    if point_format.has_waveform {
+        let has_wave_packet_item = laszip_vlr.items().iter().any(|item| {
+            matches!(
+                item.item_type(),
+                LazItemType::WavePacket13 | LazItemType::WavePacket14
+            )
+        });
+        if !has_wave_packet_item {
+            bail!(
+                "LAS point format {} contains wave packets, but the LASzip VLR contains no wave packet item",
+                point_format
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// The LAZ decompressor that a `RawLAZReader` uses. Only the parallel decompressor requires the underlying reader to
+/// be `Send`, so it is stored as a trait object to keep that bound out of `RawLAZReader`
+enum Decompressor<'a, T: Read + Seek + 'a> {
+    Sequential(LasZipDecompressor<'a, T>),
+    #[cfg(feature = "laz-parallel")]
+    Parallel(Box<dyn ParallelLAZDecompressor + Send + 'a>),
+}
+
+impl<'a, T: Read + Seek + 'a> Decompressor<'a, T> {
+    fn decompress_many(&mut self, out: &mut [u8]) -> Result<()> {
+        match self {
+            Decompressor::Sequential(decompressor) => decompressor.decompress_many(out)?,
+            #[cfg(feature = "laz-parallel")]
+            Decompressor::Parallel(decompressor) => decompressor.decompress_many(out)?,
+        }
+        Ok(())
+    }
+
+    fn seek(&mut self, point_index: u64) -> Result<()> {
+        match self {
+            Decompressor::Sequential(decompressor) => decompressor.seek(point_index)?,
+            #[cfg(feature = "laz-parallel")]
+            Decompressor::Parallel(decompressor) => decompressor.seek(point_index)?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "laz-parallel")]
+trait ParallelLAZDecompressor {
+    fn decompress_many(&mut self, out: &mut [u8]) -> Result<()>;
+    fn seek(&mut self, point_index: u64) -> Result<()>;
+}
+
+/// The parallel decompressor of laz-rs, together with the thread pool that it runs on
+#[cfg(feature = "laz-parallel")]
+struct ParallelDecompressor<T: Read + Seek + Send> {
+    decompressor: ParLasZipDecompressor<T>,
+    thread_pool: rayon::ThreadPool,
+}
+
+#[cfg(feature = "laz-parallel")]
+impl<T: Read + Seek + Send> ParallelLAZDecompressor for ParallelDecompressor<T> {
+    fn decompress_many(&mut self, out: &mut [u8]) -> Result<()> {
+        let decompressor = &mut self.decompressor;
+        self.thread_pool
+            .install(|| decompressor.decompress_many(out))
+            .map_err(map_laz_err)
+    }
+
+    fn seek(&mut self, point_index: u64) -> Result<()> {
+        self.decompressor.seek(point_index).map_err(map_laz_err)
+    }
+}
+
+pub struct RawLAZReader<'a, T: Read + Seek + 'a> {
+    reader: Decompressor<'a, T>,
+    metadata: LASMetadata,
+    layout: PointLayout,
+    las_point_records_layout: PointLayout,
+    options: LASReadOptions,
+    current_point_index: usize,
+    size_of_point_in_file: u64,
+    chunk_size: u32,
+    /// Index of the first point of each chunk. Empty if the file has variable-sized chunks and no readable chunk table
+    chunk_starts: Vec<usize>,
+    /// Point ranges of corrupt chunks that were skipped, see [`LASReadOptions::skip_corrupt_laz_chunks`]
+    skipped_point_ranges: Vec<Range<usize>>,
+    progress_callback: Option<ProgressCallback>,
+    filter: LASPointFilter,
+    spatial_index: Option<LAXIndex>,
+    /// Buffers that are reused between calls to `read_into` and `seek_point`. They are allocated on first use
+    decompression_buffer: Vec<u8>,
+    convert_buffer: Option<VectorBuffer>,
+}
+
+/// Chunk size value in the LAZ VLR that indicates that the file uses variable-sized chunks
+const VARIABLE_CHUNK_SIZE: u32 = u32::MAX;
+
+impl<'a, T: Read + Seek + 'a> RawLAZReader<'a, T> {
+    /// Creates a new `RawLAZReader` from the given `read`. See [`RawLASReader::from_read`](super::RawLASReader::from_read)
+    /// for an explanation of the `point_layout_matches_memory_layout` parameter
+    pub fn from_read(read: T, point_layout_matches_memory_layout: bool) -> Result<Self> {
+        Self::from_read_with_options(
+            read,
+            LASReadOptions {
+                point_layout_matches_memory_layout,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Creates a new `RawLAZReader` from the given `read`, using the given `options` to determine the default
+    /// `PointLayout` of the reader. See [`LASReadOptions`] for more information
+    pub fn from_read_with_options(read: T, options: LASReadOptions) -> Result<Self> {
+        Self::from_read_with_decompressor(read, options, |read, laszip_vlr| {
+            let decompressor = LasZipDecompressor::new(read, laszip_vlr).map_err(map_laz_err)?;
+            Ok(Decompressor::Sequential(decompressor))
+        })
+    }
+
+    fn from_read_with_decompressor<F: FnOnce(T, LazVlr) -> Result<Decompressor<'a, T>>>(
+        mut read: T,
+        options: LASReadOptions,
+        make_decompressor: F,
+    ) -> Result<Self> {
+        let raw_header = raw::Header::read_from(&mut read)?;
+        let offset_to_first_point_in_file = raw_header.offset_to_point_data as u64;
+        let size_of_point_in_file = raw_header.point_data_record_length as u64;
+        let point_count = point_count_from_raw_header(&raw_header);
+        let number_of_vlrs = raw_header.number_of_variable_length_records;
+        let evlrs = read_evlrs(&mut read, &raw_header)?;
+        read.seek(SeekFrom::Start(raw_header.header_size as u64))?;
+
+        let mut header_builder = Builder::new(raw_header)?;
+        header_builder.evlrs = evlrs;
+        // Read VLRs. They start right after the header (including its user-defined bytes), but they don't necessarily
+        // end at the start of the point data, see below
+        for _ in 0..number_of_vlrs {
+            let vlr = las_rs::raw::Vlr::read_from(&mut read, false).map(Vlr::new)?;
+            header_builder.vlrs.push(vlr);
+        }
+
+        // Put padding bytes into header (e.g. from leftover VLRs that have been deleted but not removed from the file)
+        let position_after_reading_vlrs = read.stream_position()?;
+        if position_after_reading_vlrs < offset_to_first_point_in_file {
+            read.by_ref()
+                .take(offset_to_first_point_in_file - position_after_reading_vlrs)
+                .read_to_end(&mut header_builder.vlr_padding)?;
+        }
+
+        let header = header_builder.into_header()?;
+
+        let mut metadata: LASMetadata = header
+            .clone()
+            .try_into()
+            .context("Could not parse LAS header")?;
+        metadata.set_point_count(point_count)?;
+        let spatial_index = match LAXIndex::from_las_metadata(&metadata) {
+            Ok(spatial_index) => spatial_index,
+            Err(why) => {
+                metadata.add_warning(format!("Ignoring invalid LAX index: {:#}", why));
+                None
+            }
+        };
+        let point_layout = default_point_layout_for_read_options(&metadata, &options)?;
+        let filter = LASPointFilter {
+            out_of_bounds: options.out_of_bounds_handling,
+            ..Default::default()
+        };
+        let matching_memory_layout = point_layout_from_las_metadata(&metadata, true)?;
+
+        read.seek(SeekFrom::Start(offset_to_first_point_in_file))?;
+
+        let laszip_vlr = match find_laszip_vlr(&header) {
+            None => Err(anyhow!(
+                "RawLAZReader::new: LAZ variable length record not found in file!"
+            )),
+            Some(vlr) => {
+                let laz_record = LazVlr::from_buffer(&vlr.data).map_err(map_laz_err)?;
+                Ok(laz_record)
+            }
+        }?;
+        validate_laz_items(&laszip_vlr, &header)?;
+        let chunk_size = laszip_vlr.chunk_size();
+        let chunk_starts = if chunk_size == VARIABLE_CHUNK_SIZE {
+            // Writers that stream their output might not have written a chunk table, in which case we fall back to
+            // the seeking of laz-rs
+            match ChunkTable::read_from(&mut read, &laszip_vlr) {
+                Ok(chunk_table) => {
+                    let mut first_point_in_chunk = 0;
+                    chunk_table
+                        .iter()
+                        .map(|entry| {
+                            let chunk_start = first_point_in_chunk;
+                            first_point_in_chunk += entry.point_count as usize;
+                            chunk_start
+                        })
+                        .collect()
+                }
+                Err(_) => vec![],
+            }
+        } else {
+            (0..metadata.point_count())
+                .step_by(chunk_size as usize)
+                .collect()
+        };
+        read.seek(SeekFrom::Start(offset_to_first_point_in_file))?;
+        let reader = make_decompressor(read, laszip_vlr)?;
+
+        Ok(Self {
+            reader,
+            metadata,
+            layout: point_layout,
+            las_point_records_layout: matching_memory_layout,
+            options,
+            current_point_index: 0,
+            progress_callback: None,
+            filter,
+            spatial_index,
+            decompression_buffer: vec![],
+            convert_buffer: None,
+            size_of_point_in_file,
+            chunk_size,
+            chunk_starts,
+            skipped_point_ranges: vec![],
+        })
+    }
+
+    pub fn las_metadata(&self) -> &LASMetadata {
+        &self.metadata
+    }
+
+    /// Sets a callback that is invoked once per chunk of points while reading. See [`ProgressCallback`] for the
+    /// parameters that are passed to the callback
+    pub fn set_progress_callback(&mut self, callback: ProgressCallback) {
+        self.progress_callback = Some(callback);
+    }
+
+    /// Only read points with one of the given `classifications`. Points with other classifications are skipped
+    /// while reading, so `read_into` returns only the number of accepted points. For the point formats 0 to 5, only
+    /// the lower five bits of the classification byte are compared. Passing an empty slice accepts all points
+    pub fn set_classification_filter(&mut self, classifications: &[u8]) {
+        self.filter.classifications = classifications.to_vec();
+    }
+
+    /// Only read points whose return number matches the given `filter`. See [`ReturnFilter`] for the available
+    /// options. Like the classification filter, this affects the number of points that `read_into` returns
+    pub fn set_return_filter(&mut self, filter: ReturnFilter) {
+        self.filter.returns = filter;
+    }
+
+    /// Only read points whose GPS time lies within `min..=max`. Like the classification filter, this affects the
+    /// number of points that `read_into` returns
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the point format of the file has no GPS time, or if `min` is larger than `max`
+    pub fn set_gps_time_range(&mut self, min: f64, max: f64) -> Result<()> {
+        if !self.metadata.point_format().has_gps_time {
+            bail!(
+                "LAS point format {} has no GPS time",
+                self.metadata.point_format()
+            );
+        }
+        if min > max {
+            bail!("Invalid GPS time range {}..={}", min, max);
+        }
+        self.filter.gps_time_range = Some((min, max));
+        Ok(())
+    }
+
+    /// Returns the spatial index of this reader, if there is one. An index that is embedded into the EVLRs of the
+    /// LAZ file is loaded automatically
+    pub fn spatial_index(&self) -> Option<&LAXIndex> {
+        self.spatial_index.as_ref()
+    }
+
+    /// Sets the spatial index that [`Self::read_bounds`] uses. Passing `None` removes the current index
+    pub fn set_spatial_index(&mut self, spatial_index: Option<LAXIndex>) {
+        self.spatial_index = spatial_index;
+    }
+
+    /// Appends all points whose world-space position lies within `bounds` to `point_buffer`. See
+    /// [`RawLASReader::read_bounds`](super::RawLASReader::read_bounds) for more information. Since seeking in LAZ
+    /// files requires decompressing up to a whole chunk, the speedup of a spatial index is smaller than for LAS files
+    pub fn read_bounds<'b, B: OwningBuffer<'b>>(
+        &mut self,
+        bounds: &AABB<f64>,
+        point_buffer: &mut B,
+    ) -> Result<usize> {
+        let point_count = self.metadata.point_count();
+        let candidate_ranges = match &self.spatial_index {
+            Some(spatial_index) => spatial_index.candidate_point_ranges(bounds, point_count),
+            None => vec![0..point_count],
+        };
+        read_candidate_point_ranges(
+            self,
+            &candidate_ranges,
+            point_buffer,
+            |reader, points, count| reader.read_into_filtered(points, count, bounds),
+        )
+    }
+
+    /// Checks all point records of the file against the LAS header. See
+    /// [`RawLASReader::validate`](super::RawLASReader::validate) for more information
+    pub fn validate(&mut self, options: &ValidationOptions) -> Result<ValidationReport> {
+        let validator = LASRecordValidator::new(&self.metadata, options)?;
+        let raw_layout = self.las_point_records_layout.clone();
+        validate_las_points(self, validator, &raw_layout, |reader, points, count| {
+            reader.read_into_default_layout(points, count)
+        })
+    }
+
+    /// Reads the binary record of the point at `point_index`, regardless of the filters of this reader. Afterwards, the
+    /// reader is at the same point as before
+    pub(crate) fn read_point_record(&mut self, point_index: usize) -> Result<Vec<u8>> {
+        let point_count = self.metadata.point_count();
+        if point_index >= point_count {
+            bail!(
+                "Point index {} is out of bounds for a file with {} points",
+                point_index,
+                point_count
+            );
+        }
+        let previous_point_index = self.current_point_index;
+        self.seek_point(SeekFrom::Start(point_index as u64))?;
+        let mut record = vec![0; self.las_point_records_layout.size_of_point_entry() as usize];
+        let layout = self.las_point_records_layout.clone();
+        let result =
+            self.read_into_default_layout(&mut ExternalMemoryBuffer::new(&mut record, layout), 1);
+        self.seek_point(SeekFrom::Start(previous_point_index as u64))?;
+        result?;
+        Ok(record)
+    }
+
+    fn report_progress(&mut self, points_read: usize) {
+        if let Some(callback) = self.progress_callback.as_mut() {
+            callback(points_read, self.metadata.point_count());
+        }
+    }
+
+    /// Returns the number of points per chunk of the LAZ file, or `None` if the file uses variable-sized chunks.
+    /// Reading in multiples of the chunk size, starting at a chunk boundary, gives the best throughput
+    pub fn chunk_size(&self) -> Option<usize> {
+        if self.chunk_size == VARIABLE_CHUNK_SIZE {
+            None
+        } else {
+            Some(self.chunk_size as usize)
+        }
+    }
+
+    /// Returns the point ranges of all corrupt chunks that were skipped so far, in the order in which they were
+    /// encountered. Chunks are only skipped if [`LASReadOptions::skip_corrupt_laz_chunks`] is enabled, otherwise
+    /// reading fails on the first corrupt chunk
+    pub fn skipped_point_ranges(&self) -> &[Range<usize>] {
+        &self.skipped_point_ranges
+    }
+
+    /// Moves the decompressor to the point at `point_index`. Decompression can only start at the first point of a
+    /// chunk, so we seek to the start of the chunk that contains `point_index` and then decompress and discard the
+    /// points up to `point_index`. When seeking forward within the current chunk, the decompressor just skips the
+    /// points in between
+    fn seek_to_point_index(&mut self, point_index: usize) -> Result<()> {
+        if self.chunk_starts.is_empty() {
+            self.reader.seek(point_index as u64)?;
+            return Ok(());
+        }
+        // Nothing can be read at the end of the file, so there is no need to decompress anything
+        if point_index >= self.metadata.point_count() {
+            return Ok(());
+        }
+
+        let chunk_index = self
+            .chunk_starts
+            .partition_point(|chunk_start| *chunk_start <= point_index)
+            - 1;
+        let chunk_start = self.chunk_starts[chunk_index];
+        let mut points_to_skip =
+            if self.current_point_index >= chunk_start && self.current_point_index <= point_index {
+                point_index - self.current_point_index
+            } else {
+                self.reader.seek(chunk_start as u64)?;
+                point_index - chunk_start
+            };
+
+        const SKIP_BUFFER_MEM_SIZE: usize = 1 << 20;
+        let max_points_per_skip =
+            (SKIP_BUFFER_MEM_SIZE / self.size_of_point_in_file as usize).max(1);
+        let size_of_skip_buffer =
+            max_points_per_skip.min(points_to_skip) * self.size_of_point_in_file as usize;
+        if self.decompression_buffer.len() < size_of_skip_buffer {
+            self.decompression_buffer.resize(size_of_skip_buffer, 0);
+        }
+        while points_to_skip > 0 {
+            let points_in_this_step = points_to_skip.min(max_points_per_skip);
+            self.reader
+                .decompress_many(
+                    &mut self.decompression_buffer
+                        [..points_in_this_step * self.size_of_point_in_file as usize],
+                )
+                .context("Failed to decompress points while seeking")?;
+            points_to_skip -= points_in_this_step;
+        }
+
+        Ok(())
+    }
+
+    fn read_into_default_layout<'b, 'c, B: BorrowedMutBuffer<'b>>(
+        &mut self,
+        point_buffer: &'c mut B,
+        count: usize,
+    ) -> Result<usize>
+    where
+        'b: 'c,
+    {
+        let num_points_to_read = usize::min(count, self.remaining_points());
+        if num_points_to_read == 0 {
+            return Ok(0);
+        }
+        if self.options.skip_corrupt_laz_chunks && !self.chunk_starts.is_empty() {
+            return self.read_into_default_layout_skipping_corrupt_chunks(
+                point_buffer,
+                num_points_to_read,
+            );
+        }
+
+        if let Some(interleaved_buffer) = point_buffer.as_interleaved_mut() {
+            let new_point_data = interleaved_buffer.get_point_range_mut(0..num_points_to_read);
+            self.reader
+                .decompress_many(new_point_data)
+                .context("Failed to read point records")?;
+            self.report_progress(self.current_point_index + num_points_to_read);
+        } else {
+            // Read point data in chunks of ~1MiB size to prevent memory problems for very large files if we were
+            // to read all data in a single chunk
+            const CHUNK_MEM_SIZE: usize = 1 << 20;
+            let num_points_per_chunk = CHUNK_MEM_SIZE / self.size_of_point_in_file as usize;
+            let num_chunks = (num_points_to_read + num_points_per_chunk - 1) / num_points_per_chunk;
+            let size_of_chunk = num_points_per_chunk * self.size_of_point_in_file as usize;
+            if self.decompression_buffer.len() < size_of_chunk {
+                self.decompression_buffer.resize(size_of_chunk, 0);
+            }
+            for chunk_idx in 0..num_chunks {
+                let bytes_in_chunk = if chunk_idx == num_chunks - 1 {
+                    (num_points_to_read - (chunk_idx * num_points_per_chunk))
+                        * self.size_of_point_in_file as usize
+                } else {
+                    size_of_chunk
+                };
+                let chunk_bytes = &mut self.decompression_buffer[..bytes_in_chunk];
+                self.reader
+                    .decompress_many(chunk_bytes)
+                    .context("Failed to read chunk of points")?;
+                let first_point_in_chunk = chunk_idx * num_points_per_chunk;
+                let chunk_end = ((chunk_idx + 1) * num_points_per_chunk).min(num_points_to_read);
+                // Safe because this function (`read_into_default_layout`) is only called if the buffer has the exact
+                // binary memory layout of the LAS file
+                unsafe {
+                    point_buffer.set_point_range(first_point_in_chunk..chunk_end, chunk_bytes);
+                }
+                self.report_progress(self.current_point_index + chunk_end);
+            }
+        }
+
+        self.current_point_index += num_points_to_read;
+
+        Ok(num_points_to_read)
+    }
+
+    /// Like `read_into_default_layout`, but decompresses at most one LAZ chunk at a time. If a chunk fails to decompress,
+    /// its remaining points are skipped and decompression continues at the next chunk. Advances the reader by
+    /// `num_points_to_read` points and returns the number of points that were written into `point_buffer`
+    fn read_into_default_layout_skipping_corrupt_chunks<'b, 'c, B: BorrowedMutBuffer<'b>>(
+        &mut self,
+        point_buffer: &'c mut B,
+        num_points_to_read: usize,
+    ) -> Result<usize>
+    where
+        'b: 'c,
+    {
+        let size_of_point = self.size_of_point_in_file as usize;
+        let point_count = self.metadata.point_count();
+        let end_of_read = self.current_point_index + num_points_to_read;
+        let mut points_written = 0;
+        while self.current_point_index < end_of_read {
+            let chunk_index = self
+                .chunk_starts
+                .partition_point(|chunk_start| *chunk_start <= self.current_point_index)
+                - 1;
+            let end_of_chunk = self
+                .chunk_starts
+                .get(chunk_index + 1)
+                .copied()
+                .unwrap_or(point_count);
+            let points_in_step = end_of_chunk.min(end_of_read) - self.current_point_index;
+            let bytes_in_step = points_in_step * size_of_point;
+            if self.decompression_buffer.len() < bytes_in_step {
+                self.decompression_buffer.resize(bytes_in_step, 0);
+            }
+
+            match self
+                .reader
+                .decompress_many(&mut self.decompression_buffer[..bytes_in_step])
+            {
+                Ok(_) => {
+                    // Safe because this function is only called if the buffer has the exact binary memory layout of
+                    // the LAS file
+                    unsafe {
+                        point_buffer.set_point_range(
+                            points_written..points_written + points_in_step,
+                            &self.decompression_buffer[..bytes_in_step],
+                        );
+                    }
+                    points_written += points_in_step;
+                    self.current_point_index += points_in_step;
+                }
+                Err(why) => {
+                    let skipped_range = self.current_point_index..end_of_chunk;
+                    self.metadata.add_warning(format!(
+                        "Skipped points {}..{} of corrupt LAZ chunk {}: {:#}",
+                        skipped_range.start, skipped_range.end, chunk_index, why
+                    ));
+                    self.skipped_point_ranges.push(skipped_range);
+                    if end_of_chunk < point_count {
+                        self.reader
+                            .seek(end_of_chunk as u64)
+                            .context("Failed to seek to the chunk after a corrupt chunk")?;
+                    }
+                    self.current_point_index = end_of_chunk;
+                }
+            }
+            self.report_progress(self.current_point_index);
+        }
+
+        Ok(points_written)
+    }
+
+    /// Reads `count` points like [`PointReader::read_into`], but only writes the points whose world-space position
+    /// lies within `bounds` into `point_buffer`. The bounds check happens on the binary point records, so rejected
+    /// points are never converted. Returns the number of points that were written into `point_buffer`. The reader
+    /// always advances by `count` points (or the number of remaining points, if that is smaller), regardless of how
+    /// many points matched
+    pub fn read_into_filtered<'b, 'c, B: BorrowedMutBuffer<'b>>(
+        &mut self,
+        point_buffer: &'c mut B,
+        count: usize,
+        bounds: &AABB<f64>,
+    ) -> Result<usize>
+    where
+        'b: 'c,
+    {
+        let filter = LASPointFilter {
+            bounds: Some(*bounds),
+            ..self.filter.clone()
+        };
+        self.read_into_with_filter(point_buffer, count, &filter)
+    }
+
+    fn read_into_with_filter<'b, 'c, B: BorrowedMutBuffer<'b>>(
+        &mut self,
+        point_buffer: &'c mut B,
+        count: usize,
+        filter: &LASPointFilter,
+    ) -> Result<usize>
+    where
+        'b: 'c,
+    {
+        if point_buffer.len() < count {
+            panic!("point_buffer.len() must be >= count");
+        }
+
+        let predicate = filter.record_predicate(&self.metadata)?;
+        let modifier = filter.record_modifier(&self.metadata)?;
+        let raw_layout = self.las_point_records_layout.clone();
+        let target_layout = point_buffer.point_layout().clone();
+        let converter = if target_layout != raw_layout {
+            Some(
+                get_default_las_converter(
+                    &raw_layout,
+                    &target_layout,
+                    &self.metadata,
+                    &self.options,
+                )
+                .context("Unsupported conversion")?,
+            )
+        } else {
+            None
+        };
+        read_filtered_las_points(
+            point_buffer,
+            count,
+            &raw_layout,
+            converter.as_ref(),
+            predicate,
+            modifier,
+            |raw_points, count| self.read_into_default_layout(raw_points, count),
+        )
+    }
+
+    fn read_into_custom_layout<'b, 'c, B: BorrowedMutBuffer<'b>>(
+        &mut self,
+        point_buffer: &'c mut B,
+        count: usize,
+    ) -> Result<usize>
+    where
+        'b: 'c,
+    {
+        let num_points_to_read = usize::min(count, self.remaining_points());
+        if num_points_to_read == 0 {
+            return Ok(0);
+        }
+
+        let mut convert_buffer = self.convert_buffer.take().unwrap_or_else(|| {
+            VectorBuffer::new_from_layout(self.las_point_records_layout.clone())
+        });
+        if convert_buffer.len() < num_points_to_read {
+            convert_buffer.resize(num_points_to_read);
+        }
+        // Fewer points than requested are read if corrupt chunks are skipped
+        let num_points_read =
+            self.read_into_default_layout(&mut convert_buffer, num_points_to_read)?;
+
+        let target_layout = point_buffer.point_layout().clone();
+        let converter = get_default_las_converter(
+            &self.las_point_records_layout,
+            &target_layout,
+            &self.metadata,
+            &self.options,
+        )
+        .context("Unsupported conversion")?;
+        converter.convert_into_range(
+            &convert_buffer,
+            0..num_points_read,
+            point_buffer,
+            0..num_points_read,
+        );
+        self.convert_buffer = Some(convert_buffer);
+
+        Ok(num_points_read)
+    }
+}
+
+/// Parallel decompression requires the underlying reader to be `Send`, since it is accessed from the threads of a
+/// thread pool
+#[cfg(feature = "laz-parallel")]
+impl<'a, T: Read + Seek + Send + 'a> RawLAZReader<'a, T> {
+    /// Creates a new `RawLAZReader` from the given `read` that decompresses the point records using `num_threads`
+    /// threads. Decompression happens in parallel on the chunks of the LAZ file, the points are still returned in
+    /// the order in which they are stored in the file. If `num_threads` is zero, the number of threads is determined
+    /// automatically based on the number of available CPU cores. See [`RawLASReader::from_read`](super::RawLASReader::from_read)
+    /// for an explanation of the `point_layout_matches_memory_layout` parameter
+    pub fn from_read_parallel(
+        read: T,
+        num_threads: usize,
+        point_layout_matches_memory_layout: bool,
+    ) -> Result<Self> {
+        Self::from_read_parallel_with_options(
+            read,
+            num_threads,
+            LASReadOptions {
+                point_layout_matches_memory_layout,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Like [`from_read_parallel`](Self::from_read_parallel), but uses the given `options` to determine the default
+    /// `PointLayout` of the reader. See [`LASReadOptions`] for more information
+    pub fn from_read_parallel_with_options(
+        read: T,
+        num_threads: usize,
+        options: LASReadOptions,
+    ) -> Result<Self> {
+        let thread_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .context("Could not create thread pool for LAZ decompression")?;
+        Self::from_read_with_decompressor(read, options, move |read, laszip_vlr| {
+            let decompressor = ParLasZipDecompressor::new(read, laszip_vlr).map_err(map_laz_err)?;
+            Ok(Decompressor::Parallel(Box::new(ParallelDecompressor {
+                decompressor,
+                thread_pool,
+            })))
+        })
+    }
+}
+
+impl<'a, T: Read + Seek + 'a> LASReaderBase for RawLAZReader<'a, T> {
+    fn remaining_points(&self) -> usize {
+        self.metadata.point_count() - self.current_point_index
+    }
+
+    fn header(&self) -> &Header {
+        self.metadata.raw_las_header().unwrap()
+    }
+}
+
+impl<'a, T: Read + Seek + 'a> PointReader for RawLAZReader<'a, T> {
+    fn read_into<'b, 'c, B: BorrowedMutBuffer<'b>>(
+        &mut self,
+        point_buffer: &'c mut B,
+        count: usize,
+    ) -> Result<usize>
+    where
+        'b: 'c,
+    {
+        if point_buffer.len() < count {
+            panic!("point_buffer.len() must be >= count");
+        }
+
+        if !self.filter.is_empty() {
+            let filter = self.filter.clone();
+            self.read_into_with_filter(point_buffer, count, &filter)
+        } else if *point_buffer.point_layout() != self.las_point_records_layout {
+            self.read_into_custom_layout(point_buffer, count)
+        } else {
+            self.read_into_default_layout(point_buffer, count)
+        }
+    }
+
+    fn get_metadata(&self) -> &dyn Metadata {
+        &self.metadata
+    }
+
+    fn get_default_point_layout(&self) -> &PointLayout {
+        &self.layout
+    }
+
+    fn skip(&mut self, count: usize) -> Result<usize> {
+        skip_points_by_seeking(self, count)
+    }
+
+    fn point_index(&self) -> Option<usize> {
+        Some(self.current_point_index)
+    }
+
+    fn point_count(&self) -> Option<usize> {
+        Some(self.metadata.point_count())
+    }
+}
+
+impl<'a, T: Read + Seek + 'a> SeekToPoint for RawLAZReader<'a, T> {
+    fn seek_point(&mut self, position: SeekFrom) -> Result<usize> {
+        let new_position = match position {
+            SeekFrom::Start(from_start) => from_start as i64,
+            SeekFrom::End(from_end) => self.metadata.point_count() as i64 + from_end,
+            SeekFrom::Current(from_current) => self.current_point_index as i64 + from_current,
+        };
+        if new_position < 0 {
+            panic!("RawLAZReader::seek_point: It is an error to seek to a point position smaller than zero!");
+        }
+        let clamped_position =
+            std::cmp::min(self.metadata.point_count() as i64, new_position) as usize;
+
+        if self.current_point_index != clamped_position {
+            self.seek_to_point_index(clamped_position)?;
+            self.current_point_index = clamped_position;
+        }
+
+        Ok(self.current_point_index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::rc::Rc;
+
+    use las_rs::point::Format;
+    use las_rs::raw::header::Evlr;
+    use las_rs::Builder as HeaderBuilder;
+    use pasture_core::layout::attributes::INTENSITY;
+    use pasture_core::nalgebra::Vector3;
+    use pasture_derive::PointType;
+
+    use super::*;
+    use crate::base::PointWriter;
+    use crate::las::{
+        compare_to_reference_data, get_test_laz_path, LASReader, LasPointFormat1, RawLAZWriter,
+    };
+
+    /// A reader that is not `Send`, similar to readers that wrap JS objects on wasm32
+    struct NonSendReader {
+        cursor: Cursor<Vec<u8>>,
+        _not_send: Rc<()>,
+    }
+
+    impl Read for NonSendReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.cursor.read(buf)
+        }
+    }
+
+    impl Seek for NonSendReader {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            self.cursor.seek(pos)
+        }
+    }
+
+    #[test]
+    fn test_raw_laz_reader_with_non_send_reader() -> Result<()> {
+        let reader = NonSendReader {
+            cursor: Cursor::new(std::fs::read(get_test_laz_path(0))?),
+            _not_send: Rc::new(()),
+        };
+        let mut laz_reader = RawLAZReader::from_read(reader, false)?;
+        let points = laz_reader.read::<VectorBuffer>(10)?;
+        compare_to_reference_data(&points, Format::new(0)?);
+
+        Ok(())
+    }
+
+    #[repr(C, packed)]
+    #[derive(PointType, Debug, Copy, Clone, bytemuck::AnyBitPattern, bytemuck::NoUninit)]
+    struct PositionAndIntensity {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+        #[pasture(BUILTIN_INTENSITY)]
+        pub intensity: u16,
+    }
+
+    /// A reader that fails on all reads that start within `failing_bytes`, similar to a file with unreadable sectors.
+    /// Reads that start before `failing_bytes` are shortened so that they end in front of it
+    struct FailingReader {
+        cursor: Cursor<Vec<u8>>,
+        failing_bytes: Range<u64>,
+    }
+
+    impl Read for FailingReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let position = self.cursor.position();
+            if self.failing_bytes.contains(&position) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    "Unreadable bytes",
+                ));
+            }
+            let max_bytes = if position < self.failing_bytes.start {
+                (self.failing_bytes.start - position) as usize
+            } else {
+                buf.len()
+            };
+            let max_bytes = max_bytes.min(buf.len());
+            self.cursor.read(&mut buf[..max_bytes])
+        }
+    }
+
+    impl Seek for FailingReader {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            self.cursor.seek(pos)
+        }
+    }
+
+    /// Writes a LAZ file with enough points for multiple chunks. The intensity of each point is its index
+    fn make_multi_chunk_laz_file(format: u8, point_count: usize) -> Result<Vec<u8>> {
+        let points = (0..point_count)
+            .map(|index| PositionAndIntensity {
+                position: Vector3::new(index as f64, 0.0, 0.0),
+                intensity: index as u16,
+            })
+            .collect::<VectorBuffer>();
+        let mut header_builder = HeaderBuilder::from((1, 4));
+        header_builder.point_format = Format::new(format)?;
+        let mut writer = RawLAZWriter::from_write_and_header(
+            Cursor::new(Vec::new()),
+            header_builder.into_header()?,
+        )?;
+        writer.write(&points)?;
+        Ok(writer.into_inner()?.into_inner())
+    }
+
+    #[test]
+    fn test_raw_laz_reader_skip_corrupt_chunks() -> Result<()> {
+        const POINT_COUNT: usize = 120_000;
+        let laz_data = make_multi_chunk_laz_file(1, POINT_COUNT)?;
+
+        // Make the middle of the second chunk unreadable. The chunks start after the 8-byte offset to the chunk table
+        let laz_vlr = RawLAZReader::from_read(Cursor::new(laz_data.clone()), false)?
+            .las_metadata()
+            .vlrs()
+            .iter()
+            .find(|vlr| is_laszip_vlr(vlr))
+            .map(|vlr| LazVlr::from_buffer(&vlr.data))
+            .expect("LAZ file has a LASzip VLR")
+            .map_err(map_laz_err)?;
+        let raw_header = raw::Header::read_from(Cursor::new(&laz_data))?;
+        let mut cursor = Cursor::new(&laz_data);
+        cursor.seek(SeekFrom::Start(raw_header.offset_to_point_data as u64))?;
+        let chunk_table = ChunkTable::read_from(&mut cursor, &laz_vlr).map_err(map_laz_err)?;
+        let first_chunk = chunk_table.iter().next().expect("File has chunks");
+        let chunk_size = first_chunk.point_count as usize;
+        assert!(chunk_size * 2 < POINT_COUNT);
+        let start_of_second_chunk =
+            raw_header.offset_to_point_data as u64 + 8 + first_chunk.byte_count;
+        let failing_bytes = start_of_second_chunk + 100..start_of_second_chunk + 200;
+
+        // By default, reading fails on the corrupt chunk
+        let mut reader = RawLAZReader::from_read(
+            FailingReader {
+                cursor: Cursor::new(laz_data.clone()),
+                failing_bytes: failing_bytes.clone(),
+            },
+            false,
+        )?;
+        assert!(reader
+            .read_attribute::<u16>(&INTENSITY, POINT_COUNT)
+            .is_err());
+
+        let mut reader = RawLAZReader::from_read_with_options(
+            FailingReader {
+                cursor: Cursor::new(laz_data.clone()),
+                failing_bytes: failing_bytes.clone(),
+            },
+            LASReadOptions {
+                skip_corrupt_laz_chunks: true,
+                ..Default::default()
+            },
+        )?;
+        let intensities = reader.read_attribute::<u16>(&INTENSITY, POINT_COUNT)?;
+        let expected_intensities = (0..chunk_size)
+            .chain(2 * chunk_size..POINT_COUNT)
+            .map(|index| index as u16)
+            .collect::<Vec<_>>();
+        assert_eq!(expected_intensities, intensities);
+        assert_eq!(&[chunk_size..2 * chunk_size], reader.skipped_point_ranges());
+        assert_eq!(1, reader.las_metadata().warnings().len());
+        assert_eq!(0, reader.remaining_points());
+
+        // Iterating over the points of a `LASReader` continues after reads that return no points because they lie
+        // within the skipped chunk
+        let mut reader = LASReader::from_read_with_options(
+            FailingReader {
+                cursor: Cursor::new(laz_data),
+                failing_bytes,
+            },
+            true,
+            LASReadOptions {
+                skip_corrupt_laz_chunks: true,
+                ..Default::default()
+            },
+        )?;
+        let intensities = reader
+            .points::<LasPointFormat1>()
+            .map(|point| point.map(|point| point.intensity))
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(expected_intensities, intensities);
+        assert_eq!(&[chunk_size..2 * chunk_size], reader.skipped_point_ranges());
+
+        Ok(())
+    }
+
+    /// Moves the LASzip VLR of the given LAS 1.4 `laz_data` into an EVLR behind the point data, like some LAZ writers
+    /// do. Since the VLR is removed in front of the point data, the absolute offset to the chunk table is adjusted
+    fn move_laszip_vlr_into_evlr(laz_data: &[u8]) -> Result<Vec<u8>> {
+        let mut raw_header = raw::Header::read_from(Cursor::new(laz_data))?;
+        let mut cursor = Cursor::new(laz_data);
+        cursor.seek(SeekFrom::Start(raw_header.header_size as u64))?;
+        let mut laszip_vlr = None;
+        for _ in 0..raw_header.number_of_variable_length_records {
+            let start_of_vlr = cursor.position() as usize;
+            let vlr = Vlr::new(raw::Vlr::read_from(&mut cursor, false)?);
+            if is_laszip_vlr(&vlr) {
+                laszip_vlr = Some((start_of_vlr..cursor.position() as usize, vlr));
+            }
+        }
+        let (vlr_range, vlr) = laszip_vlr.expect("LAZ file has a LASzip VLR");
+        let removed_bytes = vlr_range.len();
+
+        let mut data = laz_data[..vlr_range.start].to_vec();
+        data.extend_from_slice(&laz_data[vlr_range.end..]);
+        let start_of_first_evlr = data.len() as u64;
+        vlr.into_raw(true)?.write_to(&mut data)?;
+
+        raw_header.offset_to_point_data -= removed_bytes as u32;
+        raw_header.number_of_variable_length_records -= 1;
+        raw_header.evlr = Some(Evlr {
+            start_of_first_evlr,
+            number_of_evlrs: 1,
+        });
+        let header_size = raw_header.header_size as usize;
+        raw_header.write_to(&mut Cursor::new(&mut data[..header_size]))?;
+
+        let offset_to_chunk_table = raw_header.offset_to_point_data as usize;
+        let chunk_table_position = i64::from_le_bytes(
+            data[offset_to_chunk_table..offset_to_chunk_table + 8]
+                .try_into()
+                .unwrap(),
+        );
+        assert_ne!(-1, chunk_table_position);
+        data[offset_to_chunk_table..offset_to_chunk_table + 8]
+            .copy_from_slice(&(chunk_table_position - removed_bytes as i64).to_le_bytes());
+        Ok(data)
+    }
+
+    #[test]
+    fn test_raw_laz_reader_with_laszip_vlr_in_evlr() -> Result<()> {
+        const POINT_COUNT: usize = 60_000;
+        let laz_data = move_laszip_vlr_into_evlr(&make_multi_chunk_laz_file(6, POINT_COUNT)?)?;
+        let mut reader = RawLAZReader::from_read(Cursor::new(laz_data), false)?;
+        assert!(reader
+            .las_metadata()
+            .vlrs()
+            .iter()
+            .all(|vlr| !is_laszip_vlr(vlr)));
+        assert_eq!(POINT_COUNT, reader.remaining_points());
+
+        let expected_intensities = (0..POINT_COUNT)
+            .map(|index| index as u16)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            expected_intensities,
+            reader.read_attribute::<u16>(&INTENSITY, POINT_COUNT)?
+        );
+
+        // Seek into the second chunk, which requires the chunk table
+        let chunk_size = reader
+            .chunk_size()
+            .expect("LAZ writer uses fixed-size chunks");
+        let index_in_second_chunk = chunk_size + 42;
+        reader.seek_point(SeekFrom::Start(index_in_second_chunk as u64))?;
+        assert_eq!(
+            &expected_intensities[index_in_second_chunk..index_in_second_chunk + 10],
+            reader.read_attribute::<u16>(&INTENSITY, 10)?.as_slice()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_raw_laz_reader_seek_with_chunk_table() -> Result<()> {
+        const POINT_COUNT: usize = 120_000;
+        for format in [1, 6] {
+            let laz_data = make_multi_chunk_laz_file(format, POINT_COUNT)?;
+            let mut reader = RawLAZReader::from_read(Cursor::new(laz_data), false)?;
+            let chunk_size = reader
+                .chunk_size()
+                .expect("LAZ writer uses fixed-size chunks");
+            assert!(chunk_size * 2 < POINT_COUNT);
+
+            let expected_intensities = |start: usize| {
+                (start..start + 10)
+                    .map(|index| index as u16)
+                    .collect::<Vec<_>>()
+            };
+
+            // Seek into the last chunk
+            let index_in_last_chunk = 2 * chunk_size + 7;
+            assert_eq!(
+                index_in_last_chunk,
+                reader.seek_point(SeekFrom::Start(index_in_last_chunk as u64))?
+            );
+            assert_eq!(
+                expected_intensities(index_in_last_chunk),
+                reader.read_attribute::<u16>(&INTENSITY, 10)?
+            );
+
+            // Seek backwards into the middle chunk, then forwards within that chunk
+            let index_in_middle_chunk = chunk_size + 100;
+            reader.seek_point(SeekFrom::Start(index_in_middle_chunk as u64))?;
+            assert_eq!(
+                expected_intensities(index_in_middle_chunk),
+                reader.read_attribute::<u16>(&INTENSITY, 10)?
+            );
+            reader.seek_point(SeekFrom::Current(1000))?;
+            assert_eq!(
+                expected_intensities(index_in_middle_chunk + 1010),
+                reader.read_attribute::<u16>(&INTENSITY, 10)?
+            );
+
+            // Seeking to the end and back to the start
+            assert_eq!(POINT_COUNT, reader.seek_point(SeekFrom::End(0))?);
+            assert_eq!(0, reader.read_attribute::<u16>(&INTENSITY, 10)?.len());
+            reader.seek_point(SeekFrom::Start(0))?;
+            assert_eq!(
+                expected_intensities(0),
+                reader.read_attribute::<u16>(&INTENSITY, 10)?
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,1265 @@
+use std::{
+    collections::HashMap,
+    convert::TryInto,
+    io::{Cursor, Seek, SeekFrom, Write},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use byteorder::{LittleEndian, NativeEndian, ReadBytesExt, WriteBytesExt};
+use las_rs::{point::Format, Builder, Vlr};
+#[cfg(feature = "laz-parallel")]
+use laz::ParLasZipCompressor;
+use laz::{LasZipCompressor, LazItemRecordBuilder, LazVlr, LazVlrBuilder};
+use pasture_core::{containers::BorrowedBuffer, layout::PointLayout, nalgebra::Vector3};
+
+use crate::base::PointWriter;
+
+use super::{
+    drops_legacy_overlap_flag, finalize_las_header, fit_extended_scan_angle,
+    fit_legacy_classification_byte, fit_scan_angle_rank, gather_point_range,
+    get_classification_flags_reader, get_classification_reader, get_edge_of_flight_line_reader,
+    get_extended_scan_angle_rank_reader, get_gps_time_reader, get_intensity_reader, get_nir_reader,
+    get_number_of_returns_reader, get_overlap_reader, get_point_source_id_reader,
+    get_position_reader, get_return_number_reader, get_return_point_waveform_location_reader,
+    get_scan_angle_rank_reader, get_scan_direction_flag_reader, get_scanner_channel_reader,
+    get_upscaled_color_reader, get_user_data_reader, get_wave_packet_descriptor_index_reader,
+    get_waveform_data_offset_reader, get_waveform_packet_size_reader,
+    get_waveform_parameters_reader, invalid_point_context, las_metadata_of_written_file,
+    map_laz_err, point_layout_from_las_metadata, raw_evlr_for_las_header, resize_chunk_buffers,
+    set_wkt_bit_in_las_header, update_evlrs_in_las_header, update_point_counts_in_las_header,
+    validate_source_layout_for_las, validate_write_chunk_size, write_las_bit_attributes,
+    write_position_as_las_position, BitAttributeOverflowHandling, BitAttributes,
+    BitAttributesExtended, BitAttributesRegular, ChunkBounds, ColorUpscaling, ExtraBytesEntry,
+    ExtraBytesWriter, LASMetadata, MissingAttributeDefaults, OverlapFlagHandling,
+    DEFAULT_LAZ_CHUNK_SIZE, DEFAULT_WRITE_CHUNK_SIZE, LAS_WRITER_REQUIRES_SEEK,
+};
+
+/// Returns an error if LAZ files can't be compressed in chunks of `chunk_size` points. laz-rs supports fixed chunk
+/// sizes from 1 to `u32::MAX - 1` points, as `u32::MAX` marks files with chunks of variable size
+pub(crate) fn validate_laz_chunk_size(chunk_size: u32) -> Result<()> {
+    if chunk_size == 0 || chunk_size == u32::MAX {
+        bail!(
+            "Invalid LAZ chunk size {}, the chunk size must be between 1 and {} points",
+            chunk_size,
+            u32::MAX - 1
+        );
+    }
+    Ok(())
+}
+
+/// The LAZ compressor that a `RawLAZWriter` uses
+enum Compressor<T: Write + Seek + Send + 'static> {
+    Sequential(LasZipCompressor<'static, T>),
+    #[cfg(feature = "laz-parallel")]
+    Parallel(ParallelCompressor<T>),
+}
+
+impl<T: Write + Seek + Send + 'static> Compressor<T> {
+    fn compress_many(&mut self, points: &[u8]) -> Result<()> {
+        match self {
+            Compressor::Sequential(compressor) => compressor.compress_many(points)?,
+            #[cfg(feature = "laz-parallel")]
+            Compressor::Parallel(compressor) => compressor.compress_many(points)?,
+        }
+        Ok(())
+    }
+
+    fn done(&mut self) -> Result<()> {
+        match self {
+            Compressor::Sequential(compressor) => compressor.done()?,
+            #[cfg(feature = "laz-parallel")]
+            Compressor::Parallel(compressor) => compressor.done()?,
+        }
+        Ok(())
+    }
+
+    fn get_mut(&mut self) -> &mut T {
+        match self {
+            Compressor::Sequential(compressor) => compressor.get_mut(),
+            #[cfg(feature = "laz-parallel")]
+            Compressor::Parallel(compressor) => compressor.compressor.get_mut(),
+        }
+    }
+
+    fn into_inner(self) -> T {
+        match self {
+            Compressor::Sequential(compressor) => compressor.into_inner(),
+            #[cfg(feature = "laz-parallel")]
+            Compressor::Parallel(compressor) => compressor.compressor.into_inner(),
+        }
+    }
+}
+
+/// The parallel compressor of laz-rs, together with the thread pool that it runs on. laz-rs compresses the chunks of
+/// a single `compress_many` call in parallel, so point records are buffered until there is one LAZ chunk per thread
+#[cfg(feature = "laz-parallel")]
+struct ParallelCompressor<T: Write + Seek + Send> {
+    compressor: ParLasZipCompressor<T>,
+    thread_pool: rayon::ThreadPool,
+    pending_records: Vec<u8>,
+    batch_size_in_bytes: usize,
+}
+
+#[cfg(feature = "laz-parallel")]
+impl<T: Write + Seek + Send> ParallelCompressor<T> {
+    fn compress_many(&mut self, points: &[u8]) -> Result<()> {
+        self.pending_records.extend_from_slice(points);
+        if self.pending_records.len() >= self.batch_size_in_bytes {
+            self.compress_pending_records()?;
+        }
+        Ok(())
+    }
+
+    fn compress_pending_records(&mut self) -> Result<()> {
+        let compressor = &mut self.compressor;
+        let pending_records = &self.pending_records;
+        self.thread_pool
+            .install(|| compressor.compress_many(pending_records))
+            .map_err(map_laz_err)?;
+        self.pending_records.clear();
+        Ok(())
+    }
+
+    fn done(&mut self) -> Result<()> {
+        self.compress_pending_records()?;
+        self.compressor.done().map_err(map_laz_err)
+    }
+}
+
+pub(crate) struct RawLAZWriter<T: std::io::Write + std::io::Seek + Send + 'static> {
+    writer: Compressor<T>,
+    default_layout: PointLayout,
+    current_header: las::raw::Header,
+    /// VLRs that were written after the header, which are part of the `LASMetadata` of the finished file
+    vlrs: Vec<las::Vlr>,
+    evlrs: Vec<las::raw::Vlr>,
+    /// Entries of the Extra Bytes VLR and the total number of extra bytes per point record
+    extra_bytes_entries: Vec<ExtraBytesEntry>,
+    num_extra_bytes: usize,
+    bit_attribute_overflow: BitAttributeOverflowHandling,
+    overlap_flag_handling: OverlapFlagHandling,
+    /// Number of points whose overlap flag was dropped because the point format has no bit for it
+    dropped_overlap_flags: usize,
+    missing_attribute_defaults: MissingAttributeDefaults,
+    color_upscaling: ColorUpscaling,
+    /// Number of points whose records are assembled in `las_point_buffer` and compressed with a single call to the
+    /// compressor
+    write_chunk_size: usize,
+    /// Raw memory of the source points and the LAS point records of a chunk, reused between calls to `write`
+    chunk_buffer: Vec<u8>,
+    las_point_buffer: Vec<u8>,
+    requires_flush: bool,
+    /// Is the compressed point data finished? LAZ compression can only be finished once, afterwards no more points
+    /// can be written
+    is_finished: bool,
+}
+
+/// Parallel compression is only available with the `laz-parallel` feature
+#[cfg(feature = "laz-parallel")]
+impl<T: std::io::Write + std::io::Seek + Send + 'static> RawLAZWriter<T> {
+    /// Creates a new `RawLAZWriter` that compresses the point records using `num_threads` threads. The points are
+    /// buffered until there is one LAZ chunk for each thread, and the chunks are compressed in parallel and written in
+    /// order. The output is identical to the output of [`Self::from_write_and_header_with_chunk_size`]. If
+    /// `num_threads` is zero, the number of threads is determined automatically based on the number of available CPU
+    /// cores
+    pub fn from_write_and_header_parallel(
+        write: T,
+        header: las::Header,
+        num_threads: usize,
+        chunk_size: u32,
+    ) -> Result<Self> {
+        let thread_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .context("Could not create thread pool for LAZ compression")?;
+        let size_of_record = header.point_format().len() as usize;
+        Self::from_write_and_header_with_compressor(
+            write,
+            header,
+            chunk_size,
+            move |write, laz_vlr| {
+                let chunks_per_batch = thread_pool.current_num_threads().max(1);
+                let batch_size_in_bytes =
+                    laz_vlr.chunk_size() as usize * chunks_per_batch * size_of_record;
+                let compressor = ParLasZipCompressor::new(write, laz_vlr).map_err(map_laz_err)?;
+                Ok(Compressor::Parallel(ParallelCompressor {
+                    compressor,
+                    thread_pool,
+                    pending_records: Vec::with_capacity(batch_size_in_bytes),
+                    batch_size_in_bytes,
+                }))
+            },
+        )
+    }
+}
+
+impl<T: std::io::Write + std::io::Seek + Send + 'static> RawLAZWriter<T> {
+    pub fn from_write_and_header(write: T, header: las::Header) -> Result<Self> {
+        Self::from_write_and_header_with_chunk_size(write, header, DEFAULT_LAZ_CHUNK_SIZE)
+    }
+
+    /// Creates a new `RawLAZWriter` that compresses the point records in chunks of `chunk_size` points, see
+    /// [`validate_laz_chunk_size`] for the supported chunk sizes
+    pub fn from_write_and_header_with_chunk_size(
+        write: T,
+        header: las::Header,
+        chunk_size: u32,
+    ) -> Result<Self> {
+        Self::from_write_and_header_with_compressor(write, header, chunk_size, |write, laz_vlr| {
+            let compressor = LasZipCompressor::new(write, laz_vlr).map_err(map_laz_err)?;
+            Ok(Compressor::Sequential(compressor))
+        })
+    }
+
+    fn from_write_and_header_with_compressor<F: FnOnce(T, LazVlr) -> Result<Compressor<T>>>(
+        mut write: T,
+        header: las::Header,
+        chunk_size: u32,
+        make_compressor: F,
+    ) -> Result<Self> {
+        validate_laz_chunk_size(chunk_size)?;
+        write.stream_position().context(LAS_WRITER_REQUIRES_SEEK)?;
+        let las_metadata = (&header).try_into().context("Could not parse LAS header")?;
+        let default_layout = point_layout_from_las_metadata(&las_metadata, false)
+            .context("Could not determine PointLayout from given LAS header")?;
+        let extra_bytes_entries = las_metadata
+            .extra_bytes_vlr()
+            .map(|vlr| vlr.entries().to_vec())
+            .unwrap_or_default();
+        let num_extra_bytes = header.point_format().extra_bytes as usize;
+
+        let mut raw_header = header.clone().into_raw()?;
+        // raw_header.version = Version::new(1, 2);
+        raw_header.number_of_point_records = 0;
+        raw_header.number_of_points_by_return = [0; 5];
+        // Pasture always uses the 'large_file' field for keeping track of the number of points
+        raw_header.large_file = Some(Default::default());
+        raw_header.min_x = std::f64::INFINITY;
+        raw_header.min_y = std::f64::INFINITY;
+        raw_header.min_z = std::f64::INFINITY;
+        raw_header.max_x = std::f64::NEG_INFINITY;
+        raw_header.max_y = std::f64::NEG_INFINITY;
+        raw_header.max_z = std::f64::NEG_INFINITY;
+
+        if raw_header.x_scale_factor == 0.0
+            || raw_header.y_scale_factor == 0.0
+            || raw_header.z_scale_factor == 0.0
+        {
+            return Err(anyhow!("RawLASWriter::from_write_and_header: Scale factors in LAS header must not be zero!"));
+        }
+
+        // Create LAZ VLR in addition to the other VLRs in the header
+        let laz_items = LazItemRecordBuilder::default_for_point_format_id(
+            header.point_format().to_u8()?,
+            header.point_format().extra_bytes,
+        )
+        .map_err(map_laz_err)?;
+        let raw_laz_vlr = LazVlrBuilder::new(laz_items)
+            .with_fixed_chunk_size(chunk_size)
+            .build();
+        let mut raw_laz_vlr_cursor = Cursor::new(Vec::<u8>::new());
+        raw_laz_vlr.write_to(&mut raw_laz_vlr_cursor)?;
+        let laz_vlr = Vlr {
+            user_id: laz::LazVlr::USER_ID.to_owned(),
+            record_id: laz::LazVlr::RECORD_ID,
+            description: laz::LazVlr::DESCRIPTION.to_owned(),
+            data: raw_laz_vlr_cursor.into_inner(),
+        };
+
+        let mut header_builder = Builder::new(raw_header)?;
+        header_builder.vlrs.push(laz_vlr);
+        let header_with_laz_vlr = header_builder.into_header()?;
+        let mut current_header = header_with_laz_vlr.clone().into_raw()?;
+        set_wkt_bit_in_las_header(&mut current_header, &header);
+        current_header.write_to(&mut write)?;
+        for vlr in header_with_laz_vlr.vlrs() {
+            vlr.clone()
+                .into_raw(false)
+                .and_then(|raw_vlr| raw_vlr.write_to(&mut write))?;
+        }
+        if !header.vlr_padding().is_empty() {
+            write.write_all(header.vlr_padding())?;
+        }
+
+        let laz_writer = make_compressor(write, raw_laz_vlr)?;
+
+        Ok(Self {
+            writer: laz_writer,
+            default_layout,
+            current_header,
+            evlrs: header
+                .evlrs()
+                .iter()
+                .map(|evlr| evlr.clone().into_raw(true))
+                .collect::<Result<Vec<_>, _>>()?,
+            vlrs: header_with_laz_vlr.vlrs().clone(),
+            extra_bytes_entries,
+            num_extra_bytes,
+            bit_attribute_overflow: Default::default(),
+            overlap_flag_handling: Default::default(),
+            dropped_overlap_flags: 0,
+            missing_attribute_defaults: Default::default(),
+            color_upscaling: Default::default(),
+            write_chunk_size: DEFAULT_WRITE_CHUNK_SIZE,
+            chunk_buffer: vec![],
+            las_point_buffer: vec![],
+            requires_flush: false,
+            is_finished: false,
+        })
+    }
+
+    /// Consumes this `RawLAZWriter` and returns the underlying write type `T`. The LAZ file is finalized before
+    /// returning the writer
+    pub fn into_inner(mut self) -> Result<T> {
+        self.finalize()?;
+        Ok(self.writer.into_inner())
+    }
+
+    fn write_points_default_layout<'a, B: BorrowedBuffer<'a>>(
+        &mut self,
+        points: &'a B,
+    ) -> Result<()> {
+        if points.is_empty() {
+            return Ok(());
+        }
+
+        // Similar to RawLASReader, write points in chunks of `write_chunk_size` points to prevent overhead of
+        // repeated virtual calls to 'dyn PointBuffer'
+
+        let size_of_single_point = self.default_layout.size_of_point_entry() as usize;
+        let num_points_in_chunk = self.write_chunk_size.min(points.len());
+        let num_chunks = (points.len() + (num_points_in_chunk - 1)) / num_points_in_chunk;
+        let size_of_las_point = self.current_header.point_data_record_length as usize;
+        let (mut chunk_buffer, mut las_point_buffer) =
+            self.take_chunk_buffers(num_points_in_chunk, size_of_single_point);
+
+        let source_format = Format::new(self.current_header.point_data_record_format)?;
+
+        let mut points_by_return: HashMap<u8, u64> = HashMap::new();
+        let max_return_number = if self.current_header.large_file.is_some() {
+            15
+        } else {
+            5
+        };
+        for return_number in 1..=max_return_number {
+            points_by_return.insert(return_number, 0);
+        }
+
+        let first_point_index = self
+            .current_header
+            .large_file
+            .as_ref()
+            .map(|large_file| large_file.number_of_point_records)
+            .unwrap_or_default();
+        for chunk_index in 0..num_chunks {
+            let mut chunk_bounds = ChunkBounds::new();
+            points_by_return.values_mut().for_each(|count| *count = 0);
+            let points_in_cur_chunk = std::cmp::min(
+                num_points_in_chunk,
+                points.len() - (chunk_index * num_points_in_chunk),
+            );
+            let start_point_index = chunk_index * num_points_in_chunk;
+            gather_point_range(
+                points,
+                start_point_index..(start_point_index + points_in_cur_chunk),
+                &mut chunk_buffer[..(points_in_cur_chunk * size_of_single_point)],
+            );
+            let mut point_read = Cursor::new(chunk_buffer);
+            let mut las_point_write = Cursor::new(las_point_buffer);
+
+            // Read all the attributes from the raw memory inside `points` and transform them into the format that LAS expects
+            for point_index in 0..points_in_cur_chunk {
+                let point_context =
+                    || invalid_point_context(first_point_index, start_point_index + point_index);
+                let pos_x = point_read.read_f64::<NativeEndian>()?;
+                let pos_y = point_read.read_f64::<NativeEndian>()?;
+                let pos_z = point_read.read_f64::<NativeEndian>()?;
+                let world_space_position = Vector3::new(pos_x, pos_y, pos_z);
+                write_position_as_las_position(
+                    &world_space_position,
+                    &self.current_header,
+                    &mut las_point_write,
+                )?;
+                chunk_bounds.include(&world_space_position);
+
+                let intensity = point_read.read_u16::<NativeEndian>()?;
+                las_point_write.write_u16::<LittleEndian>(intensity)?;
+
+                let bit_attributes = if source_format.is_extended {
+                    let return_number = point_read.read_u8()?;
+                    let number_of_returns = point_read.read_u8()?;
+                    let classification_flags = point_read.read_u8()?;
+                    let scanner_channel = point_read.read_u8()?;
+                    let scan_direction_flag = point_read.read_u8()?;
+                    let edge_of_flight_line = point_read.read_u8()?;
+                    let overlap = point_read.read_u8()?;
+                    BitAttributes::Extended(BitAttributesExtended {
+                        return_number,
+                        number_of_returns,
+                        classification_flags,
+                        scanner_channel,
+                        scan_direction_flag,
+                        edge_of_flight_line,
+                        overlap,
+                    })
+                } else {
+                    let return_number = point_read.read_u8()?;
+                    let number_of_returns = point_read.read_u8()?;
+                    let scan_direction_flag = point_read.read_u8()?;
+                    let edge_of_flight_line = point_read.read_u8()?;
+                    BitAttributes::Regular(BitAttributesRegular {
+                        return_number,
+                        number_of_returns,
+                        scan_direction_flag,
+                        edge_of_flight_line,
+                    })
+                }
+                .fit_to_bit_widths(self.bit_attribute_overflow)
+                .with_context(point_context)?;
+                if let Some(count) = points_by_return.get_mut(&bit_attributes.return_number()) {
+                    *count += 1;
+                }
+                write_las_bit_attributes(bit_attributes, &mut las_point_write)?;
+
+                let classification = point_read.read_u8()?;
+                if source_format.is_extended {
+                    las_point_write.write_u8(classification)?;
+                } else {
+                    let classification_flags = point_read.read_u8()?;
+                    if drops_legacy_overlap_flag(
+                        0,
+                        classification_flags,
+                        self.overlap_flag_handling,
+                    )
+                    .with_context(point_context)?
+                    {
+                        self.dropped_overlap_flags += 1;
+                    }
+                    las_point_write.write_u8(
+                        fit_legacy_classification_byte(
+                            classification,
+                            classification_flags,
+                            self.bit_attribute_overflow,
+                        )
+                        .with_context(point_context)?,
+                    )?;
+                }
+
+                if source_format.is_extended {
+                    let user_data = point_read.read_u8()?;
+                    let scan_angle = point_read.read_i16::<NativeEndian>()?;
+
+                    las_point_write.write_u8(user_data)?;
+                    las_point_write.write_i16::<LittleEndian>(
+                        fit_extended_scan_angle(scan_angle, self.bit_attribute_overflow)
+                            .with_context(point_context)?,
+                    )?;
+                } else {
+                    let scan_angle = point_read.read_i8()?;
+                    let user_data = point_read.read_u8()?;
+
+                    las_point_write.write_i8(
+                        fit_scan_angle_rank(scan_angle, self.bit_attribute_overflow)
+                            .with_context(point_context)?,
+                    )?;
+                    las_point_write.write_u8(user_data)?;
+                }
+
+                let point_source_id = point_read.read_u16::<NativeEndian>()?;
+                las_point_write.write_u16::<LittleEndian>(point_source_id)?;
+
+                if source_format.has_gps_time {
+                    let gps_time = point_read.read_f64::<NativeEndian>()?;
+                    las_point_write.write_f64::<LittleEndian>(gps_time)?;
+                }
+
+                if source_format.has_color {
+                    let r = point_read.read_u16::<NativeEndian>()?;
+                    let g = point_read.read_u16::<NativeEndian>()?;
+                    let b = point_read.read_u16::<NativeEndian>()?;
+                    las_point_write.write_u16::<LittleEndian>(r)?;
+                    las_point_write.write_u16::<LittleEndian>(g)?;
+                    las_point_write.write_u16::<LittleEndian>(b)?;
+                }
+
+                if source_format.has_nir {
+                    let nir = point_read.read_u16::<NativeEndian>()?;
+                    las_point_write.write_u16::<LittleEndian>(nir)?;
+                }
+
+                if source_format.has_waveform {
+                    let wave_descriptor = point_read.read_u8()?;
+                    let wave_data_offset = point_read.read_u64::<NativeEndian>()?;
+                    let wave_packet_size = point_read.read_u32::<NativeEndian>()?;
+                    let wave_return_point = point_read.read_f32::<NativeEndian>()?;
+                    let px = point_read.read_f32::<NativeEndian>()?;
+                    let py = point_read.read_f32::<NativeEndian>()?;
+                    let pz = point_read.read_f32::<NativeEndian>()?;
+
+                    las_point_write.write_u8(wave_descriptor)?;
+                    las_point_write.write_u64::<LittleEndian>(wave_data_offset)?;
+                    las_point_write.write_u32::<LittleEndian>(wave_packet_size)?;
+                    las_point_write.write_f32::<LittleEndian>(wave_return_point)?;
+                    las_point_write.write_f32::<LittleEndian>(px)?;
+                    las_point_write.write_f32::<LittleEndian>(py)?;
+                    las_point_write.write_f32::<LittleEndian>(pz)?;
+                }
+            }
+
+            las_point_buffer = las_point_write.into_inner();
+            self.writer
+                .compress_many(&las_point_buffer[..points_in_cur_chunk * size_of_las_point])?;
+
+            // The header is only updated for chunks that have been written completely, so that it still matches the
+            // written point records if a later chunk contains invalid points
+            chunk_bounds.update_las_header(&mut self.current_header);
+            update_point_counts_in_las_header(
+                points_in_cur_chunk,
+                &points_by_return,
+                &mut self.current_header,
+            );
+            self.requires_flush = true;
+
+            chunk_buffer = point_read.into_inner();
+        }
+        self.chunk_buffer = chunk_buffer;
+        self.las_point_buffer = las_point_buffer;
+
+        Ok(())
+    }
+
+    fn write_points_custom_layout<'a, B: BorrowedBuffer<'a>>(
+        &mut self,
+        points: &'a B,
+    ) -> Result<()> {
+        if points.is_empty() {
+            return Ok(());
+        }
+        validate_source_layout_for_las(points.point_layout())?;
+
+        let size_of_single_point = points.point_layout().size_of_point_entry() as usize;
+        let num_points_in_chunk = self.write_chunk_size.min(points.len());
+        let num_chunks = (points.len() + (num_points_in_chunk - 1)) / num_points_in_chunk;
+        let size_of_las_point = self.current_header.point_data_record_length as usize;
+        let (mut chunk_buffer, mut las_point_buffer) =
+            self.take_chunk_buffers(num_points_in_chunk, size_of_single_point);
+
+        let target_format = Format::new(self.current_header.point_data_record_format)?;
+
+        let mut points_by_return: HashMap<u8, u64> = HashMap::new();
+        let max_return_number = if self.current_header.large_file.is_some() {
+            15
+        } else {
+            5
+        };
+        for return_number in 1..=max_return_number {
+            points_by_return.insert(return_number, 0);
+        }
+
+        // Positions are mandatory (see `validate_source_layout_for_las`), so their default value is never used
+        let position_reader = get_position_reader(points.point_layout(), Vector3::zeros())?;
+        let defaults = self.missing_attribute_defaults;
+        let intensity_reader = get_intensity_reader(points.point_layout(), defaults.intensity)?;
+        let return_number_reader =
+            get_return_number_reader(points.point_layout(), defaults.return_number)?;
+        let number_of_returns_reader =
+            get_number_of_returns_reader(points.point_layout(), defaults.number_of_returns)?;
+        let classification_flags_reader =
+            get_classification_flags_reader(points.point_layout(), defaults.classification_flags)?;
+        let scanner_channel_reader = if target_format.is_extended {
+            Some(get_scanner_channel_reader(
+                points.point_layout(),
+                defaults.scanner_channel,
+            )?)
+        } else {
+            None
+        };
+        let scan_direction_flag_reader =
+            get_scan_direction_flag_reader(points.point_layout(), defaults.scan_direction_flag)?;
+        let edge_of_flight_line_reader =
+            get_edge_of_flight_line_reader(points.point_layout(), defaults.edge_of_flight_line)?;
+        // The overlap flag is also read for the point formats 0-5, which can't store it, to detect if it is dropped
+        let overlap_reader = get_overlap_reader(points.point_layout(), defaults.overlap)?;
+        let classification_reader =
+            get_classification_reader(points.point_layout(), defaults.classification)?;
+        let user_data_reader = get_user_data_reader(points.point_layout(), defaults.user_data)?;
+        let scan_angle_reader = if target_format.is_extended {
+            None
+        } else {
+            Some(get_scan_angle_rank_reader(
+                points.point_layout(),
+                defaults.scan_angle_rank,
+            )?)
+        };
+        let extended_scan_angle_reader = if target_format.is_extended {
+            Some(get_extended_scan_angle_rank_reader(
+                points.point_layout(),
+                defaults.scan_angle,
+            )?)
+        } else {
+            None
+        };
+        let point_source_id_reader =
+            get_point_source_id_reader(points.point_layout(), defaults.point_source_id)?;
+        let gps_time_reader = if target_format.has_gps_time {
+            Some(get_gps_time_reader(
+                points.point_layout(),
+                defaults.gps_time,
+            )?)
+        } else {
+            None
+        };
+        let color_reader = if target_format.has_color {
+            Some(get_upscaled_color_reader(
+                points.point_layout(),
+                defaults.color_rgb,
+                self.color_upscaling,
+            )?)
+        } else {
+            None
+        };
+        let nir_reader = if target_format.has_nir {
+            Some(get_nir_reader(points.point_layout(), defaults.nir)?)
+        } else {
+            None
+        };
+        let wave_packet_descriptor_index_reader = if target_format.has_waveform {
+            Some(get_wave_packet_descriptor_index_reader(
+                points.point_layout(),
+                defaults.wave_packet_descriptor_index,
+            )?)
+        } else {
+            None
+        };
+        let waveform_data_offset_reader = if target_format.has_waveform {
+            Some(get_waveform_data_offset_reader(
+                points.point_layout(),
+                defaults.waveform_data_offset,
+            )?)
+        } else {
+            None
+        };
+        let waveform_packet_size_reader = if target_format.has_waveform {
+            Some(get_waveform_packet_size_reader(
+                points.point_layout(),
+                defaults.waveform_packet_size,
+            )?)
+        } else {
+            None
+        };
+        let return_point_waveform_location_reader = if target_format.has_waveform {
+            Some(get_return_point_waveform_location_reader(
+                points.point_layout(),
+                defaults.return_point_waveform_location,
+            )?)
+        } else {
+            None
+        };
+        let waveform_parameters_reader = if target_format.has_waveform {
+            Some(get_waveform_parameters_reader(
+                points.point_layout(),
+                defaults.waveform_parameters,
+            )?)
+        } else {
+            None
+        };
+
+        let extra_bytes_writer = if self.num_extra_bytes > 0 {
+            Some(ExtraBytesWriter::new(
+                &self.extra_bytes_entries,
+                self.num_extra_bytes,
+                points.point_layout(),
+            )?)
+        } else {
+            None
+        };
+
+        let first_point_index = self
+            .current_header
+            .large_file
+            .as_ref()
+            .map(|large_file| large_file.number_of_point_records)
+            .unwrap_or_default();
+        for chunk_index in 0..num_chunks {
+            let mut chunk_bounds = ChunkBounds::new();
+            points_by_return.values_mut().for_each(|count| *count = 0);
+            let points_in_cur_chunk = std::cmp::min(
+                num_points_in_chunk,
+                points.len() - (chunk_index * num_points_in_chunk),
+            );
+            let start_point_index = chunk_index * num_points_in_chunk;
+            gather_point_range(
+                points,
+                start_point_index..(start_point_index + points_in_cur_chunk),
+                &mut chunk_buffer[0..(points_in_cur_chunk * size_of_single_point)],
+            );
+            let mut point_read = Cursor::new(chunk_buffer);
+            let mut las_point_write = Cursor::new(las_point_buffer);
+
+            // Read all the attributes from the raw memory inside `points` and transform them into the format that LAS expects
+            for point_index in 0..points_in_cur_chunk {
+                let point_context =
+                    || invalid_point_context(first_point_index, start_point_index + point_index);
+                let position = position_reader(point_index, &mut point_read)?;
+                write_position_as_las_position(
+                    &position,
+                    &self.current_header,
+                    &mut las_point_write,
+                )?;
+                chunk_bounds.include(&position);
+
+                las_point_write
+                    .write_u16::<LittleEndian>(intensity_reader(point_index, &mut point_read)?)?;
+
+                let bit_attributes: BitAttributes = if target_format.is_extended {
+                    BitAttributes::Extended(BitAttributesExtended {
+                        return_number: return_number_reader(point_index, &mut point_read)?,
+                        number_of_returns: number_of_returns_reader(point_index, &mut point_read)?,
+                        classification_flags: classification_flags_reader(
+                            point_index,
+                            &mut point_read,
+                        )?,
+                        scanner_channel: scanner_channel_reader.as_ref().unwrap()(
+                            point_index,
+                            &mut point_read,
+                        )?,
+                        scan_direction_flag: scan_direction_flag_reader(
+                            point_index,
+                            &mut point_read,
+                        )?,
+                        edge_of_flight_line: edge_of_flight_line_reader(
+                            point_index,
+                            &mut point_read,
+                        )?,
+                        overlap: overlap_reader(point_index, &mut point_read)?,
+                    })
+                } else {
+                    BitAttributes::Regular(BitAttributesRegular {
+                        return_number: return_number_reader(point_index, &mut point_read)?,
+                        number_of_returns: number_of_returns_reader(point_index, &mut point_read)?,
+                        scan_direction_flag: scan_direction_flag_reader(
+                            point_index,
+                            &mut point_read,
+                        )?,
+                        edge_of_flight_line: edge_of_flight_line_reader(
+                            point_index,
+                            &mut point_read,
+                        )?,
+                    })
+                }
+                .fit_to_bit_widths(self.bit_attribute_overflow)
+                .with_context(point_context)?;
+                if let Some(count) = points_by_return.get_mut(&bit_attributes.return_number()) {
+                    *count += 1;
+                }
+                write_las_bit_attributes(bit_attributes, &mut las_point_write)?;
+
+                let classification = classification_reader(point_index, &mut point_read)?;
+                if target_format.is_extended {
+                    las_point_write.write_u8(classification)?;
+                } else {
+                    let classification_flags =
+                        classification_flags_reader(point_index, &mut point_read)?;
+                    let overlap = overlap_reader(point_index, &mut point_read)?;
+                    if drops_legacy_overlap_flag(
+                        overlap,
+                        classification_flags,
+                        self.overlap_flag_handling,
+                    )
+                    .with_context(point_context)?
+                    {
+                        self.dropped_overlap_flags += 1;
+                    }
+                    las_point_write.write_u8(
+                        fit_legacy_classification_byte(
+                            classification,
+                            classification_flags,
+                            self.bit_attribute_overflow,
+                        )
+                        .with_context(point_context)?,
+                    )?;
+                }
+
+                if target_format.is_extended {
+                    las_point_write.write_u8(user_data_reader(point_index, &mut point_read)?)?;
+                    let scan_angle =
+                        extended_scan_angle_reader.as_ref().unwrap()(point_index, &mut point_read)?;
+                    las_point_write.write_i16::<LittleEndian>(
+                        fit_extended_scan_angle(scan_angle, self.bit_attribute_overflow)
+                            .with_context(point_context)?,
+                    )?;
+                } else {
+                    let scan_angle =
+                        scan_angle_reader.as_ref().unwrap()(point_index, &mut point_read)?;
+                    las_point_write.write_i8(
+                        fit_scan_angle_rank(scan_angle, self.bit_attribute_overflow)
+                            .with_context(point_context)?,
+                    )?;
+                    las_point_write.write_u8(user_data_reader(point_index, &mut point_read)?)?;
+                }
+
+                las_point_write.write_u16::<LittleEndian>(point_source_id_reader(
+                    point_index,
+                    &mut point_read,
+                )?)?;
+
+                if let Some(ref reader) = gps_time_reader {
+                    las_point_write
+                        .write_f64::<LittleEndian>(reader(point_index, &mut point_read)?)?;
+                }
+
+                if let Some(ref reader) = color_reader {
+                    let color = reader(point_index, &mut point_read)?;
+                    las_point_write.write_u16::<LittleEndian>(color.x)?;
+                    las_point_write.write_u16::<LittleEndian>(color.y)?;
+                    las_point_write.write_u16::<LittleEndian>(color.z)?;
+                }
+
+                if let Some(ref reader) = nir_reader {
+                    las_point_write
+                        .write_u16::<LittleEndian>(reader(point_index, &mut point_read)?)?;
+                }
+
+                if let Some(ref reader) = wave_packet_descriptor_index_reader {
+                    las_point_write.write_u8(reader(point_index, &mut point_read)?)?;
+                }
+                if let Some(ref reader) = waveform_data_offset_reader {
+                    las_point_write
+                        .write_u64::<LittleEndian>(reader(point_index, &mut point_read)?)?;
+                }
+                if let Some(ref reader) = waveform_packet_size_reader {
+                    las_point_write
+                        .write_u32::<LittleEndian>(reader(point_index, &mut point_read)?)?;
+                }
+                if let Some(ref reader) = return_point_waveform_location_reader {
+                    las_point_write
+                        .write_f32::<LittleEndian>(reader(point_index, &mut point_read)?)?;
+                }
+                if let Some(ref reader) = waveform_parameters_reader {
+                    let params = reader(point_index, &mut point_read)?;
+                    las_point_write.write_f32::<LittleEndian>(params.x)?;
+                    las_point_write.write_f32::<LittleEndian>(params.y)?;
+                    las_point_write.write_f32::<LittleEndian>(params.z)?;
+                }
+
+                if let Some(ref extra_bytes_writer) = extra_bytes_writer {
+                    let point_start = point_index * size_of_single_point;
+                    extra_bytes_writer.write_extra_bytes(
+                        &point_read.get_ref()[point_start..(point_start + size_of_single_point)],
+                        &mut las_point_write,
+                    )?;
+                }
+            }
+
+            las_point_buffer = las_point_write.into_inner();
+            // Only the points of the current chunk are compressed, the rest of the buffer contains stale records
+            self.writer
+                .compress_many(&las_point_buffer[..points_in_cur_chunk * size_of_las_point])?;
+
+            // The header is only updated for chunks that have been written completely, so that it still matches the
+            // written point records if a later chunk contains invalid points
+            chunk_bounds.update_las_header(&mut self.current_header);
+            update_point_counts_in_las_header(
+                points_in_cur_chunk,
+                &points_by_return,
+                &mut self.current_header,
+            );
+            self.requires_flush = true;
+
+            chunk_buffer = point_read.into_inner();
+        }
+        self.chunk_buffer = chunk_buffer;
+        self.las_point_buffer = las_point_buffer;
+
+        Ok(())
+    }
+
+    /// Queues the given `evlr`, which is written after the compressed point data when the writer is flushed
+    pub fn add_evlr(&mut self, evlr: las::Vlr) -> Result<()> {
+        if self.is_finished {
+            bail!("Can't add EVLRs to a LAZ file after it has been finalized");
+        }
+        let raw_evlr = raw_evlr_for_las_header(evlr, &self.current_header)?;
+        self.evlrs.push(raw_evlr);
+        self.requires_flush = true;
+        Ok(())
+    }
+
+    /// Sets how values that don't fit into the bit fields of the LAS point records are handled
+    pub fn set_bit_attribute_overflow_handling(
+        &mut self,
+        overflow_handling: BitAttributeOverflowHandling,
+    ) {
+        self.bit_attribute_overflow = overflow_handling;
+    }
+
+    /// Sets what happens to the overlap flag of points that are written to the point formats 0-5
+    pub fn set_overlap_flag_handling(&mut self, overlap_handling: OverlapFlagHandling) {
+        self.overlap_flag_handling = overlap_handling;
+    }
+
+    /// Returns the number of points whose overlap flag was dropped, see [`OverlapFlagHandling::Drop`]
+    pub fn dropped_overlap_flags(&self) -> usize {
+        self.dropped_overlap_flags
+    }
+
+    /// Sets the values that are written for LAS attributes that are missing from the `PointLayout` of the points
+    pub fn set_missing_attribute_defaults(&mut self, defaults: MissingAttributeDefaults) {
+        self.missing_attribute_defaults = defaults;
+    }
+
+    /// Sets how `Vector3<u8>` colors are converted into the 16-bit colors of the point records
+    pub fn set_color_upscaling(&mut self, color_upscaling: ColorUpscaling) {
+        self.color_upscaling = color_upscaling;
+    }
+
+    /// Sets the number of points whose records are assembled in memory and passed to the compressor at once
+    pub fn set_write_chunk_size(&mut self, write_chunk_size: usize) -> Result<()> {
+        validate_write_chunk_size(write_chunk_size)?;
+        self.write_chunk_size = write_chunk_size;
+        Ok(())
+    }
+
+    /// Takes the reusable chunk buffers out of this writer, resized for `num_points` points. They are put back once all
+    /// chunks have been written, so they are only reallocated if writing fails
+    fn take_chunk_buffers(
+        &mut self,
+        num_points: usize,
+        size_of_source_point: usize,
+    ) -> (Vec<u8>, Vec<u8>) {
+        let mut chunk_buffer = std::mem::take(&mut self.chunk_buffer);
+        let mut las_point_buffer = std::mem::take(&mut self.las_point_buffer);
+        resize_chunk_buffers(
+            &mut chunk_buffer,
+            &mut las_point_buffer,
+            num_points,
+            size_of_source_point,
+            self.current_header.point_data_record_length as usize,
+        );
+        (chunk_buffer, las_point_buffer)
+    }
+
+    /// Writes the current header to the start of the file
+    fn write_header(&mut self) -> Result<()> {
+        // Like `RawLASWriter`, only the written header is finalized and the bounds of the writer stay untouched
+        let mut header = self.current_header.clone();
+        finalize_las_header(&mut header)?;
+
+        let mut raw_writer = self.writer.get_mut();
+
+        let current_position = raw_writer.stream_position()?;
+        raw_writer.seek(SeekFrom::Start(0))?;
+        header.write_to(&mut raw_writer)?;
+        raw_writer.seek(SeekFrom::Start(current_position))?;
+
+        Ok(())
+    }
+
+    /// Writes the extended VLRs to the end of the file
+    fn write_evlrs(&mut self) -> Result<()> {
+        let mut raw_writer = self.writer.get_mut();
+        // Assumes that self.writer is at the end of the file!
+        for evlr in self.evlrs.iter() {
+            evlr.write_to(&mut raw_writer)?;
+        }
+        Ok(())
+    }
+
+    /// Finishes the compressed point data and writes the EVLRs and the final LAS header. No more points can be written
+    /// afterwards
+    pub fn finalize(&mut self) -> Result<()> {
+        if self.is_finished {
+            return Ok(());
+        }
+        self.writer.done()?;
+        // The EVLRs are written directly after the compressed point data, including the chunk table
+        let start_of_first_evlr = self.writer.get_mut().stream_position()?;
+        update_evlrs_in_las_header(start_of_first_evlr, &self.evlrs, &mut self.current_header);
+        self.write_evlrs()?;
+        self.write_header()?;
+        self.writer.get_mut().flush()?;
+        self.is_finished = true;
+        self.requires_flush = false;
+        Ok(())
+    }
+
+    /// Returns the `LASMetadata` of the finalized LAZ file, see [`Self::finalize`]
+    pub fn las_metadata(&self) -> Result<LASMetadata> {
+        las_metadata_of_written_file(&self.current_header, &self.vlrs, &self.evlrs)
+    }
+}
+
+impl<T: std::io::Write + std::io::Seek + Send + 'static> PointWriter for RawLAZWriter<T> {
+    fn write<'a, B: BorrowedBuffer<'a>>(&mut self, points: &'a B) -> Result<()> {
+        if self.is_finished {
+            bail!("Can't write points to a LAZ file after it has been finalized, as finalizing finishes the compressed point data");
+        }
+        // The default layout contains the extra bytes in their scaled representation, so points with extra bytes are
+        // always written through the conversion path
+        if *points.point_layout() != self.default_layout || self.num_extra_bytes > 0 {
+            self.write_points_custom_layout(points)
+        } else {
+            self.write_points_default_layout(points)
+        }
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        // Points that are buffered in the compressor are only written by `finalize`, as they belong to an unfinished
+        // chunk
+        self.writer.get_mut().flush()?;
+        Ok(())
+    }
+
+    fn get_default_point_layout(&self) -> &PointLayout {
+        &self.default_layout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, io::BufWriter};
+
+    use las_rs::Builder;
+    use pasture_core::layout::conversion::BufferLayoutConverter;
+    use pasture_core::math::AABB;
+    use pasture_core::nalgebra::Point3;
+
+    use crate::{
+        base::PointReader,
+        las::{
+            epsilon_compare_point3f64, epsilon_compare_vec3f64, get_test_points_in_las_format,
+            point_layout_from_las_point_format, test_data_bounds, widened_layout, LASReader,
+            LasPointFormat0, LasPointFormat1, LasPointFormat2, LasPointFormat3, RawLAZReader,
+        },
+    };
+    use pasture_core::containers::*;
+    use pasture_derive::PointType;
+    use scopeguard::defer;
+
+    use super::*;
+    macro_rules! laz_write_tests {
+        ($name:ident, $format:expr, $point_type:ident) => {
+            mod $name {
+                use super::*;
+
+                #[test]
+                fn test_raw_laz_writer() -> Result<()> {
+                    let test_data = get_test_points_in_las_format($format, false)?;
+
+                    let format = Format::new($format)?;
+                    let mut header_builder = Builder::from((1, 4));
+                    header_builder.point_format = format.clone();
+
+                    let out_path = format!("./test_raw_las_writer_format_{}.laz", $format);
+                    defer! {
+                        std::fs::remove_file(&out_path).expect("Could not remove test file");
+                    }
+                    {
+                        let mut writer = RawLAZWriter::from_write_and_header(
+                            BufWriter::new(File::create(&out_path)?),
+                            header_builder.into_header()?,
+                        )?;
+
+                        let expected_format = point_layout_from_las_point_format(&format, false)?;
+                        assert_eq!(expected_format, *writer.get_default_point_layout());
+
+                        writer.write(&test_data)?;
+                        writer.finalize()?;
+                    }
+
+                    {
+                        let mut reader = LASReader::from_path(&out_path, false)?;
+                        let metadata = reader.get_metadata();
+                        assert_eq!(Some(test_data_bounds()), metadata.bounds());
+                        assert_eq!(Some(test_data.len()), metadata.number_of_points());
+                        assert_eq!(test_data.len(), reader.remaining_points());
+
+                        let read_points = reader.read::<VectorBuffer>(test_data.len())?;
+
+                        assert_eq!(read_points.point_layout(), test_data.point_layout());
+                        assert_eq!(read_points.len(), test_data.len());
+
+                        let expected_points = test_data
+                            .view::<$point_type>()
+                            .into_iter()
+                            .collect::<Vec<_>>();
+                        let actual_points = read_points
+                            .view::<$point_type>()
+                            .into_iter()
+                            .collect::<Vec<_>>();
+
+                        assert_eq!(expected_points, actual_points);
+                    }
+
+                    Ok(())
+                }
+
+                #[test]
+                fn test_raw_laz_writer_round_trip_with_converted_attributes() -> Result<()> {
+                    let test_data = get_test_points_in_las_format($format, false)?;
+                    let custom_layout = widened_layout(test_data.point_layout());
+                    let converter = BufferLayoutConverter::for_layouts_with_default(
+                        test_data.point_layout(),
+                        &custom_layout,
+                    );
+                    let custom_data: VectorBuffer = converter.convert(&test_data);
+
+                    let mut header_builder = Builder::from((1, 4));
+                    header_builder.point_format = Format::new($format)?;
+                    let mut writer = RawLAZWriter::from_write_and_header(
+                        Cursor::new(Vec::new()),
+                        header_builder.into_header()?,
+                    )?;
+                    writer.write(&custom_data)?;
+                    let laz_data = writer.into_inner()?.into_inner();
+
+                    let mut reader = RawLAZReader::from_read(Cursor::new(laz_data), false)?;
+                    assert_eq!(Some(test_data_bounds()), reader.get_metadata().bounds());
+                    let read_points = reader.read::<VectorBuffer>(test_data.len())?;
+                    let expected_points = test_data
+                        .view::<$point_type>()
+                        .into_iter()
+                        .collect::<Vec<_>>();
+                    let actual_points = read_points
+                        .view::<$point_type>()
+                        .into_iter()
+                        .collect::<Vec<_>>();
+                    assert_eq!(expected_points, actual_points);
+
+                    Ok(())
+                }
+
+                #[repr(C, packed)]
+                #[derive(
+                    PointType, Debug, Copy, Clone, bytemuck::AnyBitPattern, bytemuck::NoUninit,
+                )]
+                struct CustomPointType {
+                    #[pasture(BUILTIN_INTENSITY)]
+                    pub intensity: u16,
+                    #[pasture(BUILTIN_POSITION_3D)]
+                    pub lowp_position: Vector3<f32>,
+                    #[pasture(attribute = "Custom")]
+                    pub custom_attribute: u32,
+                }
+
+                #[test]
+                fn test_raw_laz_writer_from_different_point_layout() -> Result<()> {
+                    // Get test data with a different layout, e.g. some missing attributes, some additional
+                    // attributes that are not supported by LAS
+                    let test_data = vec![
+                        CustomPointType {
+                            intensity: 42,
+                            lowp_position: Vector3::new(0.1, 0.2, 0.3),
+                            custom_attribute: 1337,
+                        },
+                        CustomPointType {
+                            intensity: 43,
+                            lowp_position: Vector3::new(0.4, 0.5, 0.6),
+                            custom_attribute: 7331,
+                        },
+                    ];
+
+                    let expected_bounds =
+                        AABB::from_min_max(Point3::new(0.1, 0.2, 0.3), Point3::new(0.4, 0.5, 0.6));
+
+                    let expected_data = test_data.iter().copied().collect::<VectorBuffer>();
+
+                    let format = Format::new($format)?;
+                    let mut header_builder = Builder::from((1, 4));
+                    header_builder.point_format = format.clone();
+
+                    let out_path =
+                        format!("./test_raw_las_writer_different_format_{}.laz", $format);
+
+                    defer! {
+                        std::fs::remove_file(&out_path).expect("Could not remove test file");
+                    }
+
+                    {
+                        let mut writer = RawLAZWriter::from_write_and_header(
+                            BufWriter::new(File::create(&out_path)?),
+                            header_builder.into_header()?,
+                        )?;
+
+                        writer.write(&expected_data)?;
+                        writer.finalize()?;
+                    }
+
+                    {
+                        let mut reader = LASReader::from_path(&out_path, false)?;
+                        let metadata = reader.get_metadata();
+                        assert!(metadata.bounds().is_some());
+                        let actual_bounds = metadata.bounds().unwrap();
+                        assert!(
+                            epsilon_compare_point3f64(expected_bounds.min(), actual_bounds.min()),
+                            "Bounds are different! Expected {:?} but was {:?}",
+                            expected_bounds,
+                            actual_bounds
+                        );
+                        assert!(
+                            epsilon_compare_point3f64(expected_bounds.max(), actual_bounds.max()),
+                            "Bounds are different! Expected {:?} but was {:?}",
+                            expected_bounds,
+                            actual_bounds
+                        );
+
+                        assert_eq!(Some(test_data.len()), metadata.number_of_points());
+                        assert_eq!(test_data.len(), reader.remaining_points());
+
+                        let read_points = reader.read::<VectorBuffer>(test_data.len())?;
+
+                        assert_eq!(read_points.len(), test_data.len());
+
+                        let mut actual_points = read_points
+                            .view::<$point_type>()
+                            .into_iter()
+                            .collect::<Vec<_>>();
+
+                        // Expected positions were f32, converted to f64, this might yield rounding errors, so we compare positions separately
+                        for (idx, (expected, actual)) in
+                            test_data.iter().zip(actual_points.iter()).enumerate()
+                        {
+                            let expected_pos_lowp = expected.lowp_position;
+                            let actual_position = actual.position;
+                            let expected_highp = Vector3::new(
+                                expected_pos_lowp.x as f64,
+                                expected_pos_lowp.y as f64,
+                                expected_pos_lowp.z as f64,
+                            );
+                            assert!(
+                                epsilon_compare_vec3f64(&expected_highp, &actual_position),
+                                "Position {} is different! Expected {} but was {}",
+                                idx,
+                                expected_highp,
+                                actual_position
+                            );
+                        }
+
+                        // Zero out positions so that we can compare the other attributes
+                        actual_points
+                            .iter_mut()
+                            .for_each(|point| point.position = Default::default());
+
+                        let expected_points = test_data
+                            .iter()
+                            .map(|test_point| -> $point_type {
+                                let mut default_point: $point_type = Default::default();
+                                default_point.intensity = test_point.intensity;
+                                default_point
+                            })
+                            .collect::<Vec<_>>();
+
+                        assert_eq!(expected_points, actual_points);
+                    }
+
+                    Ok(())
+                }
+            }
+        };
+    }
+
+    laz_write_tests!(laz_write_0, 0, LasPointFormat0);
+    laz_write_tests!(laz_write_1, 1, LasPointFormat1);
+    laz_write_tests!(laz_write_2, 2, LasPointFormat2);
+    laz_write_tests!(laz_write_3, 3, LasPointFormat3);
+}
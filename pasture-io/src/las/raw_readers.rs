@@ -1,38 +1,79 @@
 use std::convert::TryInto;
 use std::io::{Read, Seek, SeekFrom};
 
-use anyhow::{anyhow, bail, Context, Result};
+use anyhow::{bail, Context, Result};
+use byteorder::{LittleEndian, ReadBytesExt};
 use las_rs::Header;
-use las_rs::{raw, Builder, Vlr};
-use laz::LasZipDecompressor;
-use pasture_core::containers::{BorrowedMutBuffer, OwningBuffer, VectorBuffer};
+use las_rs::{raw, Builder};
+use pasture_core::containers::{
+    BorrowedMutBuffer, ExternalMemoryBuffer, MakeBufferFromLayout, OwningBuffer, VectorBuffer,
+};
 use pasture_core::layout::attributes::{
-    CLASSIFICATION_FLAGS, EDGE_OF_FLIGHT_LINE, NUMBER_OF_RETURNS, POSITION_3D, RETURN_NUMBER,
-    SCANNER_CHANNEL, SCAN_DIRECTION_FLAG,
+    CLASSIFICATION, CLASSIFICATION_FLAGS, COLOR_RGB, EDGE_OF_FLIGHT_LINE, GPS_TIME, INTENSITY,
+    NUMBER_OF_RETURNS, OVERLAP, POSITION_3D, RETURN_NUMBER, SCANNER_CHANNEL, SCAN_ANGLE,
+    SCAN_ANGLE_RANK, SCAN_DIRECTION_FLAG,
 };
 use pasture_core::layout::conversion::BufferLayoutConverter;
 use pasture_core::layout::PointAttributeDataType;
+use pasture_core::math::AABB;
 use pasture_core::nalgebra::Vector3;
 use pasture_core::{layout::PointLayout, meta::Metadata};
 
 use super::{
-    map_laz_err, point_layout_from_las_metadata, LASMetadata, ATTRIBUTE_LOCAL_LAS_POSITION,
+    point_count_from_raw_header, point_layout_from_las_metadata,
+    point_layout_with_local_las_positions, point_layout_with_scan_angle_in_degrees,
+    read_candidate_point_ranges, read_filtered_las_points, validate_las_points, ColorConversion,
+    LASMetadata, LASPointFilter, LASReadOptions, LASRecordValidator, LAXIndex, ReturnFilter,
+    ValidationOptions, ValidationReport, ATTRIBUTE_LOCAL_LAS_POSITION,
 };
-use crate::base::{PointReader, SeekToPoint};
+use crate::base::{skip_points_by_seeking, PointReader, ProgressCallback, SeekToPoint};
 use crate::las::{ATTRIBUTE_BASIC_FLAGS, ATTRIBUTE_EXTENDED_FLAGS};
 
-/// Is the given VLR the LASzip VLR? Function taken from the `las` crate because it is not exported there
-fn is_laszip_vlr(vlr: &Vlr) -> bool {
-    vlr.user_id == laz::LazVlr::USER_ID && vlr.record_id == laz::LazVlr::RECORD_ID
+/// Reads all extended VLRs of the LAS file described by `raw_header` from the given `reader`. EVLRs are stored after
+/// the point records, so this seeks within `reader` and leaves it at an unspecified position
+pub(crate) fn read_evlrs<R: Read + Seek>(
+    reader: &mut R,
+    raw_header: &raw::Header,
+) -> Result<Vec<las_rs::Vlr>> {
+    let evlr_info = match raw_header.evlr.as_ref() {
+        Some(evlr_info) if evlr_info.number_of_evlrs > 0 => evlr_info,
+        _ => return Ok(vec![]),
+    };
+    reader.seek(SeekFrom::Start(evlr_info.start_of_first_evlr))?;
+    (0..evlr_info.number_of_evlrs)
+        .map(|_| las_rs::raw::Vlr::read_from(&mut *reader, true).map(las_rs::Vlr::new))
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to read EVLRs")
+}
+
+/// Fills `buffer` with binary point records from `reader`. Unlike `read_exact`, this does not fail if `reader` ends
+/// early, but returns the number of bytes that were actually read, so that truncated files can be detected precisely
+fn read_point_records<R: Read>(reader: &mut R, buffer: &mut [u8]) -> std::io::Result<usize> {
+    let mut bytes_read = 0;
+    while bytes_read < buffer.len() {
+        match reader.read(&mut buffer[bytes_read..]) {
+            Ok(0) => break,
+            Ok(count) => bytes_read += count,
+            Err(why) if why.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(why) => return Err(why),
+        }
+    }
+    Ok(bytes_read)
 }
 
 /// Returns a `BufferLayoutConverter` that performs a conversion from the given raw LAS `PointLayout` into
-/// the given `target_layout`
-fn get_default_las_converter<'a>(
+/// the given `target_layout`, using the conversion settings of the given `options`. This is the single place where
+/// the conversion of each LAS attribute is decided, all LAS and LAZ readers build one converter per `read_into` call
+/// and then run it over whole chunks of point records
+pub(crate) fn get_default_las_converter<'a>(
     raw_las_layout: &'a PointLayout,
     target_layout: &'a PointLayout,
-    las_header: &Header,
+    las_metadata: &LASMetadata,
+    options: &LASReadOptions,
 ) -> Result<BufferLayoutConverter<'a>> {
+    let las_header = las_metadata
+        .raw_las_header()
+        .context("Missing LAS header")?;
     let mut converter =
         BufferLayoutConverter::for_layouts_with_default(raw_las_layout, target_layout);
     // Add custom conversions depending on the target layout
@@ -53,7 +94,9 @@ fn get_default_las_converter<'a>(
                     ((pos.z as f64 * transforms.z.scale) + transforms.z.offset) as f32,
                 )
             }, false),
-            other => bail!("Invalid datatype {other} for POSITION_3D attribute. Only Vec3f64 and Vec3f32 are supported!"),
+            // Positions in local space are copied as-is, without applying scale and offset
+            PointAttributeDataType::Vec3i32 => converter.set_custom_mapping(&ATTRIBUTE_LOCAL_LAS_POSITION, position_attribute.attribute_definition()),
+            other => bail!("Invalid datatype {other} for POSITION_3D attribute. Only Vec3f64, Vec3f32 and Vec3i32 are supported!"),
         }
     }
 
@@ -101,6 +144,29 @@ fn get_default_las_converter<'a>(
                 true,
             );
         }
+
+        // The legacy formats store the synthetic, key-point and withheld flags in the upper three bits of the
+        // classification byte
+        if let Some(classification_attribute) =
+            target_layout.get_attribute_by_name(CLASSIFICATION.name())
+        {
+            converter.set_custom_mapping_with_transformation(
+                &CLASSIFICATION,
+                classification_attribute.attribute_definition(),
+                |classification: u8| -> u8 { classification & 0b11111 },
+                true,
+            );
+        }
+        if let Some(classification_flags_attribute) =
+            target_layout.get_attribute_by_name(CLASSIFICATION_FLAGS.name())
+        {
+            converter.set_custom_mapping_with_transformation(
+                &CLASSIFICATION,
+                classification_flags_attribute.attribute_definition(),
+                |classification: u8| -> u8 { (classification >> 5) & 0b111 },
+                true,
+            );
+        }
     } else {
         if let Some(return_number_attribute) =
             target_layout.get_attribute_by_name(RETURN_NUMBER.name())
@@ -132,6 +198,14 @@ fn get_default_las_converter<'a>(
                 true,
             );
         }
+        if let Some(overlap_attribute) = target_layout.get_attribute_by_name(OVERLAP.name()) {
+            converter.set_custom_mapping_with_transformation(
+                &ATTRIBUTE_EXTENDED_FLAGS,
+                overlap_attribute.attribute_definition(),
+                |flags: u16| -> u16 { (flags >> 11) & 0b1 },
+                true,
+            );
+        }
         if let Some(scanner_channel_attribute) =
             target_layout.get_attribute_by_name(SCANNER_CHANNEL.name())
         {
@@ -163,9 +237,137 @@ fn get_default_las_converter<'a>(
         }
     }
 
+    if options.normalize_intensity {
+        if let Some(intensity_attribute) = target_layout.get_attribute_by_name(INTENSITY.name()) {
+            match intensity_attribute.datatype() {
+                PointAttributeDataType::F32 => converter.set_custom_mapping_with_transformation(
+                    &INTENSITY,
+                    intensity_attribute.attribute_definition(),
+                    |intensity: f32| -> f32 { intensity / u16::MAX as f32 },
+                    false,
+                ),
+                PointAttributeDataType::F64 => converter.set_custom_mapping_with_transformation(
+                    &INTENSITY,
+                    intensity_attribute.attribute_definition(),
+                    |intensity: f64| -> f64 { intensity / u16::MAX as f64 },
+                    false,
+                ),
+                _ => (),
+            }
+        }
+    }
+
+    if let Some(color_attribute) = target_layout.get_attribute_by_name(COLOR_RGB.name()) {
+        if raw_las_layout.has_attribute(&COLOR_RGB)
+            && color_attribute.datatype() == PointAttributeDataType::Vec3u8
+        {
+            match options.color_conversion {
+                ColorConversion::Truncate => (),
+                ColorConversion::DivideBy257 => converter.set_custom_mapping_with_transformation(
+                    &COLOR_RGB,
+                    color_attribute.attribute_definition(),
+                    |color: Vector3<u16>| -> Vector3<u16> { color.map(|channel| channel / 257) },
+                    true,
+                ),
+                ColorConversion::ShiftBy8 => converter.set_custom_mapping_with_transformation(
+                    &COLOR_RGB,
+                    color_attribute.attribute_definition(),
+                    |color: Vector3<u16>| -> Vector3<u16> { color.map(|channel| channel >> 8) },
+                    true,
+                ),
+            }
+        }
+    }
+
+    // Scan angles with a floating-point datatype are converted into degrees, regardless of the point format. The legacy
+    // formats store the scan angle rank in whole degrees, the extended formats store the scan angle in increments of
+    // 0.006 degrees
+    let (raw_scan_angle_attribute, degrees_per_unit) = if raw_las_layout.has_attribute(&SCAN_ANGLE)
+    {
+        (&SCAN_ANGLE, 0.006)
+    } else {
+        (&SCAN_ANGLE_RANK, 1.0)
+    };
+    if raw_las_layout.has_attribute(raw_scan_angle_attribute) {
+        for scan_angle_attribute in [&SCAN_ANGLE, &SCAN_ANGLE_RANK] {
+            let target_attribute =
+                match target_layout.get_attribute_by_name(scan_angle_attribute.name()) {
+                    Some(attribute) => attribute,
+                    None => continue,
+                };
+            match target_attribute.datatype() {
+                PointAttributeDataType::F32 => converter.set_custom_mapping_with_transformation(
+                    raw_scan_angle_attribute,
+                    target_attribute.attribute_definition(),
+                    move |angle: f32| -> f32 { (angle as f64 * degrees_per_unit) as f32 },
+                    false,
+                ),
+                PointAttributeDataType::F64 => converter.set_custom_mapping_with_transformation(
+                    raw_scan_angle_attribute,
+                    target_attribute.attribute_definition(),
+                    move |angle: f64| -> f64 { angle * degrees_per_unit },
+                    false,
+                ),
+                _ => (),
+            }
+        }
+    }
+
+    // Apply scale and offset to extra bytes, if the target layout stores them as floating-point values. For all other
+    // datatypes, the raw values are used
+    if let Some(extra_bytes_vlr) = las_metadata.extra_bytes_vlr() {
+        for entry in extra_bytes_vlr
+            .entries()
+            .iter()
+            .filter(|entry| entry.is_scaled())
+        {
+            let target_attribute = match target_layout.get_attribute_by_name(entry.name()) {
+                Some(attribute) => attribute,
+                None => continue,
+            };
+            let source_attribute = entry.get_point_attribute()?;
+            let scale = entry.scale().unwrap_or(1.0);
+            let offset = entry.offset().unwrap_or(0.0);
+            match target_attribute.datatype() {
+                PointAttributeDataType::F64 => converter.set_custom_mapping_with_transformation(
+                    &source_attribute,
+                    target_attribute.attribute_definition(),
+                    move |value: f64| -> f64 { (value * scale) + offset },
+                    false,
+                ),
+                PointAttributeDataType::F32 => converter.set_custom_mapping_with_transformation(
+                    &source_attribute,
+                    target_attribute.attribute_definition(),
+                    move |value: f32| -> f32 { ((value as f64 * scale) + offset) as f32 },
+                    false,
+                ),
+                _ => (),
+            }
+        }
+    }
+
     Ok(converter)
 }
 
+/// Returns the default `PointLayout` of a LAS/LAZ reader for the given `metadata` and `options`
+pub(crate) fn default_point_layout_for_read_options(
+    metadata: &LASMetadata,
+    options: &LASReadOptions,
+) -> Result<PointLayout> {
+    let mut layout =
+        point_layout_from_las_metadata(metadata, options.point_layout_matches_memory_layout)?;
+    if options.point_layout_matches_memory_layout {
+        return Ok(layout);
+    }
+    if !options.convert_positions_to_world_space {
+        layout = point_layout_with_local_las_positions(&layout);
+    }
+    if options.scan_angle_in_degrees {
+        layout = point_layout_with_scan_angle_in_degrees(&layout);
+    }
+    Ok(layout)
+}
+
 pub(crate) trait LASReaderBase {
     /// Returns the remaining number of points in the underyling `LASReaderBase`
     fn remaining_points(&self) -> usize;
@@ -177,9 +379,16 @@ pub struct RawLASReader<T: Read + Seek> {
     metadata: LASMetadata,
     layout: PointLayout,
     las_point_records_layout: PointLayout,
+    options: LASReadOptions,
     current_point_index: usize,
     offset_to_first_point_in_file: u64,
     size_of_point_in_file: u64,
+    progress_callback: Option<ProgressCallback>,
+    filter: LASPointFilter,
+    spatial_index: Option<LAXIndex>,
+    /// Buffers that are reused between calls to `read_into`. They are allocated on first use
+    read_buffer: Vec<u8>,
+    convert_buffer: Option<VectorBuffer>,
 }
 
 impl<T: Read + Seek> RawLASReader<T> {
@@ -188,10 +397,74 @@ impl<T: Read + Seek> RawLASReader<T> {
     /// Otherwise, a more practical `PointLayout` is used that stores positions as `Vector3<f64>` values in world-space
     /// and stores attributes such as `RETURN_NUMBER`, `NUMBER_OF_RETURNS` etc. as separate values instead of the
     /// packed bitfield values. See [`point_layout_from_las_point_format`] for more information
-    pub fn from_read(mut reader: T, point_layout_matches_memory_layout: bool) -> Result<Self> {
+    pub fn from_read(reader: T, point_layout_matches_memory_layout: bool) -> Result<Self> {
+        Self::from_read_with_options(
+            reader,
+            LASReadOptions {
+                point_layout_matches_memory_layout,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Creates a new `RawLASReader` from the given `reader`, using the given `options` to determine the default
+    /// `PointLayout` of the reader. See [`LASReadOptions`] for more information
+    pub fn from_read_with_options(reader: T, options: LASReadOptions) -> Result<Self> {
+        Self::from_read_impl(reader, options, false)
+    }
+
+    /// Like `from_read_with_options`, but `reader` only contains the header and VLRs of the LAS file, i.e. everything
+    /// up to the first point record. Neither the EVLRs nor the point count are checked against the stream, which
+    /// makes this usable for parsing the header of a LAS file that is read through some other means
+    pub(crate) fn from_header_read_with_options(
+        reader: T,
+        options: LASReadOptions,
+    ) -> Result<Self> {
+        Self::from_read_impl(reader, options, true)
+    }
+
+    fn from_read_impl(
+        mut reader: T,
+        options: LASReadOptions,
+        is_header_only: bool,
+    ) -> Result<Self> {
         let raw_header = raw::Header::read_from(&mut reader)?;
         let offset_to_first_point_in_file = raw_header.offset_to_point_data as u64;
         let size_of_point_in_file = raw_header.point_data_record_length as u64;
+        let mut point_count = point_count_from_raw_header(&raw_header);
+        let mut truncation_warning = None;
+        let evlrs = if is_header_only {
+            vec![]
+        } else {
+            // Buggy exporters sometimes write fewer point records than stated in the header, so we check how many
+            // complete point records are actually in the stream
+            let mut end_of_point_records = reader.seek(SeekFrom::End(0))?;
+            if let Some(evlr_info) = raw_header.evlr.as_ref() {
+                if evlr_info.number_of_evlrs > 0 {
+                    end_of_point_records = end_of_point_records.min(evlr_info.start_of_first_evlr);
+                }
+            }
+            let bytes_of_point_records =
+                end_of_point_records.saturating_sub(offset_to_first_point_in_file);
+            let available_point_count = bytes_of_point_records
+                .checked_div(size_of_point_in_file)
+                .unwrap_or_default();
+            if available_point_count < point_count {
+                let error = TruncatedLASFileError {
+                    point_count_in_header: point_count,
+                    available_point_count,
+                    bytes_in_partial_record: bytes_of_point_records
+                        - available_point_count * size_of_point_in_file,
+                };
+                if options.strict_point_count {
+                    return Err(error.into());
+                }
+                truncation_warning = Some(error.to_string());
+                point_count = available_point_count;
+            }
+
+            read_evlrs(&mut reader, &raw_header)?
+        };
 
         // Manually read the VLRs
         reader.seek(SeekFrom::Start(raw_header.header_size as u64))?;
@@ -202,6 +475,7 @@ impl<T: Read + Seek> RawLASReader<T> {
 
         let mut builder = Builder::new(raw_header).context("Invalid LAS header")?;
         builder.vlrs = vlrs;
+        builder.evlrs = evlrs;
 
         // Even after reading all VLRs, there might be leftover bytes before the start of the actual point
         // data. These bytes have to be read and correctly stored in the LAS header, otherwise conversion
@@ -219,12 +493,26 @@ impl<T: Read + Seek> RawLASReader<T> {
 
         let header = builder.into_header().context("Invalid LAS header")?;
 
-        let metadata: LASMetadata = header
+        let mut metadata: LASMetadata = header
             .clone()
             .try_into()
             .context("Failed to parse LAS header")?;
-        let point_layout =
-            point_layout_from_las_metadata(&metadata, point_layout_matches_memory_layout)?;
+        metadata.set_point_count(point_count)?;
+        if let Some(warning) = truncation_warning {
+            metadata.add_warning(warning);
+        }
+        let spatial_index = match LAXIndex::from_las_metadata(&metadata) {
+            Ok(spatial_index) => spatial_index,
+            Err(why) => {
+                metadata.add_warning(format!("Ignoring invalid LAX index: {:#}", why));
+                None
+            }
+        };
+        let point_layout = default_point_layout_for_read_options(&metadata, &options)?;
+        let filter = LASPointFilter {
+            out_of_bounds: options.out_of_bounds_handling,
+            ..Default::default()
+        };
         let matching_memory_layout = point_layout_from_las_metadata(&metadata, true)?;
 
         reader.seek(SeekFrom::Start(offset_to_first_point_in_file))?;
@@ -234,7 +522,13 @@ impl<T: Read + Seek> RawLASReader<T> {
             metadata,
             layout: point_layout,
             las_point_records_layout: matching_memory_layout,
+            options,
             current_point_index: 0,
+            progress_callback: None,
+            filter,
+            spatial_index,
+            read_buffer: vec![],
+            convert_buffer: None,
             offset_to_first_point_in_file,
             size_of_point_in_file,
         })
@@ -244,259 +538,236 @@ impl<T: Read + Seek> RawLASReader<T> {
         &self.metadata
     }
 
-    fn read_into_default_layout<'a, 'b, B: BorrowedMutBuffer<'a>>(
-        &mut self,
-        point_buffer: &'b mut B,
-        count: usize,
-    ) -> Result<usize>
-    where
-        'a: 'b,
-    {
-        let num_points_to_read = usize::min(count, self.remaining_points());
-        if num_points_to_read == 0 {
-            return Ok(0);
-        }
-
-        if let Some(interleaved_buffer) = point_buffer.as_interleaved_mut() {
-            let new_point_data = interleaved_buffer.get_point_range_mut(0..num_points_to_read);
-            self.reader
-                .read_exact(new_point_data)
-                .context("Failed to read point records")?;
-        } else {
-            // Read point data in chunks of ~1MiB size to prevent memory problems for very large files if we were
-            // to read all data in a single chunk
-            const CHUNK_MEM_SIZE: usize = 1 << 20;
-            let num_points_per_chunk = CHUNK_MEM_SIZE / self.size_of_point_in_file as usize;
-            let num_chunks = (num_points_to_read + num_points_per_chunk - 1) / num_points_per_chunk;
-            let mut read_buffer =
-                vec![0; num_points_per_chunk * self.size_of_point_in_file as usize];
-            for chunk_idx in 0..num_chunks {
-                let bytes_in_chunk = if chunk_idx == num_chunks - 1 {
-                    (num_points_to_read - (chunk_idx * num_points_per_chunk))
-                        * self.size_of_point_in_file as usize
-                } else {
-                    read_buffer.len()
-                };
-                let chunk_bytes = &mut read_buffer[..bytes_in_chunk];
-                self.reader
-                    .read_exact(chunk_bytes)
-                    .context("Failed to read chunk of points")?;
-                let first_point_in_chunk = chunk_idx * num_points_per_chunk;
-                let chunk_end = ((chunk_idx + 1) * num_points_per_chunk).min(num_points_to_read);
-                // Safe because this function (`read_into_default_layout`) is only called if the buffer has the exact
-                // binary memory layout of the LAS file
-                unsafe {
-                    point_buffer.set_point_range(first_point_in_chunk..chunk_end, chunk_bytes);
-                }
-            }
-        }
-
-        self.current_point_index += num_points_to_read;
-
-        Ok(num_points_to_read)
+    /// Sets a callback that is invoked once per chunk of points while reading. See [`ProgressCallback`] for the
+    /// parameters that are passed to the callback
+    pub fn set_progress_callback(&mut self, callback: ProgressCallback) {
+        self.progress_callback = Some(callback);
     }
 
-    fn read_into_custom_layout<'a, 'b, B: BorrowedMutBuffer<'a>>(
-        &mut self,
-        point_buffer: &'b mut B,
-        count: usize,
-    ) -> Result<usize> {
-        let num_points_to_read = usize::min(count, self.remaining_points());
-        if num_points_to_read == 0 {
-            return Ok(0);
-        }
-
-        const CHUNK_BYTES: usize = 1 << 20; // 1 MiB
-        let points_per_chunk =
-            CHUNK_BYTES / self.las_point_records_layout.size_of_point_entry() as usize;
-        let num_chunks = (num_points_to_read + points_per_chunk - 1) / points_per_chunk;
-
-        let size_of_chunk = if num_chunks > 1 {
-            points_per_chunk
-        } else {
-            num_points_to_read
-        };
-
-        let mut convert_buffer =
-            VectorBuffer::with_capacity(size_of_chunk, self.las_point_records_layout.clone());
-        convert_buffer.resize(size_of_chunk);
-
-        let source_layout = self.las_point_records_layout.clone();
-        let target_layout = point_buffer.point_layout().clone();
-        let converter = get_default_las_converter(
-            &source_layout,
-            &target_layout,
-            self.metadata.raw_las_header().expect("Missing LAS header"),
-        )
-        .context("Unsupported conversion")?;
+    /// Only read points with one of the given `classifications`. Points with other classifications are skipped
+    /// while reading, so `read_into` returns only the number of accepted points. For the point formats 0 to 5, only
+    /// the lower five bits of the classification byte are compared. Passing an empty slice accepts all points
+    pub fn set_classification_filter(&mut self, classifications: &[u8]) {
+        self.filter.classifications = classifications.to_vec();
+    }
 
-        for chunk_idx in 0..num_chunks {
-            let points_in_current_chunk = if chunk_idx == num_chunks - 1 {
-                num_points_to_read - ((num_chunks - 1) * size_of_chunk)
-            } else {
-                size_of_chunk
-            };
+    /// Only read points whose return number matches the given `filter`. See [`ReturnFilter`] for the available
+    /// options. Like the classification filter, this affects the number of points that `read_into` returns
+    pub fn set_return_filter(&mut self, filter: ReturnFilter) {
+        self.filter.returns = filter;
+    }
 
-            self.read_into_default_layout(&mut convert_buffer, points_in_current_chunk)?;
-            let target_buffer_first_point = chunk_idx * size_of_chunk;
-            let target_buffer_last_point = target_buffer_first_point + points_in_current_chunk;
-            converter.convert_into_range(
-                &convert_buffer,
-                0..points_in_current_chunk,
-                point_buffer,
-                target_buffer_first_point..target_buffer_last_point,
+    /// Only read points whose GPS time lies within `min..=max`. Like the classification filter, this affects the
+    /// number of points that `read_into` returns
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the point format of the file has no GPS time, or if `min` is larger than `max`
+    pub fn set_gps_time_range(&mut self, min: f64, max: f64) -> Result<()> {
+        if !self.metadata.point_format().has_gps_time {
+            bail!(
+                "LAS point format {} has no GPS time",
+                self.metadata.point_format()
             );
         }
-
-        Ok(num_points_to_read)
+        if min > max {
+            bail!("Invalid GPS time range {}..={}", min, max);
+        }
+        self.filter.gps_time_range = Some((min, max));
+        Ok(())
     }
-}
 
-impl<T: Read + Seek> LASReaderBase for RawLASReader<T> {
-    fn remaining_points(&self) -> usize {
-        self.metadata.point_count() - self.current_point_index
+    /// Returns the spatial index of this reader, if there is one. An index that is embedded into the EVLRs of the
+    /// LAS file is loaded automatically
+    pub fn spatial_index(&self) -> Option<&LAXIndex> {
+        self.spatial_index.as_ref()
     }
 
-    fn header(&self) -> &Header {
-        self.metadata.raw_las_header().unwrap()
+    /// Sets the spatial index that [`Self::read_bounds`] uses, e.g. an index from a `.lax` sidecar file. Passing
+    /// `None` removes the current index
+    pub fn set_spatial_index(&mut self, spatial_index: Option<LAXIndex>) {
+        self.spatial_index = spatial_index;
     }
-}
 
-impl<T: Read + Seek> PointReader for RawLASReader<T> {
-    fn read_into<'a, 'b, B: BorrowedMutBuffer<'a>>(
+    /// Appends all points whose world-space position lies within `bounds` to `point_buffer` and returns the number
+    /// of appended points. If the reader has a spatial index, only the point records in the index cells that overlap
+    /// `bounds` are read, otherwise all point records are scanned. The current filters of this reader still apply.
+    /// Afterwards, the reader is positioned at an unspecified point
+    pub fn read_bounds<'a, B: OwningBuffer<'a>>(
         &mut self,
-        point_buffer: &'b mut B,
-        count: usize,
-    ) -> Result<usize>
-    where
-        'a: 'b,
-    {
-        if point_buffer.len() < count {
-            panic!("point_buffer.len() must be >= count");
-        }
-
-        if *point_buffer.point_layout() != self.las_point_records_layout {
-            self.read_into_custom_layout(point_buffer, count)
-        } else {
-            self.read_into_default_layout(point_buffer, count)
-        }
+        bounds: &AABB<f64>,
+        point_buffer: &mut B,
+    ) -> Result<usize> {
+        let point_count = self.metadata.point_count();
+        let candidate_ranges = match &self.spatial_index {
+            Some(spatial_index) => spatial_index.candidate_point_ranges(bounds, point_count),
+            None => vec![0..point_count],
+        };
+        read_candidate_point_ranges(
+            self,
+            &candidate_ranges,
+            point_buffer,
+            |reader, points, count| reader.read_into_filtered(points, count, bounds),
+        )
     }
 
-    fn get_metadata(&self) -> &dyn Metadata {
-        &self.metadata
+    /// Checks all point records of the file against the LAS header, e.g. whether all positions lie within the bounds
+    /// of the header. See [`ValidationOptions`] for the available checks. The filters of this reader do not apply to
+    /// the validation, and the reader is at the same point afterwards as before
+    pub fn validate(&mut self, options: &ValidationOptions) -> Result<ValidationReport> {
+        let validator = LASRecordValidator::new(&self.metadata, options)?;
+        let raw_layout = self.las_point_records_layout.clone();
+        validate_las_points(self, validator, &raw_layout, |reader, points, count| {
+            reader.read_into_default_layout(points, count)
+        })
     }
 
-    fn get_default_point_layout(&self) -> &PointLayout {
-        &self.layout
+    /// Moves the reader to the first point whose GPS time is greater than or equal to `gps_time` and returns the index
+    /// of this point. If there is no such point, the reader is moved to the end of the file and the number of points
+    /// is returned. This does a binary search over the point records, so it only reads a few records, but requires the
+    /// points to be sorted by GPS time, which is usually the case for files that were written in acquisition order.
+    /// For files that are not sorted by GPS time, the resulting position is unspecified, use
+    /// [`Self::seek_to_gps_time_linear`] for these files instead
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the point format of the file has no GPS time
+    pub fn seek_to_gps_time(&mut self, gps_time: f64) -> Result<usize> {
+        let gps_time_offset = self.gps_time_offset()?;
+        let mut low = 0;
+        let mut high = self.metadata.point_count();
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if self.read_gps_time_of_point(middle, gps_time_offset)? < gps_time {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+        self.move_to_point(low)?;
+        Ok(low)
     }
-}
 
-impl<T: Read + Seek> SeekToPoint for RawLASReader<T> {
-    fn seek_point(&mut self, position: SeekFrom) -> Result<usize> {
-        let new_position = match position {
-            SeekFrom::Start(from_start) => from_start as i64,
-            SeekFrom::End(from_end) => self.metadata.point_count() as i64 + from_end,
-            SeekFrom::Current(from_current) => self.current_point_index as i64 + from_current,
-        };
-        if new_position < 0 {
-            panic!("RawLASReader::seek_point: It is an error to seek to a point position smaller than zero!");
+    /// Like [`Self::seek_to_gps_time`], but scans all point records from the start of the file, so it also works for
+    /// files that are not sorted by GPS time
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the point format of the file has no GPS time
+    pub fn seek_to_gps_time_linear(&mut self, gps_time: f64) -> Result<usize> {
+        let gps_time_offset = self.gps_time_offset()?;
+        let point_count = self.metadata.point_count();
+        let size_of_point = self.size_of_point_in_file as usize;
+        const CHUNK_BYTES: usize = 1 << 20; // 1 MiB
+        let points_per_chunk = (CHUNK_BYTES / size_of_point).max(1);
+        if self.read_buffer.len() < points_per_chunk * size_of_point {
+            self.read_buffer.resize(points_per_chunk * size_of_point, 0);
         }
-        let clamped_position =
-            std::cmp::min(self.metadata.point_count() as i64, new_position) as usize;
 
-        if self.current_point_index != clamped_position {
-            let position_within_file = self.offset_to_first_point_in_file
-                + clamped_position as u64 * self.size_of_point_in_file;
-            self.reader.seek(SeekFrom::Start(position_within_file))?;
-            self.current_point_index = clamped_position;
+        self.reader
+            .seek(SeekFrom::Start(self.offset_to_first_point_in_file))?;
+        let mut first_point_in_chunk = 0;
+        let mut matching_point = point_count;
+        while first_point_in_chunk < point_count {
+            let points_in_chunk = (point_count - first_point_in_chunk).min(points_per_chunk);
+            let chunk = &mut self.read_buffer[..points_in_chunk * size_of_point];
+            self.reader
+                .read_exact(chunk)
+                .context("Failed to read chunk of points")?;
+            let index_in_chunk = chunk.chunks_exact(size_of_point).position(|record| {
+                let gps_time_bytes = &record[gps_time_offset..gps_time_offset + 8];
+                f64::from_le_bytes(gps_time_bytes.try_into().unwrap()) >= gps_time
+            });
+            if let Some(index_in_chunk) = index_in_chunk {
+                matching_point = first_point_in_chunk + index_in_chunk;
+                break;
+            }
+            first_point_in_chunk += points_in_chunk;
         }
 
-        Ok(self.current_point_index)
+        self.move_to_point(matching_point)?;
+        Ok(matching_point)
     }
-}
 
-pub struct RawLAZReader<'a, T: Read + Seek + Send + 'a> {
-    reader: LasZipDecompressor<'a, T>,
-    metadata: LASMetadata,
-    layout: PointLayout,
-    las_point_records_layout: PointLayout,
-    current_point_index: usize,
-    size_of_point_in_file: u64,
-}
+    fn gps_time_offset(&self) -> Result<usize> {
+        self.las_point_records_layout
+            .get_attribute(&GPS_TIME)
+            .map(|attribute| attribute.offset() as usize)
+            .context("The point format of the LAS file has no GPS time")
+    }
 
-impl<'a, T: Read + Seek + Send + 'a> RawLAZReader<'a, T> {
-    pub fn from_read(mut read: T, point_layout_matches_memory_layout: bool) -> Result<Self> {
-        let raw_header = raw::Header::read_from(&mut read)?;
-        let offset_to_first_point_in_file = raw_header.offset_to_point_data as u64;
-        let size_of_point_in_file = raw_header.point_data_record_length as u64;
-        let number_of_vlrs = raw_header.number_of_variable_length_records;
+    fn read_gps_time_of_point(
+        &mut self,
+        point_index: usize,
+        gps_time_offset: usize,
+    ) -> Result<f64> {
+        let position_within_file = self.offset_to_first_point_in_file
+            + point_index as u64 * self.size_of_point_in_file
+            + gps_time_offset as u64;
+        self.reader.seek(SeekFrom::Start(position_within_file))?;
+        Ok(self.reader.read_f64::<LittleEndian>()?)
+    }
 
-        let mut header_builder = Builder::new(raw_header)?;
-        // Read VLRs
-        for _ in 0..number_of_vlrs {
-            let vlr = las_rs::raw::Vlr::read_from(&mut read, false).map(Vlr::new)?;
-            header_builder.vlrs.push(vlr);
-        }
-        // TODO Read EVLRs
+    /// Moves the underlying reader to the point record at `point_index`, regardless of where the underlying reader
+    /// currently is
+    fn move_to_point(&mut self, point_index: usize) -> Result<()> {
+        let position_within_file =
+            self.offset_to_first_point_in_file + point_index as u64 * self.size_of_point_in_file;
+        self.reader.seek(SeekFrom::Start(position_within_file))?;
+        self.current_point_index = point_index;
+        Ok(())
+    }
 
-        // Put padding bytes into header (e.g. from leftover VLRs that have been deleted but not removed from the file)
-        let position_after_reading_vlrs = read.stream_position()?;
-        if position_after_reading_vlrs < offset_to_first_point_in_file {
-            read.by_ref()
-                .take(offset_to_first_point_in_file - position_after_reading_vlrs)
-                .read_to_end(&mut header_builder.vlr_padding)?;
+    /// Reads the binary record of the point at `point_index`, regardless of the filters of this reader. Afterwards, the
+    /// reader is at the same point as before
+    pub(crate) fn read_point_record(&mut self, point_index: usize) -> Result<Vec<u8>> {
+        let point_count = self.metadata.point_count();
+        if point_index >= point_count {
+            bail!(
+                "Point index {} is out of bounds for a file with {} points",
+                point_index,
+                point_count
+            );
         }
+        let previous_point_index = self.current_point_index;
+        self.seek_point(SeekFrom::Start(point_index as u64))?;
+        let mut record = vec![0; self.las_point_records_layout.size_of_point_entry() as usize];
+        let layout = self.las_point_records_layout.clone();
+        let result =
+            self.read_into_default_layout(&mut ExternalMemoryBuffer::new(&mut record, layout), 1);
+        self.seek_point(SeekFrom::Start(previous_point_index as u64))?;
+        result?;
+        Ok(record)
+    }
 
-        let header = header_builder.into_header()?;
-        if header.point_format().is_extended && header.point_format().has_waveform {
-            return Err(anyhow!(
-                "Compressed LAZ files with extended formats 9 and 10 are currently not supported!"
-            ));
+    fn report_progress(&mut self, points_read: usize) {
+        if let Some(callback) = self.progress_callback.as_mut() {
+            callback(points_read, self.metadata.point_count());
         }
-
-        let metadata: LASMetadata = header
-            .clone()
-            .try_into()
-            .context("Could not parse LAS header")?;
-        let point_layout =
-            point_layout_from_las_metadata(&metadata, point_layout_matches_memory_layout)?;
-        let matching_memory_layout = point_layout_from_las_metadata(&metadata, true)?;
-
-        read.seek(SeekFrom::Start(offset_to_first_point_in_file))?;
-
-        let laszip_vlr = match header.vlrs().iter().find(|vlr| is_laszip_vlr(vlr)) {
-            None => Err(anyhow!(
-                "RawLAZReader::new: LAZ variable length record not found in file!"
-            )),
-            Some(vlr) => {
-                let laz_record =
-                    laz::las::laszip::LazVlr::from_buffer(&vlr.data).map_err(map_laz_err)?;
-                Ok(laz_record)
-            }
-        }?;
-        let reader = LasZipDecompressor::new(read, laszip_vlr).map_err(map_laz_err)?;
-
-        Ok(Self {
-            reader,
-            metadata,
-            layout: point_layout,
-            las_point_records_layout: matching_memory_layout,
-            current_point_index: 0,
-            size_of_point_in_file,
-        })
     }
 
-    pub fn las_metadata(&self) -> &LASMetadata {
-        &self.metadata
+    /// Called if the file ended after `bytes_read` bytes while reading point records from the current point onwards.
+    /// Moves the reader past all complete records that were read and returns the matching error
+    fn truncated_read_error(&mut self, bytes_read: usize) -> TruncatedLASFileError {
+        let size_of_point = self.size_of_point_in_file as usize;
+        self.current_point_index += bytes_read / size_of_point;
+        TruncatedLASFileError {
+            point_count_in_header: self.metadata.point_count() as u64,
+            available_point_count: self.current_point_index as u64,
+            bytes_in_partial_record: (bytes_read % size_of_point) as u64,
+        }
     }
 
-    fn read_into_default_layout<'b, 'c, B: BorrowedMutBuffer<'b>>(
+    /// Reads the binary point records into `point_buffer`, which must have the exact memory layout of the LAS point
+    /// records. The records are read in bulk with a single `read_exact` per chunk, all parsing of individual attributes
+    /// happens afterwards on the in-memory records (see `read_into_custom_layout`)
+    fn read_into_default_layout<'a, 'b, B: BorrowedMutBuffer<'a>>(
         &mut self,
-        point_buffer: &'c mut B,
+        point_buffer: &'b mut B,
         count: usize,
     ) -> Result<usize>
     where
-        'b: 'c,
+        'a: 'b,
     {
         let num_points_to_read = usize::min(count, self.remaining_points());
         if num_points_to_read == 0 {
@@ -505,35 +776,53 @@ impl<'a, T: Read + Seek + Send + 'a> RawLAZReader<'a, T> {
 
         if let Some(interleaved_buffer) = point_buffer.as_interleaved_mut() {
             let new_point_data = interleaved_buffer.get_point_range_mut(0..num_points_to_read);
-            self.reader
-                .decompress_many(new_point_data)
+            let bytes_read = read_point_records(&mut self.reader, new_point_data)
                 .context("Failed to read point records")?;
+            if bytes_read < new_point_data.len() {
+                // Never leave a partially read record in the buffer
+                let size_of_point = self.size_of_point_in_file as usize;
+                new_point_data[(bytes_read / size_of_point) * size_of_point..].fill(0);
+                return Err(self.truncated_read_error(bytes_read).into());
+            }
+            self.report_progress(self.current_point_index + num_points_to_read);
         } else {
             // Read point data in chunks of ~1MiB size to prevent memory problems for very large files if we were
             // to read all data in a single chunk
             const CHUNK_MEM_SIZE: usize = 1 << 20;
             let num_points_per_chunk = CHUNK_MEM_SIZE / self.size_of_point_in_file as usize;
             let num_chunks = (num_points_to_read + num_points_per_chunk - 1) / num_points_per_chunk;
-            let mut read_buffer =
-                vec![0; num_points_per_chunk * self.size_of_point_in_file as usize];
+            let size_of_chunk = num_points_per_chunk * self.size_of_point_in_file as usize;
+            if self.read_buffer.len() < size_of_chunk {
+                self.read_buffer.resize(size_of_chunk, 0);
+            }
             for chunk_idx in 0..num_chunks {
                 let bytes_in_chunk = if chunk_idx == num_chunks - 1 {
                     (num_points_to_read - (chunk_idx * num_points_per_chunk))
                         * self.size_of_point_in_file as usize
                 } else {
-                    read_buffer.len()
+                    size_of_chunk
                 };
-                let chunk_bytes = &mut read_buffer[..bytes_in_chunk];
-                self.reader
-                    .decompress_many(chunk_bytes)
+                let chunk_bytes = &mut self.read_buffer[..bytes_in_chunk];
+                let bytes_read = read_point_records(&mut self.reader, chunk_bytes)
                     .context("Failed to read chunk of points")?;
                 let first_point_in_chunk = chunk_idx * num_points_per_chunk;
-                let chunk_end = ((chunk_idx + 1) * num_points_per_chunk).min(num_points_to_read);
+                let complete_points_in_chunk = bytes_read / self.size_of_point_in_file as usize;
+                let chunk_end = first_point_in_chunk + complete_points_in_chunk;
                 // Safe because this function (`read_into_default_layout`) is only called if the buffer has the exact
-                // binary memory layout of the LAS file
+                // binary memory layout of the LAS file. Only complete records are copied into the buffer
                 unsafe {
-                    point_buffer.set_point_range(first_point_in_chunk..chunk_end, chunk_bytes);
+                    point_buffer.set_point_range(
+                        first_point_in_chunk..chunk_end,
+                        &chunk_bytes
+                            [..complete_points_in_chunk * self.size_of_point_in_file as usize],
+                    );
                 }
+                if bytes_read < bytes_in_chunk {
+                    let bytes_read_in_total =
+                        first_point_in_chunk * self.size_of_point_in_file as usize + bytes_read;
+                    return Err(self.truncated_read_error(bytes_read_in_total).into());
+                }
+                self.report_progress(self.current_point_index + chunk_end);
             }
         }
 
@@ -542,38 +831,149 @@ impl<'a, T: Read + Seek + Send + 'a> RawLAZReader<'a, T> {
         Ok(num_points_to_read)
     }
 
-    fn read_into_custom_layout<'b, 'c, B: BorrowedMutBuffer<'b>>(
+    /// Reads `count` points like [`PointReader::read_into`], but only writes the points whose world-space position
+    /// lies within `bounds` into `point_buffer`. The bounds check happens on the binary point records, so rejected
+    /// points are never converted. Returns the number of points that were written into `point_buffer`. The reader
+    /// always advances by `count` points (or the number of remaining points, if that is smaller), regardless of how
+    /// many points matched
+    pub fn read_into_filtered<'a, 'b, B: BorrowedMutBuffer<'a>>(
         &mut self,
-        point_buffer: &'c mut B,
+        point_buffer: &'b mut B,
         count: usize,
+        bounds: &AABB<f64>,
     ) -> Result<usize>
     where
-        'b: 'c,
+        'a: 'b,
     {
+        let filter = LASPointFilter {
+            bounds: Some(*bounds),
+            ..self.filter.clone()
+        };
+        self.read_into_with_filter(point_buffer, count, &filter)
+    }
+
+    fn read_into_with_filter<'a, 'b, B: BorrowedMutBuffer<'a>>(
+        &mut self,
+        point_buffer: &'b mut B,
+        count: usize,
+        filter: &LASPointFilter,
+    ) -> Result<usize>
+    where
+        'a: 'b,
+    {
+        if point_buffer.len() < count {
+            panic!("point_buffer.len() must be >= count");
+        }
+
+        let predicate = filter.record_predicate(&self.metadata)?;
+        let modifier = filter.record_modifier(&self.metadata)?;
+        let raw_layout = self.las_point_records_layout.clone();
+        let target_layout = point_buffer.point_layout().clone();
+        let converter = if target_layout != raw_layout {
+            Some(
+                get_default_las_converter(
+                    &raw_layout,
+                    &target_layout,
+                    &self.metadata,
+                    &self.options,
+                )
+                .context("Unsupported conversion")?,
+            )
+        } else {
+            None
+        };
+        read_filtered_las_points(
+            point_buffer,
+            count,
+            &raw_layout,
+            converter.as_ref(),
+            predicate,
+            modifier,
+            |raw_points, count| self.read_into_default_layout(raw_points, count),
+        )
+    }
+
+    fn read_into_custom_layout<'a, 'b, B: BorrowedMutBuffer<'a>>(
+        &mut self,
+        point_buffer: &'b mut B,
+        count: usize,
+    ) -> Result<usize> {
         let num_points_to_read = usize::min(count, self.remaining_points());
         if num_points_to_read == 0 {
             return Ok(0);
         }
 
-        let mut convert_buffer =
-            VectorBuffer::with_capacity(num_points_to_read, self.las_point_records_layout.clone());
-        convert_buffer.resize(num_points_to_read);
-        self.read_into_default_layout(&mut convert_buffer, num_points_to_read)?;
+        const CHUNK_BYTES: usize = 1 << 20; // 1 MiB
+        let points_per_chunk =
+            CHUNK_BYTES / self.las_point_records_layout.size_of_point_entry() as usize;
+        let num_chunks = (num_points_to_read + points_per_chunk - 1) / points_per_chunk;
+
+        let size_of_chunk = if num_chunks > 1 {
+            points_per_chunk
+        } else {
+            num_points_to_read
+        };
+
+        let mut convert_buffer = self.convert_buffer.take().unwrap_or_else(|| {
+            VectorBuffer::new_from_layout(self.las_point_records_layout.clone())
+        });
+        if convert_buffer.len() < size_of_chunk {
+            convert_buffer.resize(size_of_chunk);
+        }
 
+        let source_layout = self.las_point_records_layout.clone();
         let target_layout = point_buffer.point_layout().clone();
         let converter = get_default_las_converter(
-            &self.las_point_records_layout,
+            &source_layout,
             &target_layout,
-            self.metadata.raw_las_header().expect("Missing LAS header"),
+            &self.metadata,
+            &self.options,
         )
         .context("Unsupported conversion")?;
-        converter.convert_into(&convert_buffer, point_buffer);
+
+        for chunk_idx in 0..num_chunks {
+            let points_in_current_chunk = if chunk_idx == num_chunks - 1 {
+                num_points_to_read - ((num_chunks - 1) * size_of_chunk)
+            } else {
+                size_of_chunk
+            };
+
+            let first_point_of_chunk_in_file = self.current_point_index;
+            if let Err(why) =
+                self.read_into_default_layout(&mut convert_buffer, points_in_current_chunk)
+            {
+                // Convert all complete records of a truncated chunk before returning the error, so that the reader
+                // position matches the points in `point_buffer`
+                if let Some(truncation) = why.downcast_ref::<TruncatedLASFileError>() {
+                    let complete_points =
+                        truncation.available_point_count as usize - first_point_of_chunk_in_file;
+                    let target_buffer_first_point = chunk_idx * size_of_chunk;
+                    converter.convert_into_range(
+                        &convert_buffer,
+                        0..complete_points,
+                        point_buffer,
+                        target_buffer_first_point..target_buffer_first_point + complete_points,
+                    );
+                }
+                self.convert_buffer = Some(convert_buffer);
+                return Err(why);
+            }
+            let target_buffer_first_point = chunk_idx * size_of_chunk;
+            let target_buffer_last_point = target_buffer_first_point + points_in_current_chunk;
+            converter.convert_into_range(
+                &convert_buffer,
+                0..points_in_current_chunk,
+                point_buffer,
+                target_buffer_first_point..target_buffer_last_point,
+            );
+        }
+        self.convert_buffer = Some(convert_buffer);
 
         Ok(num_points_to_read)
     }
 }
 
-impl<'a, T: Read + Seek + Send + 'a> LASReaderBase for RawLAZReader<'a, T> {
+impl<T: Read + Seek> LASReaderBase for RawLASReader<T> {
     fn remaining_points(&self) -> usize {
         self.metadata.point_count() - self.current_point_index
     }
@@ -583,20 +983,23 @@ impl<'a, T: Read + Seek + Send + 'a> LASReaderBase for RawLAZReader<'a, T> {
     }
 }
 
-impl<'a, T: Read + Seek + Send + 'a> PointReader for RawLAZReader<'a, T> {
-    fn read_into<'b, 'c, B: BorrowedMutBuffer<'b>>(
+impl<T: Read + Seek> PointReader for RawLASReader<T> {
+    fn read_into<'a, 'b, B: BorrowedMutBuffer<'a>>(
         &mut self,
-        point_buffer: &'c mut B,
+        point_buffer: &'b mut B,
         count: usize,
     ) -> Result<usize>
     where
-        'b: 'c,
+        'a: 'b,
     {
         if point_buffer.len() < count {
             panic!("point_buffer.len() must be >= count");
         }
 
-        if *point_buffer.point_layout() != self.las_point_records_layout {
+        if !self.filter.is_empty() {
+            let filter = self.filter.clone();
+            self.read_into_with_filter(point_buffer, count, &filter)
+        } else if *point_buffer.point_layout() != self.las_point_records_layout {
             self.read_into_custom_layout(point_buffer, count)
         } else {
             self.read_into_default_layout(point_buffer, count)
@@ -610,9 +1013,21 @@ impl<'a, T: Read + Seek + Send + 'a> PointReader for RawLAZReader<'a, T> {
     fn get_default_point_layout(&self) -> &PointLayout {
         &self.layout
     }
+
+    fn skip(&mut self, count: usize) -> Result<usize> {
+        skip_points_by_seeking(self, count)
+    }
+
+    fn point_index(&self) -> Option<usize> {
+        Some(self.current_point_index)
+    }
+
+    fn point_count(&self) -> Option<usize> {
+        Some(self.metadata.point_count())
+    }
 }
 
-impl<'a, T: Read + Seek + Send + 'a> SeekToPoint for RawLAZReader<'a, T> {
+impl<T: Read + Seek> SeekToPoint for RawLASReader<T> {
     fn seek_point(&mut self, position: SeekFrom) -> Result<usize> {
         let new_position = match position {
             SeekFrom::Start(from_start) => from_start as i64,
@@ -620,13 +1035,15 @@ impl<'a, T: Read + Seek + Send + 'a> SeekToPoint for RawLAZReader<'a, T> {
             SeekFrom::Current(from_current) => self.current_point_index as i64 + from_current,
         };
         if new_position < 0 {
-            panic!("RawLAZReader::seek_point: It is an error to seek to a point position smaller than zero!");
+            panic!("RawLASReader::seek_point: It is an error to seek to a point position smaller than zero!");
         }
         let clamped_position =
             std::cmp::min(self.metadata.point_count() as i64, new_position) as usize;
 
         if self.current_point_index != clamped_position {
-            self.reader.seek(clamped_position as u64)?;
+            let position_within_file = self.offset_to_first_point_in_file
+                + clamped_position as u64 * self.size_of_point_in_file;
+            self.reader.seek(SeekFrom::Start(position_within_file))?;
             self.current_point_index = clamped_position;
         }
 
@@ -636,21 +1053,34 @@ impl<'a, T: Read + Seek + Send + 'a> SeekToPoint for RawLAZReader<'a, T> {
 
 #[cfg(test)]
 mod tests {
+    use std::borrow::Cow;
+    use std::io::Cursor;
     use std::{fs::File, io::BufReader};
 
     use las_rs::point::Format;
-    use pasture_core::containers::BorrowedBuffer;
+    use pasture_core::containers::{
+        BorrowedBuffer, HashMapBuffer, InterleavedBuffer, MakeBufferFromLayout,
+    };
     use pasture_core::layout::attributes;
-    use pasture_core::layout::PointAttributeDataType;
-    use pasture_core::nalgebra::Vector3;
+    use pasture_core::layout::{PointAttributeDataType, PointAttributeDefinition};
+    use pasture_core::math::AABB;
+    use pasture_core::nalgebra::{Point3, Vector3};
 
+    use crate::base::PointIterator;
     use crate::las::get_test_las_path_with_extra_bytes;
     use crate::las::{
         compare_to_reference_data, compare_to_reference_data_range, get_test_las_path,
-        get_test_laz_path, test_data_bounds, test_data_classifications, test_data_colors,
+        make_las_file_in_memory, make_lax_index_in_memory, test_data_bounds,
+        test_data_classifications, test_data_colors, test_data_gps_times, test_data_intensities,
         test_data_point_count, test_data_point_source_ids, test_data_positions,
-        test_data_wavepacket_parameters,
+        test_data_scan_angle_ranks, test_data_scan_angles_extended,
+        test_data_wavepacket_parameters, ExtraBytesDataType, ExtraBytesEntryBuilder, ExtraBytesVlr,
+        LASReader, LasPointFormat0, OutOfBoundsHandling, ValidationViolation,
+        ATTRIBUTE_SCAN_ANGLE_DEGREES,
     };
+    #[cfg(feature = "laz")]
+    use crate::las::{get_test_laz_path, RawLAZReader};
+    use crate::las::{point_layout_from_las_point_format_raw, ATTRIBUTE_LOCAL_LAS_POSITION};
 
     use super::*;
 
@@ -684,8 +1114,8 @@ mod tests {
                     let mut reader = $reader::from_read(read, false)?;
 
                     assert_eq!(reader.remaining_points(), test_data_point_count());
-                    assert_eq!(reader.point_count()?, test_data_point_count());
-                    assert_eq!(reader.point_index()?, 0);
+                    assert_eq!(Some(test_data_point_count()), reader.point_count());
+                    assert_eq!(Some(0), reader.point_index());
 
                     let layout = reader.get_default_point_layout();
                     let expected_layout =
@@ -711,7 +1141,7 @@ mod tests {
                     assert_eq!(*points.point_layout(), expected_layout);
                     compare_to_reference_data(&points, format);
 
-                    assert_eq!(10, reader.point_index()?);
+                    assert_eq!(Some(10), reader.point_index());
                     assert_eq!(0, reader.remaining_points());
 
                     Ok(())
@@ -730,7 +1160,7 @@ mod tests {
                     reader.read_into(&mut buffer, 10)?;
                     compare_to_reference_data(&buffer, format);
 
-                    assert_eq!(10, reader.point_index()?);
+                    assert_eq!(Some(10), reader.point_index());
                     assert_eq!(0, reader.remaining_points());
 
                     Ok(())
@@ -758,7 +1188,7 @@ mod tests {
                         );
                     }
 
-                    assert_eq!(test_data_point_count(), reader.point_index()?);
+                    assert_eq!(Some(test_data_point_count()), reader.point_index());
                     assert_eq!(0, reader.remaining_points());
 
                     Ok(())
@@ -777,7 +1207,7 @@ mod tests {
                     reader.read_into(&mut buffer, 10)?;
                     compare_to_reference_data(&buffer, format);
 
-                    assert_eq!(10, reader.point_index()?);
+                    assert_eq!(Some(10), reader.point_index());
                     assert_eq!(0, reader.remaining_points());
 
                     Ok(())
@@ -805,7 +1235,7 @@ mod tests {
                         );
                     }
 
-                    assert_eq!(test_data_point_count(), reader.point_index()?);
+                    assert_eq!(Some(test_data_point_count()), reader.point_index());
                     assert_eq!(0, reader.remaining_points());
 
                     Ok(())
@@ -904,7 +1334,7 @@ mod tests {
                         "Wavepacket parameters do not match"
                     );
 
-                    assert_eq!(10, reader.point_index()?);
+                    assert_eq!(Some(10), reader.point_index());
                     assert_eq!(0, reader.remaining_points());
 
                     Ok(())
@@ -1011,12 +1441,91 @@ mod tests {
                         "Wavepacket parameters do not match"
                     );
 
-                    assert_eq!(10, reader.point_index()?);
+                    assert_eq!(Some(10), reader.point_index());
                     assert_eq!(0, reader.remaining_points());
 
                     Ok(())
                 }
 
+                #[test]
+                fn test_raw_las_reader_read_local_positions() -> Result<()> {
+                    let read = BufReader::new(File::open(get_test_file_path())?);
+                    let mut reader = $reader::from_read_with_options(
+                        read,
+                        LASReadOptions {
+                            convert_positions_to_world_space: false,
+                            ..Default::default()
+                        },
+                    )?;
+
+                    let position_attribute = reader
+                        .get_default_point_layout()
+                        .get_attribute_by_name(attributes::POSITION_3D.name())
+                        .expect("No POSITION_3D attribute found")
+                        .clone();
+                    assert_eq!(
+                        PointAttributeDataType::Vec3i32,
+                        position_attribute.datatype()
+                    );
+
+                    let transforms = *reader.header().transforms();
+                    let points = reader.read::<VectorBuffer>(10)?;
+                    let positions = points
+                        .view_attribute::<Vector3<i32>>(position_attribute.attribute_definition())
+                        .into_iter()
+                        .collect::<Vec<_>>();
+                    let expected_positions = test_data_positions()
+                        .into_iter()
+                        .map(|p| {
+                            Vector3::new(
+                                ((p.x - transforms.x.offset) / transforms.x.scale).round() as i32,
+                                ((p.y - transforms.y.offset) / transforms.y.scale).round() as i32,
+                                ((p.z - transforms.z.offset) / transforms.z.scale).round() as i32,
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    assert_eq!(expected_positions, positions);
+
+                    Ok(())
+                }
+
+                #[test]
+                fn test_raw_las_reader_scan_angle_in_degrees() -> Result<()> {
+                    let read = BufReader::new(File::open(get_test_file_path())?);
+                    let mut reader = $reader::from_read_with_options(
+                        read,
+                        LASReadOptions {
+                            scan_angle_in_degrees: true,
+                            ..Default::default()
+                        },
+                    )?;
+                    let format = Format::new($format)?;
+
+                    let layout = reader.get_default_point_layout();
+                    assert!(layout.has_attribute(&ATTRIBUTE_SCAN_ANGLE_DEGREES));
+                    assert!(!layout.has_attribute_with_name(attributes::SCAN_ANGLE_RANK.name()));
+
+                    let points = reader.read::<VectorBuffer>(10)?;
+                    let scan_angles = points
+                        .view_attribute::<f32>(&ATTRIBUTE_SCAN_ANGLE_DEGREES)
+                        .into_iter()
+                        .collect::<Vec<_>>();
+                    let expected_scan_angles = if format.is_extended {
+                        test_data_scan_angles_extended()
+                            .into_iter()
+                            .map(|angle| (angle as f64 * 0.006) as f32)
+                            .collect::<Vec<_>>()
+                    } else {
+                        test_data_scan_angle_ranks()
+                            .into_iter()
+                            .map(|angle| angle as f32)
+                            .collect::<Vec<_>>()
+                    };
+                    assert_eq!(expected_scan_angles, scan_angles);
+
+                    Ok(())
+                }
+
                 #[test]
                 fn test_raw_las_reader_seek() -> Result<()> {
                     let read = BufReader::new(File::open(get_test_file_path())?);
@@ -1035,6 +1544,351 @@ mod tests {
                     Ok(())
                 }
 
+                #[test]
+                fn test_raw_las_reader_read_filtered_by_bounds() -> Result<()> {
+                    let read = BufReader::new(File::open(get_test_file_path())?);
+                    let mut reader = $reader::from_read(read, false)?;
+                    let format = Format::new($format)?;
+
+                    let bounds =
+                        AABB::from_min_max(Point3::new(2.5, 2.5, 2.5), Point3::new(6.5, 6.5, 6.5));
+                    let mut points =
+                        VectorBuffer::new_from_layout(reader.get_default_point_layout().clone());
+                    points.resize(10);
+                    let num_matching_points =
+                        reader.read_into_filtered(&mut points, 10, &bounds)?;
+                    assert_eq!(4, num_matching_points);
+                    points.resize(num_matching_points);
+                    compare_to_reference_data_range(&points, format, 3..7);
+
+                    // Filtering does not change the position within the file
+                    assert_eq!(Some(10), reader.point_index());
+                    assert_eq!(0, reader.remaining_points());
+
+                    // Filtering also works with the exact binary layout of the file
+                    reader.seek_point(SeekFrom::Start(0))?;
+                    let mut raw_points = VectorBuffer::new_from_layout(
+                        point_layout_from_las_metadata(reader.las_metadata(), true)?,
+                    );
+                    raw_points.resize(10);
+                    assert_eq!(4, reader.read_into_filtered(&mut raw_points, 10, &bounds)?);
+
+                    Ok(())
+                }
+
+                #[test]
+                fn test_raw_las_reader_read_raw_records() -> Result<()> {
+                    let read = BufReader::new(File::open(get_test_file_path())?);
+                    let mut reader = $reader::from_read(read, false)?;
+                    let format = Format::new($format)?;
+                    let transforms = *reader
+                        .las_metadata()
+                        .raw_las_header()
+                        .expect("Missing LAS header")
+                        .transforms();
+
+                    let count = test_data_point_count();
+                    let mut points = VectorBuffer::new_from_layout(
+                        point_layout_from_las_point_format_raw(&format)?,
+                    );
+                    points.resize(count);
+                    assert_eq!(count, reader.read_into(&mut points, count)?);
+
+                    let positions = points
+                        .view_attribute::<Vector3<i32>>(&ATTRIBUTE_LOCAL_LAS_POSITION)
+                        .into_iter()
+                        .map(|local| {
+                            Vector3::new(
+                                transforms.x.direct(local.x),
+                                transforms.y.direct(local.y),
+                                transforms.z.direct(local.z),
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    assert_eq!(test_data_positions(), positions);
+                    let intensities = points
+                        .view_attribute::<u16>(&attributes::INTENSITY)
+                        .into_iter()
+                        .collect::<Vec<_>>();
+                    assert_eq!(test_data_intensities(), intensities);
+
+                    Ok(())
+                }
+
+                #[test]
+                fn test_raw_las_reader_validate() -> Result<()> {
+                    let read = BufReader::new(File::open(get_test_file_path())?);
+                    let mut reader = $reader::from_read(read, false)?;
+                    reader.seek_point(SeekFrom::Start(3))?;
+
+                    let report = reader.validate(&ValidationOptions {
+                        check_gps_time_monotonicity: true,
+                        ..Default::default()
+                    })?;
+                    assert!(report.is_valid(), "{}", report);
+                    assert_eq!(test_data_point_count(), report.point_count());
+                    // Validating does not change the position of the reader
+                    assert_eq!(Some(3), reader.point_index());
+
+                    Ok(())
+                }
+
+                #[test]
+                fn test_raw_las_reader_read_bounds() -> Result<()> {
+                    let read = BufReader::new(File::open(get_test_file_path())?);
+                    let mut reader = $reader::from_read(read, false)?;
+                    let format = Format::new($format)?;
+                    assert!(reader.spatial_index().is_none());
+
+                    // Without a spatial index, all points are scanned
+                    let bounds =
+                        AABB::from_min_max(Point3::new(2.5, 2.5, 2.5), Point3::new(4.9, 4.9, 4.9));
+                    let mut points =
+                        VectorBuffer::new_from_layout(reader.get_default_point_layout().clone());
+                    assert_eq!(2, reader.read_bounds(&bounds, &mut points)?);
+                    compare_to_reference_data_range(&points, format, 3..5);
+
+                    // The test points lie on the diagonal, so with a single-level quadtree, the first five points are
+                    // in the lower left cell and the other points in the upper right cell
+                    let lax_data = make_lax_index_in_memory(
+                        1,
+                        [0.0, 10.0, 0.0, 10.0],
+                        &[(1, &[(0, 4)]), (4, &[(5, 9)])],
+                    );
+                    reader.set_spatial_index(Some(LAXIndex::read_from(&lax_data[..])?));
+                    let mut points =
+                        VectorBuffer::new_from_layout(reader.get_default_point_layout().clone());
+                    assert_eq!(2, reader.read_bounds(&bounds, &mut points)?);
+                    compare_to_reference_data_range(&points, format, 3..5);
+
+                    // Only the candidate points of the index are read, so an index that misses point 4 will not find it
+                    let lax_data = make_lax_index_in_memory(
+                        1,
+                        [0.0, 10.0, 0.0, 10.0],
+                        &[(1, &[(0, 3)]), (4, &[(5, 9)])],
+                    );
+                    reader.set_spatial_index(Some(LAXIndex::read_from(&lax_data[..])?));
+                    let mut points =
+                        VectorBuffer::new_from_layout(reader.get_default_point_layout().clone());
+                    assert_eq!(1, reader.read_bounds(&bounds, &mut points)?);
+                    compare_to_reference_data_range(&points, format, 3..4);
+
+                    Ok(())
+                }
+
+                #[test]
+                fn test_raw_las_reader_classification_filter() -> Result<()> {
+                    let read = BufReader::new(File::open(get_test_file_path())?);
+                    let mut reader = $reader::from_read(read, false)?;
+                    reader.set_classification_filter(&[2, 5, 7]);
+
+                    let points = reader.read::<VectorBuffer>(10)?;
+                    assert_eq!(3, points.len());
+                    let classifications = points
+                        .view_attribute::<u8>(&attributes::CLASSIFICATION)
+                        .into_iter()
+                        .collect::<Vec<_>>();
+                    assert_eq!(vec![2, 5, 7], classifications);
+                    assert_eq!(0, reader.remaining_points());
+
+                    // An empty filter accepts all points again
+                    reader.set_classification_filter(&[]);
+                    reader.seek_point(SeekFrom::Start(0))?;
+                    assert_eq!(10, reader.read::<VectorBuffer>(10)?.len());
+
+                    Ok(())
+                }
+
+                #[test]
+                fn test_raw_las_reader_return_filter() -> Result<()> {
+                    let read = BufReader::new(File::open(get_test_file_path())?);
+                    let mut reader = $reader::from_read(read, false)?;
+                    let format = Format::new($format)?;
+
+                    // Return numbers in the test data are 0,1,...,7,0,1 for legacy formats and 0,1,...,9 for
+                    // extended formats, and the number of returns always equals the return number
+                    reader.set_return_filter(ReturnFilter::FirstOnly);
+                    let points = reader.read::<VectorBuffer>(10)?;
+                    let expected_first_returns = if format.is_extended { 1 } else { 2 };
+                    assert_eq!(expected_first_returns, points.len());
+                    assert!(points
+                        .view_attribute::<u8>(&attributes::RETURN_NUMBER)
+                        .into_iter()
+                        .all(|return_number| return_number == 1));
+
+                    reader.set_return_filter(ReturnFilter::LastOnly);
+                    reader.seek_point(SeekFrom::Start(0))?;
+                    assert_eq!(10, reader.read::<VectorBuffer>(10)?.len());
+
+                    reader.set_return_filter(ReturnFilter::Specific(3));
+                    reader.seek_point(SeekFrom::Start(0))?;
+                    let points = reader.read::<VectorBuffer>(10)?;
+                    assert_eq!(1, points.len());
+                    compare_to_reference_data_range(&points, format, 3..4);
+
+                    Ok(())
+                }
+
+                #[test]
+                fn test_raw_las_reader_gps_time_filter() -> Result<()> {
+                    let read = BufReader::new(File::open(get_test_file_path())?);
+                    let mut reader = $reader::from_read(read, false)?;
+                    let format = Format::new($format)?;
+
+                    if !format.has_gps_time {
+                        assert!(reader.set_gps_time_range(0.0, 10.0).is_err());
+                        return Ok(());
+                    }
+
+                    assert!(reader.set_gps_time_range(5.0, 1.0).is_err());
+
+                    let gps_times = test_data_gps_times();
+                    reader.set_gps_time_range(gps_times[2], gps_times[5])?;
+                    let points = reader.read::<VectorBuffer>(10)?;
+                    assert_eq!(4, points.len());
+                    compare_to_reference_data_range(&points, format, 2..6);
+
+                    Ok(())
+                }
+
+                #[test]
+                fn test_raw_las_reader_read_8bit_colors() -> Result<()> {
+                    if !Format::new($format)?.has_color {
+                        return Ok(());
+                    }
+
+                    let conversions: [(ColorConversion, fn(u16) -> u8); 3] = [
+                        (ColorConversion::Truncate, |channel| channel as u8),
+                        (ColorConversion::DivideBy257, |channel| {
+                            (channel / 257) as u8
+                        }),
+                        (ColorConversion::ShiftBy8, |channel| (channel >> 8) as u8),
+                    ];
+                    for (color_conversion, convert_channel) in conversions {
+                        let read = BufReader::new(File::open(get_test_file_path())?);
+                        let mut reader = $reader::from_read_with_options(
+                            read,
+                            LASReadOptions {
+                                color_conversion,
+                                ..Default::default()
+                            },
+                        )?;
+
+                        let colors =
+                            reader.read_attribute::<Vector3<u8>>(&attributes::COLOR_RGB, 10)?;
+                        let expected_colors = test_data_colors()
+                            .into_iter()
+                            .map(|color| color.map(convert_channel))
+                            .collect::<Vec<_>>();
+                        assert_eq!(
+                            expected_colors, colors,
+                            "Colors do not match for {color_conversion:?}"
+                        );
+                    }
+
+                    Ok(())
+                }
+
+                #[test]
+                fn test_raw_las_reader_normalize_intensity() -> Result<()> {
+                    let read = BufReader::new(File::open(get_test_file_path())?);
+                    let mut reader = $reader::from_read(read, false)?;
+                    let raw_intensities =
+                        reader.read_attribute::<f32>(&attributes::INTENSITY, 10)?;
+                    let expected_raw_intensities = test_data_intensities()
+                        .into_iter()
+                        .map(|intensity| intensity as f32)
+                        .collect::<Vec<_>>();
+                    assert_eq!(expected_raw_intensities, raw_intensities);
+
+                    let read = BufReader::new(File::open(get_test_file_path())?);
+                    let mut reader = $reader::from_read_with_options(
+                        read,
+                        LASReadOptions {
+                            normalize_intensity: true,
+                            ..Default::default()
+                        },
+                    )?;
+                    assert_eq!(
+                        PointAttributeDataType::U16,
+                        reader
+                            .get_default_point_layout()
+                            .get_attribute_by_name(attributes::INTENSITY.name())
+                            .expect("No INTENSITY attribute found")
+                            .datatype()
+                    );
+                    let normalized_intensities =
+                        reader.read_attribute::<f32>(&attributes::INTENSITY, 10)?;
+                    let expected_normalized_intensities = test_data_intensities()
+                        .into_iter()
+                        .map(|intensity| intensity as f32 / u16::MAX as f32)
+                        .collect::<Vec<_>>();
+                    assert_eq!(expected_normalized_intensities, normalized_intensities);
+
+                    Ok(())
+                }
+
+                #[test]
+                fn test_raw_las_reader_read_attribute() -> Result<()> {
+                    let read = BufReader::new(File::open(get_test_file_path())?);
+                    let mut reader = $reader::from_read(read, false)?;
+
+                    let classifications =
+                        reader.read_attribute::<u8>(&attributes::CLASSIFICATION, 10)?;
+                    assert_eq!(test_data_classifications(), classifications);
+                    assert_eq!(0, reader.remaining_points());
+
+                    // Reading with a different datatype than the default one converts the values
+                    reader.seek_point(SeekFrom::Start(0))?;
+                    let positions =
+                        reader.read_attribute::<Vector3<f32>>(&attributes::POSITION_3D, 20)?;
+                    let expected_positions = test_data_positions()
+                        .into_iter()
+                        .map(|position| position.cast::<f32>())
+                        .collect::<Vec<_>>();
+                    assert_eq!(expected_positions, positions);
+
+                    Ok(())
+                }
+
+                #[test]
+                fn test_raw_las_reader_points_by_return() -> Result<()> {
+                    let read = BufReader::new(File::open(get_test_file_path())?);
+                    let mut reader = $reader::from_read(read, false)?;
+                    let points_by_return = reader.las_metadata().points_by_return().to_vec();
+                    assert_eq!(15, points_by_return.len());
+
+                    let points = reader.read::<VectorBuffer>(test_data_point_count())?;
+                    let mut expected_points_by_return = vec![0; 15];
+                    for return_number in points.view_attribute::<u8>(&attributes::RETURN_NUMBER) {
+                        if return_number > 0 {
+                            expected_points_by_return[return_number as usize - 1] += 1;
+                        }
+                    }
+                    assert_eq!(expected_points_by_return, points_by_return);
+
+                    Ok(())
+                }
+
+                #[test]
+                fn test_raw_las_reader_read_all() -> Result<()> {
+                    let read = BufReader::new(File::open(get_test_file_path())?);
+                    let mut reader = $reader::from_read(read, false)?;
+                    let format = Format::new($format)?;
+
+                    let points = reader.read_all::<VectorBuffer>()?;
+                    assert_eq!(test_data_point_count(), points.len());
+                    compare_to_reference_data(&points, format);
+
+                    // Only the remaining points are read
+                    reader.seek_point(SeekFrom::Start(3))?;
+                    let points = reader.read_all::<HashMapBuffer>()?;
+                    assert_eq!(test_data_point_count() - 3, points.len());
+                    compare_to_reference_data_range(&points, format, 3..10);
+
+                    Ok(())
+                }
+
                 #[test]
                 fn test_raw_las_reader_seek_out_of_bounds() -> Result<()> {
                     let read = BufReader::new(File::open(get_test_file_path())?);
@@ -1064,7 +1918,7 @@ mod tests {
                     assert_eq!(*points.point_layout(), expected_layout);
                     compare_to_reference_data(&points, format);
 
-                    assert_eq!(10, reader.point_index()?);
+                    assert_eq!(Some(10), reader.point_index());
                     assert_eq!(0, reader.remaining_points());
 
                     Ok(())
@@ -1085,19 +1939,567 @@ mod tests {
     test_read_with_format!(las_format_9, 9, RawLASReader, get_test_las_path);
     test_read_with_format!(las_format_10, 10, RawLASReader, get_test_las_path);
 
+    #[cfg(feature = "laz")]
     test_read_with_format!(laz_format_0, 0, RawLAZReader, get_test_laz_path);
+    #[cfg(feature = "laz")]
     test_read_with_format!(laz_format_1, 1, RawLAZReader, get_test_laz_path);
+    #[cfg(feature = "laz")]
     test_read_with_format!(laz_format_2, 2, RawLAZReader, get_test_laz_path);
+    #[cfg(feature = "laz")]
     test_read_with_format!(laz_format_3, 3, RawLAZReader, get_test_laz_path);
+    #[cfg(feature = "laz")]
     test_read_with_format!(laz_format_4, 4, RawLAZReader, get_test_laz_path);
+    #[cfg(feature = "laz")]
     test_read_with_format!(laz_format_5, 5, RawLAZReader, get_test_laz_path);
 
-    // There is currently a bug in `laz-rs` when seeking into files with point record format 6 or higher, so they are
-    // still unsupported in pasture. See this issue here: https://github.com/laz-rs/laz-rs/issues/46
+    #[cfg(feature = "laz")]
+    test_read_with_format!(laz_format_6, 6, RawLAZReader, get_test_laz_path);
+    #[cfg(feature = "laz")]
+    test_read_with_format!(laz_format_7, 7, RawLAZReader, get_test_laz_path);
+    #[cfg(feature = "laz")]
+    test_read_with_format!(laz_format_8, 8, RawLAZReader, get_test_laz_path);
+    #[cfg(feature = "laz")]
+    test_read_with_format!(laz_format_9, 9, RawLAZReader, get_test_laz_path);
+    #[cfg(feature = "laz")]
+    test_read_with_format!(laz_format_10, 10, RawLAZReader, get_test_laz_path);
+
+    #[cfg(feature = "laz-parallel")]
+    #[test]
+    fn test_raw_laz_reader_parallel_matches_sequential() -> Result<()> {
+        for format in 0..=10 {
+            let mut sequential_reader = RawLAZReader::from_read(
+                BufReader::new(File::open(get_test_laz_path(format))?),
+                false,
+            )?;
+            let mut parallel_reader = RawLAZReader::from_read_parallel(
+                BufReader::new(File::open(get_test_laz_path(format))?),
+                2,
+                false,
+            )?;
+
+            let expected_points = sequential_reader.read::<VectorBuffer>(10)?;
+            let actual_points = parallel_reader.read::<VectorBuffer>(10)?;
+            assert_eq!(expected_points, actual_points, "Format {}", format);
+
+            parallel_reader.seek_point(SeekFrom::Start(5))?;
+            let points_after_seek = parallel_reader.read::<VectorBuffer>(5)?;
+            compare_to_reference_data_range(&points_after_seek, Format::new(format)?, 5..10);
+        }
+
+        Ok(())
+    }
+
+    fn make_las_file_with_scaled_extra_bytes(
+        raw_heights: &[u16],
+        scale: f64,
+        offset: f64,
+    ) -> Result<Vec<u8>> {
+        let mut header_builder = Builder::from((1, 4));
+        header_builder.point_format = Format::new(0)?;
+        header_builder.point_format.extra_bytes = 2;
+        let extra_bytes_vlr: ExtraBytesVlr = std::iter::once(
+            ExtraBytesEntryBuilder::new(
+                ExtraBytesDataType::U16,
+                "HeightAboveGround".into(),
+                "Height above ground".into(),
+            )
+            .with_scale(scale)
+            .with_offset(offset)
+            .build(),
+        )
+        .collect();
+        header_builder.vlrs.push((&extra_bytes_vlr).try_into()?);
+        let header = header_builder.into_header()?;
+
+        let mut point_records = vec![];
+        for height in raw_heights {
+            point_records.extend_from_slice(&[0; 20]);
+            point_records.extend_from_slice(&height.to_le_bytes());
+        }
+        make_las_file_in_memory(&header, raw_heights.len(), &point_records)
+    }
+
+    #[test]
+    fn test_raw_las_reader_legacy_classification_flags() -> Result<()> {
+        let mut header_builder = Builder::from((1, 2));
+        header_builder.point_format = Format::new(0)?;
+        let header = header_builder.into_header()?;
+        // Second point is a withheld ground point, third point is a synthetic and key-point building point
+        let mut point_records = vec![0; 60];
+        point_records[15] = 2;
+        point_records[20 + 15] = 2 | 0b100_00000;
+        point_records[40 + 15] = 6 | 0b011_00000;
+        let las_data = make_las_file_in_memory(&header, 3, &point_records)?;
+
+        let mut reader = RawLASReader::from_read(Cursor::new(las_data.clone()), false)?;
+        let points = reader.read::<VectorBuffer>(3)?;
+        let classifications = points
+            .view_attribute::<u8>(&attributes::CLASSIFICATION)
+            .into_iter()
+            .collect::<Vec<_>>();
+        assert_eq!(vec![2, 2, 6], classifications);
+        let classification_flags = points
+            .view_attribute::<u8>(&attributes::CLASSIFICATION_FLAGS)
+            .into_iter()
+            .collect::<Vec<_>>();
+        assert_eq!(vec![0, 0b100, 0b011], classification_flags);
+
+        // Reading into a custom layout goes through the same conversion
+        let custom_layout = PointLayout::from_attributes(&[
+            attributes::CLASSIFICATION_FLAGS,
+            attributes::CLASSIFICATION.with_custom_datatype(PointAttributeDataType::U16),
+        ]);
+        let mut reader = RawLASReader::from_read(Cursor::new(las_data), false)?;
+        let mut points = VectorBuffer::new_from_layout(custom_layout);
+        points.resize(3);
+        reader.read_into(&mut points, 3)?;
+        let classifications = points
+            .view_attribute::<u16>(
+                &attributes::CLASSIFICATION.with_custom_datatype(PointAttributeDataType::U16),
+            )
+            .into_iter()
+            .collect::<Vec<_>>();
+        assert_eq!(vec![2, 2, 6], classifications);
+        let classification_flags = points
+            .view_attribute::<u8>(&attributes::CLASSIFICATION_FLAGS)
+            .into_iter()
+            .collect::<Vec<_>>();
+        assert_eq!(vec![0, 0b100, 0b011], classification_flags);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_raw_las_reader_truncated_file() -> Result<()> {
+        let mut header_builder = Builder::from((1, 2));
+        header_builder.point_format = Format::new(0)?;
+        let header = header_builder.into_header()?;
+        // Header claims 4 points, but there are only 2 complete records, followed by a partial one
+        let las_data = make_las_file_in_memory(&header, 4, &[0; 50])?;
+
+        let mut reader = RawLASReader::from_read(Cursor::new(las_data.clone()), false)?;
+        assert_eq!(2, reader.las_metadata().point_count());
+        assert_eq!(1, reader.las_metadata().warnings().len());
+        assert_eq!(2, reader.read::<VectorBuffer>(4)?.len());
+
+        let strict_options = LASReadOptions {
+            strict_point_count: true,
+            ..Default::default()
+        };
+        let error = RawLASReader::from_read_with_options(Cursor::new(las_data), strict_options)
+            .err()
+            .expect("Opening a truncated file in strict mode should fail");
+        assert_eq!(
+            Some(&TruncatedLASFileError {
+                point_count_in_header: 4,
+                available_point_count: 2,
+                bytes_in_partial_record: 10,
+            }),
+            error.downcast_ref::<TruncatedLASFileError>()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_raw_las_reader_file_truncated_while_reading() -> Result<()> {
+        let mut header_builder = Builder::from((1, 2));
+        header_builder.point_format = Format::new(0)?;
+        let header = header_builder.into_header()?;
+        let point_records = (1..=80).collect::<Vec<u8>>();
+        let las_data = make_las_file_in_memory(&header, 4, &point_records)?;
+        // Cut off the last record and 5 bytes of the third record, so the file ends 15 bytes into point record 2
+        let truncated_length = las_data.len() - 25;
+
+        for point_layout_matches_memory_layout in [true, false].iter().copied() {
+            let mut reader = RawLASReader::from_read(
+                Cursor::new(las_data.clone()),
+                point_layout_matches_memory_layout,
+            )?;
+            reader.reader.get_mut().truncate(truncated_length);
+
+            let mut points =
+                VectorBuffer::with_capacity(4, reader.get_default_point_layout().clone());
+            points.resize(4);
+            let error = reader
+                .read_into(&mut points, 4)
+                .err()
+                .expect("Reading a truncated file should fail");
+            assert_eq!(
+                Some(&TruncatedLASFileError {
+                    point_count_in_header: 4,
+                    available_point_count: 2,
+                    bytes_in_partial_record: 15,
+                }),
+                error.downcast_ref::<TruncatedLASFileError>()
+            );
+            assert_eq!(Some(2), reader.point_index());
+
+            if point_layout_matches_memory_layout {
+                assert_eq!(&point_records[..40], points.get_point_range_ref(0..2));
+                assert!(points
+                    .get_point_range_ref(2..4)
+                    .iter()
+                    .all(|byte| *byte == 0));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_raw_las_reader_header_and_vlr_padding() -> Result<()> {
+        let mut header_builder = Builder::from((1, 2));
+        header_builder.point_format = Format::new(0)?;
+        header_builder.padding = vec![1, 2, 3];
+        header_builder.vlr_padding = vec![4, 5, 6, 7, 8];
+        header_builder.vlrs.push(las_rs::Vlr {
+            user_id: "pasture".to_owned(),
+            record_id: 1,
+            description: Default::default(),
+            data: vec![9; 10],
+        });
+        let header = header_builder.into_header()?;
+        let point_records = (1..=40).collect::<Vec<u8>>();
+        let las_data = make_las_file_in_memory(&header, 2, &point_records)?;
+
+        let mut reader = RawLASReader::from_read(Cursor::new(las_data), true)?;
+        let metadata = reader.las_metadata();
+        assert_eq!(&[1, 2, 3], metadata.header_padding());
+        assert_eq!(&[4, 5, 6, 7, 8], metadata.vlr_padding());
+        assert_eq!(1, metadata.vlrs().len());
+        assert_eq!(vec![9; 10], metadata.vlrs()[0].data);
+
+        let points = reader.read::<VectorBuffer>(2)?;
+        assert_eq!(&point_records[..], points.get_point_range_ref(0..2));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_raw_las_reader_extended_point_count() -> Result<()> {
+        // Files with extended point formats store their point count only in the 64-bit field
+        let mut header_builder = Builder::from((1, 4));
+        header_builder.point_format = Format::new(6)?;
+        let header = header_builder.into_header()?;
+
+        let las_data = make_las_file_in_memory(&header, 3, &[0; 90])?;
+        let mut reader = RawLASReader::from_read(Cursor::new(las_data), false)?;
+        assert_eq!(3, reader.las_metadata().point_count());
+        assert_eq!(3, reader.remaining_points());
+        assert_eq!(2, reader.seek_point(SeekFrom::End(-1))?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_raw_las_reader_seek_to_gps_time() -> Result<()> {
+        let read = BufReader::new(File::open(get_test_las_path(1))?);
+        let mut reader = RawLASReader::from_read(read, false)?;
+        let gps_times = test_data_gps_times();
+
+        for (index, gps_time) in gps_times.iter().copied().enumerate() {
+            assert_eq!(index, reader.seek_to_gps_time(gps_time)?);
+            assert_eq!(Some(index), reader.point_index());
+            assert_eq!(index, reader.seek_to_gps_time_linear(gps_time)?);
+            // Times between two points seek to the later point
+            assert_eq!(index, reader.seek_to_gps_time(gps_time - 0.5)?);
+            assert_eq!(index, reader.seek_to_gps_time_linear(gps_time - 0.5)?);
+        }
+
+        // Reading continues at the point that was found
+        reader.seek_to_gps_time(gps_times[3])?;
+        let points = reader.read::<VectorBuffer>(2)?;
+        let read_gps_times = points
+            .view_attribute::<f64>(&attributes::GPS_TIME)
+            .into_iter()
+            .collect::<Vec<_>>();
+        assert_eq!(&gps_times[3..5], read_gps_times.as_slice());
+
+        let after_last_point = gps_times[gps_times.len() - 1] + 1.0;
+        assert_eq!(
+            test_data_point_count(),
+            reader.seek_to_gps_time(after_last_point)?
+        );
+        assert_eq!(
+            test_data_point_count(),
+            reader.seek_to_gps_time_linear(after_last_point)?
+        );
+        assert_eq!(0, reader.remaining_points());
+
+        let read = BufReader::new(File::open(get_test_las_path(0))?);
+        let mut reader = RawLASReader::from_read(read, false)?;
+        assert!(reader.seek_to_gps_time(1.0).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_raw_las_reader_skip() -> Result<()> {
+        let read = BufReader::new(File::open(get_test_las_path(0))?);
+        let mut reader = RawLASReader::from_read(read, false)?;
+        assert_eq!(3, reader.skip(3)?);
+        let positions = reader
+            .read::<VectorBuffer>(2)?
+            .view_attribute::<Vector3<f64>>(&attributes::POSITION_3D)
+            .into_iter()
+            .collect::<Vec<_>>();
+        assert_eq!(&test_data_positions()[3..5], positions.as_slice());
+
+        // Skipping past the end stops at the last point
+        assert_eq!(5, reader.skip(100)?);
+        assert_eq!(0, reader.skip(1)?);
+        assert_eq!(0, reader.remaining_points());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_reader_iterate_points_with_filter_that_skips_whole_chunks() -> Result<()> {
+        // The first two chunks of the `PointIterator` contain only second returns, the first returns come after them
+        let points_per_chunk =
+            PointIterator::<RawLASReader<Cursor<Vec<u8>>>, LasPointFormat0>::POINTS_PER_CHUNK;
+        let num_points = 2 * points_per_chunk + 10;
+        let mut header_builder = Builder::from((1, 2));
+        header_builder.point_format = Format::new(0)?;
+        let header = header_builder.into_header()?;
+        let mut point_records = vec![0; num_points * 20];
+        for (index, record) in point_records.chunks_exact_mut(20).enumerate() {
+            record[12..14].copy_from_slice(&(index as u16).to_le_bytes());
+            let return_number = if index < 2 * points_per_chunk { 2 } else { 1 };
+            record[14] = return_number | (2 << 3);
+        }
+        let las_data = make_las_file_in_memory(&header, num_points, &point_records)?;
+
+        let mut reader = LASReader::from_read(Cursor::new(las_data), false, false)?;
+        reader.set_return_filter(ReturnFilter::FirstOnly);
+        let intensities = reader
+            .points::<LasPointFormat0>()
+            .map(|point| point.map(|point| point.intensity))
+            .collect::<Result<Vec<_>>>()?;
+        let expected_intensities = (2 * points_per_chunk..num_points)
+            .map(|index| index as u16)
+            .collect::<Vec<_>>();
+        assert_eq!(expected_intensities, intensities);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_raw_las_reader_out_of_bounds_handling() -> Result<()> {
+        let mut las_data = std::fs::read(get_test_las_path(0))?;
+        // Reduce max_x in the header, so that the points 6 to 9 lie outside of the bounds
+        las_data[179..187].copy_from_slice(&5.0_f64.to_le_bytes());
+        let read_points = |out_of_bounds_handling: OutOfBoundsHandling| -> Result<VectorBuffer> {
+            let options = LASReadOptions {
+                out_of_bounds_handling,
+                ..Default::default()
+            };
+            let mut reader =
+                RawLASReader::from_read_with_options(Cursor::new(las_data.clone()), options)?;
+            reader.read::<VectorBuffer>(test_data_point_count())
+        };
+        let positions = |points: &VectorBuffer| {
+            points
+                .view_attribute::<Vector3<f64>>(&attributes::POSITION_3D)
+                .into_iter()
+                .collect::<Vec<_>>()
+        };
+        let intensities = |points: &VectorBuffer| {
+            points
+                .view_attribute::<u16>(&attributes::INTENSITY)
+                .into_iter()
+                .collect::<Vec<_>>()
+        };
+
+        let kept_points = read_points(OutOfBoundsHandling::Keep)?;
+        assert_eq!(test_data_positions(), positions(&kept_points));
+
+        let remaining_points = read_points(OutOfBoundsHandling::Skip)?;
+        assert_eq!(&test_data_positions()[..6], positions(&remaining_points));
+        assert_eq!(
+            &test_data_intensities()[..6],
+            intensities(&remaining_points)
+        );
+
+        let clamped_points = read_points(OutOfBoundsHandling::ClampToHeaderBounds)?;
+        let expected_positions = test_data_positions()
+            .into_iter()
+            .map(|position| Vector3::new(position.x.min(5.0), position.y, position.z))
+            .collect::<Vec<_>>();
+        assert_eq!(expected_positions, positions(&clamped_points));
+        assert_eq!(test_data_intensities(), intensities(&clamped_points));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_raw_las_reader_validate_finds_violations() -> Result<()> {
+        let mut las_data = std::fs::read(get_test_las_path(0))?;
+        // Reduce max_x in the header, so that the points 6 to 9 lie outside of the bounds
+        las_data[179..187].copy_from_slice(&5.0_f64.to_le_bytes());
+        let offset_to_point_data = u32::from_le_bytes(las_data[96..100].try_into()?) as usize;
+        let size_of_point = Format::new(0)?.len() as usize;
+        let record_offset = |point_index: usize| offset_to_point_data + point_index * size_of_point;
+        // Point 3 gets a reserved classification, points 4 and 5 a return number larger than their number of returns
+        las_data[record_offset(3) + 15] = 13;
+        las_data[record_offset(4) + 14] = (2 << 3) | 3;
+        las_data[record_offset(5) + 14] = (1 << 3) | 2;
+
+        let mut reader = RawLASReader::from_read(Cursor::new(las_data), false)?;
+        let report = reader.validate(&ValidationOptions {
+            max_reported_indices: 2,
+            ..Default::default()
+        })?;
+        assert!(!report.is_valid());
+        assert_eq!(3, report.violations().len());
+
+        let out_of_bounds = &report.violations()[&ValidationViolation::PositionOutsideOfBounds];
+        assert_eq!(4, out_of_bounds.count);
+        assert_eq!(vec![6, 7], out_of_bounds.point_indices);
+        assert_eq!(
+            1,
+            report.violation_count(ValidationViolation::ReservedClassification)
+        );
+        assert_eq!(
+            vec![4, 5],
+            report.violations()[&ValidationViolation::ReturnNumberExceedsNumberOfReturns]
+                .point_indices
+        );
+
+        // Disabled checks report no violations
+        let report = reader.validate(&ValidationOptions {
+            check_bounds: false,
+            check_return_numbers: false,
+            check_classifications: false,
+            ..Default::default()
+        })?;
+        assert!(report.is_valid());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_raw_las_reader_reads_evlrs() -> Result<()> {
+        const WKT: &str = r#"GEOGCS["WGS 84"]"#;
+        let mut header_builder = Builder::from((1, 4));
+        header_builder.point_format = Format::new(0)?;
+        header_builder.evlrs.push(las_rs::Vlr {
+            user_id: "LASF_Projection".to_owned(),
+            record_id: 2112,
+            description: Default::default(),
+            data: WKT.as_bytes().to_vec(),
+        });
+        let header = header_builder.into_header()?;
+
+        let las_data = make_las_file_in_memory(&header, 2, &[0; 40])?;
+        let mut reader = RawLASReader::from_read(Cursor::new(las_data), false)?;
+        assert_eq!(1, reader.header().evlrs().len());
+        assert_eq!(Some(WKT), reader.las_metadata().crs_wkt());
+        // Reading the EVLRs must not affect reading the points
+        assert_eq!(2, reader.read::<VectorBuffer>(10)?.len());
+
+        Ok(())
+    }
 
-    // test_read_with_format!(laz_format_6, 6, RawLAZReader, get_test_laz_path);
-    // test_read_with_format!(laz_format_7, 7, RawLAZReader, get_test_laz_path);
-    // test_read_with_format!(laz_format_8, 8, RawLAZReader, get_test_laz_path);
+    #[test]
+    fn test_raw_las_reader_read_scaled_extra_bytes() -> Result<()> {
+        const SCALE: f64 = 0.01;
+        const OFFSET: f64 = 100.0;
+        let raw_heights = [0, 150, 1234];
+        let expected_heights = raw_heights
+            .iter()
+            .map(|height| (*height as f64 * SCALE) + OFFSET)
+            .collect::<Vec<_>>();
+
+        let las_data = make_las_file_with_scaled_extra_bytes(&raw_heights, SCALE, OFFSET)?;
+        let mut reader = RawLASReader::from_read(Cursor::new(las_data), false)?;
+        let height_attribute = reader
+            .get_default_point_layout()
+            .get_attribute_by_name("HeightAboveGround")
+            .expect("Extra bytes attribute not found in default layout")
+            .attribute_definition()
+            .clone();
+        assert_eq!(PointAttributeDataType::F64, height_attribute.datatype());
+
+        let points = reader.read::<VectorBuffer>(raw_heights.len())?;
+        let heights = points
+            .view_attribute::<f64>(&height_attribute)
+            .into_iter()
+            .collect::<Vec<_>>();
+        assert_eq!(expected_heights, heights);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_raw_las_reader_read_scaled_extra_bytes_into_custom_layout() -> Result<()> {
+        const SCALE: f64 = 0.5;
+        const OFFSET: f64 = -2.0;
+        let raw_heights = [1, 2, 3, 4];
+
+        let las_data = make_las_file_with_scaled_extra_bytes(&raw_heights, SCALE, OFFSET)?;
+        let mut reader = RawLASReader::from_read(Cursor::new(las_data), false)?;
+
+        let height_attribute = PointAttributeDefinition::custom(
+            Cow::Borrowed("HeightAboveGround"),
+            PointAttributeDataType::F32,
+        );
+        let layout =
+            PointLayout::from_attributes(&[attributes::POSITION_3D, height_attribute.clone()]);
+        let mut points = HashMapBuffer::new_from_layout(layout);
+        points.resize(raw_heights.len());
+        reader.read_into(&mut points, raw_heights.len())?;
+
+        let heights = points
+            .view_attribute::<f32>(&height_attribute)
+            .into_iter()
+            .collect::<Vec<_>>();
+        let expected_heights = raw_heights
+            .iter()
+            .map(|height| ((*height as f64 * SCALE) + OFFSET) as f32)
+            .collect::<Vec<_>>();
+        assert_eq!(expected_heights, heights);
+
+        // The raw values are still accessible by requesting the raw datatype of the extra bytes
+        let raw_height_attribute =
+            height_attribute.with_custom_datatype(PointAttributeDataType::U16);
+        reader.seek_point(SeekFrom::Start(0))?;
+        let mut raw_points = VectorBuffer::new_from_layout(PointLayout::from_attributes(&[
+            raw_height_attribute.clone(),
+        ]));
+        raw_points.resize(raw_heights.len());
+        reader.read_into(&mut raw_points, raw_heights.len())?;
+        let actual_raw_heights = raw_points
+            .view_attribute::<u16>(&raw_height_attribute)
+            .into_iter()
+            .collect::<Vec<_>>();
+        assert_eq!(raw_heights.to_vec(), actual_raw_heights);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_raw_las_reader_progress_callback() -> Result<()> {
+        let progress = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+        let progress_in_callback = progress.clone();
 
-    // Formats 9 and 10 seem to parse waveform data differently when using laz-rs, so they are unsupported for now
+        let read = BufReader::new(File::open(get_test_las_path(0))?);
+        let mut reader = RawLASReader::from_read(read, false)?;
+        reader.set_progress_callback(Box::new(move |points_read, total_points| {
+            progress_in_callback
+                .lock()
+                .unwrap()
+                .push((points_read, total_points));
+        }));
+
+        reader.read::<VectorBuffer>(4)?;
+        reader.read::<VectorBuffer>(6)?;
+        // Nothing left to read, so no progress is reported
+        reader.read::<VectorBuffer>(1)?;
+
+        assert_eq!(vec![(4, 10), (10, 10)], *progress.lock().unwrap());
+
+        Ok(())
+    }
 }
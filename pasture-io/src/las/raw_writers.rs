@@ -1,31 +1,41 @@
 use std::{
     collections::HashMap,
     convert::TryInto,
-    io::{Cursor, SeekFrom},
+    fs::File,
+    io::{BufWriter, Cursor, Seek, SeekFrom, Write},
 };
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use byteorder::{LittleEndian, NativeEndian, ReadBytesExt, WriteBytesExt};
-use las_rs::{point::Format, Builder, Vlr};
-use laz::{LasZipCompressor, LazItemRecordBuilder, LazVlr};
+use las_rs::{point::Format, Builder};
 use pasture_core::{containers::BorrowedBuffer, layout::PointLayout, nalgebra::Vector3};
 
 use crate::base::PointWriter;
 
 use super::{
-    get_classification_flags_reader, get_classification_reader, get_color_reader,
-    get_edge_of_flight_line_reader, get_extended_scan_angle_rank_reader, get_gps_time_reader,
-    get_intensity_reader, get_nir_reader, get_number_of_returns_reader, get_point_source_id_reader,
-    get_position_reader, get_return_number_reader, get_return_point_waveform_location_reader,
-    get_scan_angle_rank_reader, get_scan_direction_flag_reader, get_scanner_channel_reader,
+    drops_legacy_overlap_flag, fit_extended_scan_angle, fit_legacy_classification_byte,
+    fit_scan_angle_rank, gather_point_range, get_classification_flags_reader,
+    get_classification_reader, get_edge_of_flight_line_reader, get_extended_scan_angle_rank_reader,
+    get_gps_time_reader, get_intensity_reader, get_nir_reader, get_number_of_returns_reader,
+    get_overlap_reader, get_point_source_id_reader, get_position_reader, get_return_number_reader,
+    get_return_point_waveform_location_reader, get_scan_angle_rank_reader,
+    get_scan_direction_flag_reader, get_scanner_channel_reader, get_upscaled_color_reader,
     get_user_data_reader, get_wave_packet_descriptor_index_reader, get_waveform_data_offset_reader,
-    get_waveform_packet_size_reader, get_waveform_parameters_reader, map_laz_err,
-    point_layout_from_las_metadata, write_las_bit_attributes, write_position_as_las_position,
-    BitAttributes, BitAttributesExtended, BitAttributesRegular,
+    get_waveform_packet_size_reader, get_waveform_parameters_reader, invalid_point_context,
+    point_count_from_raw_header, point_layout_from_las_metadata, read_is_compressed_las_file,
+    validate_source_layout_for_las, write_las_bit_attributes, write_position_as_las_position,
+    BitAttributeOverflowHandling, BitAttributes, BitAttributesExtended, BitAttributesRegular,
+    ColorUpscaling, ExtraBytesEntry, ExtraBytesWriter, LASMetadata, LASReadOptions,
+    LASStreamStatistics, MissingAttributeDefaults, OverlapFlagHandling, RawLASReader,
+    DEFAULT_WRITE_CHUNK_SIZE, PROJECTION_VLR_USER_ID, SIZE_OF_EVLR_HEADER,
+    WAVEFORM_DATA_PACKETS_RECORD_ID, WAVEFORM_VLR_USER_ID, WKT_CRS_RECORD_ID,
 };
 
 /// Update the bounds in the given `las_header` by including the given `new_position`
-fn update_bounds_in_las_header(new_position: &Vector3<f64>, las_header: &mut las::raw::Header) {
+pub(crate) fn update_bounds_in_las_header(
+    new_position: &Vector3<f64>,
+    las_header: &mut las::raw::Header,
+) {
     if new_position.x < las_header.min_x {
         las_header.min_x = new_position.x;
     }
@@ -46,8 +56,39 @@ fn update_bounds_in_las_header(new_position: &Vector3<f64>, las_header: &mut las
     }
 }
 
+/// Bounds of the positions within a chunk of points. The LAS writers only add them to the LAS header once the chunk
+/// has been written, so that the bounds never include positions of points that were not written
+pub(crate) struct ChunkBounds {
+    min: Vector3<f64>,
+    max: Vector3<f64>,
+}
+
+impl ChunkBounds {
+    pub(crate) fn new() -> Self {
+        Self {
+            min: Vector3::repeat(f64::INFINITY),
+            max: Vector3::repeat(f64::NEG_INFINITY),
+        }
+    }
+
+    /// Extends the bounds by `position`
+    pub(crate) fn include(&mut self, position: &Vector3<f64>) {
+        self.min = self.min.inf(position);
+        self.max = self.max.sup(position);
+    }
+
+    /// Updates the bounds in `las_header` by including these bounds. Does nothing if no position was included
+    pub(crate) fn update_las_header(&self, las_header: &mut las::raw::Header) {
+        if self.min.x > self.max.x {
+            return;
+        }
+        update_bounds_in_las_header(&self.min, las_header);
+        update_bounds_in_las_header(&self.max, las_header);
+    }
+}
+
 /// Update the point counts in the given `las_header` using the given `additional_points` and `additional_points_by_return`
-fn update_point_counts_in_las_header(
+pub(crate) fn update_point_counts_in_las_header(
     additional_points: usize,
     additional_points_by_return: &HashMap<u8, u64>,
     las_header: &mut las::raw::Header,
@@ -65,8 +106,129 @@ fn update_point_counts_in_las_header(
         });
 }
 
+/// Returns an error if the LAS writers can't assemble the point records in chunks of `write_chunk_size` points
+pub(crate) fn validate_write_chunk_size(write_chunk_size: usize) -> Result<()> {
+    if write_chunk_size == 0 {
+        bail!("The write chunk size must be at least one point");
+    }
+    Ok(())
+}
+
+/// Resizes the reusable `chunk_buffer` and `las_point_buffer` of a LAS writer to hold `num_points` points with
+/// `size_of_source_point` bytes and their point records with `size_of_las_point` bytes. Both buffers only ever grow,
+/// so writing many small batches of points does not allocate
+pub(crate) fn resize_chunk_buffers(
+    chunk_buffer: &mut Vec<u8>,
+    las_point_buffer: &mut Vec<u8>,
+    num_points: usize,
+    size_of_source_point: usize,
+    size_of_las_point: usize,
+) {
+    let chunk_buffer_len = num_points * size_of_source_point;
+    if chunk_buffer.len() < chunk_buffer_len {
+        chunk_buffer.resize(chunk_buffer_len, 0);
+    }
+    let las_point_buffer_len = num_points * size_of_las_point;
+    if las_point_buffer.len() < las_point_buffer_len {
+        las_point_buffer.resize(las_point_buffer_len, 0);
+    }
+}
+
+/// Message for the error that LAS writers return if the underlying writer is not seekable
+pub(crate) const LAS_WRITER_REQUIRES_SEEK: &str =
+    "LAS/LAZ files can only be written to a seekable output, as the header has to be updated after all points have been written";
+
+/// Bit of the global encoding that states that the waveform data packets are stored within the LAS file
+pub(crate) const GLOBAL_ENCODING_INTERNAL_WAVEFORM_DATA_BIT: u16 = 1 << 1;
+
+/// Bit of the global encoding that states that the CRS of a LAS 1.4 file is given as OGC WKT
+const GLOBAL_ENCODING_WKT_BIT: u16 = 1 << 4;
+
+/// Sets the WKT bit in the global encoding of the given raw `las_header`, if `header` is a LAS 1.4 header that contains
+/// an OGC Coordinate System WKT VLR. LAS 1.4 requires this bit so that readers use the WKT instead of GeoTIFF keys
+pub(crate) fn set_wkt_bit_in_las_header(las_header: &mut las::raw::Header, header: &las::Header) {
+    let has_wkt_vlr = header
+        .vlrs()
+        .iter()
+        .chain(header.evlrs().iter())
+        .any(|vlr| vlr.user_id == PROJECTION_VLR_USER_ID && vlr.record_id == WKT_CRS_RECORD_ID);
+    if has_wkt_vlr && (las_header.version.major, las_header.version.minor) >= (1, 4) {
+        las_header.global_encoding |= GLOBAL_ENCODING_WKT_BIT;
+    }
+}
+
+/// Converts the given `evlr` into a raw EVLR that can be written to a file with the given `las_header`, which requires
+/// LAS 1.4. EVLRs can store payloads that are larger than `u16::MAX` bytes
+pub(crate) fn raw_evlr_for_las_header(
+    evlr: las::Vlr,
+    las_header: &las::raw::Header,
+) -> Result<las::raw::Vlr> {
+    if (las_header.version.major, las_header.version.minor) < (1, 4) {
+        bail!(
+            "EVLRs can only be written to LAS 1.4 files, but the LAS version is {}.{}",
+            las_header.version.major,
+            las_header.version.minor
+        );
+    }
+    Ok(evlr.into_raw(true)?)
+}
+
+/// Sets the position of the first EVLR and the number of EVLRs in the given `las_header`, if there are any EVLRs. If
+/// the `evlrs` contain the waveform data packets, the position of their EVLR and the global encoding bit for internal
+/// waveform data are set as well
+pub(crate) fn update_evlrs_in_las_header(
+    start_of_first_evlr: u64,
+    evlrs: &[las::raw::Vlr],
+    las_header: &mut las::raw::Header,
+) {
+    if evlrs.is_empty() {
+        return;
+    }
+    let evlr = las_header.evlr.get_or_insert_with(Default::default);
+    evlr.start_of_first_evlr = start_of_first_evlr;
+    evlr.number_of_evlrs = evlrs.len() as u32;
+
+    let mut start_of_evlr = start_of_first_evlr;
+    for evlr in evlrs {
+        let user_id = evlr.user_id.split(|byte| *byte == 0).next();
+        if user_id == Some(WAVEFORM_VLR_USER_ID.as_bytes())
+            && evlr.record_id == WAVEFORM_DATA_PACKETS_RECORD_ID
+        {
+            las_header.start_of_waveform_data_packet_record = Some(start_of_evlr);
+            las_header.global_encoding |= GLOBAL_ENCODING_INTERNAL_WAVEFORM_DATA_BIT;
+            return;
+        }
+        start_of_evlr += SIZE_OF_EVLR_HEADER + evlr.data.len() as u64;
+    }
+}
+
+/// Returns the context for errors about invalid attribute values of the point at `index_in_points` within the points
+/// that are written, where `first_point_index` is the index of the first of these points within the LAS file
+pub(crate) fn invalid_point_context(first_point_index: u64, index_in_points: usize) -> String {
+    format!(
+        "Can't write point {} of the given points (point {} of the LAS file)",
+        index_in_points,
+        first_point_index + index_in_points as u64
+    )
+}
+
 /// Do final checkup of the LAS header
-fn finalize_las_header(las_header: &mut las::raw::Header) {
+/// Returns the `LASMetadata` of a written LAS file from the `raw_header` of its writer and the (E)VLRs
+pub(crate) fn las_metadata_of_written_file(
+    raw_header: &las::raw::Header,
+    vlrs: &[las::Vlr],
+    evlrs: &[las::raw::Vlr],
+) -> Result<LASMetadata> {
+    let mut raw_header = raw_header.clone();
+    finalize_las_header(&mut raw_header)?;
+    let mut builder = Builder::new(raw_header).context("Invalid LAS header")?;
+    builder.vlrs = vlrs.to_vec();
+    builder.evlrs = evlrs.iter().cloned().map(las::Vlr::new).collect();
+    let header = builder.into_header().context("Invalid LAS header")?;
+    (&header).try_into().context("Failed to parse LAS header")
+}
+
+pub(crate) fn finalize_las_header(las_header: &mut las::raw::Header) -> Result<()> {
     // Set the legacy point counts field, if desired. The LAS standard states that the legacy number of point records field
     // must only be set if the total point count is less than u32::MAX AND the point record format is less than 6!
 
@@ -74,14 +236,32 @@ fn finalize_las_header(las_header: &mut las::raw::Header) {
         .large_file
         .as_ref()
         .expect("LAS header must contain large_file field!");
+    // An empty file has no meaningful bounds, so we write zero bounds instead of the initial (inverted) bounds
+    if large_file.number_of_point_records == 0 {
+        las_header.min_x = 0.0;
+        las_header.min_y = 0.0;
+        las_header.min_z = 0.0;
+        las_header.max_x = 0.0;
+        las_header.max_y = 0.0;
+        las_header.max_z = 0.0;
+    }
     if large_file.number_of_point_records > u32::MAX as u64 {
-        return;
+        if (las_header.version.major, las_header.version.minor) < (1, 4) {
+            bail!(
+                "Can't write {} points to a LAS {}.{} file, files with more than {} points require LAS 1.4",
+                large_file.number_of_point_records,
+                las_header.version.major,
+                las_header.version.minor,
+                u32::MAX
+            );
+        }
+        return Ok(());
     }
     // las-rs encodes the information about compression in the higher bits of the point_data_record_format, which is not
     // conforming with the LAS specification I think. So we extract the lower bits here to make sure that this check works
     let conforming_point_record_format = las_header.point_data_record_format & 0b1111;
     if conforming_point_record_format > 5 {
-        return;
+        return Ok(());
     }
 
     las_header.number_of_point_records = large_file.number_of_point_records as u32;
@@ -89,26 +269,98 @@ fn finalize_las_header(las_header: &mut las::raw::Header) {
         las_header.number_of_points_by_return[return_number] =
             large_file.number_of_points_by_return[return_number] as u32;
     }
+    Ok(())
+}
+
+/// Returns the header that a streaming LAS writer writes before the point records, i.e. `las_header` with the point
+/// counts and bounds of the given `statistics` and the position of the `evlrs` behind the announced point records
+fn las_header_for_streaming(
+    las_header: &las::raw::Header,
+    statistics: &LASStreamStatistics,
+    evlrs: &[las::raw::Vlr],
+) -> Result<las::raw::Header> {
+    let mut streaming_header = las_header.clone();
+    let large_file = streaming_header
+        .large_file
+        .get_or_insert_with(Default::default);
+    large_file.number_of_point_records = statistics.point_count;
+    large_file.number_of_points_by_return = statistics.points_by_return.unwrap_or_default();
+    streaming_header.min_x = statistics.bounds.min().x;
+    streaming_header.min_y = statistics.bounds.min().y;
+    streaming_header.min_z = statistics.bounds.min().z;
+    streaming_header.max_x = statistics.bounds.max().x;
+    streaming_header.max_y = statistics.bounds.max().y;
+    streaming_header.max_z = statistics.bounds.max().z;
+    let end_of_point_records = streaming_header.offset_to_point_data as u64
+        + statistics.point_count * streaming_header.point_data_record_length as u64;
+    update_evlrs_in_las_header(end_of_point_records, evlrs, &mut streaming_header);
+    finalize_las_header(&mut streaming_header)?;
+    Ok(streaming_header)
 }
 
 pub(crate) struct RawLASWriter<T: std::io::Write + std::io::Seek> {
     writer: T,
     default_layout: PointLayout,
     current_header: las::raw::Header,
+    /// VLRs that were written after the header, which are part of the `LASMetadata` of the finished file
+    vlrs: Vec<las::Vlr>,
     evlrs: Vec<las::raw::Vlr>,
+    /// Entries of the Extra Bytes VLR and the total number of extra bytes per point record
+    extra_bytes_entries: Vec<ExtraBytesEntry>,
+    num_extra_bytes: usize,
+    bit_attribute_overflow: BitAttributeOverflowHandling,
+    overlap_flag_handling: OverlapFlagHandling,
+    /// Number of points whose overlap flag was dropped because the point format has no bit for it
+    dropped_overlap_flags: usize,
+    missing_attribute_defaults: MissingAttributeDefaults,
+    color_upscaling: ColorUpscaling,
+    /// Number of points whose records are assembled in `las_point_buffer` and written with a single call to `writer`
+    write_chunk_size: usize,
+    /// Raw memory of the source points and the LAS point records of a chunk, reused between calls to `write`
+    chunk_buffer: Vec<u8>,
+    las_point_buffer: Vec<u8>,
     _point_start_index: u64,
     requires_flush: bool,
+    /// Statistics that were written to the header before the points, if the writer never seeks its output
+    streaming_statistics: Option<LASStreamStatistics>,
+    is_streaming_finished: bool,
 }
 
 impl<T: std::io::Write + std::io::Seek> RawLASWriter<T> {
-    pub fn from_write_and_header(mut write: T, header: las::Header) -> Result<Self> {
+    pub fn from_write_and_header(write: T, header: las::Header) -> Result<Self> {
+        Self::from_write_and_header_impl(write, header, None)
+    }
+
+    /// Creates a `RawLASWriter` that writes the given `statistics` into the LAS header before any point records, so
+    /// that `write` only has to report its position but is never seeked. Flushing this writer finishes the LAS file
+    /// and fails if the written points don't match the `statistics`
+    pub fn from_write_and_header_streaming(
+        write: T,
+        header: las::Header,
+        statistics: LASStreamStatistics,
+    ) -> Result<Self> {
+        Self::from_write_and_header_impl(write, header, Some(statistics))
+    }
+
+    fn from_write_and_header_impl(
+        mut write: T,
+        header: las::Header,
+        streaming_statistics: Option<LASStreamStatistics>,
+    ) -> Result<Self> {
+        write.stream_position().context(LAS_WRITER_REQUIRES_SEEK)?;
         let las_metadata = (&header).try_into().context("Could not parse LAS header")?;
         let default_layout = point_layout_from_las_metadata(&las_metadata, false)
             .context("Could not determine PointLayout from given LAS header")?;
+        let extra_bytes_entries = las_metadata
+            .extra_bytes_vlr()
+            .map(|vlr| vlr.entries().to_vec())
+            .unwrap_or_default();
+        let num_extra_bytes = header.point_format().extra_bytes as usize;
 
         // Sanitize header, i.e. clear point counts and bounds
         // TODO Add flag to prevent recalculating bounds
         let mut raw_header = header.clone().into_raw()?;
+        set_wkt_bit_in_las_header(&mut raw_header, &header);
         //raw_header.version = Version::new(1, 2);
         raw_header.number_of_point_records = 0;
         raw_header.number_of_points_by_return = [0; 5];
@@ -128,10 +380,20 @@ impl<T: std::io::Write + std::io::Seek> RawLASWriter<T> {
             return Err(anyhow!("RawLASWriter::from_write_and_header: Scale factors in LAS header must not be zero!"));
         }
 
-        raw_header.write_to(&mut write)?;
+        let evlrs = header
+            .evlrs()
+            .iter()
+            .map(|evlr| evlr.clone().into_raw(true))
+            .collect::<Result<Vec<_>, _>>()?;
+        match &streaming_statistics {
+            Some(statistics) => {
+                las_header_for_streaming(&raw_header, statistics, &evlrs)?.write_to(&mut write)?
+            }
+            None => raw_header.write_to(&mut write)?,
+        }
         for vlr in header.vlrs().iter() {
             if vlr.has_large_data() {
-                panic!("RawLASWriter::from_write_and_header: Header with large VLRs is currently unsupported! Please add any large VLRs to the 'evlrs' parameter of the header!");
+                bail!("VLR '{}' ({}) is too large for a regular VLR, add it to the EVLRs of the LAS header instead", vlr.user_id, vlr.record_id);
             }
             let raw_vlr = vlr.clone().into_raw(false)?;
             raw_vlr.write_to(&mut write)?;
@@ -144,30 +406,194 @@ impl<T: std::io::Write + std::io::Seek> RawLASWriter<T> {
             writer: write,
             default_layout,
             current_header: raw_header,
-            evlrs: header
-                .evlrs()
-                .iter()
-                .map(|evlr| evlr.clone().into_raw(true))
-                .collect::<Result<Vec<_>, _>>()?,
+            evlrs,
+            vlrs: header.vlrs().clone(),
+            extra_bytes_entries,
+            num_extra_bytes,
+            bit_attribute_overflow: Default::default(),
+            overlap_flag_handling: Default::default(),
+            dropped_overlap_flags: 0,
+            missing_attribute_defaults: Default::default(),
+            color_upscaling: Default::default(),
+            write_chunk_size: DEFAULT_WRITE_CHUNK_SIZE,
+            chunk_buffer: vec![],
+            las_point_buffer: vec![],
             _point_start_index: point_start_index,
             requires_flush: true,
+            streaming_statistics,
+            is_streaming_finished: false,
         })
     }
 
-    /// Consumes this `RawLASWriter` and returns the underlying write type `T`. The data is flushed
-    /// before returning the writer
+    /// Consumes this `RawLASWriter` and returns the underlying write type `T`. The LAS file is finalized before
+    /// returning the writer
     pub fn into_inner(mut self) -> Result<T> {
-        self.flush()?;
+        self.finalize()?;
         Ok(self.writer)
     }
 
+    /// Writes the final LAS header with the bounds and point counts of all written points and the EVLRs, and flushes
+    /// the underlying writer. A streaming writer checks that the written points match its statistics instead. More
+    /// points can be written afterwards, which requires finalizing the file again
+    pub fn finalize(&mut self) -> Result<()> {
+        if let Some(statistics) = self.streaming_statistics {
+            // The header was written up front, so finalizing a streaming writer finishes the file
+            if self.is_streaming_finished {
+                return Ok(());
+            }
+            self.is_streaming_finished = true;
+            self.check_streaming_statistics(&statistics)?;
+            self.write_evlrs()?;
+            self.writer.flush()?;
+            return Ok(());
+        }
+        if !self.requires_flush {
+            return Ok(());
+        }
+
+        // The EVLRs are written directly after the last point record
+        let current_index = self.writer.stream_position()?;
+        update_evlrs_in_las_header(current_index, &self.evlrs, &mut self.current_header);
+        self.write_header()?;
+        self.write_evlrs()?;
+        self.writer.seek(SeekFrom::Start(current_index))?;
+        self.writer.flush()?;
+
+        self.requires_flush = false;
+
+        Ok(())
+    }
+
+    /// Returns the `LASMetadata` of the finalized LAS file, see [`Self::finalize`]
+    pub fn las_metadata(&self) -> Result<LASMetadata> {
+        las_metadata_of_written_file(&self.current_header, &self.vlrs, &self.evlrs)
+    }
+
+    /// Queues the given `evlr`, which is written after the point records when the writer is flushed
+    pub fn add_evlr(&mut self, evlr: las::Vlr) -> Result<()> {
+        if self.streaming_statistics.is_some() {
+            bail!("EVLRs of a streaming LAS writer have to be part of the LAS header, as their position is written before the point records");
+        }
+        let raw_evlr = raw_evlr_for_las_header(evlr, &self.current_header)?;
+        self.evlrs.push(raw_evlr);
+        self.requires_flush = true;
+        Ok(())
+    }
+
+    /// Sets how values that don't fit into the bit fields of the LAS point records are handled
+    pub fn set_bit_attribute_overflow_handling(
+        &mut self,
+        overflow_handling: BitAttributeOverflowHandling,
+    ) {
+        self.bit_attribute_overflow = overflow_handling;
+    }
+
+    /// Sets what happens to the overlap flag of points that are written to the point formats 0-5
+    pub fn set_overlap_flag_handling(&mut self, overlap_handling: OverlapFlagHandling) {
+        self.overlap_flag_handling = overlap_handling;
+    }
+
+    /// Returns the number of points whose overlap flag was dropped, see [`OverlapFlagHandling::Drop`]
+    pub fn dropped_overlap_flags(&self) -> usize {
+        self.dropped_overlap_flags
+    }
+
+    /// Sets the values that are written for LAS attributes that are missing from the `PointLayout` of the points
+    pub fn set_missing_attribute_defaults(&mut self, defaults: MissingAttributeDefaults) {
+        self.missing_attribute_defaults = defaults;
+    }
+
+    /// Sets how `Vector3<u8>` colors are converted into the 16-bit colors of the point records
+    pub fn set_color_upscaling(&mut self, color_upscaling: ColorUpscaling) {
+        self.color_upscaling = color_upscaling;
+    }
+
+    /// Sets the number of points whose records are assembled in memory and written to the underlying writer at once
+    pub fn set_write_chunk_size(&mut self, write_chunk_size: usize) -> Result<()> {
+        validate_write_chunk_size(write_chunk_size)?;
+        self.write_chunk_size = write_chunk_size;
+        Ok(())
+    }
+
+    /// Takes the reusable chunk buffers out of this writer, resized for `num_points` points. They are put back once all
+    /// chunks have been written, so they are only reallocated if writing fails
+    fn take_chunk_buffers(
+        &mut self,
+        num_points: usize,
+        size_of_source_point: usize,
+    ) -> (Vec<u8>, Vec<u8>) {
+        let mut chunk_buffer = std::mem::take(&mut self.chunk_buffer);
+        let mut las_point_buffer = std::mem::take(&mut self.las_point_buffer);
+        resize_chunk_buffers(
+            &mut chunk_buffer,
+            &mut las_point_buffer,
+            num_points,
+            size_of_source_point,
+            self.current_header.point_data_record_length as usize,
+        );
+        (chunk_buffer, las_point_buffer)
+    }
+
+    /// Returns an error if the points that were written don't match the `statistics` that were written into the
+    /// header of a streaming LAS file
+    fn check_streaming_statistics(&self, statistics: &LASStreamStatistics) -> Result<()> {
+        let large_file = self
+            .current_header
+            .large_file
+            .as_ref()
+            .expect("LAS header must contain large_file field!");
+        if large_file.number_of_point_records != statistics.point_count {
+            bail!(
+                "The LAS header states {} points, but {} points were written. When streaming a LAS file, the point count has to be known before writing",
+                statistics.point_count,
+                large_file.number_of_point_records
+            );
+        }
+        if let Some(points_by_return) = statistics.points_by_return {
+            if points_by_return != large_file.number_of_points_by_return {
+                bail!(
+                    "The LAS header states {:?} points by return, but {:?} points by return were written",
+                    points_by_return,
+                    large_file.number_of_points_by_return
+                );
+            }
+        }
+        if statistics.point_count == 0 {
+            return Ok(());
+        }
+        // Positions are quantized while writing, so we allow deviations of up to one scale step
+        let header = &self.current_header;
+        let tolerance = Vector3::new(
+            header.x_scale_factor,
+            header.y_scale_factor,
+            header.z_scale_factor,
+        );
+        let expected_min = statistics.bounds.min().coords - tolerance;
+        let expected_max = statistics.bounds.max().coords + tolerance;
+        let min = Vector3::new(header.min_x, header.min_y, header.min_z);
+        let max = Vector3::new(header.max_x, header.max_y, header.max_z);
+        if (0..3).any(|axis| min[axis] < expected_min[axis] || max[axis] > expected_max[axis]) {
+            bail!(
+                "The written points range from {} to {}, which exceeds the bounds {} to {} that the LAS header states",
+                min,
+                max,
+                statistics.bounds.min(),
+                statistics.bounds.max()
+            );
+        }
+        Ok(())
+    }
+
     /// Writes the current header to the start of the file
     fn write_header(&mut self) -> Result<()> {
-        finalize_las_header(&mut self.current_header);
+        // Only the written header is finalized, as the zero bounds of an empty file must not end up in the bounds that
+        // the points which are written afterwards are added to
+        let mut header = self.current_header.clone();
+        finalize_las_header(&mut header)?;
 
         let current_position = self.writer.stream_position()?;
         self.writer.seek(SeekFrom::Start(0))?;
-        self.current_header.write_to(&mut self.writer)?;
+        header.write_to(&mut self.writer)?;
         self.writer.seek(SeekFrom::Start(current_position))?;
         Ok(())
     }
@@ -189,13 +615,15 @@ impl<T: std::io::Write + std::io::Seek> RawLASWriter<T> {
             return Ok(());
         }
 
-        // Similar to RawLASReader, write points in chunks of a fixed size to prevent overhead of
+        // Similar to RawLASReader, write points in chunks of `write_chunk_size` points to prevent overhead of
         // repeated virtual calls to 'dyn PointBuffer'
 
         let size_of_single_point = self.default_layout.size_of_point_entry() as usize;
-        let num_points_in_chunk = 50_000;
+        let num_points_in_chunk = self.write_chunk_size.min(points.len());
         let num_chunks = (points.len() + (num_points_in_chunk - 1)) / num_points_in_chunk;
-        let mut chunk_buffer: Vec<u8> = vec![0; num_points_in_chunk * size_of_single_point];
+        let size_of_las_point = self.current_header.point_data_record_length as usize;
+        let (mut chunk_buffer, mut las_point_buffer) =
+            self.take_chunk_buffers(num_points_in_chunk, size_of_single_point);
 
         let source_format = Format::new(self.current_header.point_data_record_format)?;
 
@@ -209,20 +637,32 @@ impl<T: std::io::Write + std::io::Seek> RawLASWriter<T> {
             points_by_return.insert(return_number, 0);
         }
 
+        let first_point_index = self
+            .current_header
+            .large_file
+            .as_ref()
+            .map(|large_file| large_file.number_of_point_records)
+            .unwrap_or_default();
         for chunk_index in 0..num_chunks {
+            let mut chunk_bounds = ChunkBounds::new();
+            points_by_return.values_mut().for_each(|count| *count = 0);
             let points_in_cur_chunk = std::cmp::min(
                 num_points_in_chunk,
                 points.len() - (chunk_index * num_points_in_chunk),
             );
             let start_point_index = chunk_index * num_points_in_chunk;
-            points.get_point_range(
+            gather_point_range(
+                points,
                 start_point_index..(start_point_index + points_in_cur_chunk),
                 &mut chunk_buffer[..points_in_cur_chunk * size_of_single_point],
             );
             let mut point_read = Cursor::new(chunk_buffer);
+            let mut las_point_write = Cursor::new(las_point_buffer);
 
             // Read all the attributes from the raw memory inside `points` and transform them into the format that LAS expects
-            for _ in 0..points_in_cur_chunk {
+            for point_index in 0..points_in_cur_chunk {
+                let point_context =
+                    || invalid_point_context(first_point_index, start_point_index + point_index);
                 let pos_x = point_read.read_f64::<NativeEndian>()?;
                 let pos_y = point_read.read_f64::<NativeEndian>()?;
                 let pos_z = point_read.read_f64::<NativeEndian>()?;
@@ -230,23 +670,21 @@ impl<T: std::io::Write + std::io::Seek> RawLASWriter<T> {
                 write_position_as_las_position(
                     &world_space_position,
                     &self.current_header,
-                    &mut self.writer,
+                    &mut las_point_write,
                 )?;
-                update_bounds_in_las_header(&world_space_position, &mut self.current_header);
+                chunk_bounds.include(&world_space_position);
 
                 let intensity = point_read.read_u16::<NativeEndian>()?;
-                self.writer.write_u16::<LittleEndian>(intensity)?;
+                las_point_write.write_u16::<LittleEndian>(intensity)?;
 
                 let bit_attributes = if source_format.is_extended {
                     let return_number = point_read.read_u8()?;
-                    if let Some(count) = points_by_return.get_mut(&return_number) {
-                        *count += 1;
-                    }
                     let number_of_returns = point_read.read_u8()?;
                     let classification_flags = point_read.read_u8()?;
                     let scanner_channel = point_read.read_u8()?;
                     let scan_direction_flag = point_read.read_u8()?;
                     let edge_of_flight_line = point_read.read_u8()?;
+                    let overlap = point_read.read_u8()?;
                     BitAttributes::Extended(BitAttributesExtended {
                         return_number,
                         number_of_returns,
@@ -254,12 +692,10 @@ impl<T: std::io::Write + std::io::Seek> RawLASWriter<T> {
                         scanner_channel,
                         scan_direction_flag,
                         edge_of_flight_line,
+                        overlap,
                     })
                 } else {
                     let return_number = point_read.read_u8()?;
-                    if let Some(count) = points_by_return.get_mut(&return_number) {
-                        *count += 1;
-                    }
                     let number_of_returns = point_read.read_u8()?;
                     let scan_direction_flag = point_read.read_u8()?;
                     let edge_of_flight_line = point_read.read_u8()?;
@@ -269,46 +705,78 @@ impl<T: std::io::Write + std::io::Seek> RawLASWriter<T> {
                         scan_direction_flag,
                         edge_of_flight_line,
                     })
-                };
-                write_las_bit_attributes(bit_attributes, &mut self.writer)?;
+                }
+                .fit_to_bit_widths(self.bit_attribute_overflow)
+                .with_context(point_context)?;
+                if let Some(count) = points_by_return.get_mut(&bit_attributes.return_number()) {
+                    *count += 1;
+                }
+                write_las_bit_attributes(bit_attributes, &mut las_point_write)?;
 
                 let classification = point_read.read_u8()?;
-                self.writer.write_u8(classification)?;
+                if source_format.is_extended {
+                    las_point_write.write_u8(classification)?;
+                } else {
+                    let classification_flags = point_read.read_u8()?;
+                    if drops_legacy_overlap_flag(
+                        0,
+                        classification_flags,
+                        self.overlap_flag_handling,
+                    )
+                    .with_context(point_context)?
+                    {
+                        self.dropped_overlap_flags += 1;
+                    }
+                    las_point_write.write_u8(
+                        fit_legacy_classification_byte(
+                            classification,
+                            classification_flags,
+                            self.bit_attribute_overflow,
+                        )
+                        .with_context(point_context)?,
+                    )?;
+                }
 
                 if source_format.is_extended {
                     let user_data = point_read.read_u8()?;
                     let scan_angle = point_read.read_i16::<NativeEndian>()?;
 
-                    self.writer.write_u8(user_data)?;
-                    self.writer.write_i16::<LittleEndian>(scan_angle)?;
+                    las_point_write.write_u8(user_data)?;
+                    las_point_write.write_i16::<LittleEndian>(
+                        fit_extended_scan_angle(scan_angle, self.bit_attribute_overflow)
+                            .with_context(point_context)?,
+                    )?;
                 } else {
                     let scan_angle = point_read.read_i8()?;
                     let user_data = point_read.read_u8()?;
 
-                    self.writer.write_i8(scan_angle)?;
-                    self.writer.write_u8(user_data)?;
+                    las_point_write.write_i8(
+                        fit_scan_angle_rank(scan_angle, self.bit_attribute_overflow)
+                            .with_context(point_context)?,
+                    )?;
+                    las_point_write.write_u8(user_data)?;
                 }
 
                 let point_source_id = point_read.read_u16::<NativeEndian>()?;
-                self.writer.write_u16::<LittleEndian>(point_source_id)?;
+                las_point_write.write_u16::<LittleEndian>(point_source_id)?;
 
                 if source_format.has_gps_time {
                     let gps_time = point_read.read_f64::<NativeEndian>()?;
-                    self.writer.write_f64::<LittleEndian>(gps_time)?;
+                    las_point_write.write_f64::<LittleEndian>(gps_time)?;
                 }
 
                 if source_format.has_color {
                     let r = point_read.read_u16::<NativeEndian>()?;
                     let g = point_read.read_u16::<NativeEndian>()?;
                     let b = point_read.read_u16::<NativeEndian>()?;
-                    self.writer.write_u16::<LittleEndian>(r)?;
-                    self.writer.write_u16::<LittleEndian>(g)?;
-                    self.writer.write_u16::<LittleEndian>(b)?;
+                    las_point_write.write_u16::<LittleEndian>(r)?;
+                    las_point_write.write_u16::<LittleEndian>(g)?;
+                    las_point_write.write_u16::<LittleEndian>(b)?;
                 }
 
                 if source_format.has_nir {
                     let nir = point_read.read_u16::<NativeEndian>()?;
-                    self.writer.write_u16::<LittleEndian>(nir)?;
+                    las_point_write.write_u16::<LittleEndian>(nir)?;
                 }
 
                 if source_format.has_waveform {
@@ -320,25 +788,36 @@ impl<T: std::io::Write + std::io::Seek> RawLASWriter<T> {
                     let py = point_read.read_f32::<NativeEndian>()?;
                     let pz = point_read.read_f32::<NativeEndian>()?;
 
-                    self.writer.write_u8(wave_descriptor)?;
-                    self.writer.write_u64::<LittleEndian>(wave_data_offset)?;
-                    self.writer.write_u32::<LittleEndian>(wave_packet_size)?;
-                    self.writer.write_f32::<LittleEndian>(wave_return_point)?;
-                    self.writer.write_f32::<LittleEndian>(px)?;
-                    self.writer.write_f32::<LittleEndian>(py)?;
-                    self.writer.write_f32::<LittleEndian>(pz)?;
+                    las_point_write.write_u8(wave_descriptor)?;
+                    las_point_write.write_u64::<LittleEndian>(wave_data_offset)?;
+                    las_point_write.write_u32::<LittleEndian>(wave_packet_size)?;
+                    las_point_write.write_f32::<LittleEndian>(wave_return_point)?;
+                    las_point_write.write_f32::<LittleEndian>(px)?;
+                    las_point_write.write_f32::<LittleEndian>(py)?;
+                    las_point_write.write_f32::<LittleEndian>(pz)?;
                 }
             }
 
+            las_point_buffer = las_point_write.into_inner();
+            // Only the records of the current chunk are written, the rest of the buffer contains stale records
+            self.writer
+                .write_all(&las_point_buffer[..points_in_cur_chunk * size_of_las_point])
+                .context("Failed to write chunk of points")?;
+
+            // The header is only updated for chunks that have been written completely, so that it still matches the
+            // written point records if a later chunk contains invalid points
+            chunk_bounds.update_las_header(&mut self.current_header);
+            update_point_counts_in_las_header(
+                points_in_cur_chunk,
+                &points_by_return,
+                &mut self.current_header,
+            );
+            self.requires_flush = true;
+
             chunk_buffer = point_read.into_inner();
         }
-
-        update_point_counts_in_las_header(
-            points.len(),
-            &points_by_return,
-            &mut self.current_header,
-        );
-        self.requires_flush = true;
+        self.chunk_buffer = chunk_buffer;
+        self.las_point_buffer = las_point_buffer;
 
         Ok(())
     }
@@ -350,11 +829,14 @@ impl<T: std::io::Write + std::io::Seek> RawLASWriter<T> {
         if points.is_empty() {
             return Ok(());
         }
+        validate_source_layout_for_las(points.point_layout())?;
 
         let size_of_single_point = points.point_layout().size_of_point_entry() as usize;
-        let num_points_in_chunk = 50_000;
+        let num_points_in_chunk = self.write_chunk_size.min(points.len());
         let num_chunks = (points.len() + (num_points_in_chunk - 1)) / num_points_in_chunk;
-        let mut chunk_buffer: Vec<u8> = vec![0; num_points_in_chunk * size_of_single_point];
+        let size_of_las_point = self.current_header.point_data_record_length as usize;
+        let (mut chunk_buffer, mut las_point_buffer) =
+            self.take_chunk_buffers(num_points_in_chunk, size_of_single_point);
 
         let target_format = Format::new(self.current_header.point_data_record_format)?;
 
@@ -371,106 +853,166 @@ impl<T: std::io::Write + std::io::Seek> RawLASWriter<T> {
         // TODO All the attribute readers return different types. Is there a way to still store them in a vec and iterate over them?
         // A generic 'convert N points from layout A to layout B' function would be nice
 
-        let position_reader = get_position_reader(points.point_layout());
-        let intensity_reader = get_intensity_reader(points.point_layout());
-        let return_number_reader = get_return_number_reader(points.point_layout());
-        let number_of_returns_reader = get_number_of_returns_reader(points.point_layout());
-        let classification_flags_reader = if target_format.is_extended {
-            Some(get_classification_flags_reader(points.point_layout()))
-        } else {
-            None
-        };
+        // Positions are mandatory (see `validate_source_layout_for_las`), so their default value is never used
+        let position_reader = get_position_reader(points.point_layout(), Vector3::zeros())?;
+        let defaults = self.missing_attribute_defaults;
+        let intensity_reader = get_intensity_reader(points.point_layout(), defaults.intensity)?;
+        let return_number_reader =
+            get_return_number_reader(points.point_layout(), defaults.return_number)?;
+        let number_of_returns_reader =
+            get_number_of_returns_reader(points.point_layout(), defaults.number_of_returns)?;
+        let classification_flags_reader =
+            get_classification_flags_reader(points.point_layout(), defaults.classification_flags)?;
         let scanner_channel_reader = if target_format.is_extended {
-            Some(get_scanner_channel_reader(points.point_layout()))
+            Some(get_scanner_channel_reader(
+                points.point_layout(),
+                defaults.scanner_channel,
+            )?)
         } else {
             None
         };
-        let scan_direction_flag_reader = get_scan_direction_flag_reader(points.point_layout());
-        let edge_of_flight_line_reader = get_edge_of_flight_line_reader(points.point_layout());
-        let classification_reader = get_classification_reader(points.point_layout());
-        let user_data_reader = get_user_data_reader(points.point_layout());
+        let scan_direction_flag_reader =
+            get_scan_direction_flag_reader(points.point_layout(), defaults.scan_direction_flag)?;
+        let edge_of_flight_line_reader =
+            get_edge_of_flight_line_reader(points.point_layout(), defaults.edge_of_flight_line)?;
+        // The overlap flag is also read for the point formats 0-5, which can't store it, to detect if it is dropped
+        let overlap_reader = get_overlap_reader(points.point_layout(), defaults.overlap)?;
+        let classification_reader =
+            get_classification_reader(points.point_layout(), defaults.classification)?;
+        let user_data_reader = get_user_data_reader(points.point_layout(), defaults.user_data)?;
         let scan_angle_reader = if target_format.is_extended {
             None
         } else {
-            Some(get_scan_angle_rank_reader(points.point_layout()))
+            Some(get_scan_angle_rank_reader(
+                points.point_layout(),
+                defaults.scan_angle_rank,
+            )?)
         };
         let extended_scan_angle_reader = if target_format.is_extended {
-            Some(get_extended_scan_angle_rank_reader(points.point_layout()))
+            Some(get_extended_scan_angle_rank_reader(
+                points.point_layout(),
+                defaults.scan_angle,
+            )?)
         } else {
             None
         };
-        let point_source_id_reader = get_point_source_id_reader(points.point_layout());
+        let point_source_id_reader =
+            get_point_source_id_reader(points.point_layout(), defaults.point_source_id)?;
         let gps_time_reader = if target_format.has_gps_time {
-            Some(get_gps_time_reader(points.point_layout()))
+            Some(get_gps_time_reader(
+                points.point_layout(),
+                defaults.gps_time,
+            )?)
         } else {
             None
         };
         let color_reader = if target_format.has_color {
-            Some(get_color_reader(points.point_layout()))
+            Some(get_upscaled_color_reader(
+                points.point_layout(),
+                defaults.color_rgb,
+                self.color_upscaling,
+            )?)
         } else {
             None
         };
         let nir_reader = if target_format.has_nir {
-            Some(get_nir_reader(points.point_layout()))
+            Some(get_nir_reader(points.point_layout(), defaults.nir)?)
         } else {
             None
         };
         let wave_packet_descriptor_index_reader = if target_format.has_waveform {
             Some(get_wave_packet_descriptor_index_reader(
                 points.point_layout(),
-            ))
+                defaults.wave_packet_descriptor_index,
+            )?)
         } else {
             None
         };
         let waveform_data_offset_reader = if target_format.has_waveform {
-            Some(get_waveform_data_offset_reader(points.point_layout()))
+            Some(get_waveform_data_offset_reader(
+                points.point_layout(),
+                defaults.waveform_data_offset,
+            )?)
         } else {
             None
         };
         let waveform_packet_size_reader = if target_format.has_waveform {
-            Some(get_waveform_packet_size_reader(points.point_layout()))
+            Some(get_waveform_packet_size_reader(
+                points.point_layout(),
+                defaults.waveform_packet_size,
+            )?)
         } else {
             None
         };
         let return_point_waveform_location_reader = if target_format.has_waveform {
             Some(get_return_point_waveform_location_reader(
                 points.point_layout(),
-            ))
+                defaults.return_point_waveform_location,
+            )?)
         } else {
             None
         };
         let waveform_parameters_reader = if target_format.has_waveform {
-            Some(get_waveform_parameters_reader(points.point_layout()))
+            Some(get_waveform_parameters_reader(
+                points.point_layout(),
+                defaults.waveform_parameters,
+            )?)
+        } else {
+            None
+        };
+
+        let extra_bytes_writer = if self.num_extra_bytes > 0 {
+            Some(ExtraBytesWriter::new(
+                &self.extra_bytes_entries,
+                self.num_extra_bytes,
+                points.point_layout(),
+            )?)
         } else {
             None
         };
 
+        let first_point_index = self
+            .current_header
+            .large_file
+            .as_ref()
+            .map(|large_file| large_file.number_of_point_records)
+            .unwrap_or_default();
         for chunk_index in 0..num_chunks {
+            let mut chunk_bounds = ChunkBounds::new();
+            points_by_return.values_mut().for_each(|count| *count = 0);
             let points_in_cur_chunk = std::cmp::min(
                 num_points_in_chunk,
                 points.len() - (chunk_index * num_points_in_chunk),
             );
             let start_point_index = chunk_index * num_points_in_chunk;
-            points.get_point_range(
+            gather_point_range(
+                points,
                 start_point_index..(start_point_index + points_in_cur_chunk),
                 &mut chunk_buffer[0..(points_in_cur_chunk * size_of_single_point)],
             );
             let mut point_read = Cursor::new(chunk_buffer);
+            let mut las_point_write = Cursor::new(las_point_buffer);
 
             // Read all the attributes from the raw memory inside `points` and transform them into the format that LAS expects
             for point_index in 0..points_in_cur_chunk {
+                let point_context =
+                    || invalid_point_context(first_point_index, start_point_index + point_index);
                 let position = position_reader(point_index, &mut point_read)?;
-                write_position_as_las_position(&position, &self.current_header, &mut self.writer)?;
-                update_bounds_in_las_header(&position, &mut self.current_header);
+                write_position_as_las_position(
+                    &position,
+                    &self.current_header,
+                    &mut las_point_write,
+                )?;
+                chunk_bounds.include(&position);
 
-                self.writer
+                las_point_write
                     .write_u16::<LittleEndian>(intensity_reader(point_index, &mut point_read)?)?;
 
                 let bit_attributes: BitAttributes = if target_format.is_extended {
                     BitAttributes::Extended(BitAttributesExtended {
                         return_number: return_number_reader(point_index, &mut point_read)?,
                         number_of_returns: number_of_returns_reader(point_index, &mut point_read)?,
-                        classification_flags: classification_flags_reader.as_ref().unwrap()(
+                        classification_flags: classification_flags_reader(
                             point_index,
                             &mut point_read,
                         )?,
@@ -486,6 +1028,7 @@ impl<T: std::io::Write + std::io::Seek> RawLASWriter<T> {
                             point_index,
                             &mut point_read,
                         )?,
+                        overlap: overlap_reader(point_index, &mut point_read)?,
                     })
                 } else {
                     BitAttributes::Regular(BitAttributesRegular {
@@ -500,93 +1043,268 @@ impl<T: std::io::Write + std::io::Seek> RawLASWriter<T> {
                             &mut point_read,
                         )?,
                     })
-                };
-                write_las_bit_attributes(bit_attributes, &mut self.writer)?;
+                }
+                .fit_to_bit_widths(self.bit_attribute_overflow)
+                .with_context(point_context)?;
+                if let Some(count) = points_by_return.get_mut(&bit_attributes.return_number()) {
+                    *count += 1;
+                }
+                write_las_bit_attributes(bit_attributes, &mut las_point_write)?;
 
-                self.writer
-                    .write_u8(classification_reader(point_index, &mut point_read)?)?;
+                let classification = classification_reader(point_index, &mut point_read)?;
+                if target_format.is_extended {
+                    las_point_write.write_u8(classification)?;
+                } else {
+                    let classification_flags =
+                        classification_flags_reader(point_index, &mut point_read)?;
+                    let overlap = overlap_reader(point_index, &mut point_read)?;
+                    if drops_legacy_overlap_flag(
+                        overlap,
+                        classification_flags,
+                        self.overlap_flag_handling,
+                    )
+                    .with_context(point_context)?
+                    {
+                        self.dropped_overlap_flags += 1;
+                    }
+                    las_point_write.write_u8(
+                        fit_legacy_classification_byte(
+                            classification,
+                            classification_flags,
+                            self.bit_attribute_overflow,
+                        )
+                        .with_context(point_context)?,
+                    )?;
+                }
 
                 if target_format.is_extended {
-                    self.writer
-                        .write_u8(user_data_reader(point_index, &mut point_read)?)?;
-                    self.writer
-                        .write_i16::<LittleEndian>(extended_scan_angle_reader.as_ref().unwrap()(
-                            point_index,
-                            &mut point_read,
-                        )?)?;
+                    las_point_write.write_u8(user_data_reader(point_index, &mut point_read)?)?;
+                    let scan_angle =
+                        extended_scan_angle_reader.as_ref().unwrap()(point_index, &mut point_read)?;
+                    las_point_write.write_i16::<LittleEndian>(
+                        fit_extended_scan_angle(scan_angle, self.bit_attribute_overflow)
+                            .with_context(point_context)?,
+                    )?;
                 } else {
-                    self.writer.write_i8(scan_angle_reader.as_ref().unwrap()(
-                        point_index,
-                        &mut point_read,
-                    )?)?;
-                    self.writer
-                        .write_u8(user_data_reader(point_index, &mut point_read)?)?;
+                    let scan_angle =
+                        scan_angle_reader.as_ref().unwrap()(point_index, &mut point_read)?;
+                    las_point_write.write_i8(
+                        fit_scan_angle_rank(scan_angle, self.bit_attribute_overflow)
+                            .with_context(point_context)?,
+                    )?;
+                    las_point_write.write_u8(user_data_reader(point_index, &mut point_read)?)?;
                 }
 
-                self.writer
-                    .write_u16::<LittleEndian>(point_source_id_reader(
-                        point_index,
-                        &mut point_read,
-                    )?)?;
+                las_point_write.write_u16::<LittleEndian>(point_source_id_reader(
+                    point_index,
+                    &mut point_read,
+                )?)?;
 
                 if let Some(ref reader) = gps_time_reader {
-                    self.writer
+                    las_point_write
                         .write_f64::<LittleEndian>(reader(point_index, &mut point_read)?)?;
                 }
 
                 if let Some(ref reader) = color_reader {
                     let color = reader(point_index, &mut point_read)?;
-                    self.writer.write_u16::<LittleEndian>(color.x)?;
-                    self.writer.write_u16::<LittleEndian>(color.y)?;
-                    self.writer.write_u16::<LittleEndian>(color.z)?;
+                    las_point_write.write_u16::<LittleEndian>(color.x)?;
+                    las_point_write.write_u16::<LittleEndian>(color.y)?;
+                    las_point_write.write_u16::<LittleEndian>(color.z)?;
                 }
 
                 if let Some(ref reader) = nir_reader {
-                    self.writer
+                    las_point_write
                         .write_u16::<LittleEndian>(reader(point_index, &mut point_read)?)?;
                 }
 
                 if let Some(ref reader) = wave_packet_descriptor_index_reader {
-                    self.writer
-                        .write_u8(reader(point_index, &mut point_read)?)?;
+                    las_point_write.write_u8(reader(point_index, &mut point_read)?)?;
                 }
                 if let Some(ref reader) = waveform_data_offset_reader {
-                    self.writer
+                    las_point_write
                         .write_u64::<LittleEndian>(reader(point_index, &mut point_read)?)?;
                 }
                 if let Some(ref reader) = waveform_packet_size_reader {
-                    self.writer
+                    las_point_write
                         .write_u32::<LittleEndian>(reader(point_index, &mut point_read)?)?;
                 }
                 if let Some(ref reader) = return_point_waveform_location_reader {
-                    self.writer
+                    las_point_write
                         .write_f32::<LittleEndian>(reader(point_index, &mut point_read)?)?;
                 }
                 if let Some(ref reader) = waveform_parameters_reader {
                     let params = reader(point_index, &mut point_read)?;
-                    self.writer.write_f32::<LittleEndian>(params.x)?;
-                    self.writer.write_f32::<LittleEndian>(params.y)?;
-                    self.writer.write_f32::<LittleEndian>(params.z)?;
+                    las_point_write.write_f32::<LittleEndian>(params.x)?;
+                    las_point_write.write_f32::<LittleEndian>(params.y)?;
+                    las_point_write.write_f32::<LittleEndian>(params.z)?;
+                }
+
+                if let Some(ref extra_bytes_writer) = extra_bytes_writer {
+                    let point_start = point_index * size_of_single_point;
+                    extra_bytes_writer.write_extra_bytes(
+                        &point_read.get_ref()[point_start..(point_start + size_of_single_point)],
+                        &mut las_point_write,
+                    )?;
                 }
             }
 
+            las_point_buffer = las_point_write.into_inner();
+            // Only the records of the current chunk are written, the rest of the buffer contains stale records
+            self.writer
+                .write_all(&las_point_buffer[..points_in_cur_chunk * size_of_las_point])
+                .context("Failed to write chunk of points")?;
+
+            // The header is only updated for chunks that have been written completely, so that it still matches the
+            // written point records if a later chunk contains invalid points
+            chunk_bounds.update_las_header(&mut self.current_header);
+            update_point_counts_in_las_header(
+                points_in_cur_chunk,
+                &points_by_return,
+                &mut self.current_header,
+            );
+            self.requires_flush = true;
+
             chunk_buffer = point_read.into_inner();
         }
-
-        update_point_counts_in_las_header(
-            points.len(),
-            &points_by_return,
-            &mut self.current_header,
-        );
-        self.requires_flush = true;
+        self.chunk_buffer = chunk_buffer;
+        self.las_point_buffer = las_point_buffer;
 
         Ok(())
     }
 }
 
+impl RawLASWriter<BufWriter<File>> {
+    /// Creates a `RawLASWriter` that appends points to the existing, uncompressed LAS file `file`. The header,
+    /// VLRs and existing point records are kept, new points are written after the last point record and the EVLRs
+    /// of the file are moved behind the new points when the writer is flushed
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `file` is a compressed LAZ file, if the header states more point records than there are in
+    /// the file, or if the file contains waveform data packets that can't be relocated (LAS 1.3)
+    pub fn append_to_file(mut file: File) -> Result<Self> {
+        if read_is_compressed_las_file(&mut file)? {
+            bail!("Appending points to compressed LAZ files is not supported");
+        }
+        let header = {
+            let reader = RawLASReader::from_read_with_options(
+                &mut file,
+                LASReadOptions {
+                    strict_point_count: true,
+                    ..Default::default()
+                },
+            )
+            .context("Could not read the LAS file to append to")?;
+            reader
+                .las_metadata()
+                .raw_las_header()
+                .cloned()
+                .ok_or_else(|| anyhow!("LAS file to append to has no LAS header"))?
+        };
+        let las_metadata = (&header).try_into().context("Could not parse LAS header")?;
+        let default_layout = point_layout_from_las_metadata(&las_metadata, false)
+            .context("Could not determine PointLayout from given LAS header")?;
+        let extra_bytes_entries = las_metadata
+            .extra_bytes_vlr()
+            .map(|vlr| vlr.entries().to_vec())
+            .unwrap_or_default();
+        let num_extra_bytes = header.point_format().extra_bytes as usize;
+
+        file.seek(SeekFrom::Start(0))?;
+        let mut raw_header = las::raw::Header::read_from(&mut file)?;
+        // In LAS 1.3, the waveform data packets are stored in an EVLR that las-rs doesn't read, so it would be
+        // overwritten by the new points
+        if (raw_header.version.major, raw_header.version.minor) == (1, 3)
+            && raw_header.global_encoding & GLOBAL_ENCODING_INTERNAL_WAVEFORM_DATA_BIT != 0
+            && raw_header
+                .start_of_waveform_data_packet_record
+                .unwrap_or_default()
+                != 0
+        {
+            bail!("Can't append points to a LAS 1.3 file with internal waveform data packets, as they can't be relocated behind the new points");
+        }
+
+        let point_count = point_count_from_raw_header(&raw_header);
+        // Pasture always uses the 'large_file' field for keeping track of the number of points
+        let legacy_points_by_return = raw_header.number_of_points_by_return;
+        let large_file = raw_header.large_file.get_or_insert_with(Default::default);
+        if large_file.number_of_points_by_return == [0; 15] {
+            for (count, legacy_count) in large_file
+                .number_of_points_by_return
+                .iter_mut()
+                .zip(legacy_points_by_return.iter())
+            {
+                *count = *legacy_count as u64;
+            }
+        }
+        large_file.number_of_point_records = point_count;
+        if point_count == 0 {
+            raw_header.min_x = std::f64::MAX;
+            raw_header.min_y = std::f64::MAX;
+            raw_header.min_z = std::f64::MAX;
+            raw_header.max_x = std::f64::MIN;
+            raw_header.max_y = std::f64::MIN;
+            raw_header.max_z = std::f64::MIN;
+        }
+
+        let point_start_index = raw_header.offset_to_point_data as u64;
+        let end_of_point_records =
+            point_start_index + point_count * raw_header.point_data_record_length as u64;
+        let mut writer = BufWriter::new(file);
+        writer.seek(SeekFrom::Start(end_of_point_records))?;
+
+        Ok(Self {
+            writer,
+            default_layout,
+            current_header: raw_header,
+            evlrs: header
+                .evlrs()
+                .iter()
+                .map(|evlr| evlr.clone().into_raw(true))
+                .collect::<Result<Vec<_>, _>>()?,
+            vlrs: header.vlrs().clone(),
+            extra_bytes_entries,
+            num_extra_bytes,
+            bit_attribute_overflow: Default::default(),
+            overlap_flag_handling: Default::default(),
+            dropped_overlap_flags: 0,
+            missing_attribute_defaults: Default::default(),
+            color_upscaling: Default::default(),
+            write_chunk_size: DEFAULT_WRITE_CHUNK_SIZE,
+            chunk_buffer: vec![],
+            las_point_buffer: vec![],
+            _point_start_index: point_start_index,
+            requires_flush: true,
+            streaming_statistics: None,
+            is_streaming_finished: false,
+        })
+    }
+}
+
 impl<T: std::io::Write + std::io::Seek> PointWriter for RawLASWriter<T> {
     fn write<'a, B: BorrowedBuffer<'a>>(&mut self, points: &'a B) -> Result<()> {
-        if *points.point_layout() == self.default_layout {
+        if let Some(statistics) = &self.streaming_statistics {
+            if self.is_streaming_finished {
+                bail!("Can't write points to a streaming LAS writer after it has been finalized");
+            }
+            let points_written = self
+                .current_header
+                .large_file
+                .as_ref()
+                .map(|large_file| large_file.number_of_point_records)
+                .unwrap_or_default();
+            if points_written + points.len() as u64 > statistics.point_count {
+                bail!(
+                    "Can't write {} more points, as the LAS header states {} points and {} points have already been written",
+                    points.len(),
+                    statistics.point_count,
+                    points_written
+                );
+            }
+        }
+        // The default layout contains the extra bytes in their scaled representation, so points with extra bytes are
+        // always written through the conversion path
+        if *points.point_layout() == self.default_layout && self.num_extra_bytes == 0 {
             self.write_points_default_layout(points)
         } else {
             self.write_points_custom_layout(points)
@@ -594,576 +1312,10 @@ impl<T: std::io::Write + std::io::Seek> PointWriter for RawLASWriter<T> {
     }
 
     fn flush(&mut self) -> Result<()> {
-        if !self.requires_flush {
-            return Ok(());
-        }
-
-        let current_index = self.writer.stream_position()?;
-        self.write_header()?;
-        self.write_evlrs()?;
-        self.writer.seek(SeekFrom::Start(current_index))?;
-
-        self.requires_flush = false;
-
-        Ok(())
-    }
-
-    fn get_default_point_layout(&self) -> &PointLayout {
-        &self.default_layout
-    }
-}
-
-pub(crate) struct RawLAZWriter<T: std::io::Write + std::io::Seek + Send + 'static> {
-    writer: LasZipCompressor<'static, T>,
-    default_layout: PointLayout,
-    current_header: las::raw::Header,
-    evlrs: Vec<las::raw::Vlr>,
-    requires_flush: bool,
-}
-
-impl<T: std::io::Write + std::io::Seek + Send + 'static> RawLAZWriter<T> {
-    pub fn from_write_and_header(mut write: T, header: las::Header) -> Result<Self> {
-        let las_metadata = (&header).try_into().context("Could not parse LAS header")?;
-        let default_layout = point_layout_from_las_metadata(&las_metadata, false)
-            .context("Could not determine PointLayout from given LAS header")?;
-
-        if header.point_format().extra_bytes != 0 {
-            panic!("Extra bytes in LAZ point records are currently unsupported!");
-        }
-
-        let mut raw_header = header.clone().into_raw()?;
-        // raw_header.version = Version::new(1, 2);
-        raw_header.number_of_point_records = 0;
-        raw_header.number_of_points_by_return = [0; 5];
-        // Pasture always uses the 'large_file' field for keeping track of the number of points
-        raw_header.large_file = Some(Default::default());
-        raw_header.min_x = std::f64::INFINITY;
-        raw_header.min_y = std::f64::INFINITY;
-        raw_header.min_z = std::f64::INFINITY;
-        raw_header.max_x = std::f64::NEG_INFINITY;
-        raw_header.max_y = std::f64::NEG_INFINITY;
-        raw_header.max_z = std::f64::NEG_INFINITY;
-
-        if raw_header.x_scale_factor == 0.0
-            || raw_header.y_scale_factor == 0.0
-            || raw_header.z_scale_factor == 0.0
-        {
-            return Err(anyhow!("RawLASWriter::from_write_and_header: Scale factors in LAS header must not be zero!"));
-        }
-
-        // Create LAZ VLR in addition to the other VLRs in the header
-        let laz_items = LazItemRecordBuilder::default_for_point_format_id(
-            header.point_format().to_u8()?,
-            header.point_format().extra_bytes,
-        )
-        .map_err(map_laz_err)?;
-        let raw_laz_vlr = LazVlr::from_laz_items(laz_items);
-        let mut raw_laz_vlr_cursor = Cursor::new(Vec::<u8>::new());
-        raw_laz_vlr.write_to(&mut raw_laz_vlr_cursor)?;
-        let laz_vlr = Vlr {
-            user_id: laz::LazVlr::USER_ID.to_owned(),
-            record_id: laz::LazVlr::RECORD_ID,
-            description: laz::LazVlr::DESCRIPTION.to_owned(),
-            data: raw_laz_vlr_cursor.into_inner(),
-        };
-
-        let mut header_builder = Builder::new(raw_header)?;
-        header_builder.vlrs.push(laz_vlr);
-        let header_with_laz_vlr = header_builder.into_header()?;
-        header_with_laz_vlr
-            .clone()
-            .into_raw()
-            .and_then(|raw_header_with_laz_vlr| raw_header_with_laz_vlr.write_to(&mut write))?;
-        for vlr in header_with_laz_vlr.vlrs() {
-            vlr.clone()
-                .into_raw(false)
-                .and_then(|raw_vlr| raw_vlr.write_to(&mut write))?;
-        }
-        if !header.vlr_padding().is_empty() {
-            write.write_all(header.vlr_padding())?;
-        }
-
-        let laz_writer = LasZipCompressor::new(write, raw_laz_vlr).map_err(map_laz_err)?;
-
-        Ok(Self {
-            writer: laz_writer,
-            default_layout,
-            current_header: header_with_laz_vlr.into_raw()?,
-            evlrs: header
-                .evlrs()
-                .iter()
-                .map(|evlr| evlr.clone().into_raw(true))
-                .collect::<Result<Vec<_>, _>>()?,
-            requires_flush: false,
-        })
-    }
-
-    /// Consumes this `RawLASWriter` and returns the underlying write type `T`. The data is flushed
-    /// before returning the writer
-    pub fn into_inner(mut self) -> Result<T> {
-        self.do_flush()?;
-        Ok(self.writer.into_inner())
-    }
-
-    fn write_points_default_layout<'a, B: BorrowedBuffer<'a>>(
-        &mut self,
-        points: &'a B,
-    ) -> Result<()> {
-        if points.is_empty() {
-            return Ok(());
-        }
-
-        // Similar to RawLASReader, write points in chunks of a fixed size to prevent overhead of
-        // repeated virtual calls to 'dyn PointBuffer'
-
-        let size_of_single_point = self.default_layout.size_of_point_entry() as usize;
-        let num_points_in_chunk = 50_000;
-        let num_chunks = (points.len() + (num_points_in_chunk - 1)) / num_points_in_chunk;
-        let mut chunk_buffer: Vec<u8> = vec![0; num_points_in_chunk * size_of_single_point];
-        let mut las_point_buffer: Vec<u8> =
-            vec![0; num_points_in_chunk * self.current_header.point_data_record_length as usize];
-
-        let source_format = Format::new(self.current_header.point_data_record_format)?;
-
-        let mut points_by_return: HashMap<u8, u64> = HashMap::new();
-        let max_return_number = if self.current_header.large_file.is_some() {
-            15
-        } else {
-            5
-        };
-        for return_number in 1..=max_return_number {
-            points_by_return.insert(return_number, 0);
-        }
-
-        for chunk_index in 0..num_chunks {
-            let points_in_cur_chunk = std::cmp::min(
-                num_points_in_chunk,
-                points.len() - (chunk_index * num_points_in_chunk),
-            );
-            let start_point_index = chunk_index * num_points_in_chunk;
-            points.get_point_range(
-                start_point_index..(start_point_index + points_in_cur_chunk),
-                &mut chunk_buffer[..(points_in_cur_chunk * size_of_single_point)],
-            );
-            let mut point_read = Cursor::new(chunk_buffer);
-            let mut las_point_write = Cursor::new(las_point_buffer);
-
-            // Read all the attributes from the raw memory inside `points` and transform them into the format that LAS expects
-            for _ in 0..points_in_cur_chunk {
-                let pos_x = point_read.read_f64::<NativeEndian>()?;
-                let pos_y = point_read.read_f64::<NativeEndian>()?;
-                let pos_z = point_read.read_f64::<NativeEndian>()?;
-                let world_space_position = Vector3::new(pos_x, pos_y, pos_z);
-                write_position_as_las_position(
-                    &world_space_position,
-                    &self.current_header,
-                    &mut las_point_write,
-                )?;
-                update_bounds_in_las_header(&world_space_position, &mut self.current_header);
-
-                let intensity = point_read.read_u16::<NativeEndian>()?;
-                las_point_write.write_u16::<LittleEndian>(intensity)?;
-
-                let bit_attributes = if source_format.is_extended {
-                    let return_number = point_read.read_u8()?;
-                    if let Some(count) = points_by_return.get_mut(&return_number) {
-                        *count += 1;
-                    }
-                    let number_of_returns = point_read.read_u8()?;
-                    let classification_flags = point_read.read_u8()?;
-                    let scanner_channel = point_read.read_u8()?;
-                    let scan_direction_flag = point_read.read_u8()?;
-                    let edge_of_flight_line = point_read.read_u8()?;
-                    BitAttributes::Extended(BitAttributesExtended {
-                        return_number,
-                        number_of_returns,
-                        classification_flags,
-                        scanner_channel,
-                        scan_direction_flag,
-                        edge_of_flight_line,
-                    })
-                } else {
-                    let return_number = point_read.read_u8()?;
-                    if let Some(count) = points_by_return.get_mut(&return_number) {
-                        *count += 1;
-                    }
-                    let number_of_returns = point_read.read_u8()?;
-                    let scan_direction_flag = point_read.read_u8()?;
-                    let edge_of_flight_line = point_read.read_u8()?;
-                    BitAttributes::Regular(BitAttributesRegular {
-                        return_number,
-                        number_of_returns,
-                        scan_direction_flag,
-                        edge_of_flight_line,
-                    })
-                };
-                write_las_bit_attributes(bit_attributes, &mut las_point_write)?;
-
-                let classification = point_read.read_u8()?;
-                las_point_write.write_u8(classification)?;
-
-                if source_format.is_extended {
-                    let user_data = point_read.read_u8()?;
-                    let scan_angle = point_read.read_i16::<NativeEndian>()?;
-
-                    las_point_write.write_u8(user_data)?;
-                    las_point_write.write_i16::<LittleEndian>(scan_angle)?;
-                } else {
-                    let scan_angle = point_read.read_i8()?;
-                    let user_data = point_read.read_u8()?;
-
-                    las_point_write.write_i8(scan_angle)?;
-                    las_point_write.write_u8(user_data)?;
-                }
-
-                let point_source_id = point_read.read_u16::<NativeEndian>()?;
-                las_point_write.write_u16::<LittleEndian>(point_source_id)?;
-
-                if source_format.has_gps_time {
-                    let gps_time = point_read.read_f64::<NativeEndian>()?;
-                    las_point_write.write_f64::<LittleEndian>(gps_time)?;
-                }
-
-                if source_format.has_color {
-                    let r = point_read.read_u16::<NativeEndian>()?;
-                    let g = point_read.read_u16::<NativeEndian>()?;
-                    let b = point_read.read_u16::<NativeEndian>()?;
-                    las_point_write.write_u16::<LittleEndian>(r)?;
-                    las_point_write.write_u16::<LittleEndian>(g)?;
-                    las_point_write.write_u16::<LittleEndian>(b)?;
-                }
-
-                if source_format.has_nir {
-                    let nir = point_read.read_u16::<NativeEndian>()?;
-                    las_point_write.write_u16::<LittleEndian>(nir)?;
-                }
-
-                if source_format.has_waveform {
-                    let wave_descriptor = point_read.read_u8()?;
-                    let wave_data_offset = point_read.read_u64::<NativeEndian>()?;
-                    let wave_packet_size = point_read.read_u32::<NativeEndian>()?;
-                    let wave_return_point = point_read.read_f32::<NativeEndian>()?;
-                    let px = point_read.read_f32::<NativeEndian>()?;
-                    let py = point_read.read_f32::<NativeEndian>()?;
-                    let pz = point_read.read_f32::<NativeEndian>()?;
-
-                    las_point_write.write_u8(wave_descriptor)?;
-                    las_point_write.write_u64::<LittleEndian>(wave_data_offset)?;
-                    las_point_write.write_u32::<LittleEndian>(wave_packet_size)?;
-                    las_point_write.write_f32::<LittleEndian>(wave_return_point)?;
-                    las_point_write.write_f32::<LittleEndian>(px)?;
-                    las_point_write.write_f32::<LittleEndian>(py)?;
-                    las_point_write.write_f32::<LittleEndian>(pz)?;
-                }
-            }
-
-            las_point_buffer = las_point_write.into_inner();
-            let bytes_in_current_las_chunk =
-                points_in_cur_chunk * self.current_header.point_data_record_length as usize;
-            self.writer
-                .compress_many(&las_point_buffer[..bytes_in_current_las_chunk])?;
-
-            chunk_buffer = point_read.into_inner();
-        }
-
-        update_point_counts_in_las_header(
-            points.len(),
-            &points_by_return,
-            &mut self.current_header,
-        );
-        self.requires_flush = true;
-
-        Ok(())
-    }
-
-    fn write_points_custom_layout<'a, B: BorrowedBuffer<'a>>(
-        &mut self,
-        points: &'a B,
-    ) -> Result<()> {
-        if points.is_empty() {
-            return Ok(());
-        }
-
-        let size_of_single_point = points.point_layout().size_of_point_entry() as usize;
-        let num_points_in_chunk = 50_000;
-        let num_chunks = (points.len() + (num_points_in_chunk - 1)) / num_points_in_chunk;
-        let mut chunk_buffer: Vec<u8> = vec![0; num_points_in_chunk * size_of_single_point];
-        let mut las_point_buffer: Vec<u8> =
-            vec![0; num_points_in_chunk * self.current_header.point_data_record_length as usize];
-
-        let target_format = Format::new(self.current_header.point_data_record_format)?;
-
-        let mut points_by_return: HashMap<u8, u64> = HashMap::new();
-        let max_return_number = if self.current_header.large_file.is_some() {
-            15
-        } else {
-            5
-        };
-        for return_number in 1..=max_return_number {
-            points_by_return.insert(return_number, 0);
-        }
-
-        let position_reader = get_position_reader(points.point_layout());
-        let intensity_reader = get_intensity_reader(points.point_layout());
-        let return_number_reader = get_return_number_reader(points.point_layout());
-        let number_of_returns_reader = get_number_of_returns_reader(points.point_layout());
-        let classification_flags_reader = if target_format.is_extended {
-            Some(get_classification_flags_reader(points.point_layout()))
-        } else {
-            None
-        };
-        let scanner_channel_reader = if target_format.is_extended {
-            Some(get_scanner_channel_reader(points.point_layout()))
-        } else {
-            None
-        };
-        let scan_direction_flag_reader = get_scan_direction_flag_reader(points.point_layout());
-        let edge_of_flight_line_reader = get_edge_of_flight_line_reader(points.point_layout());
-        let classification_reader = get_classification_reader(points.point_layout());
-        let user_data_reader = get_user_data_reader(points.point_layout());
-        let scan_angle_reader = if target_format.is_extended {
-            None
-        } else {
-            Some(get_scan_angle_rank_reader(points.point_layout()))
-        };
-        let extended_scan_angle_reader = if target_format.is_extended {
-            Some(get_extended_scan_angle_rank_reader(points.point_layout()))
-        } else {
-            None
-        };
-        let point_source_id_reader = get_point_source_id_reader(points.point_layout());
-        let gps_time_reader = if target_format.has_gps_time {
-            Some(get_gps_time_reader(points.point_layout()))
-        } else {
-            None
-        };
-        let color_reader = if target_format.has_color {
-            Some(get_color_reader(points.point_layout()))
-        } else {
-            None
-        };
-        let nir_reader = if target_format.has_nir {
-            Some(get_nir_reader(points.point_layout()))
-        } else {
-            None
-        };
-        let wave_packet_descriptor_index_reader = if target_format.has_waveform {
-            Some(get_wave_packet_descriptor_index_reader(
-                points.point_layout(),
-            ))
-        } else {
-            None
-        };
-        let waveform_data_offset_reader = if target_format.has_waveform {
-            Some(get_waveform_data_offset_reader(points.point_layout()))
-        } else {
-            None
-        };
-        let waveform_packet_size_reader = if target_format.has_waveform {
-            Some(get_waveform_packet_size_reader(points.point_layout()))
-        } else {
-            None
-        };
-        let return_point_waveform_location_reader = if target_format.has_waveform {
-            Some(get_return_point_waveform_location_reader(
-                points.point_layout(),
-            ))
-        } else {
-            None
-        };
-        let waveform_parameters_reader = if target_format.has_waveform {
-            Some(get_waveform_parameters_reader(points.point_layout()))
-        } else {
-            None
-        };
-
-        for chunk_index in 0..num_chunks {
-            let points_in_cur_chunk = std::cmp::min(
-                num_points_in_chunk,
-                points.len() - (chunk_index * num_points_in_chunk),
-            );
-            let start_point_index = chunk_index * num_points_in_chunk;
-            points.get_point_range(
-                start_point_index..(start_point_index + points_in_cur_chunk),
-                &mut chunk_buffer[0..(points_in_cur_chunk * size_of_single_point)],
-            );
-            let mut point_read = Cursor::new(chunk_buffer);
-            let mut las_point_write = Cursor::new(las_point_buffer);
-
-            // Read all the attributes from the raw memory inside `points` and transform them into the format that LAS expects
-            for point_index in 0..points_in_cur_chunk {
-                let position = position_reader(point_index, &mut point_read)?;
-                write_position_as_las_position(
-                    &position,
-                    &self.current_header,
-                    &mut las_point_write,
-                )?;
-                update_bounds_in_las_header(&position, &mut self.current_header);
-
-                las_point_write
-                    .write_u16::<LittleEndian>(intensity_reader(point_index, &mut point_read)?)?;
-
-                let bit_attributes: BitAttributes = if target_format.is_extended {
-                    BitAttributes::Extended(BitAttributesExtended {
-                        return_number: return_number_reader(point_index, &mut point_read)?,
-                        number_of_returns: number_of_returns_reader(point_index, &mut point_read)?,
-                        classification_flags: classification_flags_reader.as_ref().unwrap()(
-                            point_index,
-                            &mut point_read,
-                        )?,
-                        scanner_channel: scanner_channel_reader.as_ref().unwrap()(
-                            point_index,
-                            &mut point_read,
-                        )?,
-                        scan_direction_flag: scan_direction_flag_reader(
-                            point_index,
-                            &mut point_read,
-                        )?,
-                        edge_of_flight_line: edge_of_flight_line_reader(
-                            point_index,
-                            &mut point_read,
-                        )?,
-                    })
-                } else {
-                    BitAttributes::Regular(BitAttributesRegular {
-                        return_number: return_number_reader(point_index, &mut point_read)?,
-                        number_of_returns: number_of_returns_reader(point_index, &mut point_read)?,
-                        scan_direction_flag: scan_direction_flag_reader(
-                            point_index,
-                            &mut point_read,
-                        )?,
-                        edge_of_flight_line: edge_of_flight_line_reader(
-                            point_index,
-                            &mut point_read,
-                        )?,
-                    })
-                };
-                write_las_bit_attributes(bit_attributes, &mut las_point_write)?;
-
-                las_point_write.write_u8(classification_reader(point_index, &mut point_read)?)?;
-
-                if target_format.is_extended {
-                    las_point_write.write_u8(user_data_reader(point_index, &mut point_read)?)?;
-                    las_point_write.write_i16::<LittleEndian>(extended_scan_angle_reader
-                        .as_ref()
-                        .unwrap()(
-                        point_index, &mut point_read
-                    )?)?;
-                } else {
-                    las_point_write.write_i8(scan_angle_reader.as_ref().unwrap()(
-                        point_index,
-                        &mut point_read,
-                    )?)?;
-                    las_point_write.write_u8(user_data_reader(point_index, &mut point_read)?)?;
-                }
-
-                las_point_write.write_u16::<LittleEndian>(point_source_id_reader(
-                    point_index,
-                    &mut point_read,
-                )?)?;
-
-                if let Some(ref reader) = gps_time_reader {
-                    las_point_write
-                        .write_f64::<LittleEndian>(reader(point_index, &mut point_read)?)?;
-                }
-
-                if let Some(ref reader) = color_reader {
-                    let color = reader(point_index, &mut point_read)?;
-                    las_point_write.write_u16::<LittleEndian>(color.x)?;
-                    las_point_write.write_u16::<LittleEndian>(color.y)?;
-                    las_point_write.write_u16::<LittleEndian>(color.z)?;
-                }
-
-                if let Some(ref reader) = nir_reader {
-                    las_point_write
-                        .write_u16::<LittleEndian>(reader(point_index, &mut point_read)?)?;
-                }
-
-                if let Some(ref reader) = wave_packet_descriptor_index_reader {
-                    las_point_write.write_u8(reader(point_index, &mut point_read)?)?;
-                }
-                if let Some(ref reader) = waveform_data_offset_reader {
-                    las_point_write
-                        .write_u64::<LittleEndian>(reader(point_index, &mut point_read)?)?;
-                }
-                if let Some(ref reader) = waveform_packet_size_reader {
-                    las_point_write
-                        .write_u32::<LittleEndian>(reader(point_index, &mut point_read)?)?;
-                }
-                if let Some(ref reader) = return_point_waveform_location_reader {
-                    las_point_write
-                        .write_f32::<LittleEndian>(reader(point_index, &mut point_read)?)?;
-                }
-                if let Some(ref reader) = waveform_parameters_reader {
-                    let params = reader(point_index, &mut point_read)?;
-                    las_point_write.write_f32::<LittleEndian>(params.x)?;
-                    las_point_write.write_f32::<LittleEndian>(params.y)?;
-                    las_point_write.write_f32::<LittleEndian>(params.z)?;
-                }
-            }
-
-            las_point_buffer = las_point_write.into_inner();
-            self.writer.compress_many(
-                &las_point_buffer[0..num_points_in_chunk
-                    * self.current_header.point_data_record_length as usize],
-            )?;
-
-            chunk_buffer = point_read.into_inner();
-        }
-
-        update_point_counts_in_las_header(
-            points.len(),
-            &points_by_return,
-            &mut self.current_header,
-        );
-        self.requires_flush = true;
-
-        Ok(())
-    }
-
-    /// Writes the current header to the start of the file
-    fn write_header(&mut self) -> Result<()> {
-        finalize_las_header(&mut self.current_header);
-
-        let mut raw_writer = self.writer.get_mut();
-
-        let current_position = raw_writer.stream_position()?;
-        raw_writer.seek(SeekFrom::Start(0))?;
-        self.current_header.write_to(&mut raw_writer)?;
-        raw_writer.seek(SeekFrom::Start(current_position))?;
-
-        Ok(())
-    }
-
-    /// Writes the extended VLRs to the end of the file
-    fn write_evlrs(&mut self) -> Result<()> {
-        let mut raw_writer = self.writer.get_mut();
-        // Assumes that self.writer is at the end of the file!
-        for evlr in self.evlrs.iter() {
-            evlr.write_to(&mut raw_writer)?;
-        }
-        Ok(())
-    }
-
-    fn do_flush(&mut self) -> Result<()> {
-        self.writer.done()?;
-        self.write_evlrs()?;
-        self.write_header()
-    }
-}
-
-impl<T: std::io::Write + std::io::Seek + Send + 'static> PointWriter for RawLAZWriter<T> {
-    fn write<'a, B: BorrowedBuffer<'a>>(&mut self, points: &'a B) -> Result<()> {
-        if *points.point_layout() != self.default_layout {
-            self.write_points_custom_layout(points)
-        } else {
-            self.write_points_default_layout(points)
-        }
-    }
-
-    fn flush(&mut self) -> Result<()> {
-        self.do_flush()
-    }
+        // The header is only written by `finalize`
+        self.writer.flush()?;
+        Ok(())
+    }
 
     fn get_default_point_layout(&self) -> &PointLayout {
         &self.default_layout
@@ -1175,6 +1327,8 @@ mod tests {
     use std::{fs::File, io::BufWriter};
 
     use las_rs::Builder;
+    use pasture_core::layout::attributes;
+    use pasture_core::layout::conversion::BufferLayoutConverter;
     use pasture_core::math::AABB;
     use pasture_core::nalgebra::Point3;
 
@@ -1182,9 +1336,10 @@ mod tests {
         base::PointReader,
         las::{
             epsilon_compare_point3f64, epsilon_compare_vec3f64, get_test_points_in_las_format,
-            point_layout_from_las_point_format, test_data_bounds, LASReader, LasPointFormat0,
-            LasPointFormat1, LasPointFormat10, LasPointFormat2, LasPointFormat3, LasPointFormat4,
-            LasPointFormat5, LasPointFormat6, LasPointFormat7, LasPointFormat8, LasPointFormat9,
+            point_layout_from_las_point_format, test_data_bounds, widened_layout, LASReader,
+            LasPointFormat0, LasPointFormat1, LasPointFormat10, LasPointFormat2, LasPointFormat3,
+            LasPointFormat4, LasPointFormat5, LasPointFormat6, LasPointFormat7, LasPointFormat8,
+            LasPointFormat9,
         },
     };
     use pasture_core::containers::*;
@@ -1193,6 +1348,69 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_raw_las_writer_requires_positions() -> Result<()> {
+        let mut points =
+            VectorBuffer::new_from_layout(PointLayout::from_attributes(&[attributes::INTENSITY]));
+        points.resize(1);
+
+        let mut header_builder = Builder::from((1, 4));
+        header_builder.point_format = Format::new(0)?;
+        let mut writer = RawLASWriter::from_write_and_header(
+            Cursor::new(Vec::new()),
+            header_builder.into_header()?,
+        )?;
+        assert!(writer.write(&points).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_raw_las_writer_bounds_after_finalizing_empty_file() -> Result<()> {
+        let points = (1..=10)
+            .map(|index| LasPointFormat0 {
+                position: Vector3::new(index as f64, 2.0 * index as f64, 3.0 * index as f64),
+                ..Default::default()
+            })
+            .collect::<VectorBuffer>();
+
+        let mut header_builder = Builder::from((1, 4));
+        header_builder.point_format = Format::new(0)?;
+        let mut writer = RawLASWriter::from_write_and_header(
+            Cursor::new(Vec::new()),
+            header_builder.into_header()?,
+        )?;
+        // The empty file gets zero bounds, which must not be part of the bounds of the points that are written later
+        writer.finalize()?;
+        writer.write(&points)?;
+        writer.finalize()?;
+        assert_eq!(
+            Some(AABB::from_min_max(
+                Point3::new(1.0, 2.0, 3.0),
+                Point3::new(10.0, 20.0, 30.0)
+            )),
+            writer.las_metadata()?.bounds()
+        );
+
+        let las_data = writer.into_inner()?.into_inner();
+        let reader = LASReader::from_read(Cursor::new(las_data), false, false)?;
+        assert_eq!(10, reader.remaining_points());
+        let bounds = reader
+            .get_metadata()
+            .bounds()
+            .expect("LAS file must have bounds");
+        assert!(epsilon_compare_point3f64(
+            &Point3::new(1.0, 2.0, 3.0),
+            bounds.min()
+        ));
+        assert!(epsilon_compare_point3f64(
+            &Point3::new(10.0, 20.0, 30.0),
+            bounds.max()
+        ));
+
+        Ok(())
+    }
+
     macro_rules! las_write_tests {
         ($name:ident, $format:expr, $point_type:ident) => {
             mod $name {
@@ -1220,7 +1438,7 @@ mod tests {
                         assert_eq!(expected_format, *writer.get_default_point_layout());
 
                         writer.write(&test_data)?;
-                        writer.flush()?;
+                        writer.finalize()?;
                     }
 
                     {
@@ -1249,187 +1467,37 @@ mod tests {
                     Ok(())
                 }
 
-                #[repr(C, packed)]
-                #[derive(
-                    PointType, Debug, Copy, Clone, bytemuck::AnyBitPattern, bytemuck::NoUninit,
-                )]
-                struct CustomPointType {
-                    #[pasture(BUILTIN_INTENSITY)]
-                    pub intensity: u16,
-                    #[pasture(BUILTIN_POSITION_3D)]
-                    pub lowp_position: Vector3<f32>,
-                    #[pasture(attribute = "Custom")]
-                    pub custom_attribute: u32,
-                }
-
-                #[test]
-                fn test_raw_las_writer_from_different_point_layout() -> Result<()> {
-                    // Get test data with a different layout, e.g. some missing attributes, some additional
-                    // attributes that are not supported by LAS
-                    let test_data = vec![
-                        CustomPointType {
-                            intensity: 42,
-                            lowp_position: Vector3::new(0.1, 0.2, 0.3),
-                            custom_attribute: 1337,
-                        },
-                        CustomPointType {
-                            intensity: 43,
-                            lowp_position: Vector3::new(0.4, 0.5, 0.6),
-                            custom_attribute: 7331,
-                        },
-                    ];
-
-                    let expected_bounds =
-                        AABB::from_min_max(Point3::new(0.1, 0.2, 0.3), Point3::new(0.4, 0.5, 0.6));
-
-                    let expected_data = test_data.iter().copied().collect::<VectorBuffer>();
-
-                    let format = Format::new($format)?;
-                    let mut header_builder = Builder::from((1, 4));
-                    header_builder.point_format = format.clone();
-
-                    let out_path =
-                        format!("./test_raw_las_writer_different_format_{}.las", $format);
-
-                    defer! {
-                        std::fs::remove_file(&out_path).expect("Could not remove test file");
-                    }
-
-                    {
-                        let mut writer = RawLASWriter::from_write_and_header(
-                            BufWriter::new(File::create(&out_path)?),
-                            header_builder.into_header()?,
-                        )?;
-
-                        writer.write(&expected_data)?;
-                        writer.flush()?;
-                    }
-
-                    {
-                        let mut reader = LASReader::from_path(&out_path, false)?;
-                        let metadata = reader.get_metadata();
-                        assert!(metadata.bounds().is_some());
-                        let actual_bounds = metadata.bounds().unwrap();
-                        assert!(
-                            epsilon_compare_point3f64(expected_bounds.min(), actual_bounds.min()),
-                            "Bounds are different! Expected {:?} but was {:?}",
-                            expected_bounds,
-                            actual_bounds
-                        );
-                        assert!(
-                            epsilon_compare_point3f64(expected_bounds.max(), actual_bounds.max()),
-                            "Bounds are different! Expected {:?} but was {:?}",
-                            expected_bounds,
-                            actual_bounds
-                        );
-
-                        assert_eq!(test_data.len(), reader.remaining_points());
-
-                        let read_points = reader.read::<VectorBuffer>(test_data.len())?;
-
-                        assert_eq!(read_points.len(), test_data.len());
-
-                        let mut actual_points = read_points
-                            .view::<$point_type>()
-                            .into_iter()
-                            .collect::<Vec<_>>();
-
-                        // Expected positions were f32, converted to f64, this might yield rounding errors, so we compare positions separately
-                        for (idx, (expected, actual)) in
-                            test_data.iter().zip(actual_points.iter()).enumerate()
-                        {
-                            let expected_pos_lowp = expected.lowp_position;
-                            let actual_position = actual.position;
-                            let expected_highp = Vector3::new(
-                                expected_pos_lowp.x as f64,
-                                expected_pos_lowp.y as f64,
-                                expected_pos_lowp.z as f64,
-                            );
-                            assert!(
-                                epsilon_compare_vec3f64(&expected_highp, &actual_position),
-                                "Position {} is different! Expected {} but was {}",
-                                idx,
-                                expected_highp,
-                                actual_position
-                            );
-                        }
-
-                        // Zero out positions so that we can compare the other attributes
-                        actual_points
-                            .iter_mut()
-                            .for_each(|point| point.position = Default::default());
-
-                        let expected_points = test_data
-                            .iter()
-                            .map(|test_point| -> $point_type {
-                                let mut default_point: $point_type = Default::default();
-                                default_point.intensity = test_point.intensity;
-                                default_point
-                            })
-                            .collect::<Vec<_>>();
-
-                        assert_eq!(expected_points, actual_points);
-                    }
-
-                    Ok(())
-                }
-            }
-        };
-    }
-
-    macro_rules! laz_write_tests {
-        ($name:ident, $format:expr, $point_type:ident) => {
-            mod $name {
-                use super::*;
-
                 #[test]
-                fn test_raw_laz_writer() -> Result<()> {
+                fn test_raw_las_writer_with_converted_attributes() -> Result<()> {
                     let test_data = get_test_points_in_las_format($format, false)?;
+                    let custom_layout = widened_layout(test_data.point_layout());
+                    let converter = BufferLayoutConverter::for_layouts_with_default(
+                        test_data.point_layout(),
+                        &custom_layout,
+                    );
+                    let custom_data: VectorBuffer = converter.convert(&test_data);
 
-                    let format = Format::new($format)?;
                     let mut header_builder = Builder::from((1, 4));
-                    header_builder.point_format = format.clone();
-
-                    let out_path = format!("./test_raw_las_writer_format_{}.laz", $format);
-                    defer! {
-                        std::fs::remove_file(&out_path).expect("Could not remove test file");
-                    }
-                    {
-                        let mut writer = RawLAZWriter::from_write_and_header(
-                            BufWriter::new(File::create(&out_path)?),
-                            header_builder.into_header()?,
-                        )?;
-
-                        let expected_format = point_layout_from_las_point_format(&format, false)?;
-                        assert_eq!(expected_format, *writer.get_default_point_layout());
-
-                        writer.write(&test_data)?;
-                        writer.flush()?;
-                    }
-
-                    {
-                        let mut reader = LASReader::from_path(&out_path, false)?;
-                        let metadata = reader.get_metadata();
-                        assert_eq!(Some(test_data_bounds()), metadata.bounds());
-                        assert_eq!(Some(test_data.len()), metadata.number_of_points());
-                        assert_eq!(test_data.len(), reader.remaining_points());
-
-                        let read_points = reader.read::<VectorBuffer>(test_data.len())?;
-
-                        assert_eq!(read_points.point_layout(), test_data.point_layout());
-                        assert_eq!(read_points.len(), test_data.len());
-
-                        let expected_points = test_data
-                            .view::<$point_type>()
-                            .into_iter()
-                            .collect::<Vec<_>>();
-                        let actual_points = read_points
-                            .view::<$point_type>()
-                            .into_iter()
-                            .collect::<Vec<_>>();
-
-                        assert_eq!(expected_points, actual_points);
-                    }
+                    header_builder.point_format = Format::new($format)?;
+                    let mut writer = RawLASWriter::from_write_and_header(
+                        Cursor::new(Vec::new()),
+                        header_builder.into_header()?,
+                    )?;
+                    writer.write(&custom_data)?;
+                    let las_data = writer.into_inner()?.into_inner();
+
+                    // All standard attributes survive the conversion into the LAS point records unchanged
+                    let mut reader = LASReader::from_read(Cursor::new(las_data), false, false)?;
+                    let read_points = reader.read::<VectorBuffer>(test_data.len())?;
+                    let expected_points = test_data
+                        .view::<$point_type>()
+                        .into_iter()
+                        .collect::<Vec<_>>();
+                    let actual_points = read_points
+                        .view::<$point_type>()
+                        .into_iter()
+                        .collect::<Vec<_>>();
+                    assert_eq!(expected_points, actual_points);
 
                     Ok(())
                 }
@@ -1448,7 +1516,7 @@ mod tests {
                 }
 
                 #[test]
-                fn test_raw_laz_writer_from_different_point_layout() -> Result<()> {
+                fn test_raw_las_writer_from_different_point_layout() -> Result<()> {
                     // Get test data with a different layout, e.g. some missing attributes, some additional
                     // attributes that are not supported by LAS
                     let test_data = vec![
@@ -1474,20 +1542,20 @@ mod tests {
                     header_builder.point_format = format.clone();
 
                     let out_path =
-                        format!("./test_raw_las_writer_different_format_{}.laz", $format);
+                        format!("./test_raw_las_writer_different_format_{}.las", $format);
 
                     defer! {
                         std::fs::remove_file(&out_path).expect("Could not remove test file");
                     }
 
                     {
-                        let mut writer = RawLAZWriter::from_write_and_header(
+                        let mut writer = RawLASWriter::from_write_and_header(
                             BufWriter::new(File::create(&out_path)?),
                             header_builder.into_header()?,
                         )?;
 
                         writer.write(&expected_data)?;
-                        writer.flush()?;
+                        writer.finalize()?;
                     }
 
                     {
@@ -1508,7 +1576,6 @@ mod tests {
                             actual_bounds
                         );
 
-                        assert_eq!(Some(test_data.len()), metadata.number_of_points());
                         assert_eq!(test_data.len(), reader.remaining_points());
 
                         let read_points = reader.read::<VectorBuffer>(test_data.len())?;
@@ -1574,9 +1641,4 @@ mod tests {
     las_write_tests!(las_write_8, 8, LasPointFormat8);
     las_write_tests!(las_write_9, 9, LasPointFormat9);
     las_write_tests!(las_write_10, 10, LasPointFormat10);
-
-    laz_write_tests!(laz_write_0, 0, LasPointFormat0);
-    laz_write_tests!(laz_write_1, 1, LasPointFormat1);
-    laz_write_tests!(laz_write_2, 2, LasPointFormat2);
-    laz_write_tests!(laz_write_3, 3, LasPointFormat3);
 }
@@ -1,14 +1,19 @@
 use std::io::Cursor;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use byteorder::{NativeEndian, ReadBytesExt};
 use pasture_core::{
     layout::attributes,
     layout::conversion::get_converter_for_attributes,
-    layout::{conversion::AttributeConversionFn, PointAttributeMember, PointLayout, PrimitiveType},
+    layout::{
+        conversion::AttributeConversionFn, PointAttributeDataType, PointAttributeMember,
+        PointLayout, PrimitiveType,
+    },
     nalgebra::Vector3,
 };
 
+use super::ColorUpscaling;
+
 /// ReaderFn is a helper function that allows reading a single value of a specific point attribute from an arbitrary
 /// buffer, applying all necessary conversions or falling back to default values if required. This abstraction is
 /// necessary to deal with the general case of an arbitrary source point layout in the LASWriter that has to be
@@ -140,6 +145,18 @@ fn read_edge_of_flight_line_in_default_layout(
     Ok(point_read.read_u8()?)
 }
 
+fn read_overlap_in_default_layout(
+    point_read: &mut Cursor<Vec<u8>>,
+    attribute_offset: usize,
+    current_point_index: usize,
+    size_of_single_point: usize,
+) -> Result<u8> {
+    let attribute_start_pos =
+        ((current_point_index * size_of_single_point) + attribute_offset) as u64;
+    point_read.set_position(attribute_start_pos);
+    Ok(point_read.read_u8()?)
+}
+
 fn read_classification_in_default_layout(
     point_read: &mut Cursor<Vec<u8>>,
     attribute_offset: usize,
@@ -304,12 +321,15 @@ fn read_waveform_parameters_in_default_layout(
 
 macro_rules! make_get_reader_fn {
     ($name:ident, $type:ty, $attribute:ident, $read_default_fn:ident) => {
-        pub(crate) fn $name(source_layout: &PointLayout) -> ReaderFn<$type> {
+        pub(crate) fn $name(
+            source_layout: &PointLayout,
+            default_value: $type,
+        ) -> Result<ReaderFn<$type>> {
             let default_attribute = attributes::$attribute;
             let source_attribute = source_layout.get_attribute_by_name(default_attribute.name());
 
-            match source_attribute {
-                None => Box::new(|_, _| -> Result<$type> { Ok(Default::default()) }),
+            let reader: ReaderFn<$type> = match source_attribute {
+                None => Box::new(move |_, _| -> Result<$type> { Ok(default_value) }),
                 Some(attribute) => {
                     if attribute.datatype() == default_attribute.datatype() {
                         let offset_in_point = attribute.offset() as usize;
@@ -329,7 +349,14 @@ macro_rules! make_get_reader_fn {
                             attribute.attribute_definition(),
                             &default_attribute,
                         )
-                        .expect("No converter for attribute found");
+                        .with_context(|| {
+                            format!(
+                                "Can't convert attribute {} from datatype {} into datatype {}",
+                                default_attribute.name(),
+                                attribute.datatype(),
+                                default_attribute.datatype()
+                            )
+                        })?;
                         Box::new(move |current_point_index, point_read| {
                             read_attribute_in_custom_layout::<$type>(
                                 &attribute_clone,
@@ -341,7 +368,8 @@ macro_rules! make_get_reader_fn {
                         })
                     }
                 }
-            }
+            };
+            Ok(reader)
         }
     };
 }
@@ -402,6 +430,13 @@ make_get_reader_fn!(
     read_edge_of_flight_line_in_default_layout
 );
 
+make_get_reader_fn!(
+    get_overlap_reader,
+    u8,
+    OVERLAP,
+    read_overlap_in_default_layout
+);
+
 make_get_reader_fn!(
     get_classification_reader,
     u8,
@@ -488,6 +523,32 @@ make_get_reader_fn!(
     read_waveform_parameters_in_default_layout
 );
 
+/// Like `get_color_reader`, but scales `Vector3<u8>` colors of the `source_layout` into the 16-bit range of LAS colors
+/// according to `color_upscaling`
+pub(crate) fn get_upscaled_color_reader(
+    source_layout: &PointLayout,
+    default_value: Vector3<u16>,
+    color_upscaling: ColorUpscaling,
+) -> Result<ReaderFn<Vector3<u16>>> {
+    let reader = get_color_reader(source_layout, default_value)?;
+    let has_8_bit_colors = source_layout
+        .get_attribute_by_name(attributes::COLOR_RGB.name())
+        .map(|attribute| attribute.datatype() == PointAttributeDataType::Vec3u8)
+        .unwrap_or(false);
+    if !has_8_bit_colors {
+        return Ok(reader);
+    }
+    // The converted channels are at most 255, so neither scaling overflows
+    let upscale_channel: fn(u16) -> u16 = match color_upscaling {
+        ColorUpscaling::Cast => return Ok(reader),
+        ColorUpscaling::MultiplyBy257 => |channel| channel * 257,
+        ColorUpscaling::ShiftBy8 => |channel| channel << 8,
+    };
+    Ok(Box::new(move |current_point_index, point_read| {
+        reader(current_point_index, point_read).map(|color| color.map(upscale_channel))
+    }))
+}
+
 /// Attempts to convert the given LAS string (a fixed-size byte array, potentially null-terminated) into a
 /// Rust `String`. As per the LAS specification, `las_string` will be null-terminated ONLY IF the length of
 /// the string is less than the size of the array (i.e. `N`)!
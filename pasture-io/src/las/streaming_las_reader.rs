@@ -0,0 +1,413 @@
+use std::convert::TryInto;
+use std::io::{Read, SeekFrom};
+
+use anyhow::{bail, Context, Result};
+use las_rs::{raw, Builder, Header};
+use pasture_core::containers::{BorrowedMutBuffer, OwningBuffer, VectorBuffer};
+use pasture_core::{layout::PointLayout, meta::Metadata};
+
+use super::{
+    default_point_layout_for_read_options, get_default_las_converter, point_count_from_raw_header,
+    point_layout_from_las_metadata, LASMetadata, LASReadOptions, LASReaderBase,
+};
+use crate::base::{skip_points_by_seeking, PointReader, SeekToPoint};
+
+/// Size of the header of a single VLR in bytes
+const VLR_HEADER_SIZE: u64 = 54;
+
+/// Reader for uncompressed LAS files from a stream that does not support seeking, such as `stdin` or a network
+/// stream. The header, VLRs and point records are read strictly sequentially. Seeking forward is supported by
+/// skipping over point records, seeking backwards results in an error. Since EVLRs are stored after the point
+/// records, they are not available through the metadata of this reader
+pub struct StreamingLASReader<T: Read> {
+    reader: T,
+    metadata: LASMetadata,
+    layout: PointLayout,
+    las_point_records_layout: PointLayout,
+    options: LASReadOptions,
+    current_point_index: usize,
+    size_of_point_in_file: u64,
+}
+
+impl<T: Read> StreamingLASReader<T> {
+    /// Creates a new `StreamingLASReader` from the given `reader`. See [`RawLASReader::from_read`](super::RawLASReader::from_read)
+    /// for an explanation of the `point_layout_matches_memory_layout` parameter
+    pub fn from_read(reader: T, point_layout_matches_memory_layout: bool) -> Result<Self> {
+        Self::from_read_with_options(
+            reader,
+            LASReadOptions {
+                point_layout_matches_memory_layout,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Creates a new `StreamingLASReader` from the given `reader`, using the given `options` to determine the default
+    /// `PointLayout` of the reader. See [`LASReadOptions`] for more information
+    pub fn from_read_with_options(mut reader: T, options: LASReadOptions) -> Result<Self> {
+        let raw_header = raw::Header::read_from(&mut reader)?;
+        let offset_to_first_point_in_file = raw_header.offset_to_point_data as u64;
+        let size_of_point_in_file = raw_header.point_data_record_length as u64;
+        let point_count = point_count_from_raw_header(&raw_header);
+
+        // Without seeking, we have to keep track of the position within the stream ourselves
+        let mut position_in_stream = raw_header.header_size as u64;
+        let vlrs = (0..raw_header.number_of_variable_length_records as usize)
+            .map(|_| {
+                let raw_vlr = las_rs::raw::Vlr::read_from(&mut reader, false)?;
+                position_in_stream += VLR_HEADER_SIZE + raw_vlr.data.len() as u64;
+                Ok(las_rs::Vlr::new(raw_vlr))
+            })
+            .collect::<Result<Vec<_>>>()
+            .context("Failed to read VLRs")?;
+
+        let mut builder = Builder::new(raw_header).context("Invalid LAS header")?;
+        builder.vlrs = vlrs;
+
+        // Same as in `RawLASReader`, leftover bytes between the VLRs and the point records have to be stored in the
+        // header. Since we can't seek, this also moves the stream to the first point record
+        if position_in_stream < offset_to_first_point_in_file {
+            reader
+                .by_ref()
+                .take(offset_to_first_point_in_file - position_in_stream)
+                .read_to_end(&mut builder.vlr_padding)?;
+        }
+
+        let header = builder.into_header().context("Invalid LAS header")?;
+        if header.point_format().is_compressed {
+            bail!("StreamingLASReader does not support compressed LAZ files");
+        }
+
+        let mut metadata: LASMetadata = header
+            .clone()
+            .try_into()
+            .context("Failed to parse LAS header")?;
+        metadata.set_point_count(point_count)?;
+        let point_layout = default_point_layout_for_read_options(&metadata, &options)?;
+        let matching_memory_layout = point_layout_from_las_metadata(&metadata, true)?;
+
+        Ok(Self {
+            reader,
+            metadata,
+            layout: point_layout,
+            las_point_records_layout: matching_memory_layout,
+            options,
+            current_point_index: 0,
+            size_of_point_in_file,
+        })
+    }
+
+    pub fn las_metadata(&self) -> &LASMetadata {
+        &self.metadata
+    }
+
+    /// Skips over the next `count` point records in the stream without parsing them
+    fn skip_points(&mut self, count: usize) -> Result<()> {
+        let bytes_to_skip = count as u64 * self.size_of_point_in_file;
+        let skipped_bytes = std::io::copy(
+            &mut self.reader.by_ref().take(bytes_to_skip),
+            &mut std::io::sink(),
+        )?;
+        if skipped_bytes != bytes_to_skip {
+            bail!("Unexpected end of stream while skipping point records");
+        }
+        self.current_point_index += count;
+        Ok(())
+    }
+
+    fn read_into_default_layout<'a, 'b, B: BorrowedMutBuffer<'a>>(
+        &mut self,
+        point_buffer: &'b mut B,
+        count: usize,
+    ) -> Result<usize>
+    where
+        'a: 'b,
+    {
+        let num_points_to_read = usize::min(count, self.remaining_points());
+        if num_points_to_read == 0 {
+            return Ok(0);
+        }
+
+        if let Some(interleaved_buffer) = point_buffer.as_interleaved_mut() {
+            let new_point_data = interleaved_buffer.get_point_range_mut(0..num_points_to_read);
+            self.reader
+                .read_exact(new_point_data)
+                .context("Failed to read point records")?;
+        } else {
+            // Read point data in chunks of ~1MiB size to prevent memory problems for very large files if we were
+            // to read all data in a single chunk
+            const CHUNK_MEM_SIZE: usize = 1 << 20;
+            let num_points_per_chunk = CHUNK_MEM_SIZE / self.size_of_point_in_file as usize;
+            let num_chunks = (num_points_to_read + num_points_per_chunk - 1) / num_points_per_chunk;
+            let mut read_buffer =
+                vec![0; num_points_per_chunk * self.size_of_point_in_file as usize];
+            for chunk_idx in 0..num_chunks {
+                let bytes_in_chunk = if chunk_idx == num_chunks - 1 {
+                    (num_points_to_read - (chunk_idx * num_points_per_chunk))
+                        * self.size_of_point_in_file as usize
+                } else {
+                    read_buffer.len()
+                };
+                let chunk_bytes = &mut read_buffer[..bytes_in_chunk];
+                self.reader
+                    .read_exact(chunk_bytes)
+                    .context("Failed to read chunk of points")?;
+                let first_point_in_chunk = chunk_idx * num_points_per_chunk;
+                let chunk_end = ((chunk_idx + 1) * num_points_per_chunk).min(num_points_to_read);
+                // Safe because this function (`read_into_default_layout`) is only called if the buffer has the exact
+                // binary memory layout of the LAS file
+                unsafe {
+                    point_buffer.set_point_range(first_point_in_chunk..chunk_end, chunk_bytes);
+                }
+            }
+        }
+
+        self.current_point_index += num_points_to_read;
+
+        Ok(num_points_to_read)
+    }
+
+    fn read_into_custom_layout<'a, 'b, B: BorrowedMutBuffer<'a>>(
+        &mut self,
+        point_buffer: &'b mut B,
+        count: usize,
+    ) -> Result<usize> {
+        let num_points_to_read = usize::min(count, self.remaining_points());
+        if num_points_to_read == 0 {
+            return Ok(0);
+        }
+
+        const CHUNK_BYTES: usize = 1 << 20; // 1 MiB
+        let points_per_chunk =
+            CHUNK_BYTES / self.las_point_records_layout.size_of_point_entry() as usize;
+        let num_chunks = (num_points_to_read + points_per_chunk - 1) / points_per_chunk;
+
+        let size_of_chunk = if num_chunks > 1 {
+            points_per_chunk
+        } else {
+            num_points_to_read
+        };
+
+        let mut convert_buffer =
+            VectorBuffer::with_capacity(size_of_chunk, self.las_point_records_layout.clone());
+        convert_buffer.resize(size_of_chunk);
+
+        let source_layout = self.las_point_records_layout.clone();
+        let target_layout = point_buffer.point_layout().clone();
+        let converter = get_default_las_converter(
+            &source_layout,
+            &target_layout,
+            &self.metadata,
+            &self.options,
+        )
+        .context("Unsupported conversion")?;
+
+        for chunk_idx in 0..num_chunks {
+            let points_in_current_chunk = if chunk_idx == num_chunks - 1 {
+                num_points_to_read - ((num_chunks - 1) * size_of_chunk)
+            } else {
+                size_of_chunk
+            };
+
+            self.read_into_default_layout(&mut convert_buffer, points_in_current_chunk)?;
+            let target_buffer_first_point = chunk_idx * size_of_chunk;
+            let target_buffer_last_point = target_buffer_first_point + points_in_current_chunk;
+            converter.convert_into_range(
+                &convert_buffer,
+                0..points_in_current_chunk,
+                point_buffer,
+                target_buffer_first_point..target_buffer_last_point,
+            );
+        }
+
+        Ok(num_points_to_read)
+    }
+}
+
+impl<T: Read> LASReaderBase for StreamingLASReader<T> {
+    fn remaining_points(&self) -> usize {
+        self.metadata.point_count() - self.current_point_index
+    }
+
+    fn header(&self) -> &Header {
+        self.metadata.raw_las_header().unwrap()
+    }
+}
+
+impl<T: Read> PointReader for StreamingLASReader<T> {
+    fn read_into<'a, 'b, B: BorrowedMutBuffer<'a>>(
+        &mut self,
+        point_buffer: &'b mut B,
+        count: usize,
+    ) -> Result<usize>
+    where
+        'a: 'b,
+    {
+        if point_buffer.len() < count {
+            panic!("point_buffer.len() must be >= count");
+        }
+
+        if *point_buffer.point_layout() != self.las_point_records_layout {
+            self.read_into_custom_layout(point_buffer, count)
+        } else {
+            self.read_into_default_layout(point_buffer, count)
+        }
+    }
+
+    fn get_metadata(&self) -> &dyn Metadata {
+        &self.metadata
+    }
+
+    fn get_default_point_layout(&self) -> &PointLayout {
+        &self.layout
+    }
+
+    fn skip(&mut self, count: usize) -> Result<usize> {
+        skip_points_by_seeking(self, count)
+    }
+
+    fn point_index(&self) -> Option<usize> {
+        Some(self.current_point_index)
+    }
+
+    fn point_count(&self) -> Option<usize> {
+        Some(self.metadata.point_count())
+    }
+}
+
+impl<T: Read> SeekToPoint for StreamingLASReader<T> {
+    /// Seeks forward by skipping over point records. Since the underlying stream can't be rewound, seeking to a
+    /// point before the current point is an error
+    fn seek_point(&mut self, position: SeekFrom) -> Result<usize> {
+        let new_position = match position {
+            SeekFrom::Start(from_start) => from_start as i64,
+            SeekFrom::End(from_end) => self.metadata.point_count() as i64 + from_end,
+            SeekFrom::Current(from_current) => self.current_point_index as i64 + from_current,
+        };
+        if new_position < 0 {
+            panic!("StreamingLASReader::seek_point: It is an error to seek to a point position smaller than zero!");
+        }
+        let clamped_position =
+            std::cmp::min(self.metadata.point_count() as i64, new_position) as usize;
+
+        if clamped_position < self.current_point_index {
+            bail!(
+                "StreamingLASReader can't seek backwards (from point {} to point {})",
+                self.current_point_index,
+                clamped_position
+            );
+        }
+        self.skip_points(clamped_position - self.current_point_index)?;
+
+        Ok(self.current_point_index)
+    }
+
+    fn point_index(&mut self) -> Result<usize> {
+        Ok(self.current_point_index)
+    }
+
+    // The default implementation seeks to the end and back, which is not possible here
+    fn point_count(&mut self) -> Result<usize> {
+        Ok(self.metadata.point_count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::BufReader;
+
+    use las_rs::point::Format;
+    use pasture_core::containers::VectorBuffer;
+
+    use super::*;
+    use crate::las::{
+        compare_to_reference_data, compare_to_reference_data_range, get_test_las_path,
+        get_test_las_path_with_extra_bytes, RawLASReader,
+    };
+
+    /// Wraps a `Read` so that it can't accidentally be used as `Seek`
+    struct NonSeekable<R: Read>(R);
+
+    impl<R: Read> Read for NonSeekable<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.0.read(buf)
+        }
+    }
+
+    fn open_non_seekable(format: u8) -> Result<NonSeekable<BufReader<File>>> {
+        Ok(NonSeekable(BufReader::new(File::open(get_test_las_path(
+            format,
+        ))?)))
+    }
+
+    #[test]
+    fn test_streaming_las_reader_read() -> Result<()> {
+        for format in 0..=10 {
+            let mut reader = StreamingLASReader::from_read(open_non_seekable(format)?, false)?;
+            assert_eq!(Some(10), reader.point_count());
+
+            let points = reader.read::<VectorBuffer>(10)?;
+            compare_to_reference_data(&points, Format::new(format)?);
+            assert_eq!(0, reader.remaining_points());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_streaming_las_reader_matches_raw_las_reader() -> Result<()> {
+        for format in 0..=10 {
+            let path = get_test_las_path_with_extra_bytes(format);
+            let mut reader = StreamingLASReader::from_read(
+                NonSeekable(BufReader::new(File::open(&path)?)),
+                true,
+            )?;
+            let mut raw_reader = RawLASReader::from_read(BufReader::new(File::open(&path)?), true)?;
+            assert_eq!(
+                raw_reader.las_metadata().point_format(),
+                reader.las_metadata().point_format()
+            );
+            assert_eq!(
+                raw_reader.get_default_point_layout(),
+                reader.get_default_point_layout()
+            );
+
+            let expected_points = raw_reader.read::<VectorBuffer>(10)?;
+            let actual_points = reader.read::<VectorBuffer>(10)?;
+            assert_eq!(expected_points, actual_points, "Format {}", format);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_streaming_las_reader_seek_forward() -> Result<()> {
+        for format in 0..=10 {
+            let mut reader = StreamingLASReader::from_read(open_non_seekable(format)?, false)?;
+
+            assert_eq!(3, reader.seek_point(SeekFrom::Start(3))?);
+            let points = reader.read::<VectorBuffer>(2)?;
+            compare_to_reference_data_range(&points, Format::new(format)?, 3..5);
+
+            assert_eq!(7, reader.seek_point(SeekFrom::Current(2))?);
+            assert_eq!(Some(7), reader.point_index());
+            let points = reader.read::<VectorBuffer>(3)?;
+            compare_to_reference_data_range(&points, Format::new(format)?, 7..10);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_streaming_las_reader_seek_backwards_fails() -> Result<()> {
+        let mut reader = StreamingLASReader::from_read(open_non_seekable(0)?, false)?;
+        reader.seek_point(SeekFrom::Start(5))?;
+        assert!(reader.seek_point(SeekFrom::Start(2)).is_err());
+        assert!(reader.seek_point(SeekFrom::Current(-1)).is_err());
+        // Seeking to the current position is fine
+        assert_eq!(5, reader.seek_point(SeekFrom::Current(0))?);
+
+        Ok(())
+    }
+}
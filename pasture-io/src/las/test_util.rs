@@ -1,10 +1,21 @@
-use std::{borrow::Cow, ops::Range, path::PathBuf};
+use std::{
+    borrow::Cow,
+    io::{Cursor, Write},
+    ops::Range,
+    path::PathBuf,
+};
 
 use anyhow::Result;
-use las_rs::point::Format;
+use las_rs::{
+    point::Format,
+    raw::header::{Evlr, LargeFile},
+    Header,
+};
 use pasture_core::{
     containers::{BorrowedBuffer, BorrowedMutBuffer, HashMapBuffer, OwningBuffer},
-    layout::{attributes, FieldAlignment, PointAttributeDataType, PointAttributeDefinition},
+    layout::{
+        attributes, FieldAlignment, PointAttributeDataType, PointAttributeDefinition, PointLayout,
+    },
     math::AABB,
     nalgebra::{Point3, Vector3},
 };
@@ -13,6 +24,41 @@ use super::point_layout_from_las_point_format;
 
 //use super::point_layout_from_las_point_format;
 
+/// Creates the binary data of a LAX index with a quadtree of the given number of `levels` over the XY extent
+/// `[min_x, max_x, min_y, max_y]`. Each cell is given as its cell index and a list of inclusive point intervals
+pub(crate) fn make_lax_index_in_memory(
+    levels: u32,
+    extent: [f32; 4],
+    cells: &[(i32, &[(u32, u32)])],
+) -> Vec<u8> {
+    let mut data = vec![];
+    data.extend_from_slice(b"LASX");
+    data.extend_from_slice(&0_u32.to_le_bytes());
+    data.extend_from_slice(b"LASS");
+    data.extend_from_slice(&0_u32.to_le_bytes());
+    data.extend_from_slice(b"LASQ");
+    for value in [0, levels, 0, 0] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    for value in extent {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(b"LASV");
+    data.extend_from_slice(&0_u32.to_le_bytes());
+    data.extend_from_slice(&(cells.len() as u32).to_le_bytes());
+    for (cell_index, intervals) in cells {
+        let number_of_points: u32 = intervals.iter().map(|(first, last)| last - first + 1).sum();
+        data.extend_from_slice(&cell_index.to_le_bytes());
+        data.extend_from_slice(&(intervals.len() as u32).to_le_bytes());
+        data.extend_from_slice(&number_of_points.to_le_bytes());
+        for (first, last) in intervals.iter() {
+            data.extend_from_slice(&first.to_le_bytes());
+            data.extend_from_slice(&last.to_le_bytes());
+        }
+    }
+    data
+}
+
 /// Returns the path to a LAS test file with the given `format`
 pub(crate) fn get_test_las_path(format: u8) -> PathBuf {
     let mut test_file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -30,7 +76,66 @@ pub(crate) fn get_test_las_path_with_extra_bytes(format: u8) -> PathBuf {
     test_file_path
 }
 
+/// Creates the binary data of a LAS file in memory from the given `header`, followed by the given raw `point_records`
+/// and the EVLRs of the `header`. The point counts in the header are set to `num_points`
+pub(crate) fn make_las_file_in_memory(
+    header: &Header,
+    num_points: usize,
+    point_records: &[u8],
+) -> Result<Vec<u8>> {
+    let mut raw_header = header.clone().into_raw()?;
+    if !header.point_format().is_extended {
+        raw_header.number_of_point_records = num_points as u32;
+    }
+    raw_header.large_file = Some(LargeFile {
+        number_of_point_records: num_points as u64,
+        number_of_points_by_return: Default::default(),
+    });
+    if !header.evlrs().is_empty() {
+        raw_header.evlr = Some(Evlr {
+            start_of_first_evlr: raw_header.offset_to_point_data as u64
+                + point_records.len() as u64,
+            number_of_evlrs: header.evlrs().len() as u32,
+        });
+    }
+
+    let mut data = Cursor::new(Vec::new());
+    raw_header.write_to(&mut data)?;
+    for vlr in header.vlrs() {
+        vlr.clone().into_raw(false)?.write_to(&mut data)?;
+    }
+    data.write_all(header.vlr_padding())?;
+    data.write_all(point_records)?;
+    for evlr in header.evlrs() {
+        evlr.clone().into_raw(true)?.write_to(&mut data)?;
+    }
+    Ok(data.into_inner())
+}
+
+/// Returns `layout` with all attributes in reverse order and all scalar attributes in a wider datatype, so that the
+/// writer has to convert every attribute
+pub(crate) fn widened_layout(layout: &PointLayout) -> PointLayout {
+    let mut attributes = layout
+        .attributes()
+        .map(|attribute| {
+            let definition = attribute.attribute_definition();
+            let wider_datatype = match definition.datatype() {
+                PointAttributeDataType::U8 => PointAttributeDataType::U16,
+                PointAttributeDataType::U16 => PointAttributeDataType::U32,
+                PointAttributeDataType::I8 => PointAttributeDataType::I16,
+                PointAttributeDataType::I16 => PointAttributeDataType::I32,
+                PointAttributeDataType::F32 => PointAttributeDataType::F64,
+                other => other,
+            };
+            definition.with_custom_datatype(wider_datatype)
+        })
+        .collect::<Vec<_>>();
+    attributes.reverse();
+    PointLayout::from_attributes(&attributes)
+}
+
 /// Returns the path to a LAZ test file with the given `format`
+#[cfg(feature = "laz")]
 pub(crate) fn get_test_laz_path(format: u8) -> PathBuf {
     let mut test_file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     test_file_path.push(format!("resources/test/10_points_format_{}.laz", format));
@@ -95,6 +200,14 @@ fn test_data_classification_flags() -> Vec<u8> {
     vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9]
 }
 
+/// The overlap flag is bit 3 of the classification flags
+fn test_data_overlap_flags() -> Vec<u8> {
+    test_data_classification_flags()
+        .into_iter()
+        .map(|flags| (flags >> 3) & 1)
+        .collect()
+}
+
 fn test_data_scanner_channels() -> Vec<u8> {
     vec![0, 1, 2, 3, 0, 1, 2, 3, 0, 1]
 }
@@ -251,6 +364,16 @@ pub(crate) fn compare_to_reference_data_range<'a, B: BorrowedBuffer<'a>>(
             "Classification flags do not match"
         );
 
+        let overlap_flags = points
+            .view_attribute::<u8>(&attributes::OVERLAP)
+            .into_iter()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            &test_data_overlap_flags()[range.clone()],
+            overlap_flags,
+            "Overlap flags do not match"
+        );
+
         let scanner_channels = points
             .view_attribute::<u8>(&attributes::SCANNER_CHANNEL)
             .into_iter()
@@ -485,6 +608,11 @@ pub(crate) fn get_test_points_in_las_format(
                 .view_attribute_mut(&attributes::CLASSIFICATION_FLAGS)
                 .set_at(idx, value);
         }
+        for (idx, value) in test_data_overlap_flags().iter().copied().enumerate() {
+            buffer
+                .view_attribute_mut(&attributes::OVERLAP)
+                .set_at(idx, value);
+        }
         for (idx, value) in test_data_scanner_channels().iter().copied().enumerate() {
             buffer
                 .view_attribute_mut(&attributes::SCANNER_CHANNEL)
@@ -1,20 +1,88 @@
-use std::{convert::TryInto, io::Write};
+use std::io::Write;
+use std::ops::Range;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use byteorder::{LittleEndian, WriteBytesExt};
+use pasture_core::containers::BorrowedBuffer;
+use pasture_core::layout::{attributes::POSITION_3D, PointLayout};
 use pasture_core::nalgebra::Vector3;
 
 use super::BitAttributes;
 
+/// Copies the points in `range` from `points` into `chunk` using the interleaved memory layout of the point layout of
+/// `points`. Columnar buffers are gathered one attribute at a time, reading each attribute column as a contiguous slice,
+/// so that writing them never requires an interleaved copy of more than the current chunk
+pub(crate) fn gather_point_range<'a, B: BorrowedBuffer<'a>>(
+    points: &'a B,
+    range: Range<usize>,
+    chunk: &mut [u8],
+) {
+    let size_of_point = points.point_layout().size_of_point_entry() as usize;
+    assert_eq!(chunk.len(), range.len() * size_of_point);
+    match points.as_columnar() {
+        Some(columnar_points) => {
+            for attribute in points.point_layout().attributes() {
+                let byte_range = attribute.byte_range_within_point();
+                let column = columnar_points
+                    .get_attribute_range_ref(attribute.attribute_definition(), range.clone());
+                for (point_memory, value) in chunk
+                    .chunks_exact_mut(size_of_point)
+                    .zip(column.chunks_exact(byte_range.len()))
+                {
+                    point_memory[byte_range.clone()].copy_from_slice(value);
+                }
+            }
+        }
+        None => points.get_point_range(range, chunk),
+    }
+}
+
+/// Converts a single world space coordinate into the local integer coordinate of a LAS file by applying the inverse of
+/// the given `offset` and `scale`, rounding to the nearest integer. `axis` is only used for the error message
+fn quantize_las_coordinate(
+    axis: &str,
+    world_space_coordinate: f64,
+    offset: f64,
+    scale: f64,
+) -> Result<i32> {
+    let local_coordinate = ((world_space_coordinate - offset) / scale).round();
+    // This also catches NaN coordinates, since all comparisons with NaN are false
+    if !(local_coordinate >= i32::MIN as f64 && local_coordinate <= i32::MAX as f64) {
+        bail!(
+            "{} coordinate {} can't be written to the LAS file, as it is out of the representable range given the LAS offset {} and scale {}",
+            axis,
+            world_space_coordinate,
+            offset,
+            scale
+        );
+    }
+    Ok(local_coordinate as i32)
+}
+
 /// Writes the given world space position as a LAS position to the given `writer`
 pub(crate) fn write_position_as_las_position<T: Write>(
     world_space_position: &Vector3<f64>,
     las_header: &las::raw::Header,
     mut writer: T,
 ) -> Result<()> {
-    let local_x : i32 = (((world_space_position.x - las_header.x_offset) / las_header.x_scale_factor) as i64).try_into().expect("write_position_as_las_position: Position is out of bounds given the current LAS offset and scale!");
-    let local_y : i32 = (((world_space_position.y - las_header.y_offset) / las_header.y_scale_factor) as i64).try_into().expect("write_position_as_las_position: Position is out of bounds given the current LAS offset and scale!");
-    let local_z : i32 = (((world_space_position.z - las_header.z_offset) / las_header.z_scale_factor) as i64).try_into().expect("write_position_as_las_position: Position is out of bounds given the current LAS offset and scale!");
+    let local_x = quantize_las_coordinate(
+        "X",
+        world_space_position.x,
+        las_header.x_offset,
+        las_header.x_scale_factor,
+    )?;
+    let local_y = quantize_las_coordinate(
+        "Y",
+        world_space_position.y,
+        las_header.y_offset,
+        las_header.y_scale_factor,
+    )?;
+    let local_z = quantize_las_coordinate(
+        "Z",
+        world_space_position.z,
+        las_header.z_offset,
+        las_header.z_scale_factor,
+    )?;
     writer.write_i32::<LittleEndian>(local_x)?;
     writer.write_i32::<LittleEndian>(local_y)?;
     writer.write_i32::<LittleEndian>(local_z)?;
@@ -22,6 +90,102 @@ pub(crate) fn write_position_as_las_position<T: Write>(
     Ok(())
 }
 
+/// How the LAS writers convert a `COLOR_RGB` attribute with datatype `Vector3<u8>` into the 16-bit color channels of
+/// LAS point records. This is the counterpart of [`ColorConversion`](super::ColorConversion) for reading. Colors with
+/// other datatypes are converted like all other attributes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ColorUpscaling {
+    /// Write each color channel unchanged, like a regular cast from `u8` to `u16`. Most viewers expect 16-bit colors,
+    /// so they display these colors almost black
+    Cast,
+    /// Multiply each color channel by 257, which maps `[0; 255]` onto the full 16-bit range `[0; 65535]`. Reading the
+    /// colors into `Vector3<u8>` with any [`ColorConversion`](super::ColorConversion) yields the original colors
+    #[default]
+    MultiplyBy257,
+    /// Shift each color channel left by 8 bits, which is what many LAS producers do for 8-bit colors
+    ShiftBy8,
+}
+
+/// Values that the LAS writers write for LAS point attributes that are missing from the `PointLayout` of the written
+/// points, e.g. the GPS time when writing points without a `GPS_TIME` attribute in point format 1. All values are
+/// given in the datatype and unit of the corresponding LAS field and default to zero. There is no default for the
+/// position, since points without a `POSITION_3D` attribute can't be written
+/// ```
+/// # use pasture_io::las::*;
+/// let defaults = MissingAttributeDefaults {
+///     classification: 1,
+///     gps_time: 1.0e9,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MissingAttributeDefaults {
+    pub intensity: u16,
+    pub return_number: u8,
+    pub number_of_returns: u8,
+    pub classification_flags: u8,
+    pub scanner_channel: u8,
+    pub scan_direction_flag: u8,
+    pub edge_of_flight_line: u8,
+    pub overlap: u8,
+    pub classification: u8,
+    pub user_data: u8,
+    /// Scan angle in degrees for the point formats 0-5
+    pub scan_angle_rank: i8,
+    /// Scan angle in increments of 0.006 degrees for the point formats 6-10
+    pub scan_angle: i16,
+    pub point_source_id: u16,
+    pub gps_time: f64,
+    pub color_rgb: Vector3<u16>,
+    pub nir: u16,
+    pub wave_packet_descriptor_index: u8,
+    pub waveform_data_offset: u64,
+    pub waveform_packet_size: u32,
+    pub return_point_waveform_location: f32,
+    pub waveform_parameters: Vector3<f32>,
+}
+
+impl Default for MissingAttributeDefaults {
+    fn default() -> Self {
+        Self {
+            intensity: 0,
+            return_number: 0,
+            number_of_returns: 0,
+            classification_flags: 0,
+            scanner_channel: 0,
+            scan_direction_flag: 0,
+            edge_of_flight_line: 0,
+            overlap: 0,
+            classification: 0,
+            user_data: 0,
+            scan_angle_rank: 0,
+            scan_angle: 0,
+            point_source_id: 0,
+            gps_time: 0.0,
+            color_rgb: Vector3::zeros(),
+            nir: 0,
+            wave_packet_descriptor_index: 0,
+            waveform_data_offset: 0,
+            waveform_packet_size: 0,
+            return_point_waveform_location: 0.0,
+            waveform_parameters: Vector3::zeros(),
+        }
+    }
+}
+
+/// Makes sure that points with the given `source_layout` can be written into a LAS file. All LAS attributes that are
+/// missing in `source_layout` are written with default values (see [`MissingAttributeDefaults`]), except for the
+/// position, which is mandatory
+pub(crate) fn validate_source_layout_for_las(source_layout: &PointLayout) -> Result<()> {
+    if !source_layout.has_attribute_with_name(POSITION_3D.name()) {
+        bail!(
+            "Points without a POSITION_3D attribute can't be written to a LAS file (PointLayout of the points: {})",
+            source_layout
+        );
+    }
+    Ok(())
+}
+
 /// Writes the given `BitAttributes` in LAS format to the given `writer`
 pub fn write_las_bit_attributes<T: Write>(
     bit_attributes: BitAttributes,
@@ -38,7 +202,9 @@ pub fn write_las_bit_attributes<T: Write>(
         BitAttributes::Extended(attributes) => {
             let low_mask =
                 (attributes.return_number & 0b1111) | (attributes.number_of_returns & 0b1111) << 4;
+            // The overlap flag is bit 3 of the classification flags
             let high_mask = (attributes.classification_flags & 0b1111)
+                | (attributes.overlap & 0b1) << 3
                 | (attributes.scanner_channel & 0b11) << 4
                 | (attributes.scan_direction_flag & 0b1) << 6
                 | (attributes.edge_of_flight_line & 0b1) << 7;
@@ -50,6 +216,12 @@ pub fn write_las_bit_attributes<T: Write>(
     Ok(())
 }
 
+/// Packs the given `classification` and `classification_flags` into the classification byte of the legacy LAS point
+/// formats 0-5, which stores the synthetic, key-point and withheld flags in its upper three bits
+pub(crate) fn legacy_las_classification_byte(classification: u8, classification_flags: u8) -> u8 {
+    classification | (classification_flags & 0b111) << 5
+}
+
 /// Writes a Rust `str` into a LAS byte-array, since LAS encodes strings as fixed-length `u8` arrays. This copies
 /// the bytes from the Rust `str` verbatim, but might trim the `str` if it is longer than the `las_array`. Assumes
 /// that `las_array` is zero-initialized!
@@ -1,8 +1,29 @@
 #![warn(clippy::all)]
 
+#[cfg(feature = "las")]
 pub extern crate las as las_rs;
+#[cfg(feature = "parquet")]
+pub extern crate parquet as parquet_rs;
 
+#[cfg(feature = "ascii")]
 pub mod ascii;
 pub mod base;
+#[cfg(feature = "gltf")]
+pub mod gltf;
+#[cfg(feature = "las")]
 pub mod las;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+#[cfg(feature = "pcd")]
+pub mod pcd;
+#[cfg(feature = "potree")]
+pub mod potree;
+#[cfg(feature = "raw-dump")]
+pub mod raw_dump;
 pub mod tiles3d;
+
+pub use self::base::{create_writer, open_reader};
+#[cfg(any(feature = "las", feature = "ascii", feature = "parquet", feature = "gltf"))]
+pub use self::base::create_writer_stdout;
+#[cfg(any(feature = "las", feature = "ascii", feature = "pcd"))]
+pub use self::base::open_reader_stdin;
@@ -0,0 +1,8 @@
+mod parquet_writer;
+pub use self::parquet_writer::*;
+
+mod parquet_reader;
+pub use self::parquet_reader::*;
+
+mod parquet_metadata;
+pub use self::parquet_metadata::*;
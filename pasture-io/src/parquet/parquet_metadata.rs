@@ -0,0 +1,121 @@
+use std::{any::Any, fmt::Display};
+
+use parquet::file::{
+    metadata::{ParquetMetaData, RowGroupMetaData},
+    statistics::Statistics,
+};
+use pasture_core::{layout::attributes::POSITION_3D, math::AABB, meta::Metadata, nalgebra::Point3};
+
+/// Returns the name of the column that stores the given component (`x`, `y` or `z`) of the positions
+fn position_column_name(component: &str) -> String {
+    format!("{}.{}", POSITION_3D.name(), component)
+}
+
+/// Returns the range of values of the column with the given `column_name` in `row_group`, if the column has min/max
+/// statistics and stores floating point values
+fn column_value_range(row_group: &RowGroupMetaData, column_name: &str) -> Option<(f64, f64)> {
+    let column = row_group
+        .columns()
+        .iter()
+        .find(|column| column.column_descr().name() == column_name)?;
+    match column.statistics()? {
+        Statistics::Double(statistics) => Some((*statistics.min_opt()?, *statistics.max_opt()?)),
+        Statistics::Float(statistics) => {
+            Some((*statistics.min_opt()? as f64, *statistics.max_opt()? as f64))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the bounds of the positions in `row_group` from the min/max statistics of the position columns. This
+/// requires one column per coordinate (see `ArrowVectorLayout::Components`), otherwise `None` is returned
+pub(crate) fn row_group_bounds(row_group: &RowGroupMetaData) -> Option<AABB<f64>> {
+    let (min_x, max_x) = column_value_range(row_group, &position_column_name("x"))?;
+    let (min_y, max_y) = column_value_range(row_group, &position_column_name("y"))?;
+    let (min_z, max_z) = column_value_range(row_group, &position_column_name("z"))?;
+    Some(AABB::from_min_max_unchecked(
+        Point3::new(min_x, min_y, min_z),
+        Point3::new(max_x, max_y, max_z),
+    ))
+}
+
+/// `Metadata` of a Parquet file with point data
+#[derive(Debug, Clone)]
+pub struct ParquetMetadata {
+    number_of_points: usize,
+    row_group_sizes: Vec<usize>,
+    bounds: Option<AABB<f64>>,
+    created_by: Option<String>,
+}
+
+impl ParquetMetadata {
+    pub(crate) fn from_parquet_metadata(metadata: &ParquetMetaData) -> Self {
+        let row_groups = metadata.row_groups();
+        let bounds = row_groups
+            .iter()
+            .map(row_group_bounds)
+            .reduce(|a, b| Some(AABB::union(&a?, &b?)))
+            .flatten();
+        Self {
+            number_of_points: metadata.file_metadata().num_rows() as usize,
+            row_group_sizes: row_groups
+                .iter()
+                .map(|row_group| row_group.num_rows() as usize)
+                .collect(),
+            bounds,
+            created_by: metadata.file_metadata().created_by().map(str::to_owned),
+        }
+    }
+
+    /// Returns the number of points in each row group of the Parquet file
+    pub fn row_group_sizes(&self) -> &[usize] {
+        &self.row_group_sizes
+    }
+
+    /// Returns the application that wrote the Parquet file, if it is known
+    pub fn created_by(&self) -> Option<&str> {
+        self.created_by.as_deref()
+    }
+}
+
+impl Metadata for ParquetMetadata {
+    /// The bounds are known if every row group has min/max statistics for the position columns
+    fn bounds(&self) -> Option<AABB<f64>> {
+        self.bounds
+    }
+
+    fn number_of_points(&self) -> Option<usize> {
+        Some(self.number_of_points)
+    }
+
+    fn get_named_field(&self, field_name: &str) -> Option<Box<dyn Any>> {
+        match field_name {
+            "RowGroupSizes" => Some(Box::new(self.row_group_sizes.clone())),
+            "CreatedBy" => self
+                .created_by
+                .clone()
+                .map(|created_by| Box::new(created_by) as Box<dyn Any>),
+            _ => None,
+        }
+    }
+
+    fn clone_into_box(&self) -> Box<dyn Metadata> {
+        Box::new(self.clone())
+    }
+}
+
+impl Display for ParquetMetadata {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "ParquetMetadata {{")?;
+        writeln!(f, "\t\"number_of_points\": {}", self.number_of_points)?;
+        writeln!(f, "\t\"row_groups\": {}", self.row_group_sizes.len())?;
+        if let Some(bounds) = &self.bounds {
+            writeln!(f, "\t\"bounds_min\": {}", bounds.min())?;
+            writeln!(f, "\t\"bounds_max\": {}", bounds.max())?;
+        }
+        if let Some(created_by) = &self.created_by {
+            writeln!(f, "\t\"created_by\": {}", created_by)?;
+        }
+        writeln!(f, "}}")
+    }
+}
@@ -0,0 +1,472 @@
+use std::{fs::File, path::Path};
+
+use anyhow::{bail, Context, Result};
+use parquet::{
+    arrow::arrow_reader::{ParquetRecordBatchReader, ParquetRecordBatchReaderBuilder},
+    file::reader::ChunkReader,
+};
+use pasture_core::{
+    containers::{
+        point_layout_from_arrow_schema, record_batch_to_point_buffer, BorrowedBuffer,
+        BorrowedMutBuffer, InterleavedBuffer, MakeBufferFromLayout, OwningBuffer, VectorBuffer,
+    },
+    layout::{attributes::POSITION_3D, conversion::BufferLayoutConverter, PointLayout},
+    math::AABB,
+    meta::Metadata,
+    nalgebra::{Point3, Vector3},
+};
+
+use crate::base::PointReader;
+
+use super::{row_group_bounds, ParquetMetadata};
+
+/// Number of points in each `RecordBatch` that is read from the Parquet file
+const POINTS_PER_BATCH: usize = 1 << 16;
+
+/// Reader for point data in the [Apache Parquet](https://parquet.apache.org/) format, as written by the
+/// [`ParquetWriter`](super::ParquetWriter). The default `PointLayout` is created from the Arrow schema of the file (see
+/// [`point_layout_from_arrow_schema`]), so each column becomes an attribute with the name of the column.
+///
+/// The reader can be restricted to the points within a bounding box. Row groups whose min/max statistics of the
+/// position columns show that they lie outside of the bounding box are skipped without decoding them, the points of
+/// all other row groups are filtered individually
+pub struct ParquetReader {
+    batches: ParquetRecordBatchReader,
+    metadata: ParquetMetadata,
+    layout: PointLayout,
+    bounds_filter: Option<AABB<f64>>,
+    /// Points of the last `RecordBatch` in the default layout, of which the points before `next_pending_point` have
+    /// already been read
+    pending_points: VectorBuffer,
+    next_pending_point: usize,
+    current_point_index: usize,
+}
+
+impl ParquetReader {
+    /// Creates a new `ParquetReader` that reads all points of the Parquet file in `source`
+    pub fn from_chunk_reader<T: ChunkReader + 'static>(source: T) -> Result<Self> {
+        Self::new(source, None)
+    }
+
+    /// Creates a new `ParquetReader` that reads only the points of the Parquet file in `source` that lie within
+    /// `bounds`
+    ///
+    /// # Errors
+    ///
+    /// If the file has no `POSITION_3D` attribute
+    pub fn from_chunk_reader_with_bounds<T: ChunkReader + 'static>(
+        source: T,
+        bounds: AABB<f64>,
+    ) -> Result<Self> {
+        Self::new(source, Some(bounds))
+    }
+
+    /// Creates a new `ParquetReader` that reads all points of the Parquet file at `path`
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::new(Self::open_file(path.as_ref())?, None)
+    }
+
+    /// Creates a new `ParquetReader` that reads only the points of the Parquet file at `path` that lie within
+    /// `bounds`
+    ///
+    /// # Errors
+    ///
+    /// If the file has no `POSITION_3D` attribute
+    pub fn from_path_with_bounds<P: AsRef<Path>>(path: P, bounds: AABB<f64>) -> Result<Self> {
+        Self::new(Self::open_file(path.as_ref())?, Some(bounds))
+    }
+
+    /// Returns the `ParquetMetadata` of the Parquet file
+    pub fn parquet_metadata(&self) -> &ParquetMetadata {
+        &self.metadata
+    }
+
+    fn open_file(path: &Path) -> Result<File> {
+        File::open(path).with_context(|| format!("Could not open file {}", path.display()))
+    }
+
+    fn new<T: ChunkReader + 'static>(source: T, bounds_filter: Option<AABB<f64>>) -> Result<Self> {
+        let builder = ParquetRecordBatchReaderBuilder::try_new(source)
+            .context("Failed to read metadata of Parquet file")?;
+        let layout = point_layout_from_arrow_schema(builder.schema())?;
+        let metadata = ParquetMetadata::from_parquet_metadata(builder.metadata());
+
+        let builder = match &bounds_filter {
+            Some(bounds) => {
+                if !layout.has_attribute_with_name(POSITION_3D.name()) {
+                    bail!("Filtering by bounds requires a POSITION_3D attribute");
+                }
+                // Row groups without statistics for the positions have to be read
+                let row_groups = builder
+                    .metadata()
+                    .row_groups()
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, row_group)| match row_group_bounds(row_group) {
+                        Some(row_group_bounds) => row_group_bounds.intersects(bounds),
+                        None => true,
+                    })
+                    .map(|(index, _)| index)
+                    .collect();
+                builder.with_row_groups(row_groups)
+            }
+            None => builder,
+        };
+        let batches = builder
+            .with_batch_size(POINTS_PER_BATCH)
+            .build()
+            .context("Failed to create Parquet reader")?;
+
+        Ok(Self {
+            batches,
+            metadata,
+            pending_points: VectorBuffer::new_from_layout(layout.clone()),
+            layout,
+            bounds_filter,
+            next_pending_point: 0,
+            current_point_index: 0,
+        })
+    }
+
+    /// Reads the next `RecordBatch` with at least one point within the bounds filter into `self.pending_points`.
+    /// Returns `false` if there are no more points
+    fn read_next_batch(&mut self) -> Result<bool> {
+        for record_batch in self.batches.by_ref() {
+            let record_batch = record_batch.context("Failed to read points from Parquet file")?;
+            let points = record_batch_to_point_buffer::<VectorBuffer>(&record_batch)?;
+            self.pending_points = match &self.bounds_filter {
+                Some(bounds) => {
+                    let mut points_in_bounds =
+                        VectorBuffer::with_capacity(points.len(), self.layout.clone());
+                    let positions =
+                        points.view_attribute_with_conversion::<Vector3<f64>>(&POSITION_3D)?;
+                    for index in 0..points.len() {
+                        let position: Point3<f64> = positions.at(index).into();
+                        if bounds.contains(&position) {
+                            // Safe because both buffers have the same `PointLayout`
+                            unsafe {
+                                points_in_bounds.push_points(points.get_point_ref(index));
+                            }
+                        }
+                    }
+                    points_in_bounds
+                }
+                None => points,
+            };
+            self.next_pending_point = 0;
+            if !self.pending_points.is_empty() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
+
+impl PointReader for ParquetReader {
+    fn read_into<'a, 'b, B: BorrowedMutBuffer<'a>>(
+        &mut self,
+        point_buffer: &'b mut B,
+        count: usize,
+    ) -> Result<usize>
+    where
+        'a: 'b,
+    {
+        let source_layout = self.layout.clone();
+        let target_layout = point_buffer.point_layout().clone();
+        let converter =
+            BufferLayoutConverter::for_layouts_with_default(&source_layout, &target_layout);
+        let mut points_read = 0;
+        while points_read < count {
+            if self.next_pending_point == self.pending_points.len() && !self.read_next_batch()? {
+                break;
+            }
+            let points_in_chunk =
+                (count - points_read).min(self.pending_points.len() - self.next_pending_point);
+            let source_range = self.next_pending_point..self.next_pending_point + points_in_chunk;
+            let target_range = points_read..points_read + points_in_chunk;
+            if target_layout == source_layout {
+                // Safe because both buffers have the same `PointLayout`
+                unsafe {
+                    point_buffer.set_point_range(
+                        target_range,
+                        self.pending_points.get_point_range_ref(source_range),
+                    );
+                }
+            } else {
+                converter.convert_into_range(
+                    &self.pending_points,
+                    source_range,
+                    point_buffer,
+                    target_range,
+                );
+            }
+            self.next_pending_point += points_in_chunk;
+            points_read += points_in_chunk;
+        }
+        self.current_point_index += points_read;
+        Ok(points_read)
+    }
+
+    /// The number of points is unknown if the reader filters points by their bounds
+    fn point_count(&self) -> Option<usize> {
+        match self.bounds_filter {
+            Some(_) => None,
+            None => self.metadata.number_of_points(),
+        }
+    }
+
+    fn point_index(&self) -> Option<usize> {
+        Some(self.current_point_index)
+    }
+
+    fn get_metadata(&self) -> &dyn Metadata {
+        &self.metadata
+    }
+
+    fn get_default_point_layout(&self) -> &PointLayout {
+        &self.layout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pasture_core::{
+        containers::HashMapBuffer,
+        layout::{attributes::INTENSITY, PointType},
+    };
+    use pasture_derive::PointType;
+    use scopeguard::defer;
+
+    use crate::{
+        base::PointWriter,
+        parquet::{ParquetWriter, ParquetWriterBuilder},
+    };
+    use parquet::{
+        basic::{Compression, ZstdLevel},
+        file::reader::{FileReader, SerializedFileReader},
+    };
+    use pasture_core::containers::ArrowVectorLayout;
+
+    use super::*;
+
+    #[derive(
+        Debug, PointType, Copy, Clone, PartialEq, bytemuck::AnyBitPattern, bytemuck::NoUninit,
+    )]
+    #[repr(C, packed)]
+    struct ParquetTestPoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        position: Vector3<f64>,
+        #[pasture(BUILTIN_INTENSITY)]
+        intensity: u16,
+        #[pasture(BUILTIN_COLOR_RGB)]
+        color: Vector3<u16>,
+        #[pasture(BUILTIN_CLASSIFICATION)]
+        classification: u8,
+        #[pasture(BUILTIN_GPS_TIME)]
+        gps_time: f64,
+        #[pasture(attribute = "Reflectance")]
+        reflectance: f32,
+    }
+
+    /// Points along the x-axis, so that row groups of consecutive points cover disjoint ranges of x
+    fn test_points(count: usize) -> Vec<ParquetTestPoint> {
+        (0..count)
+            .map(|index| ParquetTestPoint {
+                position: Vector3::new(index as f64 + 0.5, (index % 7) as f64, -(index as f64)),
+                intensity: (index * 3) as u16,
+                color: Vector3::new(index as u16, 2 * index as u16, 65535),
+                classification: (index % 32) as u8,
+                gps_time: 1000.0 + index as f64 * 0.25,
+                reflectance: index as f32 / 10.0,
+            })
+            .collect()
+    }
+
+    fn write_test_file(
+        path: &Path,
+        points: &[ParquetTestPoint],
+        builder: ParquetWriterBuilder,
+    ) -> Result<()> {
+        let mut writer = builder.build_for_path(path, ParquetTestPoint::layout())?;
+        for chunk in points.chunks(64) {
+            writer.write(&chunk.iter().copied().collect::<VectorBuffer>())?;
+        }
+        writer.finalize()
+    }
+
+    #[test]
+    fn test_parquet_round_trip() -> Result<()> {
+        let path = std::env::temp_dir().join("pasture_test_parquet_round_trip.parquet");
+        defer! {
+            let _ = std::fs::remove_file(&path);
+        }
+        let points = test_points(1000);
+        write_test_file(
+            &path,
+            &points,
+            ParquetWriterBuilder::new().points_per_row_group(100),
+        )?;
+
+        let mut reader = ParquetReader::from_path(&path)?;
+        assert_eq!(Some(1000), reader.point_count());
+        assert_eq!(&[100; 10], reader.parquet_metadata().row_group_sizes());
+        assert_eq!(
+            Some(AABB::from_min_max_unchecked(
+                Point3::new(0.5, 0.0, -999.0),
+                Point3::new(999.5, 6.0, 0.0)
+            )),
+            reader.get_metadata().bounds()
+        );
+
+        let mut read_points = vec![points[0]; points.len()];
+        // Reading into the packed layout of `ParquetTestPoint` converts the points from the default layout
+        assert_eq!(300, reader.read_into_slice(&mut read_points[..300])?);
+        assert_eq!(700, reader.read_into_slice(&mut read_points[300..])?);
+        assert_eq!(0, reader.read_into_slice(&mut read_points)?);
+        assert_eq!(Some(1000), reader.point_index());
+        assert_eq!(points, read_points);
+
+        let mut reader = ParquetReader::from_path(&path)?;
+        let buffer = reader.read_all::<HashMapBuffer>()?;
+        assert_eq!(
+            points
+                .iter()
+                .map(|point| point.intensity)
+                .collect::<Vec<_>>(),
+            buffer
+                .view_attribute::<u16>(&INTENSITY)
+                .into_iter()
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_parquet_bounds_filter() -> Result<()> {
+        let points = test_points(1000);
+        let bounds = AABB::from_min_max_unchecked(
+            Point3::new(250.0, 0.0, -500.0),
+            Point3::new(420.0, 3.0, 0.0),
+        );
+        let expected_points = points
+            .iter()
+            .copied()
+            .filter(|point| {
+                let position = point.position;
+                bounds.contains(&position.into())
+            })
+            .collect::<Vec<_>>();
+        assert!(!expected_points.is_empty());
+
+        for vector_layout in [
+            ArrowVectorLayout::Components,
+            ArrowVectorLayout::FixedSizeList,
+        ] {
+            let path = std::env::temp_dir().join(format!(
+                "pasture_test_parquet_bounds_filter_{:?}.parquet",
+                vector_layout
+            ));
+            defer! {
+                let _ = std::fs::remove_file(&path);
+            }
+            write_test_file(
+                &path,
+                &points,
+                ParquetWriterBuilder::new()
+                    .points_per_row_group(100)
+                    .vector_layout(vector_layout),
+            )?;
+
+            let mut reader = ParquetReader::from_path_with_bounds(&path, bounds)?;
+            assert_eq!(None, reader.point_count());
+            // Without statistics for each coordinate, the bounds of the file are unknown
+            assert_eq!(
+                vector_layout == ArrowVectorLayout::Components,
+                reader.get_metadata().bounds().is_some()
+            );
+            let mut read_points = vec![points[0]; points.len()];
+            let num_points_read = reader.read_into_slice(&mut read_points)?;
+            assert_eq!(expected_points, read_points[..num_points_read]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parquet_compression() -> Result<()> {
+        let path = std::env::temp_dir().join("pasture_test_parquet_compression.parquet");
+        defer! {
+            let _ = std::fs::remove_file(&path);
+        }
+        let points = test_points(100);
+        let compression = Compression::ZSTD(ZstdLevel::try_new(5)?);
+        write_test_file(
+            &path,
+            &points,
+            ParquetWriterBuilder::new().compression(compression),
+        )?;
+
+        let file_reader = SerializedFileReader::new(File::open(&path)?)?;
+        let row_group = file_reader.metadata().row_group(0);
+        assert!(row_group
+            .columns()
+            .iter()
+            .all(|column| matches!(column.compression(), Compression::ZSTD(_))));
+
+        let mut reader = ParquetReader::from_path(&path)?;
+        let mut read_points = vec![points[0]; points.len()];
+        assert_eq!(points.len(), reader.read_into_slice(&mut read_points)?);
+        assert_eq!(points, read_points);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parquet_writer_finalizes_on_drop() -> Result<()> {
+        let path = std::env::temp_dir().join("pasture_test_parquet_writer_drop.parquet");
+        defer! {
+            let _ = std::fs::remove_file(&path);
+        }
+        let points = test_points(10);
+        {
+            let mut writer =
+                ParquetWriter::from_path_and_layout(&path, ParquetTestPoint::layout())?;
+            writer.write(&points.iter().copied().collect::<VectorBuffer>())?;
+            assert!(writer
+                .write(&VectorBuffer::new_from_layout(
+                    PointLayout::from_attributes(&[POSITION_3D])
+                ))
+                .is_err());
+        }
+        let reader = ParquetReader::from_path(&path)?;
+        assert_eq!(Some(10), reader.point_count());
+        Ok(())
+    }
+
+    #[cfg(feature = "las")]
+    #[test]
+    fn test_parquet_round_trip_of_las_reference_points() -> Result<()> {
+        use crate::las::{compare_to_reference_data, get_test_points_in_las_format};
+        use las_rs::point::Format;
+
+        for point_format in 0..=10 {
+            let path = std::env::temp_dir().join(format!(
+                "pasture_test_parquet_las_format_{}.parquet",
+                point_format
+            ));
+            defer! {
+                let _ = std::fs::remove_file(&path);
+            }
+            let source_points = get_test_points_in_las_format(point_format, false)?;
+            let mut writer = ParquetWriterBuilder::new()
+                .points_per_row_group(3)
+                .build_for_path(&path, source_points.point_layout().clone())?;
+            writer.write(&source_points)?;
+            writer.finalize()?;
+
+            let mut reader = ParquetReader::from_path(&path)?;
+            assert_eq!(&[3, 3, 3, 1], reader.parquet_metadata().row_group_sizes());
+            let points = reader.read_all::<VectorBuffer>()?;
+            compare_to_reference_data(&points, Format::new(point_format)?);
+        }
+        Ok(())
+    }
+}
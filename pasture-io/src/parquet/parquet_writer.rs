@@ -0,0 +1,231 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    sync::Arc,
+};
+
+use anyhow::{bail, Context, Result};
+use parquet::{arrow::ArrowWriter, basic::Compression, file::properties::WriterProperties};
+use pasture_core::{
+    containers::BorrowedBuffer,
+    containers::{point_buffer_to_record_batch, point_layout_to_arrow_schema, ArrowVectorLayout},
+    layout::PointLayout,
+};
+
+use crate::base::PointWriter;
+
+/// Default number of points per row group of the Parquet files that a [`ParquetWriter`] writes
+pub const DEFAULT_PARQUET_POINTS_PER_ROW_GROUP: usize = 1 << 20;
+
+/// Writer for point data in the [Apache Parquet](https://parquet.apache.org/) format. The points are converted into
+/// Arrow `RecordBatch`es (see [`point_buffer_to_record_batch`]), so each attribute becomes one column, and written in
+/// row groups of a fixed number of points. By default, vector attributes are written with one column per component,
+/// so that Parquet stores min/max statistics for each coordinate of `POSITION_3D`, which the
+/// [`ParquetReader`](super::ParquetReader) uses to skip row groups outside of a bounding box.
+///
+/// The Parquet file is only complete after [`finalize`](Self::finalize) has written its footer. Dropping the writer
+/// finalizes the file as well, but can only print errors to stderr
+pub struct ParquetWriter<W: Write + Send> {
+    writer: Option<ArrowWriter<W>>,
+    default_layout: PointLayout,
+    vector_layout: ArrowVectorLayout,
+}
+
+impl<W: Write + Send> ParquetWriter<W> {
+    /// Creates a new `ParquetWriter` that writes points in the given `point_layout` to `writer`, using the default
+    /// settings of [`ParquetWriterBuilder`]
+    ///
+    /// # Errors
+    ///
+    /// If any attribute of `point_layout` has a custom datatype
+    pub fn from_write_and_layout(writer: W, point_layout: PointLayout) -> Result<Self> {
+        ParquetWriterBuilder::new().build(writer, point_layout)
+    }
+
+    fn writer_mut(&mut self) -> &mut ArrowWriter<W> {
+        self.writer
+            .as_mut()
+            .expect("writer is only taken in into_inner and finalize")
+    }
+
+    /// Writes the footer of the Parquet file, after which the file is complete
+    ///
+    /// # Errors
+    ///
+    /// If the remaining points or the footer can't be written
+    pub fn finalize(mut self) -> Result<()> {
+        let mut writer = self
+            .writer
+            .take()
+            .expect("writer is only taken in into_inner and finalize");
+        writer
+            .finish()
+            .context("Failed to write footer of Parquet file")?;
+        writer
+            .inner_mut()
+            .flush()
+            .context("Failed to flush Parquet file")
+    }
+
+    /// Unwraps this `ParquetWriter`, returning the underlying writer. The Parquet file is finalized before returning
+    /// the writer, see [`Self::finalize`]
+    pub fn into_inner(mut self) -> Result<W> {
+        self.writer
+            .take()
+            .expect("writer is only taken in into_inner and finalize")
+            .into_inner()
+            .context("Failed to write footer of Parquet file")
+    }
+}
+
+impl ParquetWriter<BufWriter<File>> {
+    /// Creates a new `ParquetWriter` that writes points in the given `point_layout` to the file at `path`, using the
+    /// default settings of [`ParquetWriterBuilder`]
+    pub fn from_path_and_layout<P: AsRef<Path>>(
+        path: P,
+        point_layout: PointLayout,
+    ) -> Result<Self> {
+        ParquetWriterBuilder::new().build_for_path(path, point_layout)
+    }
+}
+
+impl<W: Write + Send> PointWriter for ParquetWriter<W> {
+    fn write<'a, B: BorrowedBuffer<'a>>(&mut self, points: &'a B) -> Result<()> {
+        if *points.point_layout() != self.default_layout {
+            bail!("PointLayout of the points does not match the PointLayout of the ParquetWriter");
+        }
+        let record_batch = point_buffer_to_record_batch(points, self.vector_layout)?;
+        self.writer_mut()
+            .write(&record_batch)
+            .context("Failed to write points to Parquet file")
+    }
+
+    /// Writes all buffered points as a row group and flushes the underlying writer. Row groups that are written by
+    /// `flush` can have less points than the configured number of points per row group
+    fn flush(&mut self) -> Result<()> {
+        let writer = self.writer_mut();
+        writer
+            .flush()
+            .context("Failed to write row group of Parquet file")?;
+        writer
+            .inner_mut()
+            .flush()
+            .context("Failed to flush Parquet file")
+    }
+
+    fn get_default_point_layout(&self) -> &PointLayout {
+        &self.default_layout
+    }
+}
+
+impl<W: Write + Send> Drop for ParquetWriter<W> {
+    fn drop(&mut self) {
+        // Errors can't be propagated from `drop`, users that care about them call `finalize` or `into_inner`
+        if let Some(writer) = self.writer.as_mut() {
+            if let Err(why) = writer.finish() {
+                let _ = writeln!(
+                    std::io::stderr(),
+                    "Could not finalize Parquet file while dropping the ParquetWriter: {:#}",
+                    why
+                );
+            }
+        }
+    }
+}
+
+/// Builder for a [`ParquetWriter`] with a custom compression codec, row group size and storage of vector attributes
+///
+/// ```no_run
+/// # use anyhow::Result;
+/// # use pasture_core::layout::{attributes::{INTENSITY, POSITION_3D}, PointLayout};
+/// # use pasture_io::parquet::ParquetWriterBuilder;
+/// # use pasture_io::parquet_rs::basic::{Compression, ZstdLevel};
+/// # fn main() -> Result<()> {
+/// let writer = ParquetWriterBuilder::new()
+///     .compression(Compression::ZSTD(ZstdLevel::default()))
+///     .points_per_row_group(100_000)
+///     .build_for_path(
+///         "points.parquet",
+///         PointLayout::from_attributes(&[POSITION_3D, INTENSITY]),
+///     )?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ParquetWriterBuilder {
+    compression: Compression,
+    points_per_row_group: usize,
+    vector_layout: ArrowVectorLayout,
+}
+
+impl Default for ParquetWriterBuilder {
+    fn default() -> Self {
+        Self {
+            compression: Compression::SNAPPY,
+            points_per_row_group: DEFAULT_PARQUET_POINTS_PER_ROW_GROUP,
+            vector_layout: ArrowVectorLayout::Components,
+        }
+    }
+}
+
+impl ParquetWriterBuilder {
+    /// Creates a new `ParquetWriterBuilder` with the default settings
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the compression codec of all columns. Defaults to `Compression::SNAPPY`
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Sets the number of points per row group. Defaults to [`DEFAULT_PARQUET_POINTS_PER_ROW_GROUP`]
+    pub fn points_per_row_group(mut self, points_per_row_group: usize) -> Self {
+        self.points_per_row_group = points_per_row_group.max(1);
+        self
+    }
+
+    /// Sets how vector attributes are stored. Defaults to `ArrowVectorLayout::Components`, which is required for
+    /// skipping row groups based on the bounds of their positions
+    pub fn vector_layout(mut self, vector_layout: ArrowVectorLayout) -> Self {
+        self.vector_layout = vector_layout;
+        self
+    }
+
+    /// Creates a `ParquetWriter` that writes points in the given `point_layout` to `writer`
+    ///
+    /// # Errors
+    ///
+    /// If any attribute of `point_layout` has a custom datatype
+    pub fn build<W: Write + Send>(
+        &self,
+        writer: W,
+        point_layout: PointLayout,
+    ) -> Result<ParquetWriter<W>> {
+        let schema = point_layout_to_arrow_schema(&point_layout, self.vector_layout)?;
+        let properties = WriterProperties::builder()
+            .set_compression(self.compression)
+            .set_max_row_group_size(self.points_per_row_group)
+            .build();
+        let writer = ArrowWriter::try_new(writer, Arc::new(schema), Some(properties))
+            .context("Failed to create Parquet writer")?;
+        Ok(ParquetWriter {
+            writer: Some(writer),
+            default_layout: point_layout,
+            vector_layout: self.vector_layout,
+        })
+    }
+
+    /// Creates a `ParquetWriter` that writes points in the given `point_layout` to the file at `path`
+    pub fn build_for_path<P: AsRef<Path>>(
+        &self,
+        path: P,
+        point_layout: PointLayout,
+    ) -> Result<ParquetWriter<BufWriter<File>>> {
+        let file = File::create(path.as_ref())
+            .with_context(|| format!("Could not create file {}", path.as_ref().display()))?;
+        self.build(BufWriter::new(file), point_layout)
+    }
+}
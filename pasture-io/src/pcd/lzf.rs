@@ -0,0 +1,94 @@
+use anyhow::{bail, Context, Result};
+
+/// Decompresses the LZF-compressed `compressed` data into a new buffer of `uncompressed_size` bytes. This is the
+/// compression that the `binary_compressed` data sections of PCD files use. Returns an error if the compressed data
+/// is corrupt or does not decompress to exactly `uncompressed_size` bytes
+pub(crate) fn lzf_decompress(compressed: &[u8], uncompressed_size: usize) -> Result<Vec<u8>> {
+    let mut decompressed = Vec::with_capacity(uncompressed_size);
+    let mut input_position = 0;
+    while input_position < compressed.len() {
+        let control = compressed[input_position] as usize;
+        input_position += 1;
+        if control < 32 {
+            // Literal run of `control + 1` bytes
+            let run_length = control + 1;
+            if input_position + run_length > compressed.len() {
+                bail!("Corrupt LZF data: Literal run exceeds the end of the compressed data");
+            }
+            if decompressed.len() + run_length > uncompressed_size {
+                bail!("Corrupt LZF data: Decompressed data exceeds {uncompressed_size} bytes");
+            }
+            decompressed
+                .extend_from_slice(&compressed[input_position..input_position + run_length]);
+            input_position += run_length;
+        } else {
+            // Back reference of at least 3 bytes into the already decompressed data. The upper 3 bits of the control
+            // byte store the length, with the value 7 meaning that the length continues in the next byte
+            let mut length = control >> 5;
+            if length == 7 {
+                let extra_length = compressed
+                    .get(input_position)
+                    .context("Corrupt LZF data: Missing length of back reference")?;
+                length += *extra_length as usize;
+                input_position += 1;
+            }
+            let low_distance = *compressed
+                .get(input_position)
+                .context("Corrupt LZF data: Missing distance of back reference")?;
+            input_position += 1;
+            length += 2;
+
+            let distance = ((control & 0x1f) << 8) + low_distance as usize + 1;
+            if distance > decompressed.len() {
+                bail!("Corrupt LZF data: Back reference points before the start of the data");
+            }
+            if decompressed.len() + length > uncompressed_size {
+                bail!("Corrupt LZF data: Decompressed data exceeds {uncompressed_size} bytes");
+            }
+            // The referenced range may overlap the bytes that are copied, so they have to be copied one at a time
+            let reference_start = decompressed.len() - distance;
+            for index in reference_start..reference_start + length {
+                decompressed.push(decompressed[index]);
+            }
+        }
+    }
+
+    if decompressed.len() != uncompressed_size {
+        bail!(
+            "Corrupt LZF data: Expected {} decompressed bytes but got {}",
+            uncompressed_size,
+            decompressed.len()
+        );
+    }
+    Ok(decompressed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lzf_decompress_literals_and_back_references() -> Result<()> {
+        // 'abc' as literal run, then a back reference of length 6 at distance 3, then 'd' as literal run
+        let compressed = [2, b'a', b'b', b'c', 4 << 5, 2, 0, b'd'];
+        assert_eq!(b"abcabcabcd".to_vec(), lzf_decompress(&compressed, 10)?);
+
+        // Back reference with extended length (7 + 3 + 2 = 12 bytes) at distance 1
+        let compressed = [0, b'x', 7 << 5, 3, 0];
+        assert_eq!(vec![b'x'; 13], lzf_decompress(&compressed, 13)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_lzf_decompress_rejects_corrupt_data() {
+        // Literal run that is longer than the remaining data
+        assert!(lzf_decompress(&[3, b'a', b'b'], 4).is_err());
+        // Back reference before any data was decompressed
+        assert!(lzf_decompress(&[1 << 5, 0], 3).is_err());
+        // Truncated back reference
+        assert!(lzf_decompress(&[0, b'a', 1 << 5], 4).is_err());
+        // Decompressed size does not match
+        assert!(lzf_decompress(&[1, b'a', b'b'], 3).is_err());
+        assert!(lzf_decompress(&[1, b'a', b'b'], 1).is_err());
+    }
+}
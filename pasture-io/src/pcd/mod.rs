@@ -0,0 +1,11 @@
+mod pcd_reader;
+pub use self::pcd_reader::*;
+
+mod pcd_writer;
+pub use self::pcd_writer::*;
+
+mod pcd_metadata;
+pub use self::pcd_metadata::*;
+
+mod lzf;
+pub(crate) use self::lzf::*;
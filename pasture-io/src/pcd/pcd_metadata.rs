@@ -0,0 +1,408 @@
+use std::{any::Any, fmt::Display, io::BufRead};
+
+use anyhow::{anyhow, bail, Context, Result};
+use pasture_core::{
+    layout::PointAttributeDataType,
+    math::AABB,
+    meta::Metadata,
+    nalgebra::{Quaternion, Vector3},
+};
+
+/// The encoding of the point data section of a PCD file, as given by the `DATA` entry of the header
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PcdDataFormat {
+    /// One point per line, with whitespace-separated values
+    Ascii,
+    /// Point records with the binary values of all fields of a point, one point after another
+    Binary,
+    /// A single LZF-compressed block that stores the binary values of all points field by field
+    BinaryCompressed,
+}
+
+impl PcdDataFormat {
+    fn from_header_value(value: &str) -> Result<Self> {
+        match value {
+            "ascii" => Ok(Self::Ascii),
+            "binary" => Ok(Self::Binary),
+            "binary_compressed" => Ok(Self::BinaryCompressed),
+            other => bail!("Unsupported PCD data format {other}"),
+        }
+    }
+}
+
+impl Display for PcdDataFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PcdDataFormat::Ascii => write!(f, "ascii"),
+            PcdDataFormat::Binary => write!(f, "binary"),
+            PcdDataFormat::BinaryCompressed => write!(f, "binary_compressed"),
+        }
+    }
+}
+
+/// The type of the values of a field in a PCD file, as given by the `TYPE` entry of the header
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PcdFieldType {
+    /// Signed integer values (`I`)
+    Signed,
+    /// Unsigned integer values (`U`)
+    Unsigned,
+    /// Floating point values (`F`)
+    Float,
+}
+
+impl PcdFieldType {
+    /// Returns the value of this type in the `TYPE` entry of the PCD header
+    pub fn header_value(&self) -> char {
+        match self {
+            PcdFieldType::Signed => 'I',
+            PcdFieldType::Unsigned => 'U',
+            PcdFieldType::Float => 'F',
+        }
+    }
+}
+
+/// A single field of the points in a PCD file, as given by the `FIELDS`, `SIZE`, `TYPE` and `COUNT` entries of
+/// the header
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PcdField {
+    name: String,
+    size: usize,
+    field_type: PcdFieldType,
+    count: usize,
+}
+
+impl PcdField {
+    /// Creates a new field with the given `name` and `count` values of `size` bytes and type `field_type` per point
+    pub fn new(name: String, size: usize, field_type: PcdFieldType, count: usize) -> Self {
+        Self {
+            name,
+            size,
+            field_type,
+            count,
+        }
+    }
+
+    /// Returns the name of this field
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the size of a single value of this field in bytes
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the type of the values of this field
+    pub fn field_type(&self) -> PcdFieldType {
+        self.field_type
+    }
+
+    /// Returns the number of values of this field per point
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the number of bytes that all values of this field take up in a binary point record
+    pub fn size_in_record(&self) -> usize {
+        self.size * self.count
+    }
+
+    /// Returns the `PointAttributeDataType` of a single value of this field
+    pub fn value_datatype(&self) -> PointAttributeDataType {
+        match (self.field_type, self.size) {
+            (PcdFieldType::Signed, 1) => PointAttributeDataType::I8,
+            (PcdFieldType::Signed, 2) => PointAttributeDataType::I16,
+            (PcdFieldType::Signed, 4) => PointAttributeDataType::I32,
+            (PcdFieldType::Signed, _) => PointAttributeDataType::I64,
+            (PcdFieldType::Unsigned, 1) => PointAttributeDataType::U8,
+            (PcdFieldType::Unsigned, 2) => PointAttributeDataType::U16,
+            (PcdFieldType::Unsigned, 4) => PointAttributeDataType::U32,
+            (PcdFieldType::Unsigned, _) => PointAttributeDataType::U64,
+            (PcdFieldType::Float, 4) => PointAttributeDataType::F32,
+            (PcdFieldType::Float, _) => PointAttributeDataType::F64,
+        }
+    }
+
+    /// Returns the `PointAttributeDataType` that stores all values of this field. Fields with 3 or 4 values use the
+    /// matching vector type if pasture has one, all other fields with more than one value are stored as byte arrays
+    pub fn datatype(&self) -> PointAttributeDataType {
+        match (self.count, self.value_datatype()) {
+            (1, datatype) => datatype,
+            (3, PointAttributeDataType::U8) => PointAttributeDataType::Vec3u8,
+            (3, PointAttributeDataType::U16) => PointAttributeDataType::Vec3u16,
+            (3, PointAttributeDataType::I32) => PointAttributeDataType::Vec3i32,
+            (3, PointAttributeDataType::F32) => PointAttributeDataType::Vec3f32,
+            (3, PointAttributeDataType::F64) => PointAttributeDataType::Vec3f64,
+            (4, PointAttributeDataType::U8) => PointAttributeDataType::Vec4u8,
+            _ => PointAttributeDataType::ByteArray(self.size_in_record() as u64),
+        }
+    }
+}
+
+/// The acquisition viewpoint of a PCD file, as given by the `VIEWPOINT` entry of the header
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PcdViewpoint {
+    pub translation: Vector3<f64>,
+    pub orientation: Quaternion<f64>,
+}
+
+impl Default for PcdViewpoint {
+    fn default() -> Self {
+        Self {
+            translation: Vector3::zeros(),
+            orientation: Quaternion::identity(),
+        }
+    }
+}
+
+/// `Metadata` implementation for PCD files. Contains the values of the PCD header
+#[derive(Clone, Debug)]
+pub struct PcdMetadata {
+    version: String,
+    fields: Vec<PcdField>,
+    width: usize,
+    height: usize,
+    viewpoint: PcdViewpoint,
+    points: usize,
+    data_format: PcdDataFormat,
+}
+
+impl PcdMetadata {
+    /// Returns the version of the PCD file format
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// Returns the fields of the points in the PCD file
+    pub fn fields(&self) -> &[PcdField] {
+        &self.fields
+    }
+
+    /// Returns the width of the point cloud. For unorganized point clouds, this is the number of points
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the height of the point cloud. For unorganized point clouds, this is always 1
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns `true` if the points of the PCD file are organized as an image-like grid of `width * height` points
+    pub fn is_organized(&self) -> bool {
+        self.height > 1
+    }
+
+    /// Returns the acquisition viewpoint of the point cloud
+    pub fn viewpoint(&self) -> PcdViewpoint {
+        self.viewpoint
+    }
+
+    /// Returns the number of points in the PCD file
+    pub fn points(&self) -> usize {
+        self.points
+    }
+
+    /// Returns the encoding of the point data of the PCD file
+    pub fn data_format(&self) -> PcdDataFormat {
+        self.data_format
+    }
+
+    /// Returns the number of bytes of a single binary point record
+    pub fn size_of_record(&self) -> usize {
+        self.fields.iter().map(PcdField::size_in_record).sum()
+    }
+
+    /// Reads the header of a PCD file from the given `reader`. After this call, `reader` is at the first byte of the
+    /// point data
+    pub(crate) fn read_header<R: BufRead>(reader: &mut R) -> Result<Self> {
+        let mut version = None;
+        let mut field_names: Option<Vec<String>> = None;
+        let mut sizes: Option<Vec<usize>> = None;
+        let mut types: Option<Vec<PcdFieldType>> = None;
+        let mut counts: Option<Vec<usize>> = None;
+        let mut width = None;
+        let mut height = None;
+        let mut viewpoint = PcdViewpoint::default();
+        let mut points = None;
+
+        let mut line = String::new();
+        let data_format = loop {
+            line.clear();
+            if reader
+                .read_line(&mut line)
+                .context("Failed to read PCD header")?
+                == 0
+            {
+                bail!("PCD header ended without a DATA entry");
+            }
+            let mut values = line.split_whitespace();
+            let key = match values.next() {
+                Some(key) if !key.starts_with('#') => key,
+                _ => continue,
+            };
+            let values = values.collect::<Vec<_>>();
+            match key {
+                "VERSION" => version = Some(values.join(" ")),
+                "FIELDS" => field_names = Some(values.iter().map(|v| v.to_string()).collect()),
+                "SIZE" => sizes = Some(parse_header_values(key, &values)?),
+                "TYPE" => {
+                    types = Some(
+                        values
+                            .iter()
+                            .map(|v| match *v {
+                                "I" => Ok(PcdFieldType::Signed),
+                                "U" => Ok(PcdFieldType::Unsigned),
+                                "F" => Ok(PcdFieldType::Float),
+                                other => Err(anyhow!("Invalid PCD field type {other}")),
+                            })
+                            .collect::<Result<_>>()?,
+                    )
+                }
+                "COUNT" => counts = Some(parse_header_values(key, &values)?),
+                "WIDTH" => width = Some(parse_single_header_value(key, &values)?),
+                "HEIGHT" => height = Some(parse_single_header_value(key, &values)?),
+                "VIEWPOINT" => {
+                    let v: Vec<f64> = parse_header_values(key, &values)?;
+                    if v.len() != 7 {
+                        bail!("PCD header entry VIEWPOINT must have 7 values");
+                    }
+                    viewpoint = PcdViewpoint {
+                        translation: Vector3::new(v[0], v[1], v[2]),
+                        orientation: Quaternion::new(v[3], v[4], v[5], v[6]),
+                    };
+                }
+                "POINTS" => points = Some(parse_single_header_value(key, &values)?),
+                "DATA" => {
+                    break PcdDataFormat::from_header_value(values.first().copied().unwrap_or(""))?
+                }
+                other => bail!("Unknown PCD header entry {other}"),
+            }
+        };
+
+        let field_names = field_names.context("PCD header is missing the FIELDS entry")?;
+        let sizes = sizes.context("PCD header is missing the SIZE entry")?;
+        let types = types.context("PCD header is missing the TYPE entry")?;
+        let counts = counts.unwrap_or_else(|| vec![1; field_names.len()]);
+        if sizes.len() != field_names.len()
+            || types.len() != field_names.len()
+            || counts.len() != field_names.len()
+        {
+            bail!("The PCD header entries FIELDS, SIZE, TYPE and COUNT must have the same number of values");
+        }
+        let fields = field_names
+            .into_iter()
+            .zip(sizes)
+            .zip(types)
+            .zip(counts)
+            .map(|(((name, size), field_type), count)| {
+                let valid_size = match field_type {
+                    PcdFieldType::Float => size == 4 || size == 8,
+                    _ => size == 1 || size == 2 || size == 4 || size == 8,
+                };
+                if !valid_size {
+                    bail!("Invalid size {size} of PCD field {name} with type {field_type:?}");
+                }
+                if count == 0 {
+                    bail!("PCD field {name} must have at least one value");
+                }
+                Ok(PcdField {
+                    name,
+                    size,
+                    field_type,
+                    count,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let width = width.context("PCD header is missing the WIDTH entry")?;
+        let height = height.context("PCD header is missing the HEIGHT entry")?;
+        let points = points.unwrap_or(width * height);
+        if points != width * height {
+            bail!(
+                "PCD header has {} POINTS, but WIDTH * HEIGHT is {}",
+                points,
+                width * height
+            );
+        }
+
+        Ok(Self {
+            version: version.unwrap_or_default(),
+            fields,
+            width,
+            height,
+            viewpoint,
+            points,
+            data_format,
+        })
+    }
+}
+
+fn parse_header_values<T: std::str::FromStr>(key: &str, values: &[&str]) -> Result<Vec<T>> {
+    values
+        .iter()
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| anyhow!("Invalid value {value} of PCD header entry {key}"))
+        })
+        .collect()
+}
+
+fn parse_single_header_value<T: std::str::FromStr>(key: &str, values: &[&str]) -> Result<T> {
+    match parse_header_values(key, values)?.pop() {
+        Some(value) if values.len() == 1 => Ok(value),
+        _ => bail!("PCD header entry {key} must have exactly one value"),
+    }
+}
+
+impl Metadata for PcdMetadata {
+    fn bounds(&self) -> Option<AABB<f64>> {
+        None
+    }
+
+    fn number_of_points(&self) -> Option<usize> {
+        Some(self.points)
+    }
+
+    fn get_named_field(&self, field_name: &str) -> Option<Box<dyn Any>> {
+        match field_name {
+            "VERSION" => Some(Box::new(self.version.clone())),
+            "WIDTH" => Some(Box::new(self.width)),
+            "HEIGHT" => Some(Box::new(self.height)),
+            "VIEWPOINT" => Some(Box::new(self.viewpoint)),
+            "POINTS" => Some(Box::new(self.points)),
+            "DATA" => Some(Box::new(self.data_format)),
+            _ => None,
+        }
+    }
+
+    fn clone_into_box(&self) -> Box<dyn Metadata> {
+        Box::new(self.clone())
+    }
+}
+
+impl Display for PcdMetadata {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "PcdMetadata {{")?;
+        writeln!(f, "\t\"version\": {}", self.version)?;
+        for field in &self.fields {
+            writeln!(
+                f,
+                "\t\"field\": {} ({:?}, {} bytes, {} values)",
+                field.name, field.field_type, field.size, field.count
+            )?;
+        }
+        writeln!(f, "\t\"width\": {}", self.width)?;
+        writeln!(f, "\t\"height\": {}", self.height)?;
+        writeln!(
+            f,
+            "\t\"viewpoint\": {} {}",
+            self.viewpoint.translation, self.viewpoint.orientation
+        )?;
+        writeln!(f, "\t\"points\": {}", self.points)?;
+        writeln!(f, "\t\"data\": {}", self.data_format)?;
+        writeln!(f, "}}")
+    }
+}
@@ -0,0 +1,731 @@
+use std::{
+    borrow::Cow,
+    convert::TryInto,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use byteorder::{LittleEndian, ReadBytesExt};
+use pasture_core::{
+    containers::{
+        BorrowedMutBuffer, InterleavedBuffer, InterleavedBufferMut, MakeBufferFromLayout,
+        OwningBuffer, VectorBuffer,
+    },
+    layout::{
+        attributes::{COLOR_RGB, INTENSITY, POSITION_3D},
+        conversion::BufferLayoutConverter,
+        FieldAlignment, PointAttributeDataType, PointAttributeDefinition, PointLayout,
+    },
+    meta::Metadata,
+};
+
+use crate::base::PointReader;
+
+use super::{lzf_decompress, PcdDataFormat, PcdField, PcdFieldType, PcdMetadata};
+
+/// Describes where the values of a single field of a PCD point record end up in the default `PointLayout`
+#[derive(Copy, Clone, Debug)]
+enum PcdFieldMapping {
+    /// Copy the bytes of the field unchanged to the given offset within the point
+    Copy { offset_in_point: usize },
+    /// Unpack the `0x00RRGGBB` value of a packed `rgb` field into a `Vector3<u8>` at the given offset within the point
+    PackedRgb { offset_in_point: usize },
+    /// Padding field (`_`) without a matching attribute
+    Skip,
+}
+
+/// Returns `true` if the `x`, `y` and `z` fields of a PCD file can be combined into the `POSITION_3D` attribute
+fn has_position_fields(fields: &[PcdField]) -> bool {
+    let position_fields = ["x", "y", "z"]
+        .iter()
+        .map(|name| fields.iter().find(|field| field.name() == *name))
+        .collect::<Option<Vec<_>>>();
+    match position_fields {
+        Some(position_fields) => position_fields.iter().all(|field| {
+            field.field_type() == PcdFieldType::Float
+                && field.count() == 1
+                && field.size() == position_fields[0].size()
+        }),
+        None => false,
+    }
+}
+
+/// Adds `attribute` to `layout` and returns its offset within the point
+fn add_pcd_attribute(
+    layout: &mut PointLayout,
+    attribute: PointAttributeDefinition,
+) -> Result<usize> {
+    if layout.has_attribute_with_name(attribute.name()) {
+        bail!(
+            "PCD file contains more than one field for the attribute {}",
+            attribute.name()
+        );
+    }
+    layout.add_attribute(attribute.clone(), FieldAlignment::Packed(1));
+    Ok(layout.offset_of(&attribute).unwrap() as usize)
+}
+
+/// Creates the default `PointLayout` for the given PCD fields, together with the mapping of each field into this
+/// layout. The attributes are in the order of the fields in the PCD file, except that `x`, `y` and `z` are combined
+/// into a single `POSITION_3D` attribute at the position of the first of these fields
+fn point_layout_from_pcd_fields(
+    fields: &[PcdField],
+) -> Result<(PointLayout, Vec<PcdFieldMapping>)> {
+    let combine_positions = has_position_fields(fields);
+    let mut layout = PointLayout::default();
+
+    let mut mappings = Vec::with_capacity(fields.len());
+    for field in fields {
+        let mapping = match field.name() {
+            "_" => PcdFieldMapping::Skip,
+            axis @ ("x" | "y" | "z") if combine_positions => {
+                let position_datatype = if field.size() == 4 {
+                    PointAttributeDataType::Vec3f32
+                } else {
+                    PointAttributeDataType::Vec3f64
+                };
+                let position_offset = match layout.get_attribute_by_name(POSITION_3D.name()) {
+                    Some(position) => position.offset() as usize,
+                    None => add_pcd_attribute(
+                        &mut layout,
+                        POSITION_3D.with_custom_datatype(position_datatype),
+                    )?,
+                };
+                let axis_index = match axis {
+                    "x" => 0,
+                    "y" => 1,
+                    _ => 2,
+                };
+                PcdFieldMapping::Copy {
+                    offset_in_point: position_offset + axis_index * field.size(),
+                }
+            }
+            "rgb" if field.size() == 4 && field.count() == 1 => PcdFieldMapping::PackedRgb {
+                offset_in_point: add_pcd_attribute(
+                    &mut layout,
+                    COLOR_RGB.with_custom_datatype(PointAttributeDataType::Vec3u8),
+                )?,
+            },
+            "intensity" if field.count() == 1 => PcdFieldMapping::Copy {
+                offset_in_point: add_pcd_attribute(
+                    &mut layout,
+                    INTENSITY.with_custom_datatype(field.datatype()),
+                )?,
+            },
+            name => PcdFieldMapping::Copy {
+                offset_in_point: add_pcd_attribute(
+                    &mut layout,
+                    PointAttributeDefinition::custom(Cow::Owned(name.to_owned()), field.datatype()),
+                )?,
+            },
+        };
+        mappings.push(mapping);
+    }
+    Ok((layout, mappings))
+}
+
+/// Parses the ASCII value of a single value of `field` and writes it as little-endian binary value into `target`
+fn parse_ascii_value(field: &PcdField, value: &str, target: &mut [u8]) -> Result<()> {
+    fn parse<T: std::str::FromStr>(value: &str) -> Result<T> {
+        value.parse().map_err(|_| anyhow!("Invalid value {value}"))
+    }
+
+    match field.value_datatype() {
+        PointAttributeDataType::I8 => target.copy_from_slice(&parse::<i8>(value)?.to_le_bytes()),
+        PointAttributeDataType::I16 => target.copy_from_slice(&parse::<i16>(value)?.to_le_bytes()),
+        PointAttributeDataType::I32 => target.copy_from_slice(&parse::<i32>(value)?.to_le_bytes()),
+        PointAttributeDataType::I64 => target.copy_from_slice(&parse::<i64>(value)?.to_le_bytes()),
+        PointAttributeDataType::U8 => target.copy_from_slice(&parse::<u8>(value)?.to_le_bytes()),
+        PointAttributeDataType::U16 => target.copy_from_slice(&parse::<u16>(value)?.to_le_bytes()),
+        PointAttributeDataType::U32 => target.copy_from_slice(&parse::<u32>(value)?.to_le_bytes()),
+        PointAttributeDataType::U64 => target.copy_from_slice(&parse::<u64>(value)?.to_le_bytes()),
+        PointAttributeDataType::F32 => target.copy_from_slice(&parse::<f32>(value)?.to_le_bytes()),
+        PointAttributeDataType::F64 => target.copy_from_slice(&parse::<f64>(value)?.to_le_bytes()),
+        other => unreachable!("PCD fields have no values of type {}", other),
+    }
+    Ok(())
+}
+
+/// A reader for point clouds in the PCD format of the [Point Cloud Library](https://pointclouds.org/documentation/tutorials/pcd_file_format.html).
+/// Supports the `ascii`, `binary` and `binary_compressed` data formats. The default `PointLayout` contains one
+/// attribute for each field of the PCD file:
+/// - The `x`, `y` and `z` fields become the `POSITION_3D` attribute as `Vector3<f32>` or `Vector3<f64>`
+/// - The packed `rgb` field becomes the `COLOR_RGB` attribute as `Vector3<u8>`
+/// - The `intensity` field becomes the `INTENSITY` attribute with the datatype of the field
+/// - All other fields become custom attributes with the name of the field. Fields with more than one value are
+///   stored as vectors if pasture has a matching vector type, and as byte arrays otherwise
+/// - Padding fields (`_`) are skipped
+pub struct PcdReader<R: BufRead> {
+    reader: R,
+    metadata: PcdMetadata,
+    layout: PointLayout,
+    field_mappings: Vec<PcdFieldMapping>,
+    current_point_index: usize,
+    /// The decompressed data of `binary_compressed` files, which stores the values of all points field by field
+    decompressed_data: Option<Vec<u8>>,
+    record_buffer: Vec<u8>,
+    decode_buffer: Option<VectorBuffer>,
+    line: String,
+}
+
+impl<R: BufRead> PcdReader<R> {
+    /// Creates a new `PcdReader` that reads the PCD file from the given `reader`
+    pub fn from_read(mut reader: R) -> Result<Self> {
+        let metadata = PcdMetadata::read_header(&mut reader)?;
+        let (layout, field_mappings) = point_layout_from_pcd_fields(metadata.fields())?;
+
+        let decompressed_data = if metadata.data_format() == PcdDataFormat::BinaryCompressed {
+            let compressed_size = reader
+                .read_u32::<LittleEndian>()
+                .context("Failed to read size of compressed PCD data")?
+                as usize;
+            let uncompressed_size = reader
+                .read_u32::<LittleEndian>()
+                .context("Failed to read size of uncompressed PCD data")?
+                as usize;
+            let expected_size = metadata.points() * metadata.size_of_record();
+            if uncompressed_size != expected_size {
+                bail!(
+                    "Uncompressed PCD data has {} bytes, but {} bytes are required for {} points",
+                    uncompressed_size,
+                    expected_size,
+                    metadata.points()
+                );
+            }
+            let mut compressed_data = vec![0; compressed_size];
+            reader
+                .read_exact(&mut compressed_data)
+                .context("Failed to read compressed PCD data")?;
+            Some(
+                lzf_decompress(&compressed_data, uncompressed_size)
+                    .context("Failed to decompress PCD data")?,
+            )
+        } else {
+            None
+        };
+
+        Ok(Self {
+            reader,
+            record_buffer: Vec::new(),
+            metadata,
+            layout,
+            field_mappings,
+            current_point_index: 0,
+            decompressed_data,
+            decode_buffer: None,
+            line: String::new(),
+        })
+    }
+
+    /// Returns the `PcdMetadata` of the PCD file
+    pub fn pcd_metadata(&self) -> &PcdMetadata {
+        &self.metadata
+    }
+
+    fn remaining_points(&self) -> usize {
+        self.metadata.points() - self.current_point_index
+    }
+
+    /// Reads the binary records of the next `count` points into `self.record_buffer`
+    fn read_records(&mut self, count: usize) -> Result<()> {
+        let size_of_record = self.metadata.size_of_record();
+        self.record_buffer.resize(count * size_of_record, 0);
+        match self.metadata.data_format() {
+            PcdDataFormat::Binary => self
+                .reader
+                .read_exact(&mut self.record_buffer)
+                .context("Failed to read PCD point records")?,
+            PcdDataFormat::Ascii => {
+                for (point_index, record) in self
+                    .record_buffer
+                    .chunks_exact_mut(size_of_record)
+                    .enumerate()
+                {
+                    let point_index_in_file = self.current_point_index + point_index;
+                    // Empty lines are skipped
+                    loop {
+                        self.line.clear();
+                        if self.reader.read_line(&mut self.line)? == 0 {
+                            bail!("PCD file ended before point {}", point_index_in_file);
+                        }
+                        if !self.line.trim().is_empty() {
+                            break;
+                        }
+                    }
+
+                    let mut values = self.line.split_whitespace();
+                    let mut offset_in_record = 0;
+                    for field in self.metadata.fields() {
+                        for _ in 0..field.count() {
+                            let value = values.next().ok_or_else(|| {
+                                anyhow!("Missing values of point {}", point_index_in_file)
+                            })?;
+                            parse_ascii_value(
+                                field,
+                                value,
+                                &mut record[offset_in_record..offset_in_record + field.size()],
+                            )
+                            .with_context(|| {
+                                format!(
+                                    "Invalid value of field {} of point {}",
+                                    field.name(),
+                                    point_index_in_file
+                                )
+                            })?;
+                            offset_in_record += field.size();
+                        }
+                    }
+                    if values.next().is_some() {
+                        bail!("Too many values for point {}", point_index_in_file);
+                    }
+                }
+            }
+            PcdDataFormat::BinaryCompressed => {
+                let decompressed_data = self
+                    .decompressed_data
+                    .as_ref()
+                    .expect("binary_compressed data is decompressed when creating the reader");
+                let mut offset_in_record = 0;
+                let mut offset_of_field_data = 0;
+                for field in self.metadata.fields() {
+                    let field_size = field.size_in_record();
+                    let field_data = &decompressed_data[offset_of_field_data
+                        + self.current_point_index * field_size
+                        ..offset_of_field_data + (self.current_point_index + count) * field_size];
+                    for (record, value) in self
+                        .record_buffer
+                        .chunks_exact_mut(size_of_record)
+                        .zip(field_data.chunks_exact(field_size))
+                    {
+                        record[offset_in_record..offset_in_record + field_size]
+                            .copy_from_slice(value);
+                    }
+                    offset_in_record += field_size;
+                    offset_of_field_data += self.metadata.points() * field_size;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the next `points.len()` points into `points`, which must have the default `PointLayout` of this reader
+    fn read_into_default_layout(&mut self, points: &mut [u8]) -> Result<()> {
+        let size_of_point = self.layout.size_of_point_entry() as usize;
+        let count = points.len() / size_of_point;
+        self.read_records(count)?;
+
+        let size_of_record = self.metadata.size_of_record();
+        for (record, point) in self
+            .record_buffer
+            .chunks_exact(size_of_record)
+            .zip(points.chunks_exact_mut(size_of_point))
+        {
+            let mut offset_in_record = 0;
+            for (field, mapping) in self.metadata.fields().iter().zip(&self.field_mappings) {
+                let field_size = field.size_in_record();
+                let value = &record[offset_in_record..offset_in_record + field_size];
+                match *mapping {
+                    PcdFieldMapping::Copy { offset_in_point } => {
+                        point[offset_in_point..offset_in_point + field_size].copy_from_slice(value)
+                    }
+                    PcdFieldMapping::PackedRgb { offset_in_point } => {
+                        let rgb = u32::from_le_bytes(value.try_into().unwrap());
+                        point[offset_in_point] = (rgb >> 16) as u8;
+                        point[offset_in_point + 1] = (rgb >> 8) as u8;
+                        point[offset_in_point + 2] = rgb as u8;
+                    }
+                    PcdFieldMapping::Skip => (),
+                }
+                offset_in_record += field_size;
+            }
+        }
+        self.current_point_index += count;
+        Ok(())
+    }
+}
+
+impl PcdReader<BufReader<File>> {
+    /// Creates a new `PcdReader` that reads the PCD file at the given `path`
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path.as_ref())
+            .with_context(|| format!("Could not open file {}", path.as_ref().display()))?;
+        Self::from_read(BufReader::new(file))
+    }
+}
+
+impl<R: BufRead> PointReader for PcdReader<R> {
+    fn read_into<'a, 'b, B: BorrowedMutBuffer<'a>>(
+        &mut self,
+        point_buffer: &'b mut B,
+        count: usize,
+    ) -> Result<usize>
+    where
+        'a: 'b,
+    {
+        let num_points_to_read = usize::min(count, self.remaining_points());
+        if num_points_to_read == 0 {
+            return Ok(0);
+        }
+
+        if *point_buffer.point_layout() == self.layout {
+            if let Some(interleaved_buffer) = point_buffer.as_interleaved_mut() {
+                self.read_into_default_layout(
+                    interleaved_buffer.get_point_range_mut(0..num_points_to_read),
+                )?;
+                return Ok(num_points_to_read);
+            }
+        }
+
+        // Decode the points in chunks into the default layout, and then copy or convert them into `point_buffer`
+        const POINTS_PER_CHUNK: usize = 1 << 16;
+        let mut decode_buffer = self
+            .decode_buffer
+            .take()
+            .unwrap_or_else(|| VectorBuffer::new_from_layout(self.layout.clone()));
+        decode_buffer.resize(num_points_to_read.min(POINTS_PER_CHUNK));
+        let source_layout = self.layout.clone();
+        let target_layout = point_buffer.point_layout().clone();
+        let converter =
+            BufferLayoutConverter::for_layouts_with_default(&source_layout, &target_layout);
+        for chunk_start in (0..num_points_to_read).step_by(POINTS_PER_CHUNK) {
+            let points_in_chunk = (num_points_to_read - chunk_start).min(POINTS_PER_CHUNK);
+            if let Err(why) =
+                self.read_into_default_layout(decode_buffer.get_point_range_mut(0..points_in_chunk))
+            {
+                self.decode_buffer = Some(decode_buffer);
+                return Err(why);
+            }
+            if target_layout == source_layout {
+                // Safe because both buffers have the same `PointLayout`
+                unsafe {
+                    point_buffer.set_point_range(
+                        chunk_start..chunk_start + points_in_chunk,
+                        decode_buffer.get_point_range_ref(0..points_in_chunk),
+                    );
+                }
+            } else {
+                converter.convert_into_range(
+                    &decode_buffer,
+                    0..points_in_chunk,
+                    point_buffer,
+                    chunk_start..chunk_start + points_in_chunk,
+                );
+            }
+        }
+        self.decode_buffer = Some(decode_buffer);
+        Ok(num_points_to_read)
+    }
+
+    fn get_metadata(&self) -> &dyn Metadata {
+        &self.metadata
+    }
+
+    fn get_default_point_layout(&self) -> &PointLayout {
+        &self.layout
+    }
+
+    fn point_index(&self) -> Option<usize> {
+        Some(self.current_point_index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use pasture_core::{
+        containers::{BorrowedBuffer, HashMapBuffer, SliceBufferMut},
+        nalgebra::{Quaternion, Vector3},
+    };
+
+    use crate::pcd::PcdViewpoint;
+
+    use super::*;
+
+    const TEST_POSITIONS: [[f32; 3]; 3] = [[1.0, 2.0, 3.0], [-4.5, 5.25, 6.0], [7.0, 8.0, -9.75]];
+    const TEST_COLORS: [u32; 3] = [0x00ff8040, 0x00010203, 0x00000000];
+    const TEST_INTENSITIES: [f32; 3] = [0.5, 12.0, 255.0];
+    const TEST_LABELS: [u32; 3] = [7, 0, 4_000_000_000];
+
+    fn test_pcd_header(data_format: &str) -> String {
+        format!(
+            "# .PCD v0.7 - Point Cloud Data file format
+VERSION 0.7
+FIELDS x y z rgb intensity _ label
+SIZE 4 4 4 4 4 1 4
+TYPE F F F F F U U
+COUNT 1 1 1 1 1 2 1
+WIDTH 3
+HEIGHT 1
+VIEWPOINT 1 2 3 0 0 0 1
+POINTS 3
+DATA {}
+",
+            data_format
+        )
+    }
+
+    /// Returns the values of all fields of the test points, one `Vec` of bytes per field
+    fn test_field_values() -> Vec<Vec<u8>> {
+        vec![
+            TEST_POSITIONS
+                .iter()
+                .flat_map(|p| p[0].to_le_bytes())
+                .collect(),
+            TEST_POSITIONS
+                .iter()
+                .flat_map(|p| p[1].to_le_bytes())
+                .collect(),
+            TEST_POSITIONS
+                .iter()
+                .flat_map(|p| p[2].to_le_bytes())
+                .collect(),
+            TEST_COLORS.iter().flat_map(|c| c.to_le_bytes()).collect(),
+            TEST_INTENSITIES
+                .iter()
+                .flat_map(|i| i.to_le_bytes())
+                .collect(),
+            vec![0xAB; 6],
+            TEST_LABELS.iter().flat_map(|l| l.to_le_bytes()).collect(),
+        ]
+    }
+
+    fn ascii_test_pcd() -> Vec<u8> {
+        let mut pcd = test_pcd_header("ascii");
+        for point_index in 0..3 {
+            pcd += &format!(
+                "{} {} {} {} {} 171 171 {}\n",
+                TEST_POSITIONS[point_index][0],
+                TEST_POSITIONS[point_index][1],
+                TEST_POSITIONS[point_index][2],
+                f32::from_bits(TEST_COLORS[point_index]),
+                TEST_INTENSITIES[point_index],
+                TEST_LABELS[point_index]
+            );
+        }
+        pcd.into_bytes()
+    }
+
+    fn binary_test_pcd() -> Vec<u8> {
+        let field_sizes = [4, 4, 4, 4, 4, 2, 4];
+        let field_values = test_field_values();
+        let mut pcd = test_pcd_header("binary").into_bytes();
+        for point_index in 0..3 {
+            for (values, size) in field_values.iter().zip(field_sizes) {
+                pcd.extend_from_slice(&values[point_index * size..(point_index + 1) * size]);
+            }
+        }
+        pcd
+    }
+
+    fn binary_compressed_test_pcd() -> Vec<u8> {
+        let uncompressed = test_field_values().concat();
+        // Encode the data as LZF literal runs, which are at most 32 bytes long
+        let compressed = uncompressed
+            .chunks(32)
+            .flat_map(|run| std::iter::once(run.len() as u8 - 1).chain(run.iter().copied()))
+            .collect::<Vec<_>>();
+        let mut pcd = test_pcd_header("binary_compressed").into_bytes();
+        pcd.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+        pcd.extend_from_slice(&(uncompressed.len() as u32).to_le_bytes());
+        pcd.extend_from_slice(&compressed);
+        pcd
+    }
+
+    fn check_test_points(pcd: Vec<u8>) -> Result<()> {
+        let mut reader = PcdReader::from_read(Cursor::new(pcd))?;
+        let expected_layout = PointLayout::from_attributes_packed(
+            &[
+                POSITION_3D.with_custom_datatype(PointAttributeDataType::Vec3f32),
+                COLOR_RGB.with_custom_datatype(PointAttributeDataType::Vec3u8),
+                INTENSITY.with_custom_datatype(PointAttributeDataType::F32),
+                PointAttributeDefinition::custom(
+                    Cow::Borrowed("label"),
+                    PointAttributeDataType::U32,
+                ),
+            ],
+            1,
+        );
+        assert_eq!(&expected_layout, reader.get_default_point_layout());
+
+        let points = reader.read::<VectorBuffer>(10)?;
+        assert_eq!(3, points.len());
+        assert_eq!(
+            TEST_POSITIONS
+                .iter()
+                .map(|p| Vector3::new(p[0], p[1], p[2]))
+                .collect::<Vec<_>>(),
+            points
+                .view_attribute::<Vector3<f32>>(
+                    &POSITION_3D.with_custom_datatype(PointAttributeDataType::Vec3f32)
+                )
+                .into_iter()
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![
+                Vector3::new(0xff, 0x80, 0x40),
+                Vector3::new(1, 2, 3),
+                Vector3::new(0, 0, 0)
+            ],
+            points
+                .view_attribute::<Vector3<u8>>(
+                    &COLOR_RGB.with_custom_datatype(PointAttributeDataType::Vec3u8)
+                )
+                .into_iter()
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            TEST_INTENSITIES.to_vec(),
+            points
+                .view_attribute::<f32>(&INTENSITY.with_custom_datatype(PointAttributeDataType::F32))
+                .into_iter()
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            TEST_LABELS.to_vec(),
+            points
+                .view_attribute::<u32>(&PointAttributeDefinition::custom(
+                    Cow::Borrowed("label"),
+                    PointAttributeDataType::U32
+                ))
+                .into_iter()
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(0, reader.read::<VectorBuffer>(10)?.len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_pcd_reader_ascii() -> Result<()> {
+        check_test_points(ascii_test_pcd())
+    }
+
+    #[test]
+    fn test_pcd_reader_binary() -> Result<()> {
+        check_test_points(binary_test_pcd())
+    }
+
+    #[test]
+    fn test_pcd_reader_binary_compressed() -> Result<()> {
+        check_test_points(binary_compressed_test_pcd())
+    }
+
+    #[test]
+    fn test_pcd_reader_converts_into_other_layouts() -> Result<()> {
+        for pcd in [
+            ascii_test_pcd(),
+            binary_test_pcd(),
+            binary_compressed_test_pcd(),
+        ] {
+            let mut reader = PcdReader::from_read(Cursor::new(pcd))?;
+            let mut points = HashMapBuffer::new_from_layout(PointLayout::from_attributes(&[
+                POSITION_3D,
+                INTENSITY,
+            ]));
+            points.resize(3);
+            // Read in two calls, so that the points of the second call start in the middle of the data
+            assert_eq!(1, reader.read_into(&mut points, 1)?);
+            assert_eq!(Some(1), reader.point_index());
+            assert_eq!(2, reader.read_into(&mut points.slice_mut(1..3), 5)?);
+
+            assert_eq!(
+                Vector3::new(-4.5, 5.25, 6.0),
+                points.view_attribute::<Vector3<f64>>(&POSITION_3D).at(1)
+            );
+            assert_eq!(
+                vec![0, 12, 255],
+                points
+                    .view_attribute::<u16>(&INTENSITY)
+                    .into_iter()
+                    .collect::<Vec<_>>()
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_pcd_reader_read_all_after_partial_read() -> Result<()> {
+        let mut reader = PcdReader::from_read(Cursor::new(binary_test_pcd()))?;
+        assert_eq!(1, reader.read::<VectorBuffer>(1)?.len());
+        let points = reader.read_all::<VectorBuffer>()?;
+        assert_eq!(2, points.len());
+        assert_eq!(
+            Vector3::new(-4.5, 5.25, 6.0),
+            points
+                .view_attribute::<Vector3<f32>>(
+                    &POSITION_3D.with_custom_datatype(PointAttributeDataType::Vec3f32)
+                )
+                .at(0)
+        );
+        assert_eq!(Some(3), reader.point_index());
+        assert_eq!(0, reader.read_all::<VectorBuffer>()?.len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_pcd_reader_metadata() -> Result<()> {
+        let reader = PcdReader::from_read(Cursor::new(binary_test_pcd()))?;
+        let metadata = reader.get_metadata();
+        assert_eq!(Some(3), metadata.number_of_points());
+        assert_eq!(
+            Some(&3),
+            metadata
+                .get_named_field("WIDTH")
+                .and_then(|width| width.downcast::<usize>().ok())
+                .as_deref()
+        );
+        assert_eq!(
+            Some(&1),
+            metadata
+                .get_named_field("HEIGHT")
+                .and_then(|height| height.downcast::<usize>().ok())
+                .as_deref()
+        );
+        assert_eq!(
+            Some(&PcdViewpoint {
+                translation: Vector3::new(1.0, 2.0, 3.0),
+                orientation: Quaternion::new(0.0, 0.0, 0.0, 1.0),
+            }),
+            metadata
+                .get_named_field("VIEWPOINT")
+                .and_then(|viewpoint| viewpoint.downcast::<PcdViewpoint>().ok())
+                .as_deref()
+        );
+        assert_eq!("0.7", reader.pcd_metadata().version());
+        assert_eq!(PcdDataFormat::Binary, reader.pcd_metadata().data_format());
+        assert!(!reader.pcd_metadata().is_organized());
+        Ok(())
+    }
+
+    #[test]
+    fn test_pcd_reader_rejects_invalid_files() {
+        let invalid_headers = [
+            // Missing COUNT value
+            test_pcd_header("ascii").replace("COUNT 1 1 1 1 1 2 1", "COUNT 1 1 1 1 1 2"),
+            // Invalid size of a float field
+            test_pcd_header("ascii").replace("SIZE 4 4 4", "SIZE 2 4 4"),
+            // POINTS does not match WIDTH * HEIGHT
+            test_pcd_header("ascii").replace("POINTS 3", "POINTS 4"),
+            test_pcd_header("binary_lz4"),
+            test_pcd_header("ascii").replace("DATA ascii\n", ""),
+        ];
+        for header in invalid_headers {
+            assert!(PcdReader::from_read(Cursor::new(header.into_bytes())).is_err());
+        }
+
+        // Truncated point data
+        let mut binary_pcd = binary_test_pcd();
+        binary_pcd.truncate(binary_pcd.len() - 1);
+        let mut reader = PcdReader::from_read(Cursor::new(binary_pcd)).unwrap();
+        assert!(reader.read::<VectorBuffer>(3).is_err());
+
+        let ascii_pcd = String::from_utf8(ascii_test_pcd())
+            .unwrap()
+            .replace("171 171 7", "171 171 -7");
+        let mut reader = PcdReader::from_read(Cursor::new(ascii_pcd.into_bytes())).unwrap();
+        assert!(reader.read::<VectorBuffer>(3).is_err());
+    }
+}
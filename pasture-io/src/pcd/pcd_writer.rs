@@ -0,0 +1,751 @@
+use std::{
+    convert::TryInto,
+    fmt::Write as _,
+    fs::File,
+    io::{BufWriter, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use anyhow::{bail, Context, Result};
+use pasture_core::{
+    containers::BorrowedBuffer,
+    layout::{
+        attributes::{COLOR_RGB, INTENSITY, NORMAL, POSITION_3D},
+        PointAttributeDataType, PointAttributeMember, PointLayout,
+    },
+};
+
+use crate::base::PointWriter;
+
+use super::{PcdDataFormat, PcdField, PcdFieldType, PcdViewpoint};
+
+/// Number of characters that are reserved for the `WIDTH` and `POINTS` values in the PCD header, which are only known
+/// once all points have been written. This is enough for every `u64` value
+const RESERVED_POINT_COUNT_CHARS: usize = 20;
+
+/// Describes where the values of a single field of a PCD point record come from in the written points
+#[derive(Copy, Clone, Debug)]
+enum PcdFieldSource {
+    /// Copy the bytes of the field unchanged from the given offset within the point
+    Copy { offset_in_point: usize },
+    /// Pack the `Vector3<u8>` or `Vector3<u16>` color at the given offset within the point into the `0x00RRGGBB` value
+    /// of an `rgb` field. 16-bit colors are reduced to their upper 8 bits
+    PackedRgb {
+        offset_in_point: usize,
+        is_16_bit: bool,
+    },
+}
+
+/// Returns the PCD field type and size of values with the given scalar `datatype`, or `None` if PCD has no matching
+/// type
+fn pcd_type_of_scalar(datatype: PointAttributeDataType) -> Option<(PcdFieldType, usize)> {
+    match datatype {
+        PointAttributeDataType::I8 => Some((PcdFieldType::Signed, 1)),
+        PointAttributeDataType::I16 => Some((PcdFieldType::Signed, 2)),
+        PointAttributeDataType::I32 => Some((PcdFieldType::Signed, 4)),
+        PointAttributeDataType::I64 => Some((PcdFieldType::Signed, 8)),
+        PointAttributeDataType::U8 => Some((PcdFieldType::Unsigned, 1)),
+        PointAttributeDataType::U16 => Some((PcdFieldType::Unsigned, 2)),
+        PointAttributeDataType::U32 => Some((PcdFieldType::Unsigned, 4)),
+        PointAttributeDataType::U64 => Some((PcdFieldType::Unsigned, 8)),
+        PointAttributeDataType::F32 => Some((PcdFieldType::Float, 4)),
+        PointAttributeDataType::F64 => Some((PcdFieldType::Float, 8)),
+        _ => None,
+    }
+}
+
+/// Returns the name of the PCD field for `attribute`. Field names are separated by whitespace in the PCD header, so
+/// whitespace within attribute names is replaced by underscores
+fn pcd_field_name(attribute: &PointAttributeMember) -> String {
+    if attribute.name() == INTENSITY.name() {
+        return "intensity".to_owned();
+    }
+    attribute
+        .name()
+        .chars()
+        .map(|c| if c.is_whitespace() { '_' } else { c })
+        .collect()
+}
+
+/// Returns the names of the three PCD fields that the components of the vector-valued `attribute` are decomposed into
+fn pcd_field_names_of_vector(attribute: &PointAttributeMember) -> Vec<String> {
+    if attribute.name() == POSITION_3D.name() {
+        vec!["x".to_owned(), "y".to_owned(), "z".to_owned()]
+    } else if attribute.name() == COLOR_RGB.name() {
+        vec!["r".to_owned(), "g".to_owned(), "b".to_owned()]
+    } else {
+        let name = if attribute.name() == NORMAL.name() {
+            "normal".to_owned()
+        } else {
+            pcd_field_name(attribute)
+        };
+        vec![
+            format!("{}_x", name),
+            format!("{}_y", name),
+            format!("{}_z", name),
+        ]
+    }
+}
+
+/// Creates the PCD fields for the attributes of `point_layout`, together with the source of each field within the
+/// points of this layout
+fn pcd_fields_from_point_layout(
+    point_layout: &PointLayout,
+    pack_rgb: bool,
+) -> Result<(Vec<PcdField>, Vec<PcdFieldSource>)> {
+    let mut fields = vec![];
+    let mut sources = vec![];
+    for attribute in point_layout.attributes() {
+        let offset_in_point = attribute.offset() as usize;
+        let datatype = attribute.datatype();
+        if let Some((field_type, size)) = pcd_type_of_scalar(datatype) {
+            fields.push(PcdField::new(
+                pcd_field_name(attribute),
+                size,
+                field_type,
+                1,
+            ));
+            sources.push(PcdFieldSource::Copy { offset_in_point });
+            continue;
+        }
+
+        if pack_rgb && attribute.name() == COLOR_RGB.name() {
+            let is_16_bit = match datatype {
+                PointAttributeDataType::Vec3u8 => false,
+                PointAttributeDataType::Vec3u16 => true,
+                other => bail!(
+                    "Packing COLOR_RGB into an rgb field requires Vec3u8 or Vec3u16 colors, but got {}",
+                    other
+                ),
+            };
+            fields.push(PcdField::new("rgb".to_owned(), 4, PcdFieldType::Float, 1));
+            sources.push(PcdFieldSource::PackedRgb {
+                offset_in_point,
+                is_16_bit,
+            });
+            continue;
+        }
+
+        let component_datatype = match datatype {
+            PointAttributeDataType::Vec3u8 => Some(PointAttributeDataType::U8),
+            PointAttributeDataType::Vec3u16 => Some(PointAttributeDataType::U16),
+            PointAttributeDataType::Vec3i32 => Some(PointAttributeDataType::I32),
+            PointAttributeDataType::Vec3f32 => Some(PointAttributeDataType::F32),
+            PointAttributeDataType::Vec3f64 => Some(PointAttributeDataType::F64),
+            _ => None,
+        };
+        match component_datatype.and_then(pcd_type_of_scalar) {
+            Some((field_type, size)) => {
+                for (component, name) in
+                    pcd_field_names_of_vector(attribute).into_iter().enumerate()
+                {
+                    fields.push(PcdField::new(name, size, field_type, 1));
+                    sources.push(PcdFieldSource::Copy {
+                        offset_in_point: offset_in_point + component * size,
+                    });
+                }
+            }
+            None => {
+                // All other attributes (e.g. Vec4u8 or byte arrays) are written as a single field with one byte per value
+                fields.push(PcdField::new(
+                    pcd_field_name(attribute),
+                    1,
+                    PcdFieldType::Unsigned,
+                    attribute.size() as usize,
+                ));
+                sources.push(PcdFieldSource::Copy { offset_in_point });
+            }
+        }
+    }
+
+    for (index, field) in fields.iter().enumerate() {
+        if fields[..index]
+            .iter()
+            .any(|other| other.name() == field.name())
+        {
+            bail!(
+                "PointLayout results in more than one PCD field named {}",
+                field.name()
+            );
+        }
+    }
+    Ok((fields, sources))
+}
+
+/// Appends the ASCII representation of the little-endian value `bytes` of `field` to `line`
+fn format_ascii_value(field: &PcdField, bytes: &[u8], line: &mut String) {
+    let _ = match field.value_datatype() {
+        PointAttributeDataType::I8 => write!(line, "{}", bytes[0] as i8),
+        PointAttributeDataType::I16 => {
+            write!(line, "{}", i16::from_le_bytes(bytes.try_into().unwrap()))
+        }
+        PointAttributeDataType::I32 => {
+            write!(line, "{}", i32::from_le_bytes(bytes.try_into().unwrap()))
+        }
+        PointAttributeDataType::I64 => {
+            write!(line, "{}", i64::from_le_bytes(bytes.try_into().unwrap()))
+        }
+        PointAttributeDataType::U8 => write!(line, "{}", bytes[0]),
+        PointAttributeDataType::U16 => {
+            write!(line, "{}", u16::from_le_bytes(bytes.try_into().unwrap()))
+        }
+        PointAttributeDataType::U32 => {
+            write!(line, "{}", u32::from_le_bytes(bytes.try_into().unwrap()))
+        }
+        PointAttributeDataType::U64 => {
+            write!(line, "{}", u64::from_le_bytes(bytes.try_into().unwrap()))
+        }
+        PointAttributeDataType::F32 => {
+            let value = f32::from_le_bytes(bytes.try_into().unwrap());
+            if value.is_nan() {
+                write!(line, "nan")
+            } else if field.name() == "rgb" {
+                // Packed colors are tiny floats, which are much shorter in scientific notation
+                write!(line, "{:e}", value)
+            } else {
+                write!(line, "{}", value)
+            }
+        }
+        PointAttributeDataType::F64 => {
+            let value = f64::from_le_bytes(bytes.try_into().unwrap());
+            if value.is_nan() {
+                write!(line, "nan")
+            } else {
+                write!(line, "{}", value)
+            }
+        }
+        other => unreachable!("PCD fields have no values of type {}", other),
+    };
+}
+
+/// Writer for point clouds in the PCD format of the [Point Cloud Library](https://pointclouds.org/documentation/tutorials/pcd_file_format.html).
+/// The fields of the PCD file are derived from the `PointLayout` that the writer is created with:
+/// - Scalar attributes become a single field, `INTENSITY` is written as the `intensity` field
+/// - `Vector3` attributes are decomposed into three fields: `x`, `y` and `z` for `POSITION_3D`, `normal_x`,
+///   `normal_y` and `normal_z` for `NORMAL`, `r`, `g` and `b` for `COLOR_RGB` and `<name>_x`, `<name>_y` and
+///   `<name>_z` for all other attributes
+/// - If requested through [`PcdWriter::set_pack_rgb`], `COLOR_RGB` is packed into the float `rgb` field that PCL uses
+/// - All other attributes are written as a field with one byte per value
+///
+/// The points are written as an unorganized point cloud (`HEIGHT 1`). Since the number of points is only known once
+/// all points have been written, the `WIDTH` and `POINTS` values of the header are updated in `flush`, which is also
+/// called when the `PcdWriter` is dropped
+pub struct PcdWriter<W: Write + Seek> {
+    writer: W,
+    default_layout: PointLayout,
+    data_format: PcdDataFormat,
+    viewpoint: PcdViewpoint,
+    pack_rgb: bool,
+    fields: Vec<PcdField>,
+    field_sources: Vec<PcdFieldSource>,
+    /// Positions of the `WIDTH` and `POINTS` values in the header, once the header has been written
+    point_count_positions: Option<[u64; 2]>,
+    point_count: usize,
+    requires_flush: bool,
+    point_buffer: Vec<u8>,
+    record_buffer: Vec<u8>,
+    line: String,
+}
+
+impl<W: Write + Seek> PcdWriter<W> {
+    /// Creates a new `PcdWriter` that writes points with the given `point_layout` into `writer`, using the given
+    /// `data_format`. Returns an error for `PcdDataFormat::BinaryCompressed`, which is not supported for writing
+    pub fn from_write_and_layout(
+        writer: W,
+        point_layout: PointLayout,
+        data_format: PcdDataFormat,
+    ) -> Result<Self> {
+        if data_format == PcdDataFormat::BinaryCompressed {
+            bail!("Writing PCD files with binary_compressed data is not supported");
+        }
+        let (fields, field_sources) = pcd_fields_from_point_layout(&point_layout, false)?;
+        Ok(Self {
+            writer,
+            default_layout: point_layout,
+            data_format,
+            viewpoint: Default::default(),
+            pack_rgb: false,
+            fields,
+            field_sources,
+            point_count_positions: None,
+            point_count: 0,
+            requires_flush: true,
+            point_buffer: vec![],
+            record_buffer: vec![],
+            line: String::new(),
+        })
+    }
+
+    /// Sets the acquisition viewpoint that is written to the `VIEWPOINT` entry of the header. Returns an error if the
+    /// header has already been written, which happens on the first call to `write` or `flush`
+    pub fn set_viewpoint(&mut self, viewpoint: PcdViewpoint) -> Result<()> {
+        if self.point_count_positions.is_some() {
+            bail!("The viewpoint can't be changed after the PCD header has been written");
+        }
+        self.viewpoint = viewpoint;
+        Ok(())
+    }
+
+    /// Sets whether `COLOR_RGB` is packed into a single float `rgb` field with the value `0x00RRGGBB`, which is the
+    /// convention of PCL, instead of writing separate `r`, `g` and `b` fields. Returns an error if the header has
+    /// already been written, or if the colors are neither `Vector3<u8>` nor `Vector3<u16>`
+    pub fn set_pack_rgb(&mut self, pack_rgb: bool) -> Result<()> {
+        if self.point_count_positions.is_some() {
+            bail!("The PCD fields can't be changed after the PCD header has been written");
+        }
+        let (fields, field_sources) = pcd_fields_from_point_layout(&self.default_layout, pack_rgb)?;
+        self.fields = fields;
+        self.field_sources = field_sources;
+        self.pack_rgb = pack_rgb;
+        Ok(())
+    }
+
+    /// Returns `true` if `COLOR_RGB` is packed into a single `rgb` field
+    pub fn pack_rgb(&self) -> bool {
+        self.pack_rgb
+    }
+
+    /// Returns the fields of the written PCD file
+    pub fn fields(&self) -> &[PcdField] {
+        &self.fields
+    }
+
+    /// Writes the PCD header with placeholders for the `WIDTH` and `POINTS` values
+    fn write_header(&mut self) -> Result<()> {
+        let join_fields = |value_of_field: &dyn Fn(&PcdField) -> String| {
+            self.fields
+                .iter()
+                .map(value_of_field)
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let fields_line = join_fields(&|field| field.name().to_owned());
+        let sizes_line = join_fields(&|field| field.size().to_string());
+        let types_line = join_fields(&|field| field.field_type().header_value().to_string());
+        let counts_line = join_fields(&|field| field.count().to_string());
+
+        write!(
+            self.writer,
+            "# .PCD v0.7 - Point Cloud Data file format\nVERSION 0.7\nFIELDS {}\nSIZE {}\nTYPE {}\nCOUNT {}\nWIDTH ",
+            fields_line, sizes_line, types_line, counts_line
+        )?;
+        let width_position = self.writer.stream_position()?;
+        write!(
+            self.writer,
+            "{:<width$}\nHEIGHT 1\nVIEWPOINT {} {} {} {} {} {} {}\nPOINTS ",
+            self.point_count,
+            self.viewpoint.translation.x,
+            self.viewpoint.translation.y,
+            self.viewpoint.translation.z,
+            self.viewpoint.orientation.w,
+            self.viewpoint.orientation.i,
+            self.viewpoint.orientation.j,
+            self.viewpoint.orientation.k,
+            width = RESERVED_POINT_COUNT_CHARS
+        )?;
+        let points_position = self.writer.stream_position()?;
+        write!(
+            self.writer,
+            "{:<width$}\nDATA {}\n",
+            self.point_count,
+            self.data_format,
+            width = RESERVED_POINT_COUNT_CHARS
+        )?;
+        self.point_count_positions = Some([width_position, points_position]);
+        Ok(())
+    }
+
+    /// Writes the PCD record of the point with the memory `point` into `self.record_buffer`
+    fn fill_record(&mut self, point: &[u8]) {
+        let mut offset_in_record = 0;
+        for (field, source) in self.fields.iter().zip(&self.field_sources) {
+            let field_size = field.size_in_record();
+            let record_value =
+                &mut self.record_buffer[offset_in_record..offset_in_record + field_size];
+            match *source {
+                PcdFieldSource::Copy { offset_in_point } => record_value
+                    .copy_from_slice(&point[offset_in_point..offset_in_point + field_size]),
+                PcdFieldSource::PackedRgb {
+                    offset_in_point,
+                    is_16_bit,
+                } => {
+                    let component = |index: usize| -> u32 {
+                        if is_16_bit {
+                            let offset = offset_in_point + index * 2;
+                            (u16::from_le_bytes([point[offset], point[offset + 1]]) >> 8) as u32
+                        } else {
+                            point[offset_in_point + index] as u32
+                        }
+                    };
+                    let rgb = (component(0) << 16) | (component(1) << 8) | component(2);
+                    record_value.copy_from_slice(&rgb.to_le_bytes());
+                }
+            }
+            offset_in_record += field_size;
+        }
+    }
+
+    /// Writes the PCD record in `self.record_buffer` to the output
+    fn write_record(&mut self) -> Result<()> {
+        match self.data_format {
+            PcdDataFormat::Ascii => {
+                self.line.clear();
+                let mut offset_in_record = 0;
+                for field in &self.fields {
+                    for _ in 0..field.count() {
+                        if offset_in_record > 0 {
+                            self.line.push(' ');
+                        }
+                        format_ascii_value(
+                            field,
+                            &self.record_buffer[offset_in_record..offset_in_record + field.size()],
+                            &mut self.line,
+                        );
+                        offset_in_record += field.size();
+                    }
+                }
+                self.line.push('\n');
+                self.writer.write_all(self.line.as_bytes())?;
+            }
+            _ => self.writer.write_all(&self.record_buffer)?,
+        }
+        Ok(())
+    }
+}
+
+impl PcdWriter<BufWriter<File>> {
+    /// Creates a new `PcdWriter` that writes points with the given `point_layout` into a new PCD file at `path`,
+    /// using the given `data_format`
+    pub fn from_path_and_layout<P: AsRef<Path>>(
+        path: P,
+        point_layout: PointLayout,
+        data_format: PcdDataFormat,
+    ) -> Result<Self> {
+        let file = File::create(path.as_ref()).with_context(|| {
+            format!(
+                "Could not open file {} for writing",
+                path.as_ref().display()
+            )
+        })?;
+        Self::from_write_and_layout(BufWriter::new(file), point_layout, data_format)
+    }
+}
+
+impl<W: Write + Seek> PointWriter for PcdWriter<W> {
+    fn write<'a, B: BorrowedBuffer<'a>>(&mut self, points: &'a B) -> Result<()> {
+        if *points.point_layout() != self.default_layout {
+            bail!("PointLayout of the points does not match the PointLayout of the PcdWriter");
+        }
+        if self.point_count_positions.is_none() {
+            self.write_header()?;
+        }
+
+        const POINTS_PER_CHUNK: usize = 4096;
+        let size_of_point = self.default_layout.size_of_point_entry() as usize;
+        self.point_buffer
+            .resize(points.len().min(POINTS_PER_CHUNK) * size_of_point, 0);
+        self.record_buffer
+            .resize(self.fields.iter().map(PcdField::size_in_record).sum(), 0);
+        let mut point_buffer = std::mem::take(&mut self.point_buffer);
+        for chunk_start in (0..points.len()).step_by(POINTS_PER_CHUNK) {
+            let chunk_end = (chunk_start + POINTS_PER_CHUNK).min(points.len());
+            let chunk = &mut point_buffer[..(chunk_end - chunk_start) * size_of_point];
+            points.get_point_range(chunk_start..chunk_end, chunk);
+            for point in chunk.chunks_exact(size_of_point) {
+                self.fill_record(point);
+                if let Err(why) = self.write_record() {
+                    self.point_buffer = point_buffer;
+                    return Err(why).context("Failed to write PCD point record");
+                }
+                self.point_count += 1;
+            }
+        }
+        self.point_buffer = point_buffer;
+        self.requires_flush = true;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if !self.requires_flush {
+            return Ok(());
+        }
+        if self.point_count_positions.is_none() {
+            self.write_header()?;
+        }
+        let end_position = self.writer.stream_position()?;
+        for position in self.point_count_positions.unwrap() {
+            self.writer.seek(SeekFrom::Start(position))?;
+            write!(
+                self.writer,
+                "{:<width$}",
+                self.point_count,
+                width = RESERVED_POINT_COUNT_CHARS
+            )?;
+        }
+        self.writer.seek(SeekFrom::Start(end_position))?;
+        self.writer.flush()?;
+        self.requires_flush = false;
+        Ok(())
+    }
+
+    fn get_default_point_layout(&self) -> &PointLayout {
+        &self.default_layout
+    }
+}
+
+impl<W: Write + Seek> Drop for PcdWriter<W> {
+    fn drop(&mut self) {
+        if let Err(why) = self.flush() {
+            let _ = writeln!(
+                std::io::stderr(),
+                "Error while flushing PcdWriter: {:?}",
+                why
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use pasture_core::{
+        containers::{HashMapBuffer, MakeBufferFromLayout, VectorBuffer},
+        layout::{attributes::CLASSIFICATION, PointAttributeDefinition, PointType},
+        nalgebra::{Quaternion, Vector3},
+    };
+    use pasture_derive::PointType;
+
+    use crate::{base::PointReader, pcd::PcdReader};
+
+    use super::*;
+
+    #[repr(C, packed)]
+    #[derive(
+        Copy, Clone, PartialEq, PointType, Debug, bytemuck::AnyBitPattern, bytemuck::NoUninit,
+    )]
+    struct TestPoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        position: Vector3<f64>,
+        #[pasture(BUILTIN_COLOR_RGB)]
+        color: Vector3<u16>,
+        #[pasture(BUILTIN_INTENSITY)]
+        intensity: u16,
+        #[pasture(BUILTIN_CLASSIFICATION)]
+        classification: u8,
+        #[pasture(BUILTIN_NORMAL)]
+        normal: Vector3<f32>,
+    }
+
+    fn test_points() -> Vec<TestPoint> {
+        vec![
+            TestPoint {
+                position: Vector3::new(1.0, 2.5, -3.0),
+                color: Vector3::new(0xff00, 0x8000, 0x4000),
+                intensity: 42,
+                classification: 2,
+                normal: Vector3::new(0.0, 0.0, 1.0),
+            },
+            TestPoint {
+                position: Vector3::new(1e6, 0.125, 7.0),
+                color: Vector3::new(0, 0x0100, 0xffff),
+                intensity: 65535,
+                classification: 6,
+                normal: Vector3::new(0.5, -0.5, f32::NAN),
+            },
+        ]
+    }
+
+    fn write_test_points(data_format: PcdDataFormat, pack_rgb: bool) -> Result<Vec<u8>> {
+        let points = test_points();
+        let mut writer = PcdWriter::from_write_and_layout(
+            Cursor::new(Vec::new()),
+            TestPoint::layout(),
+            data_format,
+        )?;
+        writer.set_pack_rgb(pack_rgb)?;
+        // Written in two calls, so that the header has to be updated with the number of points of both calls
+        writer.write(&points[..1].iter().copied().collect::<VectorBuffer>())?;
+        writer.write(&points[1..].iter().copied().collect::<HashMapBuffer>())?;
+        writer.flush()?;
+        Ok(writer.writer.get_ref().clone())
+    }
+
+    #[test]
+    fn test_pcd_writer_round_trip() -> Result<()> {
+        for data_format in [PcdDataFormat::Ascii, PcdDataFormat::Binary] {
+            let pcd = write_test_points(data_format, false)?;
+            let mut reader = PcdReader::from_read(Cursor::new(pcd))?;
+            let metadata = reader.pcd_metadata();
+            assert_eq!(2, metadata.width());
+            assert_eq!(1, metadata.height());
+            assert_eq!(2, metadata.points());
+            assert_eq!(
+                vec![
+                    "x",
+                    "y",
+                    "z",
+                    "r",
+                    "g",
+                    "b",
+                    "intensity",
+                    "Classification",
+                    "normal_x",
+                    "normal_y",
+                    "normal_z"
+                ],
+                metadata
+                    .fields()
+                    .iter()
+                    .map(PcdField::name)
+                    .collect::<Vec<_>>()
+            );
+
+            let points = reader.read::<VectorBuffer>(2)?;
+            let expected_points = test_points();
+            assert_eq!(
+                expected_points
+                    .iter()
+                    .map(|p| p.position)
+                    .collect::<Vec<_>>(),
+                points
+                    .view_attribute::<Vector3<f64>>(
+                        &POSITION_3D.with_custom_datatype(PointAttributeDataType::Vec3f64)
+                    )
+                    .into_iter()
+                    .collect::<Vec<_>>()
+            );
+            assert_eq!(
+                expected_points
+                    .iter()
+                    .map(|p| p.intensity)
+                    .collect::<Vec<_>>(),
+                points
+                    .view_attribute::<u16>(&INTENSITY)
+                    .into_iter()
+                    .collect::<Vec<_>>()
+            );
+            assert_eq!(
+                expected_points
+                    .iter()
+                    .map(|p| p.classification)
+                    .collect::<Vec<_>>(),
+                points
+                    .view_attribute::<u8>(&CLASSIFICATION)
+                    .into_iter()
+                    .collect::<Vec<_>>()
+            );
+            assert_eq!(
+                vec![0x4000, 0xffff],
+                points
+                    .view_attribute::<u16>(&PointAttributeDefinition::custom(
+                        "b".into(),
+                        PointAttributeDataType::U16
+                    ))
+                    .into_iter()
+                    .collect::<Vec<_>>()
+            );
+            assert!(points
+                .view_attribute::<f32>(&PointAttributeDefinition::custom(
+                    "normal_z".into(),
+                    PointAttributeDataType::F32
+                ))
+                .at(1)
+                .is_nan());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_pcd_writer_packs_rgb() -> Result<()> {
+        for data_format in [PcdDataFormat::Ascii, PcdDataFormat::Binary] {
+            let pcd = write_test_points(data_format, true)?;
+            let mut reader = PcdReader::from_read(Cursor::new(pcd))?;
+            let points = reader.read::<VectorBuffer>(2)?;
+            assert_eq!(
+                vec![Vector3::new(0xff, 0x80, 0x40), Vector3::new(0, 1, 0xff)],
+                points
+                    .view_attribute::<Vector3<u8>>(
+                        &COLOR_RGB.with_custom_datatype(PointAttributeDataType::Vec3u8)
+                    )
+                    .into_iter()
+                    .collect::<Vec<_>>()
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_pcd_writer_header() -> Result<()> {
+        let mut writer = PcdWriter::from_write_and_layout(
+            Cursor::new(Vec::new()),
+            PointLayout::from_attributes(&[
+                POSITION_3D.with_custom_datatype(PointAttributeDataType::Vec3f32)
+            ]),
+            PcdDataFormat::Ascii,
+        )?;
+        let viewpoint = PcdViewpoint {
+            translation: Vector3::new(1.0, 2.0, 3.0),
+            orientation: Quaternion::new(0.5, 0.5, 0.5, 0.5),
+        };
+        writer.set_viewpoint(viewpoint)?;
+        writer.flush()?;
+        assert!(writer.set_viewpoint(Default::default()).is_err());
+        assert!(writer.set_pack_rgb(true).is_err());
+
+        let pcd = String::from_utf8(writer.writer.get_ref().clone())?;
+        let header_lines = pcd.lines().map(str::trim_end).collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "# .PCD v0.7 - Point Cloud Data file format",
+                "VERSION 0.7",
+                "FIELDS x y z",
+                "SIZE 4 4 4",
+                "TYPE F F F",
+                "COUNT 1 1 1",
+                "WIDTH 0",
+                "HEIGHT 1",
+                "VIEWPOINT 1 2 3 0.5 0.5 0.5 0.5",
+                "POINTS 0",
+                "DATA ascii",
+            ],
+            header_lines
+        );
+
+        let reader = PcdReader::from_read(Cursor::new(pcd.into_bytes()))?;
+        assert_eq!(viewpoint, reader.pcd_metadata().viewpoint());
+        assert_eq!(Some(0), reader.point_count());
+        Ok(())
+    }
+
+    #[test]
+    fn test_pcd_writer_rejects_unsupported_configurations() {
+        assert!(PcdWriter::from_write_and_layout(
+            Cursor::new(Vec::new()),
+            TestPoint::layout(),
+            PcdDataFormat::BinaryCompressed
+        )
+        .is_err());
+
+        let mut writer = PcdWriter::from_write_and_layout(
+            Cursor::new(Vec::new()),
+            TestPoint::layout(),
+            PcdDataFormat::Binary,
+        )
+        .unwrap();
+        let points_with_other_layout =
+            HashMapBuffer::new_from_layout(PointLayout::from_attributes(&[POSITION_3D]));
+        assert!(writer.write(&points_with_other_layout).is_err());
+
+        let float_colors = PointLayout::from_attributes(&[
+            COLOR_RGB.with_custom_datatype(PointAttributeDataType::Vec3f32)
+        ]);
+        let mut writer = PcdWriter::from_write_and_layout(
+            Cursor::new(Vec::new()),
+            float_colors,
+            PcdDataFormat::Binary,
+        )
+        .unwrap();
+        assert!(writer.set_pack_rgb(true).is_err());
+    }
+}
@@ -0,0 +1,8 @@
+mod potree_writer;
+pub use self::potree_writer::*;
+
+mod potree_metadata;
+pub use self::potree_metadata::*;
+
+mod potree_octree;
+pub(crate) use self::potree_octree::*;
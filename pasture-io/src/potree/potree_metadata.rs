@@ -0,0 +1,169 @@
+use pasture_core::layout::{
+    attributes::{
+        CLASSIFICATION, CLASSIFICATION_FLAGS, COLOR_RGB, EDGE_OF_FLIGHT_LINE, GPS_TIME, INTENSITY,
+        NIR, NORMAL, NUMBER_OF_RETURNS, POINT_SOURCE_ID, RETURN_NUMBER, SCANNER_CHANNEL,
+        SCAN_ANGLE, SCAN_ANGLE_RANK, SCAN_DIRECTION_FLAG, USER_DATA,
+    },
+    PointAttributeDataType, PointAttributeDefinition,
+};
+use serde::{Deserialize, Serialize};
+
+/// The Potree version that the `PotreeWriter` writes
+pub const POTREE_VERSION: &str = "2.0";
+
+/// Contents of the `metadata.json` file of a Potree 2.0 octree
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PotreeMetadata {
+    pub version: String,
+    pub name: String,
+    pub description: String,
+    /// Total number of points in the octree
+    pub points: u64,
+    pub projection: String,
+    pub hierarchy: PotreeHierarchyInfo,
+    /// Offset of the integer positions in `octree.bin`
+    pub offset: [f64; 3],
+    /// Scale of the integer positions in `octree.bin`
+    pub scale: [f64; 3],
+    /// Minimum distance between the points of the root node. The spacing halves with each level of the octree
+    pub spacing: f64,
+    /// The cubic bounding box of the root node
+    pub bounding_box: PotreeBoundingBox,
+    pub encoding: String,
+    /// The attributes of each point record in `octree.bin`, in the order in which they are stored
+    pub attributes: Vec<PotreeAttribute>,
+}
+
+/// Describes the `hierarchy.bin` file of a Potree 2.0 octree
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PotreeHierarchyInfo {
+    /// Size in bytes of the first chunk of the hierarchy, which starts with the root node
+    pub first_chunk_size: u64,
+    /// Number of octree levels per hierarchy chunk
+    pub step_size: u32,
+    /// The deepest level of the octree, where the root node is at level 0
+    pub depth: u32,
+}
+
+/// A bounding box in the `metadata.json` file of a Potree 2.0 octree
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PotreeBoundingBox {
+    pub min: [f64; 3],
+    pub max: [f64; 3],
+}
+
+/// Describes a point attribute in the `metadata.json` file of a Potree 2.0 octree
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PotreeAttribute {
+    pub name: String,
+    pub description: String,
+    /// Size of a single value of this attribute in bytes
+    pub size: usize,
+    pub num_elements: usize,
+    pub element_size: usize,
+    /// The type of each element, e.g. `uint16` or `double`
+    #[serde(rename = "type")]
+    pub element_type: String,
+    /// Per-element minimum of the values of this attribute
+    pub min: Vec<f64>,
+    /// Per-element maximum of the values of this attribute
+    pub max: Vec<f64>,
+}
+
+/// Names of the pasture attributes that have a standard name in Potree, together with the datatype that Potree uses
+/// for them
+const STANDARD_POTREE_ATTRIBUTES: &[(&PointAttributeDefinition, &str, PointAttributeDataType)] = &[
+    (&INTENSITY, "intensity", PointAttributeDataType::U16),
+    (&RETURN_NUMBER, "return number", PointAttributeDataType::U8),
+    (
+        &NUMBER_OF_RETURNS,
+        "number of returns",
+        PointAttributeDataType::U8,
+    ),
+    (
+        &CLASSIFICATION_FLAGS,
+        "classification flags",
+        PointAttributeDataType::U8,
+    ),
+    (
+        &SCANNER_CHANNEL,
+        "scanner channel",
+        PointAttributeDataType::U8,
+    ),
+    (
+        &SCAN_DIRECTION_FLAG,
+        "scan direction flag",
+        PointAttributeDataType::U8,
+    ),
+    (
+        &EDGE_OF_FLIGHT_LINE,
+        "edge of flight line",
+        PointAttributeDataType::U8,
+    ),
+    (
+        &CLASSIFICATION,
+        "classification",
+        PointAttributeDataType::U8,
+    ),
+    (&USER_DATA, "user data", PointAttributeDataType::U8),
+    (
+        &SCAN_ANGLE_RANK,
+        "scan angle rank",
+        PointAttributeDataType::I8,
+    ),
+    (&SCAN_ANGLE, "scan angle", PointAttributeDataType::I16),
+    (
+        &POINT_SOURCE_ID,
+        "point source id",
+        PointAttributeDataType::U16,
+    ),
+    (&GPS_TIME, "gps-time", PointAttributeDataType::F64),
+    (&COLOR_RGB, "rgb", PointAttributeDataType::Vec3u16),
+    (&NIR, "nir", PointAttributeDataType::U16),
+    (&NORMAL, "normal", PointAttributeDataType::Vec3f32),
+];
+
+/// Returns the Potree element type, the number of elements and the size of each element for values of the given
+/// `datatype`, or `None` if Potree can't store values of this datatype
+pub(crate) fn potree_element_type(
+    datatype: PointAttributeDataType,
+) -> Option<(&'static str, usize, usize)> {
+    match datatype {
+        PointAttributeDataType::U8 => Some(("uint8", 1, 1)),
+        PointAttributeDataType::I8 => Some(("int8", 1, 1)),
+        PointAttributeDataType::U16 => Some(("uint16", 1, 2)),
+        PointAttributeDataType::I16 => Some(("int16", 1, 2)),
+        PointAttributeDataType::U32 => Some(("uint32", 1, 4)),
+        PointAttributeDataType::I32 => Some(("int32", 1, 4)),
+        PointAttributeDataType::U64 => Some(("uint64", 1, 8)),
+        PointAttributeDataType::I64 => Some(("int64", 1, 8)),
+        PointAttributeDataType::F32 => Some(("float", 1, 4)),
+        PointAttributeDataType::F64 => Some(("double", 1, 8)),
+        PointAttributeDataType::Vec3u8 => Some(("uint8", 3, 1)),
+        PointAttributeDataType::Vec3u16 => Some(("uint16", 3, 2)),
+        PointAttributeDataType::Vec3i32 => Some(("int32", 3, 4)),
+        PointAttributeDataType::Vec3f32 => Some(("float", 3, 4)),
+        PointAttributeDataType::Vec3f64 => Some(("double", 3, 8)),
+        PointAttributeDataType::Vec4u8 => Some(("uint8", 4, 1)),
+        _ => None,
+    }
+}
+
+/// Returns the name and datatype with which the given `attribute` is stored in a Potree octree. Attributes with a
+/// standard name in Potree (e.g. `gps-time` for `GPS_TIME`) are converted to the datatype that Potree uses for them,
+/// all other attributes keep their name and datatype. Returns `None` if Potree can't store the datatype of `attribute`
+pub(crate) fn potree_attribute_name_and_datatype(
+    attribute: &PointAttributeDefinition,
+) -> Option<(String, PointAttributeDataType)> {
+    if let Some((_, name, datatype)) = STANDARD_POTREE_ATTRIBUTES
+        .iter()
+        .find(|(standard_attribute, _, _)| standard_attribute.name() == attribute.name())
+    {
+        return Some(((*name).to_owned(), *datatype));
+    }
+    potree_element_type(attribute.datatype())
+        .map(|_| (attribute.name().to_owned(), attribute.datatype()))
+}
@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+
+use pasture_core::nalgebra::Vector3;
+
+/// The maximum depth of the octree that the `PotreeWriter` builds. Nodes at this depth keep all their points, which
+/// prevents endless subdivision for clusters of identical positions
+pub(crate) const MAX_POTREE_OCTREE_DEPTH: u32 = 20;
+
+/// A node of a Potree octree. Inner nodes store a subsample of their points with a minimum distance of the spacing of
+/// their level, leaf nodes store all their points
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PotreeOctreeNode {
+    /// Indices of the points of this node in the written point cloud, in ascending order
+    pub(crate) point_indices: Vec<usize>,
+    /// The non-empty children of this node together with their child index, in ascending order of the child index.
+    /// Bit 2 of the child index is set for the upper half along the x-axis, bit 1 for y and bit 0 for z
+    pub(crate) children: Vec<(usize, PotreeOctreeNode)>,
+}
+
+impl PotreeOctreeNode {
+    /// Returns the bitmask of the children of this node, with bit `i` set if the child with index `i` exists
+    pub(crate) fn child_mask(&self) -> u8 {
+        self.children
+            .iter()
+            .fold(0, |mask, (child_index, _)| mask | (1 << child_index))
+    }
+
+    /// Returns the depth of the subtree starting at this node, which is 0 for leaf nodes
+    pub(crate) fn depth(&self) -> u32 {
+        self.children
+            .iter()
+            .map(|(_, child)| child.depth() + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the nodes of the subtree starting at this node in breadth-first order, which is the order of the nodes
+    /// in a Potree hierarchy
+    pub(crate) fn nodes_breadth_first(&self) -> Vec<&PotreeOctreeNode> {
+        let mut nodes = vec![self];
+        let mut next_node = 0;
+        while next_node < nodes.len() {
+            let node = nodes[next_node];
+            nodes.extend(node.children.iter().map(|(_, child)| child));
+            next_node += 1;
+        }
+        nodes
+    }
+}
+
+/// Builds an octree over `num_points` points within the cube at `cube_min` with the side length `cube_size`, where
+/// `position_at` returns the position of the point with the given index. It is called once per point and level of
+/// the octree that the point passes through. Each node that has more than `max_points_per_node` points keeps one point
+/// per grid cell of size `spacing / 2^level`, the point closest to the cell center, and passes all other points on to
+/// its children
+pub(crate) fn build_potree_octree<F: Fn(usize) -> Vector3<f64>>(
+    num_points: usize,
+    position_at: &F,
+    cube_min: Vector3<f64>,
+    cube_size: f64,
+    spacing: f64,
+    max_points_per_node: usize,
+) -> PotreeOctreeNode {
+    build_node(
+        (0..num_points).collect(),
+        position_at,
+        cube_min,
+        cube_size,
+        spacing,
+        max_points_per_node,
+        0,
+    )
+}
+
+fn build_node<F: Fn(usize) -> Vector3<f64>>(
+    point_indices: Vec<usize>,
+    position_at: &F,
+    node_min: Vector3<f64>,
+    node_size: f64,
+    spacing: f64,
+    max_points_per_node: usize,
+    level: u32,
+) -> PotreeOctreeNode {
+    if point_indices.len() <= max_points_per_node || level >= MAX_POTREE_OCTREE_DEPTH {
+        return PotreeOctreeNode {
+            point_indices,
+            children: vec![],
+        };
+    }
+
+    let cell_size = spacing / (1u64 << level) as f64;
+    let child_size = node_size / 2.0;
+    let mut closest_points_per_cell: HashMap<[i64; 3], (usize, f64)> = HashMap::new();
+    // Each position is only requested once per node, since `position_at` might have to convert it. The child that a
+    // point is passed on to is determined here as well
+    let mut child_indices = Vec::with_capacity(point_indices.len());
+    for (index_in_node, point_index) in point_indices.iter().enumerate() {
+        let relative_position = position_at(*point_index) - node_min;
+        child_indices.push(
+            (usize::from(relative_position.x >= child_size) << 2)
+                | (usize::from(relative_position.y >= child_size) << 1)
+                | usize::from(relative_position.z >= child_size),
+        );
+        let cell = relative_position.map(|coordinate| (coordinate / cell_size).floor());
+        let cell_center = (cell + Vector3::repeat(0.5)) * cell_size;
+        let distance_to_center = (relative_position - cell_center).norm_squared();
+        let cell_key = [cell.x as i64, cell.y as i64, cell.z as i64];
+        let closest_point = closest_points_per_cell
+            .entry(cell_key)
+            .or_insert((index_in_node, distance_to_center));
+        if distance_to_center < closest_point.1 {
+            *closest_point = (index_in_node, distance_to_center);
+        }
+    }
+
+    let mut is_sampled = vec![false; point_indices.len()];
+    for (index_in_node, _) in closest_points_per_cell.values() {
+        is_sampled[*index_in_node] = true;
+    }
+
+    let mut sampled_points = vec![];
+    let mut child_points: Vec<Vec<usize>> = vec![vec![]; 8];
+    for ((point_index, is_sampled), child_index) in
+        point_indices.into_iter().zip(is_sampled).zip(child_indices)
+    {
+        if is_sampled {
+            sampled_points.push(point_index);
+        } else {
+            child_points[child_index].push(point_index);
+        }
+    }
+
+    let children = child_points
+        .into_iter()
+        .enumerate()
+        .filter(|(_, points)| !points.is_empty())
+        .map(|(child_index, points)| {
+            let child_min = node_min
+                + Vector3::new(
+                    ((child_index >> 2) & 1) as f64,
+                    ((child_index >> 1) & 1) as f64,
+                    (child_index & 1) as f64,
+                ) * child_size;
+            let child = build_node(
+                points,
+                position_at,
+                child_min,
+                child_size,
+                spacing,
+                max_points_per_node,
+                level + 1,
+            );
+            (child_index, child)
+        })
+        .collect();
+
+    PotreeOctreeNode {
+        point_indices: sampled_points,
+        children,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_potree_octree() {
+        // A 10x10x10 grid with a spacing of 1, so that the root node with a spacing of 5 keeps 8 points
+        let positions = (0..1000)
+            .map(|index| {
+                Vector3::new(
+                    (index % 10) as f64 + 0.5,
+                    ((index / 10) % 10) as f64 + 0.5,
+                    (index / 100) as f64 + 0.5,
+                )
+            })
+            .collect::<Vec<_>>();
+        let root = build_potree_octree(
+            positions.len(),
+            &|index| positions[index],
+            Vector3::zeros(),
+            10.0,
+            5.0,
+            100,
+        );
+
+        assert_eq!(8, root.point_indices.len());
+        assert_eq!(0xff, root.child_mask());
+        assert!(root.depth() >= 1);
+
+        // Every point is stored in exactly one node
+        let mut all_points = root
+            .nodes_breadth_first()
+            .iter()
+            .flat_map(|node| node.point_indices.iter().copied())
+            .collect::<Vec<_>>();
+        all_points.sort_unstable();
+        assert_eq!((0..1000).collect::<Vec<_>>(), all_points);
+
+        // Points of a child lie within the child's octant
+        for (child_index, child) in root.children.iter() {
+            for point_index in child.point_indices.iter() {
+                let position = positions[*point_index];
+                assert_eq!((child_index >> 2) & 1 == 1, position.x >= 5.0);
+                assert_eq!((child_index >> 1) & 1 == 1, position.y >= 5.0);
+                assert_eq!(child_index & 1 == 1, position.z >= 5.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_build_potree_octree_with_identical_positions() {
+        let positions = vec![Vector3::new(1.0, 1.0, 1.0); 50];
+        let root = build_potree_octree(
+            positions.len(),
+            &|index| positions[index],
+            Vector3::zeros(),
+            2.0,
+            0.1,
+            10,
+        );
+        // Identical positions can't be separated, so subdivision stops at the maximum depth
+        assert!(root.depth() <= MAX_POTREE_OCTREE_DEPTH);
+        let num_points: usize = root
+            .nodes_breadth_first()
+            .iter()
+            .map(|node| node.point_indices.len())
+            .sum();
+        assert_eq!(50, num_points);
+    }
+}
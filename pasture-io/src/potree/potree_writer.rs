@@ -0,0 +1,754 @@
+use std::{
+    convert::TryInto,
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use pasture_core::{
+    containers::{
+        BorrowedBuffer, InterleavedBuffer, MakeBufferFromLayout, OwningBuffer, VectorBuffer,
+    },
+    layout::{
+        attributes::POSITION_3D,
+        conversion::{get_converter_for_attributes, AttributeConversionFn},
+        PointAttributeDataType, PointLayout,
+    },
+    nalgebra::Vector3,
+};
+
+use crate::base::PointWriter;
+
+use super::{
+    build_potree_octree, potree_attribute_name_and_datatype, potree_element_type, PotreeAttribute,
+    PotreeBoundingBox, PotreeHierarchyInfo, PotreeMetadata, POTREE_VERSION,
+};
+
+/// Default maximum number of points in a leaf node of the octrees that a [`PotreeWriter`] writes
+pub const DEFAULT_POTREE_MAX_POINTS_PER_NODE: usize = 20_000;
+/// Default scale of the integer positions that a [`PotreeWriter`] writes, which stores positions with millimeter
+/// precision
+pub const DEFAULT_POTREE_SCALE: f64 = 0.001;
+/// Unless set explicitly, the spacing of the root node is the side length of its bounding cube divided by this value,
+/// like in PotreeConverter
+const POTREE_SPACING_DIVISOR: f64 = 128.0;
+/// Size of a node entry in the `hierarchy.bin` file
+const POTREE_HIERARCHY_NODE_SIZE: usize = 22;
+/// Node type of inner nodes in the `hierarchy.bin` file
+const POTREE_NODE_TYPE_INNER: u8 = 0;
+/// Node type of leaf nodes in the `hierarchy.bin` file
+const POTREE_NODE_TYPE_LEAF: u8 = 1;
+
+/// An attribute of the written points that is stored in the point records of the octree, together with the conversion
+/// into the datatype that it is stored with
+struct PotreeOutputAttribute {
+    offset_in_point: usize,
+    source_size: usize,
+    target_datatype: PointAttributeDataType,
+    converter: Option<AttributeConversionFn>,
+    potree_attribute: PotreeAttribute,
+}
+
+/// Reads the elements of the value in `bytes`, which has the given `datatype`, as `f64` values
+fn elements_as_f64(bytes: &[u8], datatype: PointAttributeDataType) -> Vec<f64> {
+    let (element_type, num_elements, element_size) =
+        potree_element_type(datatype).expect("Unsupported datatype for Potree attribute");
+    (0..num_elements)
+        .map(|element| {
+            let element_bytes = &bytes[element * element_size..(element + 1) * element_size];
+            match element_type {
+                "uint8" => element_bytes[0] as f64,
+                "int8" => element_bytes[0] as i8 as f64,
+                "uint16" => u16::from_ne_bytes(element_bytes.try_into().unwrap()) as f64,
+                "int16" => i16::from_ne_bytes(element_bytes.try_into().unwrap()) as f64,
+                "uint32" => u32::from_ne_bytes(element_bytes.try_into().unwrap()) as f64,
+                "int32" => i32::from_ne_bytes(element_bytes.try_into().unwrap()) as f64,
+                "uint64" => u64::from_ne_bytes(element_bytes.try_into().unwrap()) as f64,
+                "int64" => i64::from_ne_bytes(element_bytes.try_into().unwrap()) as f64,
+                "float" => f32::from_ne_bytes(element_bytes.try_into().unwrap()) as f64,
+                "double" => f64::from_ne_bytes(element_bytes.try_into().unwrap()),
+                other => unreachable!("Unknown Potree element type {}", other),
+            }
+        })
+        .collect()
+}
+
+/// Writer for point clouds in the [Potree 2.0](https://github.com/potree/potree) format, which the Potree viewer can
+/// display directly. A Potree 2.0 point cloud is a directory with three files: `metadata.json` describes the point
+/// cloud, `hierarchy.bin` the nodes of its octree and `octree.bin` stores the points of all nodes.
+///
+/// Inner nodes of the octree store a subsample of their points, where the points have a minimum distance of the
+/// spacing of their level. The spacing halves with each level. Nodes with at most the maximum number of points per node
+/// store all their points and become leaf nodes. Positions are stored as `i32` values with the scale of the writer,
+/// relative to the minimum of the bounds of all points. The standard attributes of Potree are stored with their
+/// Potree name and datatype (e.g. `COLOR_RGB` as 16-bit `rgb`), all other attributes with their own name and datatype.
+/// Attributes with datatypes that Potree can't store (byte arrays and custom types) are ignored.
+///
+/// The octree can only be built once all points are known, so the `PotreeWriter` keeps all written points in memory
+/// and writes the octree during `flush`. After that, no more points can be written. Use a [`PotreeWriterBuilder`] to
+/// configure the spacing, the maximum number of points per node, the scale and a limit for the number of points that
+/// are kept in memory
+pub struct PotreeWriter {
+    directory: PathBuf,
+    name: String,
+    default_layout: PointLayout,
+    cached_points: VectorBuffer,
+    max_points: Option<usize>,
+    output_attributes: Vec<PotreeOutputAttribute>,
+    scale: f64,
+    spacing: Option<f64>,
+    max_points_per_node: usize,
+    requires_flush: bool,
+}
+
+impl PotreeWriter {
+    /// Creates a new `PotreeWriter` that writes the octree into the given `directory` with the default settings. The
+    /// `directory` is created if it does not exist
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `point_layout` has no `POSITION_3D` attribute or if `directory` can't be created
+    pub fn from_directory_and_layout<P: AsRef<Path>>(
+        directory: P,
+        point_layout: PointLayout,
+    ) -> Result<Self> {
+        PotreeWriterBuilder::new().build(directory, point_layout)
+    }
+
+    /// Returns the directory that the octree is written to
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    fn make_output_attributes(point_layout: &PointLayout) -> Vec<PotreeOutputAttribute> {
+        point_layout
+            .attributes()
+            .filter(|attribute| attribute.name() != POSITION_3D.name())
+            .filter_map(|attribute| {
+                let (name, target_datatype) =
+                    potree_attribute_name_and_datatype(attribute.attribute_definition())?;
+                let (element_type, num_elements, element_size) =
+                    potree_element_type(target_datatype)?;
+                let converter = get_converter_for_attributes(
+                    attribute.attribute_definition(),
+                    &attribute
+                        .attribute_definition()
+                        .with_custom_datatype(target_datatype),
+                );
+                Some(PotreeOutputAttribute {
+                    offset_in_point: attribute.offset() as usize,
+                    source_size: attribute.size() as usize,
+                    target_datatype,
+                    converter,
+                    potree_attribute: PotreeAttribute {
+                        name,
+                        description: String::new(),
+                        size: num_elements * element_size,
+                        num_elements,
+                        element_size,
+                        element_type: element_type.to_owned(),
+                        min: vec![f64::INFINITY; num_elements],
+                        max: vec![f64::NEG_INFINITY; num_elements],
+                    },
+                })
+            })
+            .collect()
+    }
+
+    /// Builds the octree over all cached points and writes the `octree.bin`, `hierarchy.bin` and `metadata.json` files.
+    /// Positions are not copied, but converted to `Vector3<f64>` whenever they are needed: once for the bounds, once
+    /// for each level of the octree that a point passes through while building it and once for encoding the point
+    fn write_octree(&mut self) -> Result<()> {
+        let num_points = self.cached_points.len();
+        let positions = self
+            .cached_points
+            .view_attribute_with_conversion::<Vector3<f64>>(&POSITION_3D)?;
+        let (min, max) = (0..num_points).map(|index| positions.at(index)).fold(
+            (
+                Vector3::repeat(f64::INFINITY),
+                Vector3::repeat(f64::NEG_INFINITY),
+            ),
+            |(min, max), position| (min.inf(&position), max.sup(&position)),
+        );
+        let (min, max) = if num_points == 0 {
+            (Vector3::zeros(), Vector3::zeros())
+        } else {
+            (min, max)
+        };
+        let extent = max - min;
+        if extent.max() / self.scale > i32::MAX as f64 {
+            bail!(
+                "The extent {} of the points is too large to store the positions as 32-bit integers with a scale of {}",
+                extent.max(),
+                self.scale
+            );
+        }
+        let cube_size = if extent.max() > 0.0 {
+            extent.max()
+        } else {
+            1.0
+        };
+        let spacing = self.spacing.unwrap_or(cube_size / POTREE_SPACING_DIVISOR);
+        let root = build_potree_octree(
+            num_points,
+            &|index| positions.at(index),
+            min,
+            cube_size,
+            spacing,
+            self.max_points_per_node,
+        );
+        let nodes = root.nodes_breadth_first();
+
+        let size_of_record = 12
+            + self
+                .output_attributes
+                .iter()
+                .map(|attribute| attribute.potree_attribute.size)
+                .sum::<usize>();
+        let mut octree_writer = BufWriter::new(
+            File::create(self.directory.join("octree.bin"))
+                .context("Could not create octree.bin")?,
+        );
+        let mut hierarchy = Vec::with_capacity(nodes.len() * POTREE_HIERARCHY_NODE_SIZE);
+        let mut byte_offset = 0_u64;
+        let mut records = vec![];
+        let mut converted_value = vec![];
+        for node in nodes.iter() {
+            records.clear();
+            records.reserve(node.point_indices.len() * size_of_record);
+            for point_index in node.point_indices.iter() {
+                let quantized_position = (positions.at(*point_index) - min) / self.scale;
+                for coordinate in quantized_position.iter() {
+                    records.extend_from_slice(&(coordinate.round() as i32).to_le_bytes());
+                }
+
+                let point = self.cached_points.get_point_ref(*point_index);
+                for attribute in self.output_attributes.iter_mut() {
+                    let source_value = &point[attribute.offset_in_point
+                        ..attribute.offset_in_point + attribute.source_size];
+                    let value = match attribute.converter {
+                        Some(converter) => {
+                            converted_value.resize(attribute.potree_attribute.size, 0);
+                            unsafe {
+                                converter(source_value, &mut converted_value);
+                            }
+                            &converted_value[..]
+                        }
+                        None => source_value,
+                    };
+                    records.extend_from_slice(value);
+
+                    let potree_attribute = &mut attribute.potree_attribute;
+                    for (element, element_value) in
+                        elements_as_f64(value, attribute.target_datatype)
+                            .into_iter()
+                            .enumerate()
+                    {
+                        potree_attribute.min[element] =
+                            potree_attribute.min[element].min(element_value);
+                        potree_attribute.max[element] =
+                            potree_attribute.max[element].max(element_value);
+                    }
+                }
+            }
+            octree_writer
+                .write_all(&records)
+                .context("Could not write points to octree.bin")?;
+
+            let node_type = if node.children.is_empty() {
+                POTREE_NODE_TYPE_LEAF
+            } else {
+                POTREE_NODE_TYPE_INNER
+            };
+            let num_points: u32 = node
+                .point_indices
+                .len()
+                .try_into()
+                .context("Too many points in a single node")?;
+            hierarchy.push(node_type);
+            hierarchy.push(node.child_mask());
+            hierarchy.extend_from_slice(&num_points.to_le_bytes());
+            hierarchy.extend_from_slice(&byte_offset.to_le_bytes());
+            hierarchy.extend_from_slice(&(records.len() as u64).to_le_bytes());
+            byte_offset += records.len() as u64;
+        }
+        octree_writer
+            .flush()
+            .context("Could not write points to octree.bin")?;
+
+        std::fs::write(self.directory.join("hierarchy.bin"), &hierarchy)
+            .context("Could not write hierarchy.bin")?;
+
+        let mut attributes = vec![PotreeAttribute {
+            name: "position".into(),
+            description: String::new(),
+            size: 12,
+            num_elements: 3,
+            element_size: 4,
+            element_type: "int32".into(),
+            min: min.iter().copied().collect(),
+            max: max.iter().copied().collect(),
+        }];
+        attributes.extend(self.output_attributes.iter().map(|attribute| {
+            let mut potree_attribute = attribute.potree_attribute.clone();
+            // Attributes of an empty point cloud have no range
+            if num_points == 0 {
+                potree_attribute.min.iter_mut().for_each(|min| *min = 0.0);
+                potree_attribute.max.iter_mut().for_each(|max| *max = 0.0);
+            }
+            potree_attribute
+        }));
+        let metadata = PotreeMetadata {
+            version: POTREE_VERSION.into(),
+            name: self.name.clone(),
+            description: String::new(),
+            points: num_points as u64,
+            projection: String::new(),
+            hierarchy: PotreeHierarchyInfo {
+                first_chunk_size: hierarchy.len() as u64,
+                step_size: 4,
+                depth: root.depth(),
+            },
+            offset: [min.x, min.y, min.z],
+            scale: [self.scale; 3],
+            spacing,
+            bounding_box: PotreeBoundingBox {
+                min: [min.x, min.y, min.z],
+                max: [min.x + cube_size, min.y + cube_size, min.z + cube_size],
+            },
+            encoding: "DEFAULT".into(),
+            attributes,
+        };
+        let metadata_writer = BufWriter::new(
+            File::create(self.directory.join("metadata.json"))
+                .context("Could not create metadata.json")?,
+        );
+        serde_json::to_writer_pretty(metadata_writer, &metadata)
+            .context("Could not write metadata.json")?;
+
+        Ok(())
+    }
+}
+
+impl PointWriter for PotreeWriter {
+    fn write<'a, B: BorrowedBuffer<'a>>(&mut self, points: &'a B) -> Result<()> {
+        if !self.requires_flush {
+            bail!("The Potree octree has already been written, no more points can be added");
+        }
+        if *points.point_layout() != self.default_layout {
+            bail!("PointLayout of the points does not match the PointLayout of the PotreeWriter");
+        }
+        if let Some(max_points) = self.max_points {
+            if self.cached_points.len() + points.len() > max_points {
+                bail!(
+                    "Writing {} more points exceeds the limit of {} points that the PotreeWriter keeps in memory",
+                    points.len(),
+                    max_points
+                );
+            }
+        }
+        self.cached_points.append(points);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if !self.requires_flush {
+            return Ok(());
+        }
+        self.write_octree()?;
+        self.requires_flush = false;
+        // The octree is written, so the cached points are not needed anymore
+        self.cached_points.clear();
+        Ok(())
+    }
+
+    fn get_default_point_layout(&self) -> &PointLayout {
+        &self.default_layout
+    }
+}
+
+impl Drop for PotreeWriter {
+    fn drop(&mut self) {
+        if let Err(why) = self.flush() {
+            let _ = writeln!(
+                std::io::stderr(),
+                "Error while flushing PotreeWriter: {:?}",
+                why
+            );
+        }
+    }
+}
+
+/// Builder for a [`PotreeWriter`] with custom quality settings
+///
+/// ```no_run
+/// # use anyhow::Result;
+/// # use pasture_core::layout::{attributes::POSITION_3D, PointLayout};
+/// # use pasture_io::potree::PotreeWriterBuilder;
+/// # fn main() -> Result<()> {
+/// let writer = PotreeWriterBuilder::new()
+///     .spacing(1.0)
+///     .max_points_per_node(10_000)
+///     .build("pointclouds/scan", PointLayout::from_attributes(&[POSITION_3D]))?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct PotreeWriterBuilder {
+    name: Option<String>,
+    scale: f64,
+    spacing: Option<f64>,
+    max_points_per_node: usize,
+    max_points: Option<usize>,
+}
+
+impl Default for PotreeWriterBuilder {
+    fn default() -> Self {
+        Self {
+            name: None,
+            scale: DEFAULT_POTREE_SCALE,
+            spacing: None,
+            max_points_per_node: DEFAULT_POTREE_MAX_POINTS_PER_NODE,
+            max_points: None,
+        }
+    }
+}
+
+impl PotreeWriterBuilder {
+    /// Creates a new `PotreeWriterBuilder` with the default settings
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the name of the point cloud in `metadata.json`. Defaults to the name of the output directory
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = Some(name.to_owned());
+        self
+    }
+
+    /// Sets the scale of the integer positions. Defaults to [`DEFAULT_POTREE_SCALE`]
+    pub fn scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Sets the minimum distance between the points of the root node. Smaller values keep more points in the upper
+    /// levels of the octree. Defaults to the side length of the bounding cube of all points divided by 128
+    pub fn spacing(mut self, spacing: f64) -> Self {
+        self.spacing = Some(spacing);
+        self
+    }
+
+    /// Sets the maximum number of points of a node before it is split into child nodes. Defaults to
+    /// [`DEFAULT_POTREE_MAX_POINTS_PER_NODE`]
+    pub fn max_points_per_node(mut self, max_points_per_node: usize) -> Self {
+        self.max_points_per_node = max_points_per_node;
+        self
+    }
+
+    /// Sets the maximum number of points that the `PotreeWriter` keeps in memory until the octree is written. Writing
+    /// more points fails with an error and leaves the points that were written before untouched. Defaults to no limit
+    pub fn max_points(mut self, max_points: usize) -> Self {
+        self.max_points = Some(max_points);
+        self
+    }
+
+    /// Creates a `PotreeWriter` that writes the octree into the given `directory`, which is created if it does not
+    /// exist
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `point_layout` has no `POSITION_3D` attribute or if its positions can't be converted to
+    /// `Vector3<f64>`, if the scale or spacing are not positive, or if `directory` can't be created
+    pub fn build<P: AsRef<Path>>(
+        &self,
+        directory: P,
+        point_layout: PointLayout,
+    ) -> Result<PotreeWriter> {
+        if !point_layout.has_attribute_with_name(POSITION_3D.name()) {
+            bail!("The PointLayout of a PotreeWriter must have a POSITION_3D attribute");
+        }
+        let cached_points = VectorBuffer::new_from_layout(point_layout.clone());
+        // Positions are converted while the octree is written, so unsupported datatypes are rejected up front
+        cached_points
+            .view_attribute_with_conversion::<Vector3<f64>>(&POSITION_3D)
+            .context("Unsupported datatype of the POSITION_3D attribute")?;
+        if self.scale <= 0.0 || self.scale.is_nan() {
+            bail!(
+                "The scale of a PotreeWriter must be positive, but is {}",
+                self.scale
+            );
+        }
+        if let Some(spacing) = self.spacing {
+            if spacing <= 0.0 || spacing.is_nan() {
+                bail!(
+                    "The spacing of a PotreeWriter must be positive, but is {}",
+                    spacing
+                );
+            }
+        }
+        let directory = directory.as_ref().to_owned();
+        std::fs::create_dir_all(&directory).with_context(|| {
+            format!("Could not create output directory {}", directory.display())
+        })?;
+        let name = match &self.name {
+            Some(name) => name.clone(),
+            None => directory
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        };
+
+        Ok(PotreeWriter {
+            directory,
+            name,
+            output_attributes: PotreeWriter::make_output_attributes(&point_layout),
+            cached_points,
+            max_points: self.max_points,
+            default_layout: point_layout,
+            scale: self.scale,
+            spacing: self.spacing,
+            max_points_per_node: self.max_points_per_node.max(1),
+            requires_flush: true,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Cow, io::Read};
+
+    use pasture_core::layout::{
+        attributes::{CLASSIFICATION, COLOR_RGB, INTENSITY},
+        PointAttributeDefinition, PointType,
+    };
+    use pasture_derive::PointType;
+    use scopeguard::defer;
+
+    use super::*;
+
+    #[derive(
+        Debug, PointType, Copy, Clone, PartialEq, bytemuck::AnyBitPattern, bytemuck::NoUninit,
+    )]
+    #[repr(C, packed)]
+    struct PotreeTestPoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        position: Vector3<f64>,
+        #[pasture(BUILTIN_INTENSITY)]
+        intensity: u16,
+        #[pasture(BUILTIN_COLOR_RGB)]
+        color: Vector3<u8>,
+        #[pasture(BUILTIN_CLASSIFICATION)]
+        classification: u8,
+    }
+
+    fn test_points() -> Vec<PotreeTestPoint> {
+        (0..4000)
+            .map(|index| PotreeTestPoint {
+                position: Vector3::new(
+                    1000.0 + (index % 20) as f64 * 0.5,
+                    2000.0 + ((index / 20) % 20) as f64 * 0.5,
+                    10.0 + (index / 400) as f64 * 0.25,
+                ),
+                intensity: (index % 1000) as u16,
+                color: Vector3::new((index % 256) as u8, 128, 255),
+                classification: (index % 7) as u8,
+            })
+            .collect()
+    }
+
+    fn read_file(path: &Path) -> Result<Vec<u8>> {
+        let mut data = vec![];
+        File::open(path)?.read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    #[test]
+    fn test_write_potree_octree() -> Result<()> {
+        let out_dir = std::env::temp_dir().join("pasture_test_potree_writer");
+        defer! {
+            let _ = std::fs::remove_dir_all(&out_dir);
+        }
+        let points = test_points();
+        {
+            let mut writer = PotreeWriterBuilder::new()
+                .spacing(2.0)
+                .max_points_per_node(500)
+                .build(&out_dir, PotreeTestPoint::layout())?;
+            for chunk in points.chunks(1000) {
+                writer.write(&chunk.iter().copied().collect::<VectorBuffer>())?;
+            }
+            writer.flush()?;
+            assert!(writer
+                .write(&points.iter().copied().collect::<VectorBuffer>())
+                .is_err());
+        }
+
+        let metadata: PotreeMetadata =
+            serde_json::from_slice(&read_file(&out_dir.join("metadata.json"))?)?;
+        assert_eq!("2.0", metadata.version);
+        assert_eq!("pasture_test_potree_writer", metadata.name);
+        assert_eq!(points.len() as u64, metadata.points);
+        assert_eq!([1000.0, 2000.0, 10.0], metadata.offset);
+        assert_eq!([1000.0, 2000.0, 10.0], metadata.bounding_box.min);
+        assert_eq!([1009.5, 2009.5, 19.5], metadata.bounding_box.max);
+        assert_eq!(2.0, metadata.spacing);
+        assert_eq!(
+            vec!["position", "intensity", "rgb", "classification"],
+            metadata
+                .attributes
+                .iter()
+                .map(|attribute| attribute.name.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!("uint16", metadata.attributes[2].element_type);
+        assert_eq!(vec![0.0, 128.0, 255.0], metadata.attributes[2].min);
+        assert_eq!(vec![255.0, 128.0, 255.0], metadata.attributes[2].max);
+        assert_eq!(vec![999.0], metadata.attributes[1].max);
+
+        let hierarchy = read_file(&out_dir.join("hierarchy.bin"))?;
+        let octree = read_file(&out_dir.join("octree.bin"))?;
+        assert_eq!(metadata.hierarchy.first_chunk_size, hierarchy.len() as u64);
+        assert_eq!(0, hierarchy.len() % POTREE_HIERARCHY_NODE_SIZE);
+        assert!(metadata.hierarchy.depth >= 1);
+
+        // Decode all points of all nodes. The nodes are stored breadth-first, and the byte ranges of the nodes cover
+        // octree.bin without gaps
+        let size_of_record = 12 + 2 + 6 + 1;
+        let mut expected_byte_offset = 0;
+        let mut decoded_points = vec![];
+        for node in hierarchy.chunks_exact(POTREE_HIERARCHY_NODE_SIZE) {
+            let node_type = node[0];
+            let child_mask = node[1];
+            assert_eq!(node_type == POTREE_NODE_TYPE_LEAF, child_mask == 0);
+            let num_points = u32::from_le_bytes(node[2..6].try_into()?) as usize;
+            let byte_offset = u64::from_le_bytes(node[6..14].try_into()?) as usize;
+            let byte_size = u64::from_le_bytes(node[14..22].try_into()?) as usize;
+            assert_eq!(expected_byte_offset, byte_offset);
+            assert_eq!(num_points * size_of_record, byte_size);
+            if node_type == POTREE_NODE_TYPE_LEAF {
+                assert!(num_points <= 500);
+            }
+            expected_byte_offset += byte_size;
+
+            for record in octree[byte_offset..byte_offset + byte_size].chunks_exact(size_of_record)
+            {
+                let position = Vector3::new(
+                    i32::from_le_bytes(record[0..4].try_into()?) as f64 * 0.001 + 1000.0,
+                    i32::from_le_bytes(record[4..8].try_into()?) as f64 * 0.001 + 2000.0,
+                    i32::from_le_bytes(record[8..12].try_into()?) as f64 * 0.001 + 10.0,
+                );
+                let intensity = u16::from_le_bytes(record[12..14].try_into()?);
+                let red = u16::from_le_bytes(record[14..16].try_into()?);
+                let classification = record[20];
+                decoded_points.push((position, intensity, red, classification));
+            }
+        }
+        assert_eq!(octree.len(), expected_byte_offset);
+
+        let mut expected_points = points
+            .iter()
+            .map(|point| {
+                let point = *point;
+                (
+                    point.position,
+                    point.intensity,
+                    point.color.x as u16,
+                    point.classification,
+                )
+            })
+            .collect::<Vec<_>>();
+        let sort_key = |point: &(Vector3<f64>, u16, u16, u8)| {
+            (
+                (point.0.x * 1000.0).round() as i64,
+                (point.0.y * 1000.0).round() as i64,
+                (point.0.z * 1000.0).round() as i64,
+            )
+        };
+        expected_points.sort_by_key(sort_key);
+        decoded_points.sort_by_key(sort_key);
+        assert_eq!(expected_points.len(), decoded_points.len());
+        for (expected, actual) in expected_points.iter().zip(decoded_points.iter()) {
+            assert!((expected.0 - actual.0).norm() < 1e-6);
+            assert_eq!(
+                (expected.1, expected.2, expected.3),
+                (actual.1, actual.2, actual.3)
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_potree_writer_errors() {
+        let out_dir = std::env::temp_dir().join("pasture_test_potree_writer_errors");
+        defer! {
+            let _ = std::fs::remove_dir_all(&out_dir);
+        }
+        assert!(PotreeWriter::from_directory_and_layout(
+            &out_dir,
+            PointLayout::from_attributes(&[INTENSITY])
+        )
+        .is_err());
+        assert!(PotreeWriterBuilder::new()
+            .scale(0.0)
+            .build(&out_dir, PotreeTestPoint::layout())
+            .is_err());
+
+        let mut writer =
+            PotreeWriter::from_directory_and_layout(&out_dir, PotreeTestPoint::layout()).unwrap();
+        let other_layout = PointLayout::from_attributes(&[POSITION_3D, CLASSIFICATION, COLOR_RGB]);
+        assert!(writer
+            .write(&VectorBuffer::new_from_layout(other_layout))
+            .is_err());
+    }
+
+    #[test]
+    fn test_potree_writer_max_points() -> Result<()> {
+        let out_dir = std::env::temp_dir().join("pasture_test_potree_writer_max_points");
+        defer! {
+            let _ = std::fs::remove_dir_all(&out_dir);
+        }
+        let points = test_points();
+        {
+            let mut writer = PotreeWriterBuilder::new()
+                .max_points(2500)
+                .build(&out_dir, PotreeTestPoint::layout())?;
+            for chunk in points.chunks(1000).take(2) {
+                writer.write(&chunk.iter().copied().collect::<VectorBuffer>())?;
+            }
+            // The third chunk exceeds the limit, so none of its points are kept
+            assert!(writer
+                .write(&points[2000..3000].iter().copied().collect::<VectorBuffer>())
+                .is_err());
+            writer.write(&points[2000..2500].iter().copied().collect::<VectorBuffer>())?;
+            writer.flush()?;
+        }
+
+        let metadata: PotreeMetadata =
+            serde_json::from_slice(&read_file(&out_dir.join("metadata.json"))?)?;
+        assert_eq!(2500, metadata.points);
+        Ok(())
+    }
+
+    #[test]
+    fn test_potree_attribute_names() {
+        let (name, datatype) = potree_attribute_name_and_datatype(&COLOR_RGB).unwrap();
+        assert_eq!("rgb", name);
+        assert_eq!(PointAttributeDataType::Vec3u16, datatype);
+
+        let custom = PointAttributeDefinition::custom(
+            Cow::Borrowed("Reflectance"),
+            PointAttributeDataType::F32,
+        );
+        let (name, datatype) = potree_attribute_name_and_datatype(&custom).unwrap();
+        assert_eq!("Reflectance", name);
+        assert_eq!(PointAttributeDataType::F32, datatype);
+
+        let bytes = PointAttributeDefinition::custom(
+            Cow::Borrowed("Bytes"),
+            PointAttributeDataType::ByteArray(4),
+        );
+        assert!(potree_attribute_name_and_datatype(&bytes).is_none());
+    }
+}
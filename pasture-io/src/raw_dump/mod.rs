@@ -0,0 +1,8 @@
+mod raw_dump_writer;
+pub use self::raw_dump_writer::*;
+
+mod raw_dump_reader;
+pub use self::raw_dump_reader::*;
+
+mod raw_dump_metadata;
+pub use self::raw_dump_metadata::*;
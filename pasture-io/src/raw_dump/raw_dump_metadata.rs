@@ -0,0 +1,145 @@
+use std::{
+    any::Any,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use pasture_core::{layout::PointLayout, math::AABB, meta::Metadata};
+use serde::{Deserialize, Serialize};
+
+/// Version of the JSON sidecar of raw dump files that this version of pasture writes
+pub const RAW_DUMP_VERSION: u32 = 1;
+
+/// Returns the path of the JSON sidecar that belongs to the raw dump file at `path`, which is `path` with an additional
+/// `.json` extension (e.g. `points.bin.json` for `points.bin`)
+pub fn raw_dump_sidecar_path<P: AsRef<Path>>(path: P) -> PathBuf {
+    let mut sidecar_path = path.as_ref().as_os_str().to_owned();
+    sidecar_path.push(".json");
+    sidecar_path.into()
+}
+
+/// `Metadata` of a raw dump file, which is stored in its JSON sidecar. The sidecar describes the exact binary layout of
+/// the points in the raw dump file through the serialized `PointLayout`, which includes the name, datatype and offset
+/// of each attribute
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RawDumpMetadata {
+    version: u32,
+    point_layout: PointLayout,
+    number_of_points: usize,
+    bounds: Option<AABB<f64>>,
+    little_endian: bool,
+}
+
+impl RawDumpMetadata {
+    pub(crate) fn new(point_layout: PointLayout) -> Self {
+        Self {
+            version: RAW_DUMP_VERSION,
+            point_layout,
+            number_of_points: 0,
+            bounds: None,
+            little_endian: cfg!(target_endian = "little"),
+        }
+    }
+
+    /// Reads the `RawDumpMetadata` from the JSON sidecar at `sidecar_path`
+    ///
+    /// # Errors
+    ///
+    /// If the sidecar can't be read or parsed, or if it describes a raw dump file that this version of pasture can't
+    /// read (newer version or different endianness)
+    pub fn from_sidecar_path<P: AsRef<Path>>(sidecar_path: P) -> Result<Self> {
+        let sidecar = std::fs::read(sidecar_path.as_ref()).with_context(|| {
+            format!(
+                "Could not read raw dump sidecar {}",
+                sidecar_path.as_ref().display()
+            )
+        })?;
+        let metadata: Self =
+            serde_json::from_slice(&sidecar).context("Could not parse raw dump sidecar")?;
+        if metadata.version > RAW_DUMP_VERSION {
+            bail!(
+                "Raw dump sidecar has version {}, but only versions up to {} are supported",
+                metadata.version,
+                RAW_DUMP_VERSION
+            );
+        }
+        if metadata.little_endian != cfg!(target_endian = "little") {
+            bail!("Raw dump file was written on a machine with a different endianness");
+        }
+        Ok(metadata)
+    }
+
+    /// Writes this `RawDumpMetadata` as JSON to the sidecar at `sidecar_path`
+    pub(crate) fn write_to_sidecar_path(&self, sidecar_path: &Path) -> Result<()> {
+        let sidecar =
+            serde_json::to_vec_pretty(self).context("Could not serialize raw dump sidecar")?;
+        std::fs::write(sidecar_path, sidecar).with_context(|| {
+            format!(
+                "Could not write raw dump sidecar {}",
+                sidecar_path.display()
+            )
+        })
+    }
+
+    /// Adds `count` points with the given `bounds` to this `RawDumpMetadata`
+    pub(crate) fn add_points(&mut self, count: usize, bounds: Option<AABB<f64>>) {
+        self.number_of_points += count;
+        self.bounds = match (self.bounds, bounds) {
+            (Some(a), Some(b)) => Some(AABB::union(&a, &b)),
+            (a, b) => a.or(b),
+        };
+    }
+
+    /// Returns the version of the sidecar
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Returns the `PointLayout` of the points in the raw dump file
+    pub fn point_layout(&self) -> &PointLayout {
+        &self.point_layout
+    }
+
+    /// Returns the size of the raw dump file in bytes
+    pub fn size_of_point_data(&self) -> usize {
+        self.number_of_points * self.point_layout.size_of_point_entry() as usize
+    }
+}
+
+impl Metadata for RawDumpMetadata {
+    /// The bounds are only known if the points have a `POSITION_3D` attribute
+    fn bounds(&self) -> Option<AABB<f64>> {
+        self.bounds
+    }
+
+    fn number_of_points(&self) -> Option<usize> {
+        Some(self.number_of_points)
+    }
+
+    fn get_named_field(&self, field_name: &str) -> Option<Box<dyn Any>> {
+        match field_name {
+            "Version" => Some(Box::new(self.version)),
+            "PointLayout" => Some(Box::new(self.point_layout.clone())),
+            _ => None,
+        }
+    }
+
+    fn clone_into_box(&self) -> Box<dyn Metadata> {
+        Box::new(self.clone())
+    }
+}
+
+impl Display for RawDumpMetadata {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "RawDumpMetadata {{")?;
+        writeln!(f, "\t\"version\": {}", self.version)?;
+        writeln!(f, "\t\"point_layout\": {}", self.point_layout)?;
+        writeln!(f, "\t\"number_of_points\": {}", self.number_of_points)?;
+        if let Some(bounds) = &self.bounds {
+            writeln!(f, "\t\"bounds_min\": {}", bounds.min())?;
+            writeln!(f, "\t\"bounds_max\": {}", bounds.max())?;
+        }
+        writeln!(f, "}}")
+    }
+}
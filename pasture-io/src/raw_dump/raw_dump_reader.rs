@@ -0,0 +1,447 @@
+use std::{
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use anyhow::{bail, Context, Result};
+#[cfg(feature = "mmap")]
+use memmap2::Mmap;
+#[cfg(feature = "mmap")]
+use pasture_core::containers::ExternalMemoryBuffer;
+use pasture_core::{
+    containers::{
+        BorrowedMutBuffer, InterleavedBuffer, InterleavedBufferMut, MakeBufferFromLayout,
+        OwningBuffer, VectorBuffer,
+    },
+    layout::{conversion::BufferLayoutConverter, PointLayout},
+    meta::Metadata,
+};
+
+use crate::base::{PointReader, SeekToPoint};
+
+use super::{raw_dump_sidecar_path, RawDumpMetadata};
+
+/// Reader for raw dump files that were written by a [`RawDumpWriter`](super::RawDumpWriter). Reading into a buffer
+/// with the `PointLayout` of the raw dump file is a single copy from the file into the memory of the buffer. Buffers
+/// with other `PointLayout`s are filled through the default conversions of `BufferLayoutConverter`. With the `mmap`
+/// feature, [`RawDumpReader::mmap_points`] accesses the points of a raw dump file without copying them at all
+pub struct RawDumpReader<R: Read + Seek> {
+    reader: R,
+    metadata: RawDumpMetadata,
+    current_point_index: usize,
+    decode_buffer: Option<VectorBuffer>,
+}
+
+impl<R: Read + Seek> RawDumpReader<R> {
+    /// Creates a new `RawDumpReader` that reads the points of a raw dump file from `reader`, using the `metadata` from
+    /// the JSON sidecar of the file
+    ///
+    /// # Errors
+    ///
+    /// If the size of the data in `reader` does not match the number of points and the `PointLayout` of `metadata`
+    pub fn from_read_and_metadata(mut reader: R, metadata: RawDumpMetadata) -> Result<Self> {
+        let size_of_point_data = reader
+            .seek(SeekFrom::End(0))
+            .context("Could not determine the size of the raw dump file")?;
+        if size_of_point_data != metadata.size_of_point_data() as u64 {
+            bail!(
+                "Raw dump file has {} bytes, but its sidecar describes {} bytes of point data",
+                size_of_point_data,
+                metadata.size_of_point_data()
+            );
+        }
+        reader
+            .seek(SeekFrom::Start(0))
+            .context("Could not seek to the start of the raw dump file")?;
+        Ok(Self {
+            reader,
+            metadata,
+            current_point_index: 0,
+            decode_buffer: None,
+        })
+    }
+
+    /// Returns the metadata from the JSON sidecar of the raw dump file
+    pub fn raw_dump_metadata(&self) -> &RawDumpMetadata {
+        &self.metadata
+    }
+
+    fn remaining_points(&self) -> usize {
+        self.metadata.number_of_points().unwrap() - self.current_point_index
+    }
+
+    /// Reads the next `count` points of the raw dump file into `points`
+    fn read_point_records(&mut self, points: &mut [u8], count: usize) -> Result<()> {
+        self.reader
+            .read_exact(points)
+            .context("Could not read points from raw dump file")?;
+        self.current_point_index += count;
+        Ok(())
+    }
+}
+
+impl RawDumpReader<BufReader<File>> {
+    /// Creates a new `RawDumpReader` for the raw dump file at `path`. The metadata is read from the JSON sidecar next
+    /// to the file (see [`raw_dump_sidecar_path`])
+    ///
+    /// # Errors
+    ///
+    /// If the raw dump file or its sidecar can't be read, or if they don't match
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let metadata = RawDumpMetadata::from_sidecar_path(raw_dump_sidecar_path(path.as_ref()))?;
+        let file = File::open(path.as_ref())
+            .with_context(|| format!("Could not open file {}", path.as_ref().display()))?;
+        Self::from_read_and_metadata(BufReader::new(file), metadata)
+    }
+
+    /// Memory-maps the raw dump file and returns all its points as a buffer that is backed by the mapped memory, so
+    /// no points are copied. This is only possible if `point_layout` is the `PointLayout` of the raw dump file
+    ///
+    /// # Errors
+    ///
+    /// If `point_layout` does not match the `PointLayout` of the raw dump file, or if the file can't be mapped
+    #[cfg(feature = "mmap")]
+    pub fn mmap_points(&self, point_layout: &PointLayout) -> Result<ExternalMemoryBuffer<Mmap>> {
+        if point_layout != self.metadata.point_layout() {
+            bail!("Raw dump files can only be memory-mapped with the PointLayout that they were written with");
+        }
+        // Safe as long as the file is not modified by someone else while it is mapped, which is the usual caveat
+        // of memory-mapped I/O
+        let mmap = unsafe { Mmap::map(self.reader.get_ref()) }
+            .context("Could not memory-map raw dump file")?;
+        Ok(ExternalMemoryBuffer::new(mmap, point_layout.clone()))
+    }
+}
+
+impl<R: Read + Seek> PointReader for RawDumpReader<R> {
+    fn read_into<'a, 'b, B: BorrowedMutBuffer<'a>>(
+        &mut self,
+        point_buffer: &'b mut B,
+        count: usize,
+    ) -> Result<usize>
+    where
+        'a: 'b,
+    {
+        let num_points_to_read = usize::min(count, self.remaining_points());
+        if num_points_to_read == 0 {
+            return Ok(0);
+        }
+
+        let source_layout = self.metadata.point_layout().clone();
+        if *point_buffer.point_layout() == source_layout {
+            if let Some(interleaved_buffer) = point_buffer.as_interleaved_mut() {
+                self.read_point_records(
+                    interleaved_buffer.get_point_range_mut(0..num_points_to_read),
+                    num_points_to_read,
+                )?;
+                return Ok(num_points_to_read);
+            }
+        }
+
+        // Read the points in chunks in the layout of the file, and then copy or convert them into `point_buffer`
+        const POINTS_PER_CHUNK: usize = 1 << 16;
+        let mut decode_buffer = self
+            .decode_buffer
+            .take()
+            .unwrap_or_else(|| VectorBuffer::new_from_layout(source_layout.clone()));
+        decode_buffer.resize(num_points_to_read.min(POINTS_PER_CHUNK));
+        let target_layout = point_buffer.point_layout().clone();
+        let converter =
+            BufferLayoutConverter::for_layouts_with_default(&source_layout, &target_layout);
+        for chunk_start in (0..num_points_to_read).step_by(POINTS_PER_CHUNK) {
+            let points_in_chunk = (num_points_to_read - chunk_start).min(POINTS_PER_CHUNK);
+            if let Err(why) = self.read_point_records(
+                decode_buffer.get_point_range_mut(0..points_in_chunk),
+                points_in_chunk,
+            ) {
+                self.decode_buffer = Some(decode_buffer);
+                return Err(why);
+            }
+            if target_layout == source_layout {
+                // Safe because both buffers have the same `PointLayout`
+                unsafe {
+                    point_buffer.set_point_range(
+                        chunk_start..chunk_start + points_in_chunk,
+                        decode_buffer.get_point_range_ref(0..points_in_chunk),
+                    );
+                }
+            } else {
+                converter.convert_into_range(
+                    &decode_buffer,
+                    0..points_in_chunk,
+                    point_buffer,
+                    chunk_start..chunk_start + points_in_chunk,
+                );
+            }
+        }
+        self.decode_buffer = Some(decode_buffer);
+        Ok(num_points_to_read)
+    }
+
+    fn get_metadata(&self) -> &dyn Metadata {
+        &self.metadata
+    }
+
+    fn get_default_point_layout(&self) -> &PointLayout {
+        self.metadata.point_layout()
+    }
+
+    fn point_index(&self) -> Option<usize> {
+        Some(self.current_point_index)
+    }
+}
+
+impl<R: Read + Seek> SeekToPoint for RawDumpReader<R> {
+    fn seek_point(&mut self, position: SeekFrom) -> Result<usize> {
+        let number_of_points = self.metadata.number_of_points().unwrap();
+        let new_position = match position {
+            SeekFrom::Start(from_start) => from_start as i64,
+            SeekFrom::End(from_end) => number_of_points as i64 + from_end,
+            SeekFrom::Current(from_current) => self.current_point_index as i64 + from_current,
+        };
+        if new_position < 0 {
+            panic!("RawDumpReader::seek_point: It is an error to seek to a point position smaller than zero!");
+        }
+        let new_position = std::cmp::min(number_of_points as i64, new_position) as usize;
+        let size_of_point = self.metadata.point_layout().size_of_point_entry();
+        self.reader
+            .seek(SeekFrom::Start(new_position as u64 * size_of_point))
+            .context("Could not seek in raw dump file")?;
+        self.current_point_index = new_position;
+        Ok(self.current_point_index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use pasture_core::{
+        containers::{BorrowedBuffer, HashMapBuffer},
+        layout::{
+            attributes::{INTENSITY, POSITION_3D},
+            PointAttributeDataType, PointType,
+        },
+        math::AABB,
+        nalgebra::{Point3, Vector3},
+    };
+    use pasture_derive::PointType;
+    use scopeguard::defer;
+
+    use crate::{
+        base::PointWriter,
+        raw_dump::{RawDumpWriter, RAW_DUMP_VERSION},
+    };
+
+    use super::*;
+
+    #[derive(
+        Debug, PointType, Copy, Clone, PartialEq, bytemuck::AnyBitPattern, bytemuck::NoUninit,
+    )]
+    #[repr(C, packed)]
+    struct RawDumpTestPoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        position: Vector3<f64>,
+        #[pasture(BUILTIN_INTENSITY)]
+        intensity: u16,
+        #[pasture(BUILTIN_CLASSIFICATION)]
+        classification: u8,
+        #[pasture(attribute = "Reflectance")]
+        reflectance: f32,
+    }
+
+    fn test_points(count: usize) -> Vec<RawDumpTestPoint> {
+        (0..count)
+            .map(|index| RawDumpTestPoint {
+                position: Vector3::new(index as f64, -(index as f64) * 0.5, (index % 13) as f64),
+                intensity: (index * 7) as u16,
+                classification: (index % 32) as u8,
+                reflectance: index as f32 * 0.25,
+            })
+            .collect()
+    }
+
+    /// Writes `points` to a raw dump file, the first half from an interleaved and the second half from a columnar
+    /// buffer
+    fn write_test_file(path: &Path, points: &[RawDumpTestPoint]) -> Result<()> {
+        let mut writer = RawDumpWriter::from_path_and_layout(path, RawDumpTestPoint::layout())?;
+        let (first_half, second_half) = points.split_at(points.len() / 2);
+        writer.write(&first_half.iter().copied().collect::<VectorBuffer>())?;
+        writer.write(&second_half.iter().copied().collect::<HashMapBuffer>())?;
+        writer.flush()
+    }
+
+    fn test_file_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("pasture_test_raw_dump_{}.bin", name))
+    }
+
+    fn remove_test_files(path: &Path) {
+        let _ = std::fs::remove_file(path);
+        let _ = std::fs::remove_file(raw_dump_sidecar_path(path));
+    }
+
+    #[test]
+    fn test_raw_dump_round_trip() -> Result<()> {
+        let path = test_file_path("round_trip");
+        defer! {
+            remove_test_files(&path);
+        }
+        let points = test_points(1000);
+        write_test_file(&path, &points)?;
+
+        assert_eq!(
+            1000 * RawDumpTestPoint::layout().size_of_point_entry(),
+            std::fs::metadata(&path)?.len()
+        );
+        let mut reader = RawDumpReader::from_path(&path)?;
+        assert_eq!(
+            &RawDumpTestPoint::layout(),
+            reader.get_default_point_layout()
+        );
+        assert_eq!(Some(1000), reader.point_count());
+        assert_eq!(
+            Some(AABB::from_min_max_unchecked(
+                Point3::new(0.0, -499.5, 0.0),
+                Point3::new(999.0, 0.0, 12.0)
+            )),
+            reader.get_metadata().bounds()
+        );
+
+        let read_points = reader.read_all::<VectorBuffer>()?;
+        assert_eq!(
+            points,
+            read_points
+                .view::<RawDumpTestPoint>()
+                .into_iter()
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Some(1000), reader.point_index());
+
+        reader.seek_point(SeekFrom::Start(990))?;
+        let columnar_points = reader.read_all::<HashMapBuffer>()?;
+        assert_eq!(
+            &points[990..],
+            &columnar_points
+                .view::<RawDumpTestPoint>()
+                .into_iter()
+                .collect::<Vec<_>>()[..]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_raw_dump_read_with_different_layout() -> Result<()> {
+        let path = test_file_path("different_layout");
+        defer! {
+            remove_test_files(&path);
+        }
+        let points = test_points(100);
+        write_test_file(&path, &points)?;
+
+        let target_layout = PointLayout::from_attributes(&[
+            POSITION_3D.with_custom_datatype(PointAttributeDataType::Vec3f32),
+            INTENSITY.with_custom_datatype(PointAttributeDataType::F64),
+        ]);
+        let mut reader = RawDumpReader::from_path(&path)?;
+        reader.seek_point(SeekFrom::Current(10))?;
+        let mut read_points = VectorBuffer::with_capacity(90, target_layout.clone());
+        read_points.resize(90);
+        assert_eq!(90, reader.read_into(&mut read_points, 90)?);
+
+        let positions = read_points
+            .view_attribute::<Vector3<f32>>(
+                &POSITION_3D.with_custom_datatype(PointAttributeDataType::Vec3f32),
+            )
+            .into_iter()
+            .collect::<Vec<_>>();
+        let intensities = read_points
+            .view_attribute::<f64>(&INTENSITY.with_custom_datatype(PointAttributeDataType::F64))
+            .into_iter()
+            .collect::<Vec<_>>();
+        for (index, point) in points[10..].iter().enumerate() {
+            let position = point.position;
+            assert_eq!(
+                position.map(|coordinate| coordinate as f32),
+                positions[index]
+            );
+            assert_eq!(point.intensity as f64, intensities[index]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_raw_dump_sidecar() -> Result<()> {
+        let path = test_file_path("sidecar");
+        defer! {
+            remove_test_files(&path);
+        }
+        write_test_file(&path, &test_points(10))?;
+
+        let sidecar: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(raw_dump_sidecar_path(&path))?)?;
+        assert_eq!(
+            RAW_DUMP_VERSION as u64,
+            sidecar["version"].as_u64().unwrap()
+        );
+        assert_eq!(10, sidecar["number_of_points"].as_u64().unwrap());
+        assert_eq!(
+            RawDumpTestPoint::layout(),
+            serde_json::from_value(sidecar["point_layout"].clone())?
+        );
+
+        // A raw dump file that does not match its sidecar can't be read
+        std::fs::write(&path, [0; 17])?;
+        assert!(RawDumpReader::from_path(&path).is_err());
+        std::fs::remove_file(raw_dump_sidecar_path(&path))?;
+        assert!(RawDumpReader::from_path(&path).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_raw_dump_writer_errors() -> Result<()> {
+        let path = test_file_path("writer_errors");
+        defer! {
+            remove_test_files(&path);
+        }
+        let mut writer = RawDumpWriter::from_path_and_layout(&path, RawDumpTestPoint::layout())?;
+        assert!(writer
+            .write(&VectorBuffer::new_from_layout(
+                PointLayout::from_attributes(&[POSITION_3D])
+            ))
+            .is_err());
+        drop(writer);
+
+        // The sidecar of an empty raw dump file describes no points and no bounds
+        let reader = RawDumpReader::from_path(&path)?;
+        assert_eq!(Some(0), reader.point_count());
+        assert_eq!(None, reader.get_metadata().bounds());
+        Ok(())
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_raw_dump_mmap() -> Result<()> {
+        let path = test_file_path("mmap");
+        defer! {
+            remove_test_files(&path);
+        }
+        let points = test_points(500);
+        write_test_file(&path, &points)?;
+
+        let reader = RawDumpReader::from_path(&path)?;
+        let mapped_points = reader.mmap_points(&RawDumpTestPoint::layout())?;
+        assert_eq!(points.len(), mapped_points.len());
+        assert_eq!(
+            points,
+            mapped_points
+                .view::<RawDumpTestPoint>()
+                .into_iter()
+                .collect::<Vec<_>>()
+        );
+        assert!(reader
+            .mmap_points(&PointLayout::from_attributes(&[POSITION_3D]))
+            .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use pasture_core::{
+    containers::BorrowedBuffer,
+    layout::{attributes::POSITION_3D, PointLayout},
+    math::AABB,
+    nalgebra::{Point3, Vector3},
+};
+
+use crate::base::PointWriter;
+
+use super::{raw_dump_sidecar_path, RawDumpMetadata};
+
+/// Writer for raw dump files, which store the points exactly in the binary layout of their `PointLayout`, so that they
+/// can be read back without any decoding. A raw dump consists of two files: The raw dump file at the given path
+/// contains the interleaved bytes of all points, and a JSON sidecar next to it (see [`raw_dump_sidecar_path`])
+/// describes the `PointLayout`, the number of points and their bounds (see [`RawDumpMetadata`]).
+///
+/// Raw dump files are meant for intermediate storage within a processing pipeline. They are not compressed and can
+/// only be read on machines with the same endianness. The sidecar is written on every `flush` and when the writer is
+/// dropped
+pub struct RawDumpWriter {
+    writer: BufWriter<File>,
+    sidecar_path: PathBuf,
+    metadata: RawDumpMetadata,
+    default_layout: PointLayout,
+    point_records: Vec<u8>,
+    requires_flush: bool,
+}
+
+impl RawDumpWriter {
+    /// Creates a new `RawDumpWriter` that writes points in the given `point_layout` to the raw dump file at `path`
+    /// and the JSON sidecar next to it
+    ///
+    /// # Errors
+    ///
+    /// If the raw dump file or its sidecar can't be created
+    pub fn from_path_and_layout<P: AsRef<Path>>(
+        path: P,
+        point_layout: PointLayout,
+    ) -> Result<Self> {
+        let file = File::create(path.as_ref()).with_context(|| {
+            format!(
+                "Could not open file {} for writing",
+                path.as_ref().display()
+            )
+        })?;
+        let sidecar_path = raw_dump_sidecar_path(path);
+        let metadata = RawDumpMetadata::new(point_layout.clone());
+        metadata.write_to_sidecar_path(&sidecar_path)?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+            sidecar_path,
+            metadata,
+            default_layout: point_layout,
+            point_records: vec![],
+            requires_flush: false,
+        })
+    }
+
+    /// Returns the metadata of all points that have been written so far
+    pub fn metadata(&self) -> &RawDumpMetadata {
+        &self.metadata
+    }
+
+    fn bounds_of_points<'a, B: BorrowedBuffer<'a>>(points: &'a B) -> Result<Option<AABB<f64>>> {
+        if !points
+            .point_layout()
+            .has_attribute_with_name(POSITION_3D.name())
+        {
+            return Ok(None);
+        }
+        let (min, max) = points
+            .view_attribute_with_conversion::<Vector3<f64>>(&POSITION_3D)?
+            .into_iter()
+            .fold(
+                (
+                    Vector3::repeat(f64::INFINITY),
+                    Vector3::repeat(f64::NEG_INFINITY),
+                ),
+                |(min, max), position| (min.inf(&position), max.sup(&position)),
+            );
+        Ok(Some(AABB::from_min_max_unchecked(
+            Point3::from(min),
+            Point3::from(max),
+        )))
+    }
+}
+
+impl PointWriter for RawDumpWriter {
+    fn write<'a, B: BorrowedBuffer<'a>>(&mut self, points: &'a B) -> Result<()> {
+        if *points.point_layout() != self.default_layout {
+            bail!("PointLayout of the points does not match the PointLayout of the RawDumpWriter");
+        }
+        if points.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(interleaved_buffer) = points.as_interleaved() {
+            self.writer
+                .write_all(interleaved_buffer.get_point_range_ref(0..points.len()))
+                .context("Could not write points to raw dump file")?;
+        } else {
+            // Other buffers are copied into the interleaved layout in chunks
+            const POINTS_PER_CHUNK: usize = 1 << 16;
+            let size_of_point = self.default_layout.size_of_point_entry() as usize;
+            for chunk_start in (0..points.len()).step_by(POINTS_PER_CHUNK) {
+                let chunk_end = (chunk_start + POINTS_PER_CHUNK).min(points.len());
+                self.point_records
+                    .resize((chunk_end - chunk_start) * size_of_point, 0);
+                points.get_point_range(chunk_start..chunk_end, &mut self.point_records);
+                self.writer
+                    .write_all(&self.point_records)
+                    .context("Could not write points to raw dump file")?;
+            }
+        }
+
+        let bounds = Self::bounds_of_points(points)?;
+        self.metadata.add_points(points.len(), bounds);
+        self.requires_flush = true;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if !self.requires_flush {
+            return Ok(());
+        }
+        self.writer
+            .flush()
+            .context("Could not flush raw dump file")?;
+        self.metadata.write_to_sidecar_path(&self.sidecar_path)?;
+        self.requires_flush = false;
+        Ok(())
+    }
+
+    fn get_default_point_layout(&self) -> &PointLayout {
+        &self.default_layout
+    }
+}
+
+impl Drop for RawDumpWriter {
+    fn drop(&mut self) {
+        if let Err(why) = self.flush() {
+            let _ = writeln!(
+                std::io::stderr(),
+                "Error while flushing RawDumpWriter: {:?}",
+                why
+            );
+        }
+    }
+}
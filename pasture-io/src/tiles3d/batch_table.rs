@@ -1,5 +1,5 @@
 use anyhow::{anyhow, bail, Result};
-use pasture_core::math::Alignable;
+use pasture_core::{layout::PointAttributeDataType, math::Alignable};
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 use std::{
@@ -88,6 +88,58 @@ impl From<&BatchTableEntry> for Value {
 /// A 3D Tiles BatchTable header, which is a collection of BatchTableEntries
 pub type BatchTableHeader = HashMap<String, BatchTableEntry>;
 
+/// Returns the `componentType` and `type` of a binary BatchTable property that stores values of the given `datatype`.
+/// Returns `None` for datatypes that a BatchTable can't store, which are 64-bit integers, byte arrays and custom types
+pub fn batch_table_types_from_datatype(
+    datatype: PointAttributeDataType,
+) -> Option<(&'static str, &'static str)> {
+    match datatype {
+        PointAttributeDataType::U8 => Some(("UNSIGNED_BYTE", "SCALAR")),
+        PointAttributeDataType::I8 => Some(("BYTE", "SCALAR")),
+        PointAttributeDataType::U16 => Some(("UNSIGNED_SHORT", "SCALAR")),
+        PointAttributeDataType::I16 => Some(("SHORT", "SCALAR")),
+        PointAttributeDataType::U32 => Some(("UNSIGNED_INT", "SCALAR")),
+        PointAttributeDataType::I32 => Some(("INT", "SCALAR")),
+        PointAttributeDataType::F32 => Some(("FLOAT", "SCALAR")),
+        PointAttributeDataType::F64 => Some(("DOUBLE", "SCALAR")),
+        PointAttributeDataType::Vec3u8 => Some(("UNSIGNED_BYTE", "VEC3")),
+        PointAttributeDataType::Vec3u16 => Some(("UNSIGNED_SHORT", "VEC3")),
+        PointAttributeDataType::Vec3i32 => Some(("INT", "VEC3")),
+        PointAttributeDataType::Vec3f32 => Some(("FLOAT", "VEC3")),
+        PointAttributeDataType::Vec3f64 => Some(("DOUBLE", "VEC3")),
+        PointAttributeDataType::Vec4u8 => Some(("UNSIGNED_BYTE", "VEC4")),
+        _ => None,
+    }
+}
+
+/// Returns the `PointAttributeDataType` for the values of the binary BatchTable property described by
+/// `data_reference`, or `None` if there is no matching datatype
+pub fn datatype_from_batch_table_reference(
+    data_reference: &BatchTableDataReference,
+) -> Option<PointAttributeDataType> {
+    let datatype = match (
+        data_reference.component_type.as_str(),
+        data_reference.scalar_or_vector_type.as_str(),
+    ) {
+        ("UNSIGNED_BYTE", "SCALAR") => PointAttributeDataType::U8,
+        ("BYTE", "SCALAR") => PointAttributeDataType::I8,
+        ("UNSIGNED_SHORT", "SCALAR") => PointAttributeDataType::U16,
+        ("SHORT", "SCALAR") => PointAttributeDataType::I16,
+        ("UNSIGNED_INT", "SCALAR") => PointAttributeDataType::U32,
+        ("INT", "SCALAR") => PointAttributeDataType::I32,
+        ("FLOAT", "SCALAR") => PointAttributeDataType::F32,
+        ("DOUBLE", "SCALAR") => PointAttributeDataType::F64,
+        ("UNSIGNED_BYTE", "VEC3") => PointAttributeDataType::Vec3u8,
+        ("UNSIGNED_SHORT", "VEC3") => PointAttributeDataType::Vec3u16,
+        ("INT", "VEC3") => PointAttributeDataType::Vec3i32,
+        ("FLOAT", "VEC3") => PointAttributeDataType::Vec3f32,
+        ("DOUBLE", "VEC3") => PointAttributeDataType::Vec3f64,
+        ("UNSIGNED_BYTE", "VEC4") => PointAttributeDataType::Vec4u8,
+        _ => return None,
+    };
+    Some(datatype)
+}
+
 /// Deserialize a `BatchTableHeader` from the given `reader`. If successful, returns the serialized header and the
 /// `reader` will be at the start of the binary body of the 3D Tiles BatchTable. See the [3D Tiles documentation](https://github.com/CesiumGS/3d-tiles/blob/master/specification/TileFormats/BatchTable/README.md)
 /// for more information. If this operation fails, the reader will be in an undefined state.
@@ -180,4 +232,34 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_3dtiles_batch_table_datatypes() {
+        for datatype in [
+            PointAttributeDataType::U8,
+            PointAttributeDataType::I16,
+            PointAttributeDataType::F64,
+            PointAttributeDataType::Vec3u16,
+            PointAttributeDataType::Vec3f32,
+            PointAttributeDataType::Vec4u8,
+        ]
+        .iter()
+        {
+            let (component_type, scalar_or_vector_type) =
+                batch_table_types_from_datatype(*datatype).unwrap();
+            let data_reference = BatchTableDataReference {
+                byte_offset: 0,
+                component_type: component_type.into(),
+                scalar_or_vector_type: scalar_or_vector_type.into(),
+            };
+            assert_eq!(
+                Some(*datatype),
+                datatype_from_batch_table_reference(&data_reference)
+            );
+        }
+        assert_eq!(
+            None,
+            batch_table_types_from_datatype(PointAttributeDataType::U64)
+        );
+    }
 }
@@ -46,6 +46,16 @@ impl PntsMetadata {
     pub fn rtc_center(&self) -> Option<Vector3<f64>> {
         self.rtc_center
     }
+
+    /// Access the `QUANTIZED_VOLUME_OFFSET` field of the metadata, which is present for files with quantized positions
+    pub fn quantized_volume_offset(&self) -> Option<Vector3<f32>> {
+        self.quantized_volume_offset
+    }
+
+    /// Access the `QUANTIZED_VOLUME_SCALE` field of the metadata, which is present for files with quantized positions
+    pub fn quantized_volume_scale(&self) -> Option<Vector3<f32>> {
+        self.quantized_volume_scale
+    }
 }
 
 impl Metadata for PntsMetadata {
@@ -1,4 +1,5 @@
 use std::{
+    borrow::Cow,
     collections::HashMap,
     convert::TryInto,
     fs::File,
@@ -7,20 +8,24 @@ use std::{
 };
 
 use anyhow::{anyhow, bail, Context, Result};
+use byteorder::{LittleEndian, ReadBytesExt};
 use pasture_core::{
     containers::BorrowedMutBuffer,
     layout::{
         attributes::{COLOR_RGB, NORMAL, POSITION_3D},
         conversion::get_converter_for_attributes,
-        FieldAlignment, PointAttributeDataType, PointLayout,
+        FieldAlignment, PointAttributeDataType, PointAttributeDefinition, PointLayout,
     },
     meta::Metadata,
     nalgebra::{clamp, Vector3},
 };
 
-use crate::tiles3d::{deser_feature_table_header, FeatureTableValue, PntsHeader};
+use crate::tiles3d::{
+    datatype_from_batch_table_reference, deser_batch_table_header, deser_feature_table_header,
+    BatchTableEntry, BatchTableHeader, FeatureTableValue, PntsHeader,
+};
 use crate::{
-    base::{PointReader, SeekToPoint},
+    base::{skip_points_by_seeking, PointReader, SeekToPoint},
     tiles3d::{attributes::COLOR_RGBA, json_arr_to_vec3f32, json_arr_to_vec4u8},
 };
 
@@ -44,6 +49,7 @@ pub struct PntsReader<R: BufRead + Seek> {
     layout: PointLayout,
     current_point_index: usize,
     attribute_offsets: HashMap<String, u64>,
+    quantized_positions: bool,
     read_positions_mode: PntsReadPositionsMode,
 }
 
@@ -68,14 +74,21 @@ impl<R: BufRead + Seek> PntsReader<R> {
             header.feature_table_json_byte_length as usize,
             position_after_header,
         )?;
-        // TODO BatchTable support
 
         // The following functions mutate the feature table header HashMap and remove the entries that
         // are relevant. This is done because both point semantics and global semantics are stored in the
         // same header, so this makes parsing easier
-        let (layout, mut attribute_offsets) =
+        let quantized_positions = !feature_table_header.contains_key("POSITION")
+            && feature_table_header.contains_key("POSITION_QUANTIZED");
+        let (mut layout, mut attribute_offsets) =
             Self::layout_from_feature_table_header(&mut feature_table_header)?;
         let metadata = Self::metadata_from_feature_table_header(&mut feature_table_header)?;
+        if quantized_positions
+            && (metadata.quantized_volume_offset().is_none()
+                || metadata.quantized_volume_scale().is_none())
+        {
+            bail!("PNTS file has quantized positions but no QUANTIZED_VOLUME_OFFSET or QUANTIZED_VOLUME_SCALE");
+        }
 
         // TODO Log all parameters that could not be parsed. This requires logging support for pasture
 
@@ -85,12 +98,34 @@ impl<R: BufRead + Seek> PntsReader<R> {
             *offset += feature_table_binary_offset;
         }
 
+        // Without BATCH_ID, the BatchTable stores one value per point for each property, so its binary properties
+        // become additional point attributes
+        if header.batch_table_json_byte_length > 0 && !feature_table_header.contains_key("BATCH_ID")
+        {
+            let batch_table_header_offset =
+                feature_table_binary_offset + header.feature_table_binary_byte_length as u64;
+            read.seek(SeekFrom::Start(batch_table_header_offset))?;
+            let batch_table_header = deser_batch_table_header(
+                &mut read,
+                header.batch_table_json_byte_length as usize,
+                batch_table_header_offset as usize,
+            )
+            .context("Could not deserialize BatchTable header")?;
+            Self::add_batch_table_attributes(
+                &batch_table_header,
+                batch_table_header_offset + header.batch_table_json_byte_length as u64,
+                &mut layout,
+                &mut attribute_offsets,
+            );
+        }
+
         Ok(Self {
             reader: read,
             metadata,
             layout,
             current_point_index: 0,
             attribute_offsets,
+            quantized_positions,
             read_positions_mode: PntsReadPositionsMode::Absolute,
         })
     }
@@ -128,7 +163,21 @@ impl<R: BufRead + Seek> PntsReader<R> {
             header.remove("POSITION");
         }
 
-        // TODO Quantized positions, which probably require an option to de-quantize during reading (similar to LAS/LAZ)
+        // Quantized positions are only used if there are no regular positions. They are dequantized during reading, so
+        // they become regular POSITION_3D values
+        if !layout.has_attribute_with_name(POSITION_3D.name())
+            && header.contains_key("POSITION_QUANTIZED")
+        {
+            let pos_attribute = &header["POSITION_QUANTIZED"];
+            match pos_attribute {
+                FeatureTableValue::DataReference(reference) => {
+                    attribute_offsets.insert(POSITION_3D.name().to_owned(), reference.byte_offset as u64);
+                    layout.add_attribute(POSITION_3D.with_custom_datatype(PointAttributeDataType::Vec3f32), FieldAlignment::Packed(1));
+                },
+                _ => bail!("Found PNTS attribute POSITION_QUANTIZED ({:?}) but it was not a reference to the feature table binary!", pos_attribute),
+            }
+            header.remove("POSITION_QUANTIZED");
+        }
 
         if header.contains_key("RGBA") {
             let color_attribute = &header["RGBA"];
@@ -175,6 +224,38 @@ impl<R: BufRead + Seek> PntsReader<R> {
         Ok((layout, attribute_offsets))
     }
 
+    /// Adds an attribute to `layout` for each binary property of the BatchTable whose datatype is supported. The
+    /// attributes are added in alphabetical order, properties with the same name as an existing attribute are skipped
+    fn add_batch_table_attributes(
+        batch_table_header: &BatchTableHeader,
+        batch_table_binary_offset: u64,
+        layout: &mut PointLayout,
+        attribute_offsets: &mut HashMap<String, u64>,
+    ) {
+        let mut properties = batch_table_header
+            .iter()
+            .filter_map(|(name, entry)| match entry {
+                BatchTableEntry::DataReference(reference) => {
+                    datatype_from_batch_table_reference(reference)
+                        .map(|datatype| (name, reference.byte_offset, datatype))
+                }
+                BatchTableEntry::ArrayData(_) => None,
+            })
+            .collect::<Vec<_>>();
+        properties.sort_by(|a, b| a.0.cmp(b.0));
+
+        for (name, byte_offset, datatype) in properties {
+            if layout.has_attribute_with_name(name) {
+                continue;
+            }
+            attribute_offsets.insert(name.clone(), batch_table_binary_offset + byte_offset as u64);
+            layout.add_attribute(
+                PointAttributeDefinition::custom(Cow::Owned(name.clone()), datatype),
+                FieldAlignment::Packed(1),
+            );
+        }
+    }
+
     fn metadata_from_feature_table_header(
         header: &mut HashMap<String, FeatureTableValue>,
     ) -> Result<PntsMetadata> {
@@ -244,6 +325,64 @@ impl<R: BufRead + Seek> PntsReader<R> {
         ))
     }
 
+    /// Reads `count` quantized positions starting at the current point and writes them, dequantized, into the
+    /// `target_attribute` of `point_buffer`
+    fn read_quantized_positions<'a, 'b, B: BorrowedMutBuffer<'a>>(
+        &mut self,
+        point_buffer: &'b mut B,
+        target_attribute: &PointAttributeDefinition,
+        count: usize,
+    ) -> Result<()>
+    where
+        'a: 'b,
+    {
+        // Both values are checked for existence when opening the file
+        let volume_offset = self
+            .metadata
+            .quantized_volume_offset()
+            .unwrap()
+            .cast::<f64>();
+        let volume_scale = self
+            .metadata
+            .quantized_volume_scale()
+            .unwrap()
+            .cast::<f64>();
+
+        let offset_to_first_point = *self.attribute_offsets.get(POSITION_3D.name()).unwrap();
+        self.reader.seek(SeekFrom::Start(
+            offset_to_first_point + (self.current_point_index as u64 * 6),
+        ))?;
+        let mut quantized_positions = vec![0; count * 3];
+        self.reader
+            .read_u16_into::<LittleEndian>(quantized_positions.as_mut_slice())
+            .context("Could not read quantized positions")?;
+
+        let dequantized_attribute =
+            POSITION_3D.with_custom_datatype(PointAttributeDataType::Vec3f64);
+        let converter = get_converter_for_attributes(&dequantized_attribute, target_attribute);
+        let mut converted_buf: Vec<u8> = vec![0; target_attribute.size() as usize];
+        for (point_index, quantized_position) in quantized_positions.chunks_exact(3).enumerate() {
+            let position = volume_offset
+                + Vector3::new(
+                    quantized_position[0] as f64,
+                    quantized_position[1] as f64,
+                    quantized_position[2] as f64,
+                )
+                .component_mul(&volume_scale)
+                    / u16::MAX as f64;
+            let position_bytes = bytemuck::bytes_of(&position);
+            unsafe {
+                if let Some(conversion_fn) = converter {
+                    conversion_fn(position_bytes, converted_buf.as_mut_slice());
+                    point_buffer.set_attribute(target_attribute, point_index, &converted_buf);
+                } else {
+                    point_buffer.set_attribute(target_attribute, point_index, position_bytes);
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn apply_rtc_center_offset<'a, 'b, B: BorrowedMutBuffer<'a>>(
         &self,
         point_buffer: &'b mut B,
@@ -306,9 +445,24 @@ impl<R: BufRead + Seek> PointReader for PntsReader<R> {
         }
 
         let target_layout = point_buffer.point_layout().clone();
+        if self.quantized_positions {
+            if let Some(target_attribute) = target_layout.get_attribute_by_name(POSITION_3D.name())
+            {
+                self.read_quantized_positions(
+                    point_buffer,
+                    target_attribute.attribute_definition(),
+                    num_to_read,
+                )?;
+            }
+        }
         for attribute in self.layout.attributes() {
             // Try to read this attribute only if it exists in the target buffer's PointLayout
             if let Some(target_attribute) = target_layout.get_attribute_by_name(attribute.name()) {
+                // Quantized positions have been read above
+                if self.quantized_positions && attribute.name() == POSITION_3D.name() {
+                    continue;
+                }
+
                 let attribute_stride = attribute.size();
                 let offset_to_first_point_of_attribute =
                     *self.attribute_offsets.get(attribute.name()).unwrap();
@@ -372,6 +526,18 @@ impl<R: BufRead + Seek> PointReader for PntsReader<R> {
     fn get_default_point_layout(&self) -> &PointLayout {
         &self.layout
     }
+
+    fn skip(&mut self, count: usize) -> Result<usize> {
+        skip_points_by_seeking(self, count)
+    }
+
+    fn point_index(&self) -> Option<usize> {
+        Some(self.current_point_index)
+    }
+
+    fn point_count(&self) -> Option<usize> {
+        Some(self.metadata.points_length())
+    }
 }
 
 impl<R: BufRead + Seek> SeekToPoint for PntsReader<R> {
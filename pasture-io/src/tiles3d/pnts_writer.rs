@@ -2,7 +2,9 @@ use std::{
     borrow::Cow,
     collections::HashMap,
     convert::TryInto,
-    io::{Cursor, Seek, Write},
+    fs::File,
+    io::{BufWriter, Cursor, Seek, Write},
+    path::Path,
 };
 
 use anyhow::{Context, Result};
@@ -28,7 +30,10 @@ use crate::{
     },
 };
 
-use super::{BatchTableHeader, FeatureTableDataReference, FeatureTableHeader, FeatureTableValue};
+use super::{
+    batch_table_types_from_datatype, BatchTableDataReference, BatchTableEntry, BatchTableHeader,
+    FeatureTableDataReference, FeatureTableHeader, FeatureTableValue,
+};
 
 /// Maximum required alignment
 const PNTS_SEMANTICS_MAX_ALIGNMENT: usize = 8;
@@ -52,6 +57,25 @@ fn pnts_semantics_name_from_point_attribute(
     }
 }
 
+/// Appends zero bytes to `blob` until its length is a multiple of `PNTS_SEMANTICS_MAX_ALIGNMENT`
+fn pad_blob(blob: &mut Vec<u8>) {
+    blob.resize(blob.len().align_to(PNTS_SEMANTICS_MAX_ALIGNMENT), 0);
+}
+
+/// Defines the `RTC_CENTER` that the `PntsWriter` writes
+#[derive(Copy, Clone, Debug, PartialEq, Default)]
+pub enum PntsRtcCenter {
+    /// Positions are written unchanged. An `RTC_CENTER` is only written if it was set through
+    /// [`PntsWriter::set_rtc_center`]
+    #[default]
+    None,
+    /// Writes the given `RTC_CENTER` and subtracts it from all positions
+    Fixed(Vector3<f64>),
+    /// Writes the centroid of all positions as `RTC_CENTER` and subtracts it from all positions. This keeps the stored
+    /// positions close to the origin, so that large coordinates don't lose precision when they are stored as `f32`
+    Centroid,
+}
+
 /// Writer for .pnts files, the point cloud file format in the 3D Tiles standard.
 ///
 /// 3D Tiles .pnts files store their data in per-attribute memory layout. Append to data
@@ -63,7 +87,8 @@ fn pnts_semantics_name_from_point_attribute(
 /// 2) Cache all data locally in a `PerAttributePointBuffer`, and only write the data during
 ///    the `flush` call
 ///
-/// This `PntsWriter` implementation uses the second approach
+/// This `PntsWriter` implementation uses the second approach. Use a [`PntsWriterBuilder`] to write quantized positions
+/// or to place the `RTC_CENTER` automatically
 pub struct PntsWriter<W: Write + Seek> {
     writer: W,
     expected_layout: PointLayout,
@@ -71,16 +96,21 @@ pub struct PntsWriter<W: Write + Seek> {
     cached_points: HashMapBuffer,
     attribute_converters: HashMap<String, Option<AttributeConversionFn>>,
     rtc_center: Option<Vector3<f64>>,
+    rtc_center_mode: PntsRtcCenter,
+    quantize_positions: bool,
     requires_flush: bool,
 }
 
 impl<W: Write + Seek> PntsWriter<W> {
-    /// Creates a new `PntsWriter` writing to the given `writer` and using the given `point_layout`. Please note that
-    /// while 3D Tiles does in principle support arbitrary point attributes, currently only the default point semantics
-    /// are supported (see [3D Tiles specification](https://github.com/CesiumGS/3d-tiles/blob/master/specification/TileFormats/PointCloud/README.md#semantics)). All further attributes are simply ignored silently!
+    /// Creates a new `PntsWriter` writing to the given `writer` and using the given `point_layout`. The `POSITION_3D`,
+    /// `COLOR_RGB`, `ColorRGBA` and `NORMAL` attributes are written as the corresponding point semantics (see
+    /// [3D Tiles specification](https://github.com/CesiumGS/3d-tiles/blob/master/specification/TileFormats/PointCloud/README.md#semantics)).
+    /// Colors with 16 bits per channel are downscaled to the 8 bits of the `RGB` semantic if any channel exceeds 255.
+    /// All further attributes are written as binary properties of the BatchTable, except for attributes with datatypes
+    /// that a BatchTable can't store (64-bit integers, byte arrays and custom types), which are ignored silently!
     pub fn from_write_and_layout(writer: W, point_layout: PointLayout) -> Self {
-        // The PntsWriter can accept any kind of point buffer, but it will silently discard attributes that are not
-        // supported by 3D Tiles. All supported attributes that are also in `point_layout` are described by `cache_layout`
+        // The PntsWriter can accept any kind of point buffer, but it will silently discard attributes that can't be
+        // written. All supported attributes that are also in `point_layout` are described by `cache_layout`
         let (cache_layout, attribute_converters) = Self::make_compatible_layout(&point_layout);
         let cache = HashMapBuffer::new_from_layout(cache_layout.clone());
         Self {
@@ -90,6 +120,8 @@ impl<W: Write + Seek> PntsWriter<W> {
             cached_points: cache,
             attribute_converters,
             rtc_center: None,
+            rtc_center_mode: PntsRtcCenter::None,
+            quantize_positions: false,
             requires_flush: true,
         }
     }
@@ -97,29 +129,30 @@ impl<W: Write + Seek> PntsWriter<W> {
     /// Sets the given vector as the parameter for the `RTC_CENTER` semantic in the FeatureTable. As per the 3D Tiles specification,
     /// points can be defined relative to a center point, which is given by the `RTC_CENTER` semantic. Setting this value however
     /// **does not automatically translate points relative to this center!** This has to be done prior to calling `write`!
+    /// Use [`PntsRtcCenter::Fixed`] to translate the points automatically
     pub fn set_rtc_center(&mut self, rtc_center: Vector3<f64>) {
         self.rtc_center = Some(rtc_center);
     }
 
-    /// Makes the given `PointLayout` compatible with the supported point semantics of the 3D Tiles .pnts format. Doing
-    /// so is done by iterating through the attributes in the `point_layout` and checking each attribute if it is one of
-    /// the supported point semantics. If not, it is discarded. Supported semantics are then converted to the default data
-    /// type as per the [3D Tiles standard](https://github.com/CesiumGS/3d-tiles/blob/master/specification/TileFormats/PointCloud/README.md#semantics)
+    /// Makes the given `PointLayout` compatible with the .pnts format. Doing so is done by iterating through the attributes
+    /// in the `point_layout` and checking each attribute if it is one of the supported point semantics. Supported semantics
+    /// are then converted to the datatype that they are cached in, which is the default data type as per the
+    /// [3D Tiles standard](https://github.com/CesiumGS/3d-tiles/blob/master/specification/TileFormats/PointCloud/README.md#semantics),
+    /// except for positions and RGB colors which are cached with full precision and encoded when the points are written.
+    /// All other attributes are kept for the BatchTable, or discarded if the BatchTable can't store their datatype
     fn make_compatible_layout(
         point_layout: &PointLayout,
     ) -> (PointLayout, HashMap<String, Option<AttributeConversionFn>>) {
         let mut compatible_layout = PointLayout::default();
         let mut conversion_fns: HashMap<String, Option<AttributeConversionFn>> = HashMap::new();
         // TODO Support for other attributes:
-        // * Quantized positions
         // * RGB565 colors
         // * Normal oct encoded
-        // * Batch ID (and batch table with custom attributes)
 
         let color_rgba = COLOR_RGBA;
         let supported_attributes: HashMap<&str, PointAttributeDataType> = vec![
-            (POSITION_3D.name(), PointAttributeDataType::Vec3f32),
-            (COLOR_RGB.name(), PointAttributeDataType::Vec3u8),
+            (POSITION_3D.name(), PointAttributeDataType::Vec3f64),
+            (COLOR_RGB.name(), PointAttributeDataType::Vec3u16),
             (color_rgba.name(), PointAttributeDataType::Vec4u8),
             (NORMAL.name(), PointAttributeDataType::Vec3f32),
         ]
@@ -127,28 +160,33 @@ impl<W: Write + Seek> PntsWriter<W> {
         .collect();
 
         for src_attribute in point_layout.attributes() {
-            if let Some(dst_attribute_datatype) = supported_attributes.get(&src_attribute.name()) {
-                compatible_layout.add_attribute(
-                    PointAttributeDefinition::custom(
-                        Cow::Owned(src_attribute.name().to_owned()),
-                        *dst_attribute_datatype,
+            let dst_attribute_datatype = match supported_attributes.get(&src_attribute.name()) {
+                Some(datatype) => *datatype,
+                None if batch_table_types_from_datatype(src_attribute.datatype()).is_some() => {
+                    src_attribute.datatype()
+                }
+                None => continue,
+            };
+            compatible_layout.add_attribute(
+                PointAttributeDefinition::custom(
+                    Cow::Owned(src_attribute.name().to_owned()),
+                    dst_attribute_datatype,
+                ),
+                FieldAlignment::Default,
+            );
+            let dst_attribute = compatible_layout
+                .get_attribute_by_name(src_attribute.name())
+                .unwrap();
+            if src_attribute.datatype() == dst_attribute.datatype() {
+                conversion_fns.insert(src_attribute.name().to_owned(), None);
+            } else {
+                conversion_fns.insert(
+                    src_attribute.name().to_owned(),
+                    get_converter_for_attributes(
+                        src_attribute.attribute_definition(),
+                        dst_attribute.attribute_definition(),
                     ),
-                    FieldAlignment::Default,
                 );
-                let dst_attribute = compatible_layout
-                    .get_attribute_by_name(src_attribute.name())
-                    .unwrap();
-                if src_attribute.datatype() == dst_attribute.datatype() {
-                    conversion_fns.insert(src_attribute.name().to_owned(), None);
-                } else {
-                    conversion_fns.insert(
-                        src_attribute.name().to_owned(),
-                        get_converter_for_attributes(
-                            src_attribute.attribute_definition(),
-                            dst_attribute.attribute_definition(),
-                        ),
-                    );
-                }
             }
         }
 
@@ -156,12 +194,148 @@ impl<W: Write + Seek> PntsWriter<W> {
     }
 
     fn write_cached_points(&mut self) -> Result<()> {
-        let feature_table_header = self.create_feature_table();
-        let batch_table_header = self.create_batch_table();
+        let num_points = self.cached_points.len();
+        let positions = self
+            .default_layout
+            .get_attribute_by_name(POSITION_3D.name())
+            .map(|attribute| {
+                self.cached_points
+                    .view_attribute::<Vector3<f64>>(attribute.attribute_definition())
+                    .into_iter()
+                    .collect::<Vec<_>>()
+            });
+        let (rtc_center, position_offset) = match self.rtc_center_mode {
+            PntsRtcCenter::None => (self.rtc_center, Vector3::zeros()),
+            PntsRtcCenter::Fixed(rtc_center) => (Some(rtc_center), rtc_center),
+            PntsRtcCenter::Centroid => match &positions {
+                Some(positions) if !positions.is_empty() => {
+                    let centroid = positions.iter().sum::<Vector3<f64>>() / positions.len() as f64;
+                    (Some(centroid), centroid)
+                }
+                _ => (None, Vector3::zeros()),
+            },
+        };
 
-        let mut feature_table_blob = vec![];
-        let mut batch_table_blob = vec![];
+        // Global semantics. Only POINTS_LENGTH is mandatory
+        let mut feature_table_header = FeatureTableHeader::new();
+        feature_table_header.insert(
+            "POINTS_LENGTH".into(),
+            FeatureTableValue::SingleValue(json!(num_points)),
+        );
+        if let Some(rtc_center) = rtc_center {
+            feature_table_header.insert(
+                "RTC_CENTER".into(),
+                FeatureTableValue::Array(vec![
+                    json!(rtc_center.x),
+                    json!(rtc_center.y),
+                    json!(rtc_center.z),
+                ]),
+            );
+        }
 
+        // Point semantics go into the FeatureTable body and all other attributes into the BatchTable body. We store all
+        // attributes with the same memory alignment (PNTS_SEMANTICS_MAX_ALIGNMENT), which satisfies the alignment of
+        // all component types and makes both bodies end at an 8-byte boundary
+        let mut feature_table_body = vec![];
+        let mut batch_table_header = BatchTableHeader::new();
+        let mut batch_table_body = vec![];
+        for attribute in self.default_layout.attributes() {
+            let byte_offset = feature_table_body.len();
+            if attribute.name() == POSITION_3D.name() {
+                let relative_positions = positions
+                    .iter()
+                    .flatten()
+                    .map(|position| position - position_offset);
+                if self.quantize_positions {
+                    let (volume_offset, volume_scale) =
+                        Self::quantize_positions(relative_positions, &mut feature_table_body);
+                    feature_table_header.insert(
+                        "POSITION_QUANTIZED".into(),
+                        FeatureTableValue::DataReference(FeatureTableDataReference {
+                            byte_offset,
+                            component_type: None,
+                        }),
+                    );
+                    feature_table_header.insert(
+                        "QUANTIZED_VOLUME_OFFSET".into(),
+                        FeatureTableValue::Array(volume_offset.iter().map(|v| json!(v)).collect()),
+                    );
+                    feature_table_header.insert(
+                        "QUANTIZED_VOLUME_SCALE".into(),
+                        FeatureTableValue::Array(volume_scale.iter().map(|v| json!(v)).collect()),
+                    );
+                } else {
+                    for position in relative_positions {
+                        for component in position.iter() {
+                            feature_table_body
+                                .extend_from_slice(&(*component as f32).to_le_bytes());
+                        }
+                    }
+                    feature_table_header.insert(
+                        "POSITION".into(),
+                        FeatureTableValue::DataReference(FeatureTableDataReference {
+                            byte_offset,
+                            component_type: None,
+                        }),
+                    );
+                }
+            } else if let Some(semantic_name) =
+                pnts_semantics_name_from_point_attribute(attribute.attribute_definition())
+            {
+                if attribute.name() == COLOR_RGB.name() {
+                    let colors = self
+                        .cached_points
+                        .view_attribute::<Vector3<u16>>(attribute.attribute_definition())
+                        .into_iter()
+                        .collect::<Vec<_>>();
+                    // Colors with 16 bits per channel are downscaled, colors that fit into 8 bits are kept
+                    let downscale = colors.iter().any(|color| color.max() > u8::MAX as u16);
+                    feature_table_body.extend(colors.iter().flat_map(|color| {
+                        color.iter().map(move |channel| {
+                            if downscale {
+                                (channel >> 8) as u8
+                            } else {
+                                *channel as u8
+                            }
+                        })
+                    }));
+                } else {
+                    feature_table_body.extend_from_slice(
+                        self.cached_points.get_attribute_range_ref(
+                            attribute.attribute_definition(),
+                            0..num_points,
+                        ),
+                    );
+                }
+                feature_table_header.insert(
+                    semantic_name,
+                    FeatureTableValue::DataReference(FeatureTableDataReference {
+                        byte_offset,
+                        component_type: None,
+                    }),
+                );
+            } else {
+                let (component_type, scalar_or_vector_type) =
+                    batch_table_types_from_datatype(attribute.datatype())
+                        .expect("Unsupported datatype for BatchTable property");
+                batch_table_header.insert(
+                    attribute.name().to_owned(),
+                    BatchTableEntry::DataReference(BatchTableDataReference {
+                        byte_offset: batch_table_body.len(),
+                        component_type: component_type.into(),
+                        scalar_or_vector_type: scalar_or_vector_type.into(),
+                    }),
+                );
+                batch_table_body.extend_from_slice(
+                    self.cached_points
+                        .get_attribute_range_ref(attribute.attribute_definition(), 0..num_points),
+                );
+                pad_blob(&mut batch_table_body);
+            }
+            pad_blob(&mut feature_table_body);
+        }
+
+        let mut feature_table_blob = vec![];
         ser_feature_table_header(
             Cursor::new(&mut feature_table_blob),
             &feature_table_header,
@@ -169,44 +343,44 @@ impl<W: Write + Seek> PntsWriter<W> {
         )
         .context("Error serializing FeatureTable header")?;
 
-        let feature_table_byte_size = feature_table_blob.len();
-        let feature_table_body_byte_size = self.calc_feature_table_body_length();
-        let feature_table_body_byte_size_aligned =
-            (PntsHeader::BYTE_LENGTH + feature_table_byte_size + feature_table_body_byte_size)
-                .align_to(8)
-                - (PntsHeader::BYTE_LENGTH + feature_table_byte_size);
-        let start_of_batch_table_header = PntsHeader::BYTE_LENGTH
-            + feature_table_byte_size
-            + feature_table_body_byte_size_aligned;
-
-        ser_batch_table_header(
-            Cursor::new(&mut batch_table_blob),
-            &batch_table_header,
-            start_of_batch_table_header,
-        )
-        .context("Error serializing BatchTable header")?;
-        let batch_table_byte_size = batch_table_blob.len();
-        //TODO Support batch table body
-        let batch_table_body_byte_size: usize = 0;
+        // The BatchTable is omitted if there are no BatchTable properties
+        let mut batch_table_blob = vec![];
+        if !batch_table_header.is_empty() {
+            let start_of_batch_table_header =
+                PntsHeader::BYTE_LENGTH + feature_table_blob.len() + feature_table_body.len();
+            ser_batch_table_header(
+                Cursor::new(&mut batch_table_blob),
+                &batch_table_header,
+                start_of_batch_table_header,
+            )
+            .context("Error serializing BatchTable header")?;
+        }
 
-        let total_byte_length =
-            start_of_batch_table_header + batch_table_byte_size + batch_table_body_byte_size;
+        let total_byte_length = PntsHeader::BYTE_LENGTH
+            + feature_table_blob.len()
+            + feature_table_body.len()
+            + batch_table_blob.len()
+            + batch_table_body.len();
 
         let pnts_header = PntsHeader::new(
             PNTS_VERSION,
             total_byte_length
                 .try_into()
                 .expect("Size of .pnts file exceeds maximum size of 4GiB!"),
-            feature_table_byte_size
+            feature_table_blob
+                .len()
                 .try_into()
                 .expect("Size of FeatureTable header exceeds maximum size of 4GiB!"),
-            feature_table_body_byte_size_aligned
+            feature_table_body
+                .len()
                 .try_into()
                 .expect("Size of FeatureTable binary body exceeds maximum size of 4GiB!"),
-            batch_table_byte_size
+            batch_table_blob
+                .len()
                 .try_into()
                 .expect("Size of BatchTable header exceeds maximum size of 4GiB!"),
-            batch_table_body_byte_size
+            batch_table_body
+                .len()
                 .try_into()
                 .expect("Size of BatchTable binary body exceeds maximum size of 4GiB!"),
         );
@@ -214,121 +388,69 @@ impl<W: Write + Seek> PntsWriter<W> {
         bincode::serialize_into(&mut self.writer, &pnts_header)
             .context("Error while serializing .pnts header")?;
         self.writer
-            .write(feature_table_blob.as_slice())
+            .write_all(feature_table_blob.as_slice())
             .context("Error while writing FeatureTable header")?;
-        self.write_feature_table_body()?;
         self.writer
-            .write(batch_table_blob.as_slice())
+            .write_all(feature_table_body.as_slice())
+            .context("Error while writing FeatureTable body")?;
+        self.writer
+            .write_all(batch_table_blob.as_slice())
             .context("Error while writing BatchTable header")?;
-        // TODO Write BatchTable binary body. For now, it doesn't exist, so we don't have to write anything
+        self.writer
+            .write_all(batch_table_body.as_slice())
+            .context("Error while writing BatchTable body")?;
 
         self.requires_flush = false;
 
         Ok(())
     }
 
-    fn create_feature_table(&self) -> FeatureTableHeader {
-        let num_points = self.cached_points.len();
-        let cumulative_attribute_offsets = self
-            .default_layout
-            .attributes()
-            .scan(0, |state, attribute| {
-                let ret = *state;
-                *state +=
-                    (attribute.size() as usize * num_points).align_to(PNTS_SEMANTICS_MAX_ALIGNMENT);
-                Some(ret)
-            })
-            .collect::<Vec<_>>();
-
-        let mut point_semantics = self
-            .default_layout
-            .attributes()
-            .enumerate()
-            .map(|(idx, attribute)| -> (String, FeatureTableValue) {
-                let semantic_name =
-                    pnts_semantics_name_from_point_attribute(attribute.attribute_definition())
-                        .expect("Invalid point semantic");
-                (
-                    semantic_name,
-                    FeatureTableValue::DataReference(FeatureTableDataReference {
-                        byte_offset: cumulative_attribute_offsets[idx],
-                        component_type: None,
-                    }),
-                )
-            })
-            .collect::<HashMap<_, _>>();
-
-        // Create global semantics. Only POINTS_LENGTH is mandatory
-        point_semantics.insert(
-            "POINTS_LENGTH".into(),
-            FeatureTableValue::SingleValue(json!(num_points)),
+    /// Quantizes the given `positions` to 16 bits per component within their bounding box and appends them to `blob`.
+    /// Returns the `QUANTIZED_VOLUME_OFFSET` and `QUANTIZED_VOLUME_SCALE` of the quantized positions
+    fn quantize_positions<I: Iterator<Item = Vector3<f64>> + Clone>(
+        positions: I,
+        blob: &mut Vec<u8>,
+    ) -> (Vector3<f32>, Vector3<f32>) {
+        let (min, max) = positions.clone().fold(
+            (
+                Vector3::repeat(f64::INFINITY),
+                Vector3::repeat(f64::NEG_INFINITY),
+            ),
+            |(min, max), position| (min.inf(&position), max.sup(&position)),
         );
-
-        if let Some(ref rtc_center) = self.rtc_center {
-            point_semantics.insert(
-                "RTC_CENTER".into(),
-                FeatureTableValue::Array(vec![
-                    json!(rtc_center.x),
-                    json!(rtc_center.y),
-                    json!(rtc_center.z),
-                ]),
-            );
+        if min.x > max.x {
+            return (Vector3::zeros(), Vector3::zeros());
         }
 
-        point_semantics
-    }
-
-    fn create_batch_table(&self) -> BatchTableHeader {
-        Default::default()
-    }
-
-    /// Calculate the length in bytes of the FeatureTable binary body. This is based on the default PointLayout
-    /// and the number of cached points. For simplicities sake, we store all attributes with the same memory
-    /// alignment (PNTS_SEMANTICS_MAX_ALIGNMENT), which makes the calculation of total size easier. The whole FeatureTable
-    /// body has to end at an 8-byte boundary, however THIS IS NOT TAKEN INTO ACCOUNT BY THIS METHOD! The padding bytes are
-    /// written in `write_feature_table_body` instead!
-    fn calc_feature_table_body_length(&self) -> usize {
-        let num_points = self.cached_points.len();
-        self.default_layout
-            .attributes()
-            .map(|attribute| {
-                (num_points * attribute.size() as usize).align_to(PNTS_SEMANTICS_MAX_ALIGNMENT)
-            })
-            .sum()
-    }
-
-    fn write_feature_table_body(&mut self) -> Result<()> {
-        let num_points = self.cached_points.len();
-
-        for attribute in self.default_layout.attributes() {
-            let attribute_data = self
-                .cached_points
-                .get_attribute_range_ref(attribute.attribute_definition(), 0..num_points);
-            self.writer
-                .write_all(attribute_data)
-                .context("Error while writing attribute data")?;
-
-            let blob_byte_size = attribute.size() as usize * self.cached_points.len();
-            let num_padding_bytes =
-                blob_byte_size.align_to(PNTS_SEMANTICS_MAX_ALIGNMENT) - blob_byte_size;
-            if num_padding_bytes != 0 {
-                let padding_bytes = vec![0; num_padding_bytes];
-                self.writer
-                    .write_all(padding_bytes.as_slice())
-                    .context("Error while writing padding bytes")?;
+        // The offset and scale are stored as f32 values, so the quantization uses the f32 values as well to match the
+        // dequantization during reading
+        let volume_offset = min.cast::<f32>();
+        let volume_scale = (max - volume_offset.cast::<f64>()).cast::<f32>();
+        let offset = volume_offset.cast::<f64>();
+        let scale = volume_scale.cast::<f64>();
+        for position in positions {
+            for axis in 0..3 {
+                let quantized = if scale[axis] > 0.0 {
+                    ((position[axis] - offset[axis]) / scale[axis] * u16::MAX as f64)
+                        .round()
+                        .clamp(0.0, u16::MAX as f64) as u16
+                } else {
+                    0
+                };
+                blob.extend_from_slice(&quantized.to_le_bytes());
             }
         }
+        (volume_offset, volume_scale)
+    }
+}
 
-        // Write padding bytes to ensure we are at an 8-byte boundary!
-        let current_write_position = self.writer.stream_position()?;
-        let next_8_byte_boundary = current_write_position.align_to(8);
-        let num_padding_bytes = next_8_byte_boundary - current_write_position;
-        if num_padding_bytes > 0 {
-            self.writer
-                .write_all(&vec![0; num_padding_bytes as usize])?;
-        }
-
-        Ok(())
+impl PntsWriter<BufWriter<File>> {
+    /// Creates a new `PntsWriter` that writes to the file at `path`, see [`PntsWriter::from_write_and_layout`]
+    pub fn from_path_and_layout<P: AsRef<Path>>(
+        path: P,
+        point_layout: PointLayout,
+    ) -> Result<Self> {
+        PntsWriterBuilder::new().build_for_path(path, point_layout)
     }
 }
 
@@ -406,16 +528,83 @@ impl<W: Write + Seek> Drop for PntsWriter<W> {
     }
 }
 
+/// Builder for a [`PntsWriter`] that writes quantized positions or places the `RTC_CENTER` automatically
+///
+/// ```no_run
+/// # use anyhow::Result;
+/// # use pasture_core::layout::{attributes::POSITION_3D, PointLayout};
+/// # use pasture_io::tiles3d::{PntsRtcCenter, PntsWriterBuilder};
+/// # fn main() -> Result<()> {
+/// let writer = PntsWriterBuilder::new()
+///     .quantize_positions(true)
+///     .rtc_center(PntsRtcCenter::Centroid)
+///     .build_for_path("points.pnts", PointLayout::from_attributes(&[POSITION_3D]))?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Copy, Clone, Debug, Default)]
+pub struct PntsWriterBuilder {
+    quantize_positions: bool,
+    rtc_center: PntsRtcCenter,
+}
+
+impl PntsWriterBuilder {
+    /// Creates a new `PntsWriterBuilder` for `f32` positions without `RTC_CENTER`
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// If `true`, positions are written as `POSITION_QUANTIZED` with 16 bits per component instead of as `POSITION`.
+    /// The `QUANTIZED_VOLUME_OFFSET` and `QUANTIZED_VOLUME_SCALE` are computed from the bounds of all written positions
+    pub fn quantize_positions(mut self, quantize_positions: bool) -> Self {
+        self.quantize_positions = quantize_positions;
+        self
+    }
+
+    /// Sets how the `RTC_CENTER` is determined, see [`PntsRtcCenter`]
+    pub fn rtc_center(mut self, rtc_center: PntsRtcCenter) -> Self {
+        self.rtc_center = rtc_center;
+        self
+    }
+
+    /// Creates a `PntsWriter` that writes to `writer`, see [`PntsWriter::from_write_and_layout`]
+    pub fn build<W: Write + Seek>(&self, writer: W, point_layout: PointLayout) -> PntsWriter<W> {
+        let mut pnts_writer = PntsWriter::from_write_and_layout(writer, point_layout);
+        pnts_writer.quantize_positions = self.quantize_positions;
+        pnts_writer.rtc_center_mode = self.rtc_center;
+        pnts_writer
+    }
+
+    /// Creates a `PntsWriter` that writes to the file at `path`
+    pub fn build_for_path<P: AsRef<Path>>(
+        &self,
+        path: P,
+        point_layout: PointLayout,
+    ) -> Result<PntsWriter<BufWriter<File>>> {
+        let file = BufWriter::new(File::create(path.as_ref()).context(format!(
+            "Could not open file {} for writing",
+            path.as_ref().display()
+        ))?);
+        Ok(self.build(file, point_layout))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::SeekFrom;
 
-    use crate::{base::PointReader, tiles3d::PntsReader};
+    use crate::{
+        base::PointReader,
+        tiles3d::{PntsReadPositionsMode, PntsReader},
+    };
 
     use super::*;
     use pasture_core::{
         containers::VectorBuffer,
-        layout::PointType,
+        layout::{
+            attributes::{CLASSIFICATION, GPS_TIME, INTENSITY},
+            PointType,
+        },
         nalgebra::{Vector3, Vector4},
     };
     use pasture_derive::PointType;
@@ -532,7 +721,7 @@ mod tests {
 
         cursor.seek(SeekFrom::Start(0))?;
 
-        // Read back in, data read should equal data written, but missing the intensity
+        // Read back in, data read should equal data written, with the intensity read from the BatchTable
         {
             let mut reader =
                 PntsReader::from_read(&mut cursor).context("Error while creating PntsReader")?;
@@ -544,10 +733,18 @@ mod tests {
                 &[
                     POSITION_3D.with_custom_datatype(PointAttributeDataType::Vec3f32),
                     COLOR_RGB.with_custom_datatype(PointAttributeDataType::Vec3u8),
+                    INTENSITY,
                 ],
                 1,
             );
             assert_eq!(read_points_layout, *read_points.point_layout());
+            assert_eq!(
+                vec![10_000, 20_000],
+                read_points
+                    .view_attribute::<u16>(&INTENSITY)
+                    .into_iter()
+                    .collect::<Vec<_>>()
+            );
 
             assert_eq!(read_points.len(), test_point_buffer.len());
 
@@ -594,4 +791,235 @@ mod tests {
 
         Ok(())
     }
+
+    #[derive(
+        Debug, PointType, Copy, Clone, PartialEq, bytemuck::AnyBitPattern, bytemuck::NoUninit,
+    )]
+    #[repr(C, packed)]
+    struct PntsBatchTablePoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        position: Vector3<f64>,
+        #[pasture(BUILTIN_COLOR_RGB)]
+        color: Vector3<u16>,
+        #[pasture(BUILTIN_CLASSIFICATION)]
+        classification: u8,
+        #[pasture(BUILTIN_GPS_TIME)]
+        gps_time: f64,
+        #[pasture(attribute = "Offset")]
+        offset: Vector3<f32>,
+        #[pasture(attribute = "Counter")]
+        counter: u64,
+    }
+
+    fn batch_table_test_data() -> Vec<PntsBatchTablePoint> {
+        (0..5)
+            .map(|index| PntsBatchTablePoint {
+                position: Vector3::new(
+                    600_000.0 + index as f64 * 1.5,
+                    5_000_000.0 - index as f64 * 0.25,
+                    300.0 + index as f64 * 0.125,
+                ),
+                color: Vector3::new(index * 10, 255, 0),
+                classification: index as u8,
+                gps_time: 1234.5 + index as f64,
+                offset: Vector3::new(index as f32, 0.5, -1.0),
+                counter: index as u64,
+            })
+            .collect()
+    }
+
+    /// Checks the 8-byte alignment rules of the 3D Tiles specification and returns the header of the .pnts file
+    fn check_pnts_alignment(data: &[u8]) -> Result<PntsHeader> {
+        let header: PntsHeader = bincode::deserialize(data)?;
+        let byte_length = header.byte_length as usize;
+        let feature_table_json_end =
+            PntsHeader::BYTE_LENGTH + header.feature_table_json_byte_length as usize;
+        let feature_table_binary_end =
+            feature_table_json_end + header.feature_table_binary_byte_length as usize;
+        let batch_table_json_end =
+            feature_table_binary_end + header.batch_table_json_byte_length as usize;
+        assert_eq!(data.len(), byte_length);
+        assert_eq!(0, byte_length % 8);
+        assert_eq!(0, feature_table_json_end % 8);
+        assert_eq!(0, feature_table_binary_end % 8);
+        assert_eq!(0, batch_table_json_end % 8);
+        assert_eq!(
+            byte_length,
+            batch_table_json_end + header.batch_table_binary_byte_length as usize
+        );
+        Ok(header)
+    }
+
+    #[test]
+    fn test_write_pnts_batch_table() -> Result<()> {
+        let test_data = batch_table_test_data();
+        let test_point_buffer = test_data.iter().copied().collect::<VectorBuffer>();
+        let mut cursor = Cursor::new(Vec::<u8>::new());
+        {
+            let mut writer =
+                PntsWriter::from_write_and_layout(&mut cursor, PntsBatchTablePoint::layout());
+            writer.write(&test_point_buffer)?;
+            writer.flush()?;
+        }
+        let header = check_pnts_alignment(cursor.get_ref())?;
+        assert!(header.batch_table_json_byte_length > 0);
+
+        cursor.seek(SeekFrom::Start(0))?;
+        let mut reader = PntsReader::from_read(&mut cursor)?;
+        let offset_attribute = PointAttributeDefinition::custom(
+            Cow::Borrowed("Offset"),
+            PointAttributeDataType::Vec3f32,
+        );
+        // The u64 counter can't be stored in a BatchTable
+        let expected_layout = PointLayout::from_attributes_packed(
+            &[
+                POSITION_3D.with_custom_datatype(PointAttributeDataType::Vec3f32),
+                COLOR_RGB.with_custom_datatype(PointAttributeDataType::Vec3u8),
+                CLASSIFICATION,
+                GPS_TIME,
+                offset_attribute.clone(),
+            ],
+            1,
+        );
+        assert_eq!(&expected_layout, reader.get_default_point_layout());
+
+        let read_points = reader.read::<HashMapBuffer>(test_data.len())?;
+        for (index, expected) in test_data.iter().enumerate() {
+            let expected = *expected;
+            assert_eq!(
+                expected.position.cast::<f32>(),
+                read_points
+                    .view_attribute::<Vector3<f32>>(
+                        &POSITION_3D.with_custom_datatype(PointAttributeDataType::Vec3f32)
+                    )
+                    .at(index)
+            );
+            // All colors fit into 8 bits, so they are not downscaled
+            assert_eq!(
+                expected.color.cast::<u8>(),
+                read_points
+                    .view_attribute::<Vector3<u8>>(
+                        &COLOR_RGB.with_custom_datatype(PointAttributeDataType::Vec3u8)
+                    )
+                    .at(index)
+            );
+            assert_eq!(
+                expected.classification,
+                read_points.view_attribute::<u8>(&CLASSIFICATION).at(index)
+            );
+            assert_eq!(
+                { expected.gps_time },
+                read_points.view_attribute::<f64>(&GPS_TIME).at(index)
+            );
+            assert_eq!(
+                { expected.offset },
+                read_points
+                    .view_attribute::<Vector3<f32>>(&offset_attribute)
+                    .at(index)
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_pnts_quantized_positions_with_centroid() -> Result<()> {
+        let test_data = batch_table_test_data();
+        let test_point_buffer = test_data.iter().copied().collect::<VectorBuffer>();
+        let mut cursor = Cursor::new(Vec::<u8>::new());
+        {
+            let mut writer = PntsWriterBuilder::new()
+                .quantize_positions(true)
+                .rtc_center(PntsRtcCenter::Centroid)
+                .build(&mut cursor, PntsBatchTablePoint::layout());
+            writer.write(&test_point_buffer)?;
+            writer.flush()?;
+        }
+        check_pnts_alignment(cursor.get_ref())?;
+
+        cursor.seek(SeekFrom::Start(0))?;
+        let mut reader = PntsReader::from_read(&mut cursor)?;
+        let metadata = reader.get_metadata();
+        let rtc_center = metadata
+            .get_named_field("RTC_CENTER")
+            .and_then(|field| field.downcast::<Vector3<f64>>().ok())
+            .unwrap();
+        let expected_centroid = Vector3::new(600_003.0, 4_999_999.5, 300.25);
+        assert!((*rtc_center - expected_centroid).norm() < 1e-6);
+        assert!(metadata
+            .get_named_field("QUANTIZED_VOLUME_OFFSET")
+            .is_some());
+        let volume_scale = metadata
+            .get_named_field("QUANTIZED_VOLUME_SCALE")
+            .and_then(|field| field.downcast::<Vector3<f32>>().ok())
+            .unwrap();
+
+        // Read positions as f64 values, so that adding the RTC_CENTER does not lose precision
+        let mut read_points =
+            HashMapBuffer::new_from_layout(PointLayout::from_attributes(&[POSITION_3D]));
+        read_points.resize(test_data.len());
+        reader.read_into(&mut read_points, test_data.len())?;
+        let positions = read_points
+            .view_attribute::<Vector3<f64>>(&POSITION_3D)
+            .into_iter()
+            .collect::<Vec<_>>();
+        for (expected, actual) in test_data.iter().zip(positions.iter()) {
+            let expected_position = expected.position;
+            for axis in 0..3 {
+                let max_error = volume_scale[axis] as f64 / u16::MAX as f64 + 1e-4;
+                assert!((expected_position[axis] - actual[axis]).abs() <= max_error);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_pnts_fixed_rtc_center_and_downscaled_colors() -> Result<()> {
+        let test_data = vec![
+            PntsCustomLayout {
+                position: Vector3::new(101.0, 202.0, 303.0),
+                color: Vector3::new(0xffff, 0x8000, 0x00ff),
+                intensity: 1,
+            },
+            PntsCustomLayout {
+                position: Vector3::new(102.0, 204.0, 306.0),
+                color: Vector3::new(0x0100, 0x0000, 0x1234),
+                intensity: 2,
+            },
+        ];
+        let test_point_buffer = test_data.iter().copied().collect::<VectorBuffer>();
+        let mut cursor = Cursor::new(Vec::<u8>::new());
+        {
+            let mut writer = PntsWriterBuilder::new()
+                .rtc_center(PntsRtcCenter::Fixed(Vector3::new(100.0, 200.0, 300.0)))
+                .build(&mut cursor, PntsCustomLayout::layout());
+            writer.write(&test_point_buffer)?;
+        }
+        check_pnts_alignment(cursor.get_ref())?;
+
+        cursor.seek(SeekFrom::Start(0))?;
+        let mut reader = PntsReader::from_read(&mut cursor)?;
+        reader.set_read_positions_mode(PntsReadPositionsMode::RelativeToCenter);
+        let read_points = reader.read::<HashMapBuffer>(test_data.len())?;
+        let position_attribute = POSITION_3D.with_custom_datatype(PointAttributeDataType::Vec3f32);
+        assert_eq!(
+            vec![Vector3::new(1.0, 2.0, 3.0), Vector3::new(2.0, 4.0, 6.0)],
+            read_points
+                .view_attribute::<Vector3<f32>>(&position_attribute)
+                .into_iter()
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![
+                Vector3::new(0xff, 0x80, 0x00),
+                Vector3::new(0x01, 0x00, 0x12)
+            ],
+            read_points
+                .view_attribute::<Vector3<u8>>(
+                    &COLOR_RGB.with_custom_datatype(PointAttributeDataType::Vec3u8)
+                )
+                .into_iter()
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
 }
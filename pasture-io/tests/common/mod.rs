@@ -16,6 +16,8 @@ const RETURN_NUMBER_REGULAR_BITMASK: u8 = 0b111;
 const RETURN_NUMBER_EXTENDED_BITMASK: u8 = 0b1111;
 const NUMBER_OF_RETURNS_REGULAR_BITMASK: u8 = 0b111;
 const NUMBER_OF_RETURNS_EXTENDED_BITMASK: u8 = 0b1111;
+const CLASSIFICATION_REGULAR_BITMASK: u8 = 0b11111;
+const CLASSIFICATION_FLAGS_REGULAR_BITMASK: u8 = 0b111;
 const CLASSIFICATION_FLAGS_BITMASK: u8 = 0b1111;
 const SCANNER_CHANNEL_BITMASK: u8 = 0b11;
 
@@ -110,7 +112,8 @@ pub struct TestLASPointDistribution;
 impl Distribution<LasPointFormat0> for TestLASPointDistribution {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> LasPointFormat0 {
         LasPointFormat0 {
-            classification: rng.gen(),
+            classification: rng.gen::<u8>() & CLASSIFICATION_REGULAR_BITMASK,
+            classification_flags: rng.gen::<u8>() & CLASSIFICATION_FLAGS_REGULAR_BITMASK,
             edge_of_flight_line: rng.gen::<u8>() & 1,
             intensity: rng.gen(),
             number_of_returns: rng.gen::<u8>() & NUMBER_OF_RETURNS_REGULAR_BITMASK,
@@ -134,7 +137,8 @@ impl Distribution<LasPointFormat0> for TestLASPointDistribution {
 impl Distribution<LasPointFormat1> for TestLASPointDistribution {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> LasPointFormat1 {
         LasPointFormat1 {
-            classification: rng.gen(),
+            classification: rng.gen::<u8>() & CLASSIFICATION_REGULAR_BITMASK,
+            classification_flags: rng.gen::<u8>() & CLASSIFICATION_FLAGS_REGULAR_BITMASK,
             edge_of_flight_line: rng.gen::<u8>() & 1,
             intensity: rng.gen(),
             number_of_returns: rng.gen::<u8>() & NUMBER_OF_RETURNS_REGULAR_BITMASK,
@@ -159,7 +163,8 @@ impl Distribution<LasPointFormat1> for TestLASPointDistribution {
 impl Distribution<LasPointFormat2> for TestLASPointDistribution {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> LasPointFormat2 {
         LasPointFormat2 {
-            classification: rng.gen(),
+            classification: rng.gen::<u8>() & CLASSIFICATION_REGULAR_BITMASK,
+            classification_flags: rng.gen::<u8>() & CLASSIFICATION_FLAGS_REGULAR_BITMASK,
             edge_of_flight_line: rng.gen::<u8>() & 1,
             intensity: rng.gen(),
             number_of_returns: rng.gen::<u8>() & NUMBER_OF_RETURNS_REGULAR_BITMASK,
@@ -184,7 +189,8 @@ impl Distribution<LasPointFormat2> for TestLASPointDistribution {
 impl Distribution<LasPointFormat3> for TestLASPointDistribution {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> LasPointFormat3 {
         LasPointFormat3 {
-            classification: rng.gen(),
+            classification: rng.gen::<u8>() & CLASSIFICATION_REGULAR_BITMASK,
+            classification_flags: rng.gen::<u8>() & CLASSIFICATION_FLAGS_REGULAR_BITMASK,
             edge_of_flight_line: rng.gen::<u8>() & 1,
             intensity: rng.gen(),
             number_of_returns: rng.gen::<u8>() & NUMBER_OF_RETURNS_REGULAR_BITMASK,
@@ -210,7 +216,8 @@ impl Distribution<LasPointFormat3> for TestLASPointDistribution {
 impl Distribution<LasPointFormat4> for TestLASPointDistribution {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> LasPointFormat4 {
         LasPointFormat4 {
-            classification: rng.gen(),
+            classification: rng.gen::<u8>() & CLASSIFICATION_REGULAR_BITMASK,
+            classification_flags: rng.gen::<u8>() & CLASSIFICATION_FLAGS_REGULAR_BITMASK,
             edge_of_flight_line: rng.gen::<u8>() & 1,
             intensity: rng.gen(),
             number_of_returns: rng.gen::<u8>() & NUMBER_OF_RETURNS_REGULAR_BITMASK,
@@ -240,7 +247,8 @@ impl Distribution<LasPointFormat4> for TestLASPointDistribution {
 impl Distribution<LasPointFormat5> for TestLASPointDistribution {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> LasPointFormat5 {
         LasPointFormat5 {
-            classification: rng.gen(),
+            classification: rng.gen::<u8>() & CLASSIFICATION_REGULAR_BITMASK,
+            classification_flags: rng.gen::<u8>() & CLASSIFICATION_FLAGS_REGULAR_BITMASK,
             edge_of_flight_line: rng.gen::<u8>() & 1,
             intensity: rng.gen(),
             number_of_returns: rng.gen::<u8>() & NUMBER_OF_RETURNS_REGULAR_BITMASK,
@@ -270,6 +278,7 @@ impl Distribution<LasPointFormat5> for TestLASPointDistribution {
 
 impl Distribution<LasPointFormat6> for TestLASPointDistribution {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> LasPointFormat6 {
+        let classification_flags = rng.gen::<u8>() & CLASSIFICATION_FLAGS_BITMASK;
         LasPointFormat6 {
             classification: rng.gen(),
             edge_of_flight_line: rng.gen::<u8>() & 1,
@@ -289,7 +298,9 @@ impl Distribution<LasPointFormat6> for TestLASPointDistribution {
             scan_direction_flag: rng.gen::<u8>() & 1,
             user_data: rng.gen(),
             gps_time: rng.gen(),
-            classification_flags: rng.gen::<u8>() & CLASSIFICATION_FLAGS_BITMASK,
+            classification_flags,
+            // The overlap flag is bit 3 of the classification flags
+            overlap: (classification_flags >> 3) & 1,
             scanner_channel: rng.gen::<u8>() & SCANNER_CHANNEL_BITMASK,
         }
     }
@@ -297,6 +308,7 @@ impl Distribution<LasPointFormat6> for TestLASPointDistribution {
 
 impl Distribution<LasPointFormat7> for TestLASPointDistribution {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> LasPointFormat7 {
+        let classification_flags = rng.gen::<u8>() & CLASSIFICATION_FLAGS_BITMASK;
         LasPointFormat7 {
             classification: rng.gen(),
             edge_of_flight_line: rng.gen::<u8>() & 1,
@@ -316,7 +328,9 @@ impl Distribution<LasPointFormat7> for TestLASPointDistribution {
             scan_direction_flag: rng.gen::<u8>() & 1,
             user_data: rng.gen(),
             gps_time: rng.gen(),
-            classification_flags: rng.gen::<u8>() & CLASSIFICATION_FLAGS_BITMASK,
+            classification_flags,
+            // The overlap flag is bit 3 of the classification flags
+            overlap: (classification_flags >> 3) & 1,
             scanner_channel: rng.gen::<u8>() & SCANNER_CHANNEL_BITMASK,
             color_rgb: Vector3::new(rng.gen(), rng.gen(), rng.gen()),
         }
@@ -325,6 +339,7 @@ impl Distribution<LasPointFormat7> for TestLASPointDistribution {
 
 impl Distribution<LasPointFormat8> for TestLASPointDistribution {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> LasPointFormat8 {
+        let classification_flags = rng.gen::<u8>() & CLASSIFICATION_FLAGS_BITMASK;
         LasPointFormat8 {
             classification: rng.gen(),
             edge_of_flight_line: rng.gen::<u8>() & 1,
@@ -344,7 +359,9 @@ impl Distribution<LasPointFormat8> for TestLASPointDistribution {
             scan_direction_flag: rng.gen::<u8>() & 1,
             user_data: rng.gen(),
             gps_time: rng.gen(),
-            classification_flags: rng.gen::<u8>() & CLASSIFICATION_FLAGS_BITMASK,
+            classification_flags,
+            // The overlap flag is bit 3 of the classification flags
+            overlap: (classification_flags >> 3) & 1,
             scanner_channel: rng.gen::<u8>() & SCANNER_CHANNEL_BITMASK,
             color_rgb: Vector3::new(rng.gen(), rng.gen(), rng.gen()),
             nir: rng.gen(),
@@ -354,6 +371,7 @@ impl Distribution<LasPointFormat8> for TestLASPointDistribution {
 
 impl Distribution<LasPointFormat9> for TestLASPointDistribution {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> LasPointFormat9 {
+        let classification_flags = rng.gen::<u8>() & CLASSIFICATION_FLAGS_BITMASK;
         LasPointFormat9 {
             classification: rng.gen(),
             edge_of_flight_line: rng.gen::<u8>() & 1,
@@ -373,7 +391,9 @@ impl Distribution<LasPointFormat9> for TestLASPointDistribution {
             scan_direction_flag: rng.gen::<u8>() & 1,
             user_data: rng.gen(),
             gps_time: rng.gen(),
-            classification_flags: rng.gen::<u8>() & CLASSIFICATION_FLAGS_BITMASK,
+            classification_flags,
+            // The overlap flag is bit 3 of the classification flags
+            overlap: (classification_flags >> 3) & 1,
             scanner_channel: rng.gen::<u8>() & SCANNER_CHANNEL_BITMASK,
             byte_offset_to_waveform_data: 0,
             return_point_waveform_location: rng.gen(),
@@ -386,6 +406,7 @@ impl Distribution<LasPointFormat9> for TestLASPointDistribution {
 
 impl Distribution<LasPointFormat10> for TestLASPointDistribution {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> LasPointFormat10 {
+        let classification_flags = rng.gen::<u8>() & CLASSIFICATION_FLAGS_BITMASK;
         LasPointFormat10 {
             classification: rng.gen(),
             edge_of_flight_line: rng.gen::<u8>() & 1,
@@ -405,7 +426,9 @@ impl Distribution<LasPointFormat10> for TestLASPointDistribution {
             scan_direction_flag: rng.gen::<u8>() & 1,
             user_data: rng.gen(),
             gps_time: rng.gen(),
-            classification_flags: rng.gen::<u8>() & CLASSIFICATION_FLAGS_BITMASK,
+            classification_flags,
+            // The overlap flag is bit 3 of the classification flags
+            overlap: (classification_flags >> 3) & 1,
             scanner_channel: rng.gen::<u8>() & SCANNER_CHANNEL_BITMASK,
             byte_offset_to_waveform_data: rng.gen::<u32>() as u64,
             return_point_waveform_location: rng.gen(),
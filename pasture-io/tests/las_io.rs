@@ -15,10 +15,12 @@ use pasture_derive::PointType;
 use pasture_io::{
     base::{PointReader, PointWriter},
     las::{
-        is_known_las_attribute, LASReader, LASWriter, LasPointFormat0, LasPointFormat1,
-        LasPointFormat10, LasPointFormat2, LasPointFormat3, LasPointFormat4, LasPointFormat5,
-        LasPointFormat6, LasPointFormat7, LasPointFormat8, LasPointFormat9,
+        is_known_las_attribute, LASMetadata, LASReader, LASWriter, LASWriterBuilder,
+        LasPointFormat0, LasPointFormat1, LasPointFormat10, LasPointFormat2, LasPointFormat3,
+        LasPointFormat4, LasPointFormat5, LasPointFormat6, LasPointFormat7, LasPointFormat8,
+        LasPointFormat9,
     },
+    las_rs::{point::Format, GpsTimeType, Vlr},
 };
 use rand::{prelude::Distribution, thread_rng, Rng};
 
@@ -94,7 +96,8 @@ impl Distribution<PointTypeWithUnsupportedAttribute> for TestLASPointDistributio
                 rng.gen_range(-1.0..1.0),
                 rng.gen_range(-1.0..1.0),
             ),
-            classification: rng.gen(),
+            // Point formats 0-5 store the classification in 5 bits
+            classification: rng.gen_range(0..32),
         }
     }
 }
@@ -181,7 +184,7 @@ impl Distribution<ComplexPointTypeWithConversions> for TestLASPointDistribution
                 rng.gen_range(-1000..1000) as f32,
                 rng.gen_range(-1000..1000) as f32,
             ),
-            classification: rng.gen_range(0..=255) as u16,
+            classification: rng.gen_range(0..32) as u16,
             color: Vector3::new(
                 rng.gen_range(0..32000) as f64,
                 rng.gen_range(0..32000) as f64,
@@ -348,3 +351,107 @@ fn test_write_large_file_with_custom_format() -> Result<()> {
         .context("Writing large LAZ file with custom format failed")?;
     Ok(())
 }
+
+/// Returns the user ID, record ID and data of all VLRs and EVLRs in the given `metadata`, sorted so that the VLRs of
+/// two files can be compared independently of their order
+fn sorted_vlr_contents(metadata: &LASMetadata) -> Vec<(String, u16, Vec<u8>)> {
+    metadata
+        .vlrs()
+        .iter()
+        .chain(metadata.evlrs().iter())
+        .map(|vlr| (vlr.user_id.clone(), vlr.record_id, vlr.data.clone()))
+        .sorted()
+        .collect()
+}
+
+#[test]
+fn test_filter_las_file_with_metadata_from_source() -> Result<()> {
+    const COUNT: usize = 1000;
+    let source_points = thread_rng()
+        .sample_iter::<LasPointFormat6, _>(TestLASPointDistribution)
+        .take(COUNT)
+        .collect::<Vec<_>>();
+    let vendor_vlr = Vlr {
+        user_id: "pasture".to_owned(),
+        record_id: 1,
+        description: "Processing parameters".to_owned(),
+        data: b"filter=none".to_vec(),
+    };
+    let history_evlr = Vlr {
+        user_id: "pasture".to_owned(),
+        record_id: 2,
+        description: "Processing history".to_owned(),
+        data: vec![42; 1024],
+    };
+    let mut source_writer = LASWriterBuilder::new(Format::new(6)?)
+        .with_vlr(vendor_vlr)
+        .with_evlr(history_evlr)
+        .with_epsg(25832)
+        .gps_time_type(GpsTimeType::Standard)
+        .generating_software("source software")
+        .build(Cursor::new(Vec::<u8>::new()))?;
+    source_writer.write_points(source_points.iter().copied())?;
+    let source_data = source_writer.into_inner()?.into_inner();
+    let source_reader = LASReader::from_read(Cursor::new(source_data), false, false)?;
+    let source_metadata = source_reader.las_metadata();
+
+    let mut filtered_writer = LASWriterBuilder::new(Format::new(6)?)
+        .with_metadata_from(source_metadata)
+        .build(Cursor::new(Vec::<u8>::new()))?;
+    let filtered_count = filtered_writer.write_points(source_points.iter().copied().step_by(2))?;
+    let filtered_data = filtered_writer.into_inner()?.into_inner();
+    let filtered_reader = LASReader::from_read(Cursor::new(filtered_data), false, false)?;
+    let filtered_metadata = filtered_reader.las_metadata();
+
+    assert_eq!(
+        sorted_vlr_contents(source_metadata),
+        sorted_vlr_contents(filtered_metadata)
+    );
+    assert_eq!(filtered_count, filtered_metadata.point_count());
+    assert_eq!(
+        Some(GpsTimeType::Standard),
+        filtered_metadata.gps_time_type()
+    );
+    assert_eq!(
+        Some("source software"),
+        filtered_metadata.generating_software()
+    );
+
+    let header = LASWriterBuilder::new(Format::new(6)?)
+        .with_metadata_from(source_metadata)
+        .generating_software("filter tool")
+        .build_header()?;
+    assert_eq!("filter tool", header.generating_software());
+    Ok(())
+}
+
+#[cfg(feature = "laz-parallel")]
+#[test]
+fn test_read_large_laz_file_in_parallel() -> Result<()> {
+    // Large enough for several LAZ chunks, so that the chunks are decompressed by different threads
+    const COUNT: usize = 333333;
+    let expected_points = thread_rng()
+        .sample_iter::<LasPointFormat1, _>(TestLASPointDistribution)
+        .take(COUNT)
+        .collect::<VectorBuffer>();
+    let mut writer = LASWriter::from_writer_and_point_layout(
+        Cursor::new(Vec::<u8>::new()),
+        &LasPointFormat1::layout(),
+        true,
+    )?;
+    writer.write(&expected_points)?;
+    let laz_data = writer.into_inner()?.into_inner();
+
+    let mut reader = LASReader::from_read_parallel(Cursor::new(laz_data), 4, false)?;
+    let actual_points = reader.read::<VectorBuffer>(COUNT)?;
+    assert_eq!(COUNT, actual_points.len());
+    for (idx, (expected_point, actual_point)) in expected_points
+        .view::<LasPointFormat1>()
+        .into_iter()
+        .zip(actual_points.view::<LasPointFormat1>().into_iter())
+        .enumerate()
+    {
+        assert_eq!(expected_point, actual_point, "Point {idx} does not match");
+    }
+    Ok(())
+}
@@ -1,6 +1,6 @@
 use std::{path::PathBuf, time::Instant};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{App, Arg};
 use pasture_algorithms::minmax::minmax_attribute;
 use pasture_core::{
@@ -19,7 +19,7 @@ use pasture_core::{
     math::MinMax,
     nalgebra::Vector3,
 };
-use pasture_io::base::{GenericPointReader, PointReader, SeekToPoint};
+use pasture_io::base::{GenericPointReader, PointReader};
 
 struct Args {
     pub input_file: PathBuf,
@@ -83,10 +83,12 @@ macro_rules! minmax_chunk {
     };
 }
 
-fn analyze_file<R: PointReader + SeekToPoint>(reader: &mut R) -> Result<()> {
+fn analyze_file<R: PointReader>(reader: &mut R) -> Result<()> {
     print_attributes(reader.get_default_point_layout());
 
-    let total_points = reader.point_count()?;
+    let total_points = reader
+        .point_count()
+        .context("Could not determine the number of points")?;
     if total_points == 0 {
         return Ok(());
     }
@@ -195,7 +197,7 @@ fn analyze_file<R: PointReader + SeekToPoint>(reader: &mut R) -> Result<()> {
 
 fn main() -> Result<()> {
     let args = get_args()?;
-    let mut reader = GenericPointReader::open_file(&args.input_file)?;
+    let mut reader = GenericPointReader::open(&args.input_file)?;
     let meta = reader.get_metadata();
     println!("pasture info report for {}", args.input_file.display());
     println!("{}", meta);